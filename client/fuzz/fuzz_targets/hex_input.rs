@@ -0,0 +1,21 @@
+// Every hex value the CLI reads from its arguments, files and stdin: salts, call data, keys, signatures
+// and nonce keys. Parsing either fails or returns exactly the bytes the digits spell, never panics
+#![no_main]
+
+use aa_client::hexutil::{self, decode, decode_fixed, decode_word, parse_salt};
+use aa_client::nonce;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(bytes) = decode("data", input) {
+        assert_eq!(hex::encode(&bytes), hexutil::strip_prefix(input).to_ascii_lowercase());
+    }
+    if let Ok(key) = decode_fixed::<32>("private key", input) {
+        assert_eq!(decode("private key", input).unwrap(), key);
+    }
+    let _ = decode_fixed::<65>("signature", input);
+    if let Ok(word) = decode_word("word", input) {
+        assert_eq!(parse_salt(input).unwrap().to_be_bytes::<32>(), word.0);
+    }
+    let _ = nonce::parse_key(input);
+});
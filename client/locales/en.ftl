@@ -0,0 +1,150 @@
+# English messages for aa-client output
+# Every catalog defines the same ids, `cargo test` fails when they drift apart
+
+## Shared labels
+
+label-factory = Factory: { $address }
+label-factory-none = Factory: not deployed, pass --factory
+label-target = Target: { $address }
+label-owner-eoa = Owner EOA: { $address }
+label-owner = Owner: { $address }
+label-paymaster = Paymaster: { $address }
+label-paymaster-service = Paymaster service: { $url }
+label-salt = Salt: { $salt }
+label-call-data = Call data: { $data }
+label-value-wei = Value: { $value } wei
+label-userop-hash = UserOperation Hash: { $hash }
+label-configuration = Configuration:
+possible-causes = Possible causes:
+make-sure = Make sure:
+
+## Smart account lookup
+
+checking-deployed = Checking if smart account is deployed...
+using-deployed-account = Using deployed smart account: { $address }
+predicted-address = Predicted smart account address: { $address }
+account-not-deployed = Smart account not deployed at { $address }!
+hint-run-first = Run { $command } first with:
+
+## submit
+
+submit-start = Submitting transaction via smart account using aa-sdk-rs...
+setting-up-account = Setting up aa-sdk-rs SmartAccount...
+preparing-transaction = Preparing transaction for smart account execution...
+userop-created = UserOperation created correctly (no double-encoding)
+estimating-gas = Estimating gas parameters...
+gas-estimates = Gas estimates: { $estimates }
+gas-estimation-failed = Gas estimation failed (proceeding anyway): { $error }
+filling-userop = Filling UserOperation fields automatically...
+submitting-transaction = Submitting transaction via smart account...
+userop-submitted = UserOperation submitted successfully!
+checking-status = Checking UserOperation execution status...
+tx-executed = Transaction executed successfully!
+receipt-details = Receipt details: { $receipt }
+tx-completed = Smart account transaction completed!
+tx-pending = Transaction still pending...
+check-later = Check status later with hash: { $hash }
+userop-details = UserOperation details: { $details }
+status-unverified = Could not verify execution status: { $error }
+may-have-succeeded = Operation may still have succeeded - check blockchain directly
+submit-failed = Transaction submission failed: { $error }
+cause-not-deployed = Smart account not properly deployed
+cause-gas-fees = Insufficient gas fees
+cause-invalid-call = Invalid target contract or call data
+cause-bundler-connectivity = Bundler connectivity issues
+
+## generate-wallet and info
+
+wallet-generating = Generating new random wallet...
+wallet-created = New wallet created:
+label-address = Address: { $address }
+label-private-key = Private Key: { $key }
+wallet-information = Wallet information:
+wallet-public-key-note = Note: Public key is managed internally by aa-sdk-rs LocalSigner
+
+## deploy-account
+
+deploy-start = Deploying new smart account via bundler...
+label-deployer = Deployer wallet: { $address }
+deploy-via-factory = Deploying smart account using real SimpleAccountFactory contract...
+fund-predicted-address = Make sure this address is funded with ETH for gas fees
+deploy-creating-userop = Creating deployment UserOperation...
+deploy-sdk-handles = Letting aa-sdk-rs automatically handle deployment...
+deploy-sdk-will = aa-sdk-rs will automatically:
+deploy-sdk-detect = Detect that the account doesn't exist
+deploy-sdk-initcode = Generate proper initCode for factory deployment
+deploy-sdk-sender = Set the predicted address as sender
+deploy-sdk-nonce = Handle nonce management
+gas-fees-wei = Gas fees - Max fee: { $max } wei, Priority fee: { $priority } wei
+deploy-userop-created = Deployment UserOperation created!
+label-predicted-account = Predicted Account: { $address }
+deploy-sdk-factory-calls = aa-sdk-rs will handle factory calls automatically
+deploy-submitting = Submitting deployment UserOperation to bundler...
+deploy-initiated = Smart account deployment initiated successfully!
+deploy-will-be-at = The account will be deployed at: { $address }
+deploy-track = You can track this deployment on the blockchain
+deploy-failed = Error deploying smart account: { $error }
+check-bundler-running = The bundler is running and supports eth_sendUserOperation
+check-predicted-funded = The predicted account address is funded with ETH
+check-factory-deployed = The factory contract is deployed and accessible
+predict-failed = Error predicting smart account address: { $error }
+hint-factory-deployed = Make sure the factory contract is deployed and accessible
+
+## predict-address
+
+predict-start = Predicting smart account address...
+predict-via-factory = Predicting smart account address using real SimpleAccountFactory contract...
+predict-result = Real Predicted Address: { $address }
+predict-source = This address is calculated by the actual SimpleAccountFactory contract
+factory-call-failed = Error calling factory contract: { $error }
+hint-factory-and-rpc = Make sure the factory contract is deployed and the RPC URL is correct
+
+## submit-sponsored and deploy-sponsored
+
+sponsored-start = Submitting sponsored transaction via paymaster...
+sponsored-preparing = Preparing sponsored transaction...
+pre-verification-gas-raised = Increased pre_verification_gas to 46,000 to meet bundler minimum
+gas-fees-gwei = Using gas fees - Max: { $max } gwei, Priority: { $priority } gwei
+sponsorship-requesting = Requesting paymaster sponsorship...
+paymaster-data-generated = Paymaster data generated: { $data }
+paymaster-address-set = Set paymaster address: { $address }
+sponsorship-obtained = Paymaster sponsorship obtained!
+sponsorship-configured = Paymaster data configured - gas will be sponsored
+sponsored-submitting = Submitting sponsored UserOperation...
+sponsored-submitted = Sponsored transaction submitted successfully!
+sponsored-gas-covered = Gas fees are being sponsored by the paymaster!
+sponsored-executed = Sponsored transaction executed successfully!
+sponsored-completed = Gas-free transaction completed!
+sponsored-failed = Sponsored transaction failed: { $error }
+cause-sponsorship-rejected = Paymaster service rejected the sponsorship
+cause-paymaster-config = Invalid paymaster configuration
+cause-factory-inaccessible = Factory contract not accessible
+sponsored-deploy-start = Deploying sponsored smart account via paymaster...
+sponsored-deploy-no-eth = Deployment will be sponsored by paymaster - no ETH required!
+sponsored-deploy-creating = Creating sponsored deployment UserOperation...
+sponsorship-requesting-deploy = Requesting paymaster sponsorship for deployment...
+sponsorship-deploy-approved = Deployment sponsorship approved!
+sponsorship-deploy-covered = Paymaster will cover all deployment costs
+sponsored-deploy-submitting = Submitting sponsored deployment...
+sponsored-deploy-initiated = Sponsored deployment initiated successfully!
+sponsored-deploy-covered = Deployment costs are being sponsored!
+sponsored-deploy-zero-gas = Your smart account is being deployed with zero gas fees!
+sponsored-deploy-next = You can now use submit-sponsored to make gas-free transactions
+sponsored-deploy-failed = Sponsored deployment failed: { $error }
+
+## networks
+
+networks-title = Supported Networks
+network-heading = { $name } (Chain ID: { $chain_id }):
+label-rpc-template = RPC Template: { $url }
+label-bundler-template = Bundler Template: { $url }
+label-entry-point = EntryPoint: { $address }
+label-native-aa = Native account abstraction: calls are sent as type 113 transactions, no EntryPoint
+usage-examples = Usage Examples:
+example-anvil = Anvil (local)
+example-sepolia = Sepolia testnet
+example-custom-rpc = With custom RPC
+
+## Bundler checks
+
+bundler-preflight = Checking bundler chain and EntryPoint support...
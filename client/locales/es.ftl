@@ -0,0 +1,150 @@
+# Mensajes en español para la salida de aa-client
+# Define los mismos ids que en.ftl
+
+## Etiquetas comunes
+
+label-factory = Factory: { $address }
+label-factory-none = Factory: sin despliegue, usa --factory
+label-target = Destino: { $address }
+label-owner-eoa = EOA propietaria: { $address }
+label-owner = Propietario: { $address }
+label-paymaster = Paymaster: { $address }
+label-paymaster-service = Servicio de paymaster: { $url }
+label-salt = Salt: { $salt }
+label-call-data = Calldata: { $data }
+label-value-wei = Valor: { $value } wei
+label-userop-hash = Hash de la UserOperation: { $hash }
+label-configuration = Configuración:
+possible-causes = Posibles causas:
+make-sure = Asegúrate de que:
+
+## Búsqueda de la smart account
+
+checking-deployed = Comprobando si la smart account está desplegada...
+using-deployed-account = Usando la smart account desplegada: { $address }
+predicted-address = Dirección prevista de la smart account: { $address }
+account-not-deployed = ¡La smart account no está desplegada en { $address }!
+hint-run-first = Ejecuta primero { $command } con:
+
+## submit
+
+submit-start = Enviando transacción mediante la smart account con aa-sdk-rs...
+setting-up-account = Configurando la SmartAccount de aa-sdk-rs...
+preparing-transaction = Preparando la transacción para la smart account...
+userop-created = UserOperation creada correctamente (sin doble codificación)
+estimating-gas = Estimando parámetros de gas...
+gas-estimates = Estimaciones de gas: { $estimates }
+gas-estimation-failed = Falló la estimación de gas (se continúa igualmente): { $error }
+filling-userop = Completando automáticamente los campos de la UserOperation...
+submitting-transaction = Enviando transacción mediante la smart account...
+userop-submitted = ¡UserOperation enviada correctamente!
+checking-status = Comprobando el estado de ejecución de la UserOperation...
+tx-executed = ¡Transacción ejecutada correctamente!
+receipt-details = Detalles del recibo: { $receipt }
+tx-completed = ¡Transacción de la smart account completada!
+tx-pending = La transacción sigue pendiente...
+check-later = Consulta el estado más tarde con el hash: { $hash }
+userop-details = Detalles de la UserOperation: { $details }
+status-unverified = No se pudo verificar el estado de ejecución: { $error }
+may-have-succeeded = Puede que la operación se haya ejecutado: consulta la blockchain directamente
+submit-failed = Falló el envío de la transacción: { $error }
+cause-not-deployed = La smart account no está bien desplegada
+cause-gas-fees = Tarifas de gas insuficientes
+cause-invalid-call = Contrato de destino o calldata no válidos
+cause-bundler-connectivity = Problemas de conexión con el bundler
+
+## generate-wallet e info
+
+wallet-generating = Generando una nueva wallet aleatoria...
+wallet-created = Nueva wallet creada:
+label-address = Dirección: { $address }
+label-private-key = Clave privada: { $key }
+wallet-information = Información de la wallet:
+wallet-public-key-note = Nota: la clave pública la gestiona internamente el LocalSigner de aa-sdk-rs
+
+## deploy-account
+
+deploy-start = Desplegando una nueva smart account mediante el bundler...
+label-deployer = Wallet desplegadora: { $address }
+deploy-via-factory = Desplegando la smart account con el contrato SimpleAccountFactory...
+fund-predicted-address = Asegúrate de que esta dirección tenga ETH para pagar el gas
+deploy-creating-userop = Creando la UserOperation de despliegue...
+deploy-sdk-handles = aa-sdk-rs se encarga automáticamente del despliegue...
+deploy-sdk-will = aa-sdk-rs automáticamente:
+deploy-sdk-detect = Detecta que la cuenta no existe
+deploy-sdk-initcode = Genera el initCode para el despliegue mediante la factory
+deploy-sdk-sender = Usa la dirección prevista como sender
+deploy-sdk-nonce = Gestiona el nonce
+gas-fees-wei = Tarifas de gas - Máxima: { $max } wei, Prioridad: { $priority } wei
+deploy-userop-created = ¡UserOperation de despliegue creada!
+label-predicted-account = Cuenta prevista: { $address }
+deploy-sdk-factory-calls = aa-sdk-rs gestionará las llamadas a la factory automáticamente
+deploy-submitting = Enviando la UserOperation de despliegue al bundler...
+deploy-initiated = ¡Despliegue de la smart account iniciado correctamente!
+deploy-will-be-at = La cuenta se desplegará en: { $address }
+deploy-track = Puedes seguir este despliegue en la blockchain
+deploy-failed = Error al desplegar la smart account: { $error }
+check-bundler-running = El bundler está en marcha y admite eth_sendUserOperation
+check-predicted-funded = La dirección prevista de la cuenta tiene ETH
+check-factory-deployed = El contrato factory está desplegado y accesible
+predict-failed = Error al predecir la dirección de la smart account: { $error }
+hint-factory-deployed = Asegúrate de que el contrato factory esté desplegado y accesible
+
+## predict-address
+
+predict-start = Prediciendo la dirección de la smart account...
+predict-via-factory = Prediciendo la dirección con el contrato SimpleAccountFactory...
+predict-result = Dirección prevista real: { $address }
+predict-source = Esta dirección la calcula el propio contrato SimpleAccountFactory
+factory-call-failed = Error al llamar al contrato factory: { $error }
+hint-factory-and-rpc = Asegúrate de que el contrato factory esté desplegado y la URL RPC sea correcta
+
+## submit-sponsored y deploy-sponsored
+
+sponsored-start = Enviando transacción patrocinada mediante el paymaster...
+sponsored-preparing = Preparando la transacción patrocinada...
+pre-verification-gas-raised = pre_verification_gas aumentado a 46.000 para cumplir el mínimo del bundler
+gas-fees-gwei = Tarifas de gas - Máxima: { $max } gwei, Prioridad: { $priority } gwei
+sponsorship-requesting = Solicitando el patrocinio del paymaster...
+paymaster-data-generated = Datos del paymaster generados: { $data }
+paymaster-address-set = Dirección del paymaster establecida: { $address }
+sponsorship-obtained = ¡Patrocinio del paymaster obtenido!
+sponsorship-configured = Datos del paymaster configurados: el gas será patrocinado
+sponsored-submitting = Enviando la UserOperation patrocinada...
+sponsored-submitted = ¡Transacción patrocinada enviada correctamente!
+sponsored-gas-covered = ¡El paymaster patrocina las tarifas de gas!
+sponsored-executed = ¡Transacción patrocinada ejecutada correctamente!
+sponsored-completed = ¡Transacción sin gas completada!
+sponsored-failed = Falló la transacción patrocinada: { $error }
+cause-sponsorship-rejected = El servicio de paymaster rechazó el patrocinio
+cause-paymaster-config = Configuración del paymaster no válida
+cause-factory-inaccessible = El contrato factory no es accesible
+sponsored-deploy-start = Desplegando una smart account patrocinada mediante el paymaster...
+sponsored-deploy-no-eth = El paymaster patrocinará el despliegue: ¡no necesitas ETH!
+sponsored-deploy-creating = Creando la UserOperation de despliegue patrocinada...
+sponsorship-requesting-deploy = Solicitando el patrocinio del paymaster para el despliegue...
+sponsorship-deploy-approved = ¡Patrocinio del despliegue aprobado!
+sponsorship-deploy-covered = El paymaster cubrirá todos los costes del despliegue
+sponsored-deploy-submitting = Enviando el despliegue patrocinado...
+sponsored-deploy-initiated = ¡Despliegue patrocinado iniciado correctamente!
+sponsored-deploy-covered = ¡Los costes del despliegue están patrocinados!
+sponsored-deploy-zero-gas = ¡Tu smart account se está desplegando sin coste de gas!
+sponsored-deploy-next = Ya puedes usar submit-sponsored para hacer transacciones sin gas
+sponsored-deploy-failed = Falló el despliegue patrocinado: { $error }
+
+## networks
+
+networks-title = Redes compatibles
+network-heading = { $name } (Chain ID: { $chain_id }):
+label-rpc-template = Plantilla RPC: { $url }
+label-bundler-template = Plantilla del bundler: { $url }
+label-entry-point = EntryPoint: { $address }
+label-native-aa = Abstracción de cuentas nativa: las llamadas se envían como transacciones de tipo 113, sin EntryPoint
+usage-examples = Ejemplos de uso:
+example-anvil = Anvil (local)
+example-sepolia = Testnet Sepolia
+example-custom-rpc = Con un RPC personalizado
+
+## Comprobaciones del bundler
+
+bundler-preflight = Comprobando la red del bundler y su compatibilidad con el EntryPoint...
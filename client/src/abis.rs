@@ -0,0 +1,334 @@
+// Contract ABIs registered under a name in ~/.aa-client/abis, so that `call mytoken transfer 0x.. 5` can check
+// the function and its arguments against the ABI and show a receipt's events and a view call's results decoded
+
+use alloy::dyn_abi::{DynSolType, DynSolValue, Specifier};
+use alloy::json_abi::{Event, Function, JsonAbi, StateMutability};
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::calldata;
+use crate::config::data_dir;
+
+const ABIS_DIR: &str = "abis";
+
+/// A registered contract: its ABI and, when known, where it is deployed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractAbi {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    pub abi: JsonAbi,
+}
+
+/// ABI from a JSON file: a plain ABI array, or a Foundry or Hardhat artifact with an `abi` field
+pub fn parse_abi(json: &str) -> Result<JsonAbi> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let abi = match value {
+        serde_json::Value::Object(mut artifact) => artifact
+            .remove("abi")
+            .ok_or_else(|| anyhow::anyhow!("Expected an ABI array or an artifact with an \"abi\" field"))?,
+        abi => abi,
+    };
+    Ok(serde_json::from_value(abi)?)
+}
+
+impl ContractAbi {
+    /// Function by name, or by full signature to pick one overload; a name alone must match a single
+    /// overload taking `arg_count` arguments
+    pub fn function(&self, name_or_signature: &str, arg_count: usize) -> Result<&Function> {
+        let (name, signature) = match name_or_signature.split_once('(') {
+            Some((name, _)) => {
+                let parsed = Function::parse(name_or_signature)
+                    .map_err(|e| anyhow::anyhow!("Invalid function signature '{}': {}", name_or_signature, e))?;
+                (name.trim().to_string(), Some(parsed.signature()))
+            }
+            None => (name_or_signature.to_string(), None),
+        };
+        let overloads = self.abi.function(&name).map(Vec::as_slice).unwrap_or_default();
+        if overloads.is_empty() {
+            let mut names: Vec<&str> = self.abi.functions().map(|f| f.name.as_str()).collect();
+            names.dedup();
+            return Err(anyhow::anyhow!("No function '{}' in the ABI, available: {}", name, names.join(", ")));
+        }
+
+        let matching: Vec<&Function> = overloads
+            .iter()
+            .filter(|f| match &signature {
+                Some(signature) => &f.signature() == signature,
+                None => f.inputs.len() == arg_count,
+            })
+            .collect();
+        match matching.as_slice() {
+            [function] => Ok(function),
+            [] => Err(anyhow::anyhow!("Expected {}", usage(overloads))),
+            _ => Err(anyhow::anyhow!("'{}' is overloaded, give the full signature: {}", name, usage(overloads))),
+        }
+    }
+
+    /// Call data for `function`, each argument checked against its parameter type
+    pub fn encode_call(&self, function: &Function, args: &[String]) -> Result<Bytes> {
+        calldata::encode_function(function, args)
+            .map_err(|e| anyhow::anyhow!("{}\nUsage: {}", e, describe(function)))
+    }
+
+    /// Event of this contract a log was emitted as, formatted `Transfer(from: 0x.., to: 0x.., value: 5)`
+    /// None for logs of events not in the ABI
+    pub fn decode_log(&self, topics: &[B256], data: &[u8]) -> Option<String> {
+        let selector = topics.first()?;
+        let event = self.abi.events().find(|event| !event.anonymous && event.selector() == *selector)?;
+        decode_event(event, &topics[1..], data)
+    }
+}
+
+fn decode_event(event: &Event, topics: &[B256], data: &[u8]) -> Option<String> {
+    let mut indexed_topics = topics.iter();
+    let body_types = event
+        .inputs
+        .iter()
+        .filter(|param| !param.indexed)
+        .map(|param| param.resolve())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .ok()?;
+    let body = match DynSolType::Tuple(body_types).abi_decode_sequence(data).ok()? {
+        DynSolValue::Tuple(values) => values,
+        _ => return None,
+    };
+    let mut body = body.iter();
+
+    let mut fields = Vec::new();
+    for param in &event.inputs {
+        let ty = param.resolve().ok()?;
+        let value = if param.indexed {
+            let topic = indexed_topics.next()?;
+            // Dynamic values are indexed by their hash, which is all the log holds
+            if ty.is_dynamic() {
+                format!("{} (hash)", topic)
+            } else {
+                format_value(&ty.abi_decode(topic.as_slice()).ok()?)
+            }
+        } else {
+            format_value(body.next()?)
+        };
+        fields.push(match param.name.as_str() {
+            "" => value,
+            name => format!("{}: {}", name, value),
+        });
+    }
+    Some(format!("{}({})", event.name, fields.join(", ")))
+}
+
+/// Values returned by a view call, one line per output
+pub fn decode_output(function: &Function, data: &[u8]) -> Result<Vec<String>> {
+    let types = function.outputs.iter().map(|param| param.resolve()).collect::<std::result::Result<Vec<_>, _>>()?;
+    let values = match DynSolType::Tuple(types).abi_decode_sequence(data)? {
+        DynSolValue::Tuple(values) => values,
+        value => vec![value],
+    };
+    Ok(function
+        .outputs
+        .iter()
+        .zip(&values)
+        .map(|(param, value)| match param.name.as_str() {
+            "" => format_value(value),
+            name => format!("{}: {}", name, format_value(value)),
+        })
+        .collect())
+}
+
+/// Run a view function with eth_call and decode what it returns
+pub async fn call_view<P: Provider>(provider: &P, address: Address, function: &Function, call_data: Bytes) -> Result<Vec<String>> {
+    let tx = TransactionRequest::default().with_to(address).with_input(call_data);
+    decode_output(function, &provider.call(tx).await?)
+}
+
+/// Whether calling `function` reads state only, so it is run with eth_call instead of an operation
+pub fn is_read_only(function: &Function) -> bool {
+    matches!(function.state_mutability, StateMutability::View | StateMutability::Pure)
+}
+
+/// `transfer(address to, uint256 amount)`, parameter names included
+pub fn describe(function: &Function) -> String {
+    let params: Vec<String> = function
+        .inputs
+        .iter()
+        .map(|param| match param.name.as_str() {
+            "" => param.selector_type().into_owned(),
+            name => format!("{} {}", param.selector_type(), name),
+        })
+        .collect();
+    format!("{}({})", function.name, params.join(", "))
+}
+
+fn usage(overloads: &[Function]) -> String {
+    overloads.iter().map(describe).collect::<Vec<_>>().join(" or ")
+}
+
+/// Solidity-like rendering: decimal integers, checksummed addresses, hex bytes, quoted strings
+pub fn format_value(value: &DynSolValue) -> String {
+    let list = |values: &[DynSolValue]| values.iter().map(format_value).collect::<Vec<_>>().join(", ");
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::String(s) => format!("{:?}", s),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => format!("[{}]", list(values)),
+        DynSolValue::Tuple(values) => format!("({})", list(values)),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Directory of registered ABIs, one `<name>.json` file per contract
+pub struct AbiStore {
+    dir: PathBuf,
+}
+
+impl AbiStore {
+    /// Store at `~/.aa-client/abis`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(data_dir()?.join(ABIS_DIR)))
+    }
+
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Register the ABI in `file` as `name`, returning whether it replaced an existing one
+    pub fn add(&self, name: &str, file: &Path, address: Option<Address>) -> Result<bool> {
+        validate_name(name)?;
+        let json = std::fs::read_to_string(file).map_err(|e| anyhow::anyhow!("Could not read {}: {}", file.display(), e))?;
+        let abi = parse_abi(&json).map_err(|e| anyhow::anyhow!("Invalid ABI in {}: {}", file.display(), e))?;
+        if abi.functions().next().is_none() {
+            return Err(anyhow::anyhow!("{} has no functions to call", file.display()));
+        }
+
+        let path = self.path(name);
+        let replaced = path.exists();
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, serde_json::to_string_pretty(&ContractAbi { address, abi })?)?;
+        Ok(replaced)
+    }
+
+    pub fn get(&self, name: &str) -> Result<ContractAbi> {
+        validate_name(name)?;
+        let path = self.path(name);
+        if !path.exists() {
+            return Err(anyhow::anyhow!("No ABI named '{}', add one with `abi add`", name));
+        }
+        serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid ABI file {}: {}", path.display(), e))
+    }
+
+    /// Registered names, sorted
+    pub fn list(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+/// Names become file names, so only letters, digits, `-` and `_` are allowed
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow::anyhow!("ABI name '{}' may only contain letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{keccak256, U256};
+
+    const TOKEN_ABI: &str = r#"{"abi": [
+        {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+         "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+         "outputs": [{"name": "", "type": "bool"}]},
+        {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+         "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}, {"name": "data", "type": "bytes"}],
+         "outputs": []},
+        {"type": "function", "name": "balanceOf", "stateMutability": "view",
+         "inputs": [{"name": "owner", "type": "address"}],
+         "outputs": [{"name": "balance", "type": "uint256"}]},
+        {"type": "event", "name": "Transfer", "anonymous": false,
+         "inputs": [{"name": "from", "type": "address", "indexed": true},
+                    {"name": "to", "type": "address", "indexed": true},
+                    {"name": "value", "type": "uint256", "indexed": false}]}
+    ]}"#;
+
+    fn token() -> ContractAbi {
+        ContractAbi { address: None, abi: parse_abi(TOKEN_ABI).unwrap() }
+    }
+
+    #[test]
+    fn test_function_resolves_overloads() {
+        let token = token();
+        assert_eq!(token.function("transfer", 2).unwrap().signature(), "transfer(address,uint256)");
+        assert_eq!(token.function("transfer", 3).unwrap().inputs.len(), 3);
+        assert_eq!(
+            token.function("transfer(address to, uint256 amount, bytes data)", 0).unwrap().signature(),
+            "transfer(address,uint256,bytes)"
+        );
+        assert!(token.function("transfer", 1).unwrap_err().to_string().contains("transfer(address to, uint256 amount)"));
+        assert!(token.function("approve", 2).unwrap_err().to_string().contains("balanceOf"));
+        assert!(is_read_only(token.function("balanceOf", 1).unwrap()));
+
+        let function = token.function("transfer", 2).unwrap();
+        let to = "0x1234567890123456789012345678901234567890".to_string();
+        let error = token.encode_call(function, &[to, "-1".to_string()]).unwrap_err().to_string();
+        assert!(error.contains("amount"));
+    }
+
+    #[test]
+    fn test_decode_log_and_output() {
+        let token = token();
+        let from = Address::from([0x11; 20]);
+        let to = Address::from([0x22; 20]);
+        let topics = [keccak256("Transfer(address,address,uint256)"), from.into_word(), to.into_word()];
+        let data = U256::from(5).to_be_bytes::<32>();
+        assert_eq!(token.decode_log(&topics, &data).unwrap(), format!("Transfer(from: {}, to: {}, value: 5)", from, to));
+        assert!(token.decode_log(&[B256::ZERO], &data).is_none());
+
+        let balance_of = token.function("balanceOf", 1).unwrap();
+        assert_eq!(decode_output(balance_of, &data).unwrap(), vec!["balance: 5"]);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("aa-client-abis-{}", std::process::id()));
+        let file = std::env::temp_dir().join(format!("aa-client-token-{}.json", std::process::id()));
+        std::fs::write(&file, TOKEN_ABI).unwrap();
+        let store = AbiStore::open(&dir);
+
+        assert!(!store.add("mytoken", &file, Some(Address::from([0x33; 20]))).unwrap());
+        assert!(store.add("mytoken", &file, None).unwrap());
+        assert_eq!(store.list().unwrap(), vec!["mytoken"]);
+        assert_eq!(store.get("mytoken").unwrap(), token());
+        assert!(store.get("other").is_err());
+        assert!(store.add("../escape", &file, None).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_file(&file);
+    }
+}
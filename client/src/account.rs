@@ -0,0 +1,484 @@
+// Multi-owner AAAccount support: a native aa-sdk-rs SmartAccount plus owner management helpers
+// Owner management goes through a UserOperation that calls back into the account itself
+
+use aa_sdk_rs::smart_account::{AccountError, SmartAccount};
+use aa_sdk_rs::types::ExecuteCall;
+use alloy::primitives::{aliases::U192, keccak256, Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::signers::Signature;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::factory::{self, FactoryAbi};
+use crate::dummy_signature::{self, DummySignature};
+use crate::entrypoint::{self, EntryPointStatus};
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IAAAccount {
+        function execute(address target, uint256 value, bytes data) external;
+        function executeBatch(address[] targets, uint256[] values, bytes[] datas) external;
+        function addOwner(address newOwner) external;
+        function removeOwner(address ownerToRemove) external;
+        function getOwners() external view returns (address[] memory);
+        function isOwner(address owner) external view returns (bool);
+        function ownerCount() external view returns (uint256);
+        function ownerThreshold() external view returns (uint256);
+        function setThreshold(uint256 threshold) external;
+        function initializeWithOwners(address[] initialOwners, address entryPointAddr) external;
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IEntryPointNonces {
+        function getNonce(address sender, uint192 key) external view returns (uint256 nonce);
+    }
+);
+
+/// Multi-owner account deployed through AAAccountFactory
+/// The counterfactual address and initCode come from the full owner set and salt, so no
+/// fields of the UserOperation need patching after aa-sdk-rs fills it
+/// With `FactoryAbi::Simple` it is a single-owner SimpleAccount, deployed for any salt
+pub struct AAAccount<P> {
+    provider: Arc<P>,
+    owners: Vec<Address>,
+    salt: U256,
+    factory: Address,
+    /// Factory ABI before the `--factory-abi` override, multi-owner unless set otherwise
+    factory_abi: FactoryAbi,
+    entry_point: Address,
+    chain_id: u64,
+    /// Placeholder estimated with, one owner signature unless the account is signed by several
+    dummy_signature: DummySignature,
+    /// Resolved on first use, the address never changes for a given owner set and salt
+    address: Mutex<Option<Address>>,
+}
+
+impl<P> AAAccount<P>
+where
+    P: Provider + Send + Sync,
+{
+    pub fn new(provider: Arc<P>, owners: Vec<Address>, salt: U256, factory: Address, entry_point: Address, chain_id: u64) -> Self {
+        Self {
+            provider,
+            owners,
+            salt,
+            factory,
+            factory_abi: FactoryAbi::MultiOwner,
+            entry_point,
+            chain_id,
+            dummy_signature: DummySignature::Ecdsa,
+            address: Mutex::new(None),
+        }
+    }
+
+    /// Account that is already deployed at a known address
+    pub fn at(provider: Arc<P>, address: Address, factory: Address, entry_point: Address, chain_id: u64) -> Self {
+        let account = Self::new(provider, Vec::new(), U256::ZERO, factory, entry_point, chain_id);
+        *account.address.lock().unwrap() = Some(address);
+        account
+    }
+
+    /// Predict and deploy through another factory ABI, e.g. `Simple` for a single-owner SimpleAccount
+    pub fn with_factory_abi(mut self, factory_abi: FactoryAbi) -> Self {
+        self.factory_abi = factory_abi;
+        self
+    }
+
+    /// Estimate with another placeholder, e.g. `MultiOwner(n)` for operations n owners will sign
+    pub fn with_dummy_signature(mut self, dummy_signature: DummySignature) -> Self {
+        self.dummy_signature = dummy_signature;
+        self
+    }
+
+    pub fn owners(&self) -> &[Address] {
+        &self.owners
+    }
+
+    /// Factory calldata creating the account, `createAccountWithOwners(owners, salt)` unless `--factory-abi` says otherwise
+    pub fn factory_call_data(&self) -> Result<Bytes> {
+        factory::resolve(self.factory_abi).encoding().create_account_call(&self.owners, self.salt)
+    }
+}
+
+impl<P> SmartAccount<P> for AAAccount<P>
+where
+    P: Provider + Send + Sync,
+{
+    fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    fn get_entry_point_address(&self) -> Address {
+        self.entry_point
+    }
+
+    fn get_chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn get_factory_address(&self) -> Address {
+        self.factory
+    }
+
+    async fn get_factory_data(&self) -> Bytes {
+        // An owner set the ABI cannot encode already failed get_counterfactual_address, which runs first
+        self.factory_call_data().unwrap_or_default()
+    }
+
+    async fn get_counterfactual_address(&self) -> Result<Address, AccountError> {
+        if let Some(address) = *self.address.lock().unwrap() {
+            return Ok(address);
+        }
+
+        let abi = factory::resolve(self.factory_abi);
+        let address = factory::predict_address(&*self.provider, self.factory, abi, &self.owners, self.salt)
+            .await
+            .map_err(|e| AccountError::RpcError(e.to_string()))?;
+        *self.address.lock().unwrap() = Some(address);
+        Ok(address)
+    }
+
+    async fn get_account_address(&self) -> Result<Address, AccountError> {
+        self.get_counterfactual_address().await
+    }
+
+    async fn is_account_deployed(&self) -> Result<bool, AccountError> {
+        let address = self.get_account_address().await?;
+        let code = self
+            .provider
+            .get_code_at(address)
+            .await
+            .map_err(|e| AccountError::RpcError(e.to_string()))?;
+        Ok(!code.is_empty())
+    }
+
+    async fn get_nonce(&self) -> Result<U256, AccountError> {
+        let address = self.get_account_address().await?;
+        get_nonce(&*self.provider, self.entry_point, address)
+            .await
+            .map_err(|e| AccountError::RpcError(e.to_string()))
+    }
+
+    fn encode_execute(&self, call: ExecuteCall) -> Vec<u8> {
+        IAAAccount::executeCall { target: call.target, value: call.value, data: call.data }.abi_encode()
+    }
+
+    fn encode_execute_batch(&self, calls: Vec<ExecuteCall>) -> Vec<u8> {
+        let mut targets = Vec::with_capacity(calls.len());
+        let mut values = Vec::with_capacity(calls.len());
+        let mut datas = Vec::with_capacity(calls.len());
+        for call in calls {
+            targets.push(call.target);
+            values.push(call.value);
+            datas.push(call.data);
+        }
+        IAAAccount::executeBatchCall { targets, values, datas }.abi_encode()
+    }
+
+    fn get_dummy_signature(&self) -> Bytes {
+        dummy_signature::resolve(&self.dummy_signature)
+    }
+}
+
+/// CREATE2 salt AAAccountFactory derives from the base salt and owner set: keccak256(abi.encodePacked(salt, owners))
+/// Packed arrays keep their 32-byte element padding, so each owner takes a full word
+pub fn multi_owner_salt(salt: U256, owners: &[Address]) -> B256 {
+    let mut packed = salt.to_be_bytes::<32>().to_vec();
+    for owner in owners {
+        packed.extend_from_slice(owner.into_word().as_slice());
+    }
+    keccak256(packed)
+}
+
+/// Address `factory` deploys the multi-owner account at, computed without asking it
+/// The init code is the ERC1967Proxy creation code followed by the implementation and its initializeWithOwners call
+pub fn predict_multi_owner_address(
+    factory: Address,
+    proxy_creation_code: &[u8],
+    implementation: Address,
+    entry_point: Address,
+    owners: &[Address],
+    salt: U256,
+) -> Address {
+    let initialize: Bytes =
+        IAAAccount::initializeWithOwnersCall { initialOwners: owners.to_vec(), entryPointAddr: entry_point }.abi_encode().into();
+    let mut init_code = proxy_creation_code.to_vec();
+    init_code.extend((implementation, initialize).abi_encode_params());
+    factory.create2(multi_owner_salt(salt, owners), keccak256(init_code))
+}
+
+/// ERC1967Proxy creation code from its forge artifact (`contracts/out/ERC1967Proxy.sol/ERC1967Proxy.json`)
+pub fn proxy_creation_code(artifact: &str) -> Result<Bytes> {
+    let artifact: serde_json::Value = serde_json::from_str(artifact).map_err(|e| anyhow::anyhow!("Not a forge artifact: {}", e))?;
+    let object = artifact["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Forge artifact has no bytecode.object"))?;
+    Ok(crate::hexutil::decode_bytes("proxy creation code", object)?)
+}
+
+/// Calldata for `addOwner(newOwner)`
+pub fn add_owner_call_data(new_owner: Address) -> Bytes {
+    IAAAccount::addOwnerCall { newOwner: new_owner }.abi_encode().into()
+}
+
+/// Calldata for `removeOwner(ownerToRemove)`
+pub fn remove_owner_call_data(owner: Address) -> Bytes {
+    IAAAccount::removeOwnerCall { ownerToRemove: owner }.abi_encode().into()
+}
+
+/// Calls that hand `old_owner`'s seat to `new_owner` in one batch
+/// The new owner is added first, so the account never has fewer owners than it started with
+pub fn rotate_owner_calls(account: Address, old_owner: Address, new_owner: Address) -> Vec<ExecuteCall> {
+    vec![
+        ExecuteCall::new(account, U256::ZERO, add_owner_call_data(new_owner)),
+        ExecuteCall::new(account, U256::ZERO, remove_owner_call_data(old_owner)),
+    ]
+}
+
+/// Message the new owner signs (EIP-191) to show it holds its key before a rotation hands it the account
+pub fn rotation_challenge(account: Address, new_owner: Address, chain_id: u64) -> String {
+    format!("Accept ownership of smart account {} on chain {} as {}", account, chain_id, new_owner)
+}
+
+/// Check the new owner's signature over its rotation challenge
+/// Only an ECDSA signature counts, the account validates nothing else from an owner
+pub fn verify_rotation_proof(challenge: &str, new_owner: Address, proof: &[u8]) -> Result<()> {
+    let signature = Signature::try_from(proof).map_err(|e| anyhow::anyhow!("Malformed signature from the new owner: {}", e))?;
+    let recovered = signature.recover_address_from_msg(challenge.as_bytes())?;
+    if recovered != new_owner {
+        return Err(anyhow::anyhow!("Rotation challenge was signed by {}, not the new owner {}", recovered, new_owner));
+    }
+    Ok(())
+}
+
+/// Whether `account` takes `executeBatch`, probed with an empty batch called by one of its owners
+pub async fn supports_batch<P: Provider>(provider: &P, account: Address, owner: Address) -> bool {
+    let contract = IAAAccount::new(account, provider);
+    contract.executeBatch(Vec::new(), Vec::new(), Vec::new()).from(owner).call().await.is_ok()
+}
+
+/// Calldata for `setThreshold(threshold)`
+pub fn set_threshold_call_data(threshold: u64) -> Bytes {
+    IAAAccount::setThresholdCall { threshold: U256::from(threshold) }.abi_encode().into()
+}
+
+/// Owner signatures a UserOperation of `account` needs
+/// None when no threshold is set or the account predates thresholds
+pub async fn get_threshold<P: Provider>(provider: &P, account: Address) -> Option<usize> {
+    let threshold = IAAAccount::new(account, provider).ownerThreshold().call().await.ok()?._0;
+    (threshold > U256::ZERO).then(|| threshold.saturating_to::<usize>())
+}
+
+/// Check a threshold against the number of owners, as `setThreshold` does
+pub fn validate_threshold(threshold: u64, owner_count: usize) -> Result<()> {
+    if threshold == 0 || threshold as usize > owner_count {
+        return Err(anyhow::anyhow!("Threshold must be between 1 and {} owners", owner_count));
+    }
+    Ok(())
+}
+
+/// Current owners of a deployed account
+pub async fn get_owners<P: Provider>(provider: &P, account: Address) -> Result<Vec<Address>> {
+    let contract = IAAAccount::new(account, provider);
+    Ok(contract.getOwners().call().await?._0)
+}
+
+/// Next EntryPoint nonce for `sender` on the default key
+pub async fn get_nonce<P: Provider>(provider: &P, entry_point: Address, sender: Address) -> Result<U256> {
+    get_nonce_at_key(provider, entry_point, sender, U192::ZERO).await
+}
+
+/// Next EntryPoint nonce for `sender` on a 2D nonce key
+pub async fn get_nonce_at_key<P: Provider>(provider: &P, entry_point: Address, sender: Address, key: U192) -> Result<U256> {
+    let contract = IEntryPointNonces::new(entry_point, provider);
+    Ok(contract.getNonce(sender, key).call().await?.nonce)
+}
+
+/// On-chain state of a smart account, deployed or counterfactual
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountOverview {
+    pub address: Address,
+    pub deployed: bool,
+    pub balance: U256,
+    /// Deposit held for the account by each known EntryPoint
+    pub entry_points: Vec<EntryPointStatus>,
+    /// EntryPoint the nonces were read from: the account's own, or v0.7 before deployment
+    pub entry_point: Address,
+    /// (key, next nonce) for every requested key
+    pub nonces: Vec<(U192, U256)>,
+    /// None before deployment or when the account has no `getOwners()`
+    pub owners: Option<Vec<Address>>,
+    /// Owner signatures each operation needs, None when any single owner signs
+    pub threshold: Option<usize>,
+}
+
+/// Gather everything `account info` shows, nonces for each of `nonce_keys`
+pub async fn overview<P: Provider>(provider: &P, address: Address, nonce_keys: &[U192]) -> Result<AccountOverview> {
+    let deployed = !provider.get_code_at(address).await?.is_empty();
+    let balance = provider.get_balance(address).await?;
+    let entry_points = entrypoint::entry_point_statuses(provider, address).await?;
+    let entry_point = entry_points
+        .iter()
+        .find(|status| status.active)
+        .map(|status| status.version.address())
+        .unwrap_or(entrypoint::EntryPointVersion::V07.address());
+
+    let mut nonces = Vec::new();
+    for key in nonce_keys {
+        nonces.push((*key, get_nonce_at_key(provider, entry_point, address, *key).await?));
+    }
+    let owners = if deployed { get_owners(provider, address).await.ok() } else { None };
+    let threshold = if deployed { get_threshold(provider, address).await } else { None };
+
+    Ok(AccountOverview { address, deployed, balance, entry_points, entry_point, nonces, owners, threshold })
+}
+
+/// Check an owner change against the current owner set and threshold before spending gas on it
+pub fn validate_owner_change(
+    owners: &[Address],
+    threshold: Option<usize>,
+    signer: Address,
+    add: Option<Address>,
+    remove: Option<Address>,
+) -> Result<()> {
+    if !owners.contains(&signer) {
+        return Err(anyhow::anyhow!("Signer {} is not an owner of this account", signer));
+    }
+    if let Some(new_owner) = add {
+        if new_owner == Address::ZERO {
+            return Err(anyhow::anyhow!("Cannot add the zero address as an owner"));
+        }
+        if owners.contains(&new_owner) {
+            return Err(anyhow::anyhow!("{} is already an owner", new_owner));
+        }
+    }
+    if let Some(owner) = remove {
+        if !owners.contains(&owner) {
+            return Err(anyhow::anyhow!("{} is not an owner", owner));
+        }
+        if owners.len() == 1 {
+            return Err(anyhow::anyhow!("Cannot remove the last owner"));
+        }
+        if let Some(threshold) = threshold.filter(|threshold| owners.len() <= *threshold) {
+            return Err(anyhow::anyhow!("Removing {} would leave fewer owners than the threshold of {}, lower it first", owner, threshold));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_call_data_encoding() {
+        let owner = Address::from([7u8; 20]);
+
+        let add = add_owner_call_data(owner);
+        assert_eq!(&add[..4], IAAAccount::addOwnerCall::SELECTOR.as_slice());
+        assert_eq!(add.len(), 4 + 32);
+        assert_eq!(&add[16..], owner.as_slice());
+
+        let remove = remove_owner_call_data(owner);
+        assert_eq!(&remove[..4], IAAAccount::removeOwnerCall::SELECTOR.as_slice());
+        assert_eq!(&remove[16..], owner.as_slice());
+    }
+
+    #[test]
+    fn test_multi_owner_factory_data() {
+        let provider = Arc::new(crate::providers::http("http://localhost:8545").unwrap());
+        let owners = vec![Address::from([1u8; 20]), Address::from([2u8; 20])];
+        let account = AAAccount::new(provider, owners.clone(), U256::from(7), Address::from([3u8; 20]), Address::ZERO, 31337);
+
+        let decoded = factory::AAAccountFactory::createAccountWithOwnersCall::abi_decode(&account.factory_call_data().unwrap(), true).unwrap();
+        assert_eq!(decoded.owners, owners);
+        assert_eq!(decoded.salt, U256::from(7));
+        assert_eq!(account.get_dummy_signature().len(), 65);
+        let account = account.with_dummy_signature(DummySignature::MultiOwner(2));
+        assert_eq!(account.get_dummy_signature().len(), 2 * 65);
+
+        // A single-owner SimpleAccount deploys with the salt it was given
+        let provider = Arc::new(crate::providers::http("http://localhost:8545").unwrap());
+        let account = AAAccount::new(provider, vec![owners[0]], U256::from(9), Address::from([3u8; 20]), Address::ZERO, 31337)
+            .with_factory_abi(FactoryAbi::Simple);
+        let decoded = factory::SimpleAccountFactory::createAccountCall::abi_decode(&account.factory_call_data().unwrap(), true).unwrap();
+        assert_eq!(decoded.owner, owners[0]);
+        assert_eq!(decoded.salt, U256::from(9));
+    }
+
+    #[test]
+    fn test_multi_owner_create2() {
+        let owners = [Address::repeat_byte(0x01), Address::repeat_byte(0x02)];
+        let mut packed = U256::from(5).to_be_bytes::<32>().to_vec();
+        packed.extend([[0u8; 12].as_slice(), &[0x01; 20], &[0u8; 12], &[0x02; 20]].concat());
+        assert_eq!(multi_owner_salt(U256::from(5), &owners), keccak256(packed));
+
+        let predict = |owners: &[Address], salt: u64| {
+            predict_multi_owner_address(Address::repeat_byte(0xfa), &[0x60, 0x80], Address::repeat_byte(0x1a), Address::repeat_byte(0xe4), owners, U256::from(salt))
+        };
+        // The owner order is part of the salt and of the initializer
+        assert_ne!(predict(&owners, 5), predict(&[owners[1], owners[0]], 5));
+        assert_ne!(predict(&owners, 5), predict(&owners, 6));
+
+        let artifact = r#"{"abi": [], "bytecode": {"object": "0x6080"}}"#;
+        assert_eq!(proxy_creation_code(artifact).unwrap().as_ref(), &[0x60, 0x80]);
+        assert!(proxy_creation_code("{}").is_err());
+    }
+
+    #[test]
+    fn test_validate_owner_change() {
+        let a = Address::from([1u8; 20]);
+        let b = Address::from([2u8; 20]);
+        let c = Address::from([3u8; 20]);
+
+        assert!(validate_owner_change(&[a], None, a, Some(b), None).is_ok());
+        assert!(validate_owner_change(&[a], None, c, Some(b), None).is_err());
+        assert!(validate_owner_change(&[a, b], None, a, Some(b), None).is_err());
+        assert!(validate_owner_change(&[a, b], None, a, None, Some(b)).is_ok());
+        assert!(validate_owner_change(&[a, b], None, a, None, Some(c)).is_err());
+        assert!(validate_owner_change(&[a], None, a, None, Some(a)).is_err());
+        // Two of two owners cannot shrink to one
+        assert!(validate_owner_change(&[a, b], Some(2), a, None, Some(b)).is_err());
+        assert!(validate_owner_change(&[a, b, c], Some(2), a, None, Some(b)).is_ok());
+    }
+
+    #[test]
+    fn test_rotation_proof() {
+        use crate::wallet::Wallet;
+        use alloy::signers::SignerSync;
+
+        let account = Address::from([9u8; 20]);
+        let new_owner = Wallet::new([0x11; 32]).unwrap();
+        let challenge = rotation_challenge(account, new_owner.address(), 31337);
+        let proof = new_owner.signer().sign_message_sync(challenge.as_bytes()).unwrap().as_bytes();
+
+        assert!(verify_rotation_proof(&challenge, new_owner.address(), &proof).is_ok());
+        // Bound to the account and chain it was made for
+        assert!(verify_rotation_proof(&rotation_challenge(account, new_owner.address(), 1), new_owner.address(), &proof).is_err());
+        assert!(verify_rotation_proof(&challenge, Address::from([7u8; 20]), &proof).is_err());
+        assert!(verify_rotation_proof(&challenge, new_owner.address(), &proof[..64]).is_err());
+
+        let calls = rotate_owner_calls(account, Address::from([1u8; 20]), new_owner.address());
+        assert_eq!(calls[0].data, add_owner_call_data(new_owner.address()));
+        assert_eq!(calls[1].data, remove_owner_call_data(Address::from([1u8; 20])));
+        assert!(calls.iter().all(|call| call.target == account));
+    }
+
+    #[test]
+    fn test_threshold_call_data() {
+        let call_data = set_threshold_call_data(2);
+        assert_eq!(&call_data[..4], IAAAccount::setThresholdCall::SELECTOR.as_slice());
+        assert_eq!(U256::from_be_slice(&call_data[4..]), U256::from(2));
+
+        assert!(validate_threshold(2, 3).is_ok());
+        assert!(validate_threshold(3, 3).is_ok());
+        assert!(validate_threshold(0, 3).is_err());
+        assert!(validate_threshold(4, 3).is_err());
+    }
+}
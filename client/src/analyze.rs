@@ -0,0 +1,166 @@
+// Calldata cost breakdown of a UserOperation, the dominant cost of an operation on L2 rollups
+// Rollups post the bundle transaction to L1, where every zero byte costs 4 gas and every other byte 16
+
+use alloy::primitives::{address, Address, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+
+use crate::factory::{self, SimpleAccountFactory};
+use crate::multisig::UnsignedUserOperation;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IGasPriceOracle {
+        function l1BaseFee() external view returns (uint256);
+    }
+);
+
+/// OP Stack predeploy reporting the L1 base fee (Optimism, Base and other OP Stack chains)
+pub const OP_GAS_PRICE_ORACLE: Address = address!("420000000000000000000000000000000000000F");
+
+/// L1 calldata gas per zero byte
+pub const ZERO_BYTE_GAS: u64 = 4;
+/// L1 calldata gas per non-zero byte
+pub const NON_ZERO_BYTE_GAS: u64 = 16;
+
+/// Zero and non-zero bytes of one piece of calldata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteCounts {
+    pub zero: usize,
+    pub non_zero: usize,
+}
+
+impl ByteCounts {
+    pub fn of(bytes: &[u8]) -> Self {
+        let zero = bytes.iter().filter(|b| **b == 0).count();
+        Self { zero, non_zero: bytes.len() - zero }
+    }
+
+    pub fn len(&self) -> usize {
+        self.zero + self.non_zero
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gas this data costs as L1 calldata
+    pub fn calldata_gas(&self) -> u64 {
+        self.zero as u64 * ZERO_BYTE_GAS + self.non_zero as u64 * NON_ZERO_BYTE_GAS
+    }
+}
+
+/// Byte counts of an operation's variable-length fields and of its packed form, with savings to consider
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub fields: Vec<(&'static str, ByteCounts)>,
+    /// The operation as it appears in the bundle's handleOps calldata
+    pub packed: ByteCounts,
+    pub suggestions: Vec<String>,
+}
+
+impl Report {
+    /// L1 data fee upper bound in wei, before the rollup compresses the batch
+    pub fn l1_data_cost(&self, l1_base_fee: u128) -> U256 {
+        U256::from(self.packed.calldata_gas()) * U256::from(l1_base_fee)
+    }
+}
+
+/// Break down `user_op`, `packed` being its ABI-encoded PackedUserOperation including the signature
+pub fn analyze(user_op: &UnsignedUserOperation, signature: &[u8], packed: &[u8]) -> Report {
+    let init_code = user_op.init_code();
+    let paymaster_and_data = user_op.paymaster_and_data();
+    let fields = vec![
+        ("callData", ByteCounts::of(&user_op.call_data)),
+        ("initCode", ByteCounts::of(&init_code)),
+        ("paymasterAndData", ByteCounts::of(&paymaster_and_data)),
+        ("signature", ByteCounts::of(signature)),
+    ];
+
+    let mut suggestions = Vec::new();
+    if let Some(salt) = user_op.factory_data.as_deref().and_then(factory_salt) {
+        let salt_bytes = ByteCounts::of(salt);
+        if salt_bytes.non_zero > 1 {
+            let saved = (salt_bytes.non_zero - 1) as u64 * (NON_ZERO_BYTE_GAS - ZERO_BYTE_GAS);
+            suggestions.push(format!(
+                "The salt has {} non-zero bytes; a small salt such as 0x01 saves {} L1 gas on the deploying operation",
+                salt_bytes.non_zero, saved
+            ));
+        }
+    }
+    if !init_code.is_empty() {
+        suggestions.push(format!(
+            "initCode adds {} bytes ({} L1 gas); it is only sent with the first operation, later ones are cheaper",
+            init_code.len(),
+            ByteCounts::of(&init_code).calldata_gas()
+        ));
+    }
+    let call_data = ByteCounts::of(&user_op.call_data);
+    if !call_data.is_empty() && call_data.non_zero * 2 > call_data.len() {
+        suggestions.push(
+            "Most callData bytes are non-zero; round amounts and addresses with leading zero bytes encode cheaper".to_string(),
+        );
+    }
+    if signature.len() > 65 {
+        suggestions.push(format!(
+            "The signature is {} bytes; every additional owner signature adds about {} L1 gas",
+            signature.len(),
+            65 * NON_ZERO_BYTE_GAS
+        ));
+    }
+
+    Report { fields, packed: ByteCounts::of(packed), suggestions }
+}
+
+/// Salt word of a known factory call: the second head word in every factory ABI
+fn factory_salt(factory_data: &[u8]) -> Option<&[u8]> {
+    let known = factory::is_create_selector(factory_data.get(..4)?);
+    if known { factory_data.get(36..68) } else { None }
+}
+
+/// L1 base fee from the OP Stack gas price oracle, None on chains without one
+pub async fn op_stack_l1_base_fee<P: Provider>(provider: &P) -> Option<u128> {
+    if provider.get_code_at(OP_GAS_PRICE_ORACLE).await.ok()?.is_empty() {
+        return None;
+    }
+    let fee = IGasPriceOracle::new(OP_GAS_PRICE_ORACLE, provider).l1BaseFee().call().await.ok()?._0;
+    Some(fee.saturating_to())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Bytes;
+
+    #[test]
+    fn test_byte_counts() {
+        let counts = ByteCounts::of(&[0, 0, 1, 0xff]);
+        assert_eq!(counts, ByteCounts { zero: 2, non_zero: 2 });
+        assert_eq!(counts.calldata_gas(), 2 * 4 + 2 * 16);
+    }
+
+    #[test]
+    fn test_salt_suggestion() {
+        let owner = Address::from([0x11; 20]);
+        let mut user_op = UnsignedUserOperation::execute(Address::from([9u8; 20]), U256::ZERO, owner, U256::ZERO, Bytes::new());
+        user_op.factory = Some(Address::from([5u8; 20]));
+
+        let random_salt = U256::from_be_bytes([0xab; 32]);
+        user_op.factory_data = Some(SimpleAccountFactory::createAccountCall { owner, salt: random_salt }.abi_encode().into());
+        let report = analyze(&user_op, &[0x1b; 65], &[]);
+        assert!(report.suggestions[0].contains("32 non-zero bytes"));
+        assert_eq!(report.fields[1].1.len(), 20 + 4 + 64);
+
+        user_op.factory_data = Some(SimpleAccountFactory::createAccountCall { owner, salt: U256::from(1) }.abi_encode().into());
+        let report = analyze(&user_op, &[0x1b; 65], &[]);
+        assert!(!report.suggestions.iter().any(|s| s.contains("salt")));
+    }
+
+    #[test]
+    fn test_l1_data_cost() {
+        let report = Report { fields: Vec::new(), packed: ByteCounts { zero: 10, non_zero: 5 }, suggestions: Vec::new() };
+        assert_eq!(report.l1_data_cost(2), U256::from((10 * 4 + 5 * 16) * 2));
+    }
+}
@@ -2,120 +2,406 @@
 // This implements actual bundler RPC calls and contract interactions
 
 use alloy::primitives::{Address, U256};
-use alloy::providers::{Provider, ProviderBuilder};
-use alloy::sol;
+use alloy::providers::Provider;
 use anyhow::Result;
-use url::Url;
-
-// Standard ERC-4337 SimpleAccountFactory ABI
-sol!(
-    #[allow(missing_docs)]
-    #[sol(rpc)]
-    SimpleAccountFactory,
-    r#"[
-        {
-            "inputs": [
-                {"internalType": "address", "name": "owner", "type": "address"},
-                {"internalType": "uint256", "name": "salt", "type": "uint256"}
-            ],
-            "name": "createAccount",
-            "outputs": [
-                {"internalType": "contract SimpleAccount", "name": "ret", "type": "address"}
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {"internalType": "address", "name": "owner", "type": "address"},
-                {"internalType": "uint256", "name": "salt", "type": "uint256"}
-            ],
-            "name": "getAddress",
-            "outputs": [
-                {"internalType": "address", "name": "", "type": "address"}
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        }
-    ]"#
-);
-
-// AAAccountFactory ABI for multi-owner support
-sol!(
-    #[allow(missing_docs)]
-    #[sol(rpc)]
-    AAAccountFactory,
-    r#"[
-        {
-            "inputs": [
-                {"internalType": "address[]", "name": "owners", "type": "address[]"},
-                {"internalType": "uint256", "name": "salt", "type": "uint256"}
-            ],
-            "name": "createAccountWithOwners",
-            "outputs": [
-                {"internalType": "contract AAAccount", "name": "account", "type": "address"}
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {"internalType": "address[]", "name": "owners", "type": "address[]"},
-                {"internalType": "uint256", "name": "salt", "type": "uint256"}
-            ],
-            "name": "getAddressWithOwners",
-            "outputs": [
-                {"internalType": "address", "name": "", "type": "address"}
-            ],
-            "stateMutability": "view",
-            "type": "function"
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::config;
+use crate::events::{self, ClientEvent};
+use crate::factory::{self, FactoryAbi};
+use crate::health::HealthStore;
+use crate::out;
+use crate::providers::{self, ProviderRegistry};
+use crate::retry::{self, RetryPolicy};
+use crate::userop::UserOperationGasEstimation;
+
+/// Each attempt against an endpoint gets this long before it counts as failed
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Backup bundlers and racing, configured once per invocation from flags or the profile
+#[derive(Debug, Clone, Default)]
+pub struct FailoverConfig {
+    /// Endpoints tried in order after the primary `--rpc-url`
+    pub backups: Vec<String>,
+    /// Submit to every endpoint at once and keep the first accepted hash
+    pub race: bool,
+    /// Private bundlers (`--private`) that keep operations out of the public mempool
+    pub private: Vec<String>,
+}
+
+impl FailoverConfig {
+    /// Primary endpoint, backups and racing for a client created for `rpc_url`
+    /// Private bundlers replace every public endpoint, so a failure never falls back to the public mempool
+    fn route(self, rpc_url: String) -> (String, Vec<String>, bool) {
+        match self.private.split_first() {
+            Some((first, rest)) => (first.clone(), rest.to_vec(), false),
+            None => (rpc_url, self.backups, self.race),
         }
-    ]"#
-);
+    }
+}
+
+static FAILOVER: OnceLock<FailoverConfig> = OnceLock::new();
+
+/// Set the failover configuration used by every `BundlerClient` created afterwards
+pub fn set_failover(config: FailoverConfig) {
+    let _ = FAILOVER.set(config);
+}
+
+/// Whether operations are only submitted through private bundlers
+pub fn private_submission() -> bool {
+    FAILOVER.get().is_some_and(|config| !config.private.is_empty())
+}
 
 /// Modern bundler client wrapper that can create aa-sdk-rs providers
 /// This provides compatibility while enabling use of aa-sdk-rs functionality
 pub struct BundlerClient {
     rpc_url: String,
+    backups: Vec<String>,
+    race: bool,
+    entry_point: Address,
+    chain_id: u64,
+    /// Providers and the cache of what each endpoint serves
+    registry: &'static ProviderRegistry,
+    health: HealthStore,
+    retry: RetryPolicy,
 }
 
 impl BundlerClient {
     /// Create a new bundler client
-    pub fn new(rpc_url: String, _entry_point: Address, _chain_id: U256) -> Self {
-        Self { rpc_url }
+    pub fn new(rpc_url: String, entry_point: Address, chain_id: U256) -> Self {
+        let (rpc_url, backups, race) = FAILOVER.get().cloned().unwrap_or_default().route(rpc_url);
+        Self {
+            rpc_url,
+            backups,
+            race,
+            entry_point,
+            chain_id: chain_id.saturating_to::<u64>(),
+            registry: providers::registry(),
+            health: HealthStore::in_memory(),
+            retry: retry::policy(),
+        }
+    }
+
+    /// Replace the provider registry (e.g. with an in-memory one for tests)
+    pub fn with_registry(mut self, registry: &'static ProviderRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Replace the endpoint health records, in memory unless e.g. `HealthStore::persistent()` is passed
+    pub fn with_health(mut self, health: HealthStore) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Replace the retry policy applied to each endpoint before failing over
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_backups(mut self, backups: Vec<String>) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    pub fn with_racing(mut self, race: bool) -> Self {
+        self.race = race;
+        self
+    }
+
+    /// Primary endpoint followed by the backups, in configured order
+    pub fn endpoints(&self) -> Vec<String> {
+        let mut endpoints = vec![self.rpc_url.clone()];
+        for backup in &self.backups {
+            if !endpoints.contains(backup) {
+                endpoints.push(backup.clone());
+            }
+        }
+        endpoints
+    }
+
+    pub fn health(&self) -> &HealthStore {
+        &self.health
+    }
+
+    /// Run `call` against `endpoint` under the retry policy, each attempt bounded by the endpoint timeout
+    /// Only the final outcome is recorded, an endpoint that recovered on retry stays healthy
+    async fn request<T, F, Fut>(&self, endpoint: &str, method: &str, call: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let latency_ms = AtomicU64::new(0);
+        let (call, latency) = (&call, &latency_ms);
+        let result = self.retry
+            .run(&format!("{} on {}", method, endpoint), move || async move {
+                let (result, elapsed) = timed(call()).await;
+                latency.store(elapsed, Ordering::Relaxed);
+                result
+            })
+            .await;
+        self.record(endpoint, &result, latency_ms.load(Ordering::Relaxed));
+        result
+    }
+
+    fn record<T>(&self, endpoint: &str, result: &Result<T>, latency_ms: u64) {
+        match result {
+            Ok(_) => self.health.record_success(endpoint, latency_ms),
+            Err(e) => self.health.record_failure(endpoint, &e.to_string()),
+        }
+    }
+
+    /// Try `call` on each endpoint, healthy ones first, until one succeeds
+    /// With a single endpoint its error is returned unchanged
+    async fn failover<T, F, Fut>(&self, method: &str, call: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let endpoints = self.endpoints();
+        let ordered = self.health.order(&endpoints, clock::now_secs());
+        let mut last_error = None;
+        for (i, endpoint) in ordered.iter().enumerate() {
+            match call(endpoint.to_string()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    if i + 1 < ordered.len() {
+                        out!("⚠️  Bundler {} failed on {} ({}), trying {}", endpoint, method, e, ordered[i + 1]);
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if ordered.len() > 1 => Err(anyhow::anyhow!(
+                "All {} bundler endpoints failed on {}, last error: {}",
+                ordered.len(), method, e
+            )),
+            Some(e) => Err(e),
+            None => Err(anyhow::anyhow!("No bundler endpoint configured")),
+        }
+    }
+
+    // Cached reads come from the registry without touching the endpoint or its health
+    async fn chain_id_at(&self, endpoint: &str) -> Result<u64> {
+        match self.registry.cached_chain_id(endpoint) {
+            Some(chain_id) => Ok(chain_id),
+            None => self.fresh_chain_id_at(endpoint).await,
+        }
+    }
+
+    async fn fresh_chain_id_at(&self, endpoint: &str) -> Result<u64> {
+        self.request(endpoint, "eth_chainId", || self.registry.fresh_chain_id(endpoint)).await
+    }
+
+    async fn supported_entry_points_at(&self, endpoint: &str) -> Result<Vec<Address>> {
+        if let Some(entry_points) = self.registry.cached_supported_entry_points(endpoint) {
+            return Ok(entry_points);
+        }
+        self.request(endpoint, "eth_supportedEntryPoints", || self.registry.fresh_supported_entry_points(endpoint))
+            .await
+    }
+
+    /// Chain ID served by the bundler (`eth_chainId`), cached
+    pub async fn chain_id(&self) -> Result<u64> {
+        self.failover("eth_chainId", |endpoint| async move { self.chain_id_at(&endpoint).await }).await
+    }
+
+    /// EntryPoints supported by the bundler (`eth_supportedEntryPoints`), cached
+    pub async fn supported_entry_points(&self) -> Result<Vec<Address>> {
+        self.failover("eth_supportedEntryPoints", |endpoint| async move {
+            self.supported_entry_points_at(&endpoint).await
+        })
+        .await
+    }
+
+    /// Check a bundler serves the configured chain and EntryPoint before anything is signed or sent
+    /// Returns the first endpoint that passes, so callers submit to a bundler known to work
+    pub async fn preflight(&self) -> Result<String> {
+        self.failover("preflight", |endpoint| async move {
+            let chain_id_error = |e: anyhow::Error| {
+                anyhow::anyhow!("Bundler at {} did not answer eth_chainId: {}", endpoint, e)
+            };
+            let mut bundler_chain_id = self.chain_id_at(&endpoint).await.map_err(chain_id_error)?;
+            if bundler_chain_id != self.chain_id {
+                // The cached value may predate the endpoint changing chains, ask again before failing
+                bundler_chain_id = self.fresh_chain_id_at(&endpoint).await.map_err(chain_id_error)?;
+            }
+            let supported = self.supported_entry_points_at(&endpoint).await.map_err(|e| {
+                anyhow::anyhow!(
+                    "Bundler at {} did not answer eth_supportedEntryPoints, is it an ERC-4337 bundler? ({})",
+                    endpoint, e
+                )
+            })?;
+            check_capabilities(self.entry_point, self.chain_id, bundler_chain_id, &supported)?;
+            Ok(endpoint)
+        })
+        .await
+    }
+
+    /// Uncached, single-attempt `eth_chainId` round-trip to every endpoint, refreshing their health
+    pub async fn probe(&self) -> Vec<(String, Result<u64>)> {
+        let mut results = Vec::new();
+        for endpoint in self.endpoints() {
+            let (result, latency_ms) = timed(async {
+                let provider = self.registry.get(&endpoint)?;
+                Ok::<_, anyhow::Error>(provider.get_chain_id().await?)
+            }).await;
+            self.record(&endpoint, &result, latency_ms);
+            results.push((endpoint, result));
+        }
+        results
+    }
+
+    /// Submit an already signed UserOperation (`eth_sendUserOperation`), returning its hash
+    /// Fails over to backup bundlers, or races all of them when racing is enabled
+    pub async fn send_user_operation(&self, user_op: serde_json::Value, entry_point: Address) -> Result<String> {
+        if self.race && !self.backups.is_empty() {
+            return self.race_user_operation(user_op, entry_point).await;
+        }
+        self.failover("eth_sendUserOperation", |endpoint| {
+            let user_op = user_op.clone();
+            async move {
+                self.request(&endpoint, "eth_sendUserOperation", || {
+                    send_user_operation_at(endpoint.clone(), user_op.clone(), entry_point)
+                })
+                .await
+            }
+        })
+        .await
+    }
+
+    /// Gas limits for a UserOperation signed with a placeholder (`eth_estimateUserOperationGas`)
+    /// Used where the operation carries fields the SDK filler does not know, such as an EIP-7702 authorization
+    pub async fn estimate_user_operation_gas(&self, user_op: serde_json::Value, entry_point: Address) -> Result<UserOperationGasEstimation> {
+        self.failover("eth_estimateUserOperationGas", |endpoint| {
+            let user_op = user_op.clone();
+            async move {
+                self.request(&endpoint, "eth_estimateUserOperationGas", || {
+                    let (endpoint, user_op) = (endpoint.clone(), user_op.clone());
+                    async move {
+                        let provider = providers::http(&endpoint)?;
+                        Ok::<_, anyhow::Error>(provider
+                            .raw_request("eth_estimateUserOperationGas".into(), (user_op, entry_point))
+                            .await?)
+                    }
+                })
+                .await
+            }
+        })
+        .await
+        .inspect(|estimate| events::emit(ClientEvent::estimated(estimate)))
+    }
+
+    /// Send the same UserOperation to every endpoint at once, returning the first accepted hash
+    /// Bundlers share the mempool, so the slower submissions are at worst rejected as duplicates
+    async fn race_user_operation(&self, user_op: serde_json::Value, entry_point: Address) -> Result<String> {
+        let mut submissions = tokio::task::JoinSet::new();
+        for endpoint in self.endpoints() {
+            let user_op = user_op.clone();
+            submissions.spawn(async move {
+                let (result, latency_ms) = timed(send_user_operation_at(endpoint.clone(), user_op, entry_point)).await;
+                (endpoint, result, latency_ms)
+            });
+        }
+
+        let mut errors = Vec::new();
+        while let Some(joined) = submissions.join_next().await {
+            let Ok((endpoint, result, latency_ms)) = joined else {
+                continue;
+            };
+            match result {
+                Ok(user_op_hash) => {
+                    self.health.record_success(&endpoint, latency_ms);
+                    out!("🏁 Bundler {} accepted the UserOperation first", endpoint);
+                    submissions.abort_all();
+                    return Ok(user_op_hash);
+                }
+                Err(e) => {
+                    self.health.record_failure(&endpoint, &e.to_string());
+                    errors.push(format!("{}: {}", endpoint, e));
+                }
+            }
+        }
+        Err(anyhow::anyhow!("Every bundler rejected the UserOperation: {}", errors.join("; ")))
     }
 
     /// Create an Alloy provider from this bundler client configuration
     pub async fn create_provider(&self) -> Result<impl Provider<alloy::network::Ethereum>> {
-        let url = Url::parse(&self.rpc_url)?;
-        let provider = ProviderBuilder::new().on_http(url);
-        Ok(provider)
+        providers::http(&self.rpc_url)
     }
 
-    /// Get real predicted address from standard ERC-4337 SimpleAccountFactory contract
+    /// Predicted address of a single-owner account, from the factory's SimpleAccountFactory-style getAddress
     pub async fn get_predicted_address(&self, factory_address: Address, owner: Address, salt: U256) -> Result<Address> {
-        let provider = self.create_provider().await?;
-        let factory_contract = SimpleAccountFactory::new(factory_address, &provider);
-        
-        let result = factory_contract.getAddress(owner, salt).call().await?;
-        Ok(result._0)
+        self.predict_address(factory_address, factory::resolve(FactoryAbi::Simple), &[owner], salt).await
     }
 
-    /// Get predicted address for multi-owner account from AAAccountFactory contract
+    /// Predicted address of a multi-owner account, from AAAccountFactory's getAddressWithOwners
     pub async fn get_predicted_multi_owner_address(&self, factory_address: Address, owners: Vec<Address>, salt: U256) -> Result<Address> {
-        let provider = self.create_provider().await?;
-        let factory_contract = AAAccountFactory::new(factory_address, &provider);
-        
-        let result = factory_contract.getAddressWithOwners(owners, salt).call().await?;
-        Ok(result._0)
+        self.predict_address(factory_address, factory::resolve(FactoryAbi::MultiOwner), &owners, salt).await
+    }
+
+    // Asked once per process for the same factory, owners and salt
+    async fn predict_address(&self, factory_address: Address, abi: FactoryAbi, owners: &[Address], salt: U256) -> Result<Address> {
+        let rpc_url = &self.rpc_url;
+        self.retry
+            .run("getAddress", move || self.registry.predicted_address(rpc_url, factory_address, abi, owners, salt))
+            .await
+    }
+}
+
+/// Await `request` under the per-endpoint timeout, returning its result and latency
+async fn timed<T>(request: impl Future<Output = Result<T>>) -> (Result<T>, u64) {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(ENDPOINT_TIMEOUT, request).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("no response within {}s", ENDPOINT_TIMEOUT.as_secs())),
+    };
+    (result, started.elapsed().as_millis() as u64)
+}
+
+async fn send_user_operation_at(endpoint: String, user_op: serde_json::Value, entry_point: Address) -> Result<String> {
+    let provider = providers::http(&endpoint)?;
+    let user_op_hash: String = provider
+        .raw_request("eth_sendUserOperation".into(), (user_op, entry_point))
+        .await?;
+    Ok(user_op_hash)
+}
+
+/// Compare what the bundler reports against the configured EntryPoint and chain
+pub fn check_capabilities(entry_point: Address, chain_id: u64, bundler_chain_id: u64, supported: &[Address]) -> Result<()> {
+    config::check_chain_id(chain_id, bundler_chain_id, "Bundler")?;
+    if !supported.contains(&entry_point) {
+        let supported: Vec<String> = supported.iter().map(|a| a.to_string()).collect();
+        return Err(anyhow::anyhow!(
+            "Bundler does not support EntryPoint {} (supported: {})",
+            entry_point,
+            if supported.is_empty() { "none".to_string() } else { supported.join(", ") }
+        ));
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::ResponseCache;
     use alloy::primitives::Address;
 
+    /// Registry for one test, answering `reads` (endpoint, method, value) without a round-trip
+    fn registry(reads: &[(&str, &str, serde_json::Value)]) -> &'static ProviderRegistry {
+        let cache = ResponseCache::in_memory();
+        for (endpoint, method, value) in reads {
+            cache.insert(&ProviderRegistry::read_key(endpoint, method).unwrap(), value, Duration::from_secs(60));
+        }
+        Box::leak(Box::new(ProviderRegistry::new().with_endpoint_cache(cache)))
+    }
+
     #[test]
     fn test_bundler_client_creation() {
         let rpc_url = "http://localhost:8545".to_string();
@@ -127,6 +413,110 @@ mod tests {
         assert_eq!(client.rpc_url, "http://localhost:8545");
     }
 
+    #[test]
+    fn test_private_route_replaces_public_endpoints() {
+        let public = "https://public".to_string();
+        let failover = FailoverConfig { backups: vec!["https://backup".to_string()], race: true, private: Vec::new() };
+        assert_eq!(failover.clone().route(public.clone()), (public.clone(), vec!["https://backup".to_string()], true));
+
+        let private = FailoverConfig { private: vec!["https://private-1".to_string(), "https://private-2".to_string()], ..failover };
+        assert_eq!(private.route(public), ("https://private-1".to_string(), vec!["https://private-2".to_string()], false));
+    }
+
+    #[tokio::test]
+    async fn test_chain_id_served_from_cache() {
+        let rpc_url = "http://localhost:1".to_string();
+        let registry = registry(&[(rpc_url.as_str(), "eth_chainId", serde_json::json!(11155111u64))]);
+        
+        let client = BundlerClient::new(rpc_url, Address::ZERO, U256::from(1u64)).with_registry(registry);
+        
+        // Nothing listens on port 1, so this only succeeds from the cache
+        assert_eq!(client.chain_id().await.unwrap(), 11155111);
+    }
+
+    #[test]
+    fn test_check_capabilities() {
+        let entry_point = Address::from([1u8; 20]);
+        let other = Address::from([2u8; 20]);
+        
+        assert!(check_capabilities(entry_point, 31337, 31337, &[other, entry_point]).is_ok());
+        
+        let wrong_chain = check_capabilities(entry_point, 31337, 1, &[entry_point]).unwrap_err();
+        assert!(wrong_chain.to_string().contains("chain 1"));
+        
+        let unsupported = check_capabilities(entry_point, 31337, 31337, &[other]).unwrap_err();
+        assert!(unsupported.to_string().contains(&other.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_preflight_from_cache() {
+        let rpc_url = "http://localhost:1".to_string();
+        let entry_point = Address::from([1u8; 20]);
+        let registry = registry(&[
+            (rpc_url.as_str(), "eth_chainId", serde_json::json!(31337u64)),
+            (rpc_url.as_str(), "eth_supportedEntryPoints", serde_json::json!([entry_point])),
+        ]);
+        
+        let client = BundlerClient::new(rpc_url.clone(), entry_point, U256::from(31337u64))
+            .with_registry(registry);
+        assert_eq!(client.preflight().await.unwrap(), rpc_url);
+    }
+
+    #[tokio::test]
+    async fn test_preflight_fails_over_to_backup() {
+        let primary = "http://localhost:1".to_string();
+        let backup = "http://localhost:2".to_string();
+        let entry_point = Address::from([1u8; 20]);
+        // The primary serves another chain, the backup is correct
+        let registry = registry(&[
+            (primary.as_str(), "eth_chainId", serde_json::json!(1u64)),
+            (primary.as_str(), "eth_supportedEntryPoints", serde_json::json!([entry_point])),
+            (backup.as_str(), "eth_chainId", serde_json::json!(31337u64)),
+            (backup.as_str(), "eth_supportedEntryPoints", serde_json::json!([entry_point])),
+        ]);
+        
+        let retry = RetryPolicy { max_attempts: 1, ..RetryPolicy::default() };
+        let client = BundlerClient::new(primary.clone(), entry_point, U256::from(31337u64))
+            .with_backups(vec![backup.clone(), primary.clone()])
+            .with_registry(registry)
+            .with_retry(retry);
+        assert_eq!(client.endpoints().len(), 2);
+        assert_eq!(client.preflight().await.unwrap(), backup);
+        // The mismatching chain ID was dropped and asked again, which nothing on port 1 answers
+        assert_eq!(client.health().get(&primary).consecutive_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_recorded_as_failure() {
+        let client = BundlerClient::new("http://localhost:1".to_string(), Address::ZERO, U256::from(1u64))
+            .with_registry(registry(&[]));
+        
+        let probes = client.probe().await;
+        assert_eq!(probes.len(), 1);
+        assert!(probes[0].1.is_err());
+        assert_eq!(client.health().get("http://localhost:1").consecutive_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_count_as_one_failure() {
+        let retry = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            jitter: false,
+            rate_limit_budget: Duration::ZERO,
+        };
+        let client = BundlerClient::new("http://localhost:1".to_string(), Address::ZERO, U256::from(1u64))
+            .with_registry(registry(&[]))
+            .with_retry(retry);
+
+        // Connection refused is transient, so it is retried before the failure is recorded
+        assert!(client.chain_id().await.is_err());
+        let health = client.health().get("http://localhost:1");
+        assert_eq!(health.failures, 1);
+        assert!(health.last_error.unwrap().to_lowercase().contains("connect"));
+    }
+
     #[tokio::test]
     async fn test_provider_creation() {
         let rpc_url = "http://localhost:8545".to_string();
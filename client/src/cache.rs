@@ -0,0 +1,221 @@
+// TTL cache for immutable bundler/node responses
+// Persisted under ~/.aa-client so repeated CLI invocations skip the round-trips
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::clock::now_secs;
+use crate::config::data_dir;
+
+/// File name of the cache inside the client data directory
+const CACHE_FILE: &str = "rpc-cache.json";
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide cache statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Snapshot of cache hits and misses since the process started
+pub fn cache_stats() -> CacheStats {
+    CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    value: serde_json::Value,
+    /// Unix timestamp after which the entry is stale
+    expires_at: u64,
+}
+
+/// Key/value cache with per-entry TTLs, optionally persisted to disk
+pub struct ResponseCache {
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+type Entries = HashMap<String, CacheEntry>;
+
+/// Entries stored at `path`; a missing or unreadable file just means a cold cache
+fn load(path: &Path) -> Entries {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Apply `change` to the entries on disk and swap the result in, returning what was written
+/// The read-modify-write runs under an exclusive lock so concurrent invocations keep each other's entries,
+/// and the rename means a reader never sees a half-written file
+fn persist(path: &Path, change: &impl Fn(&mut Entries)) -> std::io::Result<Entries> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    // Released when `lock` is dropped
+    lock.lock()?;
+
+    let mut entries = load(path);
+    let now = now_secs();
+    entries.retain(|_, entry| entry.expires_at > now);
+    change(&mut entries);
+
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    std::fs::write(&tmp, serde_json::to_string(&entries)?)?;
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(entries)
+}
+
+impl ResponseCache {
+    /// In-memory cache that is dropped with the process
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache persisted at `~/.aa-client/rpc-cache.json`
+    /// Falls back to an in-memory cache when the data directory is unavailable
+    pub fn persistent() -> Self {
+        match data_dir() {
+            Ok(dir) => Self::at_path(dir.join(CACHE_FILE)),
+            Err(_) => Self::in_memory(),
+        }
+    }
+
+    /// Cache persisted at an explicit path
+    pub fn at_path(path: PathBuf) -> Self {
+        Self {
+            entries: Mutex::new(load(&path)),
+            path: Some(path),
+        }
+    }
+
+    /// Look up a fresh entry, counting the hit or miss
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let entries = self.entries.lock().unwrap();
+        let value = entries
+            .get(key)
+            .filter(|entry| entry.expires_at > now_secs())
+            .and_then(|entry| serde_json::from_value(entry.value.clone()).ok());
+
+        if value.is_some() {
+            HITS.fetch_add(1, Ordering::Relaxed);
+        } else {
+            MISSES.fetch_add(1, Ordering::Relaxed);
+        }
+        value
+    }
+
+    /// Store a value for `ttl`, persisting the cache when backed by a file
+    pub fn insert<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        let Ok(value) = serde_json::to_value(value) else {
+            return;
+        };
+        let entry = CacheEntry {
+            value,
+            expires_at: now_secs() + ttl.as_secs(),
+        };
+        self.update(|entries| {
+            entries.insert(key.to_string(), entry.clone());
+        });
+    }
+
+    /// Drop an entry, e.g. one a fresh read has shown to be wrong
+    pub fn remove(&self, key: &str) {
+        self.update(|entries| {
+            entries.remove(key);
+        });
+    }
+
+    fn update(&self, change: impl Fn(&mut Entries)) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = now_secs();
+        entries.retain(|_, entry| entry.expires_at > now);
+        change(&mut entries);
+
+        if let Some(path) = &self.path {
+            // Persisting is best effort, a failed write only costs a future round-trip
+            if let Ok(merged) = persist(path, &change) {
+                *entries = merged;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let cache = ResponseCache::in_memory();
+        cache.insert("chain", &31337u64, Duration::from_secs(60));
+
+        assert_eq!(cache.get::<u64>("chain"), Some(31337));
+        assert_eq!(cache.get::<u64>("missing"), None);
+    }
+
+    #[test]
+    fn test_expired_entry_is_a_miss() {
+        let cache = ResponseCache::in_memory();
+        cache.insert("chain", &1u64, Duration::from_secs(0));
+
+        assert_eq!(cache.get::<u64>("chain"), None);
+    }
+
+    #[test]
+    fn test_persistent_cache_round_trip() {
+        let path = std::env::temp_dir().join(format!("aa-client-cache-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        ResponseCache::at_path(path.clone()).insert("entry_points", &vec!["0x01".to_string()], Duration::from_secs(60));
+        let reloaded = ResponseCache::at_path(path.clone());
+        assert_eq!(reloaded.get::<Vec<String>>("entry_points"), Some(vec!["0x01".to_string()]));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("lock"));
+    }
+
+    #[test]
+    fn test_writers_keep_each_others_entries() {
+        let path = std::env::temp_dir().join(format!("aa-client-cache-merge-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // Both loaded the cold cache before either wrote, as two concurrent invocations would
+        let first = ResponseCache::at_path(path.clone());
+        let second = ResponseCache::at_path(path.clone());
+        first.insert("a", &1u64, Duration::from_secs(60));
+        second.insert("b", &2u64, Duration::from_secs(60));
+        second.remove("a");
+        first.insert("c", &3u64, Duration::from_secs(60));
+
+        let reloaded = ResponseCache::at_path(path.clone());
+        assert_eq!(reloaded.get::<u64>("a"), None);
+        assert_eq!(reloaded.get::<u64>("b"), Some(2));
+        assert_eq!(reloaded.get::<u64>("c"), Some(3));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("lock"));
+    }
+}
@@ -1,45 +1,431 @@
 // Network configuration for different chains
-use alloy::primitives::Address;
+use alloy::primitives::{address, Address};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+use crate::entrypoint::EntryPointVersion;
+use crate::dummy_signature::DummySignature;
+use crate::factory::FactoryAbi;
+use crate::paymaster::PaymasterMode;
+
+/// File name of the CLI configuration inside the client data directory
+const CONFIG_FILE: &str = "config.toml";
+
+/// Directory holding local client state (journal, config, caches)
+pub fn data_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+    Ok(home.join(".aa-client"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct NetworkConfig {
-    pub name: &'static str,
+    pub name: String,
     pub chain_id: u64,
+    /// EntryPoint used when no `--entry-point-version` is selected
     pub entry_point: Address,
-    pub factory: Address,
-    pub rpc_url_template: &'static str,
+    /// EntryPoint deployments away from their canonical address, by version
+    pub entry_points: BTreeMap<EntryPointVersion, Address>,
+    /// AAAccountFactory deployment, None where it is not deployed and --factory is required
+    pub factory: Option<Address>,
+    pub rpc_url_template: String,
+    /// Separate bundler endpoint, when the RPC node does not serve the ERC-4337 methods itself
+    pub bundler_url_template: Option<String>,
+    /// Accounts are native (zkSync): operations are sent as type 113 transactions, with no EntryPoint or bundler
+    pub native_aa: bool,
 }
 
 impl NetworkConfig {
     pub fn sepolia() -> Self {
         Self {
-            name: "Sepolia Testnet",
+            name: "Sepolia Testnet".to_string(),
             chain_id: 11155111,
-            entry_point: Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032").unwrap(),
-            factory: Address::from_str("0xDE5034D1c32E1edD9a355cbEBFF8ac16Bbb9d5C3").unwrap(),
-            rpc_url_template: "https://eth-sepolia.g.alchemy.com/v2/{api_key}",
+            entry_point: ENTRY_POINT_V07,
+            entry_points: BTreeMap::new(),
+            factory: Some(Address::from_str("0xDE5034D1c32E1edD9a355cbEBFF8ac16Bbb9d5C3").unwrap()),
+            rpc_url_template: "https://eth-sepolia.g.alchemy.com/v2/{api_key}".to_string(),
+            bundler_url_template: None,
+            native_aa: false,
         }
     }
 
     pub fn anvil() -> Self {
         Self {
-            name: "Anvil Local",
+            name: "Anvil Local".to_string(),
             chain_id: 31337,
-            entry_point: Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032").unwrap(),
-            factory: Address::from_str("0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512").unwrap(),
-            rpc_url_template: "http://localhost:8545",
+            entry_point: ENTRY_POINT_V07,
+            entry_points: BTreeMap::new(),
+            factory: Some(Address::from_str("0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512").unwrap()),
+            rpc_url_template: "http://localhost:8545".to_string(),
+            bundler_url_template: None,
+            native_aa: false,
+        }
+    }
+
+    pub fn base() -> Self {
+        Self::v07("Base", 8453, "https://base-mainnet.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn base_sepolia() -> Self {
+        Self::v07("Base Sepolia", 84532, "https://base-sepolia.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn bnb() -> Self {
+        Self::v07("BNB Chain", 56, "https://bnb-mainnet.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn avalanche() -> Self {
+        Self::v07("Avalanche C-Chain", 43114, "https://avax-mainnet.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn arbitrum_sepolia() -> Self {
+        Self::v07("Arbitrum Sepolia", 421614, "https://arb-sepolia.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn optimism_sepolia() -> Self {
+        Self::v07("Optimism Sepolia", 11155420, "https://opt-sepolia.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn zksync_era() -> Self {
+        Self::zksync("zkSync Era", 324, "https://zksync-mainnet.g.alchemy.com/v2/{api_key}")
+    }
+
+    pub fn zksync_sepolia() -> Self {
+        Self::zksync("zkSync Sepolia", 300, "https://zksync-sepolia.g.alchemy.com/v2/{api_key}")
+    }
+
+    /// zkSync chain, where the EntryPoint fields are unused
+    fn zksync(name: &str, chain_id: u64, rpc_url_template: &str) -> Self {
+        Self { native_aa: true, ..Self::v07(name, chain_id, rpc_url_template) }
+    }
+
+    /// Chain with the canonical EntryPoint v0.7 deployment and no AAAccountFactory yet
+    fn v07(name: &str, chain_id: u64, rpc_url_template: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            chain_id,
+            entry_point: ENTRY_POINT_V07,
+            entry_points: BTreeMap::new(),
+            factory: None,
+            rpc_url_template: rpc_url_template.to_string(),
+            bundler_url_template: None,
+            native_aa: false,
+        }
+    }
+
+    /// EntryPoint of `version` on this network, the network's default when no version is selected
+    pub fn entry_point_for(&self, version: Option<EntryPointVersion>) -> Address {
+        match version {
+            Some(version) => self.entry_points.get(&version).copied().unwrap_or(version.address()),
+            None => self.entry_point,
+        }
+    }
+}
+
+/// EntryPoint v0.7, deployed at the same address on every chain through the deterministic deployer
+pub const ENTRY_POINT_V07: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+
+/// Network that used to be supported and whose chain has been shut down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetiredNetwork {
+    pub name: &'static str,
+    pub chain_id: u64,
+    /// Chain ID of the network that replaced it
+    pub replacement: u64,
+}
+
+pub const RETIRED_NETWORKS: [RetiredNetwork; 2] = [
+    RetiredNetwork { name: "Goerli Testnet", chain_id: 5, replacement: 11155111 },
+    RetiredNetwork { name: "Polygon Mumbai", chain_id: 80001, replacement: 80002 },
+];
+
+pub fn retired_network(chain_id: u64) -> Option<RetiredNetwork> {
+    RETIRED_NETWORKS.into_iter().find(|network| network.chain_id == chain_id)
+}
+
+impl std::fmt::Display for RetiredNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (chain {}) has been shut down, use chain {} instead", self.name, self.chain_id, self.replacement)
+    }
+}
+
+/// Named set of defaults for the connection flags shared by most commands
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub rpc_url: Option<String>,
+    pub chain_id: Option<u64>,
+    pub factory: Option<String>,
+    pub entry_point: Option<String>,
+    pub paymaster_url: Option<String>,
+    /// File containing the hex-encoded owner private key, unencrypted; must not be readable by other users
+    pub keystore: Option<PathBuf>,
+    /// Wallet from `wallet import` used when no key is given, ahead of `keystore`
+    pub wallet: Option<String>,
+    /// Backup bundler URLs tried in order when `rpc_url` errors or times out
+    pub bundlers: Option<Vec<String>>,
+    /// EntryPoint release operations are built for, `--entry-point-version` overrides it
+    pub entry_point_version: Option<EntryPointVersion>,
+    /// Bundlers `--private` submits through instead of the public mempool
+    pub private_bundlers: Option<Vec<String>>,
+    /// Paymaster contract kind for sponsored commands, `--paymaster-mode` overrides it
+    pub paymaster_mode: Option<PaymasterMode>,
+    /// Placeholder signature gas is estimated with, `--dummy-signature` overrides it
+    pub dummy_signature: Option<DummySignature>,
+    /// Calldata layout of the profile's factory, `--factory-abi` overrides it
+    pub factory_abi: Option<FactoryAbi>,
+    /// Most ether one operation may send, with a unit (`"1eth"`), `--max-value` overrides it
+    pub max_value: Option<String>,
+    /// Operations sending more ether ask for confirmation first, `--confirm-value-above` overrides it
+    pub confirm_value_above: Option<String>,
+    /// Addresses no ether may be sent to, on top of the zero and 0x...dEaD burn addresses
+    pub denylist: Option<Vec<Address>>,
+}
+
+impl Profile {
+    /// Read the private key stored in the profile's keystore file, if any
+    /// The key is plaintext, so a file other users can read is refused like ssh refuses such a key
+    pub fn read_keystore(&self) -> Result<Option<String>> {
+        match &self.keystore {
+            Some(path) => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = std::fs::metadata(path)
+                        .map_err(|e| anyhow::anyhow!("Failed to read keystore {}: {}", path.display(), e))?
+                        .permissions()
+                        .mode();
+                    check_keystore_mode(path, mode)?;
+                }
+                let key = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read keystore {}: {}", path.display(), e))?;
+                Ok(Some(key.trim().to_string()))
+            }
+            None => Ok(None),
         }
     }
 }
 
+/// Reject a keystore whose permissions let the group or other users read or change it
+#[cfg(unix)]
+fn check_keystore_mode(path: &Path, mode: u32) -> Result<()> {
+    if mode & 0o077 != 0 {
+        return Err(anyhow::anyhow!(
+            "Keystore {} holds an unencrypted key but has mode {:o}, run `chmod 600 {}` or move the key to `wallet import`",
+            path.display(), mode & 0o777, path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Network declared under `[networks.<key>]` in the config file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomNetwork {
+    /// Display name, the table key when omitted
+    pub name: Option<String>,
+    pub chain_id: u64,
+    pub entry_point: Address,
+    /// Non-canonical EntryPoint deployments, e.g. `entry_points = { "v0.8" = "0x..." }`
+    #[serde(default)]
+    pub entry_points: BTreeMap<EntryPointVersion, Address>,
+    pub factory: Option<Address>,
+    pub rpc_url_template: String,
+    pub bundler_url_template: Option<String>,
+    /// zkSync-style native account abstraction
+    #[serde(default)]
+    pub native_aa: bool,
+}
+
+/// Contents of `~/.aa-client/config.toml`
+///
+/// ```toml
+/// default_profile = "sepolia"
+///
+/// [profiles.sepolia]
+/// rpc_url = "https://eth-sepolia.g.alchemy.com/v2/KEY"
+/// chain_id = 11155111
+/// paymaster_url = "http://localhost:3000"
+/// keystore = "/home/me/.aa-client/owner.key"
+/// wallet = "operator"
+/// bundlers = ["https://api.pimlico.io/v2/sepolia/rpc?apikey=KEY"]
+/// entry_point_version = "v0.7"
+/// private_bundlers = ["https://private-bundler.example/rpc"]
+/// paymaster_mode = "simple"
+/// dummy_signature = "multi-owner:2"
+/// factory_abi = "owners-array"
+/// max_value = "1eth"
+/// confirm_value_above = "0.1eth"
+/// denylist = ["0x..."]
+///
+/// [networks.base]
+/// name = "Base"
+/// chain_id = 8453
+/// entry_point = "0x0000000071727De22E5E9d8BAf0edAc6f37da032"
+/// factory = "0x..."
+/// rpc_url_template = "https://base-mainnet.g.alchemy.com/v2/{api_key}"
+/// bundler_url_template = "https://api.pimlico.io/v2/base/rpc?apikey={api_key}"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CliConfig {
+    /// Profile used when `--profile` is not given
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// Networks added to the built-in presets, replacing a preset with the same chain ID
+    #[serde(default)]
+    pub networks: HashMap<String, CustomNetwork>,
+}
+
+impl CliConfig {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(data_dir()?.join(CONFIG_FILE))
+    }
+
+    /// Load the config from `~/.aa-client/config.toml`, empty when the file does not exist
+    pub fn load_default() -> Result<Self> {
+        let path = Self::default_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(&path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Set one key of a profile in the config file at `path`, creating the file and profile if needed
+    /// Other settings are kept, but comments in the file are not preserved
+    pub fn set_profile_value(path: &Path, profile: &str, key: &str, value: &str) -> Result<()> {
+        let mut table: toml::Table = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            toml::Table::new()
+        };
+
+        let profiles = table
+            .entry("profiles")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("'profiles' in {} is not a table", path.display()))?;
+        let entry = profiles
+            .entry(profile)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("Profile '{}' in {} is not a table", profile, path.display()))?;
+        entry.insert(key.to_string(), toml::Value::String(value.to_string()));
+
+        let contents = toml::to_string_pretty(&table)?;
+        // Never write a file the loader would reject
+        Self::parse(&contents)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Built-in presets merged with the networks from the config file, custom ones after the presets by chain ID
+    pub fn networks(&self) -> Vec<NetworkConfig> {
+        let mut custom: Vec<NetworkConfig> = self
+            .networks
+            .iter()
+            .map(|(key, network)| NetworkConfig {
+                name: network.name.clone().unwrap_or_else(|| key.clone()),
+                chain_id: network.chain_id,
+                entry_point: network.entry_point,
+                entry_points: network.entry_points.clone(),
+                factory: network.factory,
+                rpc_url_template: network.rpc_url_template.clone(),
+                bundler_url_template: network.bundler_url_template.clone(),
+                native_aa: network.native_aa,
+            })
+            .collect();
+        custom.sort_by_key(|network| network.chain_id);
+
+        let mut networks: Vec<NetworkConfig> = list_supported_networks()
+            .into_iter()
+            .filter(|preset| !custom.iter().any(|network| network.chain_id == preset.chain_id))
+            .collect();
+        networks.extend(custom);
+        networks
+    }
+
+    /// Preset or custom network for `chain_id`
+    /// Retired networks are an error unless the config file declares them again
+    pub fn network(&self, chain_id: u64) -> Result<NetworkConfig> {
+        if let Some(network) = self.networks().into_iter().find(|network| network.chain_id == chain_id) {
+            return Ok(network);
+        }
+        match retired_network(chain_id) {
+            Some(retired) => Err(anyhow::anyhow!("{}", retired)),
+            None => Err(anyhow::anyhow!("Unsupported network {}, declare it under [networks] in the config file", chain_id)),
+        }
+    }
+
+    /// Select a profile: the explicitly requested one, otherwise `default_profile`
+    /// Requesting a profile that does not exist is an error
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&Profile>> {
+        match name.or(self.default_profile.as_deref()) {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("Profile '{}' not found in config file", name)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Precedence for a setting: explicit CLI flag, then profile, then built-in default
+pub fn resolve<T>(explicit: Option<T>, profile: Option<T>, default: T) -> T {
+    explicit.or(profile).unwrap_or(default)
+}
+
 pub fn list_supported_networks() -> Vec<NetworkConfig> {
     vec![
         NetworkConfig::sepolia(),
+        NetworkConfig::base(),
+        NetworkConfig::base_sepolia(),
+        NetworkConfig::bnb(),
+        NetworkConfig::avalanche(),
+        NetworkConfig::arbitrum_sepolia(),
+        NetworkConfig::optimism_sepolia(),
+        NetworkConfig::zksync_era(),
+        NetworkConfig::zksync_sepolia(),
         NetworkConfig::anvil(),
     ]
 }
 
+/// Fail when `source` reports another chain than the configured one
+/// The chain id is part of every UserOperation hash, so a mismatch otherwise surfaces as invalid signatures
+pub fn check_chain_id(configured: u64, reported: u64, source: &str) -> Result<()> {
+    if configured == reported {
+        return Ok(());
+    }
+    let label = |chain_id: u64| {
+        list_supported_networks()
+            .into_iter()
+            .find(|network| network.chain_id == chain_id)
+            .map(|network| format!(" ({})", network.name))
+            .unwrap_or_default()
+    };
+    Err(anyhow::anyhow!(
+        "{} serves chain {}{} but chain {}{} is configured, check --rpc-url and --chain-id",
+        source, reported, label(reported), configured, label(configured)
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,11 +441,136 @@ mod tests {
         assert_eq!(anvil.name, "Anvil Local");
     }
 
+    const SAMPLE_CONFIG: &str = r#"
+default_profile = "sepolia"
+
+[profiles.sepolia]
+rpc_url = "https://sepolia.example"
+chain_id = 11155111
+paymaster_url = "http://localhost:3000"
+
+bundlers = ["https://backup-1.example", "https://backup-2.example"]
+
+[profiles.anvil]
+rpc_url = "http://localhost:8545"
+"#;
+
+    #[test]
+    fn test_profile_selection() {
+        let config = CliConfig::parse(SAMPLE_CONFIG).unwrap();
+        
+        let default = config.profile(None).unwrap().unwrap();
+        assert_eq!(default.chain_id, Some(11155111));
+        assert_eq!(default.bundlers.as_ref().map(Vec::len), Some(2));
+        
+        let anvil = config.profile(Some("anvil")).unwrap().unwrap();
+        assert_eq!(anvil.rpc_url.as_deref(), Some("http://localhost:8545"));
+        assert_eq!(anvil.chain_id, None);
+        
+        assert!(config.profile(Some("mainnet")).is_err());
+        assert!(CliConfig::default().profile(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unknown_profile_key_rejected() {
+        assert!(CliConfig::parse("[profiles.x]\nrpc = \"typo\"").is_err());
+    }
+
+    #[test]
+    fn test_set_profile_value_keeps_other_settings() {
+        let path = std::env::temp_dir().join(format!("aa-client-config-{}.toml", std::process::id()));
+        std::fs::write(&path, SAMPLE_CONFIG).unwrap();
+        
+        CliConfig::set_profile_value(&path, "sepolia", "entry_point", "0x4337084D9E255Ff0702461CF8895CE9E3b5Ff108").unwrap();
+        let config = CliConfig::load(&path).unwrap();
+        let sepolia = config.profile(Some("sepolia")).unwrap().unwrap();
+        assert_eq!(sepolia.entry_point.as_deref(), Some("0x4337084D9E255Ff0702461CF8895CE9E3b5Ff108"));
+        assert_eq!(sepolia.chain_id, Some(11155111));
+        assert_eq!(config.default_profile.as_deref(), Some("sepolia"));
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_keystore_mode() {
+        let path = Path::new("/home/me/.aa-client/owner.key");
+        assert!(check_keystore_mode(path, 0o100600).is_ok());
+        assert!(check_keystore_mode(path, 0o100400).is_ok());
+
+        let open = check_keystore_mode(path, 0o100644).unwrap_err();
+        assert!(open.to_string().contains("644"));
+        assert!(check_keystore_mode(path, 0o100660).is_err());
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        assert_eq!(resolve(Some(1), Some(2), 3), 1);
+        assert_eq!(resolve(None, Some(2), 3), 2);
+        assert_eq!(resolve(None, None, 3), 3);
+    }
+
+    #[test]
+    fn test_custom_networks_merge_with_presets() {
+        let config = CliConfig::parse(r#"
+[networks.base]
+name = "Base"
+chain_id = 8453
+entry_point = "0x0000000071727De22E5E9d8BAf0edAc6f37da032"
+factory = "0x59bcaa1BB72972Df0446FCe98798076e718E3b61"
+rpc_url_template = "https://base-mainnet.g.alchemy.com/v2/{api_key}"
+bundler_url_template = "https://api.pimlico.io/v2/base/rpc?apikey={api_key}"
+
+[networks.devnet]
+chain_id = 31337
+entry_point = "0x0000000071727De22E5E9d8BAf0edAc6f37da032"
+entry_points = { "v0.8" = "0x1111111111111111111111111111111111111111" }
+factory = "0x59bcaa1BB72972Df0446FCe98798076e718E3b61"
+rpc_url_template = "http://devnet:8545"
+"#).unwrap();
+
+        let chain_ids: Vec<u64> = config.networks().iter().map(|n| n.chain_id).collect();
+        assert_eq!(chain_ids, vec![11155111, 84532, 56, 43114, 421614, 11155420, 324, 300, 8453, 31337]);
+
+        let devnet = config.network(31337).unwrap();
+        assert_eq!(devnet.name, "devnet");
+        assert_eq!(devnet.rpc_url_template, "http://devnet:8545");
+        assert_eq!(devnet.entry_point_for(None), ENTRY_POINT_V07);
+        assert_eq!(devnet.entry_point_for(Some(EntryPointVersion::V08)), Address::from([0x11; 20]));
+        assert_eq!(devnet.entry_point_for(Some(EntryPointVersion::V06)), EntryPointVersion::V06.address());
+        assert_eq!(config.network(8453).unwrap().bundler_url_template.as_deref(), Some("https://api.pimlico.io/v2/base/rpc?apikey={api_key}"));
+        assert!(config.network(1).is_err());
+        assert_eq!(
+            config.network(5).unwrap_err().to_string(),
+            "Goerli Testnet (chain 5) has been shut down, use chain 11155111 instead"
+        );
+
+        assert!(CliConfig::parse("[networks.bad]\nchain_id = 1\nrpc = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_check_chain_id() {
+        assert!(check_chain_id(31337, 31337, "RPC endpoint").is_ok());
+        let mismatch = check_chain_id(11155111, 31337, "RPC endpoint").unwrap_err().to_string();
+        assert_eq!(
+            mismatch,
+            "RPC endpoint serves chain 31337 (Anvil Local) but chain 11155111 (Sepolia Testnet) is configured, check --rpc-url and --chain-id"
+        );
+        assert!(check_chain_id(1, 5, "Bundler").unwrap_err().to_string().starts_with("Bundler serves chain 5 but chain 1"));
+    }
+
     #[test]
     fn test_list_supported_networks() {
         let networks = list_supported_networks();
-        assert_eq!(networks.len(), 2);
+        assert_eq!(networks.len(), 10);
         assert_eq!(networks[0].chain_id, 11155111); // Sepolia
-        assert_eq!(networks[1].chain_id, 31337);    // Anvil
+        assert_eq!(networks[9].chain_id, 31337);    // Anvil
+        assert!(networks.iter().all(|network| network.entry_point == ENTRY_POINT_V07));
+        assert_eq!(NetworkConfig::base().factory, None);
+        assert_eq!(networks.iter().filter(|network| network.native_aa).map(|n| n.chain_id).collect::<Vec<_>>(), vec![324, 300]);
+
+        for retired in RETIRED_NETWORKS {
+            assert!(networks.iter().all(|network| network.chain_id != retired.chain_id));
+        }
     }
 }
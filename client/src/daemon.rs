@@ -0,0 +1,344 @@
+// Long-running `watch --daemon`: follows pending journal operations on every chain, re-signs the ones
+// waiting on fees with higher ones and serves a status snapshot over plain HTTP on a local port or socket
+
+use alloy::primitives::{Address, U256};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::config::CliConfig;
+use crate::journal::OperationRecord;
+use crate::multisig::UnsignedUserOperation;
+use crate::stuck::Diagnosis;
+use crate::transport::LocalSocket;
+
+/// Smallest fee increase bundlers accept for an operation replacing one with the same sender and nonce
+pub const MIN_BUMP_PERCENT: u64 = 10;
+
+/// When and how far the daemon raises the fees of a waiting operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BumpPolicy {
+    /// Seconds an operation waits before it is bumped, counted again from every bump
+    pub after_secs: u64,
+    /// Increase of maxFeePerGas and maxPriorityFeePerGas
+    pub percent: u64,
+    /// maxFeePerGas is never raised above this
+    pub max_fee_cap: Option<U256>,
+}
+
+impl BumpPolicy {
+    /// Whether a higher fee can get an operation with this diagnosis included
+    pub fn applies(diagnosis: &Diagnosis) -> bool {
+        matches!(diagnosis, Diagnosis::FeesTooLow { .. } | Diagnosis::Waiting)
+    }
+
+    /// `user_op` with both fees raised by `percent` and maxFeePerGas covering `base_fee` plus the tip,
+    /// None when that goes over the cap
+    pub fn bump(&self, user_op: &UnsignedUserOperation, base_fee: U256) -> Option<UnsignedUserOperation> {
+        let raise = |fee: U256| (fee * U256::from(100 + self.percent) / U256::from(100)).max(fee + U256::from(1));
+        let priority = raise(user_op.max_priority_fee_per_gas);
+        let max_fee = raise(user_op.max_fee_per_gas).max(base_fee + priority);
+        if self.max_fee_cap.is_some_and(|cap| max_fee > cap) {
+            return None;
+        }
+
+        let mut bumped = user_op.clone();
+        bumped.max_fee_per_gas = max_fee;
+        bumped.max_priority_fee_per_gas = priority;
+        Some(bumped)
+    }
+}
+
+/// Bundler URL per chain: `CHAIN_ID=URL` flags first, then profiles setting both chain_id and rpc_url
+/// (the default profile ahead of the others), then ERC-4337 network presets whose URL needs no API key
+pub fn endpoints(config: &CliConfig, explicit: &[String]) -> Result<BTreeMap<u64, String>> {
+    let mut endpoints = BTreeMap::new();
+    for entry in explicit {
+        let (chain_id, url) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected CHAIN_ID=URL, got '{}'", entry))?;
+        let chain_id = chain_id.trim().parse::<u64>().map_err(|e| anyhow::anyhow!("Invalid chain ID in '{}': {}", entry, e))?;
+        endpoints.insert(chain_id, url.trim().to_string());
+    }
+
+    let mut profiles: Vec<_> = config.profiles.iter().collect();
+    profiles.sort_by_key(|(name, _)| (Some(name.as_str()) != config.default_profile.as_deref(), name.as_str()));
+    for (_, profile) in profiles {
+        if let (Some(chain_id), Some(url)) = (profile.chain_id, &profile.rpc_url) {
+            endpoints.entry(chain_id).or_insert_with(|| url.clone());
+        }
+    }
+
+    for network in config.networks() {
+        let url = network.bundler_url_template.as_ref().unwrap_or(&network.rpc_url_template);
+        if !url.contains("{api_key}") && !network.native_aa {
+            endpoints.entry(network.chain_id).or_insert_with(|| url.clone());
+        }
+    }
+    Ok(endpoints)
+}
+
+/// Snapshot served by the status endpoint
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Status {
+    pub started_at: u64,
+    pub last_scan_at: Option<u64>,
+    pub scans: u64,
+    pub chains: BTreeMap<u64, ChainStatus>,
+    /// Operations still pending after the last scan
+    pub pending: Vec<PendingStatus>,
+    /// Totals since the daemon started
+    pub included: u64,
+    pub failed: u64,
+    pub bumped: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChainStatus {
+    pub pending: usize,
+    /// Why the last scan of the chain failed, None when it succeeded
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingStatus {
+    pub user_op_hash: String,
+    pub chain_id: u64,
+    pub sender: Address,
+    pub tag: Option<String>,
+    pub pending_secs: u64,
+    pub diagnosis: Option<String>,
+}
+
+impl PendingStatus {
+    pub fn of(record: &OperationRecord, now: u64, diagnosis: Option<String>) -> Self {
+        Self {
+            user_op_hash: record.user_op_hash.clone(),
+            chain_id: record.chain_id,
+            sender: record.sender,
+            tag: record.tag.clone(),
+            pending_secs: now.saturating_sub(record.submitted_at),
+            diagnosis,
+        }
+    }
+}
+
+/// What one scan of a chain found and did
+#[derive(Debug, Clone, Default)]
+pub struct ChainScan {
+    pub pending: Vec<PendingStatus>,
+    pub included: u64,
+    pub failed: u64,
+    pub bumped: u64,
+    /// Last error met while scanning, the affected operations stay pending undiagnosed
+    pub error: Option<String>,
+}
+
+impl Status {
+    /// Replace the per-chain view with the scans just finished and add their counts to the totals
+    pub fn record_scan(&mut self, now: u64, scans: Vec<(u64, ChainScan)>) {
+        self.last_scan_at = Some(now);
+        self.scans += 1;
+        self.chains.clear();
+        self.pending.clear();
+        for (chain_id, scan) in scans {
+            self.chains.insert(chain_id, ChainStatus { pending: scan.pending.len(), error: scan.error });
+            self.pending.extend(scan.pending);
+            self.included += scan.included;
+            self.failed += scan.failed;
+            self.bumped += scan.bumped;
+        }
+    }
+}
+
+/// Status shared between the scan loop and the HTTP server
+pub type SharedStatus = Arc<Mutex<Status>>;
+
+/// Raw HTTP response to a request: the status as JSON on `GET /` and `GET /status`, 404 otherwise
+pub fn respond(request: &str, status: &Status) -> String {
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let (code, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/" | "/status")) => {
+            ("200 OK", serde_json::to_string_pretty(status).unwrap_or_else(|e| format!("{{\"error\":\"{}\"}}", e)))
+        }
+        _ => ("404 Not Found", "{\"error\":\"not found, try GET /status\"}".to_string()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        body.len(),
+        body
+    )
+}
+
+/// Where the status is served: a TCP address, or a local socket (`unix:///path`, `npipe://name`) for
+/// hosts that must not open a port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusAddr {
+    Tcp(SocketAddr),
+    Local(LocalSocket),
+}
+
+impl FromStr for StatusAddr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(socket) = LocalSocket::from_url(s) {
+            return Ok(StatusAddr::Local(socket));
+        }
+        s.parse()
+            .map(StatusAddr::Tcp)
+            .map_err(|_| anyhow::anyhow!("Invalid status address '{}', expected HOST:PORT, unix:///path or npipe://name", s))
+    }
+}
+
+impl std::fmt::Display for StatusAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusAddr::Tcp(addr) => write!(f, "http://{}/status", addr),
+            StatusAddr::Local(socket) => write!(f, "{} (GET /status)", socket),
+        }
+    }
+}
+
+/// Bound status endpoint, ready to accept
+pub enum StatusListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+    #[cfg(windows)]
+    NamedPipe(String, tokio::net::windows::named_pipe::NamedPipeServer),
+}
+
+impl StatusAddr {
+    /// Bind the endpoint up front, so a taken port or path fails the command instead of the background task
+    pub async fn bind(&self) -> Result<StatusListener> {
+        let listener = match self {
+            StatusAddr::Tcp(addr) => TcpListener::bind(addr).await.map(StatusListener::Tcp).map_err(anyhow::Error::from),
+            #[cfg(unix)]
+            StatusAddr::Local(LocalSocket::Unix(path)) => crate::transport::bind_unix(path).map(StatusListener::Unix),
+            #[cfg(windows)]
+            StatusAddr::Local(LocalSocket::NamedPipe(name)) => tokio::net::windows::named_pipe::ServerOptions::new()
+                .first_pipe_instance(true)
+                .create(name)
+                .map(|server| StatusListener::NamedPipe(name.clone(), server))
+                .map_err(anyhow::Error::from),
+            #[allow(unreachable_patterns)]
+            StatusAddr::Local(socket) => Err(anyhow::anyhow!("{} is not supported on this platform", socket)),
+        };
+        listener.map_err(|e| anyhow::anyhow!("Cannot serve status on {}: {}", self, e))
+    }
+}
+
+/// Answer status requests on `listener` until the process exits
+pub async fn serve(listener: StatusListener, status: SharedStatus) -> Result<()> {
+    match listener {
+        StatusListener::Tcp(listener) => loop {
+            let (stream, _) = listener.accept().await?;
+            tokio::spawn(answer(stream, status.clone()));
+        },
+        #[cfg(unix)]
+        StatusListener::Unix(listener) => loop {
+            let (stream, _) = listener.accept().await?;
+            tokio::spawn(answer(stream, status.clone()));
+        },
+        #[cfg(windows)]
+        StatusListener::NamedPipe(name, mut server) => loop {
+            server.connect().await?;
+            // Hand the connected instance off and open a fresh one for the next client
+            let next = tokio::net::windows::named_pipe::ServerOptions::new().create(&name)?;
+            tokio::spawn(answer(std::mem::replace(&mut server, next), status.clone()));
+        },
+    }
+}
+
+/// Read one request from a connection and write the status response
+async fn answer<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, status: SharedStatus) {
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request).await.unwrap_or(0);
+    let response = respond(&String::from_utf8_lossy(&request[..read]), &status.lock().unwrap());
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Profile;
+
+    #[test]
+    fn test_bump() {
+        let mut user_op = UnsignedUserOperation::execute(Address::from([9u8; 20]), U256::ZERO, Address::ZERO, U256::ZERO, Default::default());
+        user_op.max_fee_per_gas = U256::from(1000);
+        user_op.max_priority_fee_per_gas = U256::from(100);
+        let policy = BumpPolicy { after_secs: 60, percent: 15, max_fee_cap: None };
+
+        let bumped = policy.bump(&user_op, U256::from(500)).unwrap();
+        assert_eq!(bumped.max_fee_per_gas, U256::from(1150));
+        assert_eq!(bumped.max_priority_fee_per_gas, U256::from(115));
+
+        let bumped = policy.bump(&user_op, U256::from(2000)).unwrap();
+        assert_eq!(bumped.max_fee_per_gas, U256::from(2115));
+
+        let capped = BumpPolicy { max_fee_cap: Some(U256::from(1100)), ..policy };
+        assert!(capped.bump(&user_op, U256::from(500)).is_none());
+
+        assert!(BumpPolicy::applies(&Diagnosis::Waiting));
+        assert!(!BumpPolicy::applies(&Diagnosis::Dropped));
+    }
+
+    #[test]
+    fn test_status_addr() {
+        assert_eq!("127.0.0.1:9477".parse::<StatusAddr>().unwrap(), StatusAddr::Tcp("127.0.0.1:9477".parse().unwrap()));
+        assert_eq!(
+            "unix:///run/aa-watch.sock".parse::<StatusAddr>().unwrap(),
+            StatusAddr::Local(LocalSocket::Unix("/run/aa-watch.sock".to_string()))
+        );
+        assert_eq!(StatusAddr::Tcp("127.0.0.1:9477".parse().unwrap()).to_string(), "http://127.0.0.1:9477/status");
+        assert!("localhost".parse::<StatusAddr>().is_err());
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let mut config = CliConfig { default_profile: Some("main".to_string()), ..Default::default() };
+        let profile = |url: &str| Profile { rpc_url: Some(url.to_string()), chain_id: Some(11155111), ..Default::default() };
+        config.profiles.insert("alt".to_string(), profile("https://alt"));
+        config.profiles.insert("main".to_string(), profile("https://main"));
+
+        let endpoints = endpoints(&config, &["8453=https://base".to_string()]).unwrap();
+        assert_eq!(endpoints[&8453], "https://base");
+        assert_eq!(endpoints[&11155111], "https://main");
+        assert_eq!(endpoints[&31337], "http://localhost:8545");
+        assert!(!endpoints.contains_key(&84532));
+
+        assert!(super::endpoints(&config, &["base".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_record_scan() {
+        let record = OperationRecord::pending("0x01".to_string(), 1, Address::ZERO, "submit", None, false);
+        let mut status = Status::default();
+        let first = ChainScan { pending: vec![PendingStatus::of(&record, record.submitted_at + 5, None)], bumped: 1, ..Default::default() };
+        status.record_scan(10, vec![(1, first)]);
+        assert_eq!(status.pending[0].pending_secs, 5);
+
+        let second = ChainScan { included: 1, error: Some("timeout".to_string()), ..Default::default() };
+        status.record_scan(20, vec![(1, second)]);
+        assert!(status.pending.is_empty());
+        assert_eq!(status.chains[&1].error.as_deref(), Some("timeout"));
+        assert_eq!((status.scans, status.included, status.bumped), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_respond() {
+        let status = Status { scans: 3, ..Default::default() };
+        let ok = respond("GET /status HTTP/1.1\r\nHost: localhost\r\n\r\n", &status);
+        assert!(ok.starts_with("HTTP/1.1 200 OK"));
+        assert!(ok.contains("\"scans\": 3"));
+        assert!(respond("POST /status HTTP/1.1\r\n\r\n", &status).starts_with("HTTP/1.1 404"));
+    }
+}
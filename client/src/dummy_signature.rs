@@ -0,0 +1,131 @@
+// Placeholder signatures sent while the bundler estimates gas, before anyone has signed
+// preVerificationGas grows with every signature byte and verification gas with what validation does
+// with them, so a placeholder shorter than the real signature underestimates both and the operation
+// fails validation (AA26, AA40) once it is signed
+
+use alloy::primitives::{bytes, Bytes};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::hexutil;
+
+/// ECDSA-shaped placeholder: r and s of realistic size, every byte but the padding non-zero
+pub const ECDSA_SIGNATURE: Bytes = bytes!("fffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c");
+
+/// P-256 placeholder (r || s), both below the curve order and s in its lower half
+pub const P256_SIGNATURE: Bytes = bytes!("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+
+/// Shape of the signature an account's validation receives, used for its placeholder
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DummySignature {
+    /// One 65-byte owner or session key signature
+    #[default]
+    Ecdsa,
+    /// That many owner signatures concatenated, as AAAccount verifies collected multi-owner signatures
+    MultiOwner(usize),
+    /// 64-byte P-256 signature of passkey and secure-enclave signers
+    P256,
+    /// Exact placeholder for any other validation scheme
+    Custom(Bytes),
+}
+
+impl DummySignature {
+    pub fn bytes(&self) -> Bytes {
+        match self {
+            DummySignature::Ecdsa => ECDSA_SIGNATURE,
+            DummySignature::MultiOwner(signers) => ECDSA_SIGNATURE.repeat(*signers).into(),
+            DummySignature::P256 => P256_SIGNATURE,
+            DummySignature::Custom(bytes) => bytes.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for DummySignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DummySignature::Ecdsa => write!(f, "ecdsa"),
+            DummySignature::MultiOwner(signers) => write!(f, "multi-owner:{}", signers),
+            DummySignature::P256 => write!(f, "p256"),
+            DummySignature::Custom(bytes) => write!(f, "{}", bytes),
+        }
+    }
+}
+
+impl FromStr for DummySignature {
+    type Err = anyhow::Error;
+
+    /// `ecdsa`, `multi-owner:<signers>`, `p256` or the placeholder itself as `0x<hex>`
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("0x") {
+            return Ok(DummySignature::Custom(hexutil::decode_bytes("dummy signature", s)?));
+        }
+        match s.to_ascii_lowercase().split_once(':') {
+            Some(("multi-owner", signers)) => match signers.parse() {
+                Ok(signers) if signers > 0 => Ok(DummySignature::MultiOwner(signers)),
+                _ => Err(anyhow::anyhow!("Invalid signer count in '{}', expected a number above zero", s)),
+            },
+            None if s.eq_ignore_ascii_case("ecdsa") => Ok(DummySignature::Ecdsa),
+            None if s.eq_ignore_ascii_case("p256") => Ok(DummySignature::P256),
+            _ => Err(anyhow::anyhow!("Unknown dummy signature '{}', expected ecdsa, multi-owner:<signers>, p256 or 0x<hex>", s)),
+        }
+    }
+}
+
+impl Serialize for DummySignature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Config files name placeholders the way the command line does (`"multi-owner:3"`)
+impl<'de> Deserialize<'de> for DummySignature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+static OVERRIDE: OnceLock<DummySignature> = OnceLock::new();
+
+/// Placeholder for every account of the rest of the process (`--dummy-signature` or the profile's)
+pub fn set_override(signature: DummySignature) {
+    let _ = OVERRIDE.set(signature);
+}
+
+/// Placeholder to estimate with: the override when one is set, otherwise the account's own
+pub fn resolve(account_default: &DummySignature) -> Bytes {
+    OVERRIDE.get().unwrap_or(account_default).bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_lengths() {
+        assert_eq!(DummySignature::Ecdsa.bytes().len(), 65);
+        assert_eq!(DummySignature::MultiOwner(3).bytes().len(), 3 * 65);
+        assert_eq!(DummySignature::P256.bytes().len(), 64);
+        // Calldata cost is what the placeholder stands in for, so it must not be mostly zeros
+        let zeros = DummySignature::MultiOwner(2).bytes().iter().filter(|b| **b == 0).count();
+        assert!(zeros < 2 * 20);
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for signature in [
+            DummySignature::Ecdsa,
+            DummySignature::MultiOwner(5),
+            DummySignature::P256,
+            DummySignature::Custom(Bytes::from(vec![0xab; 100])),
+        ] {
+            assert_eq!(signature.to_string().parse::<DummySignature>().unwrap(), signature);
+        }
+        assert_eq!("Multi-Owner:2".parse::<DummySignature>().unwrap(), DummySignature::MultiOwner(2));
+        assert!("multi-owner:0".parse::<DummySignature>().is_err());
+        assert!("multi-owner".parse::<DummySignature>().is_err());
+        assert!("ed25519".parse::<DummySignature>().is_err());
+        assert!("0xzz".parse::<DummySignature>().is_err());
+    }
+}
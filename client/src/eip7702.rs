@@ -0,0 +1,84 @@
+// EIP-7702 delegated EOAs: the owner key's own address acts as the smart account
+// The EOA points its code at an account implementation with a signed authorization tuple, which the
+// bundler includes in its type-4 transaction, so there is no factory, initCode or counterfactual address
+
+use alloy::eips::eip7702::{Authorization, SignedAuthorization};
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::signers::SignerSync;
+use anyhow::Result;
+
+use crate::wallet::Wallet;
+
+/// Code of a delegated EOA starts with this marker, followed by the delegate address
+pub const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Code the chain installs at an EOA delegated to `delegate`
+pub fn delegation_designator(delegate: Address) -> Vec<u8> {
+    let mut code = DELEGATION_PREFIX.to_vec();
+    code.extend_from_slice(delegate.as_slice());
+    code
+}
+
+/// Delegate of an EOA from its code, None for code that is not a delegation designator
+pub fn delegated_to(code: &[u8]) -> Option<Address> {
+    match code.strip_prefix(&DELEGATION_PREFIX) {
+        Some(delegate) if delegate.len() == 20 => Some(Address::from_slice(delegate)),
+        _ => None,
+    }
+}
+
+/// Authorization tuple delegating the wallet's EOA to `delegate`, `nonce` is the EOA's transaction count
+pub fn sign_authorization(wallet: &Wallet, chain_id: u64, delegate: Address, nonce: u64) -> Result<SignedAuthorization> {
+    let authorization = Authorization { chain_id: U256::from(chain_id), address: delegate, nonce };
+    let signature = wallet.signer().sign_hash_sync(&authorization.signature_hash())?;
+    Ok(authorization.into_signed(signature))
+}
+
+/// Authorization the operation has to carry, None when the EOA already delegates to `delegate`
+/// An EOA holding contract code that is not a designator cannot be delegated
+pub async fn authorization_for<P: Provider>(provider: &P, wallet: &Wallet, chain_id: u64, delegate: Address) -> Result<Option<SignedAuthorization>> {
+    let eoa = wallet.address();
+    let code = provider.get_code_at(eoa).await?;
+    match delegated_to(&code) {
+        Some(current) if current == delegate => Ok(None),
+        None if !code.is_empty() => Err(anyhow::anyhow!("{} holds contract code, only an EOA can be delegated with EIP-7702", eoa)),
+        _ => {
+            let nonce = provider.get_transaction_count(eoa).await?;
+            sign_authorization(wallet, chain_id, delegate, nonce).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delegation_designator_round_trip() {
+        let delegate = Address::from([0x42; 20]);
+        let code = delegation_designator(delegate);
+        assert_eq!(code.len(), 23);
+        assert_eq!(delegated_to(&code), Some(delegate));
+
+        assert_eq!(delegated_to(&[]), None);
+        assert_eq!(delegated_to(&[0x60, 0x80, 0x60, 0x40]), None);
+        assert_eq!(delegated_to(&code[..22]), None);
+    }
+
+    #[test]
+    fn test_authorization_recovers_to_eoa() {
+        let wallet = Wallet::from_hex("0x0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        let delegate = Address::from([0x42; 20]);
+        let signed = sign_authorization(&wallet, 11155111, delegate, 7).unwrap();
+
+        assert_eq!(signed.address, delegate);
+        assert_eq!(signed.nonce, 7);
+        assert_eq!(signed.recover_authority().unwrap(), wallet.address());
+
+        let json = serde_json::to_value(&signed).unwrap();
+        assert_eq!(json["chainId"], "0xaa36a7");
+        assert_eq!(json["nonce"], "0x7");
+        assert!(json.get("yParity").is_some());
+    }
+}
@@ -0,0 +1,221 @@
+// Known EntryPoint deployments and deposit management across versions
+// Used by the migration assistant to move an account's deposit between EntryPoints
+
+use alloy::primitives::{address, Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::config::NetworkConfig;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IStakeManager {
+        function balanceOf(address account) external view returns (uint256);
+        function depositTo(address account) external payable;
+        function withdrawTo(address withdrawAddress, uint256 withdrawAmount) external;
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IAccountEntryPoint {
+        function entryPoint() external view returns (address);
+    }
+);
+
+/// EntryPoint releases with canonical deterministic deployments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntryPointVersion {
+    V06,
+    V07,
+    V08,
+}
+
+impl EntryPointVersion {
+    pub const ALL: [EntryPointVersion; 3] = [EntryPointVersion::V06, EntryPointVersion::V07, EntryPointVersion::V08];
+
+    /// Canonical address, identical on every chain
+    pub fn address(self) -> Address {
+        match self {
+            EntryPointVersion::V06 => address!("5FF137D4b0FDCD49DcA30c7CF57E578a026d2789"),
+            EntryPointVersion::V07 => address!("0000000071727De22E5E9d8BAf0edAc6f37da032"),
+            EntryPointVersion::V08 => address!("4337084D9E255Ff0702461CF8895CE9E3b5Ff108"),
+        }
+    }
+
+    pub fn from_address(address: Address) -> Option<Self> {
+        Self::ALL.into_iter().find(|version| version.address() == address)
+    }
+}
+
+impl std::fmt::Display for EntryPointVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntryPointVersion::V06 => write!(f, "v0.6"),
+            EntryPointVersion::V07 => write!(f, "v0.7"),
+            EntryPointVersion::V08 => write!(f, "v0.8"),
+        }
+    }
+}
+
+impl FromStr for EntryPointVersion {
+    type Err = anyhow::Error;
+
+    /// Accepts a version (`v0.7`, `0.7`) or an EntryPoint address
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim_start_matches('v') {
+            "0.6" => Ok(EntryPointVersion::V06),
+            "0.7" => Ok(EntryPointVersion::V07),
+            "0.8" => Ok(EntryPointVersion::V08),
+            other => Address::from_str(other)
+                .ok()
+                .and_then(Self::from_address)
+                .ok_or_else(|| anyhow::anyhow!("Unknown EntryPoint '{}', expected v0.6, v0.7, v0.8 or a known address", s)),
+        }
+    }
+}
+
+impl Serialize for EntryPointVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Config files name versions the way the command line does (`"v0.7"`)
+impl<'de> Deserialize<'de> for EntryPointVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// EntryPoint release chosen for the process and the networks' deployments of each release
+struct Selection {
+    version: Option<EntryPointVersion>,
+    networks: Vec<NetworkConfig>,
+}
+
+static SELECTION: OnceLock<Selection> = OnceLock::new();
+
+/// Select the EntryPoint release (`--entry-point-version` or the profile's) for the rest of the process
+pub fn set_selection(version: Option<EntryPointVersion>, networks: Vec<NetworkConfig>) {
+    let _ = SELECTION.set(Selection { version, networks });
+}
+
+pub fn selected_version() -> Option<EntryPointVersion> {
+    SELECTION.get().and_then(|selection| selection.version)
+}
+
+/// EntryPoint operations on `chain_id` are built for: the network's deployment of the selected release,
+/// the network's default without a selection, v0.7 on networks the client does not know; an error on zkSync
+pub fn resolve(chain_id: u64) -> Result<Address> {
+    let version = selected_version();
+    let network = SELECTION.get().and_then(|selection| selection.networks.iter().find(|n| n.chain_id == chain_id));
+    let address = match network {
+        Some(network) if network.native_aa => {
+            return Err(anyhow::anyhow!("{} has native account abstraction and no EntryPoint, send calls with `submit`", network.name));
+        }
+        Some(network) => network.entry_point_for(version),
+        None => version.unwrap_or(EntryPointVersion::V07).address(),
+    };
+    if version_of(address) == Some(EntryPointVersion::V06) {
+        return Err(anyhow::anyhow!(
+            "EntryPoint v0.6 takes the unpacked UserOperation format, which this client does not build; use v0.7 or v0.8"
+        ));
+    }
+    Ok(address)
+}
+
+/// Release deployed at `address`, including the non-canonical deployments of configured networks
+pub fn version_of(address: Address) -> Option<EntryPointVersion> {
+    EntryPointVersion::from_address(address).or_else(|| {
+        SELECTION.get()?.networks.iter().find_map(|network| {
+            network.entry_points.iter().find(|(_, deployed)| **deployed == address).map(|(version, _)| *version)
+        })
+    })
+}
+
+/// State of one EntryPoint as seen by an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPointStatus {
+    pub version: EntryPointVersion,
+    /// Whether the EntryPoint contract exists on this chain
+    pub deployed: bool,
+    /// The account's deposit held by this EntryPoint
+    pub deposit: U256,
+    /// Whether the account validates UserOperations from this EntryPoint
+    pub active: bool,
+}
+
+/// EntryPoint the account is bound to, via its `entryPoint()` getter
+pub async fn account_entry_point<P: Provider>(provider: &P, account: Address) -> Result<Address> {
+    let contract = IAccountEntryPoint::new(account, provider);
+    Ok(contract.entryPoint().call().await?._0)
+}
+
+/// Deposit and support status of `account` on every known EntryPoint
+pub async fn entry_point_statuses<P: Provider>(provider: &P, account: Address) -> Result<Vec<EntryPointStatus>> {
+    let active = account_entry_point(provider, account).await.ok();
+
+    let mut statuses = Vec::new();
+    for version in EntryPointVersion::ALL {
+        let deployed = !provider.get_code_at(version.address()).await?.is_empty();
+        let deposit = if deployed {
+            IStakeManager::new(version.address(), provider).balanceOf(account).call().await?._0
+        } else {
+            U256::ZERO
+        };
+        statuses.push(EntryPointStatus {
+            version,
+            deployed,
+            deposit,
+            active: active == Some(version.address()),
+        });
+    }
+    Ok(statuses)
+}
+
+/// `withdrawTo(account, amount)` calldata, must be called by the account itself
+pub fn withdraw_to_call_data(account: Address, amount: U256) -> Bytes {
+    IStakeManager::withdrawToCall { withdrawAddress: account, withdrawAmount: amount }.abi_encode().into()
+}
+
+/// `depositTo(account)` calldata, sent with the deposit as value
+pub fn deposit_to_call_data(account: Address) -> Bytes {
+    IStakeManager::depositToCall { account }.abi_encode().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_parsing() {
+        assert_eq!("v0.6".parse::<EntryPointVersion>().unwrap(), EntryPointVersion::V06);
+        assert_eq!("0.8".parse::<EntryPointVersion>().unwrap(), EntryPointVersion::V08);
+        assert_eq!(
+            "0x0000000071727De22E5E9d8BAf0edAc6f37da032".parse::<EntryPointVersion>().unwrap(),
+            EntryPointVersion::V07
+        );
+        assert!("v0.5".parse::<EntryPointVersion>().is_err());
+        assert_eq!(EntryPointVersion::V07.to_string(), "v0.7");
+    }
+
+    #[test]
+    fn test_migration_call_data() {
+        let account = Address::from([4u8; 20]);
+
+        let withdraw = withdraw_to_call_data(account, U256::from(1000));
+        assert_eq!(&withdraw[..4], IStakeManager::withdrawToCall::SELECTOR.as_slice());
+        assert_eq!(withdraw.len(), 4 + 64);
+
+        let deposit = deposit_to_call_data(account);
+        assert_eq!(&deposit[..4], IStakeManager::depositToCall::SELECTOR.as_slice());
+    }
+}
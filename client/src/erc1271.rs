@@ -0,0 +1,61 @@
+// ERC-1271 signature checks against contract wallets, which cannot produce signatures that recover to their address
+// Used for the verify-1271 command and to check multi-owner signature shares from contract owners before they are combined
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{fixed_bytes, Address, Bytes, FixedBytes, B256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+
+sol!(
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4 magicValue);
+    }
+);
+
+/// Value `isValidSignature` returns for a valid signature, its own selector
+pub const MAGIC_VALUE: FixedBytes<4> = fixed_bytes!("1626ba7e");
+
+/// Ask `contract` whether `signature` is valid for `hash`
+/// A revert or any other return value means invalid, only a failing RPC is an error
+pub async fn is_valid_signature<P: Provider>(provider: &P, contract: Address, hash: B256, signature: &Bytes) -> Result<bool> {
+    if provider.get_code_at(contract).await?.is_empty() {
+        return Err(anyhow::anyhow!("{} has no code, it is not a contract wallet", contract));
+    }
+
+    let call = IERC1271::isValidSignatureCall { hash, signature: signature.clone() };
+    let tx = TransactionRequest::default()
+        .with_to(contract)
+        .with_input(Bytes::from(call.abi_encode()));
+    match provider.call(tx).await {
+        Ok(output) => Ok(is_magic_value(&output)),
+        Err(e) if e.as_error_resp().and_then(|payload| payload.as_revert_data()).is_some() => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `isValidSignature` output is an ABI-encoded bytes4, left-aligned in one word
+fn is_magic_value(output: &[u8]) -> bool {
+    output.len() == 32 && output[..4] == MAGIC_VALUE[..] && output[4..].iter().all(|b| *b == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_value() {
+        assert_eq!(MAGIC_VALUE.0, IERC1271::isValidSignatureCall::SELECTOR);
+
+        let mut output = [0u8; 32];
+        output[..4].copy_from_slice(&MAGIC_VALUE[..]);
+        assert!(is_magic_value(&output));
+
+        assert!(!is_magic_value(&[0u8; 32]));
+        assert!(!is_magic_value(&output[..4]));
+        output[31] = 1;
+        assert!(!is_magic_value(&output));
+    }
+}
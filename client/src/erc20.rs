@@ -0,0 +1,105 @@
+// ERC-20 token calls made from the smart account
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IERC20 {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+        function decimals() external view returns (uint8);
+        function balanceOf(address owner) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+    }
+);
+
+/// Calldata for `transfer(to, amount)`
+pub fn transfer_call_data(to: Address, amount: U256) -> Bytes {
+    IERC20::transferCall { to, amount }.abi_encode().into()
+}
+
+/// Calldata for `approve(spender, amount)`
+pub fn approve_call_data(spender: Address, amount: U256) -> Bytes {
+    IERC20::approveCall { spender, amount }.abi_encode().into()
+}
+
+/// Decimals the token declares, used to scale human-readable amounts
+pub async fn decimals<P: Provider>(provider: &P, token: Address) -> Result<u8> {
+    IERC20::new(token, provider)
+        .decimals()
+        .call()
+        .await
+        .map(|decimals| decimals._0)
+        .map_err(|e| anyhow::anyhow!("Could not read decimals() of token {}, pass --decimals: {}", token, e))
+}
+
+/// Tokens `owner` holds, in base units
+pub async fn balance_of<P: Provider>(provider: &P, token: Address, owner: Address) -> Result<U256> {
+    IERC20::new(token, provider)
+        .balanceOf(owner)
+        .call()
+        .await
+        .map(|balance| balance._0)
+        .map_err(|e| anyhow::anyhow!("Could not read balanceOf() of token {}, is it an ERC-20? {}", token, e))
+}
+
+/// Tokens `spender` may still take from `owner`, in base units
+pub async fn allowance<P: Provider>(provider: &P, token: Address, owner: Address, spender: Address) -> Result<U256> {
+    IERC20::new(token, provider)
+        .allowance(owner, spender)
+        .call()
+        .await
+        .map(|allowance| allowance._0)
+        .map_err(|e| anyhow::anyhow!("Could not read allowance() of token {}, is it an ERC-20? {}", token, e))
+}
+
+/// Refuse an operation a token paymaster cannot charge: it pulls the gas cost from `account`'s balance
+/// through the allowance, so with either at zero it fails validation after the bundler accepted it
+pub fn check_can_pay(token: Address, account: Address, paymaster: Address, balance: U256, allowance: U256) -> Result<()> {
+    if balance.is_zero() {
+        return Err(anyhow::anyhow!("Account {} holds no {} to pay the paymaster with", account, token));
+    }
+    if allowance.is_zero() {
+        return Err(anyhow::anyhow!(
+            "Paymaster {} may not spend {} of account {}, approve it first: erc20 approve --token {} --spender {} --amount <AMOUNT>",
+            paymaster,
+            token,
+            account,
+            token,
+            paymaster
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_data_selectors() {
+        let to = Address::from([0x12; 20]);
+        let transfer = transfer_call_data(to, U256::from(1_000_000));
+        assert_eq!(&transfer[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(transfer.len(), 4 + 2 * 32);
+        assert_eq!(&transfer[16..36], to.as_slice());
+
+        let approve = approve_call_data(to, U256::MAX);
+        assert_eq!(&approve[..4], &[0x09, 0x5e, 0xa7, 0xb3]);
+        assert_eq!(&approve[36..], &[0xff; 32]);
+    }
+
+    #[test]
+    fn test_check_can_pay() {
+        let (token, account, paymaster) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02), Address::repeat_byte(0x03));
+        assert!(check_can_pay(token, account, paymaster, U256::from(10), U256::from(1)).is_ok());
+        assert!(check_can_pay(token, account, paymaster, U256::ZERO, U256::MAX).unwrap_err().to_string().contains("holds no"));
+        let no_allowance = check_can_pay(token, account, paymaster, U256::from(10), U256::ZERO).unwrap_err().to_string();
+        assert!(no_allowance.contains("erc20 approve"));
+    }
+}
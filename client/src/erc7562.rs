@@ -0,0 +1,228 @@
+// Static checks of the factory and paymaster against the ERC-7562 validation rules bundlers enforce
+// Bytecode is scanned without knowing which paths validation takes, so findings are warnings, not verdicts
+
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use anyhow::Result;
+
+use crate::multisig::UnsignedUserOperation;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IEntryPointStakes {
+        struct DepositInfo {
+            uint256 deposit;
+            bool staked;
+            uint112 stake;
+            uint32 unstakeDelaySec;
+            uint48 withdrawTime;
+        }
+        function getDepositInfo(address account) external view returns (DepositInfo memory info);
+    }
+);
+
+/// Shortest unstake delay bundlers accept for a staked entity (ERC-7562 MIN_UNSTAKE_DELAY)
+pub const MIN_UNSTAKE_DELAY: u32 = 86_400;
+
+/// Entity of the operation a finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Factory,
+    Paymaster,
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Entity::Factory => write!(f, "factory"),
+            Entity::Paymaster => write!(f, "paymaster"),
+        }
+    }
+}
+
+/// One rule the operation may break
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub entity: Entity,
+    pub address: Address,
+    /// ERC-7562 rule identifier (OP-011, STO-021, ...)
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Opcodes banned during validation (OP-011, OP-013, OP-031 for CREATE)
+pub fn banned_opcode_name(opcode: u8) -> Option<&'static str> {
+    Some(match opcode {
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x3a => "GASPRICE",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0xf0 => "CREATE",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    })
+}
+
+const GAS: u8 = 0x5a;
+const SLOAD: u8 = 0x54;
+const SSTORE: u8 = 0x55;
+const CREATE2: u8 = 0xf5;
+const CALLS: [u8; 4] = [0xf1, 0xf2, 0xf4, 0xfa];
+
+/// What a contract's bytecode can do, PUSH data excluded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeScan {
+    /// Banned opcodes present, in order of first appearance
+    pub banned: Vec<&'static str>,
+    /// GAS not immediately followed by a call (OP-012)
+    pub bare_gas: bool,
+    pub storage_access: bool,
+    pub create2: bool,
+}
+
+pub fn scan(code: &[u8]) -> CodeScan {
+    let mut result = CodeScan::default();
+    let mut i = 0;
+    while i < code.len() {
+        let opcode = code[i];
+        match opcode {
+            // PUSH1..PUSH32 carry immediate data that is not code
+            0x60..=0x7f => {
+                i += (opcode - 0x5f) as usize;
+            }
+            GAS => {
+                if !code.get(i + 1).is_some_and(|next| CALLS.contains(next)) {
+                    result.bare_gas = true;
+                }
+            }
+            SLOAD | SSTORE => result.storage_access = true,
+            CREATE2 => result.create2 = true,
+            _ => {
+                if let Some(name) = banned_opcode_name(opcode) {
+                    if !result.banned.contains(&name) {
+                        result.banned.push(name);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+/// Stake as the EntryPoint records it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StakeInfo {
+    pub stake: U256,
+    pub unstake_delay: u32,
+}
+
+impl StakeInfo {
+    /// Staked enough for bundlers to relax the storage and context rules; the minimum stake value is chain specific
+    pub fn is_staked(&self) -> bool {
+        self.stake > U256::ZERO && self.unstake_delay >= MIN_UNSTAKE_DELAY
+    }
+}
+
+/// Findings for one entity from its code and stake
+pub fn check_entity(entity: Entity, address: Address, code: &[u8], stake: StakeInfo, returns_context: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut finding = |rule: &'static str, message: String| findings.push(Finding { entity, address, rule, message });
+
+    if code.is_empty() {
+        finding("OP-041", format!("no code at {}, the EntryPoint will reject the operation", address));
+        return findings;
+    }
+    let scan = scan(code);
+    if !scan.banned.is_empty() {
+        finding("OP-011", format!("uses {}, banned if reached during validation", scan.banned.join(", ")));
+    }
+    if scan.bare_gas {
+        finding("OP-012", "reads GAS other than right before a call, banned during validation".to_string());
+    }
+    if entity == Entity::Factory && !scan.create2 {
+        finding("OP-031", "has no CREATE2, it cannot deploy the account at a deterministic address".to_string());
+    }
+    if !stake.is_staked() {
+        if scan.storage_access {
+            finding("STO-021", format!("is unstaked but touches storage; only storage associated with the sender is allowed without stake (unstake delay {}s, at least {}s required)", stake.unstake_delay, MIN_UNSTAKE_DELAY));
+        }
+        if returns_context {
+            finding("EREP-050", "is unstaked and the operation reserves postOp gas; an unstaked paymaster may not return a context".to_string());
+        }
+    }
+    findings
+}
+
+/// Lint the factory and paymaster of a filled operation
+pub async fn lint<P: Provider>(provider: &P, entry_point: Address, user_op: &UnsignedUserOperation) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    if let Some(factory) = user_op.factory {
+        let code = provider.get_code_at(factory).await?;
+        let stake = stake_info(provider, entry_point, factory).await?;
+        findings.extend(check_entity(Entity::Factory, factory, &code, stake, false));
+    }
+    if let Some(paymaster) = user_op.paymaster {
+        let code = provider.get_code_at(paymaster).await?;
+        let stake = stake_info(provider, entry_point, paymaster).await?;
+        let returns_context = user_op.paymaster_post_op_gas_limit.is_some_and(|gas| gas > U256::ZERO);
+        findings.extend(check_entity(Entity::Paymaster, paymaster, &code, stake, returns_context));
+    }
+    Ok(findings)
+}
+
+async fn stake_info<P: Provider>(provider: &P, entry_point: Address, entity: Address) -> Result<StakeInfo> {
+    let info = IEntryPointStakes::new(entry_point, provider).getDepositInfo(entity).call().await?.info;
+    Ok(StakeInfo { stake: U256::from(info.stake), unstake_delay: info.unstakeDelaySec })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAKED: StakeInfo = StakeInfo { stake: U256::from_limbs([1, 0, 0, 0]), unstake_delay: MIN_UNSTAKE_DELAY };
+    const UNSTAKED: StakeInfo = StakeInfo { stake: U256::ZERO, unstake_delay: 0 };
+
+    #[test]
+    fn test_scan_skips_push_data() {
+        // PUSH2 0x4242 (TIMESTAMP bytes as data), GAS CALL, SLOAD, TIMESTAMP, GAS POP
+        let code = [0x61, 0x42, 0x42, 0x5a, 0xf1, 0x54, 0x42, 0x5a, 0x50];
+        let scan = scan(&code);
+        assert_eq!(scan.banned, vec!["TIMESTAMP"]);
+        assert!(scan.bare_gas);
+        assert!(scan.storage_access);
+        assert!(!scan.create2);
+
+        let clean = super::scan(&[0x61, 0x42, 0x42, 0x5a, 0xfa, 0xf5]);
+        assert_eq!(clean, CodeScan { create2: true, ..CodeScan::default() });
+    }
+
+    #[test]
+    fn test_stake_relaxes_storage_and_context_rules() {
+        let paymaster = Address::from([0x22; 20]);
+        let code = [0x54, 0x00];
+
+        let rules = |stake| check_entity(Entity::Paymaster, paymaster, &code, stake, true)
+            .into_iter()
+            .map(|finding| finding.rule)
+            .collect::<Vec<_>>();
+        assert_eq!(rules(UNSTAKED), vec!["STO-021", "EREP-050"]);
+        assert!(rules(STAKED).is_empty());
+
+        let missing = check_entity(Entity::Factory, paymaster, &[], STAKED, false);
+        assert_eq!(missing[0].rule, "OP-041");
+        let no_create2 = check_entity(Entity::Factory, paymaster, &[0x00], STAKED, false);
+        assert_eq!(no_create2[0].rule, "OP-031");
+    }
+}
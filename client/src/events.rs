@@ -0,0 +1,108 @@
+// Structured progress of an operation for applications embedding the client, instead of parsing `out!` lines
+// Subscribers are process-wide like the output subscriber: a callback, or a channel read as an async stream
+
+use alloy::primitives::{Address, U256};
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::userop::UserOperationGasEstimation;
+
+/// One step in the life of a UserOperation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ClientEvent {
+    /// Nonce, gas and fees are being filled in
+    Filling { sender: Address, chain_id: u64 },
+    /// Gas limits returned by `eth_estimateUserOperationGas`
+    Estimated { call_gas_limit: U256, verification_gas_limit: U256, pre_verification_gas: U256 },
+    /// A paymaster signature was asked for (not emitted when a cached sponsorship is reused)
+    SponsorshipRequested { sender: Address, paymaster: Address },
+    /// A bundler accepted the operation
+    Submitted { user_op_hash: String, chain_id: u64, sender: Address },
+    /// Accepted but not included yet
+    Pending { user_op_hash: String },
+    /// Included and executed successfully
+    Included { user_op_hash: String, actual_gas_used: U256, actual_gas_cost: U256 },
+    /// Rejected, reverted or given up on; no hash when the bundler never accepted it
+    Failed { user_op_hash: Option<String>, reason: String },
+}
+
+impl ClientEvent {
+    pub fn estimated(estimate: &UserOperationGasEstimation) -> Self {
+        ClientEvent::Estimated {
+            call_gas_limit: estimate.call_gas_limit,
+            verification_gas_limit: estimate.verification_gas_limit,
+            pre_verification_gas: estimate.pre_verification_gas,
+        }
+    }
+}
+
+enum Subscriber {
+    Callback(Box<dyn Fn(&ClientEvent) + Send + Sync>),
+    Channel(UnboundedSender<ClientEvent>),
+}
+
+static SUBSCRIBERS: OnceLock<Mutex<Vec<Subscriber>>> = OnceLock::new();
+
+fn subscribers() -> &'static Mutex<Vec<Subscriber>> {
+    SUBSCRIBERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Call `callback` with every event from now on, on the task that emits it
+pub fn subscribe(callback: impl Fn(&ClientEvent) + Send + Sync + 'static) {
+    subscribers().lock().unwrap().push(Subscriber::Callback(Box::new(callback)));
+}
+
+/// Receive every event from now on; dropping the receiver unsubscribes
+pub fn stream() -> UnboundedReceiver<ClientEvent> {
+    let (sender, receiver) = unbounded_channel();
+    subscribers().lock().unwrap().push(Subscriber::Channel(sender));
+    receiver
+}
+
+/// Deliver `event` to every subscriber, a no-op when there are none
+/// The hash of a submitted operation also goes on the current span
+pub fn emit(event: ClientEvent) {
+    if let ClientEvent::Submitted { user_op_hash, .. } | ClientEvent::Pending { user_op_hash } = &event {
+        crate::telemetry::record_user_op_hash(user_op_hash);
+    }
+    let Some(subscribers) = SUBSCRIBERS.get() else {
+        return;
+    };
+    subscribers.lock().unwrap().retain(|subscriber| match subscriber {
+        Subscriber::Callback(callback) => {
+            callback(&event);
+            true
+        }
+        Subscriber::Channel(sender) => sender.send(event.clone()).is_ok(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        subscribe(move |event| {
+            if matches!(event, ClientEvent::Pending { user_op_hash } if user_op_hash == "0xevents") {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let mut events = stream();
+        let dropped = stream();
+        drop(dropped);
+
+        emit(ClientEvent::Pending { user_op_hash: "0xevents".to_string() });
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+        assert_eq!(events.recv().await, Some(ClientEvent::Pending { user_op_hash: "0xevents".to_string() }));
+
+        let json = serde_json::to_value(ClientEvent::Failed { user_op_hash: None, reason: "AA21".to_string() }).unwrap();
+        assert_eq!(json["event"], "failed");
+    }
+}
@@ -0,0 +1,168 @@
+// Process exit codes by failure class, so scripts can branch on why a command failed
+// 1 is any other error and 2 a usage error reported by clap before a command runs
+
+use crate::preflight::{NoCodeAtTarget, PreflightFailure, Rejected};
+
+/// Why a command failed, for the classes scripts commonly handle differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The EntryPoint or simulation rejected the operation (AA1x, AA2x, a reverting call), or its target has no code
+    Validation,
+    /// The account or paymaster cannot pay for gas (AA21, AA31)
+    InsufficientFunds,
+    /// The paymaster service refused to sign, or the EntryPoint refused its sponsorship (AA3x)
+    SponsorshipRejected,
+    /// Every bundler refused or failed to take the operation
+    BundlerRejected,
+    /// The operation was sent but no receipt arrived within --wait
+    ReceiptTimeout,
+}
+
+/// Exit code of errors outside the classes below
+pub const GENERIC_FAILURE: i32 = 1;
+
+impl Failure {
+    pub fn code(self) -> i32 {
+        match self {
+            Failure::Validation => 3,
+            Failure::InsufficientFunds => 4,
+            Failure::SponsorshipRejected => 5,
+            Failure::BundlerRejected => 6,
+            Failure::ReceiptTimeout => 7,
+        }
+    }
+
+    /// Class of an EntryPoint error code such as `AA25`
+    fn from_aa_code(code: &str) -> Self {
+        match code {
+            "AA21" | "AA31" => Failure::InsufficientFunds,
+            code if code.starts_with("AA3") => Failure::SponsorshipRejected,
+            _ => Failure::Validation,
+        }
+    }
+}
+
+/// Error for an operation that was sent but not included within the wait
+#[derive(Debug)]
+pub struct ReceiptTimeout {
+    pub user_op_hash: String,
+    pub timeout_secs: u64,
+}
+
+impl std::fmt::Display for ReceiptTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No receipt for {} within {}s, it may still be included", self.user_op_hash, self.timeout_secs)
+    }
+}
+
+impl std::error::Error for ReceiptTimeout {}
+
+/// Error already explained on the terminal, only its exit code is left to report
+#[derive(Debug)]
+pub struct Reported(pub anyhow::Error);
+
+impl std::fmt::Display for Reported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Reported {}
+
+/// Class of a command error, None for anything the classes do not cover
+pub fn classify(error: &anyhow::Error) -> Option<Failure> {
+    if let Some(reported) = error.downcast_ref::<Reported>() {
+        return classify(&reported.0);
+    }
+    if error.downcast_ref::<ReceiptTimeout>().is_some() {
+        return Some(Failure::ReceiptTimeout);
+    }
+    if error.downcast_ref::<NoCodeAtTarget>().is_some() {
+        return Some(Failure::Validation);
+    }
+    if let Some(Rejected(failure)) = error.downcast_ref::<Rejected>() {
+        return Some(match failure {
+            PreflightFailure::Validation { code: Some(code), .. } => Failure::from_aa_code(code),
+            _ => Failure::Validation,
+        });
+    }
+
+    // Bundler and paymaster errors arrive as JSON-RPC and HTTP error text
+    let text = error.chain().map(|cause| cause.to_string()).collect::<Vec<_>>().join(": ");
+    if let Some(code) = aa_code(&text) {
+        return Some(Failure::from_aa_code(code));
+    }
+    let text = text.to_lowercase();
+    if text.contains("insufficient funds") || text.contains("insufficient balance") {
+        Some(Failure::InsufficientFunds)
+    } else if text.contains("paymaster service") || text.contains("sponsorship") {
+        Some(Failure::SponsorshipRejected)
+    } else if text.contains("eth_senduseroperation") || text.contains("bundler") {
+        Some(Failure::BundlerRejected)
+    } else {
+        None
+    }
+}
+
+/// Exit code for a command error
+pub fn code(error: &anyhow::Error) -> i32 {
+    classify(error).map_or(GENERIC_FAILURE, Failure::code)
+}
+
+/// First EntryPoint error code (`AA` and two digits, standing alone) in `text`
+fn aa_code(text: &str) -> Option<&str> {
+    let bytes = text.as_bytes();
+    (0..bytes.len().saturating_sub(3)).find_map(|i| {
+        let standalone_start = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        let standalone_end = bytes.get(i + 4).is_none_or(|next| !next.is_ascii_alphanumeric());
+        (standalone_start
+            && standalone_end
+            && &bytes[i..i + 2] == b"AA"
+            && bytes[i + 2].is_ascii_digit()
+            && bytes[i + 3].is_ascii_digit())
+        .then(|| &text[i..i + 4])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_error_text() {
+        let classify_text = |text: &str| classify(&anyhow::anyhow!("{}", text.to_string()));
+
+        assert_eq!(classify_text("UserOperation reverted during simulation with reason: AA25 invalid account nonce"), Some(Failure::Validation));
+        assert_eq!(classify_text("AA21 didn't pay prefund"), Some(Failure::InsufficientFunds));
+        assert_eq!(classify_text("AA33 reverted"), Some(Failure::SponsorshipRejected));
+        assert_eq!(classify_text("Paymaster service error (HTTP 403): sender not allowed"), Some(Failure::SponsorshipRejected));
+        assert_eq!(classify_text("Every bundler rejected the UserOperation: http://a: gas too low"), Some(Failure::BundlerRejected));
+        assert_eq!(classify_text("invalid string length"), None);
+        // Words that merely contain AA and digits are not error codes
+        assert_eq!(aa_code("0xAA123456"), None);
+        assert_eq!(aa_code("reason: AA93 invalid paymasterAndData"), Some("AA93"));
+    }
+
+    #[test]
+    fn test_typed_errors_and_exit_codes() {
+        let rejected = anyhow::Error::new(Rejected(PreflightFailure::Validation {
+            code: Some("AA31".to_string()),
+            reason: "AA31 paymaster deposit too low".to_string(),
+            inner: None,
+        }));
+        assert_eq!(classify(&rejected), Some(Failure::InsufficientFunds));
+
+        let execution = anyhow::Error::new(Rejected(PreflightFailure::Execution { reason: "revert: paused".to_string() }));
+        assert_eq!(code(&execution), 3);
+
+        let no_code = anyhow::Error::new(NoCodeAtTarget(alloy::primitives::Address::ZERO)).context("pass --allow-eoa-target");
+        assert_eq!(code(&no_code), 3);
+
+        let timeout = anyhow::Error::new(ReceiptTimeout { user_op_hash: "0x12".to_string(), timeout_secs: 120 });
+        assert_eq!(code(&timeout), 7);
+
+        let reported = anyhow::Error::new(Reported(anyhow::anyhow!("All 2 bundler endpoints failed on eth_sendUserOperation")));
+        assert_eq!(code(&reported), 6);
+        assert_eq!(code(&anyhow::anyhow!("Profile 'x' not found in config file")), GENERIC_FAILURE);
+    }
+}
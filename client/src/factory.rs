@@ -0,0 +1,330 @@
+// Account factory ABIs the client predicts and deploys accounts through
+// Factories agree on what they do (deploy an account for owners and a salt, and tell its address before)
+// but not on the signatures, so each ABI is an `AccountFactory` encoding, selected with `--factory-abi`
+// or the profile's `factory_abi`
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Standard ERC-4337 SimpleAccountFactory ABI
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    SimpleAccountFactory,
+    r#"[
+        {
+            "inputs": [
+                {"internalType": "address", "name": "owner", "type": "address"},
+                {"internalType": "uint256", "name": "salt", "type": "uint256"}
+            ],
+            "name": "createAccount",
+            "outputs": [
+                {"internalType": "contract SimpleAccount", "name": "ret", "type": "address"}
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {"internalType": "address", "name": "owner", "type": "address"},
+                {"internalType": "uint256", "name": "salt", "type": "uint256"}
+            ],
+            "name": "getAddress",
+            "outputs": [
+                {"internalType": "address", "name": "", "type": "address"}
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        }
+    ]"#
+);
+
+// AAAccountFactory ABI for multi-owner support
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    AAAccountFactory,
+    r#"[
+        {
+            "inputs": [
+                {"internalType": "address[]", "name": "owners", "type": "address[]"},
+                {"internalType": "uint256", "name": "salt", "type": "uint256"}
+            ],
+            "name": "createAccountWithOwners",
+            "outputs": [
+                {"internalType": "contract AAAccount", "name": "account", "type": "address"}
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {"internalType": "address[]", "name": "owners", "type": "address[]"},
+                {"internalType": "uint256", "name": "salt", "type": "uint256"}
+            ],
+            "name": "getAddressWithOwners",
+            "outputs": [
+                {"internalType": "address", "name": "", "type": "address"}
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "accountImplementation",
+            "outputs": [
+                {"internalType": "contract AAAccount", "name": "", "type": "address"}
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "entryPoint",
+            "outputs": [
+                {"internalType": "contract IEntryPoint", "name": "", "type": "address"}
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        }
+    ]"#
+);
+
+sol!(
+    #[allow(missing_docs)]
+    interface IOwnersArrayFactory {
+        function createAccount(address[] owners, uint256 salt) external returns (address);
+        function getAddress(address[] owners, uint256 salt) external view returns (address);
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    interface IOwnersBytesFactory {
+        function createAccount(bytes owners, uint256 nonce) external payable returns (address);
+        function getAddress(bytes owners, uint256 nonce) external view returns (address);
+    }
+);
+
+/// Calldata of one factory ABI
+pub trait AccountFactory: Send + Sync {
+    /// Call deploying the account of `owners` and `salt`, the factory data of its first UserOperation
+    fn create_account_call(&self, owners: &[Address], salt: U256) -> Result<Bytes>;
+
+    /// View call returning the address that account is deployed at
+    fn get_address_call(&self, owners: &[Address], salt: U256) -> Result<Bytes>;
+
+    /// Selector of `create_account_call`, to recognize factory data
+    fn create_selector(&self) -> [u8; 4];
+}
+
+/// `createAccount(address owner, uint256 salt)`, one owner only
+pub struct SimpleAccountAbi;
+
+impl SimpleAccountAbi {
+    fn owner(owners: &[Address]) -> Result<Address> {
+        match owners {
+            [owner] => Ok(*owner),
+            _ => Err(anyhow::anyhow!("The simple factory ABI takes exactly one owner, got {}", owners.len())),
+        }
+    }
+}
+
+impl AccountFactory for SimpleAccountAbi {
+    fn create_account_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(SimpleAccountFactory::createAccountCall { owner: Self::owner(owners)?, salt }.abi_encode().into())
+    }
+
+    fn get_address_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(SimpleAccountFactory::getAddressCall { owner: Self::owner(owners)?, salt }.abi_encode().into())
+    }
+
+    fn create_selector(&self) -> [u8; 4] {
+        SimpleAccountFactory::createAccountCall::SELECTOR
+    }
+}
+
+/// AAAccountFactory's `createAccountWithOwners(address[] owners, uint256 salt)`
+pub struct MultiOwnerAbi;
+
+impl AccountFactory for MultiOwnerAbi {
+    fn create_account_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(AAAccountFactory::createAccountWithOwnersCall { owners: owners.to_vec(), salt }.abi_encode().into())
+    }
+
+    fn get_address_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(AAAccountFactory::getAddressWithOwnersCall { owners: owners.to_vec(), salt }.abi_encode().into())
+    }
+
+    fn create_selector(&self) -> [u8; 4] {
+        AAAccountFactory::createAccountWithOwnersCall::SELECTOR
+    }
+}
+
+/// `createAccount(address[] owners, uint256 salt)`
+pub struct OwnersArrayAbi;
+
+impl AccountFactory for OwnersArrayAbi {
+    fn create_account_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(IOwnersArrayFactory::createAccountCall { owners: owners.to_vec(), salt }.abi_encode().into())
+    }
+
+    fn get_address_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(IOwnersArrayFactory::getAddressCall { owners: owners.to_vec(), salt }.abi_encode().into())
+    }
+
+    fn create_selector(&self) -> [u8; 4] {
+        IOwnersArrayFactory::createAccountCall::SELECTOR
+    }
+}
+
+/// `createAccount(bytes owners, uint256 nonce)`, the owners ABI-encoded as `address[]` and the salt as nonce
+pub struct OwnersBytesAbi;
+
+impl AccountFactory for OwnersBytesAbi {
+    fn create_account_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(IOwnersBytesFactory::createAccountCall { owners: owners.to_vec().abi_encode().into(), nonce: salt }.abi_encode().into())
+    }
+
+    fn get_address_call(&self, owners: &[Address], salt: U256) -> Result<Bytes> {
+        Ok(IOwnersBytesFactory::getAddressCall { owners: owners.to_vec().abi_encode().into(), nonce: salt }.abi_encode().into())
+    }
+
+    fn create_selector(&self) -> [u8; 4] {
+        IOwnersBytesFactory::createAccountCall::SELECTOR
+    }
+}
+
+/// Factory ABI by name, as `--factory-abi` and the config file give it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactoryAbi {
+    /// SimpleAccountFactory, the default for single-owner commands
+    Simple,
+    /// AAAccountFactory, the default for multi-owner commands
+    MultiOwner,
+    OwnersArray,
+    OwnersBytes,
+}
+
+impl FactoryAbi {
+    pub const ALL: [FactoryAbi; 4] = [FactoryAbi::Simple, FactoryAbi::MultiOwner, FactoryAbi::OwnersArray, FactoryAbi::OwnersBytes];
+
+    pub fn encoding(&self) -> &'static dyn AccountFactory {
+        match self {
+            FactoryAbi::Simple => &SimpleAccountAbi,
+            FactoryAbi::MultiOwner => &MultiOwnerAbi,
+            FactoryAbi::OwnersArray => &OwnersArrayAbi,
+            FactoryAbi::OwnersBytes => &OwnersBytesAbi,
+        }
+    }
+}
+
+impl std::fmt::Display for FactoryAbi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FactoryAbi::Simple => write!(f, "simple"),
+            FactoryAbi::MultiOwner => write!(f, "multi-owner"),
+            FactoryAbi::OwnersArray => write!(f, "owners-array"),
+            FactoryAbi::OwnersBytes => write!(f, "owners-bytes"),
+        }
+    }
+}
+
+impl FromStr for FactoryAbi {
+    type Err = anyhow::Error;
+
+    /// `simple`, `multi-owner`, `owners-array` or `owners-bytes`
+    fn from_str(s: &str) -> Result<Self> {
+        FactoryAbi::ALL
+            .into_iter()
+            .find(|abi| abi.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("Unknown factory ABI '{}', expected simple, multi-owner, owners-array or owners-bytes", s))
+    }
+}
+
+impl Serialize for FactoryAbi {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FactoryAbi {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+static OVERRIDE: OnceLock<FactoryAbi> = OnceLock::new();
+
+/// Factory ABI for every account of the rest of the process (`--factory-abi` or the profile's)
+pub fn set_override(abi: FactoryAbi) {
+    let _ = OVERRIDE.set(abi);
+}
+
+/// ABI to talk to the factory with: the override when one is set, otherwise the command's own
+pub fn resolve(command_default: FactoryAbi) -> FactoryAbi {
+    *OVERRIDE.get().unwrap_or(&command_default)
+}
+
+/// Whether `selector` starts the create call of a known factory ABI
+pub fn is_create_selector(selector: &[u8]) -> bool {
+    FactoryAbi::ALL.iter().any(|abi| abi.encoding().create_selector() == selector)
+}
+
+/// Address returned by a factory's address view
+pub fn decode_address(output: &[u8]) -> Result<Address> {
+    Address::abi_decode(output, true).map_err(|e| anyhow::anyhow!("Factory did not return an address: {}", e))
+}
+
+/// Ask `factory` where it deploys the account of `owners` and `salt`
+pub async fn predict_address<P: Provider>(provider: &P, factory: Address, abi: FactoryAbi, owners: &[Address], salt: U256) -> Result<Address> {
+    let tx = TransactionRequest::default().with_to(factory).with_input(abi.encoding().get_address_call(owners, salt)?);
+    decode_address(&provider.call(tx).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        let owners = [Address::repeat_byte(0x01), Address::repeat_byte(0x02)];
+        let salt = U256::from(9);
+
+        let simple = FactoryAbi::Simple.encoding();
+        let call = SimpleAccountFactory::createAccountCall::abi_decode(&simple.create_account_call(&owners[..1], salt).unwrap(), true).unwrap();
+        assert_eq!((call.owner, call.salt), (owners[0], salt));
+        assert!(simple.get_address_call(&owners, salt).is_err());
+
+        let array = FactoryAbi::OwnersArray.encoding().get_address_call(&owners, salt).unwrap();
+        assert_eq!(IOwnersArrayFactory::getAddressCall::abi_decode(&array, true).unwrap().owners, owners.to_vec());
+
+        let bytes = FactoryAbi::OwnersBytes.encoding().create_account_call(&owners, salt).unwrap();
+        let call = IOwnersBytesFactory::createAccountCall::abi_decode(&bytes, true).unwrap();
+        assert_eq!(Vec::<Address>::abi_decode(&call.owners, true).unwrap(), owners.to_vec());
+        assert_eq!(call.nonce, salt);
+
+        // Same name, different ABI: the selectors must tell them apart
+        let selectors: std::collections::HashSet<_> = FactoryAbi::ALL.iter().map(|abi| abi.encoding().create_selector()).collect();
+        assert_eq!(selectors.len(), FactoryAbi::ALL.len());
+        assert!(is_create_selector(&bytes[..4]));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for abi in FactoryAbi::ALL {
+            assert_eq!(abi.to_string().parse::<FactoryAbi>().unwrap(), abi);
+        }
+        assert_eq!("Owners-Bytes".parse::<FactoryAbi>().unwrap(), FactoryAbi::OwnersBytes);
+        assert!("kernel".parse::<FactoryAbi>().is_err());
+        assert_eq!(decode_address(&Address::repeat_byte(0xaa).abi_encode()).unwrap(), Address::repeat_byte(0xaa));
+    }
+}
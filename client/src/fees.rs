@@ -0,0 +1,329 @@
+// EIP-1559 fee oracle and the fee/inclusion analytics behind `fee-advice`
+// Fee caps are sized from recent base-fee history, the chosen urgency and the bundler's minimum tip
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::journal::{OperationRecord, OperationStatus};
+
+/// Percentile of each block's priority fees reported by `eth_feeHistory`
+const REWARD_PERCENTILE: f64 = 50.0;
+
+/// Blocks of history behind a fee estimate for a new operation
+const ORACLE_BLOCKS: u64 = 20;
+
+/// Bundlers commonly reject priority fees below 0.1 gwei
+pub const MIN_PRIORITY_FEE: u128 = 100_000_000;
+
+/// Shortest sponsorship window recommended, leaves room for a resubmission
+pub const MIN_VALID_FOR: u64 = 5 * 60;
+
+/// Longest sponsorship window recommended, and the default without inclusion history
+pub const MAX_VALID_FOR: u64 = 60 * 60;
+
+/// Windows cover this many times the slow (p95) inclusion delay
+const VALID_FOR_HEADROOM: u64 = 4;
+
+/// How quickly an operation should be included, trading cost for speed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl Urgency {
+    pub fn name(self) -> &'static str {
+        match self {
+            Urgency::Slow => "slow",
+            Urgency::Normal => "normal",
+            Urgency::Fast => "fast",
+        }
+    }
+
+    /// Percentile of recent priority fees the tip has to match
+    fn reward_percentile(self) -> f64 {
+        match self {
+            Urgency::Slow => 10.0,
+            Urgency::Normal => 50.0,
+            Urgency::Fast => 90.0,
+        }
+    }
+
+    /// Fee cap over `base_fee`: slow absorbs two full blocks of base-fee increases, normal six, fast nine
+    pub fn max_fee(self, base_fee: u128, priority_fee: u128) -> u128 {
+        let headroom = match self {
+            Urgency::Slow => base_fee + base_fee / 4,
+            Urgency::Normal => base_fee * 2,
+            Urgency::Fast => base_fee * 3,
+        };
+        headroom + priority_fee
+    }
+}
+
+impl fmt::Display for Urgency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Urgency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "slow" => Ok(Urgency::Slow),
+            "normal" => Ok(Urgency::Normal),
+            "fast" => Ok(Urgency::Fast),
+            other => Err(anyhow::anyhow!("Unknown urgency '{}', expected slow, normal or fast", other)),
+        }
+    }
+}
+
+static URGENCY: OnceLock<Urgency> = OnceLock::new();
+
+/// Set the urgency used for fee estimates for the rest of the process
+pub fn set_urgency(urgency: Urgency) {
+    let _ = URGENCY.set(urgency);
+}
+
+/// Urgency selected with `--fee-urgency`, normal by default
+pub fn urgency() -> Urgency {
+    URGENCY.get().copied().unwrap_or_default()
+}
+
+/// Fee caps for a new UserOperation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl FeeEstimate {
+    /// Tip at `urgency` raised to the bundler minimum, with the cap sized for the next base fee
+    pub fn from_base_fee(urgency: Urgency, base_fee: u128, tip: u128, min_priority_fee: u128) -> Self {
+        let priority_fee = tip.max(min_priority_fee).max(MIN_PRIORITY_FEE);
+        Self {
+            max_fee_per_gas: U256::from(urgency.max_fee(base_fee, priority_fee)),
+            max_priority_fee_per_gas: U256::from(priority_fee),
+        }
+    }
+}
+
+/// Estimate fees at `urgency` from the last blocks and the bundler's minimum priority fee
+/// Nodes without `eth_feeHistory` fall back to `eth_gasPrice` as the base fee
+pub async fn estimate<P: Provider>(provider: &P, urgency: Urgency) -> Result<FeeEstimate> {
+    let (base_fee, tip) = match provider
+        .get_fee_history(ORACLE_BLOCKS, BlockNumberOrTag::Latest, &[urgency.reward_percentile()])
+        .await
+    {
+        Ok(history) => {
+            // The last entry is the base fee of the next block
+            let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+            let mut tips: Vec<u128> = history
+                .reward
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|block| block.first().copied())
+                .collect();
+            tips.sort_unstable();
+            (base_fee, percentile(&tips, 50).unwrap_or_default())
+        }
+        Err(_) => (provider.get_gas_price().await?, 0),
+    };
+
+    Ok(FeeEstimate::from_base_fee(urgency, base_fee, tip, bundler_min_priority_fee(provider).await))
+}
+
+/// Minimum tip the bundler accepts (`rundler_maxPriorityFeePerGas`), 0 when it does not say
+async fn bundler_min_priority_fee<P: Provider>(provider: &P) -> u128 {
+    provider
+        .raw_request::<_, U256>("rundler_maxPriorityFeePerGas".into(), Vec::<serde_json::Value>::new())
+        .await
+        .map(|fee| fee.saturating_to::<u128>())
+        .unwrap_or_default()
+}
+
+/// Base fee and priority fee distribution over recent blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeSummary {
+    pub blocks: usize,
+    pub latest_base_fee: u128,
+    pub median_base_fee: u128,
+    pub p90_base_fee: u128,
+    pub median_priority_fee: u128,
+}
+
+impl FeeSummary {
+    /// Summarize per-block base fees (oldest first, as returned by `eth_feeHistory`) and median rewards
+    pub fn from_history(base_fees: &[u128], rewards: &[u128]) -> Option<Self> {
+        let latest_base_fee = *base_fees.last()?;
+        let mut base_fees = base_fees.to_vec();
+        base_fees.sort_unstable();
+        let mut rewards = rewards.to_vec();
+        rewards.sort_unstable();
+
+        Some(Self {
+            blocks: base_fees.len(),
+            latest_base_fee,
+            median_base_fee: percentile(&base_fees, 50)?,
+            p90_base_fee: percentile(&base_fees, 90)?,
+            median_priority_fee: percentile(&rewards, 50).unwrap_or_default(),
+        })
+    }
+}
+
+/// Pull base fees and median priority fees for the last `blocks` blocks
+pub async fn fee_history<P: Provider>(provider: &P, blocks: u64) -> Result<FeeSummary> {
+    let history = provider
+        .get_fee_history(blocks, BlockNumberOrTag::Latest, &[REWARD_PERCENTILE])
+        .await?;
+    let rewards: Vec<u128> = history
+        .reward
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|block| block.first().copied())
+        .collect();
+
+    FeeSummary::from_history(&history.base_fee_per_gas, &rewards)
+        .ok_or_else(|| anyhow::anyhow!("Node returned no fee history"))
+}
+
+/// Seconds from submission to receipt for operations included on `chain_id`
+pub fn inclusion_delays(records: &[OperationRecord], chain_id: u64) -> Vec<u64> {
+    let mut delays: Vec<u64> = records
+        .iter()
+        .filter(|r| r.chain_id == chain_id && r.status == OperationStatus::Included)
+        .filter_map(|r| r.included_at.map(|at| at.saturating_sub(r.submitted_at)))
+        .collect();
+    delays.sort_unstable();
+    delays
+}
+
+/// Fee caps and sponsorship window suggested for the next operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recommendation {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    /// Sponsorship `validUntil` offset from now, in seconds
+    pub valid_for: u64,
+}
+
+/// Recommend fees from `fees` and a window from sorted inclusion `delays`
+/// The fee cap is sized at normal urgency over the higher of the latest and p90 base fee
+pub fn recommend(fees: &FeeSummary, delays: &[u64]) -> Recommendation {
+    let max_priority_fee_per_gas = fees.median_priority_fee.max(MIN_PRIORITY_FEE);
+    let base_fee = fees.latest_base_fee.max(fees.p90_base_fee);
+    let valid_for = match percentile(delays, 95) {
+        Some(slow) => (slow * VALID_FOR_HEADROOM).clamp(MIN_VALID_FOR, MAX_VALID_FOR),
+        None => MAX_VALID_FOR,
+    };
+
+    Recommendation {
+        max_fee_per_gas: Urgency::Normal.max_fee(base_fee, max_priority_fee_per_gas),
+        max_priority_fee_per_gas,
+        valid_for,
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice
+pub fn percentile<T: Copy>(sorted: &[T], pct: usize) -> Option<T> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank.min(sorted.len()) - 1])
+}
+
+/// Wei amount as gwei with up to three decimals
+pub fn format_gwei(wei: u128) -> String {
+    let gwei = wei / 1_000_000_000;
+    let milli = (wei % 1_000_000_000) / 1_000_000;
+    if milli == 0 {
+        gwei.to_string()
+    } else {
+        format!("{}.{:03}", gwei, milli).trim_end_matches('0').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Address;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    fn included(chain_id: u64, submitted_at: u64, delay: u64) -> OperationRecord {
+        let mut record = OperationRecord::pending("0x01".to_string(), chain_id, Address::ZERO, "submit", None, false);
+        record.status = OperationStatus::Included;
+        record.submitted_at = submitted_at;
+        record.included_at = Some(submitted_at + delay);
+        record
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(percentile(&values, 50), Some(5));
+        assert_eq!(percentile(&values, 90), Some(9));
+        assert_eq!(percentile(&values, 95), Some(10));
+        assert_eq!(percentile(&values, 0), Some(1));
+        assert_eq!(percentile::<u64>(&[], 50), None);
+    }
+
+    #[test]
+    fn test_inclusion_delays_only_count_included_ops_on_chain() {
+        let mut pending = included(1, 100, 30);
+        pending.status = OperationStatus::Pending;
+        pending.included_at = None;
+        let records = [included(1, 100, 40), included(1, 200, 12), included(10, 100, 5), pending];
+        assert_eq!(inclusion_delays(&records, 1), vec![12, 40]);
+    }
+
+    #[test]
+    fn test_recommendation() {
+        let fees = FeeSummary::from_history(&[10 * GWEI, 12 * GWEI, 11 * GWEI, 9 * GWEI], &[GWEI / 20]).unwrap();
+        assert_eq!(fees.latest_base_fee, 9 * GWEI);
+        assert_eq!(fees.p90_base_fee, 12 * GWEI);
+
+        let advice = recommend(&fees, &[20, 30, 240]);
+        // Tip raised to the bundler minimum
+        assert_eq!(advice.max_priority_fee_per_gas, MIN_PRIORITY_FEE);
+        assert_eq!(advice.max_fee_per_gas, 24 * GWEI + MIN_PRIORITY_FEE);
+        assert_eq!(advice.valid_for, 960);
+
+        assert_eq!(recommend(&fees, &[]).valid_for, MAX_VALID_FOR);
+        assert_eq!(recommend(&fees, &[10]).valid_for, MIN_VALID_FOR);
+    }
+
+    #[test]
+    fn test_estimate_by_urgency() {
+        let slow = FeeEstimate::from_base_fee(Urgency::Slow, 8 * GWEI, GWEI / 100, 0);
+        assert_eq!(slow.max_priority_fee_per_gas, U256::from(MIN_PRIORITY_FEE));
+        assert_eq!(slow.max_fee_per_gas, U256::from(10 * GWEI + MIN_PRIORITY_FEE));
+
+        // The bundler minimum wins over a lower market tip
+        let normal = FeeEstimate::from_base_fee(Urgency::Normal, 8 * GWEI, GWEI / 2, GWEI);
+        assert_eq!(normal.max_priority_fee_per_gas, U256::from(GWEI));
+        assert_eq!(normal.max_fee_per_gas, U256::from(17 * GWEI));
+
+        let fast = FeeEstimate::from_base_fee(Urgency::Fast, 8 * GWEI, 2 * GWEI, GWEI);
+        assert_eq!(fast.max_fee_per_gas, U256::from(26 * GWEI));
+
+        assert_eq!("FAST".parse::<Urgency>().unwrap(), Urgency::Fast);
+        assert!("urgent".parse::<Urgency>().is_err());
+    }
+
+    #[test]
+    fn test_format_gwei() {
+        assert_eq!(format_gwei(5 * GWEI), "5");
+        assert_eq!(format_gwei(GWEI / 5), "0.2");
+        assert_eq!(format_gwei(1_234_567_890), "1.234");
+    }
+}
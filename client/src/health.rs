@@ -0,0 +1,165 @@
+// Per-endpoint health of the configured bundlers, used to order failover
+// Persisted under ~/.aa-client so an endpoint that just failed is not retried first by the next invocation
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::clock::now_secs;
+use crate::config::data_dir;
+
+/// File name of the health records inside the client data directory
+const HEALTH_FILE: &str = "bundler-health.json";
+
+/// Consecutive failures after which an endpoint is considered down
+pub const DOWN_AFTER_FAILURES: u32 = 3;
+
+/// How long a down endpoint is skipped before it gets another chance
+pub const COOLDOWN_SECS: u64 = 5 * 60;
+
+/// Outcome counters and last observation for one endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last recorded outcome
+    pub last_checked: u64,
+}
+
+impl EndpointHealth {
+    /// Whether the endpoint failed repeatedly and is still cooling down at `now`
+    pub fn is_down(&self, now: u64) -> bool {
+        self.consecutive_failures >= DOWN_AFTER_FAILURES && now < self.last_checked + COOLDOWN_SECS
+    }
+}
+
+/// Health records keyed by endpoint URL, optionally persisted to disk
+pub struct HealthStore {
+    path: Option<PathBuf>,
+    endpoints: Mutex<HashMap<String, EndpointHealth>>,
+}
+
+impl HealthStore {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store persisted at `~/.aa-client/bundler-health.json`, in memory when the data directory is unavailable
+    pub fn persistent() -> Self {
+        match data_dir() {
+            Ok(dir) => Self::at_path(dir.join(HEALTH_FILE)),
+            Err(_) => Self::in_memory(),
+        }
+    }
+
+    pub fn at_path(path: PathBuf) -> Self {
+        let endpoints = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            endpoints: Mutex::new(endpoints),
+        }
+    }
+
+    pub fn get(&self, endpoint: &str) -> EndpointHealth {
+        self.endpoints.lock().unwrap().get(endpoint).cloned().unwrap_or_default()
+    }
+
+    pub fn record_success(&self, endpoint: &str, latency_ms: u64) {
+        self.update(endpoint, |health| {
+            health.successes += 1;
+            health.consecutive_failures = 0;
+            health.last_latency_ms = Some(latency_ms);
+            health.last_error = None;
+        });
+    }
+
+    pub fn record_failure(&self, endpoint: &str, error: &str) {
+        self.update(endpoint, |health| {
+            health.failures += 1;
+            health.consecutive_failures += 1;
+            health.last_error = Some(error.to_string());
+        });
+    }
+
+    /// Endpoints in failover order: configured order, with endpoints that are down moved last
+    pub fn order<'a>(&self, endpoints: &'a [String], now: u64) -> Vec<&'a str> {
+        let (down, up): (Vec<&String>, Vec<&String>) =
+            endpoints.iter().partition(|endpoint| self.get(endpoint).is_down(now));
+        up.into_iter().chain(down).map(String::as_str).collect()
+    }
+
+    fn update(&self, endpoint: &str, apply: impl FnOnce(&mut EndpointHealth)) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let health = endpoints.entry(endpoint.to_string()).or_default();
+        apply(health);
+        health.last_checked = now_secs();
+
+        if let Some(path) = &self.path {
+            // Best effort, losing a record only makes the next failover order less informed
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(contents) = serde_json::to_string(&*endpoints) {
+                let _ = std::fs::write(path, contents);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_goes_down_after_repeated_failures() {
+        let store = HealthStore::in_memory();
+        for _ in 0..DOWN_AFTER_FAILURES {
+            store.record_failure("http://primary", "timeout");
+        }
+        let health = store.get("http://primary");
+        assert_eq!(health.failures, DOWN_AFTER_FAILURES as u64);
+        assert!(health.is_down(now_secs()));
+        assert!(!health.is_down(health.last_checked + COOLDOWN_SECS));
+
+        store.record_success("http://primary", 42);
+        let health = store.get("http://primary");
+        assert!(!health.is_down(now_secs()));
+        assert_eq!(health.last_latency_ms, Some(42));
+        assert_eq!(health.last_error, None);
+    }
+
+    #[test]
+    fn test_order_moves_down_endpoints_last() {
+        let store = HealthStore::in_memory();
+        let endpoints = vec!["http://a".to_string(), "http://b".to_string(), "http://c".to_string()];
+        assert_eq!(store.order(&endpoints, now_secs()), vec!["http://a", "http://b", "http://c"]);
+
+        for _ in 0..DOWN_AFTER_FAILURES {
+            store.record_failure("http://a", "connection refused");
+        }
+        store.record_failure("http://b", "connection refused");
+        assert_eq!(store.order(&endpoints, now_secs()), vec!["http://b", "http://c", "http://a"]);
+    }
+
+    #[test]
+    fn test_persistent_round_trip() {
+        let path = std::env::temp_dir().join(format!("aa-client-health-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        HealthStore::at_path(path.clone()).record_failure("http://a", "timeout");
+        assert_eq!(HealthStore::at_path(path.clone()).get("http://a").failures, 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
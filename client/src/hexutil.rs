@@ -0,0 +1,163 @@
+// Strict hex parsing shared by every input: salts, call data, keys and signatures
+// One optional 0x prefix and an even number of digits; fixed-size values are never silently truncated.
+// Errors name the input but never echo it, the value may be a private key
+
+use alloy::primitives::{Bytes, B256, U256};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HexError {
+    #[error("Invalid {what}: odd number of hex digits ({digits})")]
+    OddLength { what: &'static str, digits: usize },
+
+    #[error("Invalid {what}: '{found}' at position {position} is not a hex digit")]
+    InvalidDigit { what: &'static str, found: char, position: usize },
+
+    #[error("Invalid {what}: {len} bytes, at most {max} fit")]
+    Overflow { what: &'static str, len: usize, max: usize },
+
+    #[error("Invalid {what}: {len} bytes, expected {expected}")]
+    WrongLength { what: &'static str, len: usize, expected: usize },
+}
+
+/// `input` without surrounding whitespace and its 0x / 0X prefix
+pub fn strip_prefix(input: &str) -> &str {
+    let input = input.trim();
+    input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")).unwrap_or(input)
+}
+
+/// Decode hex of any length, empty for "" or "0x"
+pub fn decode(what: &'static str, input: &str) -> Result<Vec<u8>, HexError> {
+    let digits = strip_prefix(input);
+    if let Some((position, found)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidDigit { what, found, position });
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(HexError::OddLength { what, digits: digits.len() });
+    }
+    Ok(hex::decode(digits).expect("validated hex digits"))
+}
+
+pub fn decode_bytes(what: &'static str, input: &str) -> Result<Bytes, HexError> {
+    decode(what, input).map(Bytes::from)
+}
+
+/// Decode exactly `N` bytes, e.g. a 32-byte private key or a 65-byte signature
+pub fn decode_fixed<const N: usize>(what: &'static str, input: &str) -> Result<[u8; N], HexError> {
+    let bytes = decode(what, input)?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| HexError::WrongLength { what, len: bytes.len(), expected: N })
+}
+
+/// Decode up to 32 bytes right-aligned into a word, as Solidity reads a short uint
+pub fn decode_word(what: &'static str, input: &str) -> Result<B256, HexError> {
+    let bytes = decode(what, input)?;
+    if bytes.len() > 32 {
+        return Err(HexError::Overflow { what, len: bytes.len(), max: 32 });
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(B256::from(word))
+}
+
+/// Salt as the factory's uint256: `0x01` and `0x0001` are the same salt, 33 bytes are an error
+pub fn parse_salt(input: &str) -> Result<U256, HexError> {
+    decode_word("salt", input).map(|word| U256::from_be_bytes(word.0))
+}
+
+/// Salt as printed everywhere: 0x and all 64 digits, so predict and deploy show the same text
+pub fn format_salt(salt: U256) -> String {
+    format!("{:#066x}", salt)
+}
+
+/// Fresh 32-byte salt for `--salt-random`, reproducible under `--seed`
+pub fn random_salt() -> anyhow::Result<U256> {
+    let mut bytes = [0u8; 32];
+    crate::clock::random_bytes(&mut bytes)?;
+    Ok(U256::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_prefix_and_case() {
+        assert_eq!(decode("data", "0xdeAD").unwrap(), vec![0xde, 0xad]);
+        assert_eq!(decode("data", "0XDEAD").unwrap(), vec![0xde, 0xad]);
+        assert_eq!(decode("data", " dead\n").unwrap(), vec![0xde, 0xad]);
+        assert!(decode("data", "0x").unwrap().is_empty());
+        assert!(decode("data", "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert_eq!(decode("salt", "0xabc"), Err(HexError::OddLength { what: "salt", digits: 3 }));
+        assert_eq!(decode("salt", "0xzz"), Err(HexError::InvalidDigit { what: "salt", found: 'z', position: 0 }));
+        // A second prefix is not hex
+        assert!(decode("salt", "0x0x01").is_err());
+    }
+
+    #[test]
+    fn test_fixed_and_word_sizes() {
+        assert_eq!(decode_fixed::<2>("key", "0x0102").unwrap(), [1, 2]);
+        assert_eq!(decode_fixed::<2>("key", "0x01"), Err(HexError::WrongLength { what: "key", len: 1, expected: 2 }));
+
+        assert_eq!(parse_salt("0x01").unwrap(), U256::from(1));
+        assert_eq!(parse_salt("0x0001").unwrap(), U256::from(1));
+        assert_eq!(parse_salt(&"ff".repeat(32)).unwrap(), U256::MAX);
+        // Silently dropping the 33rd byte would deploy a different account
+        assert_eq!(parse_salt(&"01".repeat(33)), Err(HexError::Overflow { what: "salt", len: 33, max: 32 }));
+
+        let formatted = format_salt(U256::from(1));
+        assert_eq!(formatted, format!("0x{}01", "0".repeat(62)));
+        assert_eq!(parse_salt(&formatted).unwrap(), U256::from(1));
+        let random = random_salt().unwrap();
+        assert_eq!(parse_salt(&format_salt(random)).unwrap(), random);
+    }
+
+    #[test]
+    fn test_errors_do_not_echo_input() {
+        let secret = format!("0x{}g", "ab".repeat(31));
+        let message = decode_fixed::<32>("private key", &secret).unwrap_err().to_string();
+        assert!(!message.contains("abab"));
+        assert!(message.contains("private key"));
+    }
+
+    proptest! {
+        // Any text a user can pass: either an error or the bytes its digits spell, never a panic
+        #[test]
+        fn decode_accepts_exactly_even_hex(input in "\\PC{0,80}") {
+            let digits = strip_prefix(&input);
+            let valid = digits.chars().all(|c| c.is_ascii_hexdigit()) && digits.len().is_multiple_of(2);
+            match decode("data", &input) {
+                Ok(bytes) => {
+                    prop_assert!(valid);
+                    prop_assert_eq!(hex::encode(bytes), digits.to_ascii_lowercase());
+                }
+                Err(_) => prop_assert!(!valid),
+            }
+            let _ = decode_fixed::<32>("key", &input);
+            let _ = decode_word("word", &input);
+        }
+
+        #[test]
+        fn decode_round_trips(bytes in prop::collection::vec(any::<u8>(), 0..80), upper: bool, prefix: bool) {
+            let digits = if upper { hex::encode_upper(&bytes) } else { hex::encode(&bytes) };
+            let input = format!("{}{}", if prefix { "0x" } else { "" }, digits);
+            prop_assert_eq!(decode("data", &input).unwrap(), bytes.clone());
+
+            // Short words are right-aligned like a Solidity uint, longer ones are refused
+            match decode_word("word", &input) {
+                Ok(word) => prop_assert_eq!(U256::from_be_bytes(word.0), U256::from_be_slice(&bytes)),
+                Err(e) => prop_assert_eq!(e, HexError::Overflow { what: "word", len: bytes.len(), max: 32 }),
+            }
+        }
+
+        #[test]
+        fn salt_round_trips(salt in any::<[u8; 32]>()) {
+            let salt = U256::from_be_bytes(salt);
+            prop_assert_eq!(parse_salt(&format_salt(salt)).unwrap(), salt);
+        }
+    }
+}
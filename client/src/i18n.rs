@@ -0,0 +1,163 @@
+// Localized user-facing CLI output backed by Fluent catalogs embedded at build time
+// Messages missing from a locale fall back to English, then to the message id itself
+
+use anyhow::Result;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Locales shipped with the CLI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Es,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Es];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Es => "es",
+        }
+    }
+
+    fn catalog(self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.ftl"),
+            Lang::Es => include_str!("../locales/es.ftl"),
+        }
+    }
+
+    /// Language from the usual locale variables (`LC_ALL`, `LC_MESSAGES`, `LANG`), English when unset or unsupported
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(Lang::En)
+    }
+}
+
+impl FromStr for Lang {
+    type Err = anyhow::Error;
+
+    /// Accepts a bare code (`es`) or a POSIX locale (`es_MX.UTF-8`)
+    fn from_str(s: &str) -> Result<Self> {
+        let code = s.split(['_', '-', '.']).next().unwrap_or_default().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code() == code)
+            .ok_or_else(|| {
+                let available: Vec<&str> = Self::ALL.iter().map(|lang| lang.code()).collect();
+                anyhow::anyhow!("Unsupported language '{}', available: {}", s, available.join(", "))
+            })
+    }
+}
+
+/// Formats messages in one locale with an English fallback
+pub struct Localizer {
+    bundle: FluentBundle<FluentResource>,
+    fallback: Option<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    pub fn new(lang: Lang) -> Self {
+        Self {
+            bundle: bundle(lang),
+            fallback: (lang != Lang::En).then(|| bundle(Lang::En)),
+        }
+    }
+
+    pub fn format(&self, id: &str, args: &[(&str, String)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, FluentValue::from(value.clone()));
+        }
+
+        for bundle in std::iter::once(&self.bundle).chain(self.fallback.as_ref()) {
+            if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+                let mut errors = Vec::new();
+                return bundle.format_pattern(pattern, Some(&fluent_args), &mut errors).into_owned();
+            }
+        }
+        id.to_string()
+    }
+}
+
+fn bundle(lang: Lang) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(lang.catalog().to_string())
+        .unwrap_or_else(|(_, errors)| panic!("Invalid {} catalog: {:?}", lang.code(), errors));
+    let locale = lang.code().parse().expect("locale codes are valid language identifiers");
+    let mut bundle = FluentBundle::new_concurrent(vec![locale]);
+    // Bidi isolation marks render as stray characters in most terminals
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).expect("catalog ids are unique");
+    bundle
+}
+
+static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+
+/// Select the output language, effective for the rest of the process
+pub fn init(lang: Lang) {
+    let _ = LOCALIZER.set(Localizer::new(lang));
+}
+
+/// Format a message in the selected language, English when `init` was never called
+pub fn tr(id: &str, args: &[(&str, String)]) -> String {
+    LOCALIZER.get_or_init(|| Localizer::new(Lang::En)).format(id, args)
+}
+
+/// `t!("message-id")` or `t!("message-id", name = value, ...)`, values are formatted with `Display`
+#[macro_export]
+macro_rules! t {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::tr($id, &[$((stringify!($name), ($value).to_string())),*])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_ids(lang: Lang) -> Vec<&'static str> {
+        let mut ids: Vec<&str> = lang
+            .catalog()
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with(' '))
+            .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_catalogs_define_the_same_messages() {
+        let english = message_ids(Lang::En);
+        assert!(!english.is_empty());
+        for lang in Lang::ALL {
+            assert_eq!(message_ids(lang), english, "{} catalog is out of sync with en", lang.code());
+        }
+    }
+
+    #[test]
+    fn test_format_messages() {
+        let spanish = Localizer::new(Lang::Es);
+        assert_eq!(
+            spanish.format("label-userop-hash", &[("hash", "0xabc".to_string())]),
+            "Hash de la UserOperation: 0xabc"
+        );
+        assert_eq!(spanish.format("no-such-message", &[]), "no-such-message");
+        assert_eq!(Localizer::new(Lang::En).format("possible-causes", &[]), "Possible causes:");
+    }
+
+    #[test]
+    fn test_lang_parsing() {
+        assert_eq!("es_MX.UTF-8".parse::<Lang>().unwrap(), Lang::Es);
+        assert_eq!("EN".parse::<Lang>().unwrap(), Lang::En);
+        assert!("fr".parse::<Lang>().is_err());
+    }
+}
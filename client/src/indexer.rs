@@ -0,0 +1,232 @@
+// Local index of the accounts a factory created, for finding the accounts an owner controls without their salts
+// Built from the factory's AccountCreated and AccountCreatedWithOwners logs: EntryPoint's AccountDeployed
+// names neither owners nor salt. Stored at ~/.aa-client/accounts.json, each sync resumes after the last block
+
+use alloy::primitives::{Address, B256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{Filter, Log};
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::data_dir;
+use crate::out;
+
+sol! {
+    event AccountCreated(address indexed account, address indexed owner, uint256 salt);
+    event AccountCreatedWithOwners(address indexed account, address[] owners, uint256 salt);
+}
+
+/// File name of the index inside the client data directory
+const INDEX_FILE: &str = "accounts.json";
+
+/// Blocks asked for per eth_getLogs, within what public nodes accept
+pub const MAX_BLOCK_RANGE: u64 = 2_000;
+
+/// Blocks scanned back from the head on a factory's first sync without `--from-block`
+pub const DEFAULT_LOOKBACK_BLOCKS: u64 = 50_000;
+
+/// One account and the owners it was created with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedAccount {
+    pub account: Address,
+    pub owners: Vec<Address>,
+    pub salt: B256,
+    /// Creation block, 0 for an account recorded when this client deployed it and not synced since
+    pub block: u64,
+}
+
+/// Accounts of one factory on one chain, and where the next sync starts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FactoryIndex {
+    pub next_block: u64,
+    pub accounts: Vec<IndexedAccount>,
+}
+
+impl FactoryIndex {
+    /// Add accounts from a scan, keeping the earliest record of an account seen twice
+    /// A record from a deployment gives way to the creation log once a sync finds it
+    pub fn extend(&mut self, accounts: impl IntoIterator<Item = IndexedAccount>) {
+        for account in accounts {
+            match self.accounts.iter_mut().find(|known| known.account == account.account) {
+                Some(known) if known.block == 0 => *known = account,
+                Some(_) => {}
+                None => self.accounts.push(account),
+            }
+        }
+    }
+
+    /// Accounts `owner` was an initial owner of, oldest first
+    pub fn owned_by(&self, owner: Address) -> Vec<&IndexedAccount> {
+        let mut accounts: Vec<_> = self.accounts.iter().filter(|account| account.owners.contains(&owner)).collect();
+        accounts.sort_by_key(|account| account.block);
+        accounts
+    }
+}
+
+/// Index entry key: chain and factory
+fn key(chain_id: u64, factory: Address) -> String {
+    format!("{}:{}", chain_id, factory)
+}
+
+/// JSON file backed index of every synced factory
+pub struct AccountIndex {
+    path: PathBuf,
+}
+
+impl AccountIndex {
+    /// Open the index at `~/.aa-client/accounts.json`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(data_dir()?.join(INDEX_FILE)))
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load_all(&self) -> Result<BTreeMap<String, FactoryIndex>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let contents = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&contents).map_err(|e| anyhow::anyhow!("Corrupt account index {}: {}", self.path.display(), e))
+    }
+
+    /// Index of `factory` on `chain_id`, empty before its first sync
+    pub fn load(&self, chain_id: u64, factory: Address) -> Result<FactoryIndex> {
+        Ok(self.load_all()?.remove(&key(chain_id, factory)).unwrap_or_default())
+    }
+
+    /// Record an account deployed from this client with the salt it used, ahead of any sync
+    pub fn record_deployment(&self, chain_id: u64, factory: Address, account: Address, owners: Vec<Address>, salt: B256) -> Result<()> {
+        let mut index = self.load(chain_id, factory)?;
+        index.extend([IndexedAccount { account, owners, salt, block: 0 }]);
+        self.save(chain_id, factory, index)
+    }
+
+    pub fn save(&self, chain_id: u64, factory: Address, index: FactoryIndex) -> Result<()> {
+        let mut all = self.load_all()?;
+        all.insert(key(chain_id, factory), index);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&all)?)?;
+        Ok(())
+    }
+}
+
+/// Account a factory creation log describes, None for other logs
+pub fn decode(log: &Log) -> Option<IndexedAccount> {
+    let block = log.block_number?;
+    let topic0 = *log.topic0()?;
+    if topic0 == AccountCreated::SIGNATURE_HASH {
+        let event = AccountCreated::decode_log(&log.inner, true).ok()?;
+        Some(IndexedAccount { account: event.account, owners: vec![event.owner], salt: event.salt.into(), block })
+    } else if topic0 == AccountCreatedWithOwners::SIGNATURE_HASH {
+        let event = AccountCreatedWithOwners::decode_log(&log.inner, true).ok()?;
+        Some(IndexedAccount { account: event.account, owners: event.owners.clone(), salt: event.salt.into(), block })
+    } else {
+        None
+    }
+}
+
+/// Scan `factory`'s creation logs up to the head into `index`, returning how many accounts were new
+/// The first sync starts at `from_block`, or `DEFAULT_LOOKBACK_BLOCKS` back from the head
+pub async fn sync<P: Provider>(provider: &P, factory: Address, index: &mut FactoryIndex, from_block: Option<u64>) -> Result<usize> {
+    let head = provider.get_block_number().await?;
+    let mut start = match (index.next_block, from_block) {
+        (_, Some(from_block)) => from_block,
+        (0, None) => head.saturating_sub(DEFAULT_LOOKBACK_BLOCKS),
+        (next_block, None) => next_block,
+    };
+    let known = index.accounts.len();
+
+    while start <= head {
+        let end = (start + MAX_BLOCK_RANGE - 1).min(head);
+        out!("🔎 Scanning blocks {}..={} of {}", start, end, factory);
+        let filter = Filter::new()
+            .address(factory)
+            .event_signature(vec![AccountCreated::SIGNATURE_HASH, AccountCreatedWithOwners::SIGNATURE_HASH])
+            .from_block(start)
+            .to_block(end);
+        let logs = provider.get_logs(&filter).await?;
+        index.extend(logs.iter().filter_map(decode));
+        // Recorded per range, so an interrupted sync resumes where it stopped
+        index.next_block = index.next_block.max(end + 1);
+        start = end + 1;
+    }
+    Ok(index.accounts.len() - known)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{LogData, U256};
+
+    fn log(data: LogData, block: u64) -> Log {
+        Log {
+            inner: alloy::primitives::Log { address: Address::repeat_byte(0xfa), data },
+            block_number: Some(block),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_creation_logs() {
+        let (account, owner) = (Address::repeat_byte(0xaa), Address::repeat_byte(0x01));
+        let single = AccountCreated { account, owner, salt: U256::from(7) }.encode_log_data();
+        let decoded = decode(&log(single, 100)).unwrap();
+        assert_eq!(decoded.owners, vec![owner]);
+        assert_eq!(decoded.salt, B256::from(U256::from(7)));
+        assert_eq!(decoded.block, 100);
+
+        let owners = vec![owner, Address::repeat_byte(0x02)];
+        let multi = AccountCreatedWithOwners { account, owners: owners.clone(), salt: U256::ZERO }.encode_log_data();
+        assert_eq!(decode(&log(multi, 101)).unwrap().owners, owners);
+
+        let other = LogData::new_unchecked(vec![B256::repeat_byte(0x55)], Default::default());
+        assert_eq!(decode(&log(other, 102)), None);
+    }
+
+    #[test]
+    fn test_owned_by_and_resume() {
+        let owner = Address::repeat_byte(0x01);
+        let indexed = |account: u8, owners: Vec<Address>, block: u64| IndexedAccount {
+            account: Address::repeat_byte(account),
+            owners,
+            salt: B256::ZERO,
+            block,
+        };
+        let mut index = FactoryIndex::default();
+        index.extend([indexed(0xbb, vec![owner], 20), indexed(0xcc, vec![Address::repeat_byte(0x02)], 10)]);
+        index.extend([indexed(0xaa, vec![Address::repeat_byte(0x02), owner], 5), indexed(0xbb, vec![owner], 30)]);
+        index.next_block = 31;
+
+        let owned: Vec<_> = index.owned_by(owner).iter().map(|account| account.account).collect();
+        assert_eq!(owned, vec![Address::repeat_byte(0xaa), Address::repeat_byte(0xbb)]);
+
+        let path = std::env::temp_dir().join(format!("aa-client-accounts-{}.json", std::process::id()));
+        let store = AccountIndex::open(&path);
+        assert_eq!(store.load(1, Address::ZERO).unwrap(), FactoryIndex::default());
+        store.save(1, Address::ZERO, index.clone()).unwrap();
+        assert_eq!(store.load(1, Address::ZERO).unwrap(), index);
+        assert_eq!(store.load(2, Address::ZERO).unwrap(), FactoryIndex::default());
+
+        // A deployment is recorded with its salt until the sync reaches its creation block
+        let deployed = Address::repeat_byte(0xdd);
+        store.record_deployment(2, Address::ZERO, deployed, vec![owner], B256::repeat_byte(0x07)).unwrap();
+        let mut synced = store.load(2, Address::ZERO).unwrap();
+        assert_eq!(synced.owned_by(owner)[0].salt, B256::repeat_byte(0x07));
+        synced.extend([indexed(0xdd, vec![owner], 40)]);
+        assert_eq!(synced.accounts.len(), 1);
+        assert_eq!(synced.accounts[0].block, 40);
+        let _ = std::fs::remove_file(path);
+    }
+}
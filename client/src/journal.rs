@@ -0,0 +1,717 @@
+// Local operations journal
+// Persists every submitted UserOperation so it can be tracked and reported on later
+
+use alloy::primitives::{Address, U256};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::clock::now_secs;
+use crate::config::data_dir;
+use crate::multisig::UnsignedUserOperation;
+
+/// File name of the journal inside the client data directory
+const JOURNAL_FILE: &str = "journal.json";
+
+/// Lifecycle state of a journaled operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    Included,
+    Failed,
+    /// Superseded by a copy re-signed with higher fees, see `replaced_by`
+    Replaced,
+}
+
+/// A single UserOperation recorded by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationRecord {
+    /// Hash returned by the bundler
+    pub user_op_hash: String,
+    /// Chain the operation was submitted to
+    pub chain_id: u64,
+    /// Smart account that sent the operation
+    pub sender: Address,
+    /// Command that produced the operation (submit, deploy-account, ...)
+    pub kind: String,
+    /// Optional user-supplied tag for cost attribution
+    pub tag: Option<String>,
+    /// Whether gas was paid by a paymaster
+    pub sponsored: bool,
+    pub status: OperationStatus,
+    /// Gas used as reported by the receipt, once known
+    pub actual_gas_used: Option<U256>,
+    /// Gas cost in wei as reported by the receipt, once known
+    pub actual_gas_cost: Option<U256>,
+    /// Unix timestamp of submission
+    pub submitted_at: u64,
+    /// Unix timestamp at which the receipt was recorded, once known
+    #[serde(default)]
+    pub included_at: Option<u64>,
+    /// The filled operation as submitted, kept so `watch` can re-sign it with higher fees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_op: Option<UnsignedUserOperation>,
+    /// Hash of the fee-bumped operation that replaced this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+}
+
+impl OperationRecord {
+    /// Create a pending record for a freshly submitted operation
+    pub fn pending(user_op_hash: String, chain_id: u64, sender: Address, kind: &str, tag: Option<String>, sponsored: bool) -> Self {
+        let submitted_at = now_secs();
+
+        Self {
+            user_op_hash,
+            chain_id,
+            sender,
+            kind: kind.to_string(),
+            tag,
+            sponsored,
+            status: OperationStatus::Pending,
+            actual_gas_used: None,
+            actual_gas_cost: None,
+            submitted_at,
+            included_at: None,
+            user_op: None,
+            replaced_by: None,
+        }
+    }
+}
+
+/// JSON file backed store of submitted operations
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Open the journal at `~/.aa-client/journal.json`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(data_dir()?.join(JOURNAL_FILE)))
+    }
+
+    /// Open a journal stored at an explicit path
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load all records, returning an empty list when the journal does not exist yet
+    pub fn load(&self) -> Result<Vec<OperationRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(&self.path)?;
+        if contents.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Corrupt journal {}: {}", self.path.display(), e))
+    }
+
+    /// Apply `change` to the records on disk and swap the result in
+    /// The read-modify-write runs under an exclusive lock so `watch --daemon` and a concurrent `submit`
+    /// keep each other's records, and the rename means a reader never sees a half-written journal
+    fn modify<T>(&self, change: impl FnOnce(&mut Vec<OperationRecord>) -> Result<T>) -> Result<T> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))?;
+        // Released when `lock` is dropped
+        lock.lock()?;
+
+        let mut records = self.load()?;
+        let changed = change(&mut records)?;
+
+        let tmp = self.path.with_extension(format!("tmp.{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string_pretty(&records)?)?;
+        if let Err(e) = std::fs::rename(&tmp, &self.path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(changed)
+    }
+
+    /// Append a new record
+    pub fn append(&self, record: OperationRecord) -> Result<()> {
+        self.modify(|records| {
+            records.push(record);
+            Ok(())
+        })
+    }
+
+    /// Update a record with the outcome reported by its receipt
+    pub fn record_receipt(&self, user_op_hash: &str, success: bool, gas_used: U256, gas_cost: U256) -> Result<()> {
+        self.update(user_op_hash, |record| {
+            record.status = if success { OperationStatus::Included } else { OperationStatus::Failed };
+            record.actual_gas_used = Some(gas_used);
+            record.actual_gas_cost = Some(gas_cost);
+            record.included_at = Some(now_secs());
+        })
+    }
+
+    /// Mark an operation that can never be included as failed, without a receipt
+    pub fn mark_failed(&self, user_op_hash: &str) -> Result<()> {
+        self.update(user_op_hash, |record| record.status = OperationStatus::Failed)
+    }
+
+    /// Keep the submitted operation with its record
+    pub fn attach_user_op(&self, user_op_hash: &str, user_op: UnsignedUserOperation) -> Result<()> {
+        self.update(user_op_hash, |record| record.user_op = Some(user_op))
+    }
+
+    /// Mark an operation superseded by its fee-bumped copy `replacement`
+    pub fn mark_replaced(&self, user_op_hash: &str, replacement: &str) -> Result<()> {
+        self.update(user_op_hash, |record| {
+            record.status = OperationStatus::Replaced;
+            record.replaced_by = Some(replacement.to_string());
+        })
+    }
+
+    /// Merge records exported from another journal, matched by hash
+    /// A known operation takes the imported copy only when that one has progressed past pending,
+    /// and keeps its own submitted operation when the imported copy has none
+    pub fn import(&self, imported: Vec<OperationRecord>) -> Result<ImportSummary> {
+        self.modify(|records| Ok(merge(records, imported)))
+    }
+
+    fn update(&self, user_op_hash: &str, apply: impl FnOnce(&mut OperationRecord)) -> Result<()> {
+        self.modify(|records| {
+            let record = records
+                .iter_mut()
+                .find(|r| r.user_op_hash == user_op_hash)
+                .ok_or_else(|| anyhow::anyhow!("Operation {} not found in journal", user_op_hash))?;
+            apply(record);
+            Ok(())
+        })
+    }
+}
+
+/// Merge `imported` into `records` as `Journal::import` describes
+fn merge(records: &mut Vec<OperationRecord>, imported: Vec<OperationRecord>) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    for mut record in imported {
+        match records.iter_mut().find(|r| r.user_op_hash == record.user_op_hash) {
+            None => {
+                records.push(record);
+                summary.added += 1;
+            }
+            Some(known) if known.status == OperationStatus::Pending && record.status != OperationStatus::Pending => {
+                if record.user_op.is_none() {
+                    record.user_op = known.user_op.take();
+                }
+                *known = record;
+                summary.updated += 1;
+            }
+            Some(known) => {
+                if known.user_op.is_none() && record.user_op.is_some() {
+                    known.user_op = record.user_op;
+                    summary.updated += 1;
+                } else {
+                    summary.unchanged += 1;
+                }
+            }
+        }
+    }
+    summary
+}
+
+/// What `Journal::import` did with each imported record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// File format of `ops export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Every field, including the submitted operation, and the only format `ops import` reads
+    Json,
+    /// One row per operation without the submitted operation, for spreadsheets and accounting
+    Csv,
+}
+
+impl ExportFormat {
+    /// Format implied by a file name: CSV for `.csv`, JSON otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ExportFormat::Csv,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Json => write!(f, "json"),
+            ExportFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(anyhow::anyhow!("Unknown export format '{}', expected json or csv", s)),
+        }
+    }
+}
+
+/// Columns of the CSV export, in order
+pub const CSV_COLUMNS: [&str; 12] = [
+    "user_op_hash",
+    "chain_id",
+    "sender",
+    "kind",
+    "tag",
+    "sponsored",
+    "status",
+    "actual_gas_used",
+    "actual_gas_cost",
+    "submitted_at",
+    "included_at",
+    "replaced_by",
+];
+
+/// Render `records` in `format`
+pub fn export(records: &[OperationRecord], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(records)?),
+        ExportFormat::Csv => Ok(export_csv(records)),
+    }
+}
+
+fn export_csv(records: &[OperationRecord]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for record in records {
+        let status = match record.status {
+            OperationStatus::Pending => "pending",
+            OperationStatus::Included => "included",
+            OperationStatus::Failed => "failed",
+            OperationStatus::Replaced => "replaced",
+        };
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let row = [
+            record.user_op_hash.clone(),
+            record.chain_id.to_string(),
+            record.sender.to_string(),
+            record.kind.clone(),
+            optional(record.tag.clone()),
+            record.sponsored.to_string(),
+            status.to_string(),
+            optional(record.actual_gas_used.map(|gas| gas.to_string())),
+            optional(record.actual_gas_cost.map(|cost| cost.to_string())),
+            record.submitted_at.to_string(),
+            optional(record.included_at.map(|at| at.to_string())),
+            optional(record.replaced_by.clone()),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field holding a separator, quote or line break (tags are free text)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Records of a JSON export, as read by `ops import`
+pub fn parse_export(contents: &str) -> Result<Vec<OperationRecord>> {
+    serde_json::from_str(contents).map_err(|e| anyhow::anyhow!("Not a JSON journal export: {}", e))
+}
+
+/// What `ops report` groups operations by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportBy {
+    /// The `--label` (`--tag`) given at submission
+    #[default]
+    Label,
+    /// The smart account that sent the operation
+    Account,
+    Chain,
+    /// The command that produced the operation
+    Kind,
+}
+
+impl std::fmt::Display for ReportBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportBy::Label => write!(f, "label"),
+            ReportBy::Account => write!(f, "account"),
+            ReportBy::Chain => write!(f, "chain"),
+            ReportBy::Kind => write!(f, "kind"),
+        }
+    }
+}
+
+impl FromStr for ReportBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "label" | "tag" => Ok(ReportBy::Label),
+            "account" | "sender" => Ok(ReportBy::Account),
+            "chain" => Ok(ReportBy::Chain),
+            "kind" => Ok(ReportBy::Kind),
+            _ => Err(anyhow::anyhow!("Unknown report grouping '{}', expected label, account, chain or kind", s)),
+        }
+    }
+}
+
+/// Time buckets of `ops report`, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportPeriod {
+    /// One bucket for the whole journal
+    #[default]
+    All,
+    Day,
+    Month,
+}
+
+impl std::fmt::Display for ReportPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportPeriod::All => write!(f, "all"),
+            ReportPeriod::Day => write!(f, "day"),
+            ReportPeriod::Month => write!(f, "month"),
+        }
+    }
+}
+
+impl FromStr for ReportPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(ReportPeriod::All),
+            "day" => Ok(ReportPeriod::Day),
+            "month" => Ok(ReportPeriod::Month),
+            _ => Err(anyhow::anyhow!("Unknown report period '{}', expected all, day or month", s)),
+        }
+    }
+}
+
+impl ReportPeriod {
+    /// Bucket of a unix timestamp: `2024-03` by month, `2024-03-09` by day, None for the whole journal
+    pub fn bucket(&self, timestamp: u64) -> Option<String> {
+        let (year, month, day) = civil_date(timestamp);
+        match self {
+            ReportPeriod::All => None,
+            ReportPeriod::Day => Some(format!("{:04}-{:02}-{:02}", year, month, day)),
+            ReportPeriod::Month => Some(format!("{:04}-{:02}", year, month)),
+        }
+    }
+}
+
+/// UTC calendar date of a unix timestamp (Howard Hinnant's days-to-civil)
+fn civil_date(timestamp: u64) -> (u64, u64, u64) {
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Aggregated gas usage for one group in one period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasReportRow {
+    /// Period bucket, None when the report covers the whole journal
+    pub period: Option<String>,
+    /// Label, account, chain or kind; "(untagged)" for operations without a label
+    pub group: String,
+    pub operations: usize,
+    pub sponsored_operations: usize,
+    /// Operations whose receipt has not been recorded yet
+    pub pending_operations: usize,
+    pub total_gas_used: U256,
+    pub total_gas_cost: U256,
+    /// Part of the gas cost a paymaster paid rather than the accounts
+    pub sponsored_gas_cost: U256,
+}
+
+/// Label used in reports for operations submitted without a tag
+pub const UNTAGGED: &str = "(untagged)";
+
+/// Aggregate gas usage and cost per group and period, sorted by period then group
+/// Operations count in the period they were included in, pending ones in the one they were submitted in
+pub fn gas_report(records: &[OperationRecord], by: ReportBy, period: ReportPeriod) -> Vec<GasReportRow> {
+    let mut rows: BTreeMap<(Option<String>, String), GasReportRow> = BTreeMap::new();
+
+    // A replaced operation and its fee-bumped copy are one operation, counted once
+    for record in records.iter().filter(|r| r.status != OperationStatus::Replaced) {
+        let group = match by {
+            ReportBy::Label => record.tag.clone().unwrap_or_else(|| UNTAGGED.to_string()),
+            ReportBy::Account => record.sender.to_string(),
+            ReportBy::Chain => record.chain_id.to_string(),
+            ReportBy::Kind => record.kind.clone(),
+        };
+        let bucket = period.bucket(record.included_at.unwrap_or(record.submitted_at));
+        let row = rows.entry((bucket.clone(), group.clone())).or_insert_with(|| GasReportRow {
+            period: bucket,
+            group,
+            operations: 0,
+            sponsored_operations: 0,
+            pending_operations: 0,
+            total_gas_used: U256::ZERO,
+            total_gas_cost: U256::ZERO,
+            sponsored_gas_cost: U256::ZERO,
+        });
+
+        let cost = record.actual_gas_cost.unwrap_or_default();
+        row.operations += 1;
+        if record.sponsored {
+            row.sponsored_operations += 1;
+            row.sponsored_gas_cost += cost;
+        }
+        if record.status == OperationStatus::Pending {
+            row.pending_operations += 1;
+        }
+        row.total_gas_used += record.actual_gas_used.unwrap_or_default();
+        row.total_gas_cost += cost;
+    }
+
+    rows.into_values().collect()
+}
+
+/// Columns of the CSV spend report, in order
+pub const REPORT_COLUMNS: [&str; 8] =
+    ["period", "group", "operations", "sponsored_operations", "pending_operations", "total_gas_used", "total_gas_cost", "sponsored_gas_cost"];
+
+/// Render a gas report as CSV, costs in wei
+pub fn report_csv(rows: &[GasReportRow]) -> String {
+    let mut csv = REPORT_COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        let fields = [
+            row.period.clone().unwrap_or_else(|| "all".to_string()),
+            row.group.clone(),
+            row.operations.to_string(),
+            row.sponsored_operations.to_string(),
+            row.pending_operations.to_string(),
+            row.total_gas_used.to_string(),
+            row.total_gas_cost.to_string(),
+            row.sponsored_gas_cost.to_string(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_journal(name: &str) -> Journal {
+        let path = std::env::temp_dir().join(format!("aa-client-journal-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Journal::open(path)
+    }
+
+    fn record(hash: &str, tag: Option<&str>, sponsored: bool) -> OperationRecord {
+        OperationRecord::pending(
+            hash.to_string(),
+            31337,
+            Address::from([1u8; 20]),
+            "submit",
+            tag.map(|t| t.to_string()),
+            sponsored,
+        )
+    }
+
+    #[test]
+    fn test_missing_journal_is_empty() {
+        let journal = temp_journal("missing");
+        assert!(journal.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_append_and_record_receipt() {
+        let journal = temp_journal("append");
+        journal.append(record("0x01", Some("checkout"), false)).unwrap();
+        journal.record_receipt("0x01", true, U256::from(21000), U256::from(42000)).unwrap();
+
+        let records = journal.load().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tag.as_deref(), Some("checkout"));
+        assert_eq!(records[0].status, OperationStatus::Included);
+        assert_eq!(records[0].actual_gas_cost, Some(U256::from(42000)));
+        assert!(records[0].included_at.unwrap() >= records[0].submitted_at);
+
+        assert!(journal.record_receipt("0x02", true, U256::ZERO, U256::ZERO).is_err());
+
+        journal.append(record("0x03", None, true)).unwrap();
+        journal.mark_failed("0x03").unwrap();
+        let records = journal.load().unwrap();
+        assert_eq!(records[1].status, OperationStatus::Failed);
+        assert_eq!(records[1].actual_gas_cost, None);
+
+        journal.append(record("0x04", None, false)).unwrap();
+        journal.mark_replaced("0x04", "0x05").unwrap();
+        let records = journal.load().unwrap();
+        assert_eq!(records[2].status, OperationStatus::Replaced);
+        assert_eq!(records[2].replaced_by.as_deref(), Some("0x05"));
+        assert_eq!(gas_report(&records, ReportBy::Label, ReportPeriod::All)[0].operations, 1);
+        let _ = std::fs::remove_file(journal.path());
+        let _ = std::fs::remove_file(journal.path().with_extension("lock"));
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_each_others_records() {
+        let journal = temp_journal("concurrent");
+        journal.append(record("0x00", None, false)).unwrap();
+
+        // A daemon recording receipts while submits append, each through its own handle
+        let writers: Vec<_> = (1..=8)
+            .map(|i| {
+                let path = journal.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let journal = Journal::open(path);
+                    journal.append(record(&format!("0x{:02x}", i), None, false)).unwrap();
+                    journal.mark_failed("0x00").unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let records = journal.load().unwrap();
+        assert_eq!(records.len(), 9);
+        assert_eq!(records[0].status, OperationStatus::Failed);
+        let _ = std::fs::remove_file(journal.path());
+        let _ = std::fs::remove_file(journal.path().with_extension("lock"));
+    }
+
+    #[test]
+    fn test_gas_report_groups_by_tag() {
+        let mut a = record("0x01", Some("checkout"), true);
+        a.status = OperationStatus::Included;
+        a.actual_gas_cost = Some(U256::from(100));
+        a.actual_gas_used = Some(U256::from(10));
+        let mut b = record("0x02", Some("checkout"), false);
+        b.status = OperationStatus::Included;
+        b.actual_gas_cost = Some(U256::from(50));
+        b.actual_gas_used = Some(U256::from(5));
+        let c = record("0x03", None, false);
+
+        let report = gas_report(&[a, b, c], ReportBy::Label, ReportPeriod::All);
+        assert_eq!(report.len(), 2);
+
+        let untagged = &report[0];
+        assert_eq!(untagged.group, UNTAGGED);
+        assert_eq!(untagged.pending_operations, 1);
+
+        let checkout = &report[1];
+        assert_eq!(checkout.group, "checkout");
+        assert_eq!(checkout.operations, 2);
+        assert_eq!(checkout.sponsored_operations, 1);
+        assert_eq!(checkout.total_gas_cost, U256::from(150));
+        assert_eq!(checkout.sponsored_gas_cost, U256::from(100));
+        assert_eq!(checkout.total_gas_used, U256::from(15));
+    }
+
+    #[test]
+    fn test_gas_report_by_month() {
+        // 2024-01-31 23:00 and 2024-02-01 01:00 UTC
+        let (january, february) = (1_706_742_000, 1_706_749_200);
+        let mut a = record("0x01", Some("project-x"), false);
+        a.submitted_at = january;
+        let mut b = record("0x02", Some("project-x"), false);
+        b.submitted_at = january;
+        b.included_at = Some(february);
+        b.actual_gas_cost = Some(U256::from(7));
+
+        let report = gas_report(&[a, b], ReportBy::Label, ReportPeriod::Month);
+        assert_eq!(report.iter().map(|row| row.period.as_deref().unwrap()).collect::<Vec<_>>(), vec!["2024-01", "2024-02"]);
+        assert_eq!(report[1].total_gas_cost, U256::from(7));
+        assert!(report_csv(&report).contains("\n2024-02,project-x,1,0,1,0,7,0\n"));
+
+        assert_eq!(ReportPeriod::Day.bucket(0).unwrap(), "1970-01-01");
+        assert_eq!(ReportPeriod::Day.bucket(951_782_400).unwrap(), "2000-02-29");
+        assert_eq!("tag".parse::<ReportBy>().unwrap(), ReportBy::Label);
+    }
+
+    #[test]
+    fn test_import_merges_by_hash() {
+        let journal = temp_journal("import");
+        journal.append(record("0x01", None, false)).unwrap();
+        journal.append(record("0x02", None, false)).unwrap();
+        journal.record_receipt("0x02", true, U256::from(1), U256::from(2)).unwrap();
+        journal
+            .attach_user_op("0x01", UnsignedUserOperation::execute(Address::ZERO, U256::ZERO, Address::ZERO, U256::ZERO, Default::default()))
+            .unwrap();
+
+        // Another machine saw 0x01 land and 0x02 still pending, and submitted 0x03
+        let mut landed = record("0x01", None, false);
+        landed.status = OperationStatus::Included;
+        let exported = export(&[landed, record("0x02", None, false), record("0x03", Some("handoff"), true)], ExportFormat::Json).unwrap();
+
+        let summary = journal.import(parse_export(&exported).unwrap()).unwrap();
+        assert_eq!(summary, ImportSummary { added: 1, updated: 1, unchanged: 1 });
+
+        let records = journal.load().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].status, OperationStatus::Included);
+        assert!(records[0].user_op.is_some());
+        assert_eq!(records[1].status, OperationStatus::Included);
+        assert_eq!(records[2].status, OperationStatus::Pending);
+        assert_eq!(records[2].tag.as_deref(), Some("handoff"));
+
+        // Importing the same export twice changes nothing
+        let summary = journal.import(parse_export(&exported).unwrap()).unwrap();
+        assert_eq!(summary, ImportSummary { added: 0, updated: 0, unchanged: 3 });
+        let _ = std::fs::remove_file(journal.path());
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut included = record("0x01", Some("team \"a\", eu"), true);
+        included.status = OperationStatus::Included;
+        included.actual_gas_cost = Some(U256::from(42));
+        let csv = export(&[included, record("0x02", None, false)], ExportFormat::Csv).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        assert!(lines[1].contains(",\"team \"\"a\"\", eu\",true,included,,42,"));
+        assert!(lines[2].starts_with("0x02,31337,"));
+        assert_eq!(lines[2].matches(',').count(), CSV_COLUMNS.len() - 1);
+
+        assert_eq!(ExportFormat::from_path(Path::new("ops.CSV")), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_path(Path::new("ops.json")), ExportFormat::Json);
+        assert!("xml".parse::<ExportFormat>().is_err());
+        assert!(parse_export(&csv).is_err());
+    }
+}
@@ -3,6 +3,56 @@ pub mod bundler;
 pub mod wallet;
 pub mod error;
 pub mod config;
+pub mod journal;
+pub mod cache;
+pub mod account;
+pub mod multisig;
+pub mod transport;
+pub mod entrypoint;
+pub mod session;
+pub mod recovery;
+pub mod qr;
+pub mod i18n;
+pub mod output;
+pub mod health;
+pub mod fees;
+pub mod retry;
+pub mod stuck;
+pub mod calldata;
+pub mod units;
+pub mod erc20;
+pub mod nonce;
+pub mod preflight;
+pub mod erc7562;
+pub mod sponsorship;
+pub mod eip7702;
+pub mod exit;
+pub mod remote_signer;
+pub mod erc1271;
+pub mod message;
+pub mod paymaster_funds;
+pub mod wallet_store;
+pub mod tui;
+pub mod analyze;
+pub mod daemon;
+pub mod events;
+pub mod zksync;
+pub mod templates;
+pub mod abis;
+pub mod paymaster;
+pub mod onboard;
+pub mod dummy_signature;
+pub mod indexer;
+pub mod providers;
+pub mod clock;
+pub mod validation;
+pub mod spending_limit;
+pub mod hexutil;
+pub mod factory;
+pub mod nft;
+pub mod walletconnect;
+pub mod rpc_server;
+pub mod telemetry;
 
 // Re-export main types for easier testing
 pub use userop::{UserOperationBuilder, UserOperationResponse, GasEstimate};
@@ -10,6 +60,8 @@ pub use bundler::BundlerClient;
 pub use wallet::{Wallet, WalletFactory};
 pub use error::AAError;
 pub use config::{NetworkConfig, list_supported_networks};
+pub use journal::{Journal, OperationRecord, OperationStatus};
+pub use events::ClientEvent;
 
 // Re-export aa-sdk-rs types for convenience
 pub use userop::{UserOperationRequest, ExecuteCall, AccountCall};
@@ -1,8 +1,10 @@
 // Refactored to use aa-sdk-rs SmartAccount functionality
 // This now properly integrates with aa-sdk-rs provider architecture
 
-use clap::{Parser, Subcommand};
-use alloy::primitives::{Address, U256, Bytes};
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap::parser::ValueSource;
+use alloy::primitives::{aliases::U192, Address, U256, Bytes};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 mod userop;
@@ -11,25 +13,223 @@ mod wallet;
 mod error;
 mod config;
 mod paymaster;
+mod journal;
+mod cache;
+mod account;
+mod multisig;
+mod transport;
+mod entrypoint;
+mod session;
+mod recovery;
+mod qr;
+mod i18n;
+mod output;
+mod health;
+mod fees;
+mod retry;
+mod stuck;
+mod calldata;
+mod units;
+mod erc20;
+mod nonce;
+mod preflight;
+mod erc7562;
+mod sponsorship;
+mod eip7702;
+mod exit;
+mod remote_signer;
+mod erc1271;
+mod message;
+mod paymaster_funds;
+mod wallet_store;
+mod tui;
+mod analyze;
+mod daemon;
+mod events;
+mod zksync;
+mod templates;
+mod abis;
+mod onboard;
+mod dummy_signature;
+mod indexer;
+mod providers;
+mod clock;
+mod validation;
+mod spending_limit;
+mod hexutil;
+mod factory;
+mod nft;
+mod walletconnect;
+mod rpc_server;
+mod telemetry;
 
-use userop::UserOperationBuilder;
+use userop::{ExecuteCall, UserOperationBuilder};
 use bundler::BundlerClient;
 use wallet::{Wallet, WalletFactory};
 use anyhow::Result;
-use config::list_supported_networks;
+use config::{CliConfig, Profile};
+use journal::{Journal, OperationRecord};
+use events::ClientEvent;
 
 // aa-sdk-rs integration - using SmartAccountProvider properly
 use aa_sdk_rs::{
-    smart_account::{SimpleAccount, SmartAccount},
+    smart_account::SmartAccount,
     provider::{SmartAccountProvider, SmartAccountProviderTrait},
 };
-use alloy::providers::ProviderBuilder;
+use alloy::providers::Provider;
 use std::sync::Arc;
+use tracing::level_filters::LevelFilter;
+use tracing::Instrument;
 
 #[derive(Parser)]
 #[command(name = "aa-client")]
 #[command(about = "Account Abstraction Client for ERC-4337")]
 struct Cli {
+    /// Print debug output too, such as RPC cache statistics, prefixed with its level and command
+    #[arg(long, short = 'v', global = true)]
+    verbose: bool,
+    
+    /// Print only warnings and errors
+    #[arg(long, short = 'q', global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    
+    /// Named profile from ~/.aa-client/config.toml supplying connection defaults
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    
+    /// Read the owner private key from the first line of stdin instead of --private-key
+    #[arg(long, global = true)]
+    private_key_stdin: bool,
+    
+    /// Use the owner key stored under this name by `wallet import` instead of --private-key
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "private_key_stdin")]
+    wallet: Option<String>,
+    
+    /// Language for CLI output (en, es), defaults to AA_LANG and then the system locale
+    #[arg(long, global = true)]
+    lang: Option<String>,
+    
+    /// Plain line-oriented output without emoji or QR art, for screen readers and CI logs
+    #[arg(long, global = true)]
+    plain: bool,
+    
+    /// OpenTelemetry collector (OTLP/HTTP) traces of fill, estimation, sponsorship and submission are exported to,
+    /// defaults to OTEL_EXPORTER_OTLP_ENDPOINT; needs a build with the otel feature
+    #[arg(long, global = true, value_name = "URL")]
+    otlp_endpoint: Option<String>,
+    
+    /// Backup bundler URL tried in order when --rpc-url errors or times out, repeat for more
+    #[arg(long = "bundler", global = true, value_name = "URL")]
+    bundlers: Vec<String>,
+    
+    /// Send UserOperations to every bundler at once and keep the first accepted hash
+    #[arg(long, global = true)]
+    race_bundlers: bool,
+    
+    /// Submit only through private bundlers, keeping operations out of the public ERC-4337 mempool
+    #[arg(long, global = true)]
+    private: bool,
+    
+    /// Private bundler URL used by --private, repeat for more (tried in order)
+    #[arg(long = "private-bundler", global = true, value_name = "URL")]
+    private_bundlers: Vec<String>,
+    
+    /// Fee level for estimated gas fees: slow, normal or fast
+    #[arg(long, global = true, default_value = "normal")]
+    fee_urgency: fees::Urgency,
+    
+    /// Attempts per network call when it fails with a transient error (1 disables retries)
+    #[arg(long, global = true, default_value = "3")]
+    max_attempts: u32,
+    
+    /// Delay before the first retry in milliseconds, doubled for every further retry
+    #[arg(long, global = true, default_value = "500")]
+    retry_backoff_ms: u64,
+    
+    /// Seconds a network call may spend waiting out rate limits (HTTP 429), honouring Retry-After (0 treats them as failures)
+    #[arg(long, global = true, default_value = "30")]
+    rate_limit_budget: u64,
+    
+    /// Wait exactly the backoff between retries instead of a randomized part of it
+    #[arg(long, global = true)]
+    no_retry_jitter: bool,
+    
+    /// Send without simulating the operation against the EntryPoint first
+    #[arg(long, global = true)]
+    no_preflight: bool,
+    
+    /// Only warn, instead of failing, when call data is sent to a target without contract code
+    #[arg(long, global = true)]
+    allow_eoa_target: bool,
+    
+    /// Refuse operations sending more ether than this, e.g. 1eth
+    #[arg(long, global = true, value_name = "AMOUNT")]
+    max_value: Option<String>,
+    
+    /// Ask before sending operations with more ether than this, e.g. 0.1eth
+    #[arg(long, global = true, value_name = "AMOUNT")]
+    confirm_value_above: Option<String>,
+    
+    /// Address no ether may be sent to, repeatable; the zero and 0x...dEaD burn addresses are always denied
+    #[arg(long = "deny-target", global = true, value_name = "ADDRESS")]
+    deny_targets: Vec<Address>,
+    
+    /// Answer yes to confirmations, e.g. for value above --confirm-value-above in scripts
+    #[arg(long, global = true)]
+    yes: bool,
+    
+    /// Build, fill, sponsor and simulate operations, then print them instead of sending them to the bundler
+    #[arg(long, global = true)]
+    dry_run: bool,
+    
+    /// Ask the paymaster for a new signature even when a cached sponsorship for the operation is still valid
+    #[arg(long, global = true)]
+    responsor: bool,
+    
+    /// Allocate nonces from a local cache shared by concurrent invocations instead of reading getNonce each time
+    #[arg(long, global = true)]
+    nonce_cache: bool,
+    
+    /// When the EntryPoint rejects the nonce (AA25): retry (read the nonce again, re-sign and send once more) or fail
+    #[arg(long, global = true, value_name = "POLICY", default_value = "retry")]
+    on_nonce_error: nonce::NonceRecovery,
+    
+    /// Sign operation hashes with a Web3Signer-compatible JSON-RPC signer instead of a local key (create --sign, op sign)
+    #[arg(long, global = true, value_name = "URL")]
+    remote_signer_url: Option<String>,
+    
+    /// Account on the remote signer, required when it holds more than one key
+    #[arg(long, global = true, requires = "remote_signer_url")]
+    remote_signer_address: Option<Address>,
+    
+    /// EntryPoint deployment to target: v0.7 (default) or v0.8, looked up in the network's entry_points
+    #[arg(long, global = true, value_name = "VERSION")]
+    entry_point_version: Option<entrypoint::EntryPointVersion>,
+    
+    /// Paymaster contract behind sponsored commands: verifying (signed by the paymaster service),
+    /// simple (no signature, the service is not called) or token:<address> (ERC-20 paymaster, no signature)
+    #[arg(long, global = true, value_name = "MODE")]
+    paymaster_mode: Option<paymaster::PaymasterMode>,
+    
+    /// Placeholder signature gas is estimated with: ecdsa (default), multi-owner:<signers>, p256 or 0x<hex>,
+    /// sized like the account's real signatures so verification gas is not underestimated
+    #[arg(long, global = true, value_name = "KIND")]
+    dummy_signature: Option<dummy_signature::DummySignature>,
+    
+    /// Factory calldata layout: simple (createAccount(owner, salt)), multi-owner (createAccountWithOwners),
+    /// owners-array (createAccount(address[], salt)) or owners-bytes (createAccount(bytes, nonce))
+    /// [default: simple for single-owner commands, multi-owner for multi-owner ones]
+    #[arg(long, global = true, value_name = "ABI")]
+    factory_abi: Option<factory::FactoryAbi>,
+    
+    /// Unix time to use as the current time, so runs are reproducible (integration tests, replay debugging)
+    #[arg(long, global = true, value_name = "UNIX_SECS")]
+    fixed_time: Option<u64>,
+    
+    /// Seed for retry jitter, so runs are reproducible; keys are always drawn from the operating system
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    
     #[command(subcommand)]
     command: Commands,
 }
@@ -38,9 +238,9 @@ struct Cli {
 enum Commands {
     /// Create and sign a UserOperation
     Create {
-        /// Private key in hex format
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Target contract address
         #[arg(short, long)]
@@ -58,9 +258,9 @@ enum Commands {
         #[arg(short, long, default_value = "http://localhost:8545")]
         rpc_url: String,
         
-        /// Entry point contract address
-        #[arg(short, long, default_value = "0x0000000071727De22E5E9d8BAf0edAc6f37da032")]
-        entry_point: String,
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
         
         /// Chain ID
         #[arg(short, long, default_value = "31337")]
@@ -73,15 +273,83 @@ enum Commands {
         /// Maximum priority fee per gas (in wei)
         #[arg(long, default_value = "2000000000")]
         max_priority_fee_per_gas: String,
+        
+        /// Smart account sending the operation (required with --sign)
+        #[arg(long)]
+        sender: Option<String>,
+        
+        /// Value to send with the call (in wei)
+        #[arg(long, default_value = "0")]
+        value: String,
+        
+        /// Gas limit for the account's execute call
+        #[arg(long, default_value = "200000")]
+        call_gas_limit: u64,
+        
+        /// Gas limit for signature validation
+        #[arg(long, default_value = "300000")]
+        verification_gas_limit: u64,
+        
+        /// Gas paid to the bundler for overhead
+        #[arg(long, default_value = "60000")]
+        pre_verification_gas: u64,
+        
+        /// Fill and sign the operation without any network access, writing it to --out for submit-file
+        #[arg(long, requires_all = ["out", "sender"])]
+        sign: bool,
+        
+        /// File the signed operation is written to
+        #[arg(long)]
+        out: Option<PathBuf>,
     },
     
-
+    /// Submit a UserOperation signed offline with `create --sign`
+    SubmitFile {
+        /// Signed UserOperation file
+        file: PathBuf,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Tag recorded in the ops journal for cost attribution
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Import a UserOperation signed by another tool (viem, userop.js) from its ERC-4337 JSON-RPC form for submit-file
+    ImportOp {
+        /// UserOperation JSON in its RPC form, including the signature
+        file: PathBuf,
+        
+        /// File the signed operation is written to
+        #[arg(long)]
+        out: PathBuf,
+        
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+    },
+    
+    /// Export a signed UserOperation file in the ERC-4337 JSON-RPC form other tools accept
+    ExportOp {
+        /// Signed UserOperation file from create --sign or import-op
+        file: PathBuf,
+        
+        /// Write the JSON here instead of printing it
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
     
     /// Submit a UserOperation to a bundler (for arbitrary transactions)
     Submit {
-        /// Private key in hex format
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Target contract address
         #[arg(short, long)]
@@ -95,17 +363,21 @@ enum Commands {
         #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
         factory: String,
         
-        /// Salt for deterministic deployment (hex string, needed to identify smart account)
+        /// Salt for deterministic deployment (hex string, needed to identify smart account; not used on zkSync)
         #[arg(short, long)]
-        salt: String,
+        salt: Option<String>,
+        
+        /// Sign with a session key file from `session create` instead of an owner key
+        #[arg(long, conflicts_with_all = ["private_key", "salt"])]
+        session_key: Option<PathBuf>,
         
         /// RPC URL for the network
         #[arg(short, long, default_value = "http://localhost:8545")]
         rpc_url: String,
         
-        /// Entry point contract address
-        #[arg(short, long, default_value = "0x0000000071727De22E5E9d8BAf0edAc6f37da032")]
-        entry_point: String,
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
         
         /// Chain ID
         #[arg(short, long, default_value = "31337")]
@@ -115,28 +387,56 @@ enum Commands {
         #[arg(long, default_value = "0")]
         value: String,
         
-        /// Maximum fee per gas (in wei)
-        #[arg(long, default_value = "20000000000")]
-        max_fee_per_gas: String,
+        /// Nonce key for a parallel stream of operations (uint192, default stream when omitted)
+        #[arg(long)]
+        nonce_key: Option<String>,
         
-        /// Maximum priority fee per gas (in wei)
-        #[arg(long, default_value = "2000000000")]
-        max_priority_fee_per_gas: String,
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// zkSync only: native account sending the call, the owner's own address when omitted
+        #[arg(long, conflicts_with = "session_key")]
+        account: Option<Address>,
+        
+        /// zkSync only: paymaster paying the fee (general flow)
+        #[arg(long, conflicts_with = "session_key")]
+        paymaster: Option<Address>,
+        
+        /// zkSync only: ERC-20 the paymaster takes its fee in (approval-based flow)
+        #[arg(long, requires_all = ["paymaster", "paymaster_allowance"])]
+        paymaster_token: Option<Address>,
+        
+        /// zkSync only: allowance of --paymaster-token approved for the paymaster, in the token's smallest unit
+        #[arg(long, requires = "paymaster_token")]
+        paymaster_allowance: Option<String>,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
     },
     
     /// Deploy a new smart account using the factory via bundler
     DeployAccount {
-        /// Private key in hex format (for signing deployment transaction)
+        /// Private key in hex format (for signing deployment transaction, prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Factory contract address
         #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
         factory: String,
         
-        /// Salt for deterministic deployment (hex string)
-        #[arg(short, long)]
-        salt: String,
+        /// Salt for deterministic deployment (hex string, at most 32 bytes)
+        #[arg(short, long, required_unless_present = "salt_random")]
+        salt: Option<String>,
+        
+        /// Deploy with a fresh random salt, printed so the account can be found again
+        #[arg(long, conflicts_with = "salt")]
+        salt_random: bool,
         
         /// RPC URL for the network
         #[arg(short, long, default_value = "http://localhost:8545")]
@@ -146,20 +446,24 @@ enum Commands {
         #[arg(short, long, default_value = "31337")]
         chain_id: u64,
         
-        /// Maximum fee per gas (in wei)
-        #[arg(long, default_value = "20000000000")]
-        max_fee_per_gas: String,
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
         
-        /// Maximum priority fee per gas (in wei)
-        #[arg(long, default_value = "2000000000")]
-        max_priority_fee_per_gas: String,
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
     },
     
     /// Deploy a new smart account with multiple owners via bundler
     DeployMultiOwnerAccount {
-        /// Private key in hex format (for signing deployment transaction)
+        /// Private key in hex format (for signing deployment transaction, prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Factory contract address
         #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
@@ -169,9 +473,211 @@ enum Commands {
         #[arg(short, long)]
         owners: String,
         
-        /// Salt for deterministic deployment (hex string)
+        /// Owner signatures every operation needs once deployed (k of n, any single owner when omitted)
+        #[arg(long)]
+        threshold: Option<u64>,
+        
+        /// Salt for deterministic deployment (hex string, at most 32 bytes)
+        #[arg(short, long, required_unless_present = "salt_random")]
+        salt: Option<String>,
+        
+        /// Deploy with a fresh random salt, printed so the account can be found again
+        #[arg(long, conflicts_with = "salt")]
+        salt_random: bool,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Add an owner to a deployed multi-owner account (signed by an existing owner)
+    AddOwner {
+        /// Private key of an existing owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        salt: String,
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Address of the owner to add
+        #[arg(short, long)]
+        owner: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
+        
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Remove an owner from a deployed multi-owner account (signed by an existing owner)
+    RemoveOwner {
+        /// Private key of an existing owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Address of the owner to remove
+        #[arg(short, long)]
+        owner: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
+        
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Change how many owner signatures a multi-owner account needs (k of n)
+    SetThreshold {
+        /// Private key of an existing owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Number of owner signatures required
+        #[arg(short, long)]
+        threshold: u64,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
+        
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// List the owners and signature threshold of a deployed multi-owner account
+    ListOwners {
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Show which EntryPoints an account supports and its deposit on each
+    EntryPoints {
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Move an account's deposit to another EntryPoint version and re-point the profile
+    MigrateEntryPoint {
+        /// Private key of an account owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// EntryPoint to migrate to (v0.6, v0.7, v0.8 or address)
+        #[arg(long)]
+        to: entrypoint::EntryPointVersion,
+        
+        /// EntryPoint to withdraw from (defaults to the one the account uses)
+        #[arg(long)]
+        from: Option<entrypoint::EntryPointVersion>,
+        
+        /// Amount to move in wei (defaults to the whole deposit, required when withdrawing from the active EntryPoint)
+        #[arg(long)]
+        amount: Option<String>,
+        
+        /// Update the selected profile's entry_point to the new EntryPoint
+        #[arg(long)]
+        set_default: bool,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
         
         /// RPC URL for the network
         #[arg(short, long, default_value = "http://localhost:8545")]
@@ -180,6 +686,10 @@ enum Commands {
         /// Chain ID
         #[arg(short, long, default_value = "31337")]
         chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
     },
     
     /// Get predicted smart account address before deployment
@@ -189,13 +699,22 @@ enum Commands {
         factory: String,
         
         /// Owner address
-        #[arg(short, long)]
-        owner: String,
+        #[arg(short, long, required_unless_present = "owners")]
+        owner: Option<String>,
+        
+        /// Comma-separated owner addresses of a multi-owner account, in deployment order
+        #[arg(long, conflicts_with = "owner")]
+        owners: Option<String>,
         
         /// Salt for deterministic deployment (hex string)
         #[arg(short, long)]
         salt: String,
         
+        /// Forge artifact of ERC1967Proxy for the local CREATE2 cross-check of --owners
+        /// [default: ../contracts/out/ERC1967Proxy.sol/ERC1967Proxy.json, skipped when missing]
+        #[arg(long, requires = "owners")]
+        proxy_artifact: Option<PathBuf>,
+        
         /// RPC URL for the network
         #[arg(short, long, default_value = "http://localhost:8545")]
         rpc_url: String,
@@ -203,16 +722,33 @@ enum Commands {
         /// Chain ID
         #[arg(short, long, default_value = "31337")]
         chain_id: u64,
+        
+        /// Also show the address as an EIP-681 payment QR code, to fund it from a mobile wallet
+        #[arg(long)]
+        qr: bool,
     },
     
     /// Generate a new random wallet
-    GenerateWallet,
+    GenerateWallet {
+        /// Also show the address as a QR code
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// Guided walkthrough that builds, prices and sends one operation, asking for each input
+    Tui,
+    
+    /// Keep owner keys encrypted under a name, for --wallet
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
     
     /// Get account information
     Info {
-        /// Private key in hex format
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
     },
     
 
@@ -222,9 +758,9 @@ enum Commands {
     
     /// Submit a sponsored UserOperation (gas paid by paymaster)
     SubmitSponsored {
-        /// Private key in hex format
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Target contract address
         #[arg(short, long)]
@@ -239,8 +775,12 @@ enum Commands {
         factory: String,
         
         /// Salt for deterministic deployment (hex string)
-        #[arg(short, long)]
-        salt: String,
+        #[arg(short, long, required_unless_present = "eip7702")]
+        salt: Option<String>,
+        
+        /// Use the EOA itself as the account, delegated with EIP-7702 to this account implementation
+        #[arg(long, conflicts_with = "salt")]
+        eip7702: Option<String>,
         
         /// RPC URL for the network
         #[arg(short, long, default_value = "http://localhost:8545")]
@@ -254,6 +794,10 @@ enum Commands {
         #[arg(long, default_value = "0")]
         value: String,
         
+        /// Nonce key for a parallel stream of operations (uint192, default stream when omitted)
+        #[arg(long)]
+        nonce_key: Option<String>,
+        
         /// Paymaster service URL
         #[arg(long, default_value = "http://localhost:3000")]
         paymaster_url: String,
@@ -262,16 +806,32 @@ enum Commands {
         #[arg(long, default_value = "api_key_123")]
         paymaster_api_key: String,
         
+        /// Single-use pre-auth token or link from issue-preauth, sent instead of the API key
+        #[arg(long)]
+        preauth_token: Option<String>,
+        
         /// Deployed paymaster contract address
         #[arg(long, default_value = "0x0000000000000000000000000000000000000000")]
         paymaster_address: String,
+        
+        /// Body encoding for paymaster service requests (json or cbor)
+        #[arg(long, default_value = "json")]
+        paymaster_encoding: paymaster::WireFormat,
+        
+        /// Seconds the paymaster sponsorship stays valid (see fee-advice for a recommendation)
+        #[arg(long, default_value = "3600")]
+        valid_for: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
     },
     
     /// Deploy sponsored smart account (deployment gas paid by paymaster)
     DeploySponsored {
-        /// Private key in hex format
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
         /// Factory contract address
         #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
@@ -297,1033 +857,6448 @@ enum Commands {
         #[arg(long, default_value = "api_key_123")]
         paymaster_api_key: String,
         
+        /// Single-use pre-auth token or link from issue-preauth, sent instead of the API key
+        #[arg(long)]
+        preauth_token: Option<String>,
+        
         /// Deployed paymaster contract address
         #[arg(long, default_value = "0x0000000000000000000000000000000000000000")]
         paymaster_address: String,
+        
+        /// Body encoding for paymaster service requests (json or cbor)
+        #[arg(long, default_value = "json")]
+        paymaster_encoding: paymaster::WireFormat,
+        
+        /// Seconds the paymaster sponsorship stays valid (see fee-advice for a recommendation)
+        #[arg(long, default_value = "3600")]
+        valid_for: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
     },
-}
-
-#[tokio::main]
-async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-
-    match &cli.command {
-        Commands::Create { private_key, target, call_data, nonce, rpc_url, entry_point, chain_id, max_fee_per_gas, max_priority_fee_per_gas } => {
-            create_user_operation(private_key, target, call_data, *nonce, rpc_url, entry_point, *chain_id, max_fee_per_gas, max_priority_fee_per_gas).await?;
-        }
-
-        Commands::Submit { private_key, target, call_data, factory, salt, rpc_url, entry_point: _, chain_id, value, max_fee_per_gas, max_priority_fee_per_gas } => {
-            submit_user_operation_fixed(private_key, target, call_data, value, factory, salt, rpc_url, *chain_id, max_fee_per_gas, max_priority_fee_per_gas).await?;
-        }
-        Commands::DeployAccount { private_key, factory, salt, rpc_url, chain_id, max_fee_per_gas, max_priority_fee_per_gas } => {
-            deploy_smart_account(private_key, factory, salt, rpc_url, *chain_id, max_fee_per_gas, max_priority_fee_per_gas).await?;
-        }
-        Commands::DeployMultiOwnerAccount { private_key, factory, owners, salt, rpc_url, chain_id } => {
-            deploy_multi_owner_account(private_key, factory, owners, salt, rpc_url, *chain_id).await?;
-        }
-        Commands::PredictAddress { factory, owner, salt, rpc_url, chain_id } => {
-            predict_smart_account_address(factory, owner, salt, rpc_url, *chain_id).await?;
-        }
-        Commands::GenerateWallet => {
-            generate_wallet().await?;
-        }
-        Commands::Info { private_key } => {
-            show_wallet_info(private_key)?;
-        }
-
-        Commands::Networks => {
-            show_network_presets()?;
-        }
-        Commands::SubmitSponsored { 
-            private_key, target, call_data, factory, salt, rpc_url, chain_id, value, 
-            paymaster_url, paymaster_api_key, paymaster_address 
-        } => {
-            submit_sponsored_user_operation(
-                private_key, target, call_data, value, factory, salt, rpc_url, *chain_id,
-                paymaster_url, paymaster_api_key, paymaster_address
-            ).await?;
-        }
-        Commands::DeploySponsored {
-            private_key, factory, salt, rpc_url, chain_id, 
-            paymaster_url, paymaster_api_key, paymaster_address
-        } => {
-            deploy_sponsored_smart_account(
-                private_key, factory, salt, rpc_url, *chain_id,
-                paymaster_url, paymaster_api_key, paymaster_address
-            ).await?;
-        }
-    }
-
-    Ok(())
-}
-
-async fn create_user_operation(
-    private_key: &str,
-    target: &str,
-    call_data: &str,
-    nonce: u64,
-    _rpc_url: &str,
-    _entry_point: &str,
-    _chain_id: u64,
-    _max_fee_per_gas: &str,
-    _max_priority_fee_per_gas: &str,
-) -> Result<()> {
-    println!("Creating UserOperation...");
-    
-    // Create wallet
-    let wallet = Wallet::from_hex(private_key)?;
-    println!("Wallet address: {}", wallet.address());
-    
-    // Parse target address
-    let target_addr = Address::from_str(target)?;
-    
-    // Parse call data
-    let call_data_bytes = if call_data.starts_with("0x") {
-        Bytes::from_str(call_data)?
-    } else {
-        Bytes::from_str(&format!("0x{}", call_data))?
-    };
-    
-    // Create UserOperation using aa-sdk-rs
-    let _user_op_request = UserOperationBuilder::new(target_addr, U256::ZERO, call_data_bytes.clone())
-        .with_sender(wallet.address())
-        .with_nonce(U256::from(nonce))
-        .build();
     
-    println!("UserOperation created successfully!");
-    println!("Sender: {}", wallet.address());
-    println!("Nonce: {}", nonce);
-    println!("Target: {}", target_addr);
-    println!("Call Data: 0x{}", hex::encode(&call_data_bytes));
-    println!("Note: aa-sdk-rs handles signing internally when submitting operations");
-    
-    Ok(())
-}
-
-
-
-/// Submit a UserOperation to a bundler using aa-sdk-rs SmartAccountProvider (FIXED VERSION)
-async fn submit_user_operation_fixed(
-    private_key: &str,
-    target: &str,
-    call_data: &str,
-    value: &str,
-    factory: &str,      // ✅ Added: Need to identify smart account
-    salt: &str,         // ✅ Added: Need to identify smart account
+    /// Generate an owner key, deploy its account through the paymaster and send an optional first sponsored call
+    /// Running it again resumes from the step a previous run stopped at
+    Onboard {
+        /// Private key to onboard instead of generating one (prefer --private-key-stdin, --wallet or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Wallet name a generated owner key is stored under, and unlocked from when resuming
+        #[arg(long, default_value = onboard::DEFAULT_WALLET_NAME)]
+        name: String,
+        
+        /// Target of the first sponsored call, none is sent when omitted
+        #[arg(short, long)]
+        target: Option<String>,
+        
+        /// Call data of the first call (hex string)
+        #[arg(short = 'd', long, default_value = "0x", requires = "target")]
+        call_data: String,
+        
+        /// Value sent with the first call (in wei)
+        #[arg(long, default_value = "0", requires = "target")]
+        value: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// Salt for deterministic deployment (hex string)
+        #[arg(short, long, default_value = "0x00")]
+        salt: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Paymaster service URL
+        #[arg(long, default_value = "http://localhost:3000")]
+        paymaster_url: String,
+        
+        /// Paymaster API key
+        #[arg(long, default_value = "api_key_123")]
+        paymaster_api_key: String,
+        
+        /// Deployed paymaster contract address
+        #[arg(long, default_value = "0x0000000000000000000000000000000000000000")]
+        paymaster_address: String,
+        
+        /// Body encoding for paymaster service requests (json or cbor)
+        #[arg(long, default_value = "json")]
+        paymaster_encoding: paymaster::WireFormat,
+        
+        /// Seconds the paymaster sponsorship stays valid (see fee-advice for a recommendation)
+        #[arg(long, default_value = "3600")]
+        valid_for: u64,
+        
+        /// Seconds to wait for each receipt before stopping, the next run resumes the wait
+        #[arg(long, default_value = "120")]
+        wait: u64,
+    },
+    
+    /// Issue a single-use token sponsoring one operation from a given smart account
+    IssuePreauth {
+        /// Smart account allowed to redeem the token
+        #[arg(short, long)]
+        sender: String,
+        
+        /// Token lifetime in seconds (the service caps this at one hour)
+        #[arg(long, default_value = "600")]
+        ttl: u64,
+        
+        /// Paymaster service URL
+        #[arg(long, default_value = "http://localhost:3000")]
+        paymaster_url: String,
+        
+        /// Paymaster API key the sponsorship is charged to
+        #[arg(long, default_value = "api_key_123")]
+        paymaster_api_key: String,
+        
+        /// Body encoding for paymaster service requests (json or cbor)
+        #[arg(long, default_value = "json")]
+        paymaster_encoding: paymaster::WireFormat,
+        
+        /// Also print the pre-auth link as a QR code
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// Import a proposal or pre-auth from scanned QR chunks, one per line
+    Scan {
+        /// File with scanned chunks (reads stdin when omitted, e.g. from a keyboard-mode scanner)
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        
+        /// Where to write the payload; signatures are merged into an existing proposal file
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    
+    /// Collect signatures from several owners offline before submitting (multi-owner accounts)
+    Op {
+        #[command(subcommand)]
+        command: OpCommands,
+    },
+    
+    /// Grant and revoke scoped session keys on a multi-owner account
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+    
+    /// Configure guardians and recover a multi-owner account after key loss
+    Recovery {
+        #[command(subcommand)]
+        command: RecoveryCommands,
+    },
+    
+    /// Inspect the local operations journal
+    Ops {
+        #[command(subcommand)]
+        command: OpsCommands,
+    },
+    
+    /// Track pending journal operations on every chain, bump fees of waiting ones and report their status
+    Watch {
+        /// Keep running, rescanning every --interval seconds and serving the status over HTTP
+        #[arg(long)]
+        daemon: bool,
+        
+        /// Bundler URL of a chain as CHAIN_ID=URL, repeatable; profiles and keyless network presets cover the rest
+        #[arg(long = "rpc", value_name = "CHAIN_ID=URL")]
+        rpc: Vec<String>,
+        
+        /// Seconds between scans
+        #[arg(long, default_value = "30")]
+        interval: u64,
+        
+        /// Re-sign unsponsored operations still waiting after this many seconds with higher fees (needs the owner key)
+        #[arg(long)]
+        bump_after: Option<u64>,
+        
+        /// Fee increase per bump in percent, bundlers reject replacements below 10
+        #[arg(long, default_value = "15")]
+        bump_percent: u64,
+        
+        /// Never raise maxFeePerGas above this many gwei
+        #[arg(long)]
+        max_fee_cap: Option<String>,
+        
+        /// Address of the HTTP status endpoint (GET /status): HOST:PORT, or unix:///path / npipe://name to open no port
+        #[arg(long, default_value = "127.0.0.1:9477")]
+        status_addr: daemon::StatusAddr,
+        
+        /// Owner private key used to re-sign bumped operations
+        #[arg(short, long)]
+        private_key: Option<String>,
+    },
+    
+    /// Inspect the configured bundler endpoints
+    Bundlers {
+        #[command(subcommand)]
+        command: BundlerCommands,
+    },
+    
+    /// Recommend fee caps and a sponsorship window from recent fee history and journaled inclusion delays
+    FeeAdvice {
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID, selects which journaled operations are analyzed
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Number of recent blocks to analyze
+        #[arg(long, default_value = "100")]
+        blocks: u64,
+    },
+    
+    /// Build call data for --call-data from a function signature
+    Calldata {
+        #[command(subcommand)]
+        command: CalldataCommands,
+    },
+    
+    /// Inspect a smart account's on-chain state
+    Account {
+        #[command(subcommand)]
+        command: AccountCommands,
+    },
+    
+    /// Send ETH from the smart account and wait for the receipt
+    Send {
+        /// Recipient address
+        #[arg(short, long)]
+        to: String,
+        
+        /// Amount with a unit suffix: 0.1eth, 20gwei or 1000wei (bare numbers are wei)
+        #[arg(short, long)]
+        value: String,
+        
+        /// Seconds to wait for the receipt after submission (0 returns right away)
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Transfer or approve ERC-20 tokens from the smart account, or read balances and allowances
+    Erc20 {
+        #[command(subcommand)]
+        command: Erc20Commands,
+    },
+    
+    /// Transfer ERC-721 and ERC-1155 tokens from the smart account
+    Nft {
+        #[command(subcommand)]
+        command: NftCommands,
+    },
+    
+    /// Pair with a dApp over WalletConnect v2 and answer its requests with the smart account
+    Connect {
+        /// Pairing URI the dApp shows as a QR code or link (wc:...@2?relay-protocol=irn&symKey=...)
+        uri: String,
+        
+        /// WalletConnect Cloud project id (falls back to WALLETCONNECT_PROJECT_ID)
+        #[arg(long)]
+        project_id: Option<String>,
+        
+        /// WalletConnect relay server
+        #[arg(long, default_value = walletconnect::DEFAULT_RELAY_URL)]
+        relay_url: String,
+        
+        /// Seconds to wait for each transaction's receipt before answering the dApp with the operation hash
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Serve the smart account as a local JSON-RPC wallet for scripts and tools such as cast
+    Serve {
+        /// Port on 127.0.0.1
+        #[arg(long, default_value_t = rpc_server::DEFAULT_PORT)]
+        port: u16,
+        
+        /// Seconds to wait for each transaction's receipt before answering with the operation hash
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Per-token daily spending limits kept by a spending limit module
+    Limits {
+        #[command(subcommand)]
+        command: LimitCommands,
+    },
+    
+    /// Fund or withdraw a paymaster's EntryPoint deposit from the smart account
+    Paymaster {
+        #[command(subcommand)]
+        command: PaymasterCommands,
+    },
+    
+    /// Compute the userOpHash of a UserOperation offline, without trusting an RPC
+    Hash {
+        /// UserOperation JSON in its RPC (unpacked) form, as sent to or returned by a bundler
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// Entry point contract address [default: the network's EntryPoint for --entry-point-version]
+        #[arg(short, long)]
+        entry_point: Option<String>,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+    },
+    
+    /// Report zero and non-zero calldata bytes, the L1 data cost on rollups and ways to shrink an operation
+    Analyze {
+        /// UserOperation JSON in its RPC (unpacked) form, signed or not
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// L1 base fee in gwei for the data cost (read from the OP Stack gas price oracle via --rpc-url when omitted)
+        #[arg(long)]
+        l1_base_fee: Option<String>,
+        
+        /// RPC URL of the rollup the operation is sent on
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Save parameterized operations to run later with `run`
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+    
+    /// Submit a saved template, giving only its variable arguments
+    Run {
+        /// Template name
+        name: String,
+        
+        /// Template argument as NAME=VALUE, repeat for each
+        #[arg(long = "arg", value_name = "NAME=VALUE")]
+        args: Vec<String>,
+        
+        /// Seconds to wait for the receipt after submission (0 returns right away)
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Register contract ABIs for `call`
+    Abi {
+        #[command(subcommand)]
+        command: AbiCommands,
+    },
+    
+    /// Call a function of a contract registered with `abi add`, arguments checked against its ABI
+    /// View functions are read with eth_call, others are submitted and their events decoded from the receipt
+    Call {
+        /// Name the ABI was added under
+        contract: String,
+        
+        /// Function name, or its full signature to pick one of several overloads
+        function: String,
+        
+        /// One argument per parameter, in Solidity literal syntax
+        #[arg(allow_negative_numbers = true)]
+        args: Vec<String>,
+        
+        /// Contract address [default: the address given to `abi add`]
+        #[arg(long)]
+        address: Option<String>,
+        
+        /// ETH sent with the call, with a unit suffix: 0.1eth, 20gwei or 1000wei
+        #[arg(long, default_value = "0")]
+        value: String,
+        
+        /// Seconds to wait for the receipt after submission (0 returns right away)
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Check a signature against a contract wallet with ERC-1271 isValidSignature
+    #[command(name = "verify-1271")]
+    Verify1271 {
+        /// Contract wallet that validates the signature (smart account or contract owner)
+        #[arg(short, long)]
+        contract: String,
+        
+        /// 32-byte hash the signature is for
+        #[arg(long, required_unless_present = "message", conflicts_with = "message")]
+        hash: Option<String>,
+        
+        /// Message signed with EIP-191 (personal_sign), checked against its digest
+        #[arg(short, long)]
+        message: Option<String>,
+        
+        /// Signature bytes (hex string)
+        #[arg(short, long)]
+        signature: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Sign a personal message (EIP-191) with the owner key, for the owner or its smart account
+    SignMessage {
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Message to sign, e.g. a SIWE message
+        #[arg(short, long)]
+        message: String,
+        
+        /// Treat --message as hex bytes instead of UTF-8 text
+        #[arg(long)]
+        hex: bool,
+        
+        #[command(flatten)]
+        account: SignatureAccountArgs,
+    },
+    
+    /// Sign EIP-712 typed data with the owner key, for the owner or its smart account
+    SignTypedData {
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Typed data JSON as passed to eth_signTypedData_v4
+        #[arg(long)]
+        file: PathBuf,
+        
+        #[command(flatten)]
+        account: SignatureAccountArgs,
+    },
+}
+
+/// Smart account a message signature is made for, ERC-6492 wrapped while it is not deployed
+#[derive(Args)]
+struct SignatureAccountArgs {
+    /// Salt of the owner's smart account; without it the plain owner signature is printed
+    #[arg(short, long)]
+    salt: Option<String>,
+    
+    /// Factory contract address that creates the smart account
+    #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+    factory: String,
+    
+    /// RPC URL for the network, to look up the account and whether it is deployed
+    #[arg(short, long, default_value = "http://localhost:8545")]
+    rpc_url: String,
+}
+
+#[derive(Subcommand)]
+enum OpCommands {
+    /// Export an unsigned UserOperation for a multi-owner account to a JSON file
+    Propose {
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Target contract address
+        #[arg(short, long)]
+        target: String,
+        
+        /// Call data (hex string)
+        #[arg(short = 'd', long)]
+        call_data: String,
+        
+        /// Value to send with the transaction (in wei)
+        #[arg(long, default_value = "0")]
+        value: String,
+        
+        /// File the proposal is written to
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Gas limit for the account's execute call
+        #[arg(long, default_value = "200000")]
+        call_gas_limit: u64,
+        
+        /// Gas limit for signature validation (grows with the number of signatures)
+        #[arg(long, default_value = "300000")]
+        verification_gas_limit: u64,
+        
+        /// Gas paid to the bundler for overhead
+        #[arg(long, default_value = "60000")]
+        pre_verification_gas: u64,
+        
+        /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+        #[arg(long)]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Also print the proposal as QR codes for an air-gapped signer
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// Rebuild a proposal from its captured build context, verifying an existing proposal file against it
+    Rebuild {
+        /// Build context JSON (the "context" object of a proposal)
+        #[arg(long)]
+        context: PathBuf,
+        
+        /// Proposal file to verify, or to create when it does not exist
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    
+    /// Add an owner signature to a proposal file
+    Sign {
+        /// Private key of the signing owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Proposal file to sign
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// Also print the signed proposal as QR codes to hand back
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// Combine the collected signatures and submit the UserOperation to the bundler
+    Submit {
+        /// Proposal file with collected signatures
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// Number of owner signatures required (defaults to the account's threshold, or every owner without one)
+        #[arg(long)]
+        threshold: Option<usize>,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionCommands {
+    /// Generate a session key and register it on the account, limited to one target and selector
+    Create {
+        /// Private key of an account owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Only contract the session key may call
+        #[arg(short, long)]
+        target: String,
+        
+        /// Only function the session key may call, as 4 bytes or a signature like "transfer(address,uint256)"
+        #[arg(long, default_value = "any")]
+        selector: String,
+        
+        /// Lifetime of the session key in seconds
+        #[arg(long, default_value = "86400")]
+        valid_for: u64,
+        
+        /// Most a single call may send (0.1eth, 20gwei, wei), zero allows none
+        #[arg(long, default_value = "0")]
+        max_value: String,
+        
+        /// Operations the session key may sign before it stops working
+        #[arg(long, default_value = "100")]
+        max_ops: u32,
+        
+        /// File the session key is written to
+        #[arg(short, long)]
+        out: PathBuf,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Revoke a session key before it expires
+    Revoke {
+        /// Private key of an account owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Address of the session key to revoke
+        #[arg(short = 'k', long)]
+        key: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RecoveryCommands {
+    /// Add a guardian, optionally setting how many guardians must approve and the timelock
+    AddGuardian {
+        /// Private key of an account owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Guardian address (an EOA or another account, never an owner)
+        #[arg(short, long)]
+        guardian: String,
+        
+        /// Guardian approvals required to recover (required before any recovery can start)
+        #[arg(long)]
+        threshold: Option<u64>,
+        
+        /// Seconds between initiating and executing a recovery, applied with --threshold
+        #[arg(long, default_value = "172800")]
+        delay: u64,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Propose a new owner set as a guardian, starting the timelock
+    Initiate {
+        /// Private key of the guardian (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Smart account to recover
+        #[arg(short, long)]
+        account: String,
+        
+        /// Comma-separated list of new owner addresses
+        #[arg(short, long)]
+        new_owners: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Approve the pending recovery as another guardian
+    Approve {
+        /// Private key of the guardian (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Smart account being recovered
+        #[arg(short, long)]
+        account: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Replace the owners once the recovery is approved and the timelock has passed
+    Execute {
+        /// Private key of any funded EOA paying for the transaction (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Smart account being recovered
+        #[arg(short, long)]
+        account: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Cancel a pending recovery as an owner
+    Cancel {
+        /// Private key of an account owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Show guardians, threshold and the pending recovery with its remaining timelock
+    Status {
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum OpsCommands {
+    /// List operations recorded in the journal
+    List {
+        /// Only show operations with this tag
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+    
+    /// Report gas usage and cost per label (or account, chain, kind), optionally per day or month
+    Report {
+        /// label, account, chain or kind
+        #[arg(long, default_value_t)]
+        by: journal::ReportBy,
+        
+        /// all, day or month (UTC)
+        #[arg(long, default_value_t)]
+        period: journal::ReportPeriod,
+        
+        /// Also write the report as CSV to this file, costs in wei
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    
+    /// Write the journal to a file, JSON (the default, re-importable) or CSV for accounting
+    Export {
+        /// Destination file
+        file: PathBuf,
+        
+        /// json or csv, inferred from the file extension when omitted
+        #[arg(long)]
+        format: Option<journal::ExportFormat>,
+    },
+    
+    /// Merge a JSON export from another machine into the journal, pending operations included
+    Import {
+        /// JSON file written by `ops export`
+        file: PathBuf,
+    },
+    
+    /// Keep scanning for operations pending too long, diagnose them and remediate or alert
+    Watch {
+        /// Bundler RPC URL, also used for chain state
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Seconds an operation may stay pending before it counts as stuck
+        #[arg(long, default_value = "300")]
+        stuck_after: u64,
+        
+        /// Seconds between scans
+        #[arg(long, default_value = "60")]
+        interval: u64,
+        
+        /// URL receiving a JSON POST with the diagnosis of every stuck operation
+        #[arg(long)]
+        webhook: Option<String>,
+        
+        /// Mark operations that can never land (dropped, nonce already used) as failed in the journal
+        #[arg(long)]
+        remediate: bool,
+        
+        /// Scan once and exit, e.g. from cron
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CalldataCommands {
+    /// ABI-encode a call, e.g. `calldata encode "transfer(address,uint256)" 0xabc... 1000000`
+    Encode {
+        /// Function signature, parameter names optional
+        signature: String,
+        
+        /// One argument per parameter, in Solidity literal syntax
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccountCommands {
+    /// Deployment status, balance, EntryPoint deposits, nonces and owners in one view
+    Info {
+        /// Smart account address
+        #[arg(short, long, required_unless_present = "owner")]
+        address: Option<String>,
+        
+        /// Owner EOA, with --salt, to look up the account the factory creates for it
+        #[arg(short, long, conflicts_with = "address", requires = "salt")]
+        owner: Option<String>,
+        
+        /// Salt used with --owner (hex string)
+        #[arg(short, long)]
+        salt: Option<String>,
+        
+        /// Factory contract address used with --owner
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// Nonce keys to show the next nonce for (repeatable, uint192)
+        #[arg(long = "nonce-key", default_value = "0")]
+        nonce_keys: Vec<String>,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Also show the address as an EIP-681 payment QR code, to fund it from a mobile wallet
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// Accounts a factory created for an owner, from its creation events indexed under ~/.aa-client
+    List {
+        /// Owner EOA to list accounts of
+        #[arg(short, long)]
+        owner: String,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Block to scan from, e.g. the factory's deployment block [default: where the last sync stopped,
+        /// or 50000 blocks back on the first]
+        #[arg(long)]
+        from_block: Option<u64>,
+        
+        /// List what is already indexed without scanning new blocks
+        #[arg(long)]
+        no_sync: bool,
+    },
+    
+    /// Replace an owner with a new one in a single batched UserOperation
+    RotateOwner {
+        /// Private key of an existing owner (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Deployed smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Address taking over the seat
+        #[arg(long)]
+        new_owner: String,
+        
+        /// Owner being replaced [default: the signing owner]
+        #[arg(long)]
+        old_owner: Option<String>,
+        
+        /// The new owner's signature over its rotation challenge (printed when omitted)
+        #[arg(long)]
+        new_owner_signature: Option<String>,
+        
+        /// Factory contract address
+        #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+        factory: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+        
+        /// Tag recorded with the operation in the local journal (e.g. checkout)
+        #[arg(long, visible_alias = "label")]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Erc20Commands {
+    /// Transfer tokens from the smart account
+    Transfer {
+        /// Token contract address
+        #[arg(long)]
+        token: String,
+        
+        /// Recipient address
+        #[arg(long)]
+        to: String,
+        
+        /// Amount in whole tokens (e.g. 1.5), scaled by the token's decimals
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Allow a spender to transfer tokens from the smart account
+    Approve {
+        /// Token contract address
+        #[arg(long)]
+        token: String,
+        
+        /// Address allowed to spend the tokens
+        #[arg(long)]
+        spender: String,
+        
+        /// Allowance in whole tokens (e.g. 1.5), or `max` for an unlimited allowance
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Tokens a smart account holds
+    Balance {
+        /// Token contract address
+        #[arg(long)]
+        token: String,
+        
+        /// Smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Tokens a spender may still transfer from a smart account
+    Allowance {
+        /// Token contract address
+        #[arg(long)]
+        token: String,
+        
+        /// Smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Address allowed to spend the tokens
+        #[arg(long)]
+        spender: String,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NftCommands {
+    /// Send a token with safeTransferFrom, after checking the smart account holds it
+    Transfer {
+        /// Token standard: erc721 or erc1155
+        #[arg(long)]
+        standard: nft::NftStandard,
+        
+        /// NFT contract address
+        #[arg(long)]
+        token: String,
+        
+        /// Recipient address
+        #[arg(long)]
+        to: String,
+        
+        /// Token ID (decimal or 0x hex)
+        #[arg(long)]
+        id: String,
+        
+        /// Number of copies, ERC-1155 only
+        #[arg(long, default_value = "1")]
+        amount: String,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum LimitCommands {
+    /// Set the daily limit of a token, replacing the current one
+    Set {
+        /// Spending limit module contract address
+        #[arg(long)]
+        module: String,
+        
+        /// Token contract address [default: ETH]
+        #[arg(long)]
+        token: Option<String>,
+        
+        /// Daily limit in whole tokens (e.g. 1.5), scaled by the token's decimals
+        #[arg(long)]
+        daily: String,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Show the limit, what was spent and what remains today
+    Remaining {
+        /// Smart account address
+        #[arg(short, long)]
+        account: String,
+        
+        /// Spending limit module contract address
+        #[arg(long)]
+        module: String,
+        
+        /// Token contract address [default: ETH]
+        #[arg(long)]
+        token: Option<String>,
+        
+        /// Token decimals (read from the token when omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+    },
+    
+    /// Remove the limit of a token together with what was spent against it
+    Reset {
+        /// Spending limit module contract address
+        #[arg(long)]
+        module: String,
+        
+        /// Token contract address [default: ETH]
+        #[arg(long)]
+        token: Option<String>,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletCommands {
+    /// Encrypt an owner key with a password and store it under a name
+    Import {
+        /// Name to select the key with, e.g. deployer or operator
+        #[arg(short, long)]
+        name: String,
+        
+        /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
+        #[arg(short, long)]
+        private_key: Option<String>,
+    },
+    
+    /// List stored wallets and their addresses
+    List,
+}
+
+#[derive(Subcommand)]
+enum AbiCommands {
+    /// Register an ABI, e.g. `abi add ./out/MyToken.sol/MyToken.json --name mytoken --address 0x..`
+    Add {
+        /// ABI JSON: a plain ABI array or a Foundry or Hardhat artifact
+        file: PathBuf,
+        
+        /// Name to call the contract by
+        #[arg(long)]
+        name: String,
+        
+        /// Address the contract is deployed at, so `call` needs no --address
+        #[arg(long)]
+        address: Option<String>,
+    },
+    
+    /// List registered ABIs
+    List,
+    
+    /// Show the functions and events of a registered ABI
+    Show {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// Save an operation under a name, e.g. `template save mint --target 0x.. --function "mint(address to, uint256 id)"`
+    Save {
+        /// Template name
+        name: String,
+        
+        /// Contract the template calls
+        #[arg(short, long)]
+        target: String,
+        
+        /// Function with named parameters, which `run --arg` fills in
+        #[arg(long)]
+        function: String,
+        
+        /// ETH sent with every call, with a unit suffix: 0.1eth, 20gwei or 1000wei
+        #[arg(short, long)]
+        value: Option<String>,
+        
+        /// Argument fixed in the template as NAME=VALUE, repeat for each
+        #[arg(long = "arg", value_name = "NAME=VALUE")]
+        args: Vec<String>,
+        
+        /// Maximum fee per gas in wei (estimated on every run when omitted)
+        #[arg(long, conflicts_with = "sponsored")]
+        max_fee_per_gas: Option<String>,
+        
+        /// Maximum priority fee per gas in wei (estimated on every run when omitted)
+        #[arg(long, conflicts_with = "sponsored")]
+        max_priority_fee_per_gas: Option<String>,
+        
+        /// Have the paymaster service pay for gas
+        #[arg(long)]
+        sponsored: bool,
+        
+        /// Paymaster service URL
+        #[arg(long)]
+        paymaster_url: Option<String>,
+        
+        /// Deployed paymaster contract address
+        #[arg(long)]
+        paymaster_address: Option<String>,
+    },
+    
+    /// List saved templates and the arguments each one still needs
+    List,
+    
+    /// Delete a saved template
+    Remove {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PaymasterCommands {
+    /// Add ETH from the smart account to the paymaster's EntryPoint deposit
+    Deposit {
+        /// Paymaster contract address (SimplePaymaster or VerifierSignaturePaymaster)
+        #[arg(long)]
+        paymaster: String,
+        
+        /// Amount with a unit suffix: 0.1eth, 20gwei or 1000wei (bare numbers are wei)
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Seconds to wait for the receipt after submission (0 returns right away)
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+    
+    /// Show the paymaster's EntryPoint deposit and stake
+    Status {
+        /// Paymaster contract address
+        #[arg(long)]
+        paymaster: String,
+        
+        /// RPC URL for the network
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Chain ID, selects the EntryPoint
+        #[arg(short, long, default_value = "31337")]
+        chain_id: u64,
+    },
+    
+    /// Withdraw from the paymaster's EntryPoint deposit, the smart account must own the paymaster
+    Withdraw {
+        /// Paymaster contract address (SimplePaymaster or VerifierSignaturePaymaster)
+        #[arg(long)]
+        paymaster: String,
+        
+        /// Amount with a unit suffix: 0.1eth, 20gwei or 1000wei (bare numbers are wei)
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Recipient of the withdrawn ETH (defaults to the smart account)
+        #[arg(long)]
+        to: Option<String>,
+        
+        /// Seconds to wait for the receipt after submission (0 returns right away)
+        #[arg(long, default_value = "120")]
+        wait: u64,
+        
+        #[command(flatten)]
+        submit: SubmitArgs,
+    },
+}
+
+/// Account, network and sponsorship options shared by commands that submit a single call
+#[derive(Args)]
+struct SubmitArgs {
+    /// Private key in hex format (prefer --private-key-stdin or AA_PRIVATE_KEY)
+    #[arg(short, long)]
+    private_key: Option<String>,
+    
+    /// Factory contract address (needed to identify smart account)
+    #[arg(short, long, default_value = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512")]
+    factory: String,
+    
+    /// Salt for deterministic deployment (hex string, needed to identify smart account)
+    #[arg(short, long)]
+    salt: String,
+    
+    /// RPC URL for the network
+    #[arg(short, long, default_value = "http://localhost:8545")]
+    rpc_url: String,
+    
+    /// Chain ID
+    #[arg(short, long, default_value = "31337")]
+    chain_id: u64,
+    
+    /// Nonce key for a parallel stream of operations (uint192, default stream when omitted)
+    #[arg(long)]
+    nonce_key: Option<String>,
+    
+    /// Maximum fee per gas in wei (estimated from recent blocks when omitted)
+    #[arg(long, conflicts_with = "sponsored")]
+    max_fee_per_gas: Option<String>,
+    
+    /// Maximum priority fee per gas in wei (estimated from recent blocks when omitted)
+    #[arg(long, conflicts_with = "sponsored")]
+    max_priority_fee_per_gas: Option<String>,
+    
+    /// Have the paymaster service pay for gas
+    #[arg(long)]
+    sponsored: bool,
+    
+    /// Paymaster service URL
+    #[arg(long, default_value = "http://localhost:3000")]
+    paymaster_url: String,
+    
+    /// Paymaster API key
+    #[arg(long, default_value = "api_key_123")]
+    paymaster_api_key: String,
+    
+    /// Single-use pre-auth token or link from issue-preauth, sent instead of the API key
+    #[arg(long)]
+    preauth_token: Option<String>,
+    
+    /// Deployed paymaster contract address
+    #[arg(long, default_value = "0x0000000000000000000000000000000000000000")]
+    paymaster_address: String,
+    
+    /// Body encoding for paymaster service requests (json or cbor)
+    #[arg(long, default_value = "json")]
+    paymaster_encoding: paymaster::WireFormat,
+    
+    /// Seconds the paymaster sponsorship stays valid (see fee-advice for a recommendation)
+    #[arg(long, default_value = "3600")]
+    valid_for: u64,
+    
+    /// Tag recorded with the operation in the local journal (e.g. checkout)
+    #[arg(long, visible_alias = "label")]
+    tag: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum BundlerCommands {
+    /// Probe the primary and backup bundlers and show their health
+    Status {
+        /// Primary bundler RPC URL, backups come from --bundler or the profile's `bundlers`
+        #[arg(short, long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        
+        /// Only show recorded health, without contacting the endpoints
+        #[arg(long)]
+        no_probe: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    
+    // Output language: --lang, then AA_LANG, then LC_ALL/LC_MESSAGES/LANG
+    let lang = match cli.lang.clone().or_else(|| std::env::var("AA_LANG").ok()) {
+        Some(lang) => lang.parse()?,
+        None => i18n::Lang::from_env(),
+    };
+    i18n::init(lang);
+    output::set_plain(cli.plain);
+    let level = match (cli.verbose, cli.quiet) {
+        (true, _) => LevelFilter::DEBUG,
+        (_, true) => LevelFilter::WARN,
+        _ => LevelFilter::INFO,
+    };
+    let exporter = telemetry::init(cli.otlp_endpoint.as_deref(), level)?;
+    
+    // Profile values replace built-in defaults, never flags given explicitly
+    // A config file that does not parse only fails commands that select one of its profiles
+    let config = match CliConfig::load_default() {
+        Ok(config) => config,
+        Err(e) if cli.profile.is_none() => {
+            out!("⚠️  {}, ignoring it", e);
+            CliConfig::default()
+        }
+        Err(e) => return Err(e),
+    };
+    let profile = config.profile(cli.profile.as_deref())?;
+    if let (Some(profile), Some((_, sub_matches))) = (profile, matches.subcommand()) {
+        apply_profile(&mut cli.command, sub_matches, profile);
+    }
+    // A template's gas and sponsorship settings sit between explicit flags and the profile
+    if let (Commands::Run { name, submit, .. }, Some((_, sub_matches))) = (&mut cli.command, matches.subcommand()) {
+        apply_template(submit, sub_matches, &templates::TemplateStore::open_default()?.get(name)?);
+    }
+    
+    // Backup bundlers given as flags replace the profile's list
+    let backups = if cli.bundlers.is_empty() {
+        profile.and_then(|p| p.bundlers.clone()).unwrap_or_default()
+    } else {
+        cli.bundlers.clone()
+    };
+    // Private bundlers given as flags replace the profile's list as well
+    let private = match (cli.private, cli.private_bundlers.is_empty()) {
+        (false, _) => Vec::new(),
+        (true, false) => cli.private_bundlers.clone(),
+        (true, true) => profile
+            .and_then(|p| p.private_bundlers.clone())
+            .filter(|bundlers| !bundlers.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("--private needs --private-bundler URL or private_bundlers in the profile"))?,
+    };
+    bundler::set_failover(bundler::FailoverConfig { backups, race: cli.race_bundlers, private });
+    if let Some(now) = cli.fixed_time {
+        clock::set_fixed_time(now);
+    }
+    if let Some(seed) = cli.seed {
+        clock::set_seed(seed);
+    }
+    fees::set_urgency(cli.fee_urgency);
+    retry::set_policy(retry::RetryPolicy {
+        max_attempts: cli.max_attempts.max(1),
+        base_delay: std::time::Duration::from_millis(cli.retry_backoff_ms),
+        jitter: !cli.no_retry_jitter,
+        rate_limit_budget: std::time::Duration::from_secs(cli.rate_limit_budget),
+        ..retry::RetryPolicy::default()
+    });
+    preflight::set_skip(cli.no_preflight);
+    preflight::set_allow_eoa_target(cli.allow_eoa_target);
+    let value_limit = |flag: &Option<String>, key: &str, from_profile: Option<&String>| {
+        flag.as_ref()
+            .or(from_profile)
+            .map(|amount| units::parse_value(amount).map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e)))
+            .transpose()
+    };
+    validation::set_guard(validation::ValueGuard {
+        max_value: value_limit(&cli.max_value, "max_value", profile.and_then(|p| p.max_value.as_ref()))?,
+        confirm_above: value_limit(&cli.confirm_value_above, "confirm_value_above", profile.and_then(|p| p.confirm_value_above.as_ref()))?,
+        denylist: cli.deny_targets.iter().chain(profile.and_then(|p| p.denylist.as_ref()).into_iter().flatten()).copied().collect(),
+        confirmed: cli.yes,
+    });
+    preflight::set_dry_run(cli.dry_run);
+    sponsorship::set_responsor(cli.responsor);
+    paymaster::set_mode(cli.paymaster_mode.or(profile.and_then(|p| p.paymaster_mode)).unwrap_or_default());
+    nonce::set_recovery(cli.on_nonce_error);
+    if cli.nonce_cache {
+        nonce::set_manager(nonce::NonceManager::persistent()?);
+    }
+    if let Some(url) = &cli.remote_signer_url {
+        remote_signer::set_config(remote_signer::RemoteSignerConfig { url: url.clone(), address: cli.remote_signer_address });
+    }
+    if let Some(signature) = cli.dummy_signature.clone().or_else(|| profile.and_then(|p| p.dummy_signature.clone())) {
+        dummy_signature::set_override(signature);
+    }
+    if let Some(abi) = cli.factory_abi.or(profile.and_then(|p| p.factory_abi)) {
+        factory::set_override(abi);
+    }
+    entrypoint::set_selection(cli.entry_point_version.or(profile.and_then(|p| p.entry_point_version)), config.networks());
+    
+    // Central credentials resolution: --wallet or flag, stdin, AA_PRIVATE_KEY, then the profile's wallet or keystore
+    let private_key_stdin = cli.private_key_stdin;
+    let private_key_arg = |flag: &Option<String>| {
+        if remote_signer::config().is_some() {
+            return Err(anyhow::anyhow!(
+                "{} signs with a local key, --remote-signer-url is only supported by create --sign and op sign",
+                matches.subcommand_name().unwrap_or_default()
+            ));
+        }
+        if let Some(name) = &cli.wallet {
+            if flag.is_some() {
+                return Err(anyhow::anyhow!("--wallet and --private-key cannot be used together"));
+            }
+            return unlock_wallet(name);
+        }
+        wallet::resolve_private_key(flag.as_deref(), private_key_stdin, || match profile {
+            Some(Profile { wallet: Some(name), .. }) => unlock_wallet(name).map(Some),
+            _ => profile.map(|p| p.read_keystore()).transpose().map(Option::flatten),
+        })
+    };
+
+    // One span per command, so verbose output and library subscribers can attribute every line
+    let span = tracing::info_span!("command", name = %matches.subcommand_name().unwrap_or_default(), userop.hash = tracing::field::Empty);
+    let command = async {
+        match &cli.command {
+            Commands::Create { 
+                private_key, target, call_data, nonce, rpc_url, entry_point, chain_id, max_fee_per_gas, max_priority_fee_per_gas,
+                sender, value, call_gas_limit, verification_gas_limit, pre_verification_gas, sign, out,
+            } => match (sign, sender, out) {
+                (true, Some(sender), Some(out)) => {
+                    let operation = OfflineOperation {
+                        sender,
+                        value,
+                        nonce: *nonce,
+                        call_gas_limit: *call_gas_limit,
+                        verification_gas_limit: *verification_gas_limit,
+                        pre_verification_gas: *pre_verification_gas,
+                        max_fee_per_gas,
+                        max_priority_fee_per_gas,
+                    };
+                    create_signed_user_operation(&OwnerKey::resolve(|| private_key_arg(private_key)).await?, target, call_data, entry_point_arg(entry_point, *chain_id)?, *chain_id, operation, out).await?;
+                }
+                _ => {
+                    create_user_operation(&private_key_arg(private_key)?, target, call_data, *nonce, rpc_url, entry_point.as_deref(), *chain_id, max_fee_per_gas, max_priority_fee_per_gas).await?;
+                }
+            },
+            Commands::SubmitFile { file, rpc_url, tag } => {
+                submit_signed_file(file, rpc_url, tag.as_deref()).await?;
+            }
+            Commands::ImportOp { file, out, entry_point, chain_id } => {
+                import_user_operation(file, out, entry_point_arg(entry_point, *chain_id)?, *chain_id)?;
+            }
+            Commands::ExportOp { file, out } => {
+                export_user_operation(file, out.as_deref())?;
+            }
+
+            Commands::Submit {
+                private_key, target, call_data, factory, salt, session_key, rpc_url, entry_point, chain_id, value, nonce_key, max_fee_per_gas, max_priority_fee_per_gas,
+                account, paymaster, paymaster_token, paymaster_allowance, tag
+            } => {
+                let native = config.network(*chain_id).is_ok_and(|network| network.native_aa);
+                if native && (nonce_key.is_some() || session_key.is_some()) {
+                    return Err(anyhow::anyhow!("--nonce-key and --session-key need an ERC-4337 EntryPoint, zkSync accounts are native"));
+                }
+                // The journal tracks UserOperations, which ops list, report and watch look up by their hash
+                if native && tag.is_some() {
+                    return Err(anyhow::anyhow!("--tag labels journaled UserOperations, zkSync transactions are not journaled"));
+                }
+                if !native && (account.is_some() || paymaster.is_some()) {
+                    return Err(anyhow::anyhow!("--account and --paymaster apply to zkSync networks, use submit-sponsored for ERC-4337 paymasters"));
+                }
+                let nonce_key = nonce_key.as_deref().map(nonce::parse_key).transpose()?;
+                match (session_key, salt) {
+                    // zkSync accounts are native, so the call goes out as a type 113 transaction instead of a UserOperation
+                    _ if native => {
+                        let paymaster = zksync_paymaster(*paymaster, *paymaster_token, paymaster_allowance.as_deref())?;
+                        submit_native_transaction(
+                            &private_key_arg(private_key)?, *account, target, call_data, value, rpc_url, *chain_id, paymaster,
+                            max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref()
+                        ).await?;
+                    }
+                    (Some(session_key), _) => {
+                        submit_with_session_key(session_key, target, call_data, value, factory, rpc_url, entry_point, nonce_key, max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), tag.as_deref()).await?;
+                    }
+                    (None, Some(salt)) => {
+                        submit_user_operation_fixed(&private_key_arg(private_key)?, target, call_data, value, factory, salt, rpc_url, entry_point_arg(entry_point, *chain_id)?, *chain_id, nonce_key, max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), tag.as_deref()).await?;
+                    }
+                    (None, None) => return Err(anyhow::anyhow!("--salt is required without --session-key")),
+                }
+            }
+            Commands::DeployAccount { private_key, factory, salt, salt_random, rpc_url, chain_id, max_fee_per_gas, max_priority_fee_per_gas, tag } => {
+                let salt = deployment_salt(salt.as_deref(), *salt_random)?;
+                deploy_smart_account(&private_key_arg(private_key)?, factory, &salt, rpc_url, *chain_id, max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), tag.as_deref()).await?;
+            }
+            Commands::DeployMultiOwnerAccount { private_key, factory, owners, threshold, salt, salt_random, rpc_url, chain_id, tag } => {
+                let salt = deployment_salt(salt.as_deref(), *salt_random)?;
+                deploy_multi_owner_account(&private_key_arg(private_key)?, factory, owners, *threshold, &salt, rpc_url, *chain_id, tag.as_deref()).await?;
+            }
+            Commands::AddOwner { private_key, account, owner, factory, rpc_url, entry_point, chain_id, max_fee_per_gas, max_priority_fee_per_gas, tag } => {
+                change_account_owner(
+                    &private_key_arg(private_key)?, account, factory, rpc_url, entry_point_arg(entry_point, *chain_id)?, *chain_id,
+                    max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), OwnerChange::Add(owner), tag.as_deref()
+                ).await?;
+            }
+            Commands::RemoveOwner { private_key, account, owner, factory, rpc_url, entry_point, chain_id, max_fee_per_gas, max_priority_fee_per_gas, tag } => {
+                change_account_owner(
+                    &private_key_arg(private_key)?, account, factory, rpc_url, entry_point_arg(entry_point, *chain_id)?, *chain_id,
+                    max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), OwnerChange::Remove(owner), tag.as_deref()
+                ).await?;
+            }
+            Commands::SetThreshold { private_key, account, threshold, factory, rpc_url, entry_point, chain_id, max_fee_per_gas, max_priority_fee_per_gas, tag } => {
+                change_account_owner(
+                    &private_key_arg(private_key)?, account, factory, rpc_url, entry_point_arg(entry_point, *chain_id)?, *chain_id,
+                    max_fee_per_gas.as_deref(), max_priority_fee_per_gas.as_deref(), OwnerChange::Threshold(*threshold), tag.as_deref()
+                ).await?;
+            }
+            Commands::ListOwners { account, rpc_url } => {
+                list_account_owners(account, rpc_url).await?;
+            }
+            Commands::EntryPoints { account, rpc_url } => {
+                show_entry_points(account, rpc_url).await?;
+            }
+            Commands::MigrateEntryPoint { private_key, account, to, from, amount, set_default, factory, rpc_url, chain_id, tag } => {
+                // Re-pointing targets the profile in use, or a "default" profile when none is configured
+                let profile_name = set_default.then(|| {
+                    cli.profile.clone().or_else(|| config.default_profile.clone()).unwrap_or_else(|| "default".to_string())
+                });
+                let migration = EntryPointMigration { to: *to, from: *from, amount: amount.as_deref(), profile_name };
+                migrate_entry_point(&private_key_arg(private_key)?, account, factory, rpc_url, *chain_id, migration, tag.as_deref()).await?;
+            }
+            Commands::PredictAddress { factory, owner, owners, salt, proxy_artifact, rpc_url, chain_id, qr } => match (owner, owners) {
+                (_, Some(owners)) => predict_multi_owner_address(factory, owners, salt, proxy_artifact.as_deref(), rpc_url, *chain_id, *qr).await?,
+                (Some(owner), None) => predict_smart_account_address(factory, owner, salt, rpc_url, *chain_id, *qr).await?,
+                (None, None) => return Err(anyhow::anyhow!("Pass --owner, or --owners for a multi-owner account")),
+            },
+            Commands::Tui => {
+                let private_key = match private_key_arg(&None) {
+                    Ok(key) => key,
+                    Err(_) => rpassword::prompt_password("Owner private key (not shown): ")?,
+                };
+                run_tui(&config, &private_key).await?;
+            }
+            Commands::Wallet { command } => match command {
+                WalletCommands::Import { name, private_key } => {
+                    let private_key = private_key_arg(private_key)?;
+                    let store = wallet_store::WalletStore::open_default()?;
+                    let password = wallet_store::password(&format!("New password for wallet '{}': ", name), true)?;
+                    let address = store.import(name, &private_key, &password)?;
+                    out!("🔐 Wallet '{}' ({}) stored in {}", name, address, store.dir().display());
+                    out!("💡 Select it with --wallet {}", name);
+                }
+                WalletCommands::List => {
+                    let wallets = wallet_store::WalletStore::open_default()?.list()?;
+                    if wallets.is_empty() {
+                        out!("No wallets stored, add one with `wallet import --name <NAME>`");
+                    }
+                    for (name, address) in wallets {
+                        out!("{}  {}", name, address);
+                    }
+                }
+            },
+            Commands::GenerateWallet { qr } => {
+                generate_wallet(*qr).await?;
+            }
+            Commands::Info { private_key } => {
+                show_wallet_info(&private_key_arg(private_key)?)?;
+            }
+
+            Commands::Networks => {
+                show_network_presets(&config)?;
+            }
+            Commands::SubmitSponsored { 
+                private_key, target, call_data, factory, salt, eip7702, rpc_url, chain_id, value, nonce_key,
+                paymaster_url, paymaster_api_key, preauth_token, paymaster_address, paymaster_encoding, valid_for, tag
+            } => {
+                let nonce_key = nonce_key.as_deref().map(nonce::parse_key).transpose()?;
+                match eip7702 {
+                    Some(delegate) => submit_delegated_sponsored(
+                        &private_key_arg(private_key)?, delegate, target, call_data, value, rpc_url, *chain_id, nonce_key,
+                        paymaster_url, paymaster_api_key, preauth_token.as_deref(), paymaster_address, *paymaster_encoding, *valid_for, tag.as_deref()
+                    ).await?,
+                    None => submit_sponsored_user_operation(
+                        &private_key_arg(private_key)?, target, call_data, value, factory, salt.as_deref().unwrap_or_default(), rpc_url, *chain_id, nonce_key,
+                        paymaster_url, paymaster_api_key, preauth_token.as_deref(), paymaster_address, *paymaster_encoding, *valid_for, tag.as_deref()
+                    ).await?,
+                };
+            }
+            Commands::DeploySponsored {
+                private_key, factory, salt, rpc_url, chain_id, 
+                paymaster_url, paymaster_api_key, preauth_token, paymaster_address, paymaster_encoding, valid_for, tag
+            } => {
+                deploy_sponsored_smart_account(
+                    &private_key_arg(private_key)?, factory, salt, rpc_url, *chain_id,
+                    paymaster_url, paymaster_api_key, preauth_token.as_deref(), paymaster_address, *paymaster_encoding, *valid_for, tag.as_deref()
+                ).await?;
+            }
+            Commands::Onboard {
+                private_key, name, target, call_data, value, factory, salt, rpc_url, chain_id,
+                paymaster_url, paymaster_api_key, paymaster_address, paymaster_encoding, valid_for, wait
+            } => {
+                // A key given any of the usual ways is onboarded as is, otherwise one is generated and kept as `name`
+                let supplied = private_key.is_some()
+                    || private_key_stdin
+                    || cli.wallet.is_some()
+                    || std::env::var(wallet::PRIVATE_KEY_ENV).is_ok()
+                    || profile.is_some_and(|p| p.wallet.is_some() || p.keystore.is_some());
+                let owner = if supplied { private_key_arg(private_key)? } else { onboarding_owner(name)? };
+                let sponsorship = Onboarding {
+                    factory, salt, rpc_url, chain_id: *chain_id, paymaster_url, paymaster_api_key, paymaster_address,
+                    paymaster_encoding: *paymaster_encoding, valid_for: *valid_for, wait: *wait,
+                };
+                let first_call = target.as_deref().map(|target| (target, call_data.as_str(), value.as_str()));
+                onboard_account(&owner, &sponsorship, first_call).await?;
+            }
+            Commands::IssuePreauth { sender, ttl, paymaster_url, paymaster_api_key, paymaster_encoding, qr } => {
+                issue_preauth_token(sender, *ttl, paymaster_url, paymaster_api_key, *paymaster_encoding, *qr).await?;
+            }
+            Commands::Scan { input, out } => {
+                scan_qr_chunks(input.as_deref(), out.as_deref())?;
+            }
+            Commands::Op { command } => match command {
+                OpCommands::Propose {
+                    account, target, call_data, value, file, rpc_url, chain_id,
+                    call_gas_limit, verification_gas_limit, pre_verification_gas, max_fee_per_gas, max_priority_fee_per_gas, qr
+                } => {
+                    let gas = ProposalGas {
+                        call_gas_limit: *call_gas_limit,
+                        verification_gas_limit: *verification_gas_limit,
+                        pre_verification_gas: *pre_verification_gas,
+                        max_fee_per_gas: max_fee_per_gas.as_deref(),
+                        max_priority_fee_per_gas: max_priority_fee_per_gas.as_deref(),
+                    };
+                    propose_operation(account, target, call_data, value, file, rpc_url, *chain_id, gas).await?;
+                    if *qr {
+                        show_proposal_qr(file)?;
+                    }
+                }
+                OpCommands::Rebuild { context, file } => {
+                    rebuild_proposal(context, file)?;
+                }
+                OpCommands::Sign { private_key, file, qr } => {
+                    sign_proposal(&OwnerKey::resolve(|| private_key_arg(private_key)).await?, file).await?;
+                    if *qr {
+                        show_proposal_qr(file)?;
+                    }
+                }
+                OpCommands::Submit { file, threshold, rpc_url, tag } => {
+                    submit_proposal(file, *threshold, rpc_url, tag.as_deref()).await?;
+                }
+            },
+            Commands::Session { command } => match command {
+                SessionCommands::Create { private_key, account, target, selector, valid_for, max_value, max_ops, out, factory, rpc_url, chain_id, tag } => {
+                    let scope = SessionScope { target, selector, valid_for: *valid_for, max_value, max_ops: *max_ops };
+                    create_session_key(&private_key_arg(private_key)?, account, scope, out, factory, rpc_url, *chain_id, tag.as_deref()).await?;
+                }
+                SessionCommands::Revoke { private_key, account, key, factory, rpc_url, chain_id, tag } => {
+                    revoke_session_key(&private_key_arg(private_key)?, account, key, factory, rpc_url, *chain_id, tag.as_deref()).await?;
+                }
+            },
+            Commands::Recovery { command } => match command {
+                RecoveryCommands::AddGuardian { private_key, account, guardian, threshold, delay, factory, rpc_url, chain_id, tag } => {
+                    let config = threshold.map(|threshold| (threshold, *delay));
+                    add_recovery_guardian(&private_key_arg(private_key)?, account, guardian, config, factory, rpc_url, *chain_id, tag.as_deref()).await?;
+                }
+                RecoveryCommands::Initiate { private_key, account, new_owners, rpc_url } => {
+                    send_guardian_action(&private_key_arg(private_key)?, account, GuardianAction::Initiate(new_owners), rpc_url).await?;
+                }
+                RecoveryCommands::Approve { private_key, account, rpc_url } => {
+                    send_guardian_action(&private_key_arg(private_key)?, account, GuardianAction::Approve, rpc_url).await?;
+                }
+                RecoveryCommands::Execute { private_key, account, rpc_url } => {
+                    send_guardian_action(&private_key_arg(private_key)?, account, GuardianAction::Execute, rpc_url).await?;
+                }
+                RecoveryCommands::Cancel { private_key, account, factory, rpc_url, chain_id, tag } => {
+                    cancel_recovery(&private_key_arg(private_key)?, account, factory, rpc_url, *chain_id, tag.as_deref()).await?;
+                }
+                RecoveryCommands::Status { account, rpc_url } => {
+                    show_recovery_status(account, rpc_url).await?;
+                }
+            },
+            Commands::Ops { command } => match command {
+                OpsCommands::List { tag } => {
+                    list_journaled_operations(tag.as_deref())?;
+                }
+                OpsCommands::Report { by, period, csv } => {
+                    show_gas_report(*by, *period, csv.as_deref())?;
+                }
+                OpsCommands::Export { file, format } => {
+                    export_journal(file, *format)?;
+                }
+                OpsCommands::Import { file } => {
+                    import_journal(file)?;
+                }
+                OpsCommands::Watch { rpc_url, stuck_after, interval, webhook, remediate, once } => {
+                    let settings = WatchSettings {
+                        stuck_after: *stuck_after,
+                        interval: *interval,
+                        webhook: webhook.as_deref(),
+                        remediate: *remediate,
+                        once: *once,
+                    };
+                    watch_operations(rpc_url, settings).await?;
+                }
+            },
+            Commands::Watch { daemon: keep_running, rpc, interval, bump_after, bump_percent, max_fee_cap, status_addr, private_key } => {
+                if *bump_percent < daemon::MIN_BUMP_PERCENT {
+                    return Err(anyhow::anyhow!("--bump-percent must be at least {}, bundlers reject smaller replacements", daemon::MIN_BUMP_PERCENT));
+                }
+                let bump = match bump_after {
+                    Some(after_secs) => {
+                        let policy = daemon::BumpPolicy {
+                            after_secs: *after_secs,
+                            percent: *bump_percent,
+                            max_fee_cap: max_fee_cap.as_deref().map(|cap| units::parse_units(cap, 9)).transpose()?,
+                        };
+                        Some((policy, Wallet::from_hex(&private_key_arg(private_key)?)?))
+                    }
+                    None => None,
+                };
+                let endpoints = daemon::endpoints(&config, rpc)?;
+                watch_journal(&config, &endpoints, *interval, bump.as_ref(), keep_running.then_some(status_addr)).await?;
+            }
+            Commands::Calldata { command } => match command {
+                CalldataCommands::Encode { signature, args } => {
+                    out!("0x{}", hex::encode(calldata::encode(signature, args)?));
+                }
+            },
+            Commands::Bundlers { command } => match command {
+                BundlerCommands::Status { rpc_url, no_probe } => {
+                    show_bundler_status(rpc_url, *no_probe).await?;
+                }
+            },
+            Commands::FeeAdvice { rpc_url, chain_id, blocks } => {
+                show_fee_advice(rpc_url, *chain_id, *blocks).await?;
+            }
+            Commands::Account { command } => match command {
+                AccountCommands::Info { address, owner, salt, factory, nonce_keys, rpc_url, qr } => {
+                    show_account_info(address.as_deref(), owner.as_deref().zip(salt.as_deref()), factory, nonce_keys, rpc_url, *qr).await?;
+                }
+                AccountCommands::List { owner, factory, rpc_url, from_block, no_sync } => {
+                    list_owned_accounts(owner, factory, rpc_url, *from_block, *no_sync).await?;
+                }
+                AccountCommands::RotateOwner { private_key, account, new_owner, old_owner, new_owner_signature, factory, rpc_url, chain_id, tag } => {
+                    let rotation = OwnerRotation {
+                        account,
+                        new_owner,
+                        old_owner: old_owner.as_deref(),
+                        new_owner_signature: new_owner_signature.as_deref(),
+                    };
+                    rotate_account_owner(&private_key_arg(private_key)?, rotation, factory, rpc_url, *chain_id, tag.as_deref()).await?;
+                }
+            },
+            Commands::Send { to, value, wait, submit } => {
+                let amount = units::parse_value(value)?;
+                let to_addr = Address::from_str(to)?;
+                out!("💸 Sending {} ETH to {}", units::format_units(amount, 18), to_addr);
+                let pending = submit_call(submit, &private_key_arg(&submit.private_key)?, to, &Bytes::new(), &amount.to_string()).await?;
+                if let (Some(user_op_hash), true) = (pending, *wait > 0) {
+                    wait_for_receipt(&submit.rpc_url, &user_op_hash, *wait).await?;
+                }
+            }
+            Commands::Template { command } => match command {
+                TemplateCommands::Save {
+                    name, target, function, value, args, max_fee_per_gas, max_priority_fee_per_gas, sponsored, paymaster_url, paymaster_address
+                } => {
+                    let template = templates::Template {
+                        target: target.clone(),
+                        function: function.clone(),
+                        value: value.clone(),
+                        args: templates::parse_args(args)?,
+                        max_fee_per_gas: max_fee_per_gas.clone(),
+                        max_priority_fee_per_gas: max_priority_fee_per_gas.clone(),
+                        sponsored: *sponsored,
+                        paymaster_url: paymaster_url.clone(),
+                        paymaster_address: paymaster_address.clone(),
+                    };
+                    let variables = template.variables()?;
+                    if templates::TemplateStore::open_default()?.save(name, template)? {
+                        out!("📋 Replaced template '{}'", name);
+                    } else {
+                        out!("📋 Saved template '{}'", name);
+                    }
+                    let usage: Vec<String> = variables.iter().map(|variable| format!("--arg {}=...", variable)).collect();
+                    out!("💡 Run it with: aa-client run {} {}", name, usage.join(" "));
+                }
+                TemplateCommands::List => {
+                    let templates = templates::TemplateStore::open_default()?.list()?;
+                    if templates.is_empty() {
+                        out!("No templates saved, create one with `template save`");
+                    }
+                    for (name, template) in templates {
+                        let sponsored = if template.sponsored { ", sponsored" } else { "" };
+                        out!("{}: {} on {}{}", name, template.function, template.target, sponsored);
+                        out!("  Arguments: {}", template.variables()?.join(", "));
+                    }
+                }
+                TemplateCommands::Remove { name } => {
+                    templates::TemplateStore::open_default()?.remove(name)?;
+                    out!("🗑️  Removed template '{}'", name);
+                }
+            },
+            Commands::Run { name, args, wait, submit } => {
+                let template = templates::TemplateStore::open_default()?.get(name)?;
+                let call_data = template.call_data(&templates::parse_args(args)?)?;
+                let value = template.value.as_deref().map(units::parse_value).transpose()?.unwrap_or_default();
+                out!("📋 Running template '{}': {} on {}", name, template.function, template.target);
+                let pending = submit_call(submit, &private_key_arg(&submit.private_key)?, &template.target, &call_data, &value.to_string()).await?;
+                if let (Some(user_op_hash), true) = (pending, *wait > 0) {
+                    wait_for_receipt(&submit.rpc_url, &user_op_hash, *wait).await?;
+                }
+            }
+            Commands::Abi { command } => match command {
+                AbiCommands::Add { file, name, address } => {
+                    let address = address.as_deref().map(Address::from_str).transpose()?;
+                    let store = abis::AbiStore::open_default()?;
+                    if store.add(name, file, address)? {
+                        out!("📜 Replaced ABI '{}'", name);
+                    } else {
+                        out!("📜 Added ABI '{}'", name);
+                    }
+                    let contract = store.get(name)?;
+                    out!("💡 Call it with: aa-client call {} <function> <args...>{}", name, if address.is_some() { "" } else { " --address 0x..." });
+                    out!("  Functions: {}", contract.abi.functions().map(abis::describe).collect::<Vec<_>>().join(", "));
+                }
+                AbiCommands::List => {
+                    let store = abis::AbiStore::open_default()?;
+                    let names = store.list()?;
+                    if names.is_empty() {
+                        out!("No ABIs added, register one with `abi add`");
+                    }
+                    for name in names {
+                        let contract = store.get(&name)?;
+                        let address = contract.address.map(|a| a.to_string()).unwrap_or_else(|| "no address".to_string());
+                        out!("{}: {} ({} functions, {} events)", name, address, contract.abi.functions().count(), contract.abi.events().count());
+                    }
+                }
+                AbiCommands::Show { name } => {
+                    let contract = abis::AbiStore::open_default()?.get(name)?;
+                    if let Some(address) = contract.address {
+                        out!("Address: {}", address);
+                    }
+                    out!("Functions:");
+                    for function in contract.abi.functions() {
+                        let kind = if abis::is_read_only(function) { " (view)" } else { "" };
+                        out!("  {}{}", abis::describe(function), kind);
+                    }
+                    out!("Events:");
+                    for event in contract.abi.events() {
+                        out!("  {}", event.signature());
+                    }
+                }
+            },
+            Commands::Call { contract, function, args, address, value, wait, submit } => {
+                let registered = abis::AbiStore::open_default()?.get(contract)?;
+                let target = match (address, registered.address) {
+                    (Some(address), _) => Address::from_str(address)?,
+                    (None, Some(address)) => address,
+                    (None, None) => return Err(anyhow::anyhow!("ABI '{}' was added without an address, pass --address", contract)),
+                };
+                let function = registered.function(function, args.len())?;
+                let call_data = registered.encode_call(function, args)?;
+
+                if abis::is_read_only(function) {
+                    let provider = providers::http(&submit.rpc_url)?;
+                    for line in abis::call_view(&provider, target, function, call_data).await? {
+                        out!("{}", line);
+                    }
+                } else {
+                    let amount = units::parse_value(value)?;
+                    out!("📜 Calling {} on {} ({})", abis::describe(function), contract, target);
+                    let pending = submit_call(submit, &private_key_arg(&submit.private_key)?, &target.to_string(), &call_data, &amount.to_string()).await?;
+                    if let (Some(user_op_hash), true) = (pending, *wait > 0) {
+                        let receipt = wait_for_receipt(&submit.rpc_url, &user_op_hash, *wait).await?;
+                        for log in receipt.logs.iter().filter(|log| log.address == target) {
+                            if let Some(event) = registered.decode_log(&log.topics, &log.data) {
+                                out!("  📣 {}", event);
+                            }
+                        }
+                    }
+                }
+            }
+            Commands::Erc20 { command } => match command {
+                Erc20Commands::Transfer { token, to, amount, decimals, submit } => {
+                    let token_addr = Address::from_str(token)?;
+                    let amount = token_amount(amount, *decimals, token_addr, &submit.rpc_url).await?;
+                    let call_data = erc20::transfer_call_data(Address::from_str(to)?, amount);
+                    out!("🪙 Transferring {} base units of {} to {}", amount, token_addr, to);
+                    submit_call(submit, &private_key_arg(&submit.private_key)?, token, &call_data, "0").await?;
+                }
+                Erc20Commands::Approve { token, spender, amount, decimals, submit } => {
+                    let token_addr = Address::from_str(token)?;
+                    let amount = if amount.eq_ignore_ascii_case("max") {
+                        U256::MAX
+                    } else {
+                        token_amount(amount, *decimals, token_addr, &submit.rpc_url).await?
+                    };
+                    let call_data = erc20::approve_call_data(Address::from_str(spender)?, amount);
+                    out!("🪙 Approving {} to spend {} base units of {}", spender, amount, token_addr);
+                    submit_call(submit, &private_key_arg(&submit.private_key)?, token, &call_data, "0").await?;
+                }
+                Erc20Commands::Balance { token, account, decimals, rpc_url } => {
+                    show_token_amount(token, account, None, *decimals, rpc_url).await?;
+                }
+                Erc20Commands::Allowance { token, account, spender, decimals, rpc_url } => {
+                    show_token_amount(token, account, Some(spender), *decimals, rpc_url).await?;
+                }
+            },
+            Commands::Nft { command } => match command {
+                NftCommands::Transfer { standard, token, to, id, amount, submit } => {
+                    let private_key = private_key_arg(&submit.private_key)?;
+                    let (token_addr, to_addr) = (Address::from_str(token)?, Address::from_str(to)?);
+                    let (id, amount) = (U256::from_str(id)?, U256::from_str(amount)?);
+                    
+                    let wallet = Wallet::from_hex(&private_key)?;
+                    let bundler_client = bundler_at(submit.rpc_url.clone(), Address::ZERO, U256::from(submit.chain_id));
+                    let account_addr = bundler_client
+                        .get_predicted_address(Address::from_str(&submit.factory)?, wallet.address(), hexutil::parse_salt(&submit.salt)?)
+                        .await?;
+                    let call_data = nft::transfer_call_data(*standard, account_addr, to_addr, id, amount)?;
+                    let provider = providers::http(&submit.rpc_url)?;
+                    nft::check_ownership(&provider, *standard, token_addr, account_addr, id, amount).await?;
+                    
+                    match standard {
+                        nft::NftStandard::Erc721 => out!("🖼️  Transferring token {} of {} to {}", id, token_addr, to_addr),
+                        nft::NftStandard::Erc1155 => out!("🖼️  Transferring {} of token {} of {} to {}", amount, id, token_addr, to_addr),
+                    }
+                    submit_call(submit, &private_key, token, &call_data, "0").await?;
+                }
+            },
+            Commands::Connect { uri, project_id, relay_url, wait, submit } => {
+                connect_dapp(submit, uri, project_id.as_deref(), relay_url, *wait, cli.yes).await?;
+            }
+            Commands::Serve { port, wait, submit } => {
+                serve_rpc(submit, *port, *wait, cli.yes).await?;
+            }
+            Commands::Limits { command } => match command {
+                LimitCommands::Set { module, token, daily, decimals, submit } => {
+                    let token_addr = limit_token(token.as_deref())?;
+                    let decimals = if token_addr.is_zero() { Some(18) } else { *decimals };
+                    let limit = token_amount(daily, decimals, token_addr, &submit.rpc_url).await?;
+                    out!("🚦 Setting the daily limit of {} to {} base units", token_name(token_addr), limit);
+                    let call_data = spending_limit::set_limit_call_data(token_addr, limit);
+                    submit_call(submit, &private_key_arg(&submit.private_key)?, module, &call_data, "0").await?;
+                }
+                LimitCommands::Remaining { account, module, token, decimals, rpc_url } => {
+                    show_spending_limit(account, module, token.as_deref(), *decimals, rpc_url).await?;
+                }
+                LimitCommands::Reset { module, token, submit } => {
+                    let token_addr = limit_token(token.as_deref())?;
+                    out!("🚦 Resetting the daily limit of {}", token_name(token_addr));
+                    let call_data = spending_limit::reset_limit_call_data(token_addr);
+                    submit_call(submit, &private_key_arg(&submit.private_key)?, module, &call_data, "0").await?;
+                }
+            },
+            Commands::Paymaster { command } => match command {
+                PaymasterCommands::Deposit { paymaster, amount, wait, submit } => {
+                    let amount = units::parse_value(amount)?;
+                    let paymaster_addr = Address::from_str(paymaster)?;
+                    out!("⛽ Depositing {} ETH for paymaster {}", units::format_units(amount, 18), paymaster_addr);
+                    let call_data = paymaster_funds::deposit_call_data();
+                    let pending = submit_call(submit, &private_key_arg(&submit.private_key)?, paymaster, &call_data, &amount.to_string()).await?;
+                    if let (Some(user_op_hash), true) = (pending, *wait > 0) {
+                        wait_for_receipt(&submit.rpc_url, &user_op_hash, *wait).await?;
+                    }
+                }
+                PaymasterCommands::Status { paymaster, rpc_url, chain_id } => {
+                    let paymaster_addr = Address::from_str(paymaster)?;
+                    let entry_point = entrypoint::resolve(*chain_id)?;
+                    let provider = providers::http(rpc_url)?;
+                    let backing = paymaster_funds::backing(&provider, entry_point, paymaster_addr).await?;
+                    out!("Paymaster: {}", paymaster_addr);
+                    out!("EntryPoint: {}", entry_point);
+                    out!("Deposit: {} ETH", units::format_units(backing.deposit, 18));
+                    let stake_state = if backing.staked { "staked" } else { "not staked" };
+                    out!("Stake: {} ETH ({}, unstake delay {}s)", units::format_units(backing.stake, 18), stake_state, backing.unstake_delay_sec);
+                    for warning in backing.warnings(paymaster_addr, U256::ZERO) {
+                        out!("⚠️  {}", warning);
+                    }
+                }
+                PaymasterCommands::Withdraw { paymaster, amount, to, wait, submit } => {
+                    let private_key = private_key_arg(&submit.private_key)?;
+                    let amount = units::parse_value(amount)?;
+                    let paymaster_addr = Address::from_str(paymaster)?;
+                    
+                    let wallet = Wallet::from_hex(&private_key)?;
+                    let bundler_client = bundler_at(submit.rpc_url.clone(), Address::ZERO, U256::from(submit.chain_id));
+                    let account_addr = bundler_client
+                        .get_predicted_address(Address::from_str(&submit.factory)?, wallet.address(), hexutil::parse_salt(&submit.salt)?)
+                        .await?;
+                    let recipient = to.as_deref().map(Address::from_str).transpose()?.unwrap_or(account_addr);
+                    let provider = providers::http(&submit.rpc_url)?;
+                    paymaster_funds::check_withdrawal(&provider, paymaster_addr, account_addr, amount).await?;
+                    
+                    out!("⛽ Withdrawing {} ETH from paymaster {} to {}", units::format_units(amount, 18), paymaster_addr, recipient);
+                    let call_data = paymaster_funds::withdraw_call_data(recipient, amount);
+                    let pending = submit_call(submit, &private_key, paymaster, &call_data, "0").await?;
+                    if let (Some(user_op_hash), true) = (pending, *wait > 0) {
+                        wait_for_receipt(&submit.rpc_url, &user_op_hash, *wait).await?;
+                    }
+                }
+            },
+            Commands::Hash { file, entry_point, chain_id } => {
+                show_user_op_hash(file, entry_point_arg(entry_point, *chain_id)?, *chain_id)?;
+            }
+            Commands::Analyze { file, l1_base_fee, rpc_url } => {
+                analyze_user_operation(file, l1_base_fee.as_deref(), rpc_url.as_deref()).await?;
+            }
+            Commands::Verify1271 { contract, hash, message, signature, rpc_url } => {
+                verify_1271(contract, hash.as_deref(), message.as_deref(), signature, rpc_url).await?;
+            }
+            Commands::SignMessage { private_key, message, hex, account } => {
+                let message = if *hex { hexutil::decode("message", message)? } else { message.as_bytes().to_vec() };
+                let wallet = Wallet::from_hex(&private_key_arg(private_key)?)?;
+                sign_off_chain(&wallet, message::message_hash(&message), account).await?;
+            }
+            Commands::SignTypedData { private_key, file, account } => {
+                let json = std::fs::read_to_string(file)
+                    .map_err(|e| anyhow::anyhow!("Cannot read typed data {}: {}", file.display(), e))?;
+                let wallet = Wallet::from_hex(&private_key_arg(private_key)?)?;
+                sign_off_chain(&wallet, message::typed_data_hash(&json)?, account).await?;
+            }
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+    if let Err(e) = command.instrument(span).await {
+        // Failures the command already explained are not printed twice
+        if e.downcast_ref::<exit::Reported>().is_none() {
+            eprintln!("Error: {:?}", e);
+        }
+        // exit skips destructors, the failed command's trace is exported first
+        drop(exporter);
+        std::process::exit(exit::code(&e));
+    }
+
+    let stats = cache::cache_stats();
+    tracing::debug!("🗄️  RPC cache: {} hits, {} misses", stats.hits, stats.misses);
+
+    Ok(())
+}
+
+/// Replace clap defaults with values from the selected profile
+/// Only arguments still at their built-in default are touched, so explicit flags keep precedence
+fn apply_profile(command: &mut Commands, matches: &ArgMatches, profile: &Profile) {
+    fn from_profile<T: Clone>(matches: &ArgMatches, id: &str, field: &mut T, value: Option<&T>) {
+        let explicit = match matches.value_source(id) {
+            Some(ValueSource::DefaultValue) | None => None,
+            Some(_) => Some(field.clone()),
+        };
+        *field = config::resolve(explicit, value.cloned(), field.clone());
+    }
+    
+    let rpc = profile.rpc_url.as_ref();
+    let chain = profile.chain_id.as_ref();
+    let factory_addr = profile.factory.as_ref();
+    let entry = profile.entry_point.clone().map(Some);
+    let entry = entry.as_ref();
+    let pm_url = profile.paymaster_url.as_ref();
+    
+    match command {
+        Commands::Create { rpc_url, entry_point, chain_id, .. } => {
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "entry_point", entry_point, entry);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::Submit { factory, rpc_url, entry_point, chain_id, .. } => {
+            from_profile(matches, "factory", factory, factory_addr);
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "entry_point", entry_point, entry);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::DeployAccount { factory, rpc_url, chain_id, .. }
+        | Commands::DeployMultiOwnerAccount { factory, rpc_url, chain_id, .. }
+        | Commands::MigrateEntryPoint { factory, rpc_url, chain_id, .. } => {
+            from_profile(matches, "factory", factory, factory_addr);
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::AddOwner { factory, rpc_url, entry_point, chain_id, .. }
+        | Commands::RemoveOwner { factory, rpc_url, entry_point, chain_id, .. }
+        | Commands::SetThreshold { factory, rpc_url, entry_point, chain_id, .. } => {
+            from_profile(matches, "factory", factory, factory_addr);
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "entry_point", entry_point, entry);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::PredictAddress { factory, rpc_url, chain_id, .. } => {
+            from_profile(matches, "factory", factory, factory_addr);
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::ListOwners { rpc_url, .. } | Commands::EntryPoints { rpc_url, .. } | Commands::SubmitFile { rpc_url, .. } | Commands::Verify1271 { rpc_url, .. } => {
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+        }
+        Commands::FeeAdvice { rpc_url, chain_id, .. } => {
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::SignMessage { account, .. } | Commands::SignTypedData { account, .. } => {
+            from_profile(matches, "factory", &mut account.factory, factory_addr);
+            from_profile(matches, "rpc_url", &mut account.rpc_url, rpc);
+        }
+        Commands::Hash { entry_point, chain_id, .. } | Commands::ImportOp { entry_point, chain_id, .. } => {
+            from_profile(matches, "entry_point", entry_point, entry);
+            from_profile(matches, "chain_id", chain_id, chain);
+        }
+        Commands::Op { command } => {
+            // Profile arguments live on the nested op subcommand
+            let Some((_, op_matches)) = matches.subcommand() else { return };
+            match command {
+                OpCommands::Propose { rpc_url, chain_id, .. } => {
+                    from_profile(op_matches, "rpc_url", rpc_url, rpc);
+                    from_profile(op_matches, "chain_id", chain_id, chain);
+                }
+                OpCommands::Submit { rpc_url, .. } => {
+                    from_profile(op_matches, "rpc_url", rpc_url, rpc);
+                }
+                OpCommands::Sign { .. } | OpCommands::Rebuild { .. } => {}
+            }
+        }
+        Commands::Session { command } => {
+            let Some((_, session_matches)) = matches.subcommand() else { return };
+            match command {
+                SessionCommands::Create { factory, rpc_url, chain_id, .. }
+                | SessionCommands::Revoke { factory, rpc_url, chain_id, .. } => {
+                    from_profile(session_matches, "factory", factory, factory_addr);
+                    from_profile(session_matches, "rpc_url", rpc_url, rpc);
+                    from_profile(session_matches, "chain_id", chain_id, chain);
+                }
+            }
+        }
+        Commands::Recovery { command } => {
+            let Some((_, recovery_matches)) = matches.subcommand() else { return };
+            match command {
+                RecoveryCommands::AddGuardian { factory, rpc_url, chain_id, .. }
+                | RecoveryCommands::Cancel { factory, rpc_url, chain_id, .. } => {
+                    from_profile(recovery_matches, "factory", factory, factory_addr);
+                    from_profile(recovery_matches, "rpc_url", rpc_url, rpc);
+                    from_profile(recovery_matches, "chain_id", chain_id, chain);
+                }
+                RecoveryCommands::Initiate { rpc_url, .. }
+                | RecoveryCommands::Approve { rpc_url, .. }
+                | RecoveryCommands::Execute { rpc_url, .. }
+                | RecoveryCommands::Status { rpc_url, .. } => {
+                    from_profile(recovery_matches, "rpc_url", rpc_url, rpc);
+                }
+            }
+        }
+        Commands::SubmitSponsored { factory, rpc_url, chain_id, paymaster_url, .. }
+        | Commands::DeploySponsored { factory, rpc_url, chain_id, paymaster_url, .. }
+        | Commands::Onboard { factory, rpc_url, chain_id, paymaster_url, .. } => {
+            from_profile(matches, "factory", factory, factory_addr);
+            from_profile(matches, "rpc_url", rpc_url, rpc);
+            from_profile(matches, "chain_id", chain_id, chain);
+            from_profile(matches, "paymaster_url", paymaster_url, pm_url);
+        }
+        Commands::IssuePreauth { paymaster_url, .. } => {
+            from_profile(matches, "paymaster_url", paymaster_url, pm_url);
+        }
+        Commands::Account { command } => {
+            let Some((_, account_matches)) = matches.subcommand() else { return };
+            match command {
+                AccountCommands::Info { factory, rpc_url, .. } | AccountCommands::List { factory, rpc_url, .. } => {
+                    from_profile(account_matches, "factory", factory, factory_addr);
+                    from_profile(account_matches, "rpc_url", rpc_url, rpc);
+                }
+                AccountCommands::RotateOwner { factory, rpc_url, chain_id, .. } => {
+                    from_profile(account_matches, "factory", factory, factory_addr);
+                    from_profile(account_matches, "rpc_url", rpc_url, rpc);
+                    from_profile(account_matches, "chain_id", chain_id, chain);
+                }
+            }
+        }
+        Commands::Send { submit, .. } | Commands::Run { submit, .. } | Commands::Call { submit, .. } => {
+            from_profile(matches, "factory", &mut submit.factory, factory_addr);
+            from_profile(matches, "rpc_url", &mut submit.rpc_url, rpc);
+            from_profile(matches, "chain_id", &mut submit.chain_id, chain);
+            from_profile(matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+        }
+        Commands::Erc20 { command } => {
+            let Some((_, erc20_matches)) = matches.subcommand() else { return };
+            match command {
+                Erc20Commands::Transfer { submit, .. } | Erc20Commands::Approve { submit, .. } => {
+                    from_profile(erc20_matches, "factory", &mut submit.factory, factory_addr);
+                    from_profile(erc20_matches, "rpc_url", &mut submit.rpc_url, rpc);
+                    from_profile(erc20_matches, "chain_id", &mut submit.chain_id, chain);
+                    from_profile(erc20_matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+                }
+                Erc20Commands::Balance { rpc_url, .. } | Erc20Commands::Allowance { rpc_url, .. } => {
+                    from_profile(erc20_matches, "rpc_url", rpc_url, rpc);
+                }
+            }
+        }
+        Commands::Nft { command: NftCommands::Transfer { submit, .. } } => {
+            let Some((_, nft_matches)) = matches.subcommand() else { return };
+            from_profile(nft_matches, "factory", &mut submit.factory, factory_addr);
+            from_profile(nft_matches, "rpc_url", &mut submit.rpc_url, rpc);
+            from_profile(nft_matches, "chain_id", &mut submit.chain_id, chain);
+            from_profile(nft_matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+        }
+        Commands::Connect { submit, .. } | Commands::Serve { submit, .. } => {
+            from_profile(matches, "factory", &mut submit.factory, factory_addr);
+            from_profile(matches, "rpc_url", &mut submit.rpc_url, rpc);
+            from_profile(matches, "chain_id", &mut submit.chain_id, chain);
+            from_profile(matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+        }
+        Commands::Limits { command } => {
+            let Some((_, limit_matches)) = matches.subcommand() else { return };
+            match command {
+                LimitCommands::Set { submit, .. } | LimitCommands::Reset { submit, .. } => {
+                    from_profile(limit_matches, "factory", &mut submit.factory, factory_addr);
+                    from_profile(limit_matches, "rpc_url", &mut submit.rpc_url, rpc);
+                    from_profile(limit_matches, "chain_id", &mut submit.chain_id, chain);
+                    from_profile(limit_matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+                }
+                LimitCommands::Remaining { rpc_url, .. } => {
+                    from_profile(limit_matches, "rpc_url", rpc_url, rpc);
+                }
+            }
+        }
+        Commands::Paymaster { command } => {
+            let Some((_, paymaster_matches)) = matches.subcommand() else { return };
+            match command {
+                PaymasterCommands::Deposit { submit, .. } | PaymasterCommands::Withdraw { submit, .. } => {
+                    from_profile(paymaster_matches, "factory", &mut submit.factory, factory_addr);
+                    from_profile(paymaster_matches, "rpc_url", &mut submit.rpc_url, rpc);
+                    from_profile(paymaster_matches, "chain_id", &mut submit.chain_id, chain);
+                    from_profile(paymaster_matches, "paymaster_url", &mut submit.paymaster_url, pm_url);
+                }
+                PaymasterCommands::Status { rpc_url, chain_id, .. } => {
+                    from_profile(paymaster_matches, "rpc_url", rpc_url, rpc);
+                    from_profile(paymaster_matches, "chain_id", chain_id, chain);
+                }
+            }
+        }
+        Commands::Bundlers { command } => {
+            let Some((_, bundler_matches)) = matches.subcommand() else { return };
+            match command {
+                BundlerCommands::Status { rpc_url, .. } => {
+                    from_profile(bundler_matches, "rpc_url", rpc_url, rpc);
+                }
+            }
+        }
+        Commands::Ops { command } => {
+            let Some((_, ops_matches)) = matches.subcommand() else { return };
+            match command {
+                OpsCommands::Watch { rpc_url, .. } => {
+                    from_profile(ops_matches, "rpc_url", rpc_url, rpc);
+                }
+                OpsCommands::List { .. } | OpsCommands::Report { .. } | OpsCommands::Export { .. } | OpsCommands::Import { .. } => {}
+            }
+        }
+        Commands::GenerateWallet { .. } | Commands::Wallet { .. } | Commands::Watch { .. } | Commands::Tui | Commands::Analyze { .. } | Commands::Info { .. } | Commands::Networks | Commands::Scan { .. } | Commands::ExportOp { .. } | Commands::Calldata { .. } | Commands::Template { .. } | Commands::Abi { .. } => {}
+    }
+}
+
+/// Fill the submit options `run` was not given explicitly from the template
+fn apply_template(submit: &mut SubmitArgs, matches: &ArgMatches, template: &templates::Template) {
+    let explicit = |id: &str| !matches!(matches.value_source(id), Some(ValueSource::DefaultValue) | None);
+    submit.sponsored |= template.sponsored;
+    if submit.max_fee_per_gas.is_none() && !submit.sponsored {
+        submit.max_fee_per_gas = template.max_fee_per_gas.clone();
+    }
+    if submit.max_priority_fee_per_gas.is_none() && !submit.sponsored {
+        submit.max_priority_fee_per_gas = template.max_priority_fee_per_gas.clone();
+    }
+    if let (false, Some(url)) = (explicit("paymaster_url"), &template.paymaster_url) {
+        submit.paymaster_url = url.clone();
+    }
+    if let (false, Some(address)) = (explicit("paymaster_address"), &template.paymaster_address) {
+        submit.paymaster_address = address.clone();
+    }
+}
+
+/// Submit one call from the smart account, through the paymaster when --sponsored is set
+/// Returns the userOpHash when the operation was accepted but had no receipt yet
+async fn submit_call(args: &SubmitArgs, private_key: &str, target: &str, call_data: &Bytes, value: &str) -> Result<Option<String>> {
+    let call_data = format!("0x{}", hex::encode(call_data));
+    let nonce_key = args.nonce_key.as_deref().map(nonce::parse_key).transpose()?;
+    if args.sponsored {
+        submit_sponsored_user_operation(
+            private_key, target, &call_data, value, &args.factory, &args.salt, &args.rpc_url, args.chain_id, nonce_key,
+            &args.paymaster_url, &args.paymaster_api_key, args.preauth_token.as_deref(), &args.paymaster_address,
+            args.paymaster_encoding, args.valid_for, args.tag.as_deref()
+        ).await
+    } else {
+        submit_user_operation_fixed(
+            private_key, target, &call_data, value, &args.factory, &args.salt, &args.rpc_url, entrypoint::resolve(args.chain_id)?, args.chain_id, nonce_key,
+            args.max_fee_per_gas.as_deref(), args.max_priority_fee_per_gas.as_deref(), args.tag.as_deref()
+        ).await
+    }
+}
+
+/// Token a spending limit applies to, the zero address (ETH) when none is given
+fn limit_token(token: Option<&str>) -> Result<Address> {
+    Ok(token.map(Address::from_str).transpose()?.unwrap_or(Address::ZERO))
+}
+
+fn token_name(token: Address) -> String {
+    if token.is_zero() { "ETH".to_string() } else { token.to_string() }
+}
+
+/// Print the daily limit of a token on an account and what remains of it
+async fn show_spending_limit(account: &str, module: &str, token: Option<&str>, decimals: Option<u8>, rpc_url: &str) -> Result<()> {
+    let account_addr = Address::from_str(account)?;
+    let module_addr = Address::from_str(module)?;
+    let token_addr = limit_token(token)?;
+    let provider = providers::http(rpc_url)?;
+    
+    let allowance = spending_limit::get_allowance(&provider, module_addr, account_addr, token_addr).await?;
+    out!("🚦 {} limit of {}", token_name(token_addr), account_addr);
+    if !allowance.is_limited() {
+        out!("  No daily limit set");
+        return Ok(());
+    }
+    let decimals = match decimals {
+        Some(decimals) => decimals,
+        None if token_addr.is_zero() => 18,
+        None => erc20::decimals(&provider, token_addr).await?,
+    };
+    let now = clock::now_secs();
+    out!("  Daily limit: {}", units::format_units(allowance.limit, decimals));
+    out!("  Remaining:   {}", units::format_units(allowance.remaining(now), decimals));
+    if now < allowance.reset_at {
+        out!("  Spent:       {} (resets in {}s)", units::format_units(allowance.spent, decimals), allowance.reset_at - now);
+    }
+    
+    Ok(())
+}
+
+/// Print an account's token balance, or with `spender` what that spender may still transfer
+async fn show_token_amount(token: &str, account: &str, spender: Option<&str>, decimals: Option<u8>, rpc_url: &str) -> Result<()> {
+    let token_addr = Address::from_str(token)?;
+    let account_addr = Address::from_str(account)?;
+    let provider = providers::http(rpc_url)?;
+    
+    let amount = match spender {
+        Some(spender) => erc20::allowance(&provider, token_addr, account_addr, Address::from_str(spender)?).await?,
+        None => erc20::balance_of(&provider, token_addr, account_addr).await?,
+    };
+    let decimals = match decimals {
+        Some(decimals) => decimals,
+        None => erc20::decimals(&provider, token_addr).await?,
+    };
+    let amount_text = if amount == U256::MAX {
+        "unlimited".to_string()
+    } else {
+        format!("{} ({} base units)", units::format_units(amount, decimals), amount)
+    };
+    match spender {
+        Some(spender) => out!("🪙 {} may spend {} of {} from {}", spender, amount_text, token_addr, account_addr),
+        None => out!("🪙 {} holds {} of {}", account_addr, amount_text, token_addr),
+    }
+    
+    Ok(())
+}
+
+/// With a token paymaster, check the account holds the token and has approved the paymaster before submitting
+async fn check_token_payment<P: Provider>(provider: &P, paymaster: Address, account: Address) -> Result<()> {
+    let paymaster::PaymasterMode::Token(token) = paymaster::mode() else { return Ok(()) };
+    let (balance, allowance) = tokio::try_join!(
+        erc20::balance_of(provider, token, account),
+        erc20::allowance(provider, token, account, paymaster),
+    )?;
+    erc20::check_can_pay(token, account, paymaster, balance, allowance)?;
+    out!("🪙 {} holds {} base units of {}, the paymaster may spend {}", account, balance, token, allowance);
+    Ok(())
+}
+
+/// Poll the bundler for an operation's receipt for up to `timeout` seconds, journaling the outcome
+async fn wait_for_receipt(rpc_url: &str, user_op_hash: &str, timeout: u64) -> Result<stuck::ReceiptSummary> {
+    let provider = providers::http(rpc_url)?;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout);
+    out!("⏳ Waiting up to {}s for the receipt of {}", timeout, user_op_hash);
+    events::emit(ClientEvent::Pending { user_op_hash: user_op_hash.to_string() });
+    
+    loop {
+        if let Some(receipt) = stuck::get_receipt(&provider, user_op_hash).await? {
+            journal_receipt(user_op_hash, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost);
+            if !receipt.success {
+                return Err(anyhow::anyhow!("Operation {} was included but reverted", user_op_hash));
+            }
+            out!("✅ Included, gas cost {} ETH", units::format_units(receipt.actual_gas_cost, 18));
+            return Ok(receipt);
+        }
+        if std::time::Instant::now() >= deadline {
+            out!("💡 {}", t!("check-later", hash = user_op_hash));
+            return Err(exit::ReceiptTimeout { user_op_hash: user_op_hash.to_string(), timeout_secs: timeout }.into());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+}
+
+/// Nonce to pin on a new operation, None to leave it to the SDK filler (default key, no --nonce-cache)
+/// The filler only ever reads key 0 and knows nothing of nonces reserved by concurrent invocations
+async fn allocate_nonce<P: alloy::providers::Provider>(
+    provider: &P,
+    entry_point: Address,
+    chain_id: u64,
+    account: Address,
+    key: Option<U192>,
+) -> Result<Option<U256>> {
+    let manager = nonce::manager();
+    if key.is_none() && manager.is_none() {
+        return Ok(None);
+    }
+    let key = key.unwrap_or_default();
+    let onchain = account::get_nonce_at_key(provider, entry_point, account, key).await?;
+    let nonce = match manager {
+        Some(manager) => manager.allocate(chain_id, account, key, onchain),
+        None => onchain,
+    };
+    out!("🔢 Nonce key {}: sequence {}", key, nonce::unpack(nonce).1);
+    Ok(Some(nonce))
+}
+
+/// Simulate a filled operation against the EntryPoint, failing with the decoded error when it would be rejected
+/// Simulation problems that are not a verdict (no eth_call on the endpoint, unfilled fields) only warn
+async fn run_preflight(
+    rpc_url: &str,
+    entry_point: Address,
+    chain_id: u64,
+    request: &userop::UserOperationRequest,
+    wallet: &Wallet,
+) -> Result<()> {
+    if !preflight::enabled() {
+        return Ok(());
+    }
+    let signed = match multisig::UnsignedUserOperation::try_from(request)
+        .and_then(|user_op| userop::SignedUserOperation::sign(user_op, entry_point, chain_id, wallet))
+    {
+        Ok(signed) => signed,
+        Err(e) => {
+            out!("⚠️  Simulation skipped: {}", e);
+            return Ok(());
+        }
+    };
+    simulate_signed(rpc_url, entry_point, &signed.user_op, signed.signature).await
+}
+
+/// Lint and simulate an already signed operation, unless `--no-preflight` is set
+async fn simulate_signed(
+    rpc_url: &str,
+    entry_point: Address,
+    user_op: &multisig::UnsignedUserOperation,
+    signature: Bytes,
+) -> Result<()> {
+    if !preflight::enabled() {
+        return Ok(());
+    }
+    out!("🧪 Simulating the UserOperation against the EntryPoint");
+    let provider = providers::http(rpc_url)?;
+    // Bundler rules are stricter than the EntryPoint, so an operation passing simulation can still be refused
+    match erc7562::lint(&provider, entry_point, user_op).await {
+        Ok(findings) => {
+            for finding in findings {
+                out!("⚠️  {} {} [{}]: {}", finding.entity, finding.address, finding.rule, finding.message);
+            }
+        }
+        Err(e) => out!("⚠️  Bundler rule checks unavailable: {}", e),
+    }
+    // The EntryPoint takes the whole prefund from the paymaster's deposit, a shortfall only shows up as AA31
+    if let Some(paymaster) = user_op.paymaster {
+        match paymaster_funds::backing(&provider, entry_point, paymaster).await {
+            Ok(backing) => {
+                for warning in backing.warnings(paymaster, paymaster_funds::max_cost(user_op)) {
+                    out!("⚠️  {}", warning);
+                }
+            }
+            Err(e) => out!("⚠️  Paymaster deposit unavailable: {}", e),
+        }
+    }
+    match preflight::simulate(&provider, entry_point, user_op, signature).await {
+        Ok(None) => {
+            out!("✅ Simulation passed");
+            Ok(())
+        }
+        Ok(Some(failure)) => Err(preflight::Rejected(failure).into()),
+        Err(e) => {
+            out!("⚠️  Simulation unavailable: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// End of a `--dry-run`: simulate the signed operation and print it instead of calling `eth_sendUserOperation`
+async fn print_dry_run(
+    rpc_url: &str,
+    entry_point: Address,
+    chain_id: u64,
+    user_op: &multisig::UnsignedUserOperation,
+    signature: Bytes,
+) -> Result<()> {
+    simulate_signed(rpc_url, entry_point, user_op, signature.clone()).await?;
+    let rpc = userop::RpcUserOperation { signature: Some(signature), ..userop::RpcUserOperation::from(user_op) };
+    out!("🧪 Dry run, nothing was sent. eth_sendUserOperation would receive:");
+    out!("{}", serde_json::to_string_pretty(&rpc)?);
+    out!("UserOperation Hash: {}", user_op.hash(entry_point, chain_id));
+    Ok(())
+}
+
+/// `--dry-run` for a filled request that `wallet` would sign
+async fn dry_run(
+    rpc_url: &str,
+    entry_point: Address,
+    chain_id: u64,
+    request: &userop::UserOperationRequest,
+    wallet: &Wallet,
+) -> Result<()> {
+    let user_op = multisig::UnsignedUserOperation::try_from(request)?;
+    let signed = userop::SignedUserOperation::sign(user_op, entry_point, chain_id, wallet)?;
+    print_dry_run(rpc_url, entry_point, chain_id, &signed.user_op, signed.signature).await
+}
+
+/// Release the cached nonce of a failed submission so the next operation re-reads the chain
+fn resync_nonce(chain_id: u64, account: Address, key: Option<U192>) {
+    if let Some(manager) = nonce::manager() {
+        manager.resync(chain_id, account, key.unwrap_or_default());
+    }
+}
+
+/// Nonce to retry with after the EntryPoint rejected one (AA25): reservations of the stream are dropped,
+/// the EntryPoint asked again and, with `--nonce-cache`, the answer reserved
+async fn refetch_nonce(rpc_url: &str, entry_point: Address, chain_id: u64, account: Address, key: Option<U192>) -> Result<U256> {
+    resync_nonce(chain_id, account, key);
+    let provider = providers::http(rpc_url)?;
+    let key = key.unwrap_or_default();
+    let onchain = account::get_nonce_at_key(&provider, entry_point, account, key).await?;
+    Ok(match nonce::manager() {
+        Some(manager) => manager.allocate(chain_id, account, key, onchain),
+        None => onchain,
+    })
+}
+
+/// Scale a whole-token amount to base units, reading decimals from the token unless given
+async fn token_amount(amount: &str, decimals: Option<u8>, token: Address, rpc_url: &str) -> Result<U256> {
+    let decimals = match decimals {
+        Some(decimals) => decimals,
+        None => {
+            let provider = providers::http(rpc_url)?;
+            erc20::decimals(&provider, token).await?
+        }
+    };
+    units::parse_units(amount, decimals)
+}
+
+async fn create_user_operation(
+    private_key: &str,
+    target: &str,
+    call_data: &str,
+    nonce: u64,
+    _rpc_url: &str,
+    _entry_point: Option<&str>,
+    _chain_id: u64,
+    _max_fee_per_gas: &str,
+    _max_priority_fee_per_gas: &str,
+) -> Result<()> {
+    out!("Creating UserOperation...");
+    
+    // Create wallet
+    let wallet = Wallet::from_hex(private_key)?;
+    out!("Wallet address: {}", wallet.address());
+    
+    // Parse target address
+    let target_addr = Address::from_str(target)?;
+    
+    // Parse call data
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    
+    // Create UserOperation using aa-sdk-rs
+    let _user_op_request = UserOperationBuilder::new(target_addr, U256::ZERO, call_data_bytes.clone())
+        .with_sender(wallet.address())
+        .with_nonce(U256::from(nonce))
+        .build();
+    
+    out!("UserOperation created successfully!");
+    out!("Sender: {}", wallet.address());
+    out!("Nonce: {}", nonce);
+    out!("{}", t!("label-target", address = target_addr));
+    out!("Call Data: 0x{}", hex::encode(&call_data_bytes));
+    out!("Note: aa-sdk-rs handles signing internally when submitting operations");
+    
+    Ok(())
+}
+
+
+
+/// Everything `create --sign` fills in, given up front because no RPC is contacted
+struct OfflineOperation<'a> {
+    sender: &'a str,
+    value: &'a str,
+    nonce: u64,
+    call_gas_limit: u64,
+    verification_gas_limit: u64,
+    pre_verification_gas: u64,
+    max_fee_per_gas: &'a str,
+    max_priority_fee_per_gas: &'a str,
+}
+
+/// Private key of a wallet stored with `wallet import`, asking for its password unless AA_WALLET_PASSWORD is set
+fn unlock_wallet(name: &str) -> Result<String> {
+    let password = wallet_store::password(&format!("Password for wallet '{}': ", name), false)?;
+    wallet_store::WalletStore::open_default()?.unlock(name, &password)
+}
+
+/// Key that signs an operation hash: a local private key or an account on a remote signer
+enum OwnerKey {
+    Local(Wallet),
+    Remote(remote_signer::RemoteSigner),
+}
+
+impl OwnerKey {
+    /// The remote signer's account when one is configured, otherwise a wallet for the resolved private key
+    async fn resolve(private_key: impl FnOnce() -> Result<String>) -> Result<Self> {
+        match remote_signer::config() {
+            Some(config) => Ok(OwnerKey::Remote(remote_signer::RemoteSigner::connect(config).await?)),
+            None => Ok(OwnerKey::Local(Wallet::from_hex(&private_key()?)?)),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            OwnerKey::Local(wallet) => wallet.address(),
+            OwnerKey::Remote(signer) => signer.address(),
+        }
+    }
+}
+
+/// `--entry-point` when given, otherwise the selected EntryPoint deployment of the chain
+fn entry_point_arg(entry_point: &Option<String>, chain_id: u64) -> Result<Address> {
+    match entry_point {
+        Some(entry_point) => Address::from_str(entry_point).map_err(|e| anyhow::anyhow!("Invalid --entry-point '{}': {}", entry_point, e)),
+        None => entrypoint::resolve(chain_id),
+    }
+}
+
+/// Fill and sign a UserOperation offline and write it for `submit-file`
+/// With a remote signer only the signing service is contacted
+async fn create_signed_user_operation(
+    owner: &OwnerKey,
+    target: &str,
+    call_data: &str,
+    entry_point: Address,
+    chain_id: u64,
+    operation: OfflineOperation<'_>,
+    out: &Path,
+) -> Result<()> {
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    
+    let context = userop::BuildContext {
+        chain_id,
+        entry_point,
+        sender: Address::from_str(operation.sender)?,
+        nonce: U256::from(operation.nonce),
+        factory: None,
+        factory_data: None,
+        target: Address::from_str(target)?,
+        value: U256::from_str_radix(operation.value, 10)?,
+        data: call_data_bytes,
+        call_gas_limit: U256::from(operation.call_gas_limit),
+        verification_gas_limit: U256::from(operation.verification_gas_limit),
+        pre_verification_gas: U256::from(operation.pre_verification_gas),
+        max_fee_per_gas: U256::from_str_radix(operation.max_fee_per_gas, 10)?,
+        max_priority_fee_per_gas: U256::from_str_radix(operation.max_priority_fee_per_gas, 10)?,
+    };
+    
+    let signed = match owner {
+        OwnerKey::Local(wallet) => userop::SignedUserOperation::sign(context.build(), context.entry_point, chain_id, wallet)?,
+        OwnerKey::Remote(signer) => userop::SignedUserOperation::sign_remote(context.build(), context.entry_point, chain_id, signer).await?,
+    };
+    signed.save(out)?;
+    
+    out!("✍️  Signed UserOperation written to {}", out.display());
+    out!("Account: {} | Nonce: {} | Chain: {}", context.sender, context.nonce, chain_id);
+    out!("Signer: {}", signed.signer);
+    out!("UserOperation Hash: {}", signed.user_op_hash);
+    out!("💡 On a connected machine run: submit-file {}", out.display());
+    
+    Ok(())
+}
+
+/// Send a UserOperation signed offline, the only step of the workflow that touches the network
+async fn submit_signed_file(file: &Path, rpc_url: &str, tag: Option<&str>) -> Result<()> {
+    let signed = userop::SignedUserOperation::load(file)?;
+    out!("🚀 Submitting {} signed by {}", file.display(), signed.signer);
+    out!("Account: {} | Chain: {}", signed.user_op.sender, signed.chain_id);
+    
+    let bundler_client = bundler_at(rpc_url.to_string(), signed.entry_point, U256::from(signed.chain_id));
+    let endpoint = bundler_client.preflight().await?;
+    if preflight::dry_run() {
+        return print_dry_run(&endpoint, signed.entry_point, signed.chain_id, &signed.user_op, signed.signature.clone()).await;
+    }
+    let user_op_hash = bundler_client.send_user_operation(serde_json::to_value(signed.to_rpc())?, signed.entry_point).await?;
+    
+    out!("✅ {}", t!("userop-submitted"));
+    out!("UserOperation Hash: {}", user_op_hash);
+    if !user_op_hash.eq_ignore_ascii_case(&signed.user_op_hash.to_string()) {
+        out!("⚠️  Bundler returned a different hash than the signed {}", signed.user_op_hash);
+    }
+    journal_operation(&user_op_hash, signed.chain_id, signed.user_op.sender, "submit-file", tag, false);
+    journal_user_op(&user_op_hash, &signed.user_op);
+    
+    Ok(())
+}
+
+/// Turn a signed RPC-form UserOperation into a signed operation file, recovering who signed it
+fn import_user_operation(file: &Path, out: &Path, entry_point: Address, chain_id: u64) -> Result<()> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Cannot read UserOperation {}: {}", file.display(), e))?;
+    let op = userop::RpcUserOperation::from_json(&contents)?;
+    let signed = userop::SignedUserOperation::from_rpc(&op, entry_point, chain_id)?;
+    signed.save(out)?;
+    
+    out!("✅ Imported {} into {}", file.display(), out.display());
+    out!("Account: {} | Nonce: {} | Chain: {}", signed.user_op.sender, signed.user_op.nonce, chain_id);
+    out!("Signer: {}", signed.signer);
+    out!("UserOperation Hash: {}", signed.user_op_hash);
+    
+    Ok(())
+}
+
+/// Write a signed operation file back out in ERC-4337 JSON-RPC form
+fn export_user_operation(file: &Path, out: Option<&Path>) -> Result<()> {
+    let json = userop::SignedUserOperation::load(file)?.to_rpc().to_json()?;
+    match out {
+        Some(out) => {
+            std::fs::write(out, &json)?;
+            out!("✅ Exported {} to {}", file.display(), out.display());
+        }
+        None => out!("{}", json),
+    }
+    
+    Ok(())
+}
+
+/// Submit a UserOperation to a bundler using aa-sdk-rs SmartAccountProvider (FIXED VERSION)
+async fn submit_user_operation_fixed(
+    private_key: &str,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    factory: &str,      // ✅ Added: Need to identify smart account
+    salt: &str,         // ✅ Added: Need to identify smart account
+    rpc_url: &str,
+    entry_point_addr: Address,
+    chain_id: u64,
+    nonce_key: Option<U192>,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+    tag: Option<&str>,
+) -> Result<Option<String>> {
+    out!("🚀 {}", t!("submit-start"));
+    
+    // ✅ Setup
+    let wallet = Wallet::from_hex(private_key)?;
+    let factory_addr = Address::from_str(factory)?;
+    let target_addr = Address::from_str(target)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    out!("🔧 {}", t!("setting-up-account"));
+    out!("{}", t!("label-factory", address = factory_addr));
+    out!("{}", t!("label-target", address = target_addr));
+    out!("{}", t!("label-owner-eoa", address = wallet.address()));
+    
+    let provider = providers::http(rpc_url)?;
+    
+    // The account the factory deploys for the owner and --salt
+    let simple_account = simple_account(&provider, wallet.address(), hexutil::parse_salt(salt)?, factory_addr, entry_point_addr, chain_id);
+    
+    // ✅ 1. CHECK IF ACCOUNT IS DEPLOYED
+    out!("🔍 {}", t!("checking-deployed"));
+    let is_deployed = simple_account.is_account_deployed().await?;
+    if !is_deployed {
+        let predicted_addr = simple_account.get_counterfactual_address().await?;
+        return Err(anyhow::anyhow!(
+            "❌ {}\n💡 {}\n  cargo run -- deploy-account --factory {} --salt {} --private-key-stdin",
+            t!("account-not-deployed", address = predicted_addr), t!("hint-run-first", command = "deploy-account"), factory, salt
+        ));
+    }
+    
+    let account_addr = simple_account.get_account_address().await?;
+    out!("✅ {}", t!("using-deployed-account", address = account_addr));
+    
+    // ✅ 2. PREPARE TRANSACTION PARAMETERS  
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    let value_amount = U256::from_str_radix(value, 10)?;
+    
+    out!("🔧 {}", t!("preparing-transaction"));
+    out!("  {}", t!("label-target", address = target_addr));
+    out!("  {}", t!("label-value-wei", value = value_amount));
+    out!("  {}", t!("label-call-data", data = format!("0x{}", hex::encode(&call_data_bytes))));
+    preflight::check_target(&provider, target_addr, &call_data_bytes).await?;
+    validation::check_value(target_addr, value_amount)?;
+    
+    // ✅ 3. CREATE USEROPERATION DIRECTLY (NO DOUBLE-ENCODING!)
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, max_fee_per_gas, max_priority_fee_per_gas).await?;
+    
+    // Fix: Pass target parameters directly to UserOperationBuilder
+    // This will create ExecuteCall internally - no manual encoding needed!
+    let mut user_op_request = UserOperationBuilder::new(
+        target_addr,        // ✅ External target (not smart account!)
+        value_amount,       // ✅ Value to send to target
+        call_data_bytes     // ✅ Call data to send to target
+    )
+    .with_gas_fees(max_fee, priority_fee)
+    .build();
+    if let Some(nonce) = allocate_nonce(&provider, entry_point_addr, chain_id, account_addr, nonce_key).await? {
+        user_op_request.nonce = Some(nonce);
+    }
+    
+    out!("✅ {}", t!("userop-created"));
+    
+    // ✅ 4. USE AA-SDK-RS CAPABILITIES
+    let smart_provider = SmartAccountProvider::new(provider, simple_account);
+    
+    // Optional: Get gas estimates
+    out!("📊 {}", t!("estimating-gas"));
+    match retry::retry("eth_estimateUserOperationGas", || smart_provider.estimate_user_operation_gas(&user_op_request)).await {
+        Ok(estimates) => {
+            events::emit(ClientEvent::estimated(&estimates));
+            out!("✅ {}", t!("gas-estimates", estimates = format!("{:?}", estimates)));
+        }
+        Err(e) => {
+            out!("⚠️  {}", t!("gas-estimation-failed", error = e));
+        }
+    }
+    
+    // Fill missing fields automatically
+    out!("🔧 {}", t!("filling-userop"));
+    events::emit(ClientEvent::Filling { sender: account_addr, chain_id });
+    let filler = &smart_provider;
+    user_op_request = retry::retry("fill UserOperation", || {
+        let mut request = user_op_request.clone();
+        async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+    }).await?;
+    
+    if preflight::dry_run() {
+        resync_nonce(chain_id, account_addr, nonce_key);
+        dry_run(rpc_url, entry_point_addr, chain_id, &user_op_request, &wallet).await?;
+        return Ok(None);
+    }
+    
+    // ✅ 5. SUBMIT WITH TRACKING
+    // A nonce taken in the meantime is read again and the operation re-signed with it, per --on-nonce-error
+    let (wallet, smart_provider) = (&wallet, &smart_provider);
+    let sent = nonce::with_recovery(
+        |nonce| {
+            let mut request = user_op_request.clone();
+            async move {
+                if nonce.is_some() {
+                    request.nonce = nonce;
+                }
+                run_preflight(rpc_url, entry_point_addr, chain_id, &request, wallet).await?;
+                out!("🚀 {}", t!("submitting-transaction"));
+                retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(request.clone(), wallet.signer()))
+                    .await
+                    .map(|user_op_hash| (user_op_hash, request))
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            }
+        },
+        || refetch_nonce(rpc_url, entry_point_addr, chain_id, account_addr, nonce_key),
+    ).await;
+    let pending = match sent {
+        Ok((user_op_hash, user_op_request)) => {
+            out!("✅ {}", t!("userop-submitted"));
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            let hash_str = format!("{:?}", user_op_hash);
+            journal_operation(&hash_str, chain_id, account_addr, "submit", tag, false);
+            if let Ok(user_op) = multisig::UnsignedUserOperation::try_from(&user_op_request) {
+                journal_user_op(&hash_str, &user_op);
+            }
+            
+            // ✅ TRACK EXECUTION STATUS
+            out!("📋 {}", t!("checking-status"));
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await; // Wait for execution
+            
+            match retry::retry("eth_getUserOperationReceipt", || smart_provider.get_user_operation_receipt(user_op_hash)).await {
+                Ok(Some(receipt)) => {
+                    journal_receipt(&hash_str, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost);
+                    out!("✅ {}", t!("tx-executed"));
+                    out!("📋 {}", t!("receipt-details", receipt = format!("{:?}", receipt)));
+                    out!("🎉 {}", t!("tx-completed"));
+                    None
+                }
+                Ok(None) => {
+                    events::emit(ClientEvent::Pending { user_op_hash: hash_str.clone() });
+                    out!("⏳ {}", t!("tx-pending"));
+                    out!("💡 {}", t!("check-later", hash = format!("{:?}", user_op_hash)));
+                    
+                    // Get more operation details
+                    if let Ok(Some(op)) = smart_provider.get_user_operation(user_op_hash).await {
+                        out!("📊 {}", t!("userop-details", details = format!("{:?}", op)));
+                    }
+                    Some(hash_str.clone())
+                }
+                Err(e) => {
+                    out!("⚠️  {}", t!("status-unverified", error = e));
+                    out!("💡 {}", t!("may-have-succeeded"));
+                    Some(hash_str)
+                }
+            }
+        }
+        Err(e) if e.is::<preflight::Rejected>() => {
+            resync_nonce(chain_id, account_addr, nonce_key);
+            return Err(e);
+        }
+        Err(e) => {
+            resync_nonce(chain_id, account_addr, nonce_key);
+            events::emit(ClientEvent::Failed { user_op_hash: None, reason: e.to_string() });
+            out!("❌ {}", t!("submit-failed", error = e));
+            out!("🔍 {}", t!("possible-causes"));
+            out!("  1. {}", t!("cause-not-deployed"));
+            out!("  2. {}", t!("cause-gas-fees"));
+            out!("  3. {}", t!("cause-invalid-call"));
+            out!("  4. {}", t!("cause-bundler-connectivity"));
+            return Err(exit::Reported(anyhow::anyhow!("{}", e)).into());
+        }
+    };
+    
+    Ok(pending)
+}
+
+/// Paymaster of a zkSync transaction: approval-based when paid in a token, general otherwise
+fn zksync_paymaster(paymaster: Option<Address>, token: Option<Address>, allowance: Option<&str>) -> Result<Option<zksync::PaymasterParams>> {
+    Ok(match (paymaster, token) {
+        (Some(paymaster), Some(token)) => {
+            let allowance = U256::from_str(allowance.unwrap_or("0"))
+                .map_err(|e| anyhow::anyhow!("Invalid --paymaster-allowance: {}", e))?;
+            Some(zksync::PaymasterParams::approval_based(paymaster, token, allowance, Bytes::new()))
+        }
+        (Some(paymaster), None) => Some(zksync::PaymasterParams::general(paymaster, Bytes::new())),
+        (None, _) => None,
+    })
+}
+
+/// Send one call from a zkSync account as an EIP-712 transaction, which the account validates itself
+/// The owner key signs; `account` defaults to the owner's address, whose default account accepts that signature
+async fn submit_native_transaction(
+    private_key: &str,
+    account: Option<Address>,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    paymaster: Option<zksync::PaymasterParams>,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let provider = providers::http(rpc_url)?;
+    config::check_chain_id(chain_id, providers::registry().chain_id(rpc_url).await?, "RPC endpoint")?;
+    let sender = account.unwrap_or(wallet.address());
+    out!("🚀 Sending from {} as a zkSync transaction (type 113)", sender);
+
+    events::emit(ClientEvent::Filling { sender, chain_id });
+    let mut transaction = zksync::NativeTransaction {
+        chain_id,
+        from: sender,
+        to: Address::from_str(target)?,
+        nonce: U256::from(provider.get_transaction_count(sender).await?),
+        value: U256::from_str_radix(value, 10)?,
+        data: hexutil::decode_bytes("call data", call_data)?,
+        gas_limit: U256::ZERO,
+        gas_per_pubdata: U256::from(zksync::DEFAULT_GAS_PER_PUBDATA),
+        max_fee_per_gas: U256::ZERO,
+        max_priority_fee_per_gas: U256::ZERO,
+        paymaster,
+    };
+    let fee = retry::retry("zks_estimateFee", || zksync::estimate_fee(&provider, &transaction)).await?;
+    transaction.apply_fee(&fee);
+    if let Some(max_fee) = max_fee_per_gas {
+        transaction.max_fee_per_gas = U256::from_str_radix(max_fee, 10)?;
+    }
+    if let Some(priority_fee) = max_priority_fee_per_gas {
+        transaction.max_priority_fee_per_gas = U256::from_str_radix(priority_fee, 10)?;
+    }
+    out!("  Nonce: {} | Gas limit: {} | Gas per pubdata: {}", transaction.nonce, transaction.gas_limit, transaction.gas_per_pubdata);
+    out!("  Max fee: {} gwei | Priority fee: {} gwei",
+         fees::format_gwei(transaction.max_fee_per_gas.saturating_to()), fees::format_gwei(transaction.max_priority_fee_per_gas.saturating_to()));
+    if let Some(params) = &transaction.paymaster {
+        out!("  Paymaster: {}", params.paymaster);
+    }
+
+    let raw = transaction.sign(&wallet)?;
+    if preflight::dry_run() {
+        out!("🧪 Dry run, not sent");
+        out!("  Signed hash: {}", transaction.signing_hash());
+        out!("  Raw transaction: {}", raw);
+        return Ok(());
+    }
+
+    let hash = zksync::send(&provider, &raw).await.map_err(|e| anyhow::anyhow!("zkSync node rejected the transaction: {}", e))?;
+    out!("✅ Transaction sent: {}", hash);
+    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    match zksync::receipt_status(&provider, hash).await? {
+        Some(true) => out!("🎉 {}", t!("tx-completed")),
+        Some(false) => return Err(anyhow::anyhow!("Transaction {} reverted", hash)),
+        None => out!("⏳ Not included yet, check {} later", hash),
+    }
+    Ok(())
+}
+
+async fn generate_wallet(qr: bool) -> Result<()> {
+    out!("{}", t!("wallet-generating"));
+    
+    // Use the existing WalletFactory::random() for real random generation
+    let wallet = WalletFactory::random()?;
+    
+    out!("{}", t!("wallet-created"));
+    out!("{}", t!("label-address", address = wallet.address()));
+    out!("{}", t!("label-private-key", key = wallet.export_private_key()));
+    // Only the address goes into the code, never the key
+    if qr {
+        qr::print_text("Address", &wallet.address().to_string())?;
+    }
+    
+    Ok(())
+}
+
+fn show_wallet_info(private_key: &str) -> Result<()> {
+    out!("{}", t!("wallet-information"));
+    
+    let wallet = Wallet::from_hex(private_key)?;
+    out!("{}", t!("label-address", address = wallet.address()));
+    out!("{}", t!("label-private-key", key = wallet.export_private_key()));
+    
+    // Note: Public key derivation now handled internally by aa-sdk-rs LocalSigner
+    out!("{}", t!("wallet-public-key-note"));
+    
+    Ok(())
+}
+
+/// The salt a deploy uses: `--salt` as given, or a fresh one for `--salt-random`
+fn deployment_salt(salt: Option<&str>, salt_random: bool) -> Result<String> {
+    match (salt, salt_random) {
+        (_, true) => {
+            let salt = hexutil::format_salt(hexutil::random_salt()?);
+            out!("🎲 Generated salt {}", salt);
+            out!("💡 Keep it: the account address depends on it (it is also recorded in the account index)");
+            Ok(salt)
+        }
+        (Some(salt), false) => Ok(salt.to_string()),
+        (None, false) => Err(anyhow::anyhow!("Pass --salt or --salt-random")),
+    }
+}
+
+/// Record a submitted deployment and its salt in the account index, shown by `account list`
+fn record_deployment(chain_id: u64, factory: Address, account: Address, owners: Vec<Address>, salt: U256) {
+    let recorded = indexer::AccountIndex::open_default()
+        .and_then(|index| index.record_deployment(chain_id, factory, account, owners, salt.into()));
+    if let Err(e) = recorded {
+        out!("⚠️  Could not record the salt in the account index: {}", e);
+    }
+}
+
+/// Single-owner SimpleAccount of `owner` and `salt`, predicted and deployed through SimpleAccountFactory
+fn simple_account<P: Provider + Clone>(
+    provider: &P,
+    owner: Address,
+    salt: U256,
+    factory: Address,
+    entry_point: Address,
+    chain_id: u64,
+) -> account::AAAccount<P> {
+    account::AAAccount::new(Arc::new(provider.clone()), vec![owner], salt, factory, entry_point, chain_id)
+        .with_factory_abi(factory::FactoryAbi::Simple)
+}
+
+/// Deploy a new smart account using the factory
+async fn deploy_smart_account(
+    private_key: &str,
+    factory: &str,
+    salt: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+    tag: Option<&str>,
+) -> Result<()> {
+    out!("🚀 {}", t!("deploy-start"));
+    
+    // Create wallet from private key
+    let wallet = Wallet::from_hex(private_key)?;
+    out!("{}", t!("label-deployer", address = wallet.address()));
+    
+    // Parse factory address
+    let factory_addr = Address::from_str(factory)?;
+    out!("{}", t!("label-factory", address = factory_addr));
+    
+    // Parse salt
+    let salt_u256 = hexutil::parse_salt(salt)?;
+    
+    out!("{}", t!("deploy-via-factory"));
+    out!("{}", t!("label-factory", address = factory_addr));
+    out!("{}", t!("label-owner", address = wallet.address()));
+    out!("{}", t!("label-salt", salt = hexutil::format_salt(salt_u256)));
+    
+    
+    // Create bundler client
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let bundler_client = bundler_at(rpc_url.to_string(), entry_point_addr, U256::from(chain_id));
+    
+    // First, get the predicted address
+    match bundler_client.get_predicted_address(factory_addr, wallet.address(), salt_u256).await {
+        Ok(predicted_address) => {
+            out!("📍 {}", t!("predicted-address", address = predicted_address));
+            out!("💡 {}", t!("fund-predicted-address"));
+            
+            out!("🔧 {}", t!("deploy-creating-userop"));
+            
+            let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+            
+            // Create concrete provider type for aa-sdk-rs
+            let provider = providers::http(rpc_url)?;
+            
+            // Create SimpleAccount with proper factory address, its initCode carries the salt
+            let simple_account = simple_account(&provider, wallet.address(), salt_u256, factory_addr, entry_point_addr, chain_id);
+            
+            // Create SmartAccountProvider
+            let smart_provider = SmartAccountProvider::new(provider, simple_account);
+            
+            // Let aa-sdk-rs automatically handle deployment - this is the key fix from the documentation!
+            out!("🔧 {}", t!("deploy-sdk-handles"));
+            out!("📊 {}", t!("deploy-sdk-will"));
+            out!("  - {}", t!("deploy-sdk-detect"));
+            out!("  - {}", t!("deploy-sdk-initcode"));
+            out!("  - {}", t!("deploy-sdk-sender"));
+            out!("  - {}", t!("deploy-sdk-nonce"));
+            
+            // Parse gas fees
+            let (max_fee, priority_fee) = resolve_fees(rpc_url, max_fee_per_gas, max_priority_fee_per_gas).await?;
+            
+            out!("{}", t!("gas-fees-wei", max = max_fee, priority = priority_fee));
+            
+            // Create a simple UserOperation and let aa-sdk-rs handle everything
+            let user_op_request = UserOperationBuilder::new(
+                predicted_address,  // Target the predicted account address
+                U256::ZERO,         // No value transfer
+                Bytes::new()        // Empty call data for deployment
+            )
+            .with_gas_fees(max_fee, priority_fee)
+            .build();
+            
+            out!("✅ {}", t!("deploy-userop-created"));
+            out!("{}", t!("label-predicted-account", address = predicted_address));
+            out!("{}", t!("deploy-sdk-factory-calls"));
+            
+            out!("🚀 {}", t!("deploy-submitting"));
+            
+            if preflight::dry_run() {
+                let filler = &smart_provider;
+                let filled = retry::retry("fill UserOperation", || {
+                    let mut request = user_op_request.clone();
+                    async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+                }).await?;
+                return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+            }
+            
+            // Submit using SmartAccountProvider to actually deploy the account
+            match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+                Ok(user_op_hash) => {
+                    out!("✅ {}", t!("deploy-initiated"));
+                    out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+                    journal_operation(&format!("{:?}", user_op_hash), chain_id, predicted_address, "deploy-account", tag, false);
+                    record_deployment(chain_id, factory_addr, predicted_address, vec![wallet.address()], salt_u256);
+                    out!("{}", t!("deploy-will-be-at", address = predicted_address));
+                    out!("{}", t!("deploy-track"));
+                }
+                Err(e) => {
+                    out!("❌ {}", t!("deploy-failed", error = e));
+                    out!("{}", t!("make-sure"));
+                    out!("  1. {}", t!("check-bundler-running"));
+                    out!("  2. {}", t!("check-predicted-funded"));
+                    out!("  3. {}", t!("check-factory-deployed"));
+                    return Err(exit::Reported(anyhow::anyhow!("{}", e)).into());
+                }
+            }
+        }
+        Err(e) => {
+            out!("❌ {}", t!("predict-failed", error = e));
+            out!("{}", t!("hint-factory-deployed"));
+            return Err(exit::Reported(e).into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Comma-separated owner list of a multi-owner account, checked as the factory checks it
+fn parse_owners(owners: &str) -> Result<Vec<Address>> {
+    let owner_addresses: Vec<Address> = owners
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| Address::from_str(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    if owner_addresses.is_empty() {
+        return Err(anyhow::anyhow!("At least one owner is required"));
+    }
+    if owner_addresses.len() > 10 {
+        return Err(anyhow::anyhow!("Maximum 10 owners allowed"));
+    }
+    
+    // Check for duplicates
+    for i in 0..owner_addresses.len() {
+        for j in (i + 1)..owner_addresses.len() {
+            if owner_addresses[i] == owner_addresses[j] {
+                return Err(anyhow::anyhow!("Duplicate owner address: {}", owner_addresses[i]));
+            }
+        }
+    }
+    Ok(owner_addresses)
+}
+
+/// Deploy a new smart account with multiple owners using AAAccountFactory via bundler
+async fn deploy_multi_owner_account(
+    private_key: &str,
+    factory: &str,
+    owners: &str,
+    threshold: Option<u64>,
+    salt: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    out!("🚀 Deploying new multi-owner smart account using AAAccountFactory via bundler...");
+    
+    // Create wallet from private key
+    let wallet = Wallet::from_hex(private_key)?;
+    out!("{}", t!("label-deployer", address = wallet.address()));
+    
+    // Parse factory address
+    let factory_addr = Address::from_str(factory)?;
+    out!("AAAccountFactory contract: {}", factory_addr);
+    
+    let owner_addresses = parse_owners(owners)?;
+    
+    if let Some(threshold) = threshold {
+        account::validate_threshold(threshold, owner_addresses.len())?;
+    }
+    
+    out!("Owners ({}):", owner_addresses.len());
+    for (i, owner) in owner_addresses.iter().enumerate() {
+        out!("  Owner {}: {}", i + 1, owner);
+    }
+    if let Some(threshold) = threshold {
+        out!("Threshold: {} of {} owners", threshold, owner_addresses.len());
+    }
+    
+    // Parse salt
+    let salt_u256 = hexutil::parse_salt(salt)?;
+    
+    out!("{}", t!("label-salt", salt = hexutil::format_salt(salt_u256)));
+    
+    
+    out!("🔧 Setting up multi-owner AAAccount...");
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    // AAAccount derives its address and initCode from the full owner set and salt
+    let aa_account = account::AAAccount::new(
+        Arc::new(provider.clone()),
+        owner_addresses.clone(),
+        salt_u256,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    
+    let predicted_address = aa_account.get_counterfactual_address().await?;
+    out!("📍 Multi-owner predicted address: {}", predicted_address);
+    out!("💡 {}", t!("fund-predicted-address"));
+    
+    if aa_account.is_account_deployed().await? {
+        out!("✅ Account already deployed at: {}", predicted_address);
+        return Ok(());
+    }
+    
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    // Parse gas fees - Set higher values to meet bundler requirements
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    
+    out!("🔧 {}", t!("deploy-creating-userop"));
+    out!("📊 {}", t!("deploy-sdk-will"));
+    out!("  - {}", t!("deploy-sdk-detect"));
+    out!("  - Generate createAccountWithOwners initCode");
+    out!("  - {}", t!("deploy-sdk-sender"));
+    out!("  - {}", t!("deploy-sdk-nonce"));
+    
+    // Self-call with empty data, only the deployment matters, unless the same operation sets the threshold.
+    // The deployer's single signature is validated before setThreshold runs
+    let deploy_call_data = match threshold {
+        Some(threshold) if threshold > 1 => account::set_threshold_call_data(threshold),
+        _ => Bytes::new(),
+    };
+    let user_op_request = UserOperationBuilder::new(
+        predicted_address,
+        U256::ZERO,
+        deploy_call_data
+    )
+    .with_gas_fees(max_fee, priority_fee)
+    .build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    out!("🚀 Submitting multi-owner deployment UserOperation to bundler...");    
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Multi-owner smart account deployment initiated successfully!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, predicted_address, "deploy-multi-owner-account", tag, false);
+            record_deployment(chain_id, factory_addr, predicted_address, owner_addresses.clone(), salt_u256);
+            out!("{}", t!("deploy-will-be-at", address = predicted_address));
+            out!("{}", t!("deploy-track"));
+            
+            out!();
+            out!("💡 Multi-owner features:");
+            match threshold {
+                Some(threshold) if threshold > 1 => {
+                    out!("- Every operation needs {} owner signatures, collect them with op propose / op sign", threshold);
+                }
+                _ => out!("- Any owner can execute transactions"),
+            }
+            out!("- Owners can add new owners (up to 10 total) with add-owner");
+            out!("- Owners can remove other owners with remove-owner");
+            out!("- Cannot remove the last owner");
+        }
+        Err(e) => {
+            out!("❌ {}", t!("deploy-failed", error = e));
+            out!("{}", t!("make-sure"));
+            out!("  1. {}", t!("check-bundler-running"));
+            out!("  2. {}", t!("check-predicted-funded"));  
+            out!("  3. The AAAccountFactory contract is deployed and accessible");
+            return Err(exit::Reported(anyhow::anyhow!("{}", e)).into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Owner set change applied by add-owner / remove-owner / set-threshold
+enum OwnerChange<'a> {
+    Add(&'a str),
+    Remove(&'a str),
+    Threshold(u64),
+}
+
+/// Add or remove an owner of a deployed multi-owner account, or change its threshold
+/// The account calls addOwner/removeOwner/setThreshold on itself through execute, signed by an existing owner
+async fn change_account_owner(
+    private_key: &str,
+    account: &str,
+    factory: &str,
+    rpc_url: &str,
+    entry_point_addr: Address,
+    chain_id: u64,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+    change: OwnerChange<'_>,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let factory_addr = Address::from_str(factory)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    let (kind, call_data, add, remove, new_threshold) = match change {
+        OwnerChange::Add(owner) => {
+            let owner = Address::from_str(owner)?;
+            out!("👥 Adding owner {} to smart account {}", owner, account_addr);
+            ("add-owner", account::add_owner_call_data(owner), Some(owner), None, None)
+        }
+        OwnerChange::Remove(owner) => {
+            let owner = Address::from_str(owner)?;
+            out!("👥 Removing owner {} from smart account {}", owner, account_addr);
+            ("remove-owner", account::remove_owner_call_data(owner), None, Some(owner), None)
+        }
+        OwnerChange::Threshold(threshold) => {
+            out!("👥 Setting the threshold of smart account {} to {}", account_addr, threshold);
+            ("set-threshold", account::set_threshold_call_data(threshold), None, None, Some(threshold))
+        }
+    };
+    out!("Signing owner: {}", wallet.address());
+    
+    let provider = providers::http(rpc_url)?;
+    
+    // Fail fast on changes the contract would revert
+    out!("🔍 Checking current owners...");
+    let owners = account::get_owners(&provider, account_addr).await?;
+    let threshold = account::get_threshold(&provider, account_addr).await;
+    account::validate_owner_change(&owners, threshold, wallet.address(), add, remove)?;
+    if let Some(new_threshold) = new_threshold {
+        account::validate_threshold(new_threshold, owners.len())?;
+    }
+    // One owner key cannot satisfy a threshold above one, the change has to collect signatures
+    if let Some(threshold) = threshold.filter(|threshold| *threshold > 1) {
+        return Err(anyhow::anyhow!(
+            "{} needs {} owner signatures, propose the change instead:\n  op propose --account {} --target {} --call-data {} --file <proposal.json>",
+            account_addr, threshold, account_addr, account_addr, call_data
+        ));
+    }
+    
+    out!("✅ Current owners: {}", owners.len());
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, max_fee_per_gas, max_priority_fee_per_gas).await?;
+    
+    // The account executes the owner change on itself
+    let mut user_op_request = UserOperationBuilder::new(account_addr, U256::ZERO, call_data)
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    if let Some(nonce) = allocate_nonce(&provider, entry_point_addr, chain_id, account_addr, None).await? {
+        user_op_request.nonce = Some(nonce);
+    }
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let filler = &smart_provider;
+    let user_op_request = retry::retry("fill UserOperation", || {
+        let mut request = user_op_request.clone();
+        async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+    }).await?;
+    
+    if preflight::dry_run() {
+        resync_nonce(chain_id, account_addr, None);
+        return dry_run(rpc_url, entry_point_addr, chain_id, &user_op_request, &wallet).await;
+    }
+    
+    // A nonce taken in the meantime is read again and the operation re-signed with it, per --on-nonce-error
+    let (wallet, smart_provider) = (&wallet, &smart_provider);
+    let sent = nonce::with_recovery(
+        |nonce| {
+            let mut request = user_op_request.clone();
+            async move {
+                if nonce.is_some() {
+                    request.nonce = nonce;
+                }
+                run_preflight(rpc_url, entry_point_addr, chain_id, &request, wallet).await?;
+                out!("🚀 Submitting owner change UserOperation...");
+                retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(request.clone(), wallet.signer()))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            }
+        },
+        || refetch_nonce(rpc_url, entry_point_addr, chain_id, account_addr, None),
+    ).await;
+    match sent {
+        Ok(user_op_hash) => {
+            out!("✅ Owner change submitted successfully!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, kind, tag, false);
+            out!("💡 Verify once included with: list-owners --account {}", account_addr);
+        }
+        Err(e) => {
+            out!("❌ Owner change failed: {}", e);
+            out!("{}", t!("make-sure"));
+            out!("  1. The account is a deployed AAAccount that accepts owner changes from itself");
+            out!("  2. The account has ETH deposited for gas fees");
+            out!("  3. The bundler is running and supports eth_sendUserOperation");
+        }
+    }
+    
+    Ok(())
+}
+
+/// Owner seat handed over by account rotate-owner
+struct OwnerRotation<'a> {
+    account: &'a str,
+    new_owner: &'a str,
+    old_owner: Option<&'a str>,
+    new_owner_signature: Option<&'a str>,
+}
+
+/// Swap an owner for a new one: addOwner and removeOwner batched into one owner-signed UserOperation
+/// The new owner first proves it holds its key, so a mistyped address cannot take over the seat
+async fn rotate_account_owner(
+    private_key: &str,
+    rotation: OwnerRotation<'_>,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(rotation.account)?;
+    let new_owner = Address::from_str(rotation.new_owner)?;
+    let old_owner = rotation.old_owner.map(Address::from_str).transpose()?.unwrap_or(wallet.address());
+    let factory_addr = Address::from_str(factory)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    out!("🔄 Rotating owner {} of {} to {}", old_owner, account_addr, new_owner);
+    let provider = providers::http(rpc_url)?;
+    
+    // Fail fast on changes the contract would revert
+    let owners = account::get_owners(&provider, account_addr).await?;
+    let threshold = account::get_threshold(&provider, account_addr).await;
+    account::validate_owner_change(&owners, threshold, wallet.address(), Some(new_owner), None)?;
+    if !owners.contains(&old_owner) {
+        return Err(anyhow::anyhow!("{} is not an owner", old_owner));
+    }
+    if let Some(threshold) = threshold.filter(|threshold| *threshold > 1) {
+        return Err(anyhow::anyhow!("{} needs {} owner signatures, propose the rotation with op propose instead", account_addr, threshold));
+    }
+    
+    let challenge = account::rotation_challenge(account_addr, new_owner, chain_id);
+    let Some(proof) = rotation.new_owner_signature else {
+        out!("✍️  The new owner signs this message first:");
+        out!("  {}", challenge);
+        out!("💡 With its key: sign-message --message \"{}\" --private-key-stdin", challenge);
+        return Err(anyhow::anyhow!("Pass the new owner's signature with --new-owner-signature"));
+    };
+    account::verify_rotation_proof(&challenge, new_owner, &hexutil::decode_fixed::<65>("new owner signature", proof)?)?;
+    if !provider.get_code_at(new_owner).await?.is_empty() {
+        return Err(anyhow::anyhow!("{} is a contract, the account only accepts ECDSA signatures from owners", new_owner));
+    }
+    out!("✅ {} holds its key", new_owner);
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let batched = account::supports_batch(&provider, account_addr, wallet.address()).await;
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    // Without executeBatch only the addition is sent, removing the old owner stays a separate step
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let builder = if batched {
+        UserOperationBuilder::new_batch(account::rotate_owner_calls(account_addr, old_owner, new_owner))
+    } else {
+        out!("⚠️  {} has no executeBatch, only adding the new owner", account_addr);
+        UserOperationBuilder::new(account_addr, U256::ZERO, account::add_owner_call_data(new_owner))
+    };
+    let user_op_request = builder.with_gas_fees(max_fee, priority_fee).build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    out!("🚀 Submitting owner rotation UserOperation...");
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Owner rotation submitted successfully!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "rotate-owner", tag, false);
+            if !batched {
+                out!("💡 Once included, finish with: remove-owner --account {} --owner {}", account_addr, old_owner);
+            }
+            out!("💡 Verify once included with: list-owners --account {}", account_addr);
+        }
+        Err(e) => {
+            out!("❌ Owner rotation failed: {}", e);
+            return Err(exit::Reported(anyhow::anyhow!("{}", e)).into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print the owners of a deployed multi-owner account
+async fn list_account_owners(account: &str, rpc_url: &str) -> Result<()> {
+    let account_addr = Address::from_str(account)?;
+    let provider = providers::http(rpc_url)?;
+    
+    let owners = account::get_owners(&provider, account_addr).await?;
+    let threshold = account::get_threshold(&provider, account_addr).await;
+    
+    out!("👥 Owners of {} ({}):", account_addr, owners.len());
+    for (i, owner) in owners.iter().enumerate() {
+        out!("  Owner {}: {}", i + 1, owner);
+    }
+    match threshold {
+        Some(threshold) => out!("Threshold: {} of {} owners", threshold, owners.len()),
+        None => out!("Threshold: none, any single owner signs"),
+    }
+    
+    Ok(())
+}
+
+/// What a new session key is allowed to do
+struct SessionScope<'a> {
+    target: &'a str,
+    selector: &'a str,
+    valid_for: u64,
+    max_value: &'a str,
+    max_ops: u32,
+}
+
+/// Generate a session key, register it on the account and save it to `out`
+/// The owner signs a UserOperation in which the account calls registerSessionKey on itself
+async fn create_session_key(
+    private_key: &str,
+    account: &str,
+    scope: SessionScope<'_>,
+    out: &Path,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let factory_addr = Address::from_str(factory)?;
+    let target_addr = Address::from_str(scope.target)?;
+    let selector = session::parse_selector(scope.selector)?;
+    let max_value = units::parse_value(scope.max_value)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    if target_addr == account_addr {
+        return Err(anyhow::anyhow!("A session key cannot target the account itself"));
+    }
+    
+    let now = clock::now_secs();
+    let valid_until = now + scope.valid_for;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    let owners = account::get_owners(&provider, account_addr).await?;
+    if !owners.contains(&wallet.address()) {
+        return Err(anyhow::anyhow!("Signer {} is not an owner of {}", wallet.address(), account_addr));
+    }
+    
+    let session_wallet = WalletFactory::random()?;
+    let session_file = session::SessionKeyFile {
+        account: account_addr,
+        chain_id,
+        address: session_wallet.address(),
+        private_key: session_wallet.export_private_key(),
+        target: target_addr,
+        selector,
+        valid_until,
+        max_value,
+    };
+    // Save before registering, so a registered key is never lost
+    session_file.save(out)?;
+    
+    out!("🔑 Registering session key {} on {}", session_wallet.address(), account_addr);
+    out!("  {}", t!("label-target", address = target_addr));
+    if selector == Default::default() {
+        out!("  Selector: any");
+    } else {
+        out!("  Selector: {}", selector);
+    }
+    out!("  Valid until: {} ({}s from now)", valid_until, scope.valid_for);
+    out!("  Value cap: {} ETH per call", units::format_units(max_value, 18));
+    out!("  Operations: {}", scope.max_ops);
+    
+    let call_data = session::register_call_data(session_wallet.address(), target_addr, selector, valid_until, max_value, scope.max_ops)?;
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let user_op_request = UserOperationBuilder::new(account_addr, U256::ZERO, call_data)
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    out!("🚀 Submitting session key registration...");
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Session key registration submitted!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "session-create", tag, false);
+            out!("💾 Session key saved to {}", out.display());
+            out!("💡 Use it with: submit --session-key {} --target {} --call-data <hex>", out.display(), target_addr);
+        }
+        Err(e) => {
+            out!("❌ Session key registration failed: {}", e);
+            out!("The unregistered key was left in {}, delete it or retry", out.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Revoke a session key through an owner-signed UserOperation
+async fn revoke_session_key(
+    private_key: &str,
+    account: &str,
+    key: &str,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let key_addr = Address::from_str(key)?;
+    let factory_addr = Address::from_str(factory)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    let owners = account::get_owners(&provider, account_addr).await?;
+    if !owners.contains(&wallet.address()) {
+        return Err(anyhow::anyhow!("Signer {} is not an owner of {}", wallet.address(), account_addr));
+    }
+    
+    out!("🔒 Revoking session key {} on {}", key_addr, account_addr);
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let user_op_request = UserOperationBuilder::new(account_addr, U256::ZERO, session::revoke_call_data(key_addr))
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Session key revocation submitted!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "session-revoke", tag, false);
+        }
+        Err(e) => {
+            out!("❌ Session key revocation failed: {}", e);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Submit a call signed by a session key instead of an owner
+/// The call is checked against the key's scope locally first, the account enforces the same rules
+async fn submit_with_session_key(
+    session_key: &Path,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    factory: &str,
+    rpc_url: &str,
+    entry_point: &Option<String>,
+    nonce_key: Option<U192>,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+    tag: Option<&str>,
+) -> Result<()> {
+    let session_file = session::SessionKeyFile::load(session_key)?;
+    let session_wallet = session_file.wallet()?;
+    let target_addr = Address::from_str(target)?;
+    let factory_addr = Address::from_str(factory)?;
+    let entry_point_addr = entry_point_arg(entry_point, session_file.chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, session_file.chain_id).await?;
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    let value_amount = U256::from_str_radix(value, 10)?;
+    
+    let now = clock::now_secs();
+    session_file.check_call(target_addr, value_amount, &call_data_bytes, now)?;
+    
+    out!("🔑 Submitting via session key {} for {}", session_wallet.address(), session_file.account);
+    
+    let provider = providers::http(rpc_url)?;
+    
+    if !session::is_active(&provider, session_file.account, session_file.address, now).await? {
+        return Err(anyhow::anyhow!("Session key {} is not registered or was revoked", session_file.address));
+    }
+    preflight::check_target(&provider, target_addr, &call_data_bytes).await?;
+    validation::check_value(target_addr, value_amount)?;
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        session_file.account,
+        factory_addr,
+        entry_point_addr,
+        session_file.chain_id,
+    );
+    let keyed = allocate_nonce(&provider, entry_point_addr, session_file.chain_id, session_file.account, nonce_key).await?;
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, max_fee_per_gas, max_priority_fee_per_gas).await?;
+    let mut user_op_request = UserOperationBuilder::new(target_addr, value_amount, call_data_bytes)
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    if keyed.is_some() {
+        user_op_request.nonce = keyed;
+    }
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        resync_nonce(session_file.chain_id, session_file.account, nonce_key);
+        return dry_run(rpc_url, entry_point_addr, session_file.chain_id, &filled, &session_wallet).await;
+    }
+    
+    let (smart_provider, session_wallet) = (&smart_provider, &session_wallet);
+    let sent = nonce::with_recovery(
+        |nonce| {
+            let mut request = user_op_request.clone();
+            async move {
+                if nonce.is_some() {
+                    request.nonce = nonce;
+                }
+                retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(request.clone(), session_wallet.signer()))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            }
+        },
+        || refetch_nonce(rpc_url, entry_point_addr, session_file.chain_id, session_file.account, nonce_key),
+    ).await;
+    match sent {
+        Ok(user_op_hash) => {
+            out!("✅ Transaction submitted successfully!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), session_file.chain_id, session_file.account, "session-submit", tag, false);
+        }
+        Err(e) => {
+            resync_nonce(session_file.chain_id, session_file.account, nonce_key);
+            out!("❌ Session key submission failed: {}", e);
+            out!("Make sure the key has not expired and the account has ETH deposited for gas fees");
+        }
+    }
+    
+    Ok(())
+}
+
+/// Add a guardian through an owner-signed UserOperation, batched with the recovery config when given
+async fn add_recovery_guardian(
+    private_key: &str,
+    account: &str,
+    guardian: &str,
+    config: Option<(u64, u64)>,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let guardian_addr = Address::from_str(guardian)?;
+    let factory_addr = Address::from_str(factory)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    let owners = account::get_owners(&provider, account_addr).await?;
+    if !owners.contains(&wallet.address()) {
+        return Err(anyhow::anyhow!("Signer {} is not an owner of {}", wallet.address(), account_addr));
+    }
+    if owners.contains(&guardian_addr) {
+        return Err(anyhow::anyhow!("{} is an owner and cannot also be a guardian", guardian_addr));
+    }
+    
+    let status = recovery::get_status(&provider, account_addr).await?;
+    if status.guardians.contains(&guardian_addr) {
+        return Err(anyhow::anyhow!("{} is already a guardian", guardian_addr));
+    }
+    if let Some((threshold, _)) = config {
+        let guardian_count = status.guardians.len() as u64 + 1;
+        if threshold == 0 || threshold > guardian_count {
+            return Err(anyhow::anyhow!("Threshold must be between 1 and {} guardians", guardian_count));
+        }
+    }
+    
+    out!("🛡️  Adding guardian {} to {}", guardian_addr, account_addr);
+    if let Some((threshold, delay)) = config {
+        out!("  Threshold: {} guardian approvals", threshold);
+        out!("  Timelock: {}s", delay);
+    }
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let user_op_request = UserOperationBuilder::new_batch(recovery::add_guardian_calls(account_addr, guardian_addr, config))
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Guardian change submitted!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "add-guardian", tag, false);
+            if config.is_none() && status.threshold == 0 {
+                out!("💡 Recovery stays disabled until a threshold is set with --threshold");
+            }
+        }
+        Err(e) => {
+            out!("❌ Guardian change failed: {}", e);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Recovery steps taken by guardians, or anyone in the case of execute
+enum GuardianAction<'a> {
+    Initiate(&'a str),
+    Approve,
+    Execute,
+}
+
+/// Send a recovery step as a regular transaction from the guardian's EOA
+async fn send_guardian_action(private_key: &str, account: &str, action: GuardianAction<'_>, rpc_url: &str) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    
+    let provider = providers::http(rpc_url)?;
+    let status = recovery::get_status(&provider, account_addr).await?;
+    let now = clock::now_secs();
+    
+    // Fail fast on steps the contract would revert
+    let call_data = match action {
+        GuardianAction::Initiate(new_owners) => {
+            let new_owners: Vec<Address> = new_owners
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(Address::from_str)
+                .collect::<Result<Vec<_>, _>>()?;
+            if new_owners.is_empty() {
+                return Err(anyhow::anyhow!("At least one new owner is required"));
+            }
+            if !status.guardians.contains(&wallet.address()) {
+                return Err(anyhow::anyhow!("{} is not a guardian of {}", wallet.address(), account_addr));
+            }
+            if status.threshold == 0 {
+                return Err(anyhow::anyhow!("Recovery is not configured on {}, an owner must set a threshold first", account_addr));
+            }
+            if status.pending() {
+                return Err(anyhow::anyhow!("A recovery is already pending, approve it or have an owner cancel it"));
+            }
+            out!("🛟 Initiating recovery of {} to {} new owner(s)", account_addr, new_owners.len());
+            recovery::initiate_call_data(new_owners)
+        }
+        GuardianAction::Approve => {
+            if !status.guardians.contains(&wallet.address()) {
+                return Err(anyhow::anyhow!("{} is not a guardian of {}", wallet.address(), account_addr));
+            }
+            if !status.pending() {
+                return Err(anyhow::anyhow!("No recovery is pending on {}", account_addr));
+            }
+            out!("🛟 Approving recovery of {}", account_addr);
+            recovery::approve_call_data()
+        }
+        GuardianAction::Execute => {
+            if !status.pending() {
+                return Err(anyhow::anyhow!("No recovery is pending on {}", account_addr));
+            }
+            if status.approvals_missing() > 0 {
+                return Err(anyhow::anyhow!("Recovery needs {} more guardian approval(s)", status.approvals_missing()));
+            }
+            if !status.ready(now) {
+                return Err(anyhow::anyhow!("Recovery is timelocked for another {}s", status.seconds_remaining(now)));
+            }
+            out!("🛟 Executing recovery of {}", account_addr);
+            recovery::execute_call_data()
+        }
+    };
+    
+    if preflight::dry_run() {
+        out!("🧪 Dry run, nothing was sent. Transaction from {}:", wallet.address());
+        out!("  to: {}", account_addr);
+        out!("  data: 0x{}", hex::encode(&call_data));
+        return Ok(());
+    }
+    let tx_hash = recovery::send_transaction(&wallet, rpc_url, account_addr, call_data).await?;
+    out!("✅ Transaction mined: {:?}", tx_hash);
+    out!("💡 Check progress with: recovery status --account {}", account_addr);
+    
+    Ok(())
+}
+
+/// Cancel a pending recovery through an owner-signed UserOperation
+async fn cancel_recovery(
+    private_key: &str,
+    account: &str,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let factory_addr = Address::from_str(factory)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    let status = recovery::get_status(&provider, account_addr).await?;
+    if !status.pending() {
+        return Err(anyhow::anyhow!("No recovery is pending on {}", account_addr));
+    }
+    
+    out!("🚫 Cancelling recovery of {}", account_addr);
+    
+    let aa_account = account::AAAccount::at(
+        Arc::new(provider.clone()),
+        account_addr,
+        factory_addr,
+        entry_point_addr,
+        chain_id,
+    );
+    let smart_provider = SmartAccountProvider::new(provider, aa_account);
+    
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let user_op_request = UserOperationBuilder::new(account_addr, U256::ZERO, recovery::cancel_call_data())
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+    
+    if preflight::dry_run() {
+        let filler = &smart_provider;
+        let filled = retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }).await?;
+        return dry_run(rpc_url, entry_point_addr, chain_id, &filled, &wallet).await;
+    }
+    
+    match retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await {
+        Ok(user_op_hash) => {
+            out!("✅ Recovery cancellation submitted!");
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "cancel-recovery", tag, false);
+        }
+        Err(e) => {
+            out!("❌ Recovery cancellation failed: {}", e);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print guardians, threshold and the pending recovery of an account
+async fn show_recovery_status(account: &str, rpc_url: &str) -> Result<()> {
+    let account_addr = Address::from_str(account)?;
+    let provider = providers::http(rpc_url)?;
+    
+    let status = recovery::get_status(&provider, account_addr).await?;
+    let now = clock::now_secs();
+    
+    out!("🛡️  Recovery for {}", account_addr);
+    out!("Guardians ({}):", status.guardians.len());
+    for guardian in &status.guardians {
+        out!("  {}", guardian);
+    }
+    if status.threshold == 0 {
+        out!("Threshold: not configured, recovery disabled");
+    } else {
+        out!("Threshold: {} of {}", status.threshold, status.guardians.len());
+        out!("Timelock: {}s", status.delay);
+    }
+    
+    if !status.pending() {
+        out!("No recovery pending");
+        return Ok(());
+    }
+    
+    out!("⏳ Pending recovery to {} owner(s):", status.new_owners.len());
+    for owner in &status.new_owners {
+        out!("  {}", owner);
+    }
+    out!("Approvals: {}/{}", status.approvals, status.threshold);
+    if status.ready(now) {
+        out!("✅ Ready to execute with: recovery execute --account {}", account_addr);
+    } else if status.seconds_remaining(now) > 0 {
+        out!("Executable in {}s (at {})", status.seconds_remaining(now), status.execute_after);
+    }
+    
+    Ok(())
+}
+
+/// Print support and deposit status of an account on every known EntryPoint
+async fn show_entry_points(account: &str, rpc_url: &str) -> Result<()> {
+    let account_addr = Address::from_str(account)?;
+    let provider = providers::http(rpc_url)?;
+    
+    let statuses = entrypoint::entry_point_statuses(&provider, account_addr).await?;
+    
+    out!("🧭 EntryPoints for {}", account_addr);
+    for status in &statuses {
+        let marker = if status.active { "✅ active" } else if status.deployed { "  " } else { "⚪ not deployed" };
+        out!("  {} {} {}", status.version, status.version.address(), marker);
+        if status.deployed {
+            out!("      Deposit: {} wei", status.deposit);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Show an account's on-chain state, given its address or the (owner, salt) it was created from
+/// Sync the factory's account index and print the accounts `owner` was created as an owner of
+async fn list_owned_accounts(owner: &str, factory: &str, rpc_url: &str, from_block: Option<u64>, no_sync: bool) -> Result<()> {
+    let owner_addr = Address::from_str(owner)?;
+    let factory_addr = Address::from_str(factory)?;
+    let provider = providers::http(rpc_url)?;
+    let chain_id = providers::registry().chain_id(rpc_url).await?;
+    
+    let store = indexer::AccountIndex::open_default()?;
+    let mut index = store.load(chain_id, factory_addr)?;
+    if !no_sync {
+        let result = indexer::sync(&provider, factory_addr, &mut index, from_block).await;
+        // Whatever was scanned before a failure is kept, the next sync resumes from there
+        store.save(chain_id, factory_addr, index.clone())?;
+        let added = result?;
+        out!("✅ Indexed {} new account(s), synced up to block {}", added, index.next_block.saturating_sub(1));
+    }
+    
+    let accounts = index.owned_by(owner_addr);
+    if accounts.is_empty() {
+        out!("No accounts of {} created by {} in the indexed blocks", owner_addr, factory_addr);
+        if index.next_block == 0 {
+            out!("💡 Nothing is indexed yet, run without --no-sync");
+        } else {
+            out!("💡 Accounts created earlier are found with --from-block <factory deployment block>");
+        }
+        return Ok(());
+    }
+    out!("📇 Accounts of {} created by {} (chain {}):", owner_addr, factory_addr, chain_id);
+    for account in accounts {
+        let co_owners = account.owners.len() - 1;
+        let co_owners = if co_owners > 0 { format!(", {} other initial owner(s)", co_owners) } else { String::new() };
+        let block = match account.block {
+            0 => "deployed from here, not synced yet".to_string(),
+            block => format!("block {}", block),
+        };
+        out!("  {} salt {} ({}{})", account.account, account.salt, block, co_owners);
+    }
+    out!("💡 Owners at creation; owners may have changed since, see account info --address <account>");
+    
+    Ok(())
+}
+
+async fn show_account_info(
+    address: Option<&str>,
+    owner_salt: Option<(&str, &str)>,
+    factory: &str,
+    nonce_keys: &[String],
+    rpc_url: &str,
+    qr: bool,
+) -> Result<()> {
+    let account_addr = match (address, owner_salt) {
+        (Some(address), _) => Address::from_str(address)?,
+        (None, Some((owner, salt))) => {
+            let bundler_client = bundler_at(rpc_url.to_string(), Address::ZERO, U256::ZERO);
+            bundler_client
+                .get_predicted_address(Address::from_str(factory)?, Address::from_str(owner)?, hexutil::parse_salt(salt)?)
+                .await?
+        }
+        (None, None) => return Err(anyhow::anyhow!("Pass --address, or --owner with --salt")),
+    };
+    let keys = nonce_keys.iter().map(|key| nonce::parse_key(key)).collect::<Result<Vec<_>>>()?;
+    
+    let provider = providers::http(rpc_url)?;
+    let info = account::overview(&provider, account_addr, &keys).await?;
+    
+    out!("📇 Account {}", info.address);
+    if info.deployed {
+        out!("  Deployed: yes");
+    } else {
+        out!("  Deployed: no (counterfactual, deployed by its first UserOperation)");
+    }
+    out!("  Balance: {} ETH", units::format_units(info.balance, 18));
+    out!("  EntryPoint deposits:");
+    for status in info.entry_points.iter().filter(|status| status.deployed) {
+        let marker = if status.active { " (active)" } else { "" };
+        out!("    {} {}: {} ETH{}", status.version, status.version.address(), units::format_units(status.deposit, 18), marker);
+    }
+    out!("  Nonces on {}:", info.entry_point);
+    for (key, next) in &info.nonces {
+        out!("    key {}: sequence {} (nonce {})", key, nonce::unpack(*next).1, next);
+    }
+    match &info.owners {
+        Some(owners) => {
+            out!("  Owners ({}):", owners.len());
+            for owner in owners {
+                out!("    {}", owner);
+            }
+            if let Some(threshold) = info.threshold {
+                out!("  Threshold: {} of {} owners", threshold, owners.len());
+            }
+        }
+        None if info.deployed => out!("  Owners: not available (no getOwners() on this account)"),
+        None => match owner_salt {
+            Some((owner, _)) => out!("  Owners: {} once deployed", owner),
+            None => out!("  Owners: set at deployment"),
+        },
+    }
+    if qr {
+        let chain_id = providers::registry().chain_id(rpc_url).await?;
+        qr::print_text("Fund", &qr::payment_uri(info.address, Some(chain_id)))?;
+    }
+    
+    Ok(())
+}
+
+/// Parameters of migrate-entry-point beyond the connection settings
+struct EntryPointMigration<'a> {
+    to: entrypoint::EntryPointVersion,
+    from: Option<entrypoint::EntryPointVersion>,
+    amount: Option<&'a str>,
+    /// Profile to re-point, when --set-default was given
+    profile_name: Option<String>,
+}
+
+/// Withdraw the deposit from one EntryPoint and deposit it into another in a single batch
+async fn migrate_entry_point(
+    private_key: &str,
+    account: &str,
+    factory: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    migration: EntryPointMigration<'_>,
+    tag: Option<&str>,
+) -> Result<()> {
+    let wallet = Wallet::from_hex(private_key)?;
+    let account_addr = Address::from_str(account)?;
+    let factory_addr = Address::from_str(factory)?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    out!("🧭 Inspecting EntryPoints for {}...", account_addr);
+    let statuses = entrypoint::entry_point_statuses(&provider, account_addr).await?;
+    let active = statuses
+        .iter()
+        .find(|s| s.active)
+        .ok_or_else(|| anyhow::anyhow!("{} does not report a known EntryPoint", account_addr))?;
+    // The migration UserOperation goes through the account's active EntryPoint
+    let rpc_url: &str = &preflight_bundler(rpc_url, active.version.address(), chain_id).await?;
+    let provider = providers::http(rpc_url)?;
+    let from = migration.from.unwrap_or(active.version);
+    let source = statuses.iter().find(|s| s.version == from).expect("every version has a status");
+    let target = statuses.iter().find(|s| s.version == migration.to).expect("every version has a status");
+    
+    if from == migration.to {
+        return Err(anyhow::anyhow!("Source and target EntryPoint are both {}", from));
+    }
+    if !target.deployed {
+        return Err(anyhow::anyhow!("EntryPoint {} is not deployed on this chain", migration.to));
+    }
+    
+    // Gas for this very operation is taken from the active deposit during validation,
+    // so draining it completely would make the withdrawal revert
+    let amount = match migration.amount {
+        Some(amount) => U256::from_str_radix(amount, 10)?,
+        None if source.active => {
+            return Err(anyhow::anyhow!(
+                "{} is the account's active EntryPoint and pays for this migration, pass --amount to leave room for gas (deposit: {} wei)",
+                from, source.deposit
+            ));
+        }
+        None => source.deposit,
+    };
+    if amount > source.deposit {
+        return Err(anyhow::anyhow!("Cannot move {} wei, deposit on {} is only {} wei", amount, from, source.deposit));
+    }
+    
+    out!("📦 Moving {} wei: {} → {}", amount, from, migration.to);
+    
+    if amount.is_zero() {
+        out!("ℹ️  Nothing to move on {}", from);
+    } else {
+        let aa_account = account::AAAccount::at(
+            Arc::new(provider.clone()),
+            account_addr,
+            factory_addr,
+            active.version.address(),
+            chain_id,
+        );
+        let smart_provider = SmartAccountProvider::new(provider, aa_account);
+        
+        let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+        
+        let user_op_request = UserOperationBuilder::new_batch(vec![
+            ExecuteCall::new(from.address(), U256::ZERO, entrypoint::withdraw_to_call_data(account_addr, amount)),
+            ExecuteCall::new(migration.to.address(), amount, entrypoint::deposit_to_call_data(account_addr)),
+        ])
+        .with_gas_fees(max_fee, priority_fee)
+        .build();
+        
+        if preflight::dry_run() {
+            let filler = &smart_provider;
+            let filled = retry::retry("fill UserOperation", || {
+                let mut request = user_op_request.clone();
+                async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+            }).await?;
+            // Signing and simulation assume v0.7 hashing, which the active EntryPoint may not use
+            out!("🧪 Dry run, nothing was sent and the profile is unchanged. Unsigned operation for {}:", active.version);
+            out!("{}", serde_json::to_string_pretty(&userop::RpcUserOperation::from(&filled))?);
+            return Ok(());
+        }
+        
+        out!("🚀 Submitting withdraw + deposit batch via {}...", active.version);
+        let user_op_hash = retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(user_op_request.clone(), wallet.signer())).await?;
+        out!("✅ Migration UserOperation submitted!");
+        out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+        journal_operation(&format!("{:?}", user_op_hash), chain_id, account_addr, "migrate-entry-point", tag, false);
+    }
+    
+    if let Some(profile_name) = migration.profile_name {
+        let path = CliConfig::default_path()?;
+        CliConfig::set_profile_value(&path, &profile_name, "entry_point", &migration.to.address().to_string())?;
+        out!("⚙️  Profile '{}' now uses EntryPoint {} ({})", profile_name, migration.to, migration.to.address());
+    }
+    
+    if !target.active {
+        out!("⚠️  The account still validates UserOperations only from {} (fixed at initialization)", active.version);
+        out!("   Deploy a new account bound to {} before routing operations through it", migration.to);
+    }
+    
+    Ok(())
+}
+
+/// Predict smart account address before deployment
+async fn predict_smart_account_address(
+    factory: &str,
+    owner: &str,
+    salt: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    qr: bool,
+) -> Result<()> {
+    out!("{}", t!("predict-start"));
+    
+    // Parse addresses
+    let factory_addr = Address::from_str(factory)?;
+    let owner_addr = Address::from_str(owner)?;
+    
+    // Parse salt
+    let salt_u256 = hexutil::parse_salt(salt)?;
+    
+    out!("{}", t!("predict-via-factory"));
+    out!("{}", t!("label-factory", address = factory_addr));
+    out!("{}", t!("label-owner", address = owner_addr));
+    out!("{}", t!("label-salt", salt = hexutil::format_salt(salt_u256)));
+    
+    verify_chain_id(rpc_url, chain_id).await?;
+    
+    // Create bundler client for contract calls
+    let bundler_client = bundler_at(
+        rpc_url.to_string(),
+        Address::ZERO, // Entry point not needed for this call
+        U256::from(chain_id),
+    );
+    
+    // Get real predicted address from the factory contract
+    match bundler_client.get_predicted_address(factory_addr, owner_addr, salt_u256).await {
+        Ok(predicted_address) => {
+            out!("✅ {}", t!("predict-result", address = predicted_address));
+            out!("{}", t!("predict-source"));
+            if qr {
+                qr::print_text("Fund", &qr::payment_uri(predicted_address, Some(chain_id)))?;
+            }
+        }
+        Err(e) => {
+            out!("❌ {}", t!("factory-call-failed", error = e));
+            out!("{}", t!("hint-factory-and-rpc"));
+            return Err(exit::Reported(e).into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Where forge writes the ERC1967Proxy artifact, relative to client/
+const PROXY_ARTIFACT: &str = "../contracts/out/ERC1967Proxy.sol/ERC1967Proxy.json";
+
+/// Predict a multi-owner account's address from the factory, then recompute it locally with CREATE2
+async fn predict_multi_owner_address(
+    factory: &str,
+    owners: &str,
+    salt: &str,
+    proxy_artifact: Option<&Path>,
     rpc_url: &str,
     chain_id: u64,
-    max_fee_per_gas: &str,
-    max_priority_fee_per_gas: &str,
+    qr: bool,
 ) -> Result<()> {
-    println!("🚀 Submitting transaction via smart account using aa-sdk-rs...");
+    out!("{}", t!("predict-start"));
     
-    // ✅ Setup
-    let wallet = Wallet::from_hex(private_key)?;
     let factory_addr = Address::from_str(factory)?;
-    let target_addr = Address::from_str(target)?;
-    let entry_point_addr = Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?;
+    let owner_addresses = parse_owners(owners)?;
+    let salt_u256 = hexutil::parse_salt(salt)?;
     
-    println!("🔧 Setting up aa-sdk-rs SmartAccount...");
-    println!("Factory: {}", factory_addr);
-    println!("Target: {}", target_addr);
-    println!("Owner EOA: {}", wallet.address());
+    out!("{}", t!("predict-via-factory"));
+    out!("{}", t!("label-factory", address = factory_addr));
+    for (i, owner) in owner_addresses.iter().enumerate() {
+        out!("  Owner {}: {}", i + 1, owner);
+    }
+    out!("{}", t!("label-salt", salt = hexutil::format_salt(salt_u256)));
     
-    let url = url::Url::parse(rpc_url)?;
-    let provider = ProviderBuilder::new().on_http(url);
+    verify_chain_id(rpc_url, chain_id).await?;
     
-    // TODO: Migrate to SimpleAccount::new_with_salt for better salt support
-    let simple_account = SimpleAccount::new(
-        Arc::new(provider.clone()),
-        wallet.address(),
-        factory_addr,
-        entry_point_addr,
-        chain_id,
-    );
+    let bundler_client = bundler_at(rpc_url.to_string(), Address::ZERO, U256::from(chain_id));
+    let predicted_address = match bundler_client.get_predicted_multi_owner_address(factory_addr, owner_addresses.clone(), salt_u256).await {
+        Ok(predicted_address) => predicted_address,
+        Err(e) => {
+            out!("❌ {}", t!("factory-call-failed", error = e));
+            out!("{}", t!("hint-factory-and-rpc"));
+            return Err(exit::Reported(e).into());
+        }
+    };
+    out!("✅ {}", t!("predict-result", address = predicted_address));
+    out!("{}", t!("predict-source"));
+    
+    // The factory's answer is only as good as the RPC; the same CREATE2 computed here must agree
+    let artifact_path = proxy_artifact.unwrap_or(Path::new(PROXY_ARTIFACT));
+    match std::fs::read_to_string(artifact_path) {
+        // Only AAAccountFactory's CREATE2 scheme is known here
+        _ if factory::resolve(factory::FactoryAbi::MultiOwner) != factory::FactoryAbi::MultiOwner => {
+            out!("⚠️  Skipped the local CREATE2 cross-check, it only knows AAAccountFactory's address scheme");
+        }
+        Ok(artifact) => {
+            let provider = providers::http(rpc_url)?;
+            let factory_contract = factory::AAAccountFactory::new(factory_addr, &provider);
+            let implementation = factory_contract.accountImplementation().call().await?._0;
+            let entry_point = factory_contract.entryPoint().call().await?._0;
+            let local = account::predict_multi_owner_address(
+                factory_addr,
+                &account::proxy_creation_code(&artifact)?,
+                implementation,
+                entry_point,
+                &owner_addresses,
+                salt_u256,
+            );
+            if local != predicted_address {
+                return Err(anyhow::anyhow!(
+                    "Local CREATE2 gives {} but the factory reports {}, do not fund either before checking the factory and {}",
+                    local,
+                    predicted_address,
+                    artifact_path.display()
+                ));
+            }
+            out!("✅ Cross-checked locally with CREATE2 (implementation {})", implementation);
+        }
+        Err(e) if proxy_artifact.is_some() => return Err(anyhow::anyhow!("Cannot read {}: {}", artifact_path.display(), e)),
+        Err(e) => {
+            out!("⚠️  Skipped the local CREATE2 cross-check, cannot read {}: {}", artifact_path.display(), e);
+            out!("💡 Run forge build in contracts/, or pass --proxy-artifact");
+        }
+    }
     
-    // ✅ 1. CHECK IF ACCOUNT IS DEPLOYED
-    println!("🔍 Checking if smart account is deployed...");
-    let is_deployed = simple_account.is_account_deployed().await?;
-    if !is_deployed {
+    if qr {
+        qr::print_text("Fund", &qr::payment_uri(predicted_address, Some(chain_id)))?;
+    }
+    out!("💡 The owner order is part of the address: deploy with the same --owners and --salt");
+    
+    Ok(())
+}
+
+/// Submit a sponsored UserOperation where gas is paid by paymaster
+async fn submit_sponsored_user_operation(
+    private_key: &str,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    factory: &str,
+    salt: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    nonce_key: Option<U192>,
+    paymaster_url: &str,
+    paymaster_api_key: &str,
+    preauth_token: Option<&str>,
+    paymaster_address: &str,
+    paymaster_encoding: paymaster::WireFormat,
+    valid_for: u64,
+    tag: Option<&str>,
+) -> Result<Option<String>> {
+    out!("🎉 {}", t!("sponsored-start"));
+    
+    // Setup
+    let wallet = Wallet::from_hex(private_key)?;
+    let factory_addr = Address::from_str(factory)?;
+    let target_addr = Address::from_str(target)?;
+    let paymaster_addr = Address::from_str(paymaster_address)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    out!("🔧 {}", t!("label-configuration"));
+    out!("  {}", t!("label-factory", address = factory_addr));
+    out!("  {}", t!("label-target", address = target_addr));
+    out!("  {}", t!("label-paymaster", address = paymaster_addr));
+    out!("  {}", t!("label-owner-eoa", address = wallet.address()));
+    
+    let provider = providers::http(rpc_url)?;
+    
+    // The account the factory deploys for the owner and --salt
+    let simple_account = simple_account(&provider, wallet.address(), hexutil::parse_salt(salt)?, factory_addr, entry_point_addr, chain_id);
+    
+    // Calls that do not depend on each other run concurrently, all on the one provider built above
+    out!("🔍 {}", t!("checking-deployed"));
+    let (is_deployed, account_addr, fees) = tokio::join!(
+        simple_account.is_account_deployed(),
+        simple_account.get_account_address(),
+        resolve_fees_with(&provider, None, None),
+    );
+    if !is_deployed? {
         let predicted_addr = simple_account.get_counterfactual_address().await?;
         return Err(anyhow::anyhow!(
-            "❌ Smart account not deployed at {}!\n💡 Run deploy-account first with:\n  cargo run -- deploy-account --factory {} --salt {} --private-key {}",
-            predicted_addr, factory, salt, private_key
+            "❌ {}\n💡 {}\n  cargo run -- deploy-sponsored --factory {} --salt {} --private-key-stdin --paymaster-url {} --paymaster-api-key {}",
+            t!("account-not-deployed", address = predicted_addr), t!("hint-run-first", command = "deploy-sponsored"), factory, salt, paymaster_url, paymaster_api_key
         ));
     }
     
-    let account_addr = simple_account.get_account_address().await?;
-    println!("✅ Using deployed smart account: {}", account_addr);
+    let account_addr = account_addr?;
+    out!("✅ {}", t!("using-deployed-account", address = account_addr));
+    check_token_payment(&provider, paymaster_addr, account_addr).await?;
     
-    // ✅ 2. PREPARE TRANSACTION PARAMETERS  
-    let call_data_bytes = if call_data.starts_with("0x") {
-        Bytes::from_str(call_data)?
-    } else {
-        Bytes::from_str(&format!("0x{}", call_data))?
-    };
+    // Prepare transaction parameters
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
     let value_amount = U256::from_str_radix(value, 10)?;
     
-    println!("🔧 Preparing transaction for smart account execution...");
-    println!("  External target: {}", target_addr);
-    println!("  Value to send: {} wei", value_amount);
-    println!("  Call data: 0x{}", hex::encode(&call_data_bytes));
+    out!("🔧 {}", t!("sponsored-preparing"));
+    out!("  {}", t!("label-target", address = target_addr));
+    out!("  {}", t!("label-value-wei", value = value_amount));
+    out!("  {}", t!("label-call-data", data = format!("0x{}", hex::encode(&call_data_bytes))));
+    out!("  {}", t!("label-paymaster-service", url = paymaster_url));
+    preflight::check_target(&provider, target_addr, &call_data_bytes).await?;
+    validation::check_value(target_addr, value_amount)?;
     
-    // ✅ 3. CREATE USEROPERATION DIRECTLY (NO DOUBLE-ENCODING!)
-    let max_fee = U256::from_str_radix(max_fee_per_gas, 10)?;
-    let priority_fee = U256::from_str_radix(max_priority_fee_per_gas, 10)?;
+    // Create UserOperation with explicit gas fees to meet bundler requirements
+    let (max_fee, priority_fee) = fees?;
     
-    // Fix: Pass target parameters directly to UserOperationBuilder
-    // This will create ExecuteCall internally - no manual encoding needed!
     let mut user_op_request = UserOperationBuilder::new(
-        target_addr,        // ✅ External target (not smart account!)
-        value_amount,       // ✅ Value to send to target
-        call_data_bytes     // ✅ Call data to send to target
+        target_addr,
+        value_amount,
+        call_data_bytes
     )
     .with_gas_fees(max_fee, priority_fee)
     .build();
     
-    println!("✅ UserOperation created correctly (no double-encoding)");
+    let smart_provider = SmartAccountProvider::new(provider.clone(), simple_account);
+    let paymaster_service = paymaster::PaymasterService::new(
+        paymaster_url.to_string(),
+        paymaster_api_key.to_string(),
+        paymaster_addr,
+    ).with_wire_format(paymaster_encoding);
+    let paymaster_service = match preauth_token {
+        Some(token) => paymaster_service.with_preauth_token(paymaster::parse_preauth_token(token)),
+        None => paymaster_service,
+    };
     
-    // ✅ 4. USE AA-SDK-RS CAPABILITIES
-    let smart_provider = SmartAccountProvider::new(provider, simple_account);
+    // Gas is estimated with stub paymaster data, or the paymaster's validation and postOp go unpaid (AA33)
+    let (nonce, stub) = tokio::join!(
+        allocate_nonce(&provider, entry_point_addr, chain_id, account_addr, nonce_key),
+        paymaster_service.stub(account_addr),
+    );
+    if let Some(nonce) = nonce? {
+        user_op_request.nonce = Some(nonce);
+    }
+    let stub = stub?;
+    stub.attach(&mut user_op_request);
     
-    // Optional: Get gas estimates
-    println!("📊 Estimating gas parameters...");
-    match smart_provider.estimate_user_operation_gas(&user_op_request).await {
-        Ok(estimates) => {
-            println!("✅ Gas estimates: {:?}", estimates);
-        }
-        Err(e) => {
-            println!("⚠️  Gas estimation failed (proceeding anyway): {}", e);
+    // Fill UserOperation fields first to get gas estimates
+    out!("🔧 {}", t!("filling-userop"));
+    events::emit(ClientEvent::Filling { sender: account_addr, chain_id });
+    out!("💰 {}", t!("gas-fees-gwei",
+             max = max_fee / U256::from(1_000_000_000u64),
+             priority = priority_fee / U256::from(1_000_000_000u64)));
+    // The verifier is only needed once sponsorship is signed, so it is read while the bundler estimates
+    let filler = &smart_provider;
+    let (filled, verifier) = tokio::join!(
+        retry::retry("fill UserOperation", || {
+            let mut request = user_op_request.clone();
+            async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+        }),
+        paymaster_verifier(&provider, paymaster_addr),
+    );
+    user_op_request = filled?;
+    stub.attach(&mut user_op_request);
+    let paymaster_service = match verifier {
+        Some(verifier) => paymaster_service.with_expected_verifier(chain_id, verifier),
+        None => paymaster_service,
+    };
+
+    // Ensure pre_verification_gas meets bundler minimum requirements
+    if let Some(pre_verification_gas) = user_op_request.pre_verification_gas {
+        if pre_verification_gas < U256::from(46_000) {
+            user_op_request.pre_verification_gas = Some(U256::from(46_000));
+            out!("🔧 {}", t!("pre-verification-gas-raised"));
         }
     }
     
-    // Fill missing fields automatically
-    println!("🔧 Filling UserOperation fields automatically...");
-    smart_provider.fill_user_operation(&mut user_op_request).await?;
-    
-    // ✅ 5. SUBMIT WITH TRACKING
-    println!("🚀 Submitting transaction via smart account...");
-    match smart_provider.send_user_operation(user_op_request, wallet.signer()).await {
-        Ok(user_op_hash) => {
-            println!("✅ UserOperation submitted successfully!");
-            println!("UserOperation Hash: {:?}", user_op_hash);
+    // Let bundler estimate gas automatically for better efficiency
+    // Removed manual gas limit override to improve gas efficiency ratio
+
+    // Request paymaster sponsorship AFTER all gas adjustments are finalized
+    out!("💰 {}", t!("sponsorship-requesting"));
+    
+    // The signed paymaster data replaces the stub AFTER fill_user_operation so filling cannot overwrite it;
+    // an expired sponsorship is replaced and the operation simulated and sent again
+    // The paymaster signature covers the nonce, so a retry after AA25 is sponsored again
+    let (wallet, smart_provider, paymaster_service) = (&wallet, &smart_provider, &paymaster_service);
+    let sent = nonce::with_recovery(
+        |nonce| {
+            let mut request = user_op_request.clone();
+            async move {
+                if nonce.is_some() {
+                    request.nonce = nonce;
+                }
+                paymaster_service.submit_sponsored(&request, valid_for, |sponsored| async move {
+                    let paymaster_and_data = sponsored.paymaster_data.clone().unwrap_or_default();
+                    out!("💡 {}", t!("paymaster-data-generated", data = format!("0x{}", hex::encode(&paymaster_and_data))));
+                    out!("🔧 {}", t!("paymaster-address-set", address = paymaster_addr));
+                    out!("✅ {}", t!("sponsorship-obtained"));
+                    out!("📋 {}", t!("sponsorship-configured"));
+                    
+                    if preflight::dry_run() {
+                        dry_run(rpc_url, entry_point_addr, chain_id, &sponsored, wallet).await?;
+                        return Ok(None);
+                    }
+                    run_preflight(rpc_url, entry_point_addr, chain_id, &sponsored, wallet).await?;
+                    
+                    // Submit the sponsored UserOperation
+                    out!("🚀 {}", t!("sponsored-submitting"));
+                    retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(sponsored.clone(), wallet.signer()))
+                        .await
+                        .map(Some)
+                        .map_err(|e| anyhow::anyhow!("{}", e))
+                }).await
+            }
+        },
+        || refetch_nonce(rpc_url, entry_point_addr, chain_id, account_addr, nonce_key),
+    ).await;
+    let pending = match sent {
+        Ok(None) => {
+            resync_nonce(chain_id, account_addr, nonce_key);
+            None
+        }
+        Ok(Some(user_op_hash)) => {
+            out!("✅ {}", t!("sponsored-submitted"));
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            out!("💰 {}", t!("sponsored-gas-covered"));
+            let hash_str = format!("{:?}", user_op_hash);
+            journal_operation(&hash_str, chain_id, account_addr, "submit-sponsored", tag, true);
             
-            // ✅ TRACK EXECUTION STATUS
-            println!("📋 Checking UserOperation execution status...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await; // Wait for execution
+            // Track execution status
+            out!("📋 {}", t!("checking-status"));
+            //tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
             
-            match smart_provider.get_user_operation_receipt(user_op_hash).await {
+            match retry::retry("eth_getUserOperationReceipt", || smart_provider.get_user_operation_receipt(user_op_hash)).await {
                 Ok(Some(receipt)) => {
-                    println!("✅ Transaction executed successfully!");
-                    println!("📋 Receipt details: {:?}", receipt);
-                    println!("🎉 Smart account transaction completed!");
+                    journal_receipt(&hash_str, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost);
+                    out!("✅ {}", t!("sponsored-executed"));
+                    out!("📋 {}", t!("receipt-details", receipt = format!("{:?}", receipt)));
+                    out!("🎉 {}", t!("sponsored-completed"));
+                    None
                 }
                 Ok(None) => {
-                    println!("⏳ Transaction still pending...");
-                    println!("💡 Check status later with hash: {:?}", user_op_hash);
+                    events::emit(ClientEvent::Pending { user_op_hash: hash_str.clone() });
+                    out!("⏳ {}", t!("tx-pending"));
+                    out!("💡 {}", t!("check-later", hash = format!("{:?}", user_op_hash)));
+                    Some(hash_str.clone())
+                }
+                Err(e) => {
+                    out!("⚠️  {}", t!("status-unverified", error = e));
+                    out!("💡 {}", t!("may-have-succeeded"));
+                    Some(hash_str)
+                }
+            }
+        }
+        Err(e) if e.is::<preflight::Rejected>() => {
+            resync_nonce(chain_id, account_addr, nonce_key);
+            return Err(e);
+        }
+        Err(e) => {
+            resync_nonce(chain_id, account_addr, nonce_key);
+            events::emit(ClientEvent::Failed { user_op_hash: None, reason: e.to_string() });
+            out!("❌ {}", t!("sponsored-failed", error = e));
+            out!("🔍 {}", t!("possible-causes"));
+            out!("  1. {}", t!("cause-sponsorship-rejected"));
+            out!("  2. {}", t!("cause-not-deployed"));
+            out!("  3. {}", t!("cause-paymaster-config"));
+            out!("  4. {}", t!("cause-bundler-connectivity"));
+            return Err(exit::Reported(e).into());
+        }
+    };
+    
+    Ok(pending)
+}
+
+/// Submit a sponsored UserOperation from an EOA delegated to `delegate` with EIP-7702
+/// The EOA is the sender, so there is no factory or deployment; the authorization rides along until the delegation is on chain
+async fn submit_delegated_sponsored(
+    private_key: &str,
+    delegate: &str,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    nonce_key: Option<U192>,
+    paymaster_url: &str,
+    paymaster_api_key: &str,
+    preauth_token: Option<&str>,
+    paymaster_address: &str,
+    paymaster_encoding: paymaster::WireFormat,
+    valid_for: u64,
+    tag: Option<&str>,
+) -> Result<Option<String>> {
+    out!("🎉 {}", t!("sponsored-start"));
+    
+    let wallet = Wallet::from_hex(private_key)?;
+    let eoa = wallet.address();
+    let delegate_addr = Address::from_str(delegate)?;
+    let target_addr = Address::from_str(target)?;
+    let paymaster_addr = Address::from_str(paymaster_address)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    
+    out!("🔧 {}", t!("label-configuration"));
+    out!("  {}", t!("label-owner-eoa", address = eoa));
+    out!("  EIP-7702 delegate: {}", delegate_addr);
+    out!("  {}", t!("label-target", address = target_addr));
+    out!("  {}", t!("label-paymaster", address = paymaster_addr));
+    
+    let provider = providers::http(rpc_url)?;
+    check_token_payment(&provider, paymaster_addr, eoa).await?;
+    let authorization = eip7702::authorization_for(&provider, &wallet, chain_id, delegate_addr).await?;
+    match &authorization {
+        Some(authorization) => out!("✍️  Signed an authorization delegating {} to {} (EOA nonce {})", eoa, delegate_addr, authorization.nonce),
+        None => out!("✅ {} already delegates to {}", eoa, delegate_addr),
+    }
+    
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    let value_amount = U256::from_str_radix(value, 10)?;
+    preflight::check_target(&provider, target_addr, &call_data_bytes).await?;
+    validation::check_value(target_addr, value_amount)?;
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    let nonce = match allocate_nonce(&provider, entry_point_addr, chain_id, eoa, nonce_key).await? {
+        Some(nonce) => nonce,
+        None => account::get_nonce(&provider, entry_point_addr, eoa).await?,
+    };
+    let mut user_op = multisig::UnsignedUserOperation::execute(eoa, nonce, target_addr, value_amount, call_data_bytes);
+    user_op.max_fee_per_gas = max_fee;
+    user_op.max_priority_fee_per_gas = priority_fee;
+    
+    // The SDK filler knows nothing of the authorization, so the bundler estimates the raw operation
+    out!("🔧 {}", t!("filling-userop"));
+    events::emit(ClientEvent::Filling { sender: eoa, chain_id });
+    let paymaster_service = paymaster::PaymasterService::new(
+        paymaster_url.to_string(),
+        paymaster_api_key.to_string(),
+        paymaster_addr,
+    ).with_wire_format(paymaster_encoding);
+    let paymaster_service = match preauth_token {
+        Some(token) => paymaster_service.with_preauth_token(paymaster::parse_preauth_token(token)),
+        None => paymaster_service,
+    };
+    let paymaster_service = expect_paymaster_verifier(paymaster_service, rpc_url, chain_id).await;
+    // Estimated with stub paymaster data so the paymaster's validation and postOp are paid for
+    paymaster_service.stub(eoa).await?.attach_unsigned(&mut user_op);
+    let bundler_client = bundler_at(rpc_url.to_string(), entry_point_addr, U256::from(chain_id));
+    let estimate_op = userop::RpcUserOperation {
+        signature: Some(dummy_signature::resolve(&dummy_signature::DummySignature::Ecdsa)),
+        eip7702_auth: authorization.clone(),
+        ..userop::RpcUserOperation::from(&user_op)
+    };
+    let estimate = bundler_client.estimate_user_operation_gas(serde_json::to_value(&estimate_op)?, entry_point_addr).await?;
+    user_op.call_gas_limit = estimate.call_gas_limit;
+    user_op.verification_gas_limit = estimate.verification_gas_limit;
+    user_op.pre_verification_gas = estimate.pre_verification_gas.max(U256::from(46_000));
+    
+    out!("💰 {}", t!("sponsorship-requesting"));
+    let request = userop::UserOperationRequest::from(userop::RpcUserOperation::from(&user_op));
+    let (wallet, bundler_client, authorization, paymaster_service) = (&wallet, &bundler_client, &authorization, &paymaster_service);
+    let sent = nonce::with_recovery(
+        |nonce| {
+            let mut request = request.clone();
+            async move {
+                if nonce.is_some() {
+                    request.nonce = nonce;
+                }
+                paymaster_service.submit_sponsored(&request, valid_for, |sponsored| async move {
+                    out!("✅ {}", t!("sponsorship-obtained"));
+                    let user_op = multisig::UnsignedUserOperation::try_from(&sponsored)?;
+                    let signed = userop::SignedUserOperation::sign(user_op, entry_point_addr, chain_id, wallet)?;
+                    let rpc = userop::RpcUserOperation { eip7702_auth: authorization.clone(), ..signed.to_rpc() };
                     
-                    // Get more operation details
-                    if let Ok(Some(op)) = smart_provider.get_user_operation(user_op_hash).await {
-                        println!("📊 UserOperation details: {:?}", op);
+                    if preflight::dry_run() {
+                        out!("🧪 Dry run, not sent. Signed UserOperation:");
+                        out!("{}", rpc.to_json()?);
+                        out!("UserOperation Hash: {}", signed.user_op_hash);
+                        return Ok(None);
                     }
+                    // Before the delegation lands the EOA has no code, so a local simulation would always fail
+                    if authorization.is_none() {
+                        run_preflight(rpc_url, entry_point_addr, chain_id, &sponsored, wallet).await?;
+                    }
+                    
+                    out!("🚀 {}", t!("sponsored-submitting"));
+                    bundler_client.send_user_operation(serde_json::to_value(&rpc)?, entry_point_addr).await.map(Some)
+                }).await
+            }
+        },
+        || refetch_nonce(rpc_url, entry_point_addr, chain_id, eoa, nonce_key),
+    ).await;
+    match sent {
+        Ok(None) => {
+            resync_nonce(chain_id, eoa, nonce_key);
+            Ok(None)
+        }
+        Ok(Some(user_op_hash)) => {
+            out!("✅ {}", t!("sponsored-submitted"));
+            out!("{}", t!("label-userop-hash", hash = &user_op_hash));
+            out!("💰 {}", t!("sponsored-gas-covered"));
+            journal_operation(&user_op_hash, chain_id, eoa, "submit-sponsored", tag, true);
+            Ok(Some(user_op_hash))
+        }
+        Err(e) => {
+            resync_nonce(chain_id, eoa, nonce_key);
+            Err(e)
+        }
+    }
+}
+
+/// Check sponsorship signatures against the verifier the paymaster contract trusts
+/// A paymaster without `verifier()` or an unreachable node only skips the check, and `--no-preflight` turns it off
+async fn expect_paymaster_verifier(
+    paymaster_service: paymaster::PaymasterService,
+    rpc_url: &str,
+    chain_id: u64,
+) -> paymaster::PaymasterService {
+    let provider = match providers::http(rpc_url) {
+        Ok(provider) => provider,
+        Err(_) => return paymaster_service,
+    };
+    match paymaster_verifier(&provider, paymaster_service.paymaster_address).await {
+        Some(verifier) => paymaster_service.with_expected_verifier(chain_id, verifier),
+        None => paymaster_service,
+    }
+}
+
+/// Verifier `paymaster` trusts, None when the check is skipped
+async fn paymaster_verifier<P: alloy::providers::Provider>(provider: &P, paymaster: Address) -> Option<Address> {
+    if !preflight::enabled() {
+        return None;
+    }
+    match paymaster::read_verifier(provider, paymaster).await {
+        Ok(verifier) => Some(verifier),
+        Err(e) => {
+            out!("⚠️  Could not read the paymaster's verifier, its signatures are not checked locally: {}", e);
+            None
+        }
+    }
+}
+
+/// Ask the paymaster service for a single-use sponsorship token and print it as a shareable link
+async fn issue_preauth_token(
+    sender: &str,
+    ttl: u64,
+    paymaster_url: &str,
+    paymaster_api_key: &str,
+    paymaster_encoding: paymaster::WireFormat,
+    qr: bool,
+) -> Result<()> {
+    let sender_addr = Address::from_str(sender)?;
+    let paymaster_service = paymaster::PaymasterService::new(
+        paymaster_url.to_string(),
+        paymaster_api_key.to_string(),
+        Address::ZERO,
+    ).with_wire_format(paymaster_encoding);
+    
+    let grant = paymaster_service.issue_preauth(sender_addr, Some(ttl)).await?;
+    
+    out!("🎟️  Pre-auth token issued for {}", grant.sender);
+    out!("Token: {}", grant.token);
+    out!("Expires at: {}", grant.expires_at);
+    out!("Link: {}", grant.link(paymaster_url));
+    out!("💡 Redeem once with: submit-sponsored --preauth-token {} ...", grant.token);
+    if qr {
+        qr::print_qr(qr::PayloadKind::PreAuth, grant.link(paymaster_url).as_bytes())?;
+    }
+    
+    Ok(())
+}
+
+/// Deploy a sponsored smart account where deployment gas is paid by paymaster
+async fn deploy_sponsored_smart_account(
+    private_key: &str,
+    factory: &str,
+    salt: &str,
+    rpc_url: &str,
+    chain_id: u64,
+    paymaster_url: &str,
+    paymaster_api_key: &str,
+    preauth_token: Option<&str>,
+    paymaster_address: &str,
+    paymaster_encoding: paymaster::WireFormat,
+    valid_for: u64,
+    tag: Option<&str>,
+) -> Result<Option<String>> {
+    out!("🎉 {}", t!("sponsored-deploy-start"));
+    
+    // Setup
+    let wallet = Wallet::from_hex(private_key)?;
+    let factory_addr = Address::from_str(factory)?;
+    let paymaster_addr = Address::from_str(paymaster_address)?;
+    
+    out!("🔧 {}", t!("label-configuration"));
+    out!("  {}", t!("label-factory", address = factory_addr));
+    out!("  {}", t!("label-paymaster", address = paymaster_addr));
+    out!("  {}", t!("label-owner", address = wallet.address()));
+    out!("  {}", t!("label-paymaster-service", url = paymaster_url));
+    
+    // Parse salt
+    let salt_u256 = hexutil::parse_salt(salt)?;
+    
+    
+    // Get predicted address
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let bundler_client = bundler_at(rpc_url.to_string(), entry_point_addr, U256::from(chain_id));
+    
+    let predicted_address = bundler_client.get_predicted_address(factory_addr, wallet.address(), salt_u256).await?;
+    out!("📍 {}", t!("predicted-address", address = predicted_address));
+    out!("💰 {}", t!("sponsored-deploy-no-eth"));
+    
+    // Setup aa-sdk-rs
+    let rpc_url: &str = &preflight_bundler(rpc_url, entry_point_addr, chain_id).await?;
+    let provider = providers::http(rpc_url)?;
+    check_token_payment(&provider, paymaster_addr, predicted_address).await?;
+    
+    // Its initCode carries the salt, so it deploys at the predicted address
+    let simple_account = simple_account(&provider, wallet.address(), salt_u256, factory_addr, entry_point_addr, chain_id);
+    
+    let smart_provider = SmartAccountProvider::new(provider, simple_account);
+    
+    // Create deployment UserOperation
+    out!("🔧 {}", t!("sponsored-deploy-creating"));
+    let mut user_op_request = UserOperationBuilder::new(
+        predicted_address,
+        U256::ZERO,
+        Bytes::new()
+    ).build();
+    
+    // Set explicit gas fees to meet bundler requirements
+    let (max_fee, priority_fee) = resolve_fees(rpc_url, None, None).await?;
+    
+    user_op_request.max_fee_per_gas = Some(max_fee);
+    user_op_request.max_priority_fee_per_gas = Some(priority_fee);
+    
+    out!("💰 {}", t!("gas-fees-gwei",
+             max = max_fee / U256::from(1_000_000_000u64),
+             priority = priority_fee / U256::from(1_000_000_000u64)));
+    
+    let paymaster_service = paymaster::PaymasterService::new(
+        paymaster_url.to_string(),
+        paymaster_api_key.to_string(),
+        paymaster_addr,
+    ).with_wire_format(paymaster_encoding);
+    let paymaster_service = match preauth_token {
+        Some(token) => paymaster_service.with_preauth_token(paymaster::parse_preauth_token(token)),
+        None => paymaster_service,
+    };
+    let paymaster_service = expect_paymaster_verifier(paymaster_service, rpc_url, chain_id).await;
+    // Estimated with stub paymaster data so the paymaster's validation and postOp are paid for
+    let stub = paymaster_service.stub(predicted_address).await?;
+    stub.attach(&mut user_op_request);
+    
+    // Fill UserOperation fields
+    let filler = &smart_provider;
+    user_op_request = retry::retry("fill UserOperation", || {
+        let mut request = user_op_request.clone();
+        async move { filler.fill_user_operation(&mut request).await.map(|_| request) }
+    }).await?;
+    stub.attach(&mut user_op_request);
+    
+    // Ensure pre_verification_gas meets bundler minimum requirements (DEPLOYMENT FUNCTION)
+    if let Some(pre_verification_gas) = user_op_request.pre_verification_gas {
+        if pre_verification_gas < U256::from(46_000) {
+            user_op_request.pre_verification_gas = Some(U256::from(46_000));
+            out!("🔧 {}", t!("pre-verification-gas-raised"));
+        }
+    }
+    
+    // Let bundler estimate gas automatically for better efficiency
+    // Removed manual gas limit override to improve gas efficiency ratio
+    
+    // Request paymaster sponsorship for deployment AFTER all gas adjustments are finalized
+    out!("💰 {}", t!("sponsorship-requesting-deploy"));
+    
+    // The signed paymaster data replaces the stub AFTER fill_user_operation so filling cannot overwrite it;
+    // an expired sponsorship is replaced and the deployment sent again
+    let (wallet, smart_provider) = (&wallet, &smart_provider);
+    let sent = paymaster_service.submit_sponsored(&user_op_request, valid_for, |sponsored| async move {
+        let paymaster_and_data = sponsored.paymaster_data.clone().unwrap_or_default();
+        out!("💡 {}", t!("paymaster-data-generated", data = format!("0x{}", hex::encode(&paymaster_and_data))));
+        out!("🔧 {}", t!("paymaster-address-set", address = paymaster_addr));
+        out!("✅ {}", t!("sponsorship-deploy-approved"));
+        out!("📋 {}", t!("sponsorship-deploy-covered"));
+        
+        if preflight::dry_run() {
+            dry_run(rpc_url, entry_point_addr, chain_id, &sponsored, wallet).await?;
+            return Ok(None);
+        }
+        
+        // Submit sponsored deployment
+        out!("🚀 {}", t!("sponsored-deploy-submitting"));
+        retry::retry("eth_sendUserOperation", || smart_provider.send_user_operation(sponsored.clone(), wallet.signer()))
+            .await
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }).await;
+    let user_op_hash = match sent {
+        Ok(None) => None,
+        Ok(Some(user_op_hash)) => {
+            out!("✅ {}", t!("sponsored-deploy-initiated"));
+            out!("{}", t!("label-userop-hash", hash = format!("{:?}", user_op_hash)));
+            journal_operation(&format!("{:?}", user_op_hash), chain_id, predicted_address, "deploy-sponsored", tag, true);
+            record_deployment(chain_id, factory_addr, predicted_address, vec![wallet.address()], salt_u256);
+            out!("💰 {}", t!("sponsored-deploy-covered"));
+            out!("📍 {}", t!("deploy-will-be-at", address = predicted_address));
+            out!();
+            out!("🎉 {}", t!("sponsored-deploy-zero-gas"));
+            out!("💡 {}", t!("sponsored-deploy-next"));
+            Some(format!("{:?}", user_op_hash))
+        }
+        Err(e) => {
+            out!("❌ {}", t!("sponsored-deploy-failed", error = e));
+            out!("🔍 {}", t!("possible-causes"));
+            out!("  1. {}", t!("cause-sponsorship-rejected"));
+            out!("  2. {}", t!("cause-factory-inaccessible"));
+            out!("  3. {}", t!("cause-paymaster-config"));
+            out!("  4. {}", t!("cause-bundler-connectivity"));
+            return Err(exit::Reported(e).into());
+        }
+    };
+    
+    Ok(user_op_hash)
+}
+
+/// Network and paymaster settings of an onboarding run
+struct Onboarding<'a> {
+    factory: &'a str,
+    salt: &'a str,
+    rpc_url: &'a str,
+    chain_id: u64,
+    paymaster_url: &'a str,
+    paymaster_api_key: &'a str,
+    paymaster_address: &'a str,
+    paymaster_encoding: paymaster::WireFormat,
+    valid_for: u64,
+    wait: u64,
+}
+
+/// The owner key stored as wallet `name`, generating and storing a new one on the first run
+fn onboarding_owner(name: &str) -> Result<String> {
+    let store = wallet_store::WalletStore::open_default()?;
+    if let Some(address) = store.list()?.get(name) {
+        out!("🔑 Resuming onboarding of owner {} (wallet '{}')", address, name);
+        return unlock_wallet(name);
+    }
+    let wallet = WalletFactory::random()?;
+    let password = wallet_store::password(&format!("New password for wallet '{}': ", name), true)?;
+    let address = store.import(name, &wallet.export_private_key(), &password)?;
+    out!("🔐 Generated owner {} and stored it as wallet '{}' in {}", address, name, store.dir().display());
+    Ok(wallet.export_private_key())
+}
+
+/// Deploy the owner's account through the paymaster and send the first call, skipping the steps the chain
+/// and the journal show as done and waiting on the ones still pending
+async fn onboard_account(private_key: &str, settings: &Onboarding<'_>, first_call: Option<(&str, &str, &str)>) -> Result<()> {
+    let owner = Wallet::from_hex(private_key)?.address();
+    let factory_addr = Address::from_str(settings.factory)?;
+    let entry_point_addr = entrypoint::resolve(settings.chain_id)?;
+    let bundler_client = bundler_at(settings.rpc_url.to_string(), entry_point_addr, U256::from(settings.chain_id));
+    let account = bundler_client.get_predicted_address(factory_addr, owner, hexutil::parse_salt(settings.salt)?).await?;
+    out!("🚀 Onboarding owner {} with account {}", owner, account);
+    
+    // Set once a deployment receipt is in, in case the node serving code lags behind the bundler
+    let mut deployed = false;
+    // A call whose receipt came back reverted is journaled as failed, and must not be sent again in this run
+    let mut call_sent = false;
+    loop {
+        deployed = deployed || providers::registry().has_code(settings.rpc_url, account).await?;
+        let records = Journal::open_default()?.load()?;
+        match onboard::next_step(&records, settings.chain_id, account, deployed, first_call.is_some()) {
+            onboard::Step::Deploy => {
+                out!("📦 Step 1/2: sponsored deployment");
+                let pending = deploy_sponsored_smart_account(
+                    private_key, settings.factory, settings.salt, settings.rpc_url, settings.chain_id,
+                    settings.paymaster_url, settings.paymaster_api_key, None, settings.paymaster_address,
+                    settings.paymaster_encoding, settings.valid_for, None
+                ).await?;
+                let Some(user_op_hash) = pending else { return Ok(()) };
+                wait_for_receipt(settings.rpc_url, &user_op_hash, settings.wait).await?;
+                deployed = true;
+            }
+            onboard::Step::AwaitDeployment(user_op_hash) => {
+                out!("⏳ Resuming: deployment {} was submitted earlier", user_op_hash);
+                wait_for_receipt(settings.rpc_url, &user_op_hash, settings.wait).await?;
+                deployed = true;
+            }
+            onboard::Step::FirstCall => {
+                let Some((target, call_data, value)) = first_call.filter(|_| !call_sent) else {
+                    return Err(anyhow::anyhow!("The first call from {} was not included, see `ops list --tag {}`", account, onboard::FIRST_CALL_TAG));
+                };
+                call_sent = true;
+                out!("📨 Step 2/2: first sponsored call to {}", target);
+                let pending = submit_sponsored_user_operation(
+                    private_key, target, call_data, value, settings.factory, settings.salt, settings.rpc_url, settings.chain_id, None,
+                    settings.paymaster_url, settings.paymaster_api_key, None, settings.paymaster_address,
+                    settings.paymaster_encoding, settings.valid_for, Some(onboard::FIRST_CALL_TAG)
+                ).await?;
+                if preflight::dry_run() {
+                    return Ok(());
                 }
-                Err(e) => {
-                    println!("⚠️  Could not verify execution status: {}", e);
-                    println!("💡 Operation may still have succeeded - check blockchain directly");
+                if let Some(user_op_hash) = pending {
+                    wait_for_receipt(settings.rpc_url, &user_op_hash, settings.wait).await?;
                 }
             }
-        }
-        Err(e) => {
-            println!("❌ Transaction submission failed: {}", e);
-            println!("🔍 Possible causes:");
-            println!("  1. Smart account not properly deployed");
-            println!("  2. Insufficient gas fees");
-            println!("  3. Invalid target contract or call data");
-            println!("  4. Bundler connectivity issues");
+            onboard::Step::AwaitFirstCall(user_op_hash) => {
+                out!("⏳ Resuming: first call {} was submitted earlier", user_op_hash);
+                wait_for_receipt(settings.rpc_url, &user_op_hash, settings.wait).await?;
+            }
+            onboard::Step::Done => break,
         }
     }
     
+    out!("🎉 Account {} is onboarded, gas paid by the paymaster", account);
     Ok(())
 }
 
-async fn generate_wallet() -> Result<()> {
-    println!("Generating new random wallet...");
-    
-    // Use the existing WalletFactory::random() for real random generation
-    let wallet = WalletFactory::random()?;
+/// Show network presets and configuration
+fn show_network_presets(config: &CliConfig) -> Result<()> {
+    let title = t!("networks-title");
+    out!("🌐 {}", title);
+    out!("{}", "=".repeat(title.chars().count() + 3));
+    out!();
+    
+    for network in config.networks() {
+        out!("📍 {}", t!("network-heading", name = network.name, chain_id = network.chain_id));
+        if network.native_aa {
+            out!("  {}", t!("label-native-aa"));
+        } else {
+            out!("  {}", t!("label-entry-point", address = network.entry_point));
+        }
+        match network.factory {
+            Some(factory) => out!("  {}", t!("label-factory", address = factory)),
+            None => out!("  {}", t!("label-factory-none")),
+        }
+        out!("  {}", t!("label-rpc-template", url = network.rpc_url_template));
+        // Without a bundler template the RPC URL serves the bundler methods too
+        if let Some(bundler) = &network.bundler_url_template {
+            out!("  {}", t!("label-bundler-template", url = bundler));
+        }
+        out!();
+    }
     
-    println!("New wallet created:");
-    println!("Address: {}", wallet.address());
-    println!("Private Key: {}", wallet.export_private_key());
+    out!("📋 {}", t!("usage-examples"));
+    out!("  # {}", t!("example-anvil"));
+    out!("  aa-client demo --yes");
+    out!();
+    out!("  # {}", t!("example-sepolia"));
+    out!("  aa-client create --chain-id 11155111 --private-key YOUR_KEY --target 0x... --call-data 0x... --nonce 0");
+    out!();
+    out!("  # {}", t!("example-custom-rpc"));
+    out!("  aa-client create --rpc-url https://eth-sepolia.g.alchemy.com/v2/YOUR_API_KEY --chain-id 11155111 ...");
     
     Ok(())
 }
 
-fn show_wallet_info(private_key: &str) -> Result<()> {
-    println!("Wallet information:");
-    
-    let wallet = Wallet::from_hex(private_key)?;
-    println!("Address: {}", wallet.address());
-    println!("Private Key: {}", wallet.export_private_key());
-    
-    // Note: Public key derivation now handled internally by aa-sdk-rs LocalSigner
-    println!("Note: Public key is managed internally by aa-sdk-rs LocalSigner");
-    
-    Ok(())
+/// Gas settings written into a proposal; they are fixed once owners start signing
+struct ProposalGas<'a> {
+    call_gas_limit: u64,
+    verification_gas_limit: u64,
+    pre_verification_gas: u64,
+    max_fee_per_gas: Option<&'a str>,
+    max_priority_fee_per_gas: Option<&'a str>,
 }
 
-/// Deploy a new smart account using the factory
-async fn deploy_smart_account(
-    private_key: &str,
-    factory: &str,
-    salt: &str,
+/// Export an unsigned UserOperation for a multi-owner account
+async fn propose_operation(
+    account: &str,
+    target: &str,
+    call_data: &str,
+    value: &str,
+    file: &Path,
     rpc_url: &str,
     chain_id: u64,
-    max_fee_per_gas: &str,
-    max_priority_fee_per_gas: &str,
+    gas: ProposalGas<'_>,
 ) -> Result<()> {
-    println!("🚀 Deploying new smart account via bundler...");
+    out!("📝 Proposing UserOperation for multi-owner signing...");
     
-    // Create wallet from private key
-    let wallet = Wallet::from_hex(private_key)?;
-    println!("Deployer wallet: {}", wallet.address());
+    let account_addr = Address::from_str(account)?;
+    let target_addr = Address::from_str(target)?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let call_data_bytes = hexutil::decode_bytes("call data", call_data)?;
+    let value_amount = U256::from_str_radix(value, 10)?;
+    verify_chain_id(rpc_url, chain_id).await?;
     
-    // Parse factory address
-    let factory_addr = Address::from_str(factory)?;
-    println!("Factory contract: {}", factory_addr);
+    let provider = providers::http(rpc_url)?;
+    let owners = account::get_owners(&provider, account_addr).await?;
+    let threshold = account::get_threshold(&provider, account_addr).await;
+    let nonce = account::get_nonce(&provider, entry_point_addr, account_addr).await?;
+    let (max_fee_per_gas, max_priority_fee_per_gas) =
+        resolve_fees(rpc_url, gas.max_fee_per_gas, gas.max_priority_fee_per_gas).await?;
     
-    // Parse salt
-    let salt_bytes = if salt.starts_with("0x") {
-        hex::decode(&salt[2..])?
-    } else {
-        hex::decode(salt)?
+    // Everything fetched above is captured so the build can be reproduced offline
+    let context = userop::BuildContext {
+        chain_id,
+        entry_point: entry_point_addr,
+        sender: account_addr,
+        nonce,
+        factory: None,
+        factory_data: None,
+        target: target_addr,
+        value: value_amount,
+        data: call_data_bytes,
+        call_gas_limit: U256::from(gas.call_gas_limit),
+        verification_gas_limit: U256::from(gas.verification_gas_limit),
+        pre_verification_gas: U256::from(gas.pre_verification_gas),
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
     };
     
-    // Create bundler client for RPC calls
-    let _bundler = BundlerClient::new(
-        rpc_url.to_string(),
-        Address::ZERO, // Not needed for deployment
-        U256::from(chain_id),
-    );
-    
-    println!("Deploying smart account using real SimpleAccountFactory contract...");
-    println!("Factory: {}", factory_addr);
-    println!("Owner: {}", wallet.address());
-    println!("Salt: 0x{}", hex::encode(&salt_bytes));
-    
-    // Convert salt bytes to U256
-    let mut salt_array = [0u8; 32];
-    let start_idx = 32usize.saturating_sub(salt_bytes.len());
-    salt_array[start_idx..].copy_from_slice(&salt_bytes[..32.min(salt_bytes.len())]);
-    let salt_u256 = U256::from_be_bytes(salt_array);
+    let mut proposal = multisig::Proposal::from_context(context);
+    proposal.threshold = threshold;
+    proposal.save(file)?;
     
-    // Create bundler client
-    let bundler_client = BundlerClient::new(
-        rpc_url.to_string(),
-        Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?, // Default entry point
-        U256::from(chain_id),
-    );
+    out!("✅ Proposal written to {}", file.display());
+    out!("Account: {} | Nonce: {}", account_addr, nonce);
+    out!("UserOperation Hash: {}", proposal.user_op_hash);
+    match threshold {
+        Some(threshold) => out!("👥 Any {} of these owners need to sign ({}):", threshold, owners.len()),
+        None => out!("👥 Owners who need to sign ({}):", owners.len()),
+    }
+    for owner in &owners {
+        out!("  {}", owner);
+    }
+    out!("💡 Each owner runs: op sign --file {} --private-key-stdin", file.display());
     
-    // First, get the predicted address
-    match bundler_client.get_predicted_address(factory_addr, wallet.address(), salt_u256).await {
-        Ok(predicted_address) => {
-            println!("📍 Predicted smart account address: {}", predicted_address);
-            println!("💡 Make sure this address is funded with ETH for gas fees");
-            
-            println!("🔧 Creating deployment UserOperation...");
-            
-            // Create concrete provider type for aa-sdk-rs
-            let url = url::Url::parse(rpc_url)?;
-            let provider = ProviderBuilder::new().on_http(url);
-            
-            // Create SimpleAccount with proper factory address
-            let entry_point_addr = Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?;
-            // TODO: Migrate to SimpleAccount::new_with_salt for better salt support
-            let simple_account = SimpleAccount::new(
-                Arc::new(provider.clone()),
-                wallet.address(),      // Owner (EOA)
-                factory_addr,          // Factory address
-                entry_point_addr,      // EntryPoint address  
-                chain_id,
-            );
-            
-            // Create SmartAccountProvider
-            let smart_provider = SmartAccountProvider::new(provider, simple_account);
-            
-            // Let aa-sdk-rs automatically handle deployment - this is the key fix from the documentation!
-            println!("🔧 Letting aa-sdk-rs automatically handle deployment...");
-            println!("📊 aa-sdk-rs will automatically:");
-            println!("  - Detect that the account doesn't exist");
-            println!("  - Generate proper initCode for factory deployment");
-            println!("  - Set the predicted address as sender");
-            println!("  - Handle nonce management");
-            
-            // Parse gas fees
-            let max_fee = U256::from_str_radix(max_fee_per_gas, 10)?;
-            let priority_fee = U256::from_str_radix(max_priority_fee_per_gas, 10)?;
-            
-            println!("Gas fees - Max fee: {} wei, Priority fee: {} wei", max_fee, priority_fee);
-            
-            // Create a simple UserOperation and let aa-sdk-rs handle everything
-            let user_op_request = UserOperationBuilder::new(
-                predicted_address,  // Target the predicted account address
-                U256::ZERO,         // No value transfer
-                Bytes::new()        // Empty call data for deployment
-            )
-            .with_gas_fees(max_fee, priority_fee)
-            .build();
-            
-            println!("✅ Deployment UserOperation created!");
-            println!("Predicted Account: {}", predicted_address);
-            println!("aa-sdk-rs will handle factory calls automatically");
-            
-            println!("🚀 Submitting deployment UserOperation to bundler...");
-            
-            // Submit using SmartAccountProvider to actually deploy the account
-            match smart_provider.send_user_operation(user_op_request, wallet.signer()).await {
-                Ok(user_op_hash) => {
-                    println!("✅ Smart account deployment initiated successfully!");
-                    println!("UserOperation Hash: {:?}", user_op_hash);
-                    println!("The account will be deployed at: {}", predicted_address);
-                    println!("You can track this deployment on the blockchain");
-                }
-                Err(e) => {
-                    println!("❌ Error deploying smart account: {}", e);
-                    println!("Make sure:");
-                    println!("  1. The bundler is running and supports eth_sendUserOperation");
-                    println!("  2. The predicted account address is funded with ETH");
-                    println!("  3. The factory contract is deployed and accessible");
-                }
-            }
-        }
-        Err(e) => {
-            println!("❌ Error predicting smart account address: {}", e);
-            println!("Make sure the factory contract is deployed and accessible");
+    Ok(())
+}
+
+/// Rebuild a proposal offline from a captured build context
+fn rebuild_proposal(context: &Path, file: &Path) -> Result<()> {
+    let context = userop::BuildContext::load(context)?;
+    let proposal = multisig::Proposal::from_context(context);
+    
+    // Comparing against an existing proposal is the audit, writing a new one is the fallback
+    if file.exists() {
+        let existing = multisig::Proposal::load(file)?;
+        if existing.user_op_hash != proposal.user_op_hash {
+            return Err(anyhow::anyhow!(
+                "Rebuilt UserOperation hash {} differs from {} in {}",
+                proposal.user_op_hash, existing.user_op_hash, file.display()
+            ));
         }
+        out!("✅ Rebuild matches {} byte for byte", file.display());
+    } else {
+        proposal.save(file)?;
+        out!("✅ Proposal written to {}", file.display());
     }
+    out!("UserOperation Hash: {}", proposal.user_op_hash);
     
     Ok(())
 }
 
-/// Deploy a new smart account with multiple owners using AAAccountFactory via bundler
-async fn deploy_multi_owner_account(
-    private_key: &str,
-    factory: &str,
-    owners: &str,
-    salt: &str,
-    rpc_url: &str,
-    chain_id: u64,
-) -> Result<()> {
-    println!("🚀 Deploying new multi-owner smart account using AAAccountFactory via bundler...");
-    
-    // Create wallet from private key
-    let wallet = Wallet::from_hex(private_key)?;
-    println!("Deployer wallet: {}", wallet.address());
+/// Add the caller's owner signature to a proposal
+async fn sign_proposal(owner: &OwnerKey, file: &Path) -> Result<()> {
+    let mut proposal = multisig::Proposal::load(file)?;
     
-    // Parse factory address
-    let factory_addr = Address::from_str(factory)?;
-    println!("AAAccountFactory contract: {}", factory_addr);
+    out!("✍️  Signing proposal {}", file.display());
+    out!("Account: {} | Chain: {}", proposal.user_op.sender, proposal.chain_id);
+    out!("UserOperation Hash: {}", proposal.user_op_hash);
     
-    // Parse owners list
-    let owner_addresses: Vec<Address> = owners
-        .split(',')
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| Address::from_str(s))
-        .collect::<Result<Vec<_>, _>>()?;
+    if !proposal.accepts_signer(owner.address()) {
+        return Err(anyhow::anyhow!(
+            "Proposal already has the {} signatures it needs, submit it with: op submit --file {}",
+            proposal.signatures.len(),
+            file.display()
+        ));
+    }
+    match owner {
+        OwnerKey::Local(wallet) => proposal.sign(wallet)?,
+        OwnerKey::Remote(signer) => proposal.sign_remote(signer).await?,
+    }
+    proposal.save(file)?;
     
-    // Validate owners
-    if owner_addresses.is_empty() {
-        return Err(anyhow::anyhow!("At least one owner is required"));
+    out!("✅ Signed by {} ({} signatures collected)", owner.address(), proposal.signatures.len());
+    match proposal.threshold {
+        Some(_) if proposal.threshold_met() => out!("🎯 Threshold met, submit with: op submit --file {}", file.display()),
+        Some(threshold) => out!("⏳ {} more signature(s) needed", threshold - proposal.signatures.len()),
+        None => {}
     }
-    if owner_addresses.len() > 10 {
-        return Err(anyhow::anyhow!("Maximum 10 owners allowed"));
+    
+    Ok(())
+}
+
+/// Print a proposal file as QR chunks, compact JSON keeps the number of codes down
+fn show_proposal_qr(file: &Path) -> Result<()> {
+    let proposal = multisig::Proposal::load(file)?;
+    qr::print_qr(qr::PayloadKind::Proposal, &serde_json::to_vec(&proposal)?)
+}
+
+/// Reassemble a payload from scanned QR chunks and import it
+fn scan_qr_chunks(input: Option<&Path>, out: Option<&Path>) -> Result<()> {
+    use std::io::BufRead;
+    
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(std::io::BufReader::new(std::fs::File::open(path)?)),
+        None => {
+            out!("📷 Scan QR parts now, one per line (Ctrl-D to stop)...");
+            Box::new(std::io::stdin().lock())
+        }
+    };
+    
+    let mut assembler = qr::ChunkAssembler::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match assembler.push(&line) {
+            Ok(true) => break,
+            Ok(false) => out!("✅ Part received, still missing {:?} of {}", assembler.missing(), assembler.total()),
+            Err(e) => out!("⚠️  Skipped scan: {}", e),
+        }
     }
     
-    // Check for duplicates
-    for i in 0..owner_addresses.len() {
-        for j in (i + 1)..owner_addresses.len() {
-            if owner_addresses[i] == owner_addresses[j] {
-                return Err(anyhow::anyhow!("Duplicate owner address: {}", owner_addresses[i]));
+    let (kind, payload) = assembler.finish()?;
+    match kind {
+        qr::PayloadKind::Proposal => {
+            let scanned = multisig::Proposal::from_json(std::str::from_utf8(&payload)?)?;
+            let out = out.ok_or_else(|| anyhow::anyhow!("A scanned proposal needs --out"))?;
+            let proposal = if out.exists() {
+                let mut existing = multisig::Proposal::load(out)?;
+                existing.merge(scanned)?;
+                existing
+            } else {
+                scanned
+            };
+            proposal.save(out)?;
+            out!("✅ Proposal {} saved to {}", proposal.user_op_hash, out.display());
+            match proposal.threshold {
+                Some(threshold) => out!("Signatures collected: {} of {}", proposal.signatures.len(), threshold),
+                None => out!("Signatures collected: {}", proposal.signatures.len()),
+            }
+        }
+        qr::PayloadKind::PreAuth => {
+            let link = String::from_utf8(payload)?;
+            out!("🎟️  Pre-auth link: {}", link);
+            out!("Token: {}", paymaster::parse_preauth_token(&link));
+            if let Some(out) = out {
+                std::fs::write(out, &link)?;
+                out!("💾 Saved to {}", out.display());
             }
         }
     }
     
-    println!("Owners ({}):", owner_addresses.len());
-    for (i, owner) in owner_addresses.iter().enumerate() {
-        println!("  Owner {}: {}", i + 1, owner);
+    Ok(())
+}
+
+/// Combine collected owner signatures and submit the proposal to the bundler
+async fn submit_proposal(file: &Path, threshold: Option<usize>, rpc_url: &str, tag: Option<&str>) -> Result<()> {
+    let proposal = multisig::Proposal::load(file)?;
+    let account_addr = proposal.user_op.sender;
+    
+    let provider = providers::http(rpc_url)?;
+    let owners = account::get_owners(&provider, account_addr).await?;
+    
+    proposal.verify_shares(&provider, &owners).await?;
+    // The account's own threshold is authoritative, every owner signs when it has none
+    let required = match threshold {
+        Some(threshold) => threshold,
+        None => account::get_threshold(&provider, account_addr).await.unwrap_or(owners.len()),
+    };
+    if proposal.signatures.len() < required {
+        let missing = proposal.missing_signers(&owners);
+        return Err(anyhow::anyhow!(
+            "Only {} of {} required signatures collected, still missing: {}",
+            proposal.signatures.len(),
+            required,
+            missing.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+        ));
     }
     
-    // Parse salt
-    let salt_bytes = if salt.starts_with("0x") {
-        hex::decode(&salt[2..])?
-    } else {
-        hex::decode(salt)?
-    };
+    if preflight::dry_run() {
+        return print_dry_run(rpc_url, proposal.entry_point, proposal.chain_id, &proposal.user_op, proposal.combined_signature()?).await;
+    }
     
-    println!("Salt: 0x{}", hex::encode(&salt_bytes));
-    println!("⚠️  Note: Salt will be handled by aa-sdk-rs automatically");
+    let mut user_op = serde_json::to_value(&proposal.user_op)?;
+    user_op["signature"] = serde_json::to_value(proposal.combined_signature()?)?;
     
-    println!("🔧 Setting up aa-sdk-rs for multi-owner deployment...");
+    out!("🚀 Submitting UserOperation with {} owner signatures...", proposal.signatures.len());
+    let bundler_client = bundler_at(rpc_url.to_string(), proposal.entry_point, U256::from(proposal.chain_id));
+    bundler_client.preflight().await?;
+    let user_op_hash = bundler_client.send_user_operation(user_op, proposal.entry_point).await?;
     
-    // Create concrete provider type for aa-sdk-rs
-    let url = url::Url::parse(rpc_url)?;
-    let provider = ProviderBuilder::new().on_http(url);
+    out!("✅ {}", t!("userop-submitted"));
+    out!("UserOperation Hash: {}", user_op_hash);
+    journal_operation(&user_op_hash, proposal.chain_id, account_addr, "op-submit", tag, false);
     
-    // ⚠️ LIMITATION: aa-sdk-rs SimpleAccount doesn't support multi-owner natively
-    // Using first owner as primary owner, factory must handle multi-owner logic
-    let primary_owner = owner_addresses[0];
-    // TODO: Migrate to SimpleAccount::new_with_salt for better salt support
-    let simple_account = SimpleAccount::new(
-        Arc::new(provider.clone()),
-        primary_owner,         // Primary owner from the list
-        factory_addr,          // AAAccountFactory address  
-        Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?, // EntryPoint address
+    Ok(())
+}
+
+/// Abort before anything is built when the RPC endpoint is on another chain than `chain_id`
+/// Always asks the endpoint, a cached chain ID would miss a node restarted on another chain
+async fn verify_chain_id(rpc_url: &str, chain_id: u64) -> Result<()> {
+    let reported = retry::retry("eth_chainId", || providers::registry().fresh_chain_id(rpc_url)).await?;
+    config::check_chain_id(chain_id, reported, "RPC endpoint")?;
+    if let Some(retired) = config::retired_network(chain_id) {
+        out!("⚠️  {}", retired);
+    }
+    Ok(())
+}
+
+/// Bundler client that keeps endpoint health under ~/.aa-client, so the next invocation fails over past an endpoint that just failed
+fn bundler_at(rpc_url: String, entry_point: Address, chain_id: U256) -> BundlerClient {
+    BundlerClient::new(rpc_url, entry_point, chain_id).with_health(health::HealthStore::persistent())
+}
+
+/// Fail fast when the RPC endpoint is on another chain or no bundler serves the chain and EntryPoint,
+/// returning the endpoint to submit to. Every command that builds an operation goes through here
+async fn preflight_bundler(rpc_url: &str, entry_point: Address, chain_id: u64) -> Result<String> {
+    verify_chain_id(rpc_url, chain_id).await?;
+    out!("🔎 {}", t!("bundler-preflight"));
+    let endpoint = bundler_at(rpc_url.to_string(), entry_point, U256::from(chain_id)).preflight().await?;
+    if bundler::private_submission() {
+        out!("🔒 Submitting privately through {}", endpoint);
+    } else if endpoint != rpc_url {
+        out!("🔀 Using backup bundler {}", endpoint);
+    }
+    Ok(endpoint)
+}
+
+/// Fee caps for a new UserOperation: explicit flags win, the fee oracle fills in the rest
+async fn resolve_fees(rpc_url: &str, max_fee_per_gas: Option<&str>, max_priority_fee_per_gas: Option<&str>) -> Result<(U256, U256)> {
+    let provider = providers::http(rpc_url)?;
+    resolve_fees_with(&provider, max_fee_per_gas, max_priority_fee_per_gas).await
+}
+
+/// `resolve_fees` on a provider the caller already has
+async fn resolve_fees_with<P: alloy::providers::Provider>(
+    provider: &P,
+    max_fee_per_gas: Option<&str>,
+    max_priority_fee_per_gas: Option<&str>,
+) -> Result<(U256, U256)> {
+    if let (Some(max_fee), Some(priority_fee)) = (max_fee_per_gas, max_priority_fee_per_gas) {
+        return Ok((U256::from_str_radix(max_fee, 10)?, U256::from_str_radix(priority_fee, 10)?));
+    }
+
+    let urgency = fees::urgency();
+    let estimate = retry::retry("eth_feeHistory", || fees::estimate(provider, urgency)).await?;
+
+    let priority_fee = match max_priority_fee_per_gas {
+        Some(priority_fee) => U256::from_str_radix(priority_fee, 10)?,
+        None => estimate.max_priority_fee_per_gas,
+    };
+    // A custom tip keeps the estimated base-fee headroom on top of it
+    let max_fee = match max_fee_per_gas {
+        Some(max_fee) => U256::from_str_radix(max_fee, 10)?,
+        None => estimate.max_fee_per_gas - estimate.max_priority_fee_per_gas + priority_fee,
+    };
+    out!("⛽ Estimated {} fees: max {} gwei, priority {} gwei",
+         urgency, fees::format_gwei(max_fee.saturating_to()), fees::format_gwei(priority_fee.saturating_to()));
+    Ok((max_fee, priority_fee))
+}
+
+async fn show_bundler_status(rpc_url: &str, no_probe: bool) -> Result<()> {
+    let client = bundler_at(rpc_url.to_string(), Address::ZERO, U256::ZERO);
+    let probes = if no_probe { Vec::new() } else { client.probe().await };
+    let now = clock::now_secs();
+    
+    out!("🛰️  Bundler endpoints (failover order as configured):");
+    for (i, endpoint) in client.endpoints().iter().enumerate() {
+        let role = if i == 0 { "primary".to_string() } else { format!("backup {}", i) };
+        let record = client.health().get(endpoint);
+        let state = match probes.iter().find(|(probed, _)| probed == endpoint) {
+            Some((_, Ok(chain_id))) => format!("✅ up, chain {}", chain_id),
+            Some((_, Err(_))) => "❌ unreachable".to_string(),
+            None if record.is_down(now) => "❌ down (cooling off)".to_string(),
+            None if record.last_checked == 0 => "❔ never contacted".to_string(),
+            None => "✅ up".to_string(),
+        };
+        out!("{} [{}] {}", endpoint, role, state);
+        let latency = record.last_latency_ms.map(|ms| format!("{} ms", ms)).unwrap_or_else(|| "-".to_string());
+        out!("  Latency: {}, successes: {}, failures: {} ({} in a row)",
+             latency, record.successes, record.failures, record.consecutive_failures);
+        if let Some(error) = &record.last_error {
+            out!("  Last error: {}", error);
+        }
+    }
+    if client.endpoints().len() == 1 {
+        out!("💡 Add backups with --bundler <URL> or `bundlers = [...]` in a profile");
+    }
+    Ok(())
+}
+
+/// Record a submitted operation in the local journal and announce it to event subscribers
+/// Journal failures are reported but never fail the command, the operation is already on its way
+fn journal_operation(user_op_hash: &str, chain_id: u64, sender: Address, kind: &str, tag: Option<&str>, sponsored: bool) {
+    events::emit(ClientEvent::Submitted { user_op_hash: user_op_hash.to_string(), chain_id, sender });
+    let record = OperationRecord::pending(
+        user_op_hash.to_string(),
         chain_id,
+        sender,
+        kind,
+        tag.map(|t| t.to_string()),
+        sponsored,
     );
-    
-    println!("📋 Primary owner (for aa-sdk-rs): {}", primary_owner);
-    println!("📋 Total owners requested: {} addresses", owner_addresses.len());
-    for (i, owner) in owner_addresses.iter().enumerate() {
-        println!("  Owner {}: {}", i + 1, owner);
+    match Journal::open_default().and_then(|journal| journal.append(record)) {
+        Ok(()) => {
+            if let Some(tag) = tag {
+                out!("🏷️  Recorded in journal with tag: {}", tag);
+            }
+        }
+        Err(e) => out!("⚠️  Could not record operation in journal: {}", e),
+    }
+}
+
+/// Keep the submitted operation with its journal record, so `watch --bump-after` can re-sign it
+fn journal_user_op(user_op_hash: &str, user_op: &multisig::UnsignedUserOperation) {
+    if let Err(e) = Journal::open_default().and_then(|journal| journal.attach_user_op(user_op_hash, user_op.clone())) {
+        out!("⚠️  Could not record operation in journal: {}", e);
+    }
+}
+
+/// Included event for a successful receipt, Failed for a reverted one
+fn emit_outcome(user_op_hash: &str, success: bool, gas_used: U256, gas_cost: U256) {
+    let user_op_hash = user_op_hash.to_string();
+    events::emit(if success {
+        ClientEvent::Included { user_op_hash, actual_gas_used: gas_used, actual_gas_cost: gas_cost }
+    } else {
+        ClientEvent::Failed { user_op_hash: Some(user_op_hash), reason: "included but reverted".to_string() }
+    });
+}
+
+/// Record the receipt outcome of a journaled operation
+fn journal_receipt(user_op_hash: &str, success: bool, gas_used: U256, gas_cost: U256) {
+    emit_outcome(user_op_hash, success, gas_used, gas_cost);
+    if let Err(e) = Journal::open_default().and_then(|journal| journal.record_receipt(user_op_hash, success, gas_used, gas_cost)) {
+        out!("⚠️  Could not update journal with receipt: {}", e);
+    }
+}
+
+/// List operations recorded in the local journal
+fn list_journaled_operations(tag: Option<&str>) -> Result<()> {
+    let journal = Journal::open_default()?;
+    let records = journal.load()?;
+    
+    out!("📋 Operations journal ({})", journal.path().display());
+    
+    let mut shown = 0;
+    for record in records.iter().filter(|r| tag.is_none() || r.tag.as_deref() == tag) {
+        shown += 1;
+        out!("{}", record.user_op_hash);
+        out!("  Kind: {} | Chain: {} | Sender: {}", record.kind, record.chain_id, record.sender);
+        out!("  Status: {:?} | Sponsored: {} | Tag: {}", record.status, record.sponsored, record.tag.as_deref().unwrap_or("-"));
+        if let Some(gas_cost) = record.actual_gas_cost {
+            out!("  Gas cost: {} wei", gas_cost);
+        }
+        if let Some(replacement) = &record.replaced_by {
+            out!("  Replaced by: {}", replacement);
+        }
     }
     
-    // ✅ Get predicted address BEFORE moving simple_account into provider
-    let predicted_address = simple_account.get_counterfactual_address().await?;
-    println!("📍 aa-sdk-rs predicted address: {}", predicted_address);
-    println!("💡 Make sure this address is funded with ETH for gas fees");
-    println!("⚠️  Note: This is single-owner prediction, multi-owner may require custom handling");
-    
-    // Create SmartAccountProvider (this moves simple_account)
-    let smart_provider = SmartAccountProvider::new(provider, simple_account);
-    
-    // Parse gas fees - Set higher values to meet bundler requirements
-    let max_fee = U256::from_str_radix("5000000000", 10)?; // 5 gwei (reasonable for Sepolia)  
-    let priority_fee = U256::from_str_radix("200000000", 10)?; // 0.2 gwei (above 0.1 gwei minimum)
-    
-    println!("🔧 Creating deployment UserOperation...");
-    println!("📊 aa-sdk-rs will automatically:");
-    println!("  - Detect that the account doesn't exist");
-    println!("  - Generate initCode for factory deployment");
-    println!("  - Set the predicted address as sender");
-    println!("  - Handle nonce management");
-    
-    // ✅ FIXED: Generate multi-owner initCode manually
-    println!("🔧 Generating custom initCode for multi-owner deployment...");
-    
-    // Recreate bundler client and provider for factory interactions
-    let bundler_client = BundlerClient::new(
-        rpc_url.to_string(),
-        Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?,
-        U256::from(chain_id),
-    );
-    
-    // Convert salt bytes to U256 for factory call
-    let mut salt_array = [0u8; 32];
-    let start_idx = 32usize.saturating_sub(salt_bytes.len());
-    salt_array[start_idx..].copy_from_slice(&salt_bytes[..32.min(salt_bytes.len())]);
-    let salt_u256 = U256::from_be_bytes(salt_array);
-    
-    // Get the actual predicted address for multi-owner deployment
-    let actual_predicted_address = bundler_client.get_predicted_multi_owner_address(factory_addr, owner_addresses.clone(), salt_u256).await?;
-    println!("📍 Real multi-owner predicted address: {}", actual_predicted_address);
-    println!("💡 Make sure THIS address is funded with ETH: {}", actual_predicted_address);
-    
-    // Generate call data for createAccountWithOwners
-    let factory_provider = bundler_client.create_provider().await?;
-    let factory_contract = bundler::AAAccountFactory::new(factory_addr, &factory_provider);
-    let factory_call_data = factory_contract.createAccountWithOwners(owner_addresses.clone(), salt_u256).calldata().clone();
-    
-    // Create custom initCode: factory_address + encoded_function_call
-    let mut init_code = Vec::new();
-    init_code.extend_from_slice(factory_addr.as_slice());
-    init_code.extend_from_slice(&factory_call_data);
-    
-    println!("✅ Custom initCode generated for {} owners", owner_addresses.len());
-    println!("🔍 InitCode: 0x{}", hex::encode(&init_code));
-    
-    // Create UserOperation with multi-owner settings
-    let mut user_op_request = UserOperationBuilder::new(
-        actual_predicted_address,  // ✅ Real multi-owner predicted address
-        U256::ZERO,               // No direct value transfer
-        Bytes::new()              // Empty call data for deployment
-    )
-    .with_gas_fees(max_fee, priority_fee)
-    .build();
-    
-    // ✅ CRITICAL: Set factory and factory_data for multi-owner deployment
-            user_op_request.factory = Some(factory_addr);
-        user_op_request.factory_data = Some(Bytes::from(factory_call_data));
-        // CRITICAL: Override sender to use multi-owner predicted address, not aa-sdk-rs single-owner prediction
-        user_op_request.sender = Some(actual_predicted_address);
-        // CRITICAL: For new account deployment, nonce must be 0
-        user_op_request.nonce = Some(U256::ZERO);
-    
-    println!("✅ Multi-owner deployment UserOperation created!");
-    println!("Target Account: {}", actual_predicted_address);
-    println!("Custom initCode set for multi-owner factory deployment");
-    println!("🔍 InitCode contains {} owners", owner_addresses.len());
-    
-    println!("🚀 Submitting multi-owner deployment UserOperation to bundler...");
+    if shown == 0 {
+        out!("No operations recorded");
+    }
     
-    // Submit using the same pattern as working deploy-account
-    match smart_provider.send_user_operation(user_op_request, wallet.signer()).await {
-                Ok(user_op_hash) => {
-                    println!("✅ Multi-owner smart account deployment initiated successfully!");
-                    println!("UserOperation Hash: {:?}", user_op_hash);
-                    println!("The account will be deployed at: {}", predicted_address);
-                    println!("You can track this deployment on the blockchain");
-                    
-                    println!();
-                    println!();
-                    println!("💡 Expected multi-owner features (if factory supports it):");
-                    println!("- Any owner can execute transactions");
-                    println!("- Owners can add new owners (up to 10 total)");
-                    println!("- Owners can remove other owners (but not themselves)");
-                    println!("- Cannot remove the last owner");
-                    println!();
-                    println!("⚠️  Note: This deployment used single-owner aa-sdk-rs pattern");
-                    println!("   The actual multi-owner logic depends on factory implementation");
-                }
+    Ok(())
+}
+
+/// How `ops watch` scans and what it may do on its own
+struct WatchSettings<'a> {
+    stuck_after: u64,
+    interval: u64,
+    webhook: Option<&'a str>,
+    remediate: bool,
+    once: bool,
+}
+
+/// Scan the journal for stuck operations on the bundler's chain until interrupted
+/// Each operation is alerted on once per run, a remediated one leaves the pending set
+async fn watch_operations(rpc_url: &str, settings: WatchSettings<'_>) -> Result<()> {
+    let provider = providers::http(rpc_url)?;
+    let chain_id = providers::registry().chain_id(rpc_url).await?;
+    let entry_point_addr = entrypoint::resolve(chain_id)?;
+    let journal = Journal::open_default()?;
+    let http = reqwest::Client::new();
+    let mut alerted = std::collections::HashSet::new();
+    
+    out!("👀 Watching chain {} for operations pending over {}s", chain_id, settings.stuck_after);
+    loop {
+        let now = clock::now_secs();
+        let stuck: Vec<OperationRecord> = journal
+            .load()?
+            .into_iter()
+            .filter(|r| r.chain_id == chain_id && r.status == journal::OperationStatus::Pending)
+            .filter(|r| now.saturating_sub(r.submitted_at) >= settings.stuck_after)
+            .collect();
+        
+        for record in stuck {
+            let observation = match stuck::observe(&provider, &record, entry_point_addr).await {
+                Ok(observation) => observation,
                 Err(e) => {
-                    println!("❌ Error deploying smart account: {}", e);
-                    println!("Make sure:");
-                    println!("  1. The bundler is running and supports eth_sendUserOperation");
-                    println!("  2. The predicted account address is funded with ETH");  
-                    println!("  3. The AAAccountFactory contract is deployed and accessible");
-                    println!("  4. Factory supports single-owner deployment pattern for primary owner");
+                    out!("⚠️  Could not check {}: {}", record.user_op_hash, e);
+                    continue;
+                }
+            };
+            let diagnosis = stuck::diagnose(&observation, now);
+            let remediation = stuck::remediation(&diagnosis, settings.remediate);
+            
+            match remediation {
+                stuck::Remediation::RecordReceipt => {
+                    if let Some(receipt) = &observation.receipt {
+                        journal_receipt(&record.user_op_hash, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost);
+                    }
+                    out!("✅ {} was included, journal updated", record.user_op_hash);
+                    continue;
+                }
+                stuck::Remediation::MarkFailed => {
+                    journal.mark_failed(&record.user_op_hash)?;
+                    events::emit(ClientEvent::Failed { user_op_hash: Some(record.user_op_hash.clone()), reason: diagnosis.describe() });
+                    out!("🧹 {} {}, marked failed", record.user_op_hash, diagnosis.describe());
+                }
+                stuck::Remediation::Alert => {
+                    if alerted.contains(&record.user_op_hash) {
+                        continue;
+                    }
+                    out!("⚠️  {} stuck for {}s: {}", record.user_op_hash, now - record.submitted_at, diagnosis.describe());
                 }
             }
+            
+            alerted.insert(record.user_op_hash.clone());
+            if let Some(webhook) = settings.webhook {
+                let alert = stuck::StuckAlert {
+                    user_op_hash: record.user_op_hash.clone(),
+                    chain_id,
+                    sender: record.sender,
+                    tag: record.tag.clone(),
+                    pending_secs: now - record.submitted_at,
+                    summary: diagnosis.describe(),
+                    diagnosis,
+                    remediation,
+                };
+                let (http, alert) = (&http, &alert);
+                let delivered = retry::retry("webhook", move || async move {
+                    http.post(webhook).json(alert).send().await?.error_for_status()
+                })
+                .await;
+                if let Err(e) = delivered {
+                    out!("❌ Webhook delivery failed for {}: {}", record.user_op_hash, e);
+                }
+            }
+        }
+        
+        if settings.once {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(settings.interval)).await;
+    }
+}
+
+/// Scan pending journal operations on every chain with a known bundler, once or as a daemon serving its status
+async fn watch_journal(
+    config: &CliConfig,
+    endpoints: &std::collections::BTreeMap<u64, String>,
+    interval: u64,
+    bump: Option<&(daemon::BumpPolicy, Wallet)>,
+    status_addr: Option<&daemon::StatusAddr>,
+) -> Result<()> {
+    let journal = Journal::open_default()?;
+    let status: daemon::SharedStatus = Arc::new(std::sync::Mutex::new(daemon::Status {
+        started_at: clock::now_secs(),
+        ..Default::default()
+    }));
+    if let Some(addr) = status_addr {
+        tokio::spawn(daemon::serve(addr.bind().await?, status.clone()));
+        out!("👀 Watching {} chain(s), status at {}", endpoints.len(), addr);
+    }
+    if let Some((policy, wallet)) = bump {
+        out!("⛽ Operations waiting over {}s get {}% higher fees, re-signed by {}", policy.after_secs, policy.percent, wallet.address());
+    }
     
-    Ok(())
+    loop {
+        let now = clock::now_secs();
+        let mut pending: std::collections::BTreeMap<u64, Vec<OperationRecord>> = std::collections::BTreeMap::new();
+        for record in journal.load()?.into_iter().filter(|r| r.status == journal::OperationStatus::Pending) {
+            pending.entry(record.chain_id).or_default().push(record);
+        }
+        
+        let mut scans = Vec::new();
+        for (chain_id, records) in pending {
+            let scan = match endpoints.get(&chain_id) {
+                Some(rpc_url) => {
+                    let entry_point = entrypoint::resolve(chain_id).unwrap_or(config::ENTRY_POINT_V07);
+                    scan_chain(rpc_url, entry_point, &records, &journal, bump, now).await
+                }
+                None => daemon::ChainScan {
+                    pending: records.iter().map(|r| daemon::PendingStatus::of(r, now, None)).collect(),
+                    error: Some(format!("no bundler URL, pass --rpc {}=URL", chain_id)),
+                    ..Default::default()
+                },
+            };
+            if let Some(error) = &scan.error {
+                out!("⚠️  Chain {}: {}", chain_id, error);
+            }
+            scans.push((chain_id, scan));
+        }
+        
+        let mut snapshot = status.lock().unwrap();
+        snapshot.record_scan(now, scans);
+        if status_addr.is_none() {
+            out!(
+                "📋 {} pending on {} chain(s) | included {} | failed {} | bumped {}",
+                snapshot.pending.len(), snapshot.chains.len(), snapshot.included, snapshot.failed, snapshot.bumped
+            );
+            return Ok(());
+        }
+        drop(snapshot);
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
 }
 
-/// Predict smart account address before deployment
-async fn predict_smart_account_address(
-    factory: &str,
-    owner: &str,
-    salt: &str,
+/// Check the pending operations of one chain: journal receipts and dead operations, bump the fees of waiting ones
+async fn scan_chain(
     rpc_url: &str,
-    chain_id: u64,
-) -> Result<()> {
-    println!("Predicting smart account address...");
-    
-    // Parse addresses
-    let factory_addr = Address::from_str(factory)?;
-    let owner_addr = Address::from_str(owner)?;
-    
-    // Parse salt
-    let salt_bytes = if salt.starts_with("0x") {
-        hex::decode(&salt[2..])?
-    } else {
-        hex::decode(salt)?
+    entry_point: Address,
+    records: &[OperationRecord],
+    journal: &Journal,
+    bump: Option<&(daemon::BumpPolicy, Wallet)>,
+    now: u64,
+) -> daemon::ChainScan {
+    let mut scan = daemon::ChainScan::default();
+    let provider = match providers::http(rpc_url) {
+        Ok(provider) => provider,
+        Err(e) => {
+            scan.pending = records.iter().map(|r| daemon::PendingStatus::of(r, now, None)).collect();
+            scan.error = Some(e.to_string());
+            return scan;
+        }
     };
     
-    println!("Predicting smart account address using real SimpleAccountFactory contract...");
-    println!("Factory: {}", factory_addr);
-    println!("Owner: {}", owner_addr);
-    println!("Salt: 0x{}", hex::encode(&salt_bytes));
-    
-    // Convert salt bytes to U256
-    let mut salt_array = [0u8; 32];
-    let start_idx = 32usize.saturating_sub(salt_bytes.len());
-    salt_array[start_idx..].copy_from_slice(&salt_bytes[..32.min(salt_bytes.len())]);
-    let salt_u256 = U256::from_be_bytes(salt_array);
-    
-    // Create bundler client for contract calls
-    let bundler_client = BundlerClient::new(
-        rpc_url.to_string(),
-        Address::ZERO, // Entry point not needed for this call
-        U256::from(chain_id),
-    );
-    
-    // Get real predicted address from the factory contract
-    match bundler_client.get_predicted_address(factory_addr, owner_addr, salt_u256).await {
-        Ok(predicted_address) => {
-            println!("✅ Real Predicted Address: {}", predicted_address);
-            println!("This address is calculated by the actual SimpleAccountFactory contract");
+    for record in records {
+        let observation = match stuck::observe(&provider, record, entry_point).await {
+            Ok(observation) => observation,
+            Err(e) => {
+                scan.pending.push(daemon::PendingStatus::of(record, now, None));
+                scan.error = Some(format!("could not check {}: {}", record.user_op_hash, e));
+                continue;
+            }
+        };
+        let diagnosis = stuck::diagnose(&observation, now);
+        let updated = match stuck::remediation(&diagnosis, true) {
+            stuck::Remediation::RecordReceipt => observation.receipt.as_ref().map(|receipt| {
+                if receipt.success { scan.included += 1 } else { scan.failed += 1 }
+                emit_outcome(&record.user_op_hash, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost);
+                out!("✅ {} included on chain {}{}", record.user_op_hash, record.chain_id, if receipt.success { "" } else { " but reverted" });
+                journal.record_receipt(&record.user_op_hash, receipt.success, receipt.actual_gas_used, receipt.actual_gas_cost)
+            }),
+            stuck::Remediation::MarkFailed => {
+                scan.failed += 1;
+                events::emit(ClientEvent::Failed { user_op_hash: Some(record.user_op_hash.clone()), reason: diagnosis.describe() });
+                out!("🧹 {} {}, marked failed", record.user_op_hash, diagnosis.describe());
+                Some(journal.mark_failed(&record.user_op_hash))
+            }
+            stuck::Remediation::Alert => None,
+        };
+        if let Some(updated) = updated {
+            if let Err(e) = updated {
+                scan.error = Some(format!("could not update the journal: {}", e));
+            }
+            continue;
         }
-        Err(e) => {
-            println!("❌ Error calling factory contract: {}", e);
-            println!("Make sure the factory contract is deployed and the RPC URL is correct");
+        
+        let due = bump.filter(|(policy, _)| {
+            !record.sponsored && now.saturating_sub(record.submitted_at) >= policy.after_secs && daemon::BumpPolicy::applies(&diagnosis)
+        });
+        if let (Some((policy, wallet)), Some(user_op)) = (due, &record.user_op) {
+            match bump_fees(rpc_url, entry_point, record, user_op, policy, wallet, observation.base_fee, journal).await {
+                Ok(Some(replacement)) => {
+                    scan.bumped += 1;
+                    scan.pending.push(daemon::PendingStatus::of(&replacement, now, None));
+                    continue;
+                }
+                Ok(None) => out!("⚠️  {} is waiting but a bump would exceed --max-fee-cap", record.user_op_hash),
+                Err(e) => out!("❌ Could not bump the fees of {}: {}", record.user_op_hash, e),
+            }
         }
+        scan.pending.push(daemon::PendingStatus::of(record, now, Some(diagnosis.describe())));
     }
-    
-    Ok(())
+    scan
 }
 
-/// Submit a sponsored UserOperation where gas is paid by paymaster
-async fn submit_sponsored_user_operation(
-    private_key: &str,
-    target: &str,
-    call_data: &str,
-    value: &str,
-    factory: &str,
-    salt: &str,
+/// Re-sign a waiting operation with higher fees and send it as the replacement of the journaled one
+/// Returns the replacement's record, None when the policy's cap leaves no room
+#[allow(clippy::too_many_arguments)]
+async fn bump_fees(
     rpc_url: &str,
-    chain_id: u64,
-    paymaster_url: &str,
-    paymaster_api_key: &str,
-    paymaster_address: &str,
-) -> Result<()> {
-    println!("🎉 Submitting sponsored transaction via paymaster...");
-    
-    // Setup
-    let wallet = Wallet::from_hex(private_key)?;
-    let factory_addr = Address::from_str(factory)?;
-    let target_addr = Address::from_str(target)?;
-    let paymaster_addr = Address::from_str(paymaster_address)?;
-    let entry_point_addr = Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?;
-    
-    println!("🔧 Configuration:");
-    println!("  Factory: {}", factory_addr);
-    println!("  Target: {}", target_addr);
-    println!("  Paymaster: {}", paymaster_addr);
-    println!("  Owner EOA: {}", wallet.address());
-    
-    let url = url::Url::parse(rpc_url)?;
-    let provider = ProviderBuilder::new().on_http(url);
-    
-    // TODO: Migrate to SimpleAccount::new_with_salt for better salt support
-    let simple_account = SimpleAccount::new(
-        Arc::new(provider.clone()),
-        wallet.address(),
-        factory_addr,
-        entry_point_addr,
-        chain_id,
-    );
-    
-    // Check if account is deployed
-    println!("🔍 Checking if smart account is deployed...");
-    let is_deployed = simple_account.is_account_deployed().await?;
-    if !is_deployed {
-        let predicted_addr = simple_account.get_counterfactual_address().await?;
-        return Err(anyhow::anyhow!(
-            "❌ Smart account not deployed at {}!\n💡 Run deploy-sponsored first with:\n  cargo run -- deploy-sponsored --factory {} --salt {} --private-key {} --paymaster-url {} --paymaster-api-key {}",
-            predicted_addr, factory, salt, private_key, paymaster_url, paymaster_api_key
-        ));
-    }
-    
-    let account_addr = simple_account.get_account_address().await?;
-    println!("✅ Using deployed smart account: {}", account_addr);
-    
-    // Prepare transaction parameters
-    let call_data_bytes = if call_data.starts_with("0x") {
-        Bytes::from_str(call_data)?
-    } else {
-        Bytes::from_str(&format!("0x{}", call_data))?
+    entry_point: Address,
+    record: &OperationRecord,
+    user_op: &multisig::UnsignedUserOperation,
+    policy: &daemon::BumpPolicy,
+    wallet: &Wallet,
+    base_fee: U256,
+    journal: &Journal,
+) -> Result<Option<OperationRecord>> {
+    let Some(bumped) = policy.bump(user_op, base_fee) else {
+        return Ok(None);
     };
-    let value_amount = U256::from_str_radix(value, 10)?;
+    let signed = userop::SignedUserOperation::sign(bumped, entry_point, record.chain_id, wallet)?;
+    let bundler_client = bundler_at(rpc_url.to_string(), entry_point, U256::from(record.chain_id));
+    let user_op_hash = bundler_client.send_user_operation(serde_json::to_value(signed.to_rpc())?, entry_point).await?;
     
-    println!("🔧 Preparing sponsored transaction...");
-    println!("  Target: {}", target_addr);
-    println!("  Value: {} wei", value_amount);
-    println!("  Call data: 0x{}", hex::encode(&call_data_bytes));
-    println!("  Paymaster service: {}", paymaster_url);
-    
-    // Create UserOperation with explicit gas fees to meet bundler requirements
-    let max_fee = U256::from_str_radix("5000000000", 10)?; // 5 gwei (reasonable for Sepolia)  
-    let priority_fee = U256::from_str_radix("200000000", 10)?; // 0.2 gwei (above 0.1 gwei minimum)
+    out!(
+        "⛽ {} re-sent with maxFeePerGas {} wei as {}",
+        record.user_op_hash, signed.user_op.max_fee_per_gas, user_op_hash
+    );
+    let mut replacement = OperationRecord::pending(user_op_hash.clone(), record.chain_id, record.sender, &record.kind, record.tag.clone(), false);
+    replacement.user_op = Some(signed.user_op);
+    journal.append(replacement.clone())?;
+    journal.mark_replaced(&record.user_op_hash, &user_op_hash)?;
+    Ok(Some(replacement))
+}
+
+/// Show gas usage and cost aggregated per group and period, optionally written to a CSV file
+fn show_gas_report(by: journal::ReportBy, period: journal::ReportPeriod, csv: Option<&Path>) -> Result<()> {
+    let records = Journal::open_default()?.load()?;
+    let report = journal::gas_report(&records, by, period);
     
-    let mut user_op_request = UserOperationBuilder::new(
-        target_addr,
-        value_amount,
-        call_data_bytes
-    )
-    .with_gas_fees(max_fee, priority_fee)
-    .build();
+    out!("⛽ Gas report by {}", by);
+    out!("====================");
     
-    let smart_provider = SmartAccountProvider::new(provider, simple_account);
+    if report.is_empty() {
+        out!("No operations recorded");
+        return Ok(());
+    }
     
-    // Fill UserOperation fields first to get gas estimates
-    println!("🔧 Filling UserOperation fields...");
-    println!("💰 Using gas fees - Max: {} gwei, Priority: {} gwei", 
-             max_fee / U256::from(1_000_000_000u64),
-             priority_fee / U256::from(1_000_000_000u64));
-        smart_provider.fill_user_operation(&mut user_op_request).await?;
-
-    // Ensure pre_verification_gas meets bundler minimum requirements
-    if let Some(pre_verification_gas) = user_op_request.pre_verification_gas {
-        if pre_verification_gas < U256::from(46_000) {
-            user_op_request.pre_verification_gas = Some(U256::from(46_000));
-            println!("🔧 Increased pre_verification_gas to 46,000 to meet bundler minimum");
+    let mut current_period = None;
+    for row in &report {
+        if row.period.is_some() && row.period != current_period {
+            out!("📅 {}", row.period.as_deref().unwrap_or_default());
+            current_period = row.period.clone();
         }
+        out!("🏷️  {}", row.group);
+        out!("  Operations: {} ({} sponsored, {} pending)", row.operations, row.sponsored_operations, row.pending_operations);
+        out!("  Gas used: {}", row.total_gas_used);
+        out!("  Gas cost: {} ETH ({} ETH sponsored)", units::format_units(row.total_gas_cost, 18), units::format_units(row.sponsored_gas_cost, 18));
     }
     
-    // Let bundler estimate gas automatically for better efficiency
-    // Removed manual gas limit override to improve gas efficiency ratio
+    if let Some(file) = csv {
+        std::fs::write(file, journal::report_csv(&report))?;
+        out!("📤 Wrote the report to {}", file.display());
+    }
+    Ok(())
+}
 
-    // Request paymaster sponsorship AFTER all gas adjustments are finalized
-    println!("💰 Requesting paymaster sponsorship...");
-    let paymaster_service = paymaster::PaymasterService::new(
-        paymaster_url.to_string(),
-        paymaster_api_key.to_string(),
-        paymaster_addr,
-    );
-    
-    let valid_until = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() + 3600; // 1 hour from now
-    let paymaster_config = paymaster_service
-        .request_sponsorship(&user_op_request, valid_until, Some(0))
-        .await?;
-    
-    // Add paymaster data to UserOperation AFTER filling
-    let paymaster_and_data = paymaster_service.build_paymaster_and_data(&paymaster_config);
-    println!("💡 Paymaster data generated: 0x{}", hex::encode(&paymaster_and_data));
-    
-    // CRITICAL: Set paymaster data AFTER fill_user_operation to prevent it being overwritten!
-    user_op_request.paymaster_data = Some(paymaster_and_data.clone());
-    
-    // CRITICAL: Set the paymaster address directly (do not derive from paymaster_data)
-    user_op_request.paymaster = Some(paymaster_addr);
-    println!("🔧 Set paymaster address: {}", paymaster_addr);
-    
-    // Let bundler estimate paymaster gas automatically for better efficiency
-    // Removed manual paymaster gas limit overrides to improve gas efficiency ratio
-    
-    // FORCE: Clear the default empty paymaster data and set ours
-    println!("🔧 Overriding aa-sdk-rs default paymaster behavior...");
-    
-    // Debug: Verify the paymaster data is actually set
-    println!("🔍 Debug - UserOperation paymaster_data field: {:?}", 
-             user_op_request.paymaster_data.as_ref().map(|d| format!("0x{}", hex::encode(d))));
-    
-    // Debug: Print the full UserOperation structure
-    println!("🔍 Debug - Full UserOperation before submission:");
-    println!("  sender: {:?}", user_op_request.sender);
-    println!("  nonce: {:?}", user_op_request.nonce);
-    println!("  paymaster_data: {:?}", user_op_request.paymaster_data.as_ref().map(|d| hex::encode(d)));
-    println!("  max_fee_per_gas: {:?}", user_op_request.max_fee_per_gas);
-    println!("  max_priority_fee_per_gas: {:?}", user_op_request.max_priority_fee_per_gas);
-    
-    println!("✅ Paymaster sponsorship obtained!");
-    println!("📋 Paymaster data configured - gas will be sponsored");
-    
-    // Submit the sponsored UserOperation
-    println!("🚀 Submitting sponsored UserOperation...");
-    match smart_provider.send_user_operation(user_op_request, wallet.signer()).await {
-        Ok(user_op_hash) => {
-            println!("✅ Sponsored transaction submitted successfully!");
-            println!("UserOperation Hash: {:?}", user_op_hash);
-            println!("💰 Gas fees are being sponsored by the paymaster!");
-            
-            // Track execution status
-            println!("📋 Checking transaction execution status...");
-            //tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-            
-            match smart_provider.get_user_operation_receipt(user_op_hash).await {
-                Ok(Some(receipt)) => {
-                    println!("✅ Sponsored transaction executed successfully!");
-                    println!("📋 Receipt: {:?}", receipt);
-                    println!("🎉 Gas-free transaction completed!");
-                }
-                Ok(None) => {
-                    println!("⏳ Transaction still pending...");
-                    println!("💡 Check status later with hash: {:?}", user_op_hash);
-                }
-                Err(e) => {
-                    println!("⚠️  Could not verify execution status: {}", e);
-                    println!("💡 Operation may still have succeeded");
-                }
-            }
-        }
-        Err(e) => {
-            println!("❌ Sponsored transaction failed: {}", e);
-            println!("🔍 Possible causes:");
-            println!("  1. Paymaster service rejected the sponsorship");
-            println!("  2. Smart account not properly deployed");
-            println!("  3. Invalid paymaster configuration");
-            println!("  4. Bundler connectivity issues");
+/// Write the journal to `file` for another machine or for accounting
+fn export_journal(file: &Path, format: Option<journal::ExportFormat>) -> Result<()> {
+    let records = Journal::open_default()?.load()?;
+    let format = format.unwrap_or_else(|| journal::ExportFormat::from_path(file));
+    std::fs::write(file, journal::export(&records, format)?)?;
+    
+    let pending = records.iter().filter(|r| r.status == journal::OperationStatus::Pending).count();
+    out!("📤 Exported {} operations ({} pending) as {} to {}", records.len(), pending, format, file.display());
+    Ok(())
+}
+
+/// Merge a JSON export into the local journal
+fn import_journal(file: &Path) -> Result<()> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", file.display(), e))?;
+    let records = journal::parse_export(&contents)?;
+    let journal = Journal::open_default()?;
+    let summary = journal.import(records)?;
+    
+    out!("📥 Imported {} into {}", file.display(), journal.path().display());
+    out!("  Added: {} | Updated: {} | Unchanged: {}", summary.added, summary.updated, summary.unchanged);
+    Ok(())
+}
+
+/// Recommend fee caps and a sponsorship window for `chain_id`
+/// Inclusion delays come from the local journal, so the window falls back to one hour until operations have landed
+async fn show_fee_advice(rpc_url: &str, chain_id: u64, blocks: u64) -> Result<()> {
+    let provider = providers::http(rpc_url)?;
+    
+    let fee_summary = fees::fee_history(&provider, blocks).await?;
+    let records = Journal::open_default()?.load()?;
+    let delays = fees::inclusion_delays(&records, chain_id);
+    let advice = fees::recommend(&fee_summary, &delays);
+    
+    out!("📈 Fee history over the last {} blocks (chain {})", fee_summary.blocks, chain_id);
+    out!("  Base fee: latest {} gwei, median {} gwei, p90 {} gwei",
+         fees::format_gwei(fee_summary.latest_base_fee),
+         fees::format_gwei(fee_summary.median_base_fee),
+         fees::format_gwei(fee_summary.p90_base_fee));
+    out!("  Priority fee: median {} gwei", fees::format_gwei(fee_summary.median_priority_fee));
+    
+    match (fees::percentile(&delays, 50), fees::percentile(&delays, 95)) {
+        (Some(median), Some(slow)) => {
+            out!("⏱️  Inclusion delay over {} journaled operations: median {}s, p95 {}s", delays.len(), median, slow);
         }
+        _ => out!("⏱️  No included operations journaled for chain {}, using the default window", chain_id),
     }
     
+    out!();
+    out!("💡 Recommended settings:");
+    out!("  --max-fee-per-gas {} --max-priority-fee-per-gas {}", advice.max_fee_per_gas, advice.max_priority_fee_per_gas);
+    out!("  ({} gwei / {} gwei)", fees::format_gwei(advice.max_fee_per_gas), fees::format_gwei(advice.max_priority_fee_per_gas));
+    out!("  --valid-for {} for submit-sponsored and deploy-sponsored", advice.valid_for);
+    
     Ok(())
 }
 
-/// Deploy a sponsored smart account where deployment gas is paid by paymaster
-async fn deploy_sponsored_smart_account(
-    private_key: &str,
-    factory: &str,
-    salt: &str,
-    rpc_url: &str,
-    chain_id: u64,
-    paymaster_url: &str,
-    paymaster_api_key: &str,
-    paymaster_address: &str,
-) -> Result<()> {
-    println!("🎉 Deploying sponsored smart account via paymaster...");
+/// Print the userOpHash of an operation file and the EIP-191 digest its owner signs
+fn show_user_op_hash(file: &Path, entry_point: Address, chain_id: u64) -> Result<()> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Cannot read UserOperation {}: {}", file.display(), e))?;
+    let user_op = multisig::UnsignedUserOperation::try_from(&userop::RpcUserOperation::from_json(&contents)?)?;
+    let user_op_hash = user_op.hash(entry_point, chain_id);
+    
+    out!("Sender: {} | Nonce: {}", user_op.sender, user_op.nonce);
+    out!("EntryPoint: {} | Chain: {}", entry_point, chain_id);
+    out!("UserOperation Hash: {}", user_op_hash);
+    out!("Signed digest (EIP-191): {}", alloy::primitives::eip191_hash_message(user_op_hash));
     
-    // Setup
+    Ok(())
+}
+
+/// Walk through network, account, call and sponsorship, show what it costs, then send it and follow the receipt
+async fn run_tui(config: &CliConfig, private_key: &str) -> Result<()> {
+    let mut prompt = tui::Prompter::terminal();
     let wallet = Wallet::from_hex(private_key)?;
-    let factory_addr = Address::from_str(factory)?;
-    let paymaster_addr = Address::from_str(paymaster_address)?;
     
-    println!("🔧 Configuration:");
-    println!("  Factory: {}", factory_addr);
-    println!("  Paymaster: {}", paymaster_addr);
-    println!("  Owner: {}", wallet.address());
-    println!("  Paymaster service: {}", paymaster_url);
-    
-    // Parse salt
-    let salt_bytes = if salt.starts_with("0x") {
-        hex::decode(&salt[2..])?
+    prompt.heading("Network")?;
+    let networks = config.networks();
+    let names: Vec<String> = networks.iter().map(|n| format!("{} (chain {})", n.name, n.chain_id)).collect();
+    let network = &networks[prompt.choose("Network", &names, 0)?];
+    let rpc_url = if network.rpc_url_template.contains("{api_key}") {
+        let answer = prompt.ask("API key, or a full RPC URL", None)?;
+        if answer.starts_with("http") { answer } else { network.rpc_url_template.replace("{api_key}", &answer) }
     } else {
-        hex::decode(salt)?
+        prompt.ask("RPC URL", Some(&network.rpc_url_template))?
+    };
+    verify_chain_id(&rpc_url, network.chain_id).await?;
+    
+    prompt.heading("Account")?;
+    let default_factory = network.factory.map(|f| f.to_string());
+    let factory = prompt.ask_parsed("Factory", default_factory.as_deref(), |a| Ok(Address::from_str(a)?))?;
+    let salt = prompt.ask_parsed("Salt", Some("0x00"), |a| Ok(hexutil::parse_salt(a).map(|_| a.to_string())?))?;
+    let bundler_client = bundler_at(rpc_url.clone(), network.entry_point, U256::from(network.chain_id));
+    let account_addr = bundler_client.get_predicted_address(factory, wallet.address(), hexutil::parse_salt(&salt)?).await?;
+    let provider = providers::http(&rpc_url)?;
+    let balance = provider.get_balance(account_addr).await?;
+    out!("Owner {} controls {} ({} ETH)", wallet.address(), account_addr, units::format_units(balance, 18));
+    
+    prompt.heading("Call")?;
+    let target = prompt.ask_parsed("Target address", None, |a| Ok(Address::from_str(a)?))?;
+    let kinds = ["ETH transfer only".to_string(), "Function call (ABI encoded for you)".to_string(), "Raw call data".to_string()];
+    let call_data = match prompt.choose("What to call", &kinds, 0)? {
+        0 => Bytes::new(),
+        1 => {
+            let signature = prompt.ask("Function signature, e.g. transfer(address,uint256)", None)?;
+            let args = prompt.ask_list("Arguments in Solidity literal syntax")?;
+            calldata::encode(&signature, &args)?
+        }
+        _ => prompt.ask_parsed("Call data (hex)", None, |a| Ok(hexutil::decode_bytes("call data", a)?))?,
+    };
+    let value = prompt.ask_parsed("Value (0.1eth, 20gwei, wei)", Some("0"), units::parse_value)?;
+    
+    prompt.heading("Gas")?;
+    let sponsored = prompt.confirm("Have a paymaster pay for gas", false)?;
+    let (paymaster_url, paymaster_api_key, paymaster_address) = if sponsored {
+        (
+            prompt.ask("Paymaster service URL", Some("http://localhost:3000"))?,
+            prompt.ask("Paymaster API key", None)?,
+            prompt.ask_parsed("Paymaster contract", None, |a| Ok(Address::from_str(a)?))?.to_string(),
+        )
+    } else {
+        ("http://localhost:3000".to_string(), String::new(), Address::ZERO.to_string())
     };
     
-    let mut salt_array = [0u8; 32];
-    let start_idx = 32usize.saturating_sub(salt_bytes.len());
-    salt_array[start_idx..].copy_from_slice(&salt_bytes[..32.min(salt_bytes.len())]);
-    let salt_u256 = U256::from_be_bytes(salt_array);
-    
-    // Get predicted address
-    let bundler_client = BundlerClient::new(
-        rpc_url.to_string(),
-        Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?,
-        U256::from(chain_id),
-    );
-    
-    let predicted_address = bundler_client.get_predicted_address(factory_addr, wallet.address(), salt_u256).await?;
-    println!("📍 Predicted smart account address: {}", predicted_address);
-    println!("💰 Deployment will be sponsored by paymaster - no ETH required!");
-    
-    // Setup aa-sdk-rs
-    let url = url::Url::parse(rpc_url)?;
-    let provider = ProviderBuilder::new().on_http(url);
-    
-    let entry_point_addr = Address::from_str("0x0000000071727De22E5E9d8BAf0edAc6f37da032")?;
-    // TODO: Migrate to SimpleAccount::new_with_salt for better salt support
-    let simple_account = SimpleAccount::new(
-        Arc::new(provider.clone()),
-        wallet.address(),
-        factory_addr,
-        entry_point_addr,
-        chain_id,
-    );
-    
-    let smart_provider = SmartAccountProvider::new(provider, simple_account);
+    prompt.heading("Summary")?;
+    let estimate = fees::estimate(&provider, fees::urgency()).await?;
+    out!("Network:  {} via {}", network.name, rpc_url);
+    out!("Account:  {}", account_addr);
+    out!("Call:     {} with {} bytes of call data, {} ETH", target, call_data.len(), units::format_units(value, 18));
+    out!("Fees:     max {} gwei, tip {} gwei ({})",
+        fees::format_gwei(estimate.max_fee_per_gas.saturating_to()),
+        fees::format_gwei(estimate.max_priority_fee_per_gas.saturating_to()),
+        fees::urgency().name());
+    if sponsored {
+        out!("Gas paid: by the paymaster {}", paymaster_address);
+    } else {
+        out!("Gas paid: by the account, gas limits are estimated by the bundler when sending");
+    }
+    if value > balance {
+        out!("⚠️  The account holds less than the value sent");
+    }
+    out!("Same operation as a command:");
+    out!("  aa-client submit --target {} --call-data 0x{} --value {} --salt {} --factory {} --rpc-url {} --chain-id {}{}",
+        target, hex::encode(&call_data), value, salt, factory, rpc_url, network.chain_id,
+        if sponsored { format!(" --sponsored --paymaster-address {} --paymaster-url {}", paymaster_address, paymaster_url) } else { String::new() });
+    if !prompt.confirm("Send it", true)? {
+        out!("Nothing was sent");
+        return Ok(());
+    }
     
-    // Create deployment UserOperation
-    println!("🔧 Creating sponsored deployment UserOperation...");
-    let mut user_op_request = UserOperationBuilder::new(
-        predicted_address,
-        U256::ZERO,
-        Bytes::new()
-    ).build();
+    let args = SubmitArgs {
+        private_key: None,
+        factory: factory.to_string(),
+        salt,
+        rpc_url: rpc_url.clone(),
+        chain_id: network.chain_id,
+        nonce_key: None,
+        max_fee_per_gas: (!sponsored).then(|| estimate.max_fee_per_gas.to_string()),
+        max_priority_fee_per_gas: (!sponsored).then(|| estimate.max_priority_fee_per_gas.to_string()),
+        sponsored,
+        paymaster_url,
+        paymaster_api_key,
+        preauth_token: None,
+        paymaster_address,
+        paymaster_encoding: paymaster::WireFormat::Json,
+        valid_for: 3600,
+        tag: Some("tui".to_string()),
+    };
+    if let Some(user_op_hash) = submit_call(&args, private_key, &target.to_string(), &call_data, &value.to_string()).await? {
+        wait_for_receipt(&rpc_url, &user_op_hash, 120).await?;
+    }
     
-    // Set explicit gas fees to meet bundler requirements
-    let max_fee = U256::from_str_radix("5000000000", 10)?; // 5 gwei
-    let priority_fee = U256::from_str_radix("200000000", 10)?; // 0.2 gwei (above 0.1 gwei minimum)
+    Ok(())
+}
+
+/// Sign an EIP-191 or EIP-712 digest with the owner key
+/// With --salt the signature is for the owner's smart account, wrapped for ERC-6492 until it is deployed
+async fn sign_off_chain(wallet: &Wallet, digest: alloy::primitives::B256, account: &SignatureAccountArgs) -> Result<()> {
+    out!("Signer: {}", wallet.address());
+    out!("Digest: {}", digest);
+    
+    let Some(salt) = &account.salt else {
+        out!("Signature: {}", message::sign_digest(wallet, digest)?);
+        return Ok(());
+    };
     
-    user_op_request.max_fee_per_gas = Some(max_fee);
-    user_op_request.max_priority_fee_per_gas = Some(priority_fee);
+    let salt_u256 = hexutil::parse_salt(salt)?;
+    let factory_addr = Address::from_str(&account.factory)?;
+    let bundler_client = bundler_at(account.rpc_url.clone(), Address::ZERO, U256::ZERO);
+    let account_addr = bundler_client.get_predicted_address(factory_addr, wallet.address(), salt_u256).await?;
     
-    println!("💰 Using gas fees - Max: {} gwei, Priority: {} gwei", 
-             max_fee / U256::from(1_000_000_000u64),
-             priority_fee / U256::from(1_000_000_000u64));
+    let (deployed, signature) = account_signature(wallet, digest, account_addr, factory_addr, salt_u256, &account.rpc_url).await?;
+    if deployed {
+        out!("Account: {} (deployed, verifiable with isValidSignature)", account_addr);
+    } else {
+        out!("Account: {} (not deployed, signature wrapped for ERC-6492)", account_addr);
+    }
+    out!("Signature: {}", signature);
     
-    // Fill UserOperation fields
-    smart_provider.fill_user_operation(&mut user_op_request).await?;
+    Ok(())
+}
+
+/// Owner signature of `digest` for a smart account, wrapped for ERC-6492 while the account is not deployed
+/// Returns whether the account is deployed along with the signature
+async fn account_signature(
+    wallet: &Wallet,
+    digest: alloy::primitives::B256,
+    account: Address,
+    factory_addr: Address,
+    salt: U256,
+    rpc_url: &str,
+) -> Result<(bool, Bytes)> {
+    let signature = message::sign_digest(wallet, digest)?;
+    if providers::registry().has_code(rpc_url, account).await? {
+        return Ok((true, signature));
+    }
+    let factory_data = factory::resolve(factory::FactoryAbi::Simple).encoding().create_account_call(&[wallet.address()], salt)?;
+    Ok((false, message::wrap_erc6492(factory_addr, factory_data, signature)))
+}
+
+/// The smart account answering dApp requests, over WalletConnect or the local JSON-RPC server
+struct DappSigner<'a> {
+    submit: &'a SubmitArgs,
+    wallet: Wallet,
+    private_key: String,
+    account: Address,
+    factory: Address,
+    salt: U256,
+    /// Seconds to wait for a transaction's receipt, 0 to answer with the operation hash right away
+    wait: u64,
+    /// Requests go through without asking (--yes)
+    confirmed: bool,
+}
+
+impl<'a> DappSigner<'a> {
+    async fn new(submit: &'a SubmitArgs, wait: u64, confirmed: bool) -> Result<Self> {
+        let private_key = private_key_arg(&submit.private_key)?;
+        let wallet = Wallet::from_hex(&private_key)?;
+        let (factory, salt) = (Address::from_str(&submit.factory)?, hexutil::parse_salt(&submit.salt)?);
+        let account = bundler_at(submit.rpc_url.clone(), Address::ZERO, U256::from(submit.chain_id))
+            .get_predicted_address(factory, wallet.address(), salt)
+            .await?;
+        Ok(DappSigner { submit, wallet, private_key, account, factory, salt, wait, confirmed })
+    }
     
-    // Ensure pre_verification_gas meets bundler minimum requirements (DEPLOYMENT FUNCTION)
-    if let Some(pre_verification_gas) = user_op_request.pre_verification_gas {
-        if pre_verification_gas < U256::from(46_000) {
-            user_op_request.pre_verification_gas = Some(U256::from(46_000));
-            println!("🔧 Increased pre_verification_gas to 46,000 to meet bundler minimum");
+    /// Show the request and have it confirmed on the terminal unless --yes was given
+    fn confirm(&self, request: &walletconnect::DappRequest) -> Result<(), walletconnect::Rejection> {
+        if request.address() != self.account {
+            return Err(walletconnect::Rejection::invalid_params(format!("{} is not the connected account {}", request.address(), self.account)));
+        }
+        let description = match request {
+            walletconnect::DappRequest::SendTransaction { to, value, data, .. } => {
+                format!("Send {} ETH to {} with {} bytes of call data?", units::format_units(*value, 18), to, data.len())
+            }
+            walletconnect::DappRequest::PersonalSign { message, .. } => {
+                format!("Sign the message \"{}\"?", String::from_utf8_lossy(message))
+            }
+            walletconnect::DappRequest::SignTypedData { .. } => "Sign typed data?".to_string(),
+        };
+        out!("📨 {}", description);
+        if !self.confirmed && !tui::Prompter::terminal().confirm(&description, false)? {
+            return Err(walletconnect::Rejection::user_rejected());
         }
+        Ok(())
     }
     
-    // Let bundler estimate gas automatically for better efficiency
-    // Removed manual gas limit override to improve gas efficiency ratio
-    
-    // Request paymaster sponsorship for deployment AFTER all gas adjustments are finalized
-    println!("💰 Requesting paymaster sponsorship for deployment...");
-    let paymaster_service = paymaster::PaymasterService::new(
-        paymaster_url.to_string(),
-        paymaster_api_key.to_string(),
-        paymaster_addr,
-    );
-    
-    let valid_until = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() + 3600; // 1 hour from now
-    let paymaster_config = paymaster_service
-        .request_sponsorship(&user_op_request, valid_until, Some(0))
-        .await?;
-    
-    // Add paymaster data to UserOperation
-    let paymaster_and_data = paymaster_service.build_paymaster_and_data(&paymaster_config);
-    println!("💡 Paymaster data generated: 0x{}", hex::encode(&paymaster_and_data));
+    /// Submit a transaction request as a UserOperation and return its hash
+    async fn send(&self, request: walletconnect::DappRequest) -> Result<String, walletconnect::Rejection> {
+        self.confirm(&request)?;
+        let walletconnect::DappRequest::SendTransaction { to, value, data, .. } = request else {
+            return Err(walletconnect::Rejection::invalid_params("not a transaction"));
+        };
+        let pending = submit_call(self.submit, &self.private_key, &to.to_string(), &data, &value.to_string()).await?;
+        pending.ok_or_else(|| anyhow::anyhow!("Dry run, nothing was sent").into())
+    }
     
-    // CRITICAL: Set paymaster data AFTER fill_user_operation to prevent it being overwritten!
-    user_op_request.paymaster_data = Some(paymaster_and_data.clone());
+    /// Answer a request: transactions with the hash of the bundle transaction, signatures with the account signature
+    async fn answer(&self, request: walletconnect::DappRequest) -> Result<serde_json::Value, walletconnect::Rejection> {
+        let digest = match &request {
+            walletconnect::DappRequest::SendTransaction { .. } => {
+                let user_op_hash = self.send(request).await?;
+                // dApps look the answer up with eth_getTransactionReceipt, so prefer the bundle transaction
+                let hash = match self.wait {
+                    0 => user_op_hash,
+                    _ => match wait_for_receipt(&self.submit.rpc_url, &user_op_hash, self.wait).await {
+                        Ok(receipt) => receipt.receipt.map(|tx| tx.transaction_hash.to_string()).unwrap_or(user_op_hash),
+                        Err(e) if e.is::<exit::ReceiptTimeout>() => user_op_hash,
+                        Err(e) => return Err(e.into()),
+                    },
+                };
+                return Ok(serde_json::json!(hash));
+            }
+            walletconnect::DappRequest::PersonalSign { message, .. } => message::message_hash(message),
+            walletconnect::DappRequest::SignTypedData { json, .. } => message::typed_data_hash(json)?,
+        };
+        self.confirm(&request)?;
+        let (_, signature) = account_signature(&self.wallet, digest, self.account, self.factory, self.salt, &self.submit.rpc_url).await?;
+        out!("✍️  Signed {}", digest);
+        Ok(serde_json::json!(signature.to_string()))
+    }
+}
+
+/// Pair with a dApp over WalletConnect, then send its transactions as UserOperations and sign its messages as the account
+/// Runs until the dApp disconnects or Ctrl-C ends the session
+async fn connect_dapp(submit: &SubmitArgs, uri: &str, project_id: Option<&str>, relay_url: &str, wait: u64, confirmed: bool) -> Result<()> {
+    let uri: walletconnect::PairingUri = uri.parse()?;
+    let project_id = match project_id {
+        Some(project_id) => project_id.to_string(),
+        None => std::env::var("WALLETCONNECT_PROJECT_ID")
+            .map_err(|_| anyhow::anyhow!("The relay needs a WalletConnect Cloud project id: pass --project-id or set WALLETCONNECT_PROJECT_ID"))?,
+    };
+    let signer = DappSigner::new(submit, wait, confirmed).await?;
     
-    // CRITICAL: Set the paymaster address directly (do not derive from paymaster_data)
-    user_op_request.paymaster = Some(paymaster_addr);
-    println!("🔧 Set paymaster address: {}", paymaster_addr);
+    out!("🔗 Pairing through {}", relay_url);
+    let mut pairing = walletconnect::Pairing::open(relay_url, &project_id, uri).await?;
+    let proposal = pairing.proposal().await?;
+    out!("📨 {} ({}) asks to connect", proposal.dapp.name, proposal.dapp.url);
     
-    // Let bundler estimate paymaster gas automatically for better efficiency (DEPLOYMENT FUNCTION)
-    // Removed manual paymaster gas limit overrides to improve gas efficiency ratio
+    if !proposal.supports_chain(submit.chain_id) {
+        let chains = proposal.required_chains.join(", ");
+        pairing.reject(&proposal, walletconnect::Rejection::unsupported_chain(&chains)).await?;
+        return Err(anyhow::anyhow!("The dApp requires {}, the account is on eip155:{}", chains, submit.chain_id));
+    }
+    if !proposal.unsupported_methods.is_empty() {
+        out!("⚠️  Requests for {} will be refused", proposal.unsupported_methods.join(", "));
+    }
+    let question = format!("Connect account {} on chain {}?", signer.account, submit.chain_id);
+    if !confirmed && !tui::Prompter::terminal().confirm(&question, false)? {
+        pairing.reject(&proposal, walletconnect::Rejection::user_rejected()).await?;
+        out!("❌ Connection rejected");
+        return Ok(());
+    }
     
-    println!("🔧 Overriding aa-sdk-rs default paymaster behavior...");
+    let session = pairing.approve(proposal, signer.account, submit.chain_id).await?;
+    out!("✅ Connected to {} as {}, Ctrl-C disconnects", session.dapp.name, signer.account);
+    let dapp_name = session.dapp.name.clone();
+    session.serve(|request| signer.answer(request)).await?;
     
-    println!("✅ Deployment sponsorship approved!");
-    println!("📋 Paymaster will cover all deployment costs");
+    out!("👋 Session with {} ended", dapp_name);
+    Ok(())
+}
+
+/// Serve the smart account as an unlocked node on localhost: account methods and transactions are answered
+/// here, everything else by the node at --rpc-url
+async fn serve_rpc(submit: &SubmitArgs, port: u16, wait: u64, confirmed: bool) -> Result<()> {
+    let signer = DappSigner::new(submit, wait, confirmed).await?;
+    let provider = providers::http(&submit.rpc_url)?;
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot listen on 127.0.0.1:{}: {}", port, e))?;
+    out!("🔌 Serving {} (chain {}) on http://127.0.0.1:{}", signer.account, submit.chain_id, port);
+    out!("💡 cast send --rpc-url http://127.0.0.1:{} --unlocked --from {} ...", port, signer.account);
+    
+    let (signer, provider) = (&signer, &provider);
+    rpc_server::serve(listener, move |method, params| async move {
+        let chain_id = submit.chain_id;
+        match rpc_server::route(&method, &params)? {
+            rpc_server::Call::Accounts => Ok(serde_json::json!([signer.account])),
+            rpc_server::Call::ChainId => Ok(serde_json::json!(format!("{:#x}", chain_id))),
+            rpc_server::Call::Dapp(request) => signer.answer(request).await,
+            rpc_server::Call::SendCalls(request) => Ok(serde_json::json!({ "id": signer.send(request).await? })),
+            rpc_server::Call::CallsStatus(id) => {
+                // EIP-5792 statuses: 100 pending, 200 included, 500 reverted
+                let (status, receipts) = match stuck::get_receipt(provider, &id).await? {
+                    None => (100, Vec::new()),
+                    Some(receipt) => (
+                        if receipt.success { 200 } else { 500 },
+                        vec![serde_json::json!({
+                            "status": if receipt.success { "0x1" } else { "0x0" },
+                            "gasUsed": format!("{:#x}", receipt.actual_gas_used),
+                            "transactionHash": receipt.receipt.map(|tx| tx.transaction_hash),
+                        })],
+                    ),
+                };
+                Ok(serde_json::json!({
+                    "version": "2.0.0",
+                    "id": id,
+                    "chainId": format!("{:#x}", chain_id),
+                    "status": status,
+                    "atomic": true,
+                    "receipts": receipts,
+                }))
+            }
+            rpc_server::Call::Capabilities => Ok(serde_json::json!({
+                (format!("{:#x}", chain_id)): {
+                    "paymasterService": { "supported": submit.sponsored },
+                    "atomic": { "status": "unsupported" },
+                }
+            })),
+            rpc_server::Call::SwitchChain(chain) if chain == chain_id => Ok(serde_json::Value::Null),
+            rpc_server::Call::SwitchChain(chain) => {
+                Err(walletconnect::Rejection { code: 4902, message: format!("Chain {} is not served here, only {}", chain, chain_id) })
+            }
+            rpc_server::Call::Forward => rpc_server::forward(provider, &method, &params).await,
+        }
+    }).await
+}
+
+/// Print the calldata breakdown of an operation file, its L1 data cost when a base fee is known, and suggestions
+async fn analyze_user_operation(file: &Path, l1_base_fee: Option<&str>, rpc_url: Option<&str>) -> Result<()> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Cannot read UserOperation {}: {}", file.display(), e))?;
+    let rpc_op = userop::RpcUserOperation::from_json(&contents)?;
+    let user_op = multisig::UnsignedUserOperation::try_from(&rpc_op)?;
+    // An unsigned operation is measured with the placeholder gas would be estimated with
+    let signature = rpc_op.signature.clone().unwrap_or_else(|| dummy_signature::resolve(&dummy_signature::DummySignature::Ecdsa));
+    let packed = alloy::sol_types::SolValue::abi_encode(&preflight::pack(&user_op, signature.clone()));
+    let report = analyze::analyze(&user_op, &signature, &packed);
+    
+    out!("📐 Calldata of {} (nonce {})", user_op.sender, user_op.nonce);
+    for (name, counts) in &report.fields {
+        out!("  {:<17} {:>5} bytes ({} zero, {} non-zero) = {} gas", name, counts.len(), counts.zero, counts.non_zero, counts.calldata_gas());
+    }
+    out!("  {:<17} {:>5} bytes ({} zero, {} non-zero) = {} gas", "packed operation", report.packed.len(), report.packed.zero, report.packed.non_zero, report.packed.calldata_gas());
+    if rpc_op.signature.is_none() {
+        out!("  (unsigned, measured with a {}-byte placeholder signature)", signature.len());
+    }
     
-    // Submit sponsored deployment
-    println!("🚀 Submitting sponsored deployment...");
-    match smart_provider.send_user_operation(user_op_request, wallet.signer()).await {
-        Ok(user_op_hash) => {
-            println!("✅ Sponsored deployment initiated successfully!");
-            println!("UserOperation Hash: {:?}", user_op_hash);
-            println!("💰 Deployment costs are being sponsored!");
-            println!("📍 Account will be deployed at: {}", predicted_address);
-            println!();
-            println!("🎉 Your smart account is being deployed with zero gas fees!");
-            println!("💡 You can now use submit-sponsored to make gas-free transactions");
+    let l1_base_fee = match (l1_base_fee, rpc_url) {
+        (Some(gwei), _) => Some(units::parse_units(gwei, 9)?.saturating_to::<u128>()),
+        (None, Some(rpc_url)) => {
+            let provider = providers::http(rpc_url)?;
+            let fee = analyze::op_stack_l1_base_fee(&provider).await;
+            if fee.is_none() {
+                out!("💡 No OP Stack gas price oracle on {}, pass --l1-base-fee for the L1 data cost", rpc_url);
+            }
+            fee
         }
-        Err(e) => {
-            println!("❌ Sponsored deployment failed: {}", e);
-            println!("🔍 Possible causes:");
-            println!("  1. Paymaster service rejected the sponsorship");
-            println!("  2. Factory contract not accessible");
-            println!("  3. Invalid paymaster configuration");
-            println!("  4. Bundler connectivity issues");
+        (None, None) => None,
+    };
+    if let Some(l1_base_fee) = l1_base_fee {
+        out!("L1 data cost at {} gwei: up to {} ETH before batch compression",
+            fees::format_gwei(l1_base_fee), units::format_units(report.l1_data_cost(l1_base_fee), 18));
+    }
+    
+    if !report.suggestions.is_empty() {
+        out!("Suggestions:");
+        for suggestion in &report.suggestions {
+            out!("  • {}", suggestion);
         }
     }
     
     Ok(())
 }
 
-/// Show network presets and configuration
-fn show_network_presets() -> Result<()> {
-    println!("🌐 Supported Networks");
-    println!("=====================");
-    println!();
-    
-    let networks = list_supported_networks();
-    
-    for network in networks {
-        println!("📍 {} (Chain ID: {}):", network.name, network.chain_id);
-        println!("  EntryPoint: {}", network.entry_point);
-        println!("  Factory: {}", network.factory);
-        println!("  RPC Template: {}", network.rpc_url_template);
-        // Bundler URL is the same as RPC URL for simplicity
-        println!();
-    }
-    
-    println!("📋 Usage Examples:");
-    println!("  # Anvil (local)");
-    println!("  aa-client demo --yes");
-    println!();
-    println!("  # Sepolia testnet");
-    println!("  aa-client create --chain-id 11155111 --private-key YOUR_KEY --target 0x... --call-data 0x... --nonce 0");
-    println!();
-    println!("  # With custom RPC");
-    println!("  aa-client create --rpc-url https://eth-sepolia.g.alchemy.com/v2/YOUR_API_KEY --chain-id 11155111 ...");
+/// Ask a contract wallet whether it accepts a signature, for a raw hash or an EIP-191 message
+async fn verify_1271(contract: &str, hash: Option<&str>, message: Option<&str>, signature: &str, rpc_url: &str) -> Result<()> {
+    let contract_addr = Address::from_str(contract)?;
+    let hash = match hash {
+        Some(hash) => alloy::primitives::B256::from(hexutil::decode_fixed::<32>("hash", hash)?),
+        None => alloy::primitives::eip191_hash_message(message.unwrap_or_default()),
+    };
+    let signature = hexutil::decode_bytes("signature", signature)?;
+    
+    let provider = providers::http(rpc_url)?;
+    
+    out!("🔏 Checking signature with {}", contract_addr);
+    out!("Hash: {}", hash);
+    if !erc1271::is_valid_signature(&provider, contract_addr, hash, &signature).await? {
+        return Err(anyhow::anyhow!("{} rejected the signature for {}", contract_addr, hash));
+    }
+    out!("✅ Valid ERC-1271 signature");
     
     Ok(())
 }
@@ -0,0 +1,106 @@
+// Off-chain message signatures (EIP-191, EIP-712) from the owner key, for dApps that ask a smart account to sign
+// AAAccount.isValidSignature recovers the owner from the hash it is given, so the owner's signature over the
+// EIP-191 or EIP-712 digest is valid for the account as is. An account that is not deployed yet has no
+// isValidSignature to call, and its signature is wrapped for ERC-6492 so verifiers can deploy it in simulation.
+
+use alloy::dyn_abi::TypedData;
+use alloy::primitives::{b256, eip191_hash_message, Address, Bytes, B256};
+use alloy::signers::SignerSync;
+use alloy::sol_types::SolValue;
+use anyhow::Result;
+
+use crate::wallet::Wallet;
+
+/// Suffix marking an ERC-6492 wrapped signature
+pub const ERC6492_MAGIC: B256 = b256!("6492649264926492649264926492649264926492649264926492649264926492");
+
+/// Digest signed for a personal message (`personal_sign`)
+pub fn message_hash(message: &[u8]) -> B256 {
+    eip191_hash_message(message)
+}
+
+/// EIP-712 digest of typed data in the `eth_signTypedData_v4` JSON form (types, primaryType, domain, message)
+pub fn typed_data_hash(json: &str) -> Result<B256> {
+    let typed_data: TypedData = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid EIP-712 typed data: {}", e))?;
+    Ok(typed_data.eip712_signing_hash()?)
+}
+
+/// 65-byte owner signature over an EIP-191 or EIP-712 digest
+pub fn sign_digest(wallet: &Wallet, digest: B256) -> Result<Bytes> {
+    let signature = wallet.signer().sign_hash_sync(&digest)?;
+    Ok(Bytes::from(signature.as_bytes().to_vec()))
+}
+
+/// ERC-6492 signature of a counterfactual account: the factory call that deploys it, then the inner signature
+pub fn wrap_erc6492(factory: Address, factory_data: Bytes, signature: Bytes) -> Bytes {
+    let mut wrapped = (factory, factory_data, signature).abi_encode_params();
+    wrapped.extend_from_slice(ERC6492_MAGIC.as_slice());
+    wrapped.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::Signature;
+
+    #[test]
+    fn test_typed_data_hash() {
+        // The "Mail" example from EIP-712
+        let json = r#"{
+            "types": {
+                "EIP712Domain": [
+                    {"name": "name", "type": "string"},
+                    {"name": "version", "type": "string"},
+                    {"name": "chainId", "type": "uint256"},
+                    {"name": "verifyingContract", "type": "address"}
+                ],
+                "Person": [{"name": "name", "type": "string"}, {"name": "wallet", "type": "address"}],
+                "Mail": [
+                    {"name": "from", "type": "Person"},
+                    {"name": "to", "type": "Person"},
+                    {"name": "contents", "type": "string"}
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": {"name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"},
+                "to": {"name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"},
+                "contents": "Hello, Bob!"
+            }
+        }"#;
+        assert_eq!(
+            typed_data_hash(json).unwrap(),
+            b256!("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+        assert!(typed_data_hash("{}").is_err());
+    }
+
+    #[test]
+    fn test_message_signature_recovers_to_owner() {
+        let wallet = Wallet::new([0x11; 32]).unwrap();
+        let signature = sign_digest(&wallet, message_hash(b"hello")).unwrap();
+
+        let signature = Signature::try_from(signature.as_ref()).unwrap();
+        assert_eq!(signature.recover_address_from_msg(b"hello").unwrap(), wallet.address());
+    }
+
+    #[test]
+    fn test_wrap_erc6492() {
+        let factory = Address::from([7u8; 20]);
+        let wrapped = wrap_erc6492(factory, Bytes::from(vec![1, 2, 3]), Bytes::from(vec![0xaa; 65]));
+
+        assert!(wrapped.ends_with(ERC6492_MAGIC.as_slice()));
+        let (decoded_factory, factory_data, signature) =
+            <(Address, Bytes, Bytes)>::abi_decode_params(&wrapped[..wrapped.len() - 32], true).unwrap();
+        assert_eq!(decoded_factory, factory);
+        assert_eq!(factory_data, Bytes::from(vec![1, 2, 3]));
+        assert_eq!(signature.len(), 65);
+    }
+}
@@ -0,0 +1,521 @@
+// Offline signature collection for multi-owner accounts
+// An unsigned UserOperation is proposed to a JSON file, each owner appends a signature,
+// and the collected signatures are combined into one when submitting
+
+use alloy::primitives::{b256, keccak256, Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::signers::{Signature, SignerSync};
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::eip7702;
+use crate::entrypoint::{self, EntryPointVersion};
+use crate::remote_signer::RemoteSigner;
+use crate::userop::BuildContext;
+use crate::wallet::Wallet;
+
+sol!(
+    #[allow(missing_docs)]
+    interface IAAAccountExecute {
+        function execute(address target, uint256 value, bytes data) external;
+    }
+);
+
+/// EntryPoint v0.8 EIP-712 type of a UserOperation, bytes fields hashed
+const PACKED_USEROP_TYPEHASH: B256 = b256!("29a0bca4af4be3421398da00295e58e6d7de38cb492214754cb6a47507dd6f8e");
+const EIP712_DOMAIN_TYPEHASH: B256 = b256!("8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f");
+
+/// Length of a single ECDSA signature (r, s, v)
+pub const SIGNATURE_LENGTH: usize = 65;
+
+/// ERC-4337 v0.7 UserOperation in its RPC (unpacked) JSON form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedUserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory_data: Option<Bytes>,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_verification_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_post_op_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_data: Option<Bytes>,
+}
+
+/// Pack two 128-bit values into one word, high half first (accountGasLimits, gasFees)
+pub fn pack_u128_pair(high: U256, low: U256) -> B256 {
+    B256::from(((high << 128) | (low & U256::from(u128::MAX))).to_be_bytes::<32>())
+}
+
+impl UnsignedUserOperation {
+    /// UserOperation calling `execute(target, value, data)` on the account
+    pub fn execute(sender: Address, nonce: U256, target: Address, value: U256, data: Bytes) -> Self {
+        let call_data = IAAAccountExecute::executeCall { target, value, data }.abi_encode();
+        Self {
+            sender,
+            nonce,
+            factory: None,
+            factory_data: None,
+            call_data: call_data.into(),
+            call_gas_limit: U256::ZERO,
+            verification_gas_limit: U256::ZERO,
+            pre_verification_gas: U256::ZERO,
+            max_fee_per_gas: U256::ZERO,
+            max_priority_fee_per_gas: U256::ZERO,
+            paymaster: None,
+            paymaster_verification_gas_limit: None,
+            paymaster_post_op_gas_limit: None,
+            paymaster_data: None,
+        }
+    }
+
+    /// factory || factoryData, empty for a deployed account
+    pub fn init_code(&self) -> Vec<u8> {
+        match self.factory {
+            Some(factory) => {
+                let mut init_code = factory.to_vec();
+                init_code.extend_from_slice(self.factory_data.as_deref().unwrap_or_default());
+                init_code
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// paymaster || verification gas || postOp gas || paymasterData, empty when unsponsored
+    pub fn paymaster_and_data(&self) -> Vec<u8> {
+        match self.paymaster {
+            Some(paymaster) => {
+                let mut data = paymaster.to_vec();
+                let verification = self.paymaster_verification_gas_limit.unwrap_or_default().to::<u128>();
+                let post_op = self.paymaster_post_op_gas_limit.unwrap_or_default().to::<u128>();
+                data.extend_from_slice(&verification.to_be_bytes());
+                data.extend_from_slice(&post_op.to_be_bytes());
+                data.extend_from_slice(self.paymaster_data.as_deref().unwrap_or_default());
+                data
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// EntryPoint `getUserOpHash`: every owner signs this hash
+    /// v0.7 hashes the packed fields with the EntryPoint and chain ID, v0.8 makes them an EIP-712 digest
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> B256 {
+        let fields = (
+            self.sender,
+            self.nonce,
+            keccak256(self.init_code()),
+            keccak256(&self.call_data),
+            pack_u128_pair(self.verification_gas_limit, self.call_gas_limit),
+            self.pre_verification_gas,
+            pack_u128_pair(self.max_priority_fee_per_gas, self.max_fee_per_gas),
+            keccak256(self.paymaster_and_data()),
+        );
+
+        if entrypoint::version_of(entry_point) == Some(EntryPointVersion::V08) {
+            let struct_hash = keccak256([PACKED_USEROP_TYPEHASH.as_slice(), &fields.abi_encode()].concat());
+            let domain_separator = keccak256(
+                (EIP712_DOMAIN_TYPEHASH, keccak256("ERC4337"), keccak256("1"), U256::from(chain_id), entry_point).abi_encode(),
+            );
+            return keccak256([&[0x19, 0x01], domain_separator.as_slice(), struct_hash.as_slice()].concat());
+        }
+        keccak256((keccak256(fields.abi_encode()), entry_point, U256::from(chain_id)).abi_encode())
+    }
+}
+
+/// Signature contributed by one owner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnerSignature {
+    pub signer: Address,
+    pub signature: Bytes,
+}
+
+/// UserOperation awaiting owner signatures, as stored in the proposal file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proposal {
+    pub chain_id: u64,
+    pub entry_point: Address,
+    pub user_op: UnsignedUserOperation,
+    /// Hash the owners sign, kept for review before signing
+    pub user_op_hash: B256,
+    /// Inputs the operation was built from, so co-signers can rebuild and audit it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<BuildContext>,
+    /// Owner signatures the account needs, read when proposing; None collects from every owner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<usize>,
+    #[serde(default)]
+    pub signatures: Vec<OwnerSignature>,
+}
+
+impl Proposal {
+    pub fn new(user_op: UnsignedUserOperation, entry_point: Address, chain_id: u64) -> Self {
+        let user_op_hash = user_op.hash(entry_point, chain_id);
+        Self {
+            chain_id,
+            entry_point,
+            user_op,
+            user_op_hash,
+            context: None,
+            threshold: None,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Proposal built from a captured context, which is kept alongside the operation
+    pub fn from_context(context: BuildContext) -> Self {
+        let mut proposal = Self::new(context.build(), context.entry_point, context.chain_id);
+        proposal.context = Some(context);
+        proposal
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read proposal {}: {}", path.display(), e))?;
+        Self::from_json(&contents)
+    }
+
+    /// Parse and verify a proposal received from a file or a QR scan
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let proposal: Self = serde_json::from_str(contents)?;
+
+        // The stored hash is only informative, never trust it over the operation itself
+        let expected = proposal.user_op.hash(proposal.entry_point, proposal.chain_id);
+        if proposal.user_op_hash != expected {
+            return Err(anyhow::anyhow!(
+                "Proposal hash {} does not match its UserOperation ({}), the file was modified",
+                proposal.user_op_hash, expected
+            ));
+        }
+        if let Some(context) = &proposal.context {
+            let rebuilt = context.build();
+            if rebuilt != proposal.user_op || context.chain_id != proposal.chain_id || context.entry_point != proposal.entry_point {
+                return Err(anyhow::anyhow!("Proposal UserOperation does not match a rebuild from its context"));
+            }
+        }
+        Ok(proposal)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Take over signatures collected on another copy of the same proposal
+    pub fn merge(&mut self, other: Proposal) -> Result<()> {
+        if other.user_op_hash != self.user_op_hash {
+            return Err(anyhow::anyhow!(
+                "Cannot merge proposal {} into {}, they are different operations",
+                other.user_op_hash, self.user_op_hash
+            ));
+        }
+        for signature in other.signatures {
+            if !self.signatures.iter().any(|s| s.signer == signature.signer) {
+                self.signatures.push(signature);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sign the UserOperation hash (EIP-191) with an owner key, replacing any earlier signature from it
+    pub fn sign(&mut self, wallet: &Wallet) -> Result<()> {
+        let signature = wallet.signer().sign_message_sync(self.user_op_hash.as_slice())?;
+        self.add_signature(wallet.address(), signature);
+        Ok(())
+    }
+
+    /// Sign the UserOperation hash with an owner account held by a remote signer
+    pub async fn sign_remote(&mut self, signer: &RemoteSigner) -> Result<()> {
+        let signature = signer.sign_message(self.user_op_hash.as_slice()).await?;
+        self.add_signature(signer.address(), signature);
+        Ok(())
+    }
+
+    fn add_signature(&mut self, signer: Address, signature: Signature) {
+        self.signatures.retain(|s| s.signer != signer);
+        self.signatures.push(OwnerSignature {
+            signer,
+            signature: Bytes::from(signature.as_bytes().to_vec()),
+        });
+    }
+
+    /// Multi-owner signature encoding: 65-byte signatures concatenated in ascending signer order
+    pub fn combined_signature(&self) -> Result<Bytes> {
+        let mut signatures = self.signatures.clone();
+        signatures.sort_by_key(|s| s.signer);
+
+        let mut combined = Vec::with_capacity(signatures.len() * SIGNATURE_LENGTH);
+        for owner_signature in &signatures {
+            if owner_signature.signature.len() != SIGNATURE_LENGTH {
+                return Err(anyhow::anyhow!("Signature from {} is not {} bytes", owner_signature.signer, SIGNATURE_LENGTH));
+            }
+            combined.extend_from_slice(&owner_signature.signature);
+        }
+        Ok(combined.into())
+    }
+
+    /// Check that every collected signature recovers to the signer it claims and that signer is an owner
+    pub fn verify_signatures(&self, owners: &[Address]) -> Result<()> {
+        for owner_signature in &self.signatures {
+            if !owners.contains(&owner_signature.signer) {
+                return Err(anyhow::anyhow!("{} signed but is not an owner of {}", owner_signature.signer, self.user_op.sender));
+            }
+            let signature = Signature::try_from(owner_signature.signature.as_ref())
+                .map_err(|e| anyhow::anyhow!("Malformed signature from {}: {}", owner_signature.signer, e))?;
+            let recovered = signature.recover_address_from_msg(self.user_op_hash.as_slice())?;
+            if recovered != owner_signature.signer {
+                return Err(anyhow::anyhow!("Signature claimed by {} was made by {}", owner_signature.signer, recovered));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check every share before aggregation
+    /// The account only recovers ECDSA signatures, so a share from a contract owner is refused here
+    /// instead of the combined signature failing validation in the bundler
+    pub async fn verify_shares<P: Provider>(&self, provider: &P, owners: &[Address]) -> Result<()> {
+        for share in &self.signatures {
+            check_ecdsa_owner(share.signer, &provider.get_code_at(share.signer).await?)?;
+        }
+        self.verify_signatures(owners)
+    }
+
+    /// Whether enough signatures are collected to submit, never for a proposal without a threshold
+    pub fn threshold_met(&self) -> bool {
+        self.threshold.is_some_and(|threshold| self.signatures.len() >= threshold)
+    }
+
+    /// Whether `signer` may still add a signature: once the threshold is met only earlier signers may re-sign
+    pub fn accepts_signer(&self, signer: Address) -> bool {
+        !self.threshold_met() || self.signatures.iter().any(|s| s.signer == signer)
+    }
+
+    /// Owners who have not signed yet
+    pub fn missing_signers(&self, owners: &[Address]) -> Vec<Address> {
+        owners
+            .iter()
+            .filter(|owner| !self.signatures.iter().any(|s| s.signer == **owner))
+            .copied()
+            .collect()
+    }
+}
+
+/// Reject a signer holding contract code: AAAccount has no ERC-1271 path for owners
+/// An EOA delegated with EIP-7702 still signs with its key, so a delegation designator is fine
+fn check_ecdsa_owner(signer: Address, code: &[u8]) -> Result<()> {
+    if code.is_empty() || eip7702::delegated_to(code).is_some() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "{} is a contract, the account only accepts ECDSA signatures from EOA owners so its share cannot be combined; \
+         have an EOA owner sign instead",
+        signer
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn proposal() -> Proposal {
+        let user_op = UnsignedUserOperation::execute(
+            Address::from([9u8; 20]),
+            U256::from(3),
+            Address::from([5u8; 20]),
+            U256::ZERO,
+            Bytes::from(vec![0xde, 0xad]),
+        );
+        Proposal::new(user_op, Address::from([1u8; 20]), 31337)
+    }
+
+    #[test]
+    fn test_hash_binds_chain_and_entry_point() {
+        let p = proposal();
+        assert_ne!(p.user_op.hash(p.entry_point, 1), p.user_op_hash);
+        assert_ne!(p.user_op.hash(Address::ZERO, p.chain_id), p.user_op_hash);
+    }
+
+    #[test]
+    fn test_v08_hash_is_eip712() {
+        use alloy::sol_types::{eip712_domain, SolStruct};
+        sol! {
+            struct PackedUserOperation {
+                address sender;
+                uint256 nonce;
+                bytes initCode;
+                bytes callData;
+                bytes32 accountGasLimits;
+                uint256 preVerificationGas;
+                bytes32 gasFees;
+                bytes paymasterAndData;
+            }
+        }
+
+        let user_op = proposal().user_op;
+        let entry_point = EntryPointVersion::V08.address();
+        let typed = PackedUserOperation {
+            sender: user_op.sender,
+            nonce: user_op.nonce,
+            initCode: user_op.init_code().into(),
+            callData: user_op.call_data.clone(),
+            accountGasLimits: pack_u128_pair(user_op.verification_gas_limit, user_op.call_gas_limit),
+            preVerificationGas: user_op.pre_verification_gas,
+            gasFees: pack_u128_pair(user_op.max_priority_fee_per_gas, user_op.max_fee_per_gas),
+            paymasterAndData: user_op.paymaster_and_data().into(),
+        };
+        let domain = eip712_domain! { name: "ERC4337", version: "1", chain_id: 31337, verifying_contract: entry_point, };
+
+        assert_eq!(user_op.hash(entry_point, 31337), typed.eip712_signing_hash(&domain));
+        assert_ne!(user_op.hash(entry_point, 31337), user_op.hash(EntryPointVersion::V07.address(), 31337));
+    }
+
+    #[test]
+    fn test_signatures_combine_in_signer_order() {
+        let mut p = proposal();
+        let a = Wallet::new([0x11; 32]).unwrap();
+        let b = Wallet::new([0x22; 32]).unwrap();
+        p.sign(&a).unwrap();
+        p.sign(&b).unwrap();
+        p.sign(&a).unwrap();
+        assert_eq!(p.signatures.len(), 2);
+
+        let combined = p.combined_signature().unwrap();
+        assert_eq!(combined.len(), 2 * SIGNATURE_LENGTH);
+
+        let mut signers = vec![a.address(), b.address()];
+        signers.sort();
+        for (i, chunk) in combined.chunks(SIGNATURE_LENGTH).enumerate() {
+            let signature = Signature::try_from(chunk).unwrap();
+            let recovered = signature.recover_address_from_msg(p.user_op_hash.as_slice()).unwrap();
+            assert_eq!(recovered, signers[i]);
+        }
+
+        assert!(p.verify_signatures(&signers).is_ok());
+        assert!(p.verify_signatures(&signers[..1]).is_err());
+        assert!(p.missing_signers(&signers).is_empty());
+        assert_eq!(p.missing_signers(&[Address::ZERO]), vec![Address::ZERO]);
+
+        p.signatures[0].signature = Bytes::from(vec![0xab; 96]);
+        assert!(p.verify_signatures(&signers).is_err());
+    }
+
+    #[test]
+    fn test_contract_owners_rejected() {
+        let signer = Address::from([7u8; 20]);
+        assert!(check_ecdsa_owner(signer, &[]).is_ok());
+        assert!(check_ecdsa_owner(signer, &eip7702::delegation_designator(Address::from([8u8; 20]))).is_ok());
+
+        let contract = check_ecdsa_owner(signer, &[0x60, 0x80, 0x60, 0x40]).unwrap_err();
+        assert!(contract.to_string().contains("is a contract"));
+    }
+
+    #[test]
+    fn test_collection_stops_at_threshold() {
+        let (a, b, c) = (Wallet::new([0x11; 32]).unwrap(), Wallet::new([0x22; 32]).unwrap(), Wallet::new([0x33; 32]).unwrap());
+        let mut p = proposal();
+        p.sign(&a).unwrap();
+        p.sign(&b).unwrap();
+        // Without a threshold every owner is asked
+        assert!(!p.threshold_met());
+        assert!(p.accepts_signer(c.address()));
+
+        p.threshold = Some(2);
+        assert!(p.threshold_met());
+        assert!(!p.accepts_signer(c.address()));
+        assert!(p.accepts_signer(a.address()));
+
+        p.threshold = Some(3);
+        assert!(!p.threshold_met());
+        assert!(p.accepts_signer(c.address()));
+    }
+
+    #[test]
+    fn test_merge_signed_copies() {
+        let mut p = proposal();
+        let mut copy = p.clone();
+        p.sign(&Wallet::new([0x11; 32]).unwrap()).unwrap();
+        copy.sign(&Wallet::new([0x22; 32]).unwrap()).unwrap();
+        copy.sign(&Wallet::new([0x11; 32]).unwrap()).unwrap();
+
+        p.merge(copy).unwrap();
+        assert_eq!(p.signatures.len(), 2);
+
+        let mut other = proposal();
+        other.user_op.nonce = U256::from(4);
+        let other = Proposal::new(other.user_op, other.entry_point, other.chain_id);
+        assert!(p.merge(other).is_err());
+    }
+
+    #[test]
+    fn test_tampered_proposal_is_rejected() {
+        let path = std::env::temp_dir().join(format!("aa-client-proposal-{}.json", std::process::id()));
+        let mut p = proposal();
+        p.save(&path).unwrap();
+        assert!(Proposal::load(&path).is_ok());
+
+        p.user_op.nonce = U256::from(4);
+        p.save(&path).unwrap();
+        assert!(Proposal::load(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_context_must_rebuild_the_operation() {
+        let path = std::env::temp_dir().join(format!("aa-client-proposal-ctx-{}.json", std::process::id()));
+        let p = proposal();
+        let context = BuildContext {
+            chain_id: p.chain_id,
+            entry_point: p.entry_point,
+            sender: p.user_op.sender,
+            nonce: p.user_op.nonce,
+            factory: None,
+            factory_data: None,
+            target: Address::from([5u8; 20]),
+            value: U256::ZERO,
+            data: Bytes::from(vec![0xde, 0xad]),
+            call_gas_limit: U256::ZERO,
+            verification_gas_limit: U256::ZERO,
+            pre_verification_gas: U256::ZERO,
+            max_fee_per_gas: U256::ZERO,
+            max_priority_fee_per_gas: U256::ZERO,
+        };
+
+        let mut from_context = Proposal::from_context(context);
+        assert_eq!(from_context.user_op, p.user_op);
+        from_context.save(&path).unwrap();
+        assert!(Proposal::load(&path).is_ok());
+
+        // Consistent hash but an operation the context does not produce
+        from_context.user_op.call_gas_limit = U256::from(1);
+        from_context.user_op_hash = from_context.user_op.hash(from_context.entry_point, from_context.chain_id);
+        from_context.save(&path).unwrap();
+        assert!(Proposal::load(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    proptest! {
+        #[test]
+        fn pack_u128_pair_matches_encode_packed(high: u128, low: u128) {
+            let packed = pack_u128_pair(U256::from(high), U256::from(low));
+            // accountGasLimits and gasFees are abi.encodePacked(uint128, uint128)
+            prop_assert_eq!(packed.to_vec(), (high, low).abi_encode_packed());
+            prop_assert_eq!(U256::from_be_bytes(packed.0) >> 128, U256::from(high));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// NFT transfers made from the smart account
+// Both standards go through safeTransferFrom, so a contract recipient that cannot hold the token rejects it
+// instead of locking it away
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use std::str::FromStr;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IERC721 {
+        function safeTransferFrom(address from, address to, uint256 tokenId) external;
+        function ownerOf(uint256 tokenId) external view returns (address);
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IERC1155 {
+        function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data) external;
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+    }
+);
+
+/// Token standard of the NFT contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+impl std::fmt::Display for NftStandard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NftStandard::Erc721 => write!(f, "erc721"),
+            NftStandard::Erc1155 => write!(f, "erc1155"),
+        }
+    }
+}
+
+impl FromStr for NftStandard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "erc721" => Ok(NftStandard::Erc721),
+            "erc1155" => Ok(NftStandard::Erc1155),
+            _ => Err(anyhow::anyhow!("Unknown NFT standard '{}', expected erc721 or erc1155", s)),
+        }
+    }
+}
+
+/// Calldata moving `amount` of token `id` from `from` to `to`; ERC-721 moves exactly one token
+pub fn transfer_call_data(standard: NftStandard, from: Address, to: Address, id: U256, amount: U256) -> Result<Bytes> {
+    match standard {
+        NftStandard::Erc721 if amount != U256::from(1) => {
+            Err(anyhow::anyhow!("An ERC-721 token is unique, the amount must be 1 (got {})", amount))
+        }
+        NftStandard::Erc721 => Ok(IERC721::safeTransferFromCall { from, to, tokenId: id }.abi_encode().into()),
+        NftStandard::Erc1155 if amount.is_zero() => Err(anyhow::anyhow!("Nothing to transfer, the amount is 0")),
+        NftStandard::Erc1155 => {
+            Ok(IERC1155::safeTransferFromCall { from, to, id, amount, data: Bytes::new() }.abi_encode().into())
+        }
+    }
+}
+
+/// Check `account` holds `amount` of token `id` before a transfer that would otherwise revert on chain
+pub async fn check_ownership<P: Provider>(
+    provider: &P,
+    standard: NftStandard,
+    token: Address,
+    account: Address,
+    id: U256,
+    amount: U256,
+) -> Result<()> {
+    match standard {
+        NftStandard::Erc721 => {
+            let owner = IERC721::new(token, provider)
+                .ownerOf(id)
+                .call()
+                .await
+                .map_err(|e| anyhow::anyhow!("Could not read ownerOf({}) of {}, does the token exist? {}", id, token, e))?
+                ._0;
+            if owner != account {
+                return Err(anyhow::anyhow!("Token {} of {} belongs to {}, not to {}", id, token, owner, account));
+            }
+        }
+        NftStandard::Erc1155 => {
+            let balance = IERC1155::new(token, provider)
+                .balanceOf(account, id)
+                .call()
+                .await
+                .map_err(|e| anyhow::anyhow!("Could not read balanceOf() of {}, is it an ERC-1155? {}", token, e))?
+                ._0;
+            if balance < amount {
+                return Err(anyhow::anyhow!("{} holds {} of token {} of {}, cannot send {}", account, balance, id, token, amount));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_call_data() {
+        let (from, to) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+
+        let erc721 = transfer_call_data(NftStandard::Erc721, from, to, U256::from(7), U256::from(1)).unwrap();
+        // safeTransferFrom(address,address,uint256), not the overload taking data
+        assert_eq!(&erc721[..4], &[0x42, 0x84, 0x2e, 0x0e]);
+        assert!(transfer_call_data(NftStandard::Erc721, from, to, U256::from(7), U256::from(2)).is_err());
+
+        let erc1155 = transfer_call_data(NftStandard::Erc1155, from, to, U256::from(7), U256::from(3)).unwrap();
+        let decoded = IERC1155::safeTransferFromCall::abi_decode(&erc1155, true).unwrap();
+        assert_eq!((decoded.from, decoded.to, decoded.id, decoded.amount), (from, to, U256::from(7), U256::from(3)));
+        assert!(transfer_call_data(NftStandard::Erc1155, from, to, U256::from(7), U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_parse_standard() {
+        assert_eq!("ERC-721".parse::<NftStandard>().unwrap(), NftStandard::Erc721);
+        assert_eq!(NftStandard::Erc1155.to_string().parse::<NftStandard>().unwrap(), NftStandard::Erc1155);
+        assert!("erc20".parse::<NftStandard>().is_err());
+    }
+}
@@ -0,0 +1,331 @@
+// Two-dimensional EntryPoint nonces
+// The upper 192 bits pick an independent stream (the key), the lower 64 count operations within it,
+// so operations on different keys never wait on each other
+
+use alloy::primitives::{aliases::U192, Address, U256};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::data_dir;
+use crate::out;
+
+/// File name of the nonce cache inside the client data directory
+const NONCE_FILE: &str = "nonces.json";
+
+/// Full nonce for `sequence` on `key`
+pub fn pack(key: U192, sequence: u64) -> U256 {
+    (U256::from(key) << 64) | U256::from(sequence)
+}
+
+/// Split a nonce into its key and sequence
+pub fn unpack(nonce: U256) -> (U192, u64) {
+    (U192::from(nonce >> 64), nonce.as_limbs()[0])
+}
+
+/// Parse a `--nonce-key` value, decimal or 0x-prefixed hex
+pub fn parse_key(key: &str) -> Result<U192> {
+    U192::from_str(key).map_err(|e| anyhow::anyhow!("Invalid nonce key '{}' (uint192, decimal or 0x hex): {}", key, e))
+}
+
+/// Optimistic nonce allocation, so operations can be prepared concurrently without all reading the same `getNonce`
+/// Each (chain, sender, key) stream remembers the next sequence it handed out; the chain wins when it is ahead
+pub struct NonceManager {
+    path: Option<PathBuf>,
+    next: Mutex<HashMap<String, u64>>,
+}
+
+impl NonceManager {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            next: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache persisted at `~/.aa-client/nonces.json`, shared by concurrent invocations
+    pub fn persistent() -> Result<Self> {
+        Ok(Self::at_path(data_dir()?.join(NONCE_FILE)))
+    }
+
+    pub fn at_path(path: PathBuf) -> Self {
+        Self {
+            next: Mutex::new(Self::read(&path)),
+            path: Some(path),
+        }
+    }
+
+    /// Reserve the next nonce of a stream, given the nonce the EntryPoint currently expects
+    pub fn allocate(&self, chain_id: u64, sender: Address, key: U192, onchain: U256) -> U256 {
+        let mut next = self.next.lock().unwrap();
+        if let Some(path) = &self.path {
+            // Pick up what other processes allocated since we loaded
+            *next = Self::read(path);
+        }
+        let (_, onchain_sequence) = unpack(onchain);
+        let stream = next.entry(stream_id(chain_id, sender, key)).or_default();
+        let sequence = (*stream).max(onchain_sequence);
+        *stream = sequence + 1;
+        self.write(&next);
+        pack(key, sequence)
+    }
+
+    /// Forget what was handed out on a stream after a failed submission, so the next allocation follows the chain again
+    pub fn resync(&self, chain_id: u64, sender: Address, key: U192) {
+        let mut next = self.next.lock().unwrap();
+        if let Some(path) = &self.path {
+            *next = Self::read(path);
+        }
+        next.remove(&stream_id(chain_id, sender, key));
+        self.write(&next);
+    }
+
+    fn read(path: &Path) -> HashMap<String, u64> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, next: &HashMap<String, u64>) {
+        if let Some(path) = &self.path {
+            // Best effort, a lost entry only means the next allocation re-reads the chain
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(contents) = serde_json::to_string(next) {
+                let _ = std::fs::write(path, contents);
+            }
+        }
+    }
+}
+
+fn stream_id(chain_id: u64, sender: Address, key: U192) -> String {
+    format!("{}:{}:{}", chain_id, sender, key)
+}
+
+static MANAGER: OnceLock<NonceManager> = OnceLock::new();
+
+/// Allocate nonces through `manager` for the rest of the process
+pub fn set_manager(manager: NonceManager) {
+    let _ = MANAGER.set(manager);
+}
+
+/// Manager enabled with `--nonce-cache`, None when every operation reads `getNonce` itself
+pub fn manager() -> Option<&'static NonceManager> {
+    MANAGER.get()
+}
+
+/// What submission does when the EntryPoint rejects an operation's nonce (AA25), e.g. after another
+/// process or wallet used it between the read and the send
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonceRecovery {
+    /// Read the nonce the EntryPoint expects, rebuild and re-sign the operation and send it once more
+    #[default]
+    Retry,
+    /// Fail with the bundler's error
+    Fail,
+}
+
+impl Display for NonceRecovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NonceRecovery::Retry => write!(f, "retry"),
+            NonceRecovery::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+impl FromStr for NonceRecovery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "retry" => Ok(NonceRecovery::Retry),
+            "fail" => Ok(NonceRecovery::Fail),
+            _ => Err(anyhow::anyhow!("Unknown nonce recovery '{}', expected retry or fail", s)),
+        }
+    }
+}
+
+static RECOVERY: OnceLock<NonceRecovery> = OnceLock::new();
+
+/// Nonce recovery for the rest of the process (`--on-nonce-error`)
+pub fn set_recovery(recovery: NonceRecovery) {
+    let _ = RECOVERY.set(recovery);
+}
+
+pub fn recovery() -> NonceRecovery {
+    RECOVERY.get().copied().unwrap_or_default()
+}
+
+/// Whether `error` is the EntryPoint rejecting the nonce, from the bundler or from simulation
+pub fn is_nonce_error(error: &impl Display) -> bool {
+    let message = error.to_string();
+    message.contains("AA25") || message.to_ascii_lowercase().contains("invalid account nonce")
+}
+
+/// Submit through `attempt`, and after an AA25 once more with the nonce `refetch` reads when the policy retries
+/// `attempt` gets None for the operation as built and the new nonce for the retry, with which it rebuilds,
+/// re-sponsors and re-signs the operation; any other error, or a second AA25, is returned as is
+pub async fn with_recovery<T, A, AFut, R, RFut>(mut attempt: A, refetch: R) -> Result<T>
+where
+    A: FnMut(Option<U256>) -> AFut,
+    AFut: Future<Output = Result<T>>,
+    R: FnOnce() -> RFut,
+    RFut: Future<Output = Result<U256>>,
+{
+    match attempt(None).await {
+        Err(e) if recovery() == NonceRecovery::Retry && is_nonce_error(&e) => {
+            let nonce = refetch().await?;
+            out!("🔢 Nonce was already used, retrying with sequence {}", unpack(nonce).1);
+            attempt(Some(nonce)).await
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let key = U192::from(7);
+        let nonce = pack(key, 3);
+        assert_eq!(nonce, (U256::from(7) << 64) + U256::from(3));
+        assert_eq!(unpack(nonce), (key, 3));
+
+        assert_eq!(pack(U192::ZERO, 42), U256::from(42));
+        assert_eq!(unpack(pack(U192::MAX, u64::MAX)), (U192::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("0").unwrap(), U192::ZERO);
+        assert_eq!(parse_key("0x10").unwrap(), U192::from(16));
+        assert!(parse_key("-1").is_err());
+        assert!(parse_key(&format!("0x1{}", "0".repeat(48))).is_err());
+    }
+
+    #[test]
+    fn test_manager_allocates_ahead_of_chain() {
+        let manager = NonceManager::in_memory();
+        let sender = Address::from([0x11; 20]);
+        let key = U192::from(1);
+        let onchain = pack(key, 5);
+
+        assert_eq!(manager.allocate(1, sender, key, onchain), pack(key, 5));
+        assert_eq!(manager.allocate(1, sender, key, onchain), pack(key, 6));
+        // Other keys and chains are independent streams
+        assert_eq!(manager.allocate(1, sender, U192::ZERO, U256::ZERO), U256::ZERO);
+        assert_eq!(manager.allocate(2, sender, key, onchain), pack(key, 5));
+        // The chain moving past the cache wins
+        assert_eq!(manager.allocate(1, sender, key, pack(key, 10)), pack(key, 10));
+
+        manager.resync(1, sender, key);
+        assert_eq!(manager.allocate(1, sender, key, pack(key, 8)), pack(key, 8));
+    }
+
+    #[test]
+    fn test_manager_persists_allocations() {
+        let path = std::env::temp_dir().join(format!("aa-client-nonces-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sender = Address::from([0x22; 20]);
+
+        let first = NonceManager::at_path(path.clone());
+        let second = NonceManager::at_path(path.clone());
+        assert_eq!(first.allocate(1, sender, U192::ZERO, U256::from(3)), U256::from(3));
+        assert_eq!(second.allocate(1, sender, U192::ZERO, U256::from(3)), U256::from(4));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_nonce_recovery() {
+        assert_eq!("retry".parse::<NonceRecovery>().unwrap(), NonceRecovery::Retry);
+        assert_eq!("FAIL".parse::<NonceRecovery>().unwrap(), NonceRecovery::Fail);
+        assert!("twice".parse::<NonceRecovery>().is_err());
+        assert_eq!(NonceRecovery::default().to_string(), "retry");
+
+        assert!(is_nonce_error(&"UserOperation rejected: AA25 invalid account nonce"));
+        assert!(is_nonce_error(&"Invalid account nonce"));
+        assert!(!is_nonce_error(&"AA21 didn't pay prefund"));
+    }
+
+    #[tokio::test]
+    async fn test_with_recovery_retries_once_with_refetched_nonce() {
+        let mut nonces = Vec::new();
+        let sent = with_recovery(
+            |nonce| {
+                nonces.push(nonce);
+                async move {
+                    match nonce {
+                        Some(nonce) => Ok(nonce),
+                        None => Err(anyhow::anyhow!("FailedOp(0, \"AA25 invalid account nonce\")")),
+                    }
+                }
+            },
+            || async { Ok(U256::from(9)) },
+        )
+        .await;
+        assert_eq!(sent.unwrap(), U256::from(9));
+        assert_eq!(nonces, vec![None, Some(U256::from(9))]);
+
+        // Other errors and a second AA25 are not retried
+        let mut attempts = 0;
+        let failed: Result<()> = with_recovery(
+            |_| {
+                attempts += 1;
+                async { Err(anyhow::anyhow!("AA25 invalid account nonce")) }
+            },
+            || async { Ok(U256::ZERO) },
+        )
+        .await;
+        assert!(failed.is_err());
+        assert_eq!(attempts, 2);
+
+        // An operation the account itself rejected is never sent again, nor the nonce read
+        let (mut attempts, mut refetched) = (0, false);
+        let failed: Result<()> = with_recovery(
+            |_| {
+                attempts += 1;
+                async { Err(anyhow::anyhow!("FailedOp(0, \"AA21 didn't pay prefund\")")) }
+            },
+            || {
+                refetched = true;
+                async { Ok(U256::ZERO) }
+            },
+        )
+        .await;
+        assert!(failed.unwrap_err().to_string().contains("AA21"));
+        assert_eq!(attempts, 1);
+        assert!(!refetched);
+    }
+
+    proptest! {
+        #[test]
+        fn pack_matches_encode_packed(key in any::<[u8; 24]>(), sequence in any::<u64>()) {
+            use alloy::sol_types::{sol_data, SolType};
+
+            let key = U192::from_be_bytes(key);
+            let nonce = pack(key, sequence);
+            // The EntryPoint's `uint256(key) << 64 | seq` is abi.encodePacked(uint192 key, uint64 seq)
+            let encoded = <(sol_data::Uint<192>, sol_data::Uint<64>)>::abi_encode_packed(&(key, sequence));
+            prop_assert_eq!(nonce.to_be_bytes::<32>().to_vec(), encoded);
+            prop_assert_eq!(unpack(nonce), (key, sequence));
+        }
+
+        #[test]
+        fn parse_key_reads_what_it_prints(key in any::<[u8; 24]>()) {
+            let key = U192::from_be_bytes(key);
+            prop_assert_eq!(parse_key(&key.to_string()).unwrap(), key);
+            prop_assert_eq!(parse_key(&format!("{:#x}", key)).unwrap(), key);
+        }
+    }
+}
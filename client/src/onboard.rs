@@ -0,0 +1,109 @@
+// Gasless onboarding: owner key, sponsored deployment and an optional first sponsored call in one command
+// Progress is read back from the chain and the operations journal, so a run that stopped part way
+// (timeout, bundler outage, Ctrl-C) picks up at the step it left instead of sponsoring anything twice
+
+use alloy::primitives::Address;
+
+use crate::journal::{OperationRecord, OperationStatus};
+
+/// Default wallet name a generated owner key is stored under
+pub const DEFAULT_WALLET_NAME: &str = "onboard";
+
+/// Tag journaled with the first call, which tells it apart from later calls of the same account
+pub const FIRST_CALL_TAG: &str = "onboard";
+
+/// Journal kind of sponsored deployments
+const DEPLOY_KIND: &str = "deploy-sponsored";
+/// Journal kind of sponsored calls
+const CALL_KIND: &str = "submit-sponsored";
+
+/// What an onboarding run does next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Request a sponsored deployment
+    Deploy,
+    /// A deployment was submitted earlier, wait for its receipt
+    AwaitDeployment(String),
+    /// Send the first sponsored call
+    FirstCall,
+    /// The first call was submitted earlier, wait for its receipt
+    AwaitFirstCall(String),
+    Done,
+}
+
+/// Next step for `account`, given whether its code is on chain and whether a first call was asked for
+/// Failed operations are retried; replaced ones are followed through their fee-bumped copy
+pub fn next_step(records: &[OperationRecord], chain_id: u64, account: Address, deployed: bool, first_call: bool) -> Step {
+    let latest = |kind: &str, tag: Option<&str>| {
+        records.iter().rev().find(|record| {
+            record.chain_id == chain_id
+                && record.sender == account
+                && record.kind == kind
+                && record.status != OperationStatus::Replaced
+                && tag.is_none_or(|tag| record.tag.as_deref() == Some(tag))
+        })
+    };
+
+    if !deployed {
+        return match latest(DEPLOY_KIND, None) {
+            // An included deployment whose code the node does not show yet is waited on, not sent again
+            Some(record) if record.status != OperationStatus::Failed => Step::AwaitDeployment(record.user_op_hash.clone()),
+            _ => Step::Deploy,
+        };
+    }
+    if !first_call {
+        return Step::Done;
+    }
+    match latest(CALL_KIND, Some(FIRST_CALL_TAG)) {
+        Some(record) if record.status == OperationStatus::Included => Step::Done,
+        Some(record) if record.status == OperationStatus::Pending => Step::AwaitFirstCall(record.user_op_hash.clone()),
+        _ => Step::FirstCall,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: Address = Address::repeat_byte(0x11);
+
+    fn record(hash: &str, kind: &str, tag: Option<&str>, status: OperationStatus) -> OperationRecord {
+        let mut record = OperationRecord::pending(hash.to_string(), 1, ACCOUNT, kind, tag.map(str::to_string), true);
+        record.status = status;
+        record
+    }
+
+    #[test]
+    fn test_fresh_account_deploys() {
+        assert_eq!(next_step(&[], 1, ACCOUNT, false, true), Step::Deploy);
+        // Operations of another account or chain are not checkpoints of this one
+        let mut other = record("0x01", DEPLOY_KIND, None, OperationStatus::Pending);
+        other.chain_id = 2;
+        assert_eq!(next_step(&[other], 1, ACCOUNT, false, true), Step::Deploy);
+    }
+
+    #[test]
+    fn test_resumes_pending_deployment() {
+        let records = [record("0x01", DEPLOY_KIND, None, OperationStatus::Failed), record("0x02", DEPLOY_KIND, None, OperationStatus::Pending)];
+        assert_eq!(next_step(&records, 1, ACCOUNT, false, true), Step::AwaitDeployment("0x02".to_string()));
+        assert_eq!(next_step(&records[..1], 1, ACCOUNT, false, true), Step::Deploy);
+    }
+
+    #[test]
+    fn test_first_call_checkpoints() {
+        assert_eq!(next_step(&[], 1, ACCOUNT, true, false), Step::Done);
+        assert_eq!(next_step(&[], 1, ACCOUNT, true, true), Step::FirstCall);
+
+        // Untagged calls are the account's own traffic, not the onboarding call
+        let untagged = record("0x01", CALL_KIND, None, OperationStatus::Included);
+        assert_eq!(next_step(&[untagged], 1, ACCOUNT, true, true), Step::FirstCall);
+
+        let pending = record("0x02", CALL_KIND, Some(FIRST_CALL_TAG), OperationStatus::Pending);
+        assert_eq!(next_step(&[pending.clone()], 1, ACCOUNT, true, true), Step::AwaitFirstCall("0x02".to_string()));
+        let replaced = record("0x02", CALL_KIND, Some(FIRST_CALL_TAG), OperationStatus::Replaced);
+        let included = record("0x03", CALL_KIND, Some(FIRST_CALL_TAG), OperationStatus::Included);
+        assert_eq!(next_step(&[replaced, included], 1, ACCOUNT, true, true), Step::Done);
+        let failed = record("0x04", CALL_KIND, Some(FIRST_CALL_TAG), OperationStatus::Failed);
+        assert_eq!(next_step(&[pending, failed], 1, ACCOUNT, true, true), Step::FirstCall);
+    }
+}
@@ -0,0 +1,214 @@
+// Terminal output layer: user-facing lines go through `out!` so presentation is decided in one place
+// Lines are tracing events; the binary installs `init`, library users see nothing unless they add a subscriber
+// Plain mode drops emoji and unicode art for screen readers and CI logs
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Events below this target are client output, everything else comes from dependencies
+const TARGET: &str = "aa_client";
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Icons that carry meaning keep it as a word in plain mode, every other icon is decoration
+const ICON_WORDS: [(&str, &str); 6] = [
+    ("✅", "OK:"),
+    ("❌", "ERROR:"),
+    ("⚠️", "WARNING:"),
+    ("⚠", "WARNING:"),
+    ("⏳", "PENDING:"),
+    ("💡", "HINT:"),
+];
+
+/// Switch plain output on or off for the rest of the process
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Emit one line of output, at the level its leading icon stands for
+pub fn line(text: &str) {
+    match level_of(text) {
+        Level::ERROR => tracing::error!("{}", text),
+        Level::WARN => tracing::warn!("{}", text),
+        _ => tracing::info!("{}", text),
+    }
+}
+
+/// ❌ lines are errors, ⚠️ lines warnings, the rest informational
+pub fn level_of(text: &str) -> Level {
+    let body = text.trim_start();
+    if body.starts_with('❌') {
+        Level::ERROR
+    } else if body.starts_with('⚠') {
+        Level::WARN
+    } else {
+        Level::INFO
+    }
+}
+
+/// Send client output to stdout: warnings and errors only for `Quiet`, debug lines too for `Verbose`
+pub fn init(level: LevelFilter) {
+    let _ = tracing_subscriber::registry().with(layer(level)).try_init();
+}
+
+/// The layer `init` installs, for subscribers that add their own layers next to it
+/// Its filter only applies to itself, so `--quiet` does not hide spans from other layers
+pub fn layer<S>(level: LevelFilter) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .event_format(HumanFormat { verbose: level >= LevelFilter::DEBUG })
+        .with_writer(std::io::stdout)
+        .with_ansi(false)
+        .with_filter(Targets::new().with_target(TARGET, level))
+}
+
+/// Human-readable formatter: the message alone, as `println!` printed it
+/// Verbose output prefixes the level and the command span so debug lines can be told apart
+pub struct HumanFormat {
+    pub verbose: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for HumanFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        let mut prefix = String::new();
+        if self.verbose {
+            write!(prefix, "{:>5} ", event.metadata().level())?;
+            for span in ctx.event_scope().into_iter().flat_map(|scope| scope.from_root()) {
+                prefix.push_str(span.name());
+                if let Some(fields) = span.extensions().get::<FormattedFields<N>>().filter(|fields| !fields.is_empty()) {
+                    write!(prefix, "{{{}}}", fields)?;
+                }
+                prefix.push_str(": ");
+            }
+        }
+
+        let mut message = Message::default();
+        event.record(&mut message);
+        for line in message.0.split('\n') {
+            if is_plain() {
+                writeln!(writer, "{}{}", prefix, plain(line))?;
+            } else {
+                writeln!(writer, "{}{}", prefix, line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `message` field of an event
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// Emoji, pictographs, dingbats, box drawing and block elements
+fn is_decoration(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x200D | 0x2139 | 0x2190..=0x21FF | 0x2300..=0x23FF | 0x2500..=0x27BF | 0x2B00..=0x2BFF | 0xFE00..=0xFE0F | 0x1F000..=0x1FAFF
+    )
+}
+
+/// Rewrite a line as plain text, keeping indentation and accented letters
+pub fn plain(line: &str) -> String {
+    let line = line.replace('→', "->");
+    let body = line.trim_start();
+    let mut out = line[..line.len() - body.len()].to_string();
+
+    let body = match ICON_WORDS.iter().find(|(icon, _)| body.starts_with(icon)) {
+        Some((icon, word)) => {
+            out.push_str(word);
+            out.push(' ');
+            body[icon.len()..].trim_start()
+        }
+        None => body,
+    };
+
+    // Drop decorations along with the space that separated them from the text
+    let mut after_decoration = false;
+    for c in body.chars() {
+        if is_decoration(c) {
+            after_decoration = true;
+        } else if c == ' ' && after_decoration && (out.is_empty() || out.ends_with(' ')) {
+            continue;
+        } else {
+            after_decoration = false;
+            out.push(c);
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// `println!` through the output layer, at info level unless the line starts with ❌ or ⚠️
+#[macro_export]
+macro_rules! out {
+    () => {
+        $crate::output::line("")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::line(&format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_strips_decorations() {
+        assert_eq!(plain("🚀 Submitting transaction..."), "Submitting transaction...");
+        assert_eq!(plain("  📍 Account: 0xabc"), "  Account: 0xabc");
+        assert_eq!(plain("🗄️  RPC cache: 1 hits"), "RPC cache: 1 hits");
+        assert_eq!(plain("Done 🎉 really"), "Done really");
+        assert_eq!(plain("v0.6 → v0.8"), "v0.6 -> v0.8");
+        assert_eq!(plain("██▀▀"), "");
+    }
+
+    #[test]
+    fn test_level_follows_leading_icon() {
+        assert_eq!(level_of("❌ Transaction failed"), Level::ERROR);
+        assert_eq!(level_of("  ⚠️  Gas estimation failed"), Level::WARN);
+        assert_eq!(level_of("✅ Done ❌"), Level::INFO);
+        assert_eq!(level_of(""), Level::INFO);
+    }
+
+    #[test]
+    fn test_plain_keeps_meaningful_icons_as_words() {
+        assert_eq!(plain("✅ Transacción ejecutada"), "OK: Transacción ejecutada");
+        assert_eq!(plain("⚠️  Gas estimation failed"), "WARNING: Gas estimation failed");
+        assert_eq!(plain("    ❌ 0xabc"), "    ERROR: 0xabc");
+        assert_eq!(plain("Factory: 0x59bc"), "Factory: 0x59bc");
+    }
+}
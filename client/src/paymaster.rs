@@ -1,7 +1,206 @@
-use alloy::primitives::{Address, U256, Bytes};
-use serde::{Deserialize, Serialize};
+use alloy::primitives::{bytes, keccak256, Address, B256, U256, Bytes};
+use alloy::providers::Provider;
+use alloy::signers::Signature;
+use alloy::sol;
+use alloy::sol_types::SolValue;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use aa_sdk_rs::types::UserOperationRequest;
 use anyhow::Result;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::clock::now_secs;
+use crate::events::{self, ClientEvent};
+use crate::hexutil;
+use crate::out;
+use crate::multisig::UnsignedUserOperation;
+use crate::retry;
+use crate::sponsorship::{self, SponsorshipCache};
+use crate::telemetry;
+use crate::transport::{self, LocalReply, LocalSocket};
+
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Sponsorships requested for one operation before an expiring window is reported as a failure
+pub const MAX_SPONSORSHIP_ATTEMPTS: u32 = 3;
+
+/// Paymaster gas limits of stubs built without the service: VerifierSignaturePaymaster's validation, and a
+/// postOp as large as a token paymaster's transfer, both with headroom
+const STUB_VERIFICATION_GAS_LIMIT: u64 = 60_000;
+const STUB_POST_OP_GAS_LIMIT: u64 = 50_000;
+
+/// The throwaway signature paymaster-service stubs carry: recoverable, so VerifierSignaturePaymaster's
+/// `ECDSA.recover` returns a non-verifier address instead of reverting the estimation
+const STUB_SIGNATURE: Bytes = bytes!("9983a724ea5d466ac4c6aa9dca8444b4b2ec8626708a7ac0bbdc1d58417ed9190a10a54a69f6b79208c67e072a1a91e02dc2fbb52f840ba84f3d7df8811627271b");
+
+/// How far ahead a local stub's validUntil lies, for the byte mix of a real one
+const STUB_VALIDITY_SECS: u64 = 3600;
+
+/// Whether simulation or the bundler rejected the paymaster signature for its validity window (AA32)
+pub fn is_sponsorship_expired(error: &anyhow::Error) -> bool {
+    format!("{:#}", error).contains("AA32")
+}
+
+/// Body encoding used when talking to the paymaster-service
+/// CBOR trims payload size and parse cost for high-volume sponsorship traffic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl FromStr for WireFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(WireFormat::Json),
+            "cbor" => Ok(WireFormat::Cbor),
+            other => Err(anyhow::anyhow!("Unknown wire format '{}', expected json or cbor", other)),
+        }
+    }
+}
+
+/// Kind of paymaster contract, which decides what paymasterData carries and whether the service is asked at all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaymasterMode {
+    /// VerifierSignaturePaymaster: the service signs every operation, paymasterData is
+    /// signature (65) || validUntil (8) || validAfter (8)
+    #[default]
+    Verifying,
+    /// SimplePaymaster: sponsors any operation, paymasterData is empty and the service is never called
+    Simple,
+    /// ERC-20 paymaster priced on chain: paymasterData is the token address, charged through the
+    /// account's allowance, and the service is never called
+    Token(Address),
+}
+
+impl PaymasterMode {
+    /// paymasterData of modes that need no signature, None when the service must sign
+    pub fn unsigned_data(&self) -> Option<Bytes> {
+        match self {
+            PaymasterMode::Verifying => None,
+            PaymasterMode::Simple => Some(Bytes::new()),
+            PaymasterMode::Token(token) => Some(Bytes::copy_from_slice(token.as_slice())),
+        }
+    }
+}
+
+impl std::fmt::Display for PaymasterMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymasterMode::Verifying => write!(f, "verifying"),
+            PaymasterMode::Simple => write!(f, "simple"),
+            PaymasterMode::Token(token) => write!(f, "token:{}", token),
+        }
+    }
+}
+
+impl FromStr for PaymasterMode {
+    type Err = anyhow::Error;
+
+    /// `verifying`, `simple` or `token:<address>`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().split_once(':') {
+            Some(("token", token)) => Ok(PaymasterMode::Token(
+                Address::from_str(token).map_err(|e| anyhow::anyhow!("Invalid token address in '{}': {}", s, e))?,
+            )),
+            None if s.eq_ignore_ascii_case("verifying") => Ok(PaymasterMode::Verifying),
+            None if s.eq_ignore_ascii_case("simple") => Ok(PaymasterMode::Simple),
+            _ => Err(anyhow::anyhow!("Unknown paymaster mode '{}', expected verifying, simple or token:<address>", s)),
+        }
+    }
+}
+
+impl Serialize for PaymasterMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Config files name modes the way the command line does (`"token:0x..."`)
+impl<'de> Deserialize<'de> for PaymasterMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IVerifierSignaturePaymaster {
+        function verifier() external view returns (address);
+    }
+);
+
+/// Key a VerifierSignaturePaymaster accepts sponsorship signatures from
+pub async fn read_verifier<P: Provider>(provider: &P, paymaster: Address) -> Result<Address> {
+    Ok(IVerifierSignaturePaymaster::new(paymaster, provider).verifier().call().await?._0)
+}
+
+/// `_pmHash` of VerifierSignaturePaymaster:
+/// keccak256(abi.encode(_packForPaymaster(op), chainid, paymaster, validUntil, validAfter))
+pub fn paymaster_hash(
+    user_op: &PackedUserOperationData,
+    chain_id: u64,
+    paymaster: Address,
+    valid_until: u64,
+    valid_after: u64,
+) -> Result<B256> {
+    let packed = (
+        Address::from_str(&user_op.sender)?,
+        U256::from_str(&user_op.nonce)?,
+        keccak256(hexutil::decode("init_code", &user_op.init_code)?),
+        keccak256(hexutil::decode("call_data", &user_op.call_data)?),
+        hexutil::decode_word("account_gas_limits", &user_op.account_gas_limits)?,
+        U256::from_str(&user_op.pre_verification_gas)?,
+        hexutil::decode_word("gas_fees", &user_op.gas_fees)?,
+    )
+        .abi_encode_params();
+    Ok(keccak256((Bytes::from(packed), U256::from(chain_id), paymaster, valid_until, valid_after).abi_encode_params()))
+}
+
+/// Verifier the paymaster contract trusts, checked against every signature the service returns
+/// so that a service configured for another chain or paymaster fails before the bundler does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedVerifier {
+    pub chain_id: u64,
+    pub verifier: Address,
+}
+
+impl ExpectedVerifier {
+    /// Recover the signer of `config` over `user_op` the way the contract does and compare it with the verifier
+    pub fn check(&self, user_op: &PackedUserOperationData, config: &PaymasterConfig) -> Result<()> {
+        let hash = paymaster_hash(user_op, self.chain_id, config.paymaster_address, config.valid_until, config.valid_after)?;
+        let signer = Signature::try_from(&config.signature[..])
+            .map_err(|e| anyhow::anyhow!("Paymaster signature is not a valid ECDSA signature: {}", e))?
+            .recover_address_from_msg(hash.as_slice())?;
+        if signer != self.verifier {
+            return Err(anyhow::anyhow!(
+                "Paymaster signature recovers to {}, but paymaster {} trusts verifier {} on chain {}. \
+                 The service signs for another chain_id or paymaster_address, or with another verifier key",
+                signer, config.paymaster_address, self.verifier, self.chain_id
+            ));
+        }
+        Ok(())
+    }
+}
+
+static MODE: OnceLock<PaymasterMode> = OnceLock::new();
+
+/// Paymaster mode for the rest of the process (`--paymaster-mode` or the profile's)
+pub fn set_mode(mode: PaymasterMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn mode() -> PaymasterMode {
+    MODE.get().copied().unwrap_or_default()
+}
 
 #[derive(Debug, Clone)]
 pub struct PaymasterConfig {
@@ -14,14 +213,17 @@ pub struct PaymasterConfig {
 /// Request format for paymaster-service
 #[derive(Debug, Serialize)]
 pub struct PaymasterServiceRequest {
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preauth_token: Option<String>,
     pub user_operation: PackedUserOperationData,
     pub valid_until: u64,
     pub valid_after: Option<u64>,
 }
 
 /// PackedUserOperation format expected by paymaster-service
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PackedUserOperationData {
     pub sender: String,
     pub nonce: String,
@@ -36,17 +238,127 @@ pub struct PackedUserOperationData {
 /// Response from paymaster-service
 #[derive(Debug, Deserialize)]
 pub struct PaymasterServiceResponse {
+    /// `verifying` or `simple`, missing from services older than response version 2
+    #[serde(default)]
+    pub paymaster_kind: Option<String>,
+    /// Absent when the service sponsors a SimplePaymaster
+    #[serde(default)]
     pub signature: String,
     pub valid_until: u64,
     pub valid_after: u64,
     pub paymaster_data: String,
+    /// Signed by the service's test verifier, not valid against the production paymaster
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// Request for the paymaster fields an operation is estimated with
+#[derive(Debug, Serialize)]
+pub struct StubRequest {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preauth_token: Option<String>,
+    pub sender: String,
+}
+
+/// Response from paymaster-service's `/stub`
+#[derive(Debug, Deserialize)]
+pub struct StubResponse {
+    pub paymaster_data: String,
+    pub paymaster_verification_gas_limit: u64,
+    pub paymaster_post_op_gas_limit: u64,
+    #[serde(default)]
+    pub is_final: bool,
+}
+
+/// Paymaster fields attached while estimating gas, so the estimate covers the paymaster's validation
+/// and postOp; the signed data replaces `paymaster_data` once the operation is final
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymasterStub {
+    pub paymaster: Address,
+    pub paymaster_data: Bytes,
+    pub verification_gas_limit: U256,
+    pub post_op_gas_limit: U256,
+}
+
+impl PaymasterStub {
+    /// Stub for `mode` built without the service: the data of modes that take no signature, otherwise
+    /// signed-length data around `STUB_SIGNATURE`
+    pub fn local(paymaster: Address, mode: PaymasterMode) -> Self {
+        let paymaster_data = mode.unsigned_data().unwrap_or_else(|| {
+            let valid_until = now_secs() + STUB_VALIDITY_SECS;
+            [STUB_SIGNATURE.as_ref(), &valid_until.to_be_bytes(), &0u64.to_be_bytes()].concat().into()
+        });
+        Self {
+            paymaster,
+            paymaster_data,
+            verification_gas_limit: U256::from(STUB_VERIFICATION_GAS_LIMIT),
+            post_op_gas_limit: U256::from(STUB_POST_OP_GAS_LIMIT),
+        }
+    }
+
+    /// Set the paymaster fields of `user_op`, keeping gas limits an estimate already raised above the stub's
+    /// Filling may reset paymaster fields, so this is applied again after it
+    pub fn attach(&self, user_op: &mut UserOperationRequest) {
+        user_op.paymaster = Some(self.paymaster);
+        user_op.paymaster_data = Some(self.paymaster_data.clone());
+        user_op.paymaster_verification_gas_limit = Some(user_op.paymaster_verification_gas_limit.unwrap_or_default().max(self.verification_gas_limit));
+        user_op.paymaster_post_op_gas_limit = Some(user_op.paymaster_post_op_gas_limit.unwrap_or_default().max(self.post_op_gas_limit));
+    }
+
+    /// `attach` for operations the client estimates itself
+    pub fn attach_unsigned(&self, user_op: &mut UnsignedUserOperation) {
+        user_op.paymaster = Some(self.paymaster);
+        user_op.paymaster_data = Some(self.paymaster_data.clone());
+        user_op.paymaster_verification_gas_limit = Some(user_op.paymaster_verification_gas_limit.unwrap_or_default().max(self.verification_gas_limit));
+        user_op.paymaster_post_op_gas_limit = Some(user_op.paymaster_post_op_gas_limit.unwrap_or_default().max(self.post_op_gas_limit));
+    }
+}
+
+/// Request for a single-use sponsorship token bound to one sender
+#[derive(Debug, Serialize)]
+pub struct PreAuthRequest {
+    pub api_key: String,
+    pub sender: String,
+    pub ttl_secs: Option<u64>,
+}
+
+/// Single-use sponsorship token issued by paymaster-service
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreAuthGrant {
+    pub token: String,
+    pub sender: String,
+    pub expires_at: u64,
+}
+
+impl PreAuthGrant {
+    /// Shareable link carrying the token, for handing a sponsored operation to someone else
+    pub fn link(&self, service_url: &str) -> String {
+        format!("aa-preauth:{}?paymaster={}&sender={}", self.token, service_url, self.sender)
+    }
+}
+
+/// Accept either a bare pre-auth token or a link produced by `PreAuthGrant::link`
+pub fn parse_preauth_token(value: &str) -> String {
+    let value = value.trim();
+    let token = value.strip_prefix("aa-preauth:").unwrap_or(value);
+    token.split('?').next().unwrap_or(token).to_string()
 }
 
 /// Paymaster service client for ERC-4337 gas sponsorship
 pub struct PaymasterService {
     pub service_url: String,
     pub api_key: String,
+    /// Redeemed instead of the API key when set
+    pub preauth_token: Option<String>,
     pub paymaster_address: Address,
+    pub wire_format: WireFormat,
+    pub mode: PaymasterMode,
+    /// Checked against every new signature when set
+    pub expected_verifier: Option<ExpectedVerifier>,
+    /// Sponsorships reused while still valid, unless `--responsor` is set
+    cache: SponsorshipCache,
     client: reqwest::Client,
 }
 
@@ -55,85 +367,282 @@ impl PaymasterService {
         Self {
             service_url,
             api_key,
+            preauth_token: None,
             paymaster_address,
+            wire_format: WireFormat::Json,
+            mode: mode(),
+            expected_verifier: None,
+            cache: SponsorshipCache::persistent(),
             client: reqwest::Client::new(),
         }
     }
 
-    /// Request sponsorship for a UserOperation
+    /// Select the request body encoding (JSON by default)
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.wire_format = wire_format;
+        self
+    }
+
+    /// Override the process-wide paymaster mode
+    pub fn with_mode(mut self, mode: PaymasterMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Reject signatures that do not recover to `verifier` on `chain_id`
+    pub fn with_expected_verifier(mut self, chain_id: u64, verifier: Address) -> Self {
+        self.expected_verifier = Some(ExpectedVerifier { chain_id, verifier });
+        self
+    }
+
+    /// Authenticate with a single-use pre-auth token instead of the API key
+    pub fn with_preauth_token(mut self, token: String) -> Self {
+        self.preauth_token = Some(token);
+        self
+    }
+
+    /// Use `cache` for sponsorship reuse instead of the one under ~/.aa-client
+    pub fn with_cache(mut self, cache: SponsorshipCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Ask the service for a single-use token sponsoring one operation from `sender`
+    pub async fn issue_preauth(&self, sender: Address, ttl_secs: Option<u64>) -> Result<PreAuthGrant> {
+        let request = PreAuthRequest {
+            api_key: self.api_key.clone(),
+            sender: format!("{:?}", sender),
+            ttl_secs,
+        };
+        self.post("/preauth", &request).await
+    }
+
+    /// Paymaster fields to estimate an operation from `sender` with, before it can be signed for
+    /// Services without `/stub` get a locally built stub of the same length
+    pub async fn stub(&self, sender: Address) -> Result<PaymasterStub> {
+        if self.mode != PaymasterMode::Verifying {
+            return Ok(PaymasterStub::local(self.paymaster_address, self.mode));
+        }
+        let request = StubRequest {
+            api_key: if self.preauth_token.is_some() { String::new() } else { self.api_key.clone() },
+            preauth_token: self.preauth_token.clone(),
+            sender: format!("{:?}", sender),
+        };
+        let response: StubResponse = match self.post("/stub", &request).await {
+            Ok(response) => response,
+            Err(e) if e.to_string().contains("(HTTP 404") => {
+                out!("🔧 Paymaster service has no /stub, estimating with a local stub");
+                return Ok(PaymasterStub::local(self.paymaster_address, self.mode));
+            }
+            Err(e) => return Err(e),
+        };
+        if response.is_final {
+            out!("🔧 The paymaster's stub data is final, it takes no signature");
+        }
+        Ok(PaymasterStub {
+            paymaster: self.paymaster_address,
+            paymaster_data: hexutil::decode_bytes("stub paymaster data", &response.paymaster_data)?,
+            verification_gas_limit: U256::from(response.paymaster_verification_gas_limit),
+            post_op_gas_limit: U256::from(response.paymaster_post_op_gas_limit),
+        })
+    }
+
+    /// Request sponsorship for a UserOperation, reusing an unexpired one for the same operation
     pub async fn request_sponsorship(
         &self,
         user_op: &UserOperationRequest,
         valid_until: u64,
         valid_after: Option<u64>,
     ) -> Result<PaymasterConfig> {
+        if self.mode != PaymasterMode::Verifying {
+            return Err(anyhow::anyhow!("A {} paymaster needs no sponsorship signature", self.mode));
+        }
         // Convert UserOperationRequest to format expected by paymaster-service
         let packed_user_op = self.convert_user_operation(user_op)?;
-        
+        self.sponsor(packed_user_op, valid_until, valid_after, !sponsorship::responsor()).await
+    }
+
+    /// Sponsor `user_op`, attach the paymaster fields and hand it to `send`
+    /// A send failing on an expired window, or after `valid_until` passed, gets a new signature and another try
+    /// Paymasters that take no signature get their data attached right away, without asking the service
+    pub async fn submit_sponsored<T, F, Fut>(&self, user_op: &UserOperationRequest, valid_for: u64, mut send: F) -> Result<T>
+    where
+        F: FnMut(UserOperationRequest) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some(paymaster_data) = self.mode.unsigned_data() {
+            out!("🔧 {} paymaster, no sponsorship signature needed", self.mode);
+            let mut sponsored = user_op.clone();
+            sponsored.paymaster = Some(self.paymaster_address);
+            sponsored.paymaster_data = Some(paymaster_data);
+            return send(sponsored).await;
+        }
+
+        let packed_user_op = self.convert_user_operation(user_op)?;
+        let mut reuse = !sponsorship::responsor();
+        let mut attempt = 1;
+        loop {
+            let config = self.sponsor(packed_user_op.clone(), now_secs() + valid_for, Some(0), reuse).await?;
+            let mut sponsored = user_op.clone();
+            sponsored.paymaster = Some(self.paymaster_address);
+            sponsored.paymaster_data = Some(self.build_paymaster_and_data(&config));
+            match send(sponsored).await {
+                Err(e) if attempt < MAX_SPONSORSHIP_ATTEMPTS && (is_sponsorship_expired(&e) || now_secs() >= config.valid_until) => {
+                    out!("⌛ Sponsorship valid until {} expired before the operation was accepted, requesting a new one", config.valid_until);
+                    reuse = false;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Cached sponsorship for the operation when `reuse` allows it, otherwise a new signature
+    #[tracing::instrument(name = "sponsor", skip_all, fields(otel.name = telemetry::SPONSOR, paymaster = %self.paymaster_address, reused = false))]
+    async fn sponsor(
+        &self,
+        packed_user_op: PackedUserOperationData,
+        valid_until: u64,
+        valid_after: Option<u64>,
+        reuse: bool,
+    ) -> Result<PaymasterConfig> {
+        let key = sponsorship::operation_key(&self.service_url, self.paymaster_address, &packed_user_op);
+
+        if reuse {
+            if let Some(config) = self.cache.get(key, self.paymaster_address, now_secs()) {
+                tracing::Span::current().record("reused", true);
+                out!("♻️  Reusing the sponsorship for this operation, valid until {} (pass --responsor for a new one)", config.valid_until);
+                return Ok(config);
+            }
+        }
+
+        events::emit(ClientEvent::SponsorshipRequested {
+            sender: packed_user_op.sender.parse()?,
+            paymaster: self.paymaster_address,
+        });
+        let config = self.sign(packed_user_op, valid_until, valid_after).await?;
+        self.cache.insert(key, &config, now_secs());
+        Ok(config)
+    }
+
+    /// Ask the service to sign an operation
+    async fn sign(
+        &self,
+        packed_user_op: PackedUserOperationData,
+        valid_until: u64,
+        valid_after: Option<u64>,
+    ) -> Result<PaymasterConfig> {
+        // A pre-auth token stands in for the API key, which is then never sent
         let request = PaymasterServiceRequest {
-            api_key: self.api_key.clone(),
+            api_key: if self.preauth_token.is_some() { String::new() } else { self.api_key.clone() },
+            preauth_token: self.preauth_token.clone(),
             user_operation: packed_user_op,
             valid_until,
             valid_after,
         };
 
-        println!("🔧 Requesting paymaster sponsorship...");
-        println!("Service URL: {}", self.service_url);
-        println!("Valid until: {}", valid_until);
+        out!("🔧 Requesting paymaster sponsorship...");
+        out!("Service URL: {}", self.service_url);
+        out!("Valid until: {}", valid_until);
         
-        let response = self
-            .client
-            .post(&format!("{}/sign", self.service_url))
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Paymaster service error: {}", error_text));
+        let service_response: PaymasterServiceResponse = self.post("/sign", &request).await?;
+        if service_response.sandbox {
+            out!("🧪 Sandbox sponsorship: signed with the test verifier, only a sandbox paymaster accepts it");
         }
-
-        let service_response: PaymasterServiceResponse = response.json().await?;
         
-        // Check if this is a SimplePaymaster response (empty signature/data)
-        if service_response.signature == "0x" && service_response.paymaster_data == "0x" {
-            println!("SimplePaymaster detected - no signature needed");
-            
-            // For SimplePaymaster, create empty signature and data
-            let mut signature = [0u8; 65];
-            // Set a dummy signature that won't be used for validation
-            signature[0] = 0x00; // r starts with 0
-            
-            println!("SimplePaymaster sponsorship approved!");
-            println!("Signature: 0x{}", service_response.signature);
-            println!("Paymaster data: 0x{}", service_response.paymaster_data);
-
-            return Ok(PaymasterConfig {
-                paymaster_address: self.paymaster_address,
-                signature,
-                valid_until: service_response.valid_until,
-                valid_after: service_response.valid_after,
-            });
+        if service_response.paymaster_kind.as_deref() == Some("simple") || hexutil::strip_prefix(&service_response.signature).is_empty() {
+            return Err(anyhow::anyhow!(
+                "The paymaster service returned no signature, it sponsors a SimplePaymaster: pass --paymaster-mode simple"
+            ));
         }
         
         // Parse signature from hex for VerifierSignaturePaymaster
-        let signature_bytes = hex::decode(&service_response.signature)?;
-        if signature_bytes.len() != 65 {
-            return Err(anyhow::anyhow!("Invalid signature length: expected 65 bytes, got {}", signature_bytes.len()));
-        }
-        
-        let mut signature = [0u8; 65];
-        signature.copy_from_slice(&signature_bytes);
-
-        println!("VerifierSignaturePaymaster sponsorship approved!");
-        println!("Signature: 0x{}", service_response.signature);
-        println!("Paymaster data: 0x{}", service_response.paymaster_data);
-
-        Ok(PaymasterConfig {
+        let signature = hexutil::decode_fixed::<65>("paymaster signature", &service_response.signature)?;
+        let config = PaymasterConfig {
             paymaster_address: self.paymaster_address,
             signature,
             valid_until: service_response.valid_until,
             valid_after: service_response.valid_after,
+        };
+        if let Some(expected) = &self.expected_verifier {
+            expected.check(&request.user_operation, &config)?;
+            out!("🔏 Signature recovers to the paymaster's verifier {}", expected.verifier);
+        }
+
+        out!("VerifierSignaturePaymaster sponsorship approved!");
+        out!("Signature: 0x{}", service_response.signature);
+        out!("Paymaster data: 0x{}", service_response.paymaster_data);
+
+        Ok(config)
+    }
+
+    /// POST a request to the service in the configured encoding, retrying transient failures
+    async fn post<T: Serialize, R: DeserializeOwned>(&self, path: &str, request: &T) -> Result<R> {
+        let (body, content_type) = match self.wire_format {
+            WireFormat::Json => (serde_json::to_vec(request)?, JSON_CONTENT_TYPE),
+            WireFormat::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(request, &mut body)?;
+                (body, CBOR_CONTENT_TYPE)
+            }
+        };
+
+        // Error statuses carry "(HTTP nnn)" so gateway and rate-limit replies are retried, rejections are not
+        let body = &body;
+        let reply = retry::retry(&format!("Paymaster {}", path), move || async move {
+            let reply = self.exchange(path, content_type, body.clone()).await?;
+            if !(200..300).contains(&reply.status) {
+                let error_text = String::from_utf8_lossy(&reply.body);
+                // The retry policy reads the server's Retry-After back out of the message
+                return Err(match reply.retry_after {
+                    Some(delay) => anyhow::anyhow!("Paymaster service error (HTTP {}, retry after {}s): {}", reply.status, delay.as_secs(), error_text),
+                    None => anyhow::anyhow!("Paymaster service error (HTTP {}): {}", reply.status, error_text),
+                });
+            }
+            Ok(reply)
+        })
+        .await?;
+
+        // Decode by what the service actually sent, older services always answer JSON
+        let is_cbor = reply
+            .content_type
+            .as_deref()
+            .map(|v| v.contains(CBOR_CONTENT_TYPE))
+            .unwrap_or(false);
+        if is_cbor {
+            Ok(ciborium::from_reader(reply.body.as_slice())?)
+        } else {
+            Ok(serde_json::from_slice(&reply.body)?)
+        }
+    }
+
+    /// One request/reply round-trip over HTTP or a local socket
+    async fn exchange(&self, path: &str, content_type: &str, body: Vec<u8>) -> Result<LocalReply> {
+        if let Some(socket) = LocalSocket::from_url(&self.service_url) {
+            return transport::post(&socket, path, content_type, body).await;
+        }
+
+        let mut request = self.client
+            .post(format!("{}{}", self.service_url, path))
+            .header(CONTENT_TYPE, content_type)
+            .header(ACCEPT, content_type);
+        for (name, value) in telemetry::trace_headers() {
+            request = request.header(name, value);
+        }
+        let response = request.body(body).send().await?;
+        Ok(LocalReply {
+            status: response.status().as_u16(),
+            content_type: response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string()),
+            retry_after: response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(retry::parse_retry_after),
+            body: response.bytes().await?.to_vec(),
         })
     }
 
@@ -185,15 +694,8 @@ impl PaymasterService {
     /// Build paymasterAndData EXTRA DATA ONLY (v0.7): signature + validUntil + validAfter
     /// The bundler/EntryPoint prefixes address (20) + verificationGas (16) + postOpGas (16).
     /// Here we must only return the paymaster-specific data: 65 + 8 + 8 = 81 bytes.
-    /// For SimplePaymaster, returns empty data since no signature is needed.
     pub fn build_paymaster_and_data(&self, config: &PaymasterConfig) -> Bytes {
-        // Check if this is a SimplePaymaster (signature starts with 0x00)
-        if config.signature[0] == 0x00 {
-            println!("SimplePaymaster detected - returning empty paymaster data");
-            return Bytes::new(); // Empty data for SimplePaymaster
-        }
-        
-        // For VerifierSignaturePaymaster, build signature + validUntil + validAfter
+        // VerifierSignaturePaymaster: signature + validUntil + validAfter
         let mut data = Vec::new();
         
         // Signature (65 bytes: r || s || v)
@@ -232,3 +734,182 @@ impl std::fmt::Display for PaymasterError {
 }
 
 impl std::error::Error for PaymasterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_format_parsing() {
+        assert_eq!("json".parse::<WireFormat>().unwrap(), WireFormat::Json);
+        assert_eq!("CBOR".parse::<WireFormat>().unwrap(), WireFormat::Cbor);
+        assert!("protobuf".parse::<WireFormat>().is_err());
+        assert_eq!(WireFormat::default(), WireFormat::Json);
+    }
+
+    #[test]
+    fn test_paymaster_mode() {
+        let token = Address::from([0x11; 20]);
+        assert_eq!("simple".parse::<PaymasterMode>().unwrap(), PaymasterMode::Simple);
+        assert_eq!(format!("token:{}", token).parse::<PaymasterMode>().unwrap(), PaymasterMode::Token(token));
+        assert_eq!(PaymasterMode::Token(token).to_string().parse::<PaymasterMode>().unwrap(), PaymasterMode::Token(token));
+        assert!("token".parse::<PaymasterMode>().is_err());
+        assert!("token:0x12".parse::<PaymasterMode>().is_err());
+
+        assert_eq!(PaymasterMode::Verifying.unsigned_data(), None);
+        assert_eq!(PaymasterMode::Simple.unsigned_data(), Some(Bytes::new()));
+        assert_eq!(PaymasterMode::Token(token).unsigned_data().unwrap().as_ref(), token.as_slice());
+    }
+
+    #[test]
+    fn test_stub_is_replaced_by_signed_data() {
+        let paymaster = Address::from([0x22; 20]);
+        let stub = PaymasterStub::local(paymaster, PaymasterMode::Verifying);
+        // As long as signed data, so preVerificationGas covers the real one
+        assert_eq!(stub.paymaster_data.len(), 81);
+
+        let mut user_op = UserOperationRequest::default();
+        stub.attach(&mut user_op);
+        assert_eq!(user_op.paymaster, Some(paymaster));
+        assert_eq!(user_op.paymaster_verification_gas_limit, Some(U256::from(STUB_VERIFICATION_GAS_LIMIT)));
+
+        // A higher estimate survives attaching the stub again after filling
+        user_op.paymaster_verification_gas_limit = Some(U256::from(90_000));
+        stub.attach(&mut user_op);
+        assert_eq!(user_op.paymaster_verification_gas_limit, Some(U256::from(90_000)));
+
+        let simple = PaymasterStub::local(paymaster, PaymasterMode::Simple);
+        assert!(simple.paymaster_data.is_empty());
+    }
+
+    #[test]
+    fn test_signature_starting_with_zero_is_kept() {
+        let service = PaymasterService::new("http://localhost:3000".to_string(), String::new(), Address::ZERO)
+            .with_cache(SponsorshipCache::in_memory());
+        let config = PaymasterConfig { paymaster_address: Address::ZERO, signature: [0u8; 65], valid_until: 2, valid_after: 1 };
+        let data = service.build_paymaster_and_data(&config);
+        assert_eq!(data.len(), 81);
+        assert_eq!(data[80], 1);
+    }
+
+    #[test]
+    fn test_canonical_vectors() {
+        use aa_test_vectors::{unprefixed, SPONSORSHIPS};
+
+        for vector in SPONSORSHIPS {
+            let op = vector.user_operation;
+            let mut request = UserOperationRequest::default();
+            request.sender = Some(op.sender.parse().unwrap());
+            request.nonce = Some(op.nonce.parse().unwrap());
+            request.factory = op.factory.map(|factory| factory.parse().unwrap());
+            request.factory_data = op.factory.map(|_| op.factory_data.parse().unwrap());
+            request.call_data = Some(op.call_data.parse().unwrap());
+            request.verification_gas_limit = Some(U256::from(op.verification_gas_limit));
+            request.call_gas_limit = Some(U256::from(op.call_gas_limit));
+            request.pre_verification_gas = Some(U256::from(op.pre_verification_gas));
+            request.max_priority_fee_per_gas = Some(U256::from(op.max_priority_fee_per_gas));
+            request.max_fee_per_gas = Some(U256::from(op.max_fee_per_gas));
+
+            let paymaster: Address = vector.paymaster.parse().unwrap();
+            let service = PaymasterService::new("http://localhost:3000".to_string(), String::new(), paymaster);
+            let packed = service.convert_user_operation(&request).unwrap();
+            assert_eq!(packed.sender, op.sender, "{}", vector.name);
+            assert_eq!(packed.nonce, op.nonce, "{}", vector.name);
+            assert_eq!(packed.init_code, op.init_code, "{}", vector.name);
+            assert_eq!(packed.call_data, op.call_data, "{}", vector.name);
+            assert_eq!(packed.account_gas_limits, op.account_gas_limits, "{}", vector.name);
+            assert_eq!(packed.pre_verification_gas, op.pre_verification_gas.to_string(), "{}", vector.name);
+            assert_eq!(packed.gas_fees, op.gas_fees, "{}", vector.name);
+
+            let mut signature = [0u8; 65];
+            signature.copy_from_slice(&hex::decode(unprefixed(vector.signature)).unwrap());
+            let config = PaymasterConfig { paymaster_address: paymaster, signature, valid_until: vector.valid_until, valid_after: vector.valid_after };
+            assert_eq!(hex::encode(service.build_paymaster_and_data(&config)), unprefixed(vector.paymaster_data), "{}", vector.name);
+        }
+    }
+
+    #[test]
+    fn test_expected_verifier_recovers_vector_signatures() {
+        use aa_test_vectors::{unprefixed, SPONSORSHIPS};
+
+        for vector in SPONSORSHIPS {
+            let op = vector.user_operation;
+            let user_op = PackedUserOperationData {
+                sender: op.sender.to_string(),
+                nonce: op.nonce.to_string(),
+                init_code: op.init_code.to_string(),
+                call_data: op.call_data.to_string(),
+                account_gas_limits: op.account_gas_limits.to_string(),
+                pre_verification_gas: op.pre_verification_gas.to_string(),
+                gas_fees: op.gas_fees.to_string(),
+                paymaster_and_data: "0x".to_string(),
+            };
+            let paymaster: Address = vector.paymaster.parse().unwrap();
+            let hash = paymaster_hash(&user_op, vector.chain_id, paymaster, vector.valid_until, vector.valid_after).unwrap();
+            assert_eq!(hash, vector.paymaster_hash.parse::<B256>().unwrap(), "{}", vector.name);
+
+            let mut signature = [0u8; 65];
+            signature.copy_from_slice(&hex::decode(unprefixed(vector.signature)).unwrap());
+            let config = PaymasterConfig { paymaster_address: paymaster, signature, valid_until: vector.valid_until, valid_after: vector.valid_after };
+            let verifier: Address = vector.verifier.parse().unwrap();
+            ExpectedVerifier { chain_id: vector.chain_id, verifier }.check(&user_op, &config).unwrap();
+
+            // A service signing for another chain, paymaster or key is caught
+            assert!(ExpectedVerifier { chain_id: vector.chain_id + 1, verifier }.check(&user_op, &config).is_err());
+            assert!(ExpectedVerifier { chain_id: vector.chain_id, verifier: Address::ZERO }.check(&user_op, &config).is_err());
+            let other_paymaster = PaymasterConfig { paymaster_address: Address::ZERO, ..config };
+            assert!(ExpectedVerifier { chain_id: vector.chain_id, verifier }.check(&user_op, &other_paymaster).is_err());
+        }
+    }
+
+    #[test]
+    fn test_cbor_request_encoding() {
+        let request = PaymasterServiceRequest {
+            api_key: "api_key_123".to_string(),
+            preauth_token: None,
+            user_operation: PackedUserOperationData {
+                sender: "0x1234567890123456789012345678901234567890".to_string(),
+                nonce: "1".to_string(),
+                init_code: "0x".to_string(),
+                call_data: "0x1234".to_string(),
+                account_gas_limits: "0x00000000000f424000000000000f4240".to_string(),
+                pre_verification_gas: "21000".to_string(),
+                gas_fees: "0x000000000077359400000000003b9aca00".to_string(),
+                paymaster_and_data: "0x".to_string(),
+            },
+            valid_until: 1_700_000_000,
+            valid_after: Some(0),
+        };
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&request, &mut cbor).unwrap();
+        let json = serde_json::to_vec(&request).unwrap();
+        assert!(cbor.len() < json.len());
+
+        let decoded: serde_json::Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(decoded["user_operation"]["nonce"], "1");
+        assert_eq!(decoded["valid_until"], 1_700_000_000u64);
+    }
+
+    #[test]
+    fn test_sponsorship_expiry_detection() {
+        let bundler = anyhow::anyhow!("UserOperation reverted during simulation with reason: AA32 paymaster expired or not due");
+        assert!(is_sponsorship_expired(&bundler));
+        let wrapped = anyhow::anyhow!("AA32 paymaster expired or not due").context("eth_sendUserOperation failed");
+        assert!(is_sponsorship_expired(&wrapped));
+        assert!(!is_sponsorship_expired(&anyhow::anyhow!("AA33 reverted")));
+    }
+
+    #[test]
+    fn test_preauth_link_round_trip() {
+        let grant = PreAuthGrant {
+            token: "pa_abc123".to_string(),
+            sender: "0x1234567890123456789012345678901234567890".to_string(),
+            expires_at: 1_700_000_000,
+        };
+        let link = grant.link("http://localhost:3000");
+        assert!(link.starts_with("aa-preauth:pa_abc123?"));
+        assert_eq!(parse_preauth_token(&link), "pa_abc123");
+        assert_eq!(parse_preauth_token(" pa_abc123 "), "pa_abc123");
+    }
+}
@@ -0,0 +1,148 @@
+// EntryPoint deposit of a SimplePaymaster or VerifierSignaturePaymaster, managed from the smart account
+// Both inherit deposit() and the owner-only withdrawTo() from BasePaymaster
+// Before a sponsored operation is sent, the deposit is checked against the most the operation can cost,
+// which the EntryPoint takes up front and otherwise rejects with AA31
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+
+use crate::erc7562::IEntryPointStakes;
+use crate::multisig::UnsignedUserOperation;
+use crate::units;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IPaymasterFunds {
+        function deposit() external payable;
+        function withdrawTo(address withdrawAddress, uint256 amount) external;
+        function getDeposit() external view returns (uint256);
+        function owner() external view returns (address);
+    }
+);
+
+/// Calldata for `deposit()`, the value sent with the call is what gets deposited
+pub fn deposit_call_data() -> Bytes {
+    IPaymasterFunds::depositCall {}.abi_encode().into()
+}
+
+/// Calldata for `withdrawTo(to, amount)`
+pub fn withdraw_call_data(to: Address, amount: U256) -> Bytes {
+    IPaymasterFunds::withdrawToCall { withdrawAddress: to, amount }.abi_encode().into()
+}
+
+/// Paymaster's deposit at its EntryPoint
+pub async fn deposit<P: Provider>(provider: &P, paymaster: Address) -> Result<U256> {
+    IPaymasterFunds::new(paymaster, provider)
+        .getDeposit()
+        .call()
+        .await
+        .map(|deposit| deposit._0)
+        .map_err(|e| anyhow::anyhow!("Could not read getDeposit() of paymaster {}: {}", paymaster, e))
+}
+
+/// Fail before submitting when `account` may not withdraw or the deposit does not cover `amount`
+pub async fn check_withdrawal<P: Provider>(provider: &P, paymaster: Address, account: Address, amount: U256) -> Result<()> {
+    let owner = IPaymasterFunds::new(paymaster, provider).owner().call().await?._0;
+    if owner != account {
+        return Err(anyhow::anyhow!("Only the paymaster owner {} can withdraw, the smart account is {}", owner, account));
+    }
+    let available = deposit(provider, paymaster).await?;
+    if amount > available {
+        return Err(anyhow::anyhow!("Paymaster {} has {} wei deposited, cannot withdraw {}", paymaster, available, amount));
+    }
+    Ok(())
+}
+
+/// Deposit and stake a paymaster holds at its EntryPoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backing {
+    pub deposit: U256,
+    pub stake: U256,
+    /// False while the stake is unlocked for withdrawal, even if some is left
+    pub staked: bool,
+    pub unstake_delay_sec: u32,
+}
+
+impl Backing {
+    /// Problems sponsoring an operation that may cost up to `max_cost` would run into
+    pub fn warnings(&self, paymaster: Address, max_cost: U256) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.deposit < max_cost {
+            warnings.push(format!(
+                "Paymaster {} has {} ETH deposited but the operation may cost up to {} ETH, bundlers reject it with AA31 (top it up with `paymaster deposit`)",
+                paymaster,
+                units::format_units(self.deposit, 18),
+                units::format_units(max_cost, 18)
+            ));
+        }
+        if !self.staked && self.stake > U256::ZERO {
+            warnings.push(format!(
+                "Paymaster {} unlocked its stake of {} ETH, bundlers treat it as unstaked",
+                paymaster,
+                units::format_units(self.stake, 18)
+            ));
+        }
+        warnings
+    }
+}
+
+/// Paymaster's deposit and stake from `getDepositInfo` of the EntryPoint
+pub async fn backing<P: Provider>(provider: &P, entry_point: Address, paymaster: Address) -> Result<Backing> {
+    let info = IEntryPointStakes::new(entry_point, provider).getDepositInfo(paymaster).call().await?.info;
+    Ok(Backing {
+        deposit: info.deposit,
+        stake: U256::from(info.stake),
+        staked: info.staked,
+        unstake_delay_sec: info.unstakeDelaySec,
+    })
+}
+
+/// Most the EntryPoint can charge for `user_op`: every gas limit at maxFeePerGas (the required prefund)
+pub fn max_cost(user_op: &UnsignedUserOperation) -> U256 {
+    let gas = user_op.call_gas_limit
+        + user_op.verification_gas_limit
+        + user_op.pre_verification_gas
+        + user_op.paymaster_verification_gas_limit.unwrap_or_default()
+        + user_op.paymaster_post_op_gas_limit.unwrap_or_default();
+    gas * user_op.max_fee_per_gas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_data_selectors() {
+        assert_eq!(deposit_call_data().as_ref(), &[0xd0, 0xe3, 0x0d, 0xb0]);
+
+        let to = Address::from([0x12; 20]);
+        let withdraw = withdraw_call_data(to, U256::from(5));
+        assert_eq!(&withdraw[..4], &[0x20, 0x5c, 0x28, 0x78]);
+        assert_eq!(&withdraw[16..36], to.as_slice());
+        assert_eq!(withdraw[67], 5);
+    }
+
+    #[test]
+    fn test_deposit_warnings() {
+        let paymaster = Address::from([0x22; 20]);
+        let mut user_op = UnsignedUserOperation::execute(Address::from([9u8; 20]), U256::ZERO, paymaster, U256::ZERO, Bytes::new());
+        user_op.call_gas_limit = U256::from(100_000);
+        user_op.verification_gas_limit = U256::from(50_000);
+        user_op.pre_verification_gas = U256::from(40_000);
+        user_op.paymaster_verification_gas_limit = Some(U256::from(10_000));
+        user_op.max_fee_per_gas = U256::from(2);
+        assert_eq!(max_cost(&user_op), U256::from(400_000));
+
+        let backing = Backing { deposit: U256::from(400_000), stake: U256::ZERO, staked: false, unstake_delay_sec: 0 };
+        assert!(backing.warnings(paymaster, max_cost(&user_op)).is_empty());
+        let short = backing.warnings(paymaster, U256::from(400_001));
+        assert!(short[0].contains("AA31"));
+
+        let unlocked = Backing { stake: U256::from(1), ..backing };
+        assert!(unlocked.warnings(paymaster, U256::ZERO)[0].contains("unlocked"));
+    }
+}
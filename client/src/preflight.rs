@@ -0,0 +1,356 @@
+// Local simulation of a signed UserOperation before it is sent to the bundler
+// Validation runs as an eth_call of `handleOps`, where every AA1x-AA9x failure reverts with FailedOp and its code.
+// `handleOps` swallows reverts of the account call itself, so the call is also replayed from the EntryPoint.
+// v0.7 moved simulateValidation/simulateHandleOp into EntryPointSimulations, which only runs as a code override
+// of the EntryPoint; the deployed `handleOps` reports the same AA codes without shipping that bytecode.
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{address, Address, Bytes};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolError};
+use alloy::transports::TransportError;
+use anyhow::Result;
+use std::sync::OnceLock;
+
+use crate::multisig::{pack_u128_pair, UnsignedUserOperation};
+use crate::out;
+
+sol!(
+    #[allow(missing_docs)]
+    struct PackedUserOperation {
+        address sender;
+        uint256 nonce;
+        bytes initCode;
+        bytes callData;
+        bytes32 accountGasLimits;
+        uint256 preVerificationGas;
+        bytes32 gasFees;
+        bytes paymasterAndData;
+        bytes signature;
+    }
+
+    #[allow(missing_docs)]
+    interface IEntryPointHandleOps {
+        function handleOps(PackedUserOperation[] ops, address beneficiary) external;
+        error FailedOp(uint256 opIndex, string reason);
+        error FailedOpWithRevert(uint256 opIndex, string reason, bytes inner);
+    }
+);
+
+/// Caller and fee recipient of the simulated `handleOps`, never holds funds
+const SIMULATION_BENEFICIARY: Address = address!("000000000000000000000000000000000000dEaD");
+
+/// Why the simulation rejected an operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightFailure {
+    /// The EntryPoint rejected the operation while validating it (account, factory or paymaster)
+    Validation {
+        /// `AA23` and friends, when the reason carries one
+        code: Option<String>,
+        reason: String,
+        /// Decoded revert of the account or paymaster behind the failure
+        inner: Option<String>,
+    },
+    /// Validation passes but the account call reverts, the operation would land and fail
+    Execution { reason: String },
+}
+
+impl PreflightFailure {
+    /// Explanation for terminal output, with a hint for known AA codes
+    pub fn describe(&self) -> String {
+        match self {
+            PreflightFailure::Validation { code, reason, inner } => {
+                let mut message = format!("validation failed: {}", reason);
+                if let Some(inner) = inner {
+                    message.push_str(&format!(" ({})", inner));
+                }
+                if let Some(hint) = code.as_deref().and_then(aa_hint) {
+                    message.push_str(&format!("\n💡 {}", hint));
+                }
+                message
+            }
+            PreflightFailure::Execution { reason } => format!("the account call reverts: {}", reason),
+        }
+    }
+}
+
+/// Error for a simulation that rejected the operation, so callers can tell it from a failed send
+#[derive(Debug)]
+pub struct Rejected(pub PreflightFailure);
+
+impl std::fmt::Display for Rejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "❌ Simulation rejected the UserOperation, {}\n(pass --no-preflight to send it anyway)", self.0.describe())
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// What usually causes an EntryPoint error code
+pub fn aa_hint(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "AA10" => "the account already exists, drop factory and factoryData",
+        "AA13" => "the factory call reverted or ran out of verificationGasLimit",
+        "AA14" => "the factory returned a different address than sender, check owner and salt",
+        "AA15" => "the factory did not deploy code at sender",
+        "AA20" => "the account is not deployed and the operation has no factory",
+        "AA21" => "the account cannot pay the prefund, fund it or deposit to the EntryPoint",
+        "AA22" => "the signature is outside its validAfter/validUntil window",
+        "AA23" => "validateUserOp reverted, see the inner revert",
+        "AA24" => "the signature does not match an owner of the account",
+        "AA25" => "the nonce is not the next one for its key, another operation used it",
+        "AA26" => "validation used more than verificationGasLimit",
+        "AA30" => "the paymaster address has no code",
+        "AA31" => "the paymaster deposit on the EntryPoint is too low",
+        "AA32" => "the sponsorship expired or is not valid yet, request a new one",
+        "AA33" => "validatePaymasterUserOp reverted, see the inner revert",
+        "AA34" => "the paymaster signature is invalid",
+        "AA36" => "paymaster validation used more than paymasterVerificationGasLimit",
+        "AA40" => "validation used more than verificationGasLimit",
+        "AA41" => "verificationGasLimit is too low for the prefund accounting",
+        "AA50" => "the paymaster postOp reverted",
+        "AA51" => "the prefund does not cover the actual gas cost",
+        "AA93" => "paymasterAndData is shorter than the 52-byte header",
+        "AA94" => "a gas value overflows uint120",
+        "AA95" => "handleOps ran out of gas",
+        _ => return None,
+    })
+}
+
+/// FailedOp or FailedOpWithRevert data from a reverted `handleOps`
+pub fn decode_validation_revert(data: &[u8]) -> Option<PreflightFailure> {
+    let (reason, inner) = if let Ok(failed) = IEntryPointHandleOps::FailedOp::abi_decode(data, true) {
+        (failed.reason, None)
+    } else if let Ok(failed) = IEntryPointHandleOps::FailedOpWithRevert::abi_decode(data, true) {
+        (failed.reason, Some(decode_revert(&failed.inner)))
+    } else {
+        return None;
+    };
+    let code = reason
+        .get(..4)
+        .filter(|code| code.starts_with("AA") && code[2..].chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string);
+    Some(PreflightFailure::Validation { code, reason, inner })
+}
+
+/// Error(string), Panic(uint256) or the raw selector of a custom error
+pub fn decode_revert(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "reverted without data".to_string();
+    }
+    alloy::sol_types::decode_revert_reason(data)
+        .unwrap_or_else(|| format!("custom error 0x{}", hex::encode(&data[..data.len().min(4)])))
+}
+
+/// v0.7 packed form of a signed operation, as `handleOps` takes it
+pub fn pack(user_op: &UnsignedUserOperation, signature: Bytes) -> PackedUserOperation {
+    PackedUserOperation {
+        sender: user_op.sender,
+        nonce: user_op.nonce,
+        initCode: user_op.init_code().into(),
+        callData: user_op.call_data.clone(),
+        accountGasLimits: pack_u128_pair(user_op.verification_gas_limit, user_op.call_gas_limit),
+        preVerificationGas: user_op.pre_verification_gas,
+        gasFees: pack_u128_pair(user_op.max_priority_fee_per_gas, user_op.max_fee_per_gas),
+        paymasterAndData: user_op.paymaster_and_data().into(),
+        signature,
+    }
+}
+
+/// Simulate a signed operation against `entry_point`; None when it would validate and execute
+pub async fn simulate<P: Provider>(
+    provider: &P,
+    entry_point: Address,
+    user_op: &UnsignedUserOperation,
+    signature: Bytes,
+) -> Result<Option<PreflightFailure>> {
+    let handle_ops = IEntryPointHandleOps::handleOpsCall {
+        ops: vec![pack(user_op, signature)],
+        beneficiary: SIMULATION_BENEFICIARY,
+    };
+    let tx = TransactionRequest::default()
+        .with_from(SIMULATION_BENEFICIARY)
+        .with_to(entry_point)
+        .with_input(Bytes::from(handle_ops.abi_encode()));
+    if let Err(e) = provider.call(tx).await {
+        let data = revert_data(&e).ok_or(e)?;
+        return match decode_validation_revert(&data) {
+            Some(failure) => Ok(Some(failure)),
+            None => Ok(Some(PreflightFailure::Validation { code: None, reason: decode_revert(&data), inner: None })),
+        };
+    }
+
+    // The account call only runs from the EntryPoint; before deployment there is no code to replay against
+    if user_op.factory.is_none() {
+        let tx = TransactionRequest::default()
+            .with_from(entry_point)
+            .with_to(user_op.sender)
+            .with_input(user_op.call_data.clone());
+        if let Err(e) = provider.call(tx).await {
+            let data = revert_data(&e).ok_or(e)?;
+            return Ok(Some(PreflightFailure::Execution { reason: decode_revert(&data) }));
+        }
+    }
+    Ok(None)
+}
+
+fn revert_data(error: &TransportError) -> Option<Bytes> {
+    error.as_error_resp().and_then(|payload| payload.as_revert_data())
+}
+
+static SKIP: OnceLock<bool> = OnceLock::new();
+
+/// Turn preflight simulation off for the rest of the process (`--no-preflight`)
+pub fn set_skip(skip: bool) {
+    let _ = SKIP.set(skip);
+}
+
+pub fn enabled() -> bool {
+    !SKIP.get().copied().unwrap_or(false)
+}
+
+static DRY_RUN: OnceLock<bool> = OnceLock::new();
+
+/// Stop every mutating command right before `eth_sendUserOperation` (`--dry-run`)
+pub fn set_dry_run(dry_run: bool) {
+    let _ = DRY_RUN.set(dry_run);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.get().copied().unwrap_or(false)
+}
+
+/// Error for call data sent to an address without code: nothing runs, yet the operation succeeds and pays for gas
+#[derive(Debug)]
+pub struct NoCodeAtTarget(pub Address);
+
+impl std::fmt::Display for NoCodeAtTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Target {} has no contract code, so its call data would not run and the operation would succeed doing nothing", self.0)
+    }
+}
+
+impl std::error::Error for NoCodeAtTarget {}
+
+static ALLOW_EOA_TARGET: OnceLock<bool> = OnceLock::new();
+
+/// Only warn about call data sent to addresses without code for the rest of the process (`--allow-eoa-target`)
+pub fn set_allow_eoa_target(allow: bool) {
+    let _ = ALLOW_EOA_TARGET.set(allow);
+}
+
+/// Whether a call with `call_data` to an account holding `code` reaches nothing; plain transfers never do
+pub fn reaches_no_code(code: &[u8], call_data: &[u8]) -> bool {
+    code.is_empty() && !call_data.is_empty()
+}
+
+/// Fail before building an operation whose call data goes to an address without code
+/// A node that cannot answer eth_getCode only skips the check
+pub async fn check_target<P: Provider>(provider: &P, target: Address, call_data: &[u8]) -> Result<()> {
+    if call_data.is_empty() {
+        return Ok(());
+    }
+    let code = match provider.get_code_at(target).await {
+        Ok(code) => code,
+        Err(e) => {
+            out!("⚠️  Could not check for code at target {}: {}", target, e);
+            return Ok(());
+        }
+    };
+    if !reaches_no_code(&code, call_data) {
+        return Ok(());
+    }
+    if ALLOW_EOA_TARGET.get().copied().unwrap_or(false) {
+        out!("⚠️  {} (allowed by --allow-eoa-target)", NoCodeAtTarget(target));
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(NoCodeAtTarget(target))
+        .context("Check the target address and chain, or pass --allow-eoa-target if nothing should run"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use alloy::primitives::U256;
+
+    #[test]
+    fn test_decode_failed_op() {
+        let data = IEntryPointHandleOps::FailedOp { opIndex: U256::ZERO, reason: "AA25 invalid account nonce".to_string() }.abi_encode();
+        let failure = decode_validation_revert(&data).unwrap();
+        assert_eq!(
+            failure,
+            PreflightFailure::Validation { code: Some("AA25".to_string()), reason: "AA25 invalid account nonce".to_string(), inner: None }
+        );
+        assert!(failure.describe().contains("another operation used it"));
+    }
+
+    #[test]
+    fn test_decode_failed_op_with_inner_revert() {
+        let inner = alloy::sol_types::Revert { reason: "AAAccount: not owner".to_string() }.abi_encode();
+        let data = IEntryPointHandleOps::FailedOpWithRevert {
+            opIndex: U256::ZERO,
+            reason: "AA23 reverted".to_string(),
+            inner: inner.into(),
+        }
+        .abi_encode();
+
+        match decode_validation_revert(&data).unwrap() {
+            PreflightFailure::Validation { code, inner, .. } => {
+                assert_eq!(code.as_deref(), Some("AA23"));
+                assert_eq!(inner.as_deref(), Some("revert: AAAccount: not owner"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(decode_validation_revert(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(decode_revert(&[0xde, 0xad, 0xbe, 0xef, 0x00]), "custom error 0xdeadbeef");
+    }
+
+    #[test]
+    fn test_pack_matches_hashed_fields() {
+        let mut user_op = UnsignedUserOperation::execute(Address::from([0x11; 20]), U256::from(3), Address::ZERO, U256::ZERO, Bytes::new());
+        user_op.verification_gas_limit = U256::from(100_000);
+        user_op.call_gas_limit = U256::from(50_000);
+        let packed = pack(&user_op, Bytes::from(vec![0u8; 65]));
+
+        assert_eq!(packed.accountGasLimits.as_slice()[..16], 100_000u128.to_be_bytes());
+        assert_eq!(packed.accountGasLimits.as_slice()[16..], 50_000u128.to_be_bytes());
+        assert!(packed.initCode.is_empty());
+        assert_eq!(packed.signature.len(), 65);
+    }
+
+    #[test]
+    fn test_call_data_to_address_without_code() {
+        assert!(reaches_no_code(&[], &[0xa9, 0x05, 0x9c, 0xbb]));
+        // A plain ETH transfer to an EOA is fine
+        assert!(!reaches_no_code(&[], &[]));
+        assert!(!reaches_no_code(&[0x60, 0x80], &[0xa9, 0x05, 0x9c, 0xbb]));
+        // EIP-7702 delegated EOAs carry a delegation designator as code
+        assert!(!reaches_no_code(&[0xef, 0x01, 0x00], &[0x01]));
+    }
+
+    proptest! {
+        #[test]
+        fn pack_puts_gas_fields_where_the_entry_point_reads_them(
+            verification_gas_limit: u128,
+            call_gas_limit: u128,
+            max_priority_fee_per_gas: u128,
+            max_fee_per_gas: u128,
+        ) {
+            use alloy::sol_types::SolValue;
+
+            let mut user_op = UnsignedUserOperation::execute(Address::ZERO, U256::ZERO, Address::ZERO, U256::ZERO, Bytes::new());
+            user_op.verification_gas_limit = U256::from(verification_gas_limit);
+            user_op.call_gas_limit = U256::from(call_gas_limit);
+            user_op.max_priority_fee_per_gas = U256::from(max_priority_fee_per_gas);
+            user_op.max_fee_per_gas = U256::from(max_fee_per_gas);
+
+            // UserOperationLib: verification gas and priority fee in the high 128 bits
+            let packed = pack(&user_op, Bytes::new());
+            prop_assert_eq!(packed.accountGasLimits.to_vec(), (verification_gas_limit, call_gas_limit).abi_encode_packed());
+            prop_assert_eq!(packed.gasFees.to_vec(), (max_priority_fee_per_gas, max_fee_per_gas).abi_encode_packed());
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// Shared HTTP providers, one per RPC URL for the life of the process
+// Every `ProviderBuilder::on_http` opens its own connection pool, so a command going through several
+// helpers paid a TLS handshake per helper. Clones of a registered provider share its pool instead
+// Reads that rarely change for an endpoint (chain ID, supported EntryPoints, a factory's address for the
+// same owners and salt, code once deployed) are kept too, so helpers asking again cost no round-trip.
+// This is the only cache of these reads; BundlerClient and the CLI helpers all go through it
+
+use alloy::network::{Ethereum, TransactionBuilder};
+use alloy::primitives::{Address, U256};
+use alloy::providers::fillers::{FillProvider, JoinFill, RecommendedFillers};
+use alloy::providers::{Identity, Provider, ProviderBuilder, RootProvider};
+use alloy::rpc::types::TransactionRequest;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+use crate::cache::ResponseCache;
+use crate::factory::{self, FactoryAbi};
+
+/// A URL can start serving another chain (a restarted anvil, a fork, a re-pointed endpoint), so its chain ID
+/// is only kept for a few minutes and refreshed as soon as a caller sees a mismatch
+const CHAIN_ID_TTL: Duration = Duration::from_secs(5 * 60);
+/// A bundler may start supporting another EntryPoint after an upgrade
+const SUPPORTED_ENTRY_POINTS_TTL: Duration = Duration::from_secs(10 * 60);
+/// Chain state cannot change for the same call, the TTL only bounds what a long-running process keeps
+const PREDICTED_ADDRESS_TTL: Duration = Duration::from_secs(60 * 60);
+const CODE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// What `ProviderBuilder::new().on_http(..)` builds: a plain HTTP provider with the recommended fillers
+pub type HttpProvider = FillProvider<JoinFill<Identity, <Ethereum as RecommendedFillers>::RecommendedFillers>, RootProvider>;
+
+/// Providers built so far, keyed by normalized URL, and the reads made through them
+pub struct ProviderRegistry {
+    providers: Mutex<HashMap<String, HttpProvider>>,
+    /// What each endpoint serves (chain ID, EntryPoints), persisted by the process-wide registry
+    endpoints: ResponseCache,
+    /// Chain state (predicted addresses, code), for this process only since a restarted node loses it
+    reads: ResponseCache,
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_url(rpc_url: &str) -> Result<Url> {
+    Url::parse(rpc_url).map_err(|e| anyhow::anyhow!("Invalid RPC URL '{}': {}", rpc_url, e))
+}
+
+impl ProviderRegistry {
+    /// Registry keeping every read in memory
+    pub fn new() -> Self {
+        Self {
+            providers: Mutex::new(HashMap::new()),
+            endpoints: ResponseCache::in_memory(),
+            reads: ResponseCache::in_memory(),
+        }
+    }
+
+    /// Keep what endpoints serve in `cache`, e.g. the persistent one so repeated invocations skip the round-trips
+    pub fn with_endpoint_cache(mut self, cache: ResponseCache) -> Self {
+        self.endpoints = cache;
+        self
+    }
+
+    /// Provider for `rpc_url`, built on first use and cloned (sharing connections) afterwards
+    pub fn get(&self, rpc_url: &str) -> Result<HttpProvider> {
+        let url = parse_url(rpc_url)?;
+        let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+        let provider = providers
+            .entry(url.to_string())
+            .or_insert_with(|| ProviderBuilder::new().on_http(url));
+        Ok(provider.clone())
+    }
+
+    /// Number of distinct endpoints connected to
+    pub fn len(&self) -> usize {
+        self.providers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cache key of `read` on `rpc_url`, the same for every spelling of the URL
+    pub fn read_key(rpc_url: &str, read: &str) -> Result<String> {
+        Ok(format!("{}|{}", parse_url(rpc_url)?, read))
+    }
+
+    /// `eth_chainId` of `rpc_url`
+    pub async fn chain_id(&self, rpc_url: &str) -> Result<u64> {
+        match self.cached_chain_id(rpc_url) {
+            Some(chain_id) => Ok(chain_id),
+            None => self.fresh_chain_id(rpc_url).await,
+        }
+    }
+
+    /// `eth_chainId` of `rpc_url` if it was asked recently
+    pub fn cached_chain_id(&self, rpc_url: &str) -> Option<u64> {
+        self.endpoints.get(&Self::read_key(rpc_url, "eth_chainId").ok()?)
+    }
+
+    /// `eth_chainId` asked of `rpc_url` itself, replacing the cached value for later reads
+    pub async fn fresh_chain_id(&self, rpc_url: &str) -> Result<u64> {
+        let key = Self::read_key(rpc_url, "eth_chainId")?;
+        let chain_id = self.get(rpc_url)?.get_chain_id().await?;
+        self.endpoints.insert(&key, &chain_id, CHAIN_ID_TTL);
+        Ok(chain_id)
+    }
+
+    /// `eth_supportedEntryPoints` of the bundler at `rpc_url`
+    pub async fn supported_entry_points(&self, rpc_url: &str) -> Result<Vec<Address>> {
+        match self.cached_supported_entry_points(rpc_url) {
+            Some(entry_points) => Ok(entry_points),
+            None => self.fresh_supported_entry_points(rpc_url).await,
+        }
+    }
+
+    /// `eth_supportedEntryPoints` of the bundler at `rpc_url` if it was asked recently
+    pub fn cached_supported_entry_points(&self, rpc_url: &str) -> Option<Vec<Address>> {
+        self.endpoints.get(&Self::read_key(rpc_url, "eth_supportedEntryPoints").ok()?)
+    }
+
+    /// `eth_supportedEntryPoints` asked of the bundler at `rpc_url` itself, replacing the cached value
+    pub async fn fresh_supported_entry_points(&self, rpc_url: &str) -> Result<Vec<Address>> {
+        let key = Self::read_key(rpc_url, "eth_supportedEntryPoints")?;
+        let entry_points: Vec<Address> = self
+            .get(rpc_url)?
+            .raw_request("eth_supportedEntryPoints".into(), Vec::<serde_json::Value>::new())
+            .await?;
+        self.endpoints.insert(&key, &entry_points, SUPPORTED_ENTRY_POINTS_TTL);
+        Ok(entry_points)
+    }
+
+    /// Where `factory` deploys the account of `owners` and `salt`, asked once per distinct call
+    pub async fn predicted_address(&self, rpc_url: &str, factory: Address, abi: FactoryAbi, owners: &[Address], salt: U256) -> Result<Address> {
+        let call_data = abi.encoding().get_address_call(owners, salt)?;
+        let key = Self::read_key(rpc_url, &format!("eth_call|{}|{}", factory, call_data))?;
+        if let Some(address) = self.reads.get(&key) {
+            return Ok(address);
+        }
+        let tx = TransactionRequest::default().with_to(factory).with_input(call_data);
+        let address = factory::decode_address(&self.get(rpc_url)?.call(tx).await?)?;
+        self.reads.insert(&key, &address, PREDICTED_ADDRESS_TTL);
+        Ok(address)
+    }
+
+    /// Whether `address` has code; only deployed code is kept, an empty account is asked again
+    /// since the command may be about to deploy it
+    pub async fn has_code(&self, rpc_url: &str, address: Address) -> Result<bool> {
+        let key = Self::read_key(rpc_url, &format!("eth_getCode|{}", address))?;
+        if self.reads.get::<bool>(&key).is_some() {
+            return Ok(true);
+        }
+        let deployed = !self.get(rpc_url)?.get_code_at(address).await?.is_empty();
+        if deployed {
+            self.reads.insert(&key, &true, CODE_TTL);
+        }
+        Ok(deployed)
+    }
+}
+
+static REGISTRY: OnceLock<ProviderRegistry> = OnceLock::new();
+
+/// The process-wide registry used by the CLI and library helpers, keeping what endpoints serve across invocations
+pub fn registry() -> &'static ProviderRegistry {
+    REGISTRY.get_or_init(|| ProviderRegistry::new().with_endpoint_cache(ResponseCache::persistent()))
+}
+
+/// Shared provider for `rpc_url`
+pub fn http(rpc_url: &str) -> Result<HttpProvider> {
+    registry().get(rpc_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_provider_per_url() {
+        let registry = ProviderRegistry::new();
+        registry.get("http://localhost:8545").unwrap();
+        // Url normalization makes the trailing slash the same endpoint
+        registry.get("http://localhost:8545/").unwrap();
+        assert_eq!(registry.len(), 1);
+
+        registry.get("http://localhost:3000").unwrap();
+        assert_eq!(registry.len(), 2);
+
+        assert!(registry.get("not a url").is_err());
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cached_reads_skip_the_endpoint() {
+        // Nothing listens on port 1, so only a cached read can succeed
+        let rpc_url = "http://127.0.0.1:1";
+        let registry = ProviderRegistry::new();
+        registry.endpoints.insert(&ProviderRegistry::read_key(rpc_url, "eth_chainId").unwrap(), &31337u64, CHAIN_ID_TTL);
+        assert_eq!(registry.chain_id("http://127.0.0.1:1/").await.unwrap(), 31337);
+        assert!(registry.fresh_chain_id(rpc_url).await.is_err());
+        assert!(registry.supported_entry_points(rpc_url).await.is_err());
+
+        let (factory, owner) = (Address::repeat_byte(0xfa), Address::repeat_byte(0x01));
+        let abi = factory::resolve(FactoryAbi::Simple);
+        let call_data = abi.encoding().get_address_call(&[owner], U256::from(7)).unwrap();
+        let key = ProviderRegistry::read_key(rpc_url, &format!("eth_call|{}|{}", factory, call_data)).unwrap();
+        registry.reads.insert(&key, &Address::repeat_byte(0xaa), PREDICTED_ADDRESS_TTL);
+        assert_eq!(registry.predicted_address(rpc_url, factory, abi, &[owner], U256::from(7)).await.unwrap(), Address::repeat_byte(0xaa));
+        // Another salt is another call
+        assert!(registry.predicted_address(rpc_url, factory, abi, &[owner], U256::from(8)).await.is_err());
+    }
+}
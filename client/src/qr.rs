@@ -0,0 +1,246 @@
+// QR handoff of prepared operations and sponsorship pre-auths between devices without files or networks
+// Payloads are split into numbered chunks so large proposals fit in scannable codes
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{EcLevel, QrCode};
+
+use crate::{hexutil, out, output};
+
+/// Prefix of every chunk, so stray scans are rejected early
+const CHUNK_PREFIX: &str = "AA";
+/// Payload bytes per chunk, 300 bytes keeps each code readable by phone cameras on a terminal
+pub const CHUNK_BYTES: usize = 300;
+
+/// What a chunked payload carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    /// Multi-owner proposal JSON, see `multisig::Proposal`
+    Proposal,
+    /// Pre-auth sponsorship link
+    PreAuth,
+}
+
+impl PayloadKind {
+    fn code(self) -> char {
+        match self {
+            PayloadKind::Proposal => 'P',
+            PayloadKind::PreAuth => 'A',
+        }
+    }
+
+    fn from_code(code: &str) -> Result<Self> {
+        match code {
+            "P" => Ok(PayloadKind::Proposal),
+            "A" => Ok(PayloadKind::PreAuth),
+            other => Err(anyhow::anyhow!("Unknown QR payload kind '{}'", other)),
+        }
+    }
+}
+
+/// Short checksum tying the chunks of one payload together
+fn checksum(payload: &[u8]) -> String {
+    hex::encode_upper(&alloy::primitives::keccak256(payload)[..4])
+}
+
+/// Split a payload into chunk strings `AA:<kind>:<index>/<total>:<checksum>:<hex>`
+/// Uppercase hex and these separators are all in the QR alphanumeric set, which packs denser than byte mode
+pub fn encode_chunks(kind: PayloadKind, payload: &[u8]) -> Vec<String> {
+    let checksum = checksum(payload);
+    let parts: Vec<&[u8]> = if payload.is_empty() {
+        vec![&[]]
+    } else {
+        payload.chunks(CHUNK_BYTES).collect()
+    };
+    let total = parts.len();
+
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            format!("{}:{}:{}/{}:{}:{}", CHUNK_PREFIX, kind.code(), i + 1, total, checksum, hex::encode_upper(part))
+        })
+        .collect()
+}
+
+/// Collects scanned chunks in any order, ignoring duplicates
+#[derive(Debug, Default)]
+pub struct ChunkAssembler {
+    kind: Option<PayloadKind>,
+    checksum: Option<String>,
+    parts: Vec<Option<Vec<u8>>>,
+}
+
+impl ChunkAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one scanned chunk, returning true once every chunk is present
+    pub fn push(&mut self, chunk: &str) -> Result<bool> {
+        let fields: Vec<&str> = chunk.trim().splitn(5, ':').collect();
+        if fields.len() != 5 || fields[0] != CHUNK_PREFIX {
+            return Err(anyhow::anyhow!("Not an aa-client QR chunk"));
+        }
+        let kind = PayloadKind::from_code(fields[1])?;
+        let (index, total) = fields[2]
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Malformed chunk position '{}'", fields[2]))?;
+        let index: usize = index.parse()?;
+        let total: usize = total.parse()?;
+        if total == 0 || index == 0 || index > total {
+            return Err(anyhow::anyhow!("Chunk position {}/{} is out of range", index, total));
+        }
+        let data = hexutil::decode("QR chunk data", fields[4])?;
+
+        match (&self.kind, &self.checksum) {
+            (Some(expected_kind), Some(expected_checksum)) => {
+                if *expected_kind != kind || expected_checksum != fields[3] || self.parts.len() != total {
+                    return Err(anyhow::anyhow!("Chunk belongs to a different payload"));
+                }
+            }
+            _ => {
+                self.kind = Some(kind);
+                self.checksum = Some(fields[3].to_string());
+                self.parts = vec![None; total];
+            }
+        }
+        self.parts[index - 1] = Some(data);
+        Ok(self.is_complete())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.parts.is_empty() && self.parts.iter().all(Option::is_some)
+    }
+
+    /// Chunk positions still missing, 1-based
+    pub fn missing(&self) -> Vec<usize> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| part.is_none())
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Reassemble the payload and verify it against the checksum carried by every chunk
+    pub fn finish(self) -> Result<(PayloadKind, Vec<u8>)> {
+        let missing = self.missing();
+        let (Some(kind), Some(expected)) = (self.kind, self.checksum) else {
+            return Err(anyhow::anyhow!("No chunks scanned"));
+        };
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("Missing chunks {:?} of {}", missing, self.parts.len()));
+        }
+
+        let payload: Vec<u8> = self.parts.into_iter().flatten().flatten().collect();
+        if checksum(&payload) != expected {
+            return Err(anyhow::anyhow!("Scanned payload does not match its checksum"));
+        }
+        Ok((kind, payload))
+    }
+}
+
+/// Render one chunk as a terminal QR code using half-block characters
+pub fn render(chunk: &str) -> Result<String> {
+    let code = QrCode::with_error_correction_level(chunk.as_bytes(), EcLevel::L)?;
+    Ok(code.render::<Dense1x2>().quiet_zone(true).build())
+}
+
+/// Print a payload as one or more QR codes, numbered for the scanning side
+/// Plain output prints the chunk text instead, which `scan` accepts just the same
+pub fn print_qr(kind: PayloadKind, payload: &[u8]) -> Result<()> {
+    let chunks = encode_chunks(kind, payload);
+    let total = chunks.len();
+    for (i, chunk) in chunks.iter().enumerate() {
+        if output::is_plain() {
+            out!("QR part {}/{}:", i + 1, total);
+            out!("{}", chunk);
+        } else {
+            out!("📱 QR part {}/{}", i + 1, total);
+            out!("{}", render(chunk)?);
+        }
+    }
+    if total > 1 {
+        out!("💡 Scan all {} parts, in any order, with: scan --out <file>", total);
+    }
+    Ok(())
+}
+
+/// EIP-681 payment URI for `address`, pinned to `chain_id` when known so wallets switch to the right network
+pub fn payment_uri(address: Address, chain_id: Option<u64>) -> String {
+    match chain_id {
+        Some(chain_id) => format!("ethereum:{}@{}", address, chain_id),
+        None => format!("ethereum:{}", address),
+    }
+}
+
+/// Print a short text such as an address or payment URI as a single QR code, for mobile wallets to scan
+/// Unlike `print_qr` the text is encoded as is, without chunk framing
+pub fn print_text(label: &str, text: &str) -> Result<()> {
+    if output::is_plain() {
+        out!("QR {}: {}", label, text);
+    } else {
+        out!("📱 {}: {}", label, text);
+        out!("{}", render(text)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_round_trip_out_of_order() {
+        let payload: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let chunks = encode_chunks(PayloadKind::Proposal, &payload);
+        assert_eq!(chunks.len(), 4);
+
+        let mut assembler = ChunkAssembler::new();
+        assert!(!assembler.push(&chunks[3]).unwrap());
+        assert!(!assembler.push(&chunks[0]).unwrap());
+        // Scanning the same code twice is harmless
+        assert!(!assembler.push(&chunks[0]).unwrap());
+        assert_eq!(assembler.missing(), vec![2, 3]);
+        assert!(!assembler.push(&chunks[2]).unwrap());
+        assert!(assembler.push(&chunks[1]).unwrap());
+
+        let (kind, decoded) = assembler.finish().unwrap();
+        assert_eq!(kind, PayloadKind::Proposal);
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_rejects_foreign_chunks() {
+        let first = encode_chunks(PayloadKind::PreAuth, b"aa-preauth:pa_1");
+        let second = encode_chunks(PayloadKind::PreAuth, b"aa-preauth:pa_2");
+
+        let mut assembler = ChunkAssembler::new();
+        assert!(assembler.push("hello").is_err());
+        assert!(assembler.push(&first[0]).unwrap());
+        assert!(assembler.push(&second[0]).is_err());
+        assert_eq!(assembler.finish().unwrap().1, b"aa-preauth:pa_1");
+    }
+
+    #[test]
+    fn test_chunks_are_alphanumeric() {
+        let chunks = encode_chunks(PayloadKind::Proposal, b"{\"chainId\":1}");
+        let alphanumeric = |c: char| c.is_ascii_digit() || c.is_ascii_uppercase() || " $%*+-./:".contains(c);
+        assert!(chunks.iter().all(|chunk| chunk.chars().all(alphanumeric)));
+        assert!(render(&chunks[0]).unwrap().lines().count() > 10);
+    }
+
+    #[test]
+    fn test_payment_uri() {
+        let address: Address = "0xd710e28ecfb47f55f234513ce3be18a31974590c".parse().unwrap();
+        assert_eq!(payment_uri(address, Some(11155111)), "ethereum:0xd710e28ECFb47f55f234513cE3Be18a31974590c@11155111");
+        assert_eq!(payment_uri(address, None), "ethereum:0xd710e28ECFb47f55f234513cE3Be18a31974590c");
+        assert!(render(&payment_uri(address, Some(1))).is_ok());
+    }
+}
@@ -0,0 +1,174 @@
+// Social recovery: guardians can replace the owners of an AAAccount after a timelock
+// Guardian configuration is an owner UserOperation, guardian actions are plain transactions from the guardian's EOA
+
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, Bytes, TxHash, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+
+use crate::userop::ExecuteCall;
+use crate::wallet::Wallet;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IRecovery {
+        function addGuardian(address guardian) external;
+        function removeGuardian(address guardian) external;
+        function setRecoveryConfig(uint256 threshold, uint64 delay) external;
+        function initiateRecovery(address[] newOwners) external;
+        function approveRecovery() external;
+        function executeRecovery() external;
+        function cancelRecovery() external;
+        function getGuardians() external view returns (address[] memory);
+        function guardianThreshold() external view returns (uint256);
+        function recoveryDelay() external view returns (uint64);
+        function getPendingRecovery() external view returns (address[] memory newOwners, uint64 executeAfter, uint256 approvals);
+    }
+);
+
+/// Guardian configuration and pending recovery of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryStatus {
+    pub guardians: Vec<Address>,
+    pub threshold: u64,
+    /// Timelock in seconds between initiating and executing a recovery
+    pub delay: u64,
+    pub new_owners: Vec<Address>,
+    /// Unix timestamp the pending recovery unlocks at, zero when none is pending
+    pub execute_after: u64,
+    pub approvals: u64,
+}
+
+impl RecoveryStatus {
+    pub fn pending(&self) -> bool {
+        self.execute_after != 0
+    }
+
+    /// Seconds left on the timelock
+    pub fn seconds_remaining(&self, now: u64) -> u64 {
+        self.execute_after.saturating_sub(now)
+    }
+
+    pub fn approvals_missing(&self) -> u64 {
+        self.threshold.saturating_sub(self.approvals)
+    }
+
+    /// Whether executeRecovery would succeed at `now`
+    pub fn ready(&self, now: u64) -> bool {
+        self.pending() && self.approvals_missing() == 0 && now >= self.execute_after
+    }
+}
+
+/// Read the guardian configuration and pending recovery of a deployed account
+pub async fn get_status<P: Provider>(provider: &P, account: Address) -> Result<RecoveryStatus> {
+    let contract = IRecovery::new(account, provider);
+    let guardians = contract.getGuardians().call().await?._0;
+    let threshold = contract.guardianThreshold().call().await?._0;
+    let delay = contract.recoveryDelay().call().await?._0;
+    let pending = contract.getPendingRecovery().call().await?;
+
+    Ok(RecoveryStatus {
+        guardians,
+        threshold: threshold.to::<u64>(),
+        delay,
+        new_owners: pending.newOwners,
+        execute_after: pending.executeAfter,
+        approvals: pending.approvals.to::<u64>(),
+    })
+}
+
+/// Calls the account makes on itself to add a guardian, and optionally set the threshold and timelock
+pub fn add_guardian_calls(account: Address, guardian: Address, config: Option<(u64, u64)>) -> Vec<ExecuteCall> {
+    let mut calls = vec![ExecuteCall::new(
+        account,
+        U256::ZERO,
+        Bytes::from(IRecovery::addGuardianCall { guardian }.abi_encode()),
+    )];
+    if let Some((threshold, delay)) = config {
+        calls.push(ExecuteCall::new(
+            account,
+            U256::ZERO,
+            Bytes::from(IRecovery::setRecoveryConfigCall { threshold: U256::from(threshold), delay }.abi_encode()),
+        ));
+    }
+    calls
+}
+
+pub fn initiate_call_data(new_owners: Vec<Address>) -> Bytes {
+    IRecovery::initiateRecoveryCall { newOwners: new_owners }.abi_encode().into()
+}
+
+pub fn approve_call_data() -> Bytes {
+    IRecovery::approveRecoveryCall {}.abi_encode().into()
+}
+
+pub fn execute_call_data() -> Bytes {
+    IRecovery::executeRecoveryCall {}.abi_encode().into()
+}
+
+pub fn cancel_call_data() -> Bytes {
+    IRecovery::cancelRecoveryCall {}.abi_encode().into()
+}
+
+/// Send a call to the account as a regular transaction from `wallet`, waiting for it to be mined
+/// Guardians are not owners, so they cannot go through a UserOperation
+pub async fn send_transaction(wallet: &Wallet, rpc_url: &str, account: Address, data: Bytes) -> Result<TxHash> {
+    let url = url::Url::parse(rpc_url)?;
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(wallet.signer().clone()))
+        .on_http(url);
+
+    let tx = TransactionRequest::default().with_to(account).with_input(data);
+    let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+    if !receipt.status() {
+        return Err(anyhow::anyhow!("Transaction {} reverted", receipt.transaction_hash));
+    }
+    Ok(receipt.transaction_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_guardian_calls() {
+        let account = Address::from([1u8; 20]);
+        let guardian = Address::from([2u8; 20]);
+
+        let calls = add_guardian_calls(account, guardian, None);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(&calls[0].data[..4], IRecovery::addGuardianCall::SELECTOR.as_slice());
+
+        let calls = add_guardian_calls(account, guardian, Some((2, 86400)));
+        assert_eq!(calls.len(), 2);
+        assert!(calls.iter().all(|call| call.target == account));
+        let config = IRecovery::setRecoveryConfigCall::abi_decode(&calls[1].data, true).unwrap();
+        assert_eq!(config.threshold, U256::from(2));
+        assert_eq!(config.delay, 86400);
+    }
+
+    #[test]
+    fn test_recovery_timelock() {
+        let status = RecoveryStatus {
+            guardians: vec![Address::from([2u8; 20]), Address::from([3u8; 20])],
+            threshold: 2,
+            delay: 100,
+            new_owners: vec![Address::from([4u8; 20])],
+            execute_after: 1100,
+            approvals: 1,
+        };
+
+        assert!(status.pending());
+        assert_eq!(status.seconds_remaining(1000), 100);
+        assert!(!status.ready(1100));
+
+        let approved = RecoveryStatus { approvals: 2, ..status };
+        assert!(!approved.ready(1099));
+        assert!(approved.ready(1100));
+        assert_eq!(approved.seconds_remaining(2000), 0);
+    }
+}
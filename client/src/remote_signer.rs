@@ -0,0 +1,100 @@
+// Signing through a remote Web3Signer-compatible endpoint, so the owner key never leaves the signing service
+// Uses the Ethereum JSON-RPC surface Web3Signer and Consensys-style signers expose: eth_accounts and eth_sign
+
+use alloy::primitives::{Address, Bytes};
+use alloy::providers::Provider;
+use alloy::signers::Signature;
+use anyhow::Result;
+use std::sync::OnceLock;
+
+use crate::providers;
+
+static REMOTE: OnceLock<RemoteSignerConfig> = OnceLock::new();
+
+/// Endpoint and account given with `--remote-signer-url` and `--remote-signer-address`
+#[derive(Debug, Clone)]
+pub struct RemoteSignerConfig {
+    pub url: String,
+    pub address: Option<Address>,
+}
+
+/// Sign with the remote signer for this process instead of a local key
+pub fn set_config(config: RemoteSignerConfig) {
+    let _ = REMOTE.set(config);
+}
+
+/// Remote signer configured for this process, if any
+pub fn config() -> Option<&'static RemoteSignerConfig> {
+    REMOTE.get()
+}
+
+/// One account held by a remote signer
+pub struct RemoteSigner {
+    url: String,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Connect to `config.url` and select the account to sign with
+    pub async fn connect(config: &RemoteSignerConfig) -> Result<Self> {
+        let provider = providers::http(&config.url)?;
+        let accounts = provider
+            .get_accounts()
+            .await
+            .map_err(|e| anyhow::anyhow!("Remote signer {} did not list its accounts: {}", config.url, e))?;
+        let address = select_account(&accounts, config.address)?;
+        Ok(Self { url: config.url.clone(), address })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// EIP-191 signature over `message` (eth_sign), checked to recover to the selected account
+    pub async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let provider = providers::http(&self.url)?;
+        let signature: Bytes = provider
+            .raw_request("eth_sign".into(), (self.address, Bytes::copy_from_slice(message)))
+            .await
+            .map_err(|e| anyhow::anyhow!("Remote signer refused to sign for {}: {}", self.address, e))?;
+        let signature = Signature::try_from(signature.as_ref())
+            .map_err(|e| anyhow::anyhow!("Remote signer returned a malformed signature: {}", e))?;
+
+        let recovered = signature.recover_address_from_msg(message)?;
+        if recovered != self.address {
+            return Err(anyhow::anyhow!("Remote signer signed with {} instead of {}", recovered, self.address));
+        }
+        Ok(signature)
+    }
+}
+
+/// Account to sign with: the requested one if the signer holds it, otherwise the signer's only account
+fn select_account(accounts: &[Address], requested: Option<Address>) -> Result<Address> {
+    match (requested, accounts) {
+        (Some(address), _) if accounts.contains(&address) => Ok(address),
+        (Some(address), _) => Err(anyhow::anyhow!("Remote signer does not hold a key for {}", address)),
+        (None, [address]) => Ok(*address),
+        (None, []) => Err(anyhow::anyhow!("Remote signer holds no keys")),
+        (None, _) => Err(anyhow::anyhow!(
+            "Remote signer holds {} keys, choose one with --remote-signer-address",
+            accounts.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_account() {
+        let first = Address::from([1u8; 20]);
+        let second = Address::from([2u8; 20]);
+
+        assert_eq!(select_account(&[first], None).unwrap(), first);
+        assert_eq!(select_account(&[first, second], Some(second)).unwrap(), second);
+        assert!(select_account(&[], None).is_err());
+        assert!(select_account(&[first], Some(second)).unwrap_err().to_string().contains(&second.to_string()));
+        assert!(select_account(&[first, second], None).unwrap_err().to_string().contains("--remote-signer-address"));
+    }
+}
@@ -0,0 +1,306 @@
+// Retry policy shared by every network call the client makes (bundler, RPC node, paymaster-service)
+// Only transient transport failures are retried, validation errors fail on the first attempt
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tracing::Instrument;
+
+use crate::{out, telemetry};
+
+/// Attempt count, backoff and jitter applied to network calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one, 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its value so clients don't retry in lockstep
+    pub jitter: bool,
+    /// Total time a call may spend waiting out rate limits (HTTP 429), which do not use up attempts
+    pub rate_limit_budget: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            jitter: true,
+            rate_limit_budget: Duration::from_secs(30),
+        }
+    }
+}
+
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Set the policy used by `retry` for the rest of the process
+pub fn set_policy(policy: RetryPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// Policy in effect, the default when `set_policy` was never called
+pub fn policy() -> RetryPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Run `call` under the process-wide policy, see `RetryPolicy::run`
+pub async fn retry<T, E, F, Fut>(what: &str, call: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    policy().run(what, call).await
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (1 for the first retry), before jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+        // Without randomness the full backoff is waited, never a shorter one
+        let mut bytes = [0u8; 8];
+        if crate::clock::random_bytes(&mut bytes).is_err() {
+            return backoff;
+        }
+        let fraction = u64::from_le_bytes(bytes) as f64 / u64::MAX as f64;
+        backoff.mul_f64(0.5 + fraction / 2.0)
+    }
+
+    // Retry-After when the error carries one, otherwise the regular backoff without jitter, which
+    // keeps the budget check and the actual wait in agreement
+    fn throttle_delay(&self, error: &impl Display, throttled: u32) -> Duration {
+        retry_after(error).unwrap_or_else(|| self.backoff(throttled))
+    }
+
+    /// Call `call` until it succeeds, fails permanently, or runs out of attempts
+    /// `what` names the call in the retry notices and its span, the last error is returned unchanged
+    pub async fn run<T, E, F, Fut>(&self, what: &str, call: F) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let span = telemetry::call_span(what);
+        let result = self.attempts(what, call).instrument(span.clone()).await;
+        if let Err(e) = &result {
+            telemetry::record_error(&span, e);
+        }
+        result
+    }
+
+    async fn attempts<T, E, F, Fut>(&self, what: &str, mut call: F) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        // Rate-limit waits so far, and the time they took out of the budget
+        let (mut throttled, mut waited) = (0, Duration::ZERO);
+        loop {
+            match call().await {
+                Ok(value) => return Ok(value),
+                // The server said when to come back: wait that long (or back off) as long as the budget allows
+                Err(e) if is_rate_limited(&e) && waited + self.throttle_delay(&e, throttled + 1) <= self.rate_limit_budget => {
+                    throttled += 1;
+                    let delay = self.throttle_delay(&e, throttled);
+                    out!("⏳ {} was rate limited, retrying in {} ms ({} ms of {} s budget left)",
+                         what, delay.as_millis(), (self.rate_limit_budget - waited - delay).as_millis(), self.rate_limit_budget.as_secs());
+                    tokio::time::sleep(delay).await;
+                    waited += delay;
+                }
+                Err(e) if attempt < self.max_attempts && is_retryable(&e) => {
+                    let delay = self.delay(attempt);
+                    out!("🔁 {} failed ({}), retrying in {} ms ({}/{})",
+                         what, e, delay.as_millis(), attempt + 1, self.max_attempts);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    tracing::Span::current().record("retries", attempt - 1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Message fragments of failures that may go away on their own
+/// Covers hyper/reqwest transport errors, gateway statuses and rate limiting by RPC providers
+const TRANSIENT_PATTERNS: [&str; 16] = [
+    "timed out",
+    "timeout",
+    "no response within",
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "broken pipe",
+    "error sending request",
+    "error trying to connect",
+    "dns error",
+    "temporarily unavailable",
+    "service unavailable",
+    "bad gateway",
+    "too many requests",
+    "rate limit",
+    "limit exceeded",
+];
+
+/// HTTP statuses worth retrying, as carried by "(HTTP nnn)" or "(HTTP nnn, retry after ..)" in service errors
+const TRANSIENT_STATUSES: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// Message fragments of rate-limit rejections: paymaster-service and alloy HTTP statuses, and JSON-RPC errors
+const RATE_LIMIT_PATTERNS: [&str; 4] = ["(http 429", "http error 429", "too many requests", "rate limit"];
+
+/// Marker paymaster errors carry a Retry-After header in, e.g. "(HTTP 429, retry after 5s)"
+const RETRY_AFTER_MARKER: &str = "retry after ";
+
+/// Whether an error is a transient transport failure rather than a permanent rejection
+pub fn is_retryable(error: &impl Display) -> bool {
+    // `{:#}` includes the cause chain of anyhow errors
+    let message = format!("{:#}", error).to_ascii_lowercase();
+    TRANSIENT_PATTERNS.iter().any(|pattern| message.contains(pattern))
+        || TRANSIENT_STATUSES.iter().any(|status| message.contains(&format!("(http {}", status)))
+        || is_rate_limited(error)
+}
+
+/// Whether the server turned the request away for sending too many
+pub fn is_rate_limited(error: &impl Display) -> bool {
+    let message = format!("{:#}", error).to_ascii_lowercase();
+    RATE_LIMIT_PATTERNS.iter().any(|pattern| message.contains(pattern))
+}
+
+/// Parse a Retry-After header given in seconds; HTTP dates are left to the regular backoff
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Delay an error message carries after `RETRY_AFTER_MARKER`
+pub fn retry_after(error: &impl Display) -> Option<Duration> {
+    let message = format!("{:#}", error).to_ascii_lowercase();
+    let rest = &message[message.find(RETRY_AFTER_MARKER)? + RETRY_AFTER_MARKER.len()..];
+    parse_retry_after(rest.split(|c: char| !c.is_ascii_digit()).next()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn quick(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            jitter: false,
+            rate_limit_budget: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(is_retryable(&"error sending request for url (http://localhost:8545/): connection refused"));
+        assert!(is_retryable(&"Paymaster service error (HTTP 503): overloaded"));
+        assert!(is_retryable(&"HTTP error 429 with body: Too Many Requests"));
+        assert!(!is_retryable(&"Paymaster service error (HTTP 400): invalid api key"));
+        assert!(!is_retryable(&"AA23 reverted: invalid signature"));
+    }
+
+    #[test]
+    fn test_rate_limits_and_retry_after() {
+        let throttled = "Paymaster service error (HTTP 429, retry after 7s): slow down";
+        assert!(is_rate_limited(&throttled) && is_retryable(&throttled));
+        assert_eq!(retry_after(&throttled), Some(Duration::from_secs(7)));
+        assert!(is_rate_limited(&"HTTP error 429 with body: Too Many Requests"));
+        assert_eq!(retry_after(&"HTTP error 429 with body: Too Many Requests"), None);
+        assert!(!is_rate_limited(&"Paymaster service error (HTTP 503): overloaded"));
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy { jitter: false, ..RetryPolicy::default() };
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(5), Duration::from_secs(8));
+        assert_eq!(policy.backoff(40), Duration::from_secs(8));
+
+        let jittered = RetryPolicy::default().delay(2);
+        assert!(jittered >= Duration::from_millis(500) && jittered <= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        let calls = Cell::new(0);
+        let result: Result<u32, String> = quick(3)
+            .run("eth_chainId", || {
+                calls.set(calls.get() + 1);
+                let call = calls.get();
+                async move { if call < 3 { Err("connection reset by peer".to_string()) } else { Ok(call) } }
+            })
+            .await;
+        assert_eq!(result, Ok(3));
+
+        calls.set(0);
+        let result: Result<u32, String> = quick(2)
+            .run("eth_chainId", || {
+                calls.set(calls.get() + 1);
+                async { Err("request timed out".to_string()) }
+            })
+            .await;
+        assert_eq!(result, Err("request timed out".to_string()));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_spend_the_budget_not_attempts() {
+        let calls = Cell::new(0);
+        let policy = RetryPolicy { rate_limit_budget: Duration::from_millis(10), ..quick(1) };
+        let result: Result<u32, String> = policy
+            .run("Paymaster /sign", || {
+                calls.set(calls.get() + 1);
+                let call = calls.get();
+                async move { if call < 4 { Err("(HTTP 429): slow down".to_string()) } else { Ok(call) } }
+            })
+            .await;
+        // Backoffs of 1, 2 and 2 ms fit the budget even with a single attempt
+        assert_eq!(result, Ok(4));
+
+        // A Retry-After longer than the budget is not waited out
+        calls.set(0);
+        let result: Result<u32, String> = policy
+            .run("Paymaster /sign", || {
+                calls.set(calls.get() + 1);
+                async { Err("(HTTP 429, retry after 60s): slow down".to_string()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_fail_immediately() {
+        let calls = Cell::new(0);
+        let result: Result<(), String> = quick(5)
+            .run("eth_sendUserOperation", || {
+                calls.set(calls.get() + 1);
+                async { Err("AA21 didn't pay prefund".to_string()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,266 @@
+// Local JSON-RPC endpoint behind `serve`: an EIP-1193 style wallet for scripts and tools such as `cast`.
+// Account methods are answered by the smart account, transactions become UserOperations and everything else
+// goes to the upstream node unchanged, so tools that expect an unlocked node work without changes.
+// Requests are handled one at a time: confirmations never interleave and nonces never race
+
+use alloy::providers::Provider;
+use alloy::transports::RpcError;
+use anyhow::Result;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::walletconnect::{self, DappRequest, Rejection};
+
+pub const DEFAULT_PORT: u16 = 8546;
+
+/// Bodies are JSON-RPC requests, a megabyte fits any call data a node would accept
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// What a JSON-RPC method asks of the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    /// eth_accounts and eth_requestAccounts
+    Accounts,
+    ChainId,
+    /// eth_sendTransaction, personal_sign and eth_signTypedData(_v4)
+    Dapp(DappRequest),
+    /// EIP-5792 wallet_sendCalls with its single call, answered with the UserOperation hash right away
+    SendCalls(DappRequest),
+    /// EIP-5792 wallet_getCallsStatus of a UserOperation hash
+    CallsStatus(String),
+    Capabilities,
+    SwitchChain(u64),
+    /// Anything else, answered by the upstream node
+    Forward,
+}
+
+fn chain_param(value: Option<&Value>) -> Result<u64, Rejection> {
+    let chain = value.and_then(Value::as_str).ok_or_else(|| Rejection::invalid_params("missing chainId"))?;
+    let digits = chain.strip_prefix("0x").ok_or_else(|| Rejection::invalid_params("chainId must be hex"))?;
+    u64::from_str_radix(digits, 16).map_err(|_| Rejection::invalid_params("chainId must be hex"))
+}
+
+/// Decide who answers `method`
+pub fn route(method: &str, params: &Value) -> Result<Call, Rejection> {
+    match method {
+        "eth_accounts" | "eth_requestAccounts" => Ok(Call::Accounts),
+        "eth_chainId" => Ok(Call::ChainId),
+        "eth_sendTransaction" | "personal_sign" | "eth_signTypedData" | "eth_signTypedData_v4" => {
+            walletconnect::parse_request(method, params).map(Call::Dapp)
+        }
+        "wallet_sendCalls" => {
+            let batch = params.get(0).ok_or_else(|| Rejection::invalid_params("missing calls"))?;
+            let calls = batch.get("calls").and_then(Value::as_array).ok_or_else(|| Rejection::invalid_params("missing calls"))?;
+            let [call] = calls.as_slice() else {
+                // An owner-signed execute makes one call; executeBatch would have to be called by the EntryPoint
+                return Err(Rejection { code: 5740, message: format!("Only one call per batch is supported, got {}", calls.len()) });
+            };
+            let mut tx = call.clone();
+            tx["from"] = batch.get("from").cloned().unwrap_or(Value::Null);
+            walletconnect::parse_request("eth_sendTransaction", &json!([tx])).map(Call::SendCalls)
+        }
+        "wallet_getCallsStatus" => match params.get(0).and_then(Value::as_str) {
+            Some(id) => Ok(Call::CallsStatus(id.to_string())),
+            None => Err(Rejection::invalid_params("missing batch id")),
+        },
+        "wallet_getCapabilities" => Ok(Call::Capabilities),
+        "wallet_switchEthereumChain" => chain_param(params.get(0).and_then(|p| p.get("chainId"))).map(Call::SwitchChain),
+        "eth_sign" | "eth_signTransaction" | "wallet_addEthereumChain" | "wallet_watchAsset" => Err(Rejection::unsupported_method(method)),
+        _ if method.starts_with("wallet_") => Err(Rejection::unsupported_method(method)),
+        _ => Ok(Call::Forward),
+    }
+}
+
+/// Pass a request on to the upstream node, keeping its error code and message
+pub async fn forward<P: Provider>(provider: &P, method: &str, params: &Value) -> Result<Value, Rejection> {
+    match provider.raw_request::<_, Value>(method.to_string().into(), params.clone()).await {
+        Ok(result) => Ok(result),
+        Err(RpcError::ErrorResp(payload)) => Err(Rejection { code: payload.code, message: payload.message.to_string() }),
+        Err(e) => Err(anyhow::anyhow!("Upstream node failed: {}", e).into()),
+    }
+}
+
+/// EIP-1193 error codes where they differ from WalletConnect's
+fn provider_code(code: i64) -> i64 {
+    match code {
+        5000 => 4001,
+        5100 => 4901,
+        code => code,
+    }
+}
+
+fn response(id: Value, result: Result<Value, Rejection>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(rejection) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": provider_code(rejection.code), "message": rejection.message },
+        }),
+    }
+}
+
+/// A parsed HTTP request, or the status to refuse it with
+#[derive(Debug, PartialEq, Eq)]
+enum HttpRequest {
+    JsonRpc(Value),
+    Refused(&'static str, &'static str),
+}
+
+/// Check the head of an HTTP request and return the body length it announces
+/// Anything a browser page could send without a CORS preflight is refused: a plain form POST must not spend funds
+fn check_head(head: &str) -> Result<usize, HttpRequest> {
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap_or_default();
+    if !request_line.starts_with("POST ") {
+        return Err(HttpRequest::Refused("405 Method Not Allowed", "only POST with a JSON-RPC body"));
+    }
+    let (mut length, mut json) = (None, false);
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => length = value.trim().parse::<usize>().ok(),
+            "content-type" => json = value.trim().to_ascii_lowercase().starts_with("application/json"),
+            "origin" => return Err(HttpRequest::Refused("403 Forbidden", "requests from web pages are not served")),
+            _ => {}
+        }
+    }
+    if !json {
+        return Err(HttpRequest::Refused("415 Unsupported Media Type", "Content-Type must be application/json"));
+    }
+    match length {
+        Some(length) if length <= MAX_BODY_BYTES => Ok(length),
+        Some(_) => Err(HttpRequest::Refused("413 Payload Too Large", "request body too large")),
+        None => Err(HttpRequest::Refused("411 Length Required", "Content-Length is required")),
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<HttpRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(anyhow::anyhow!("Connection closed before the request ended"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+        if buffer.len() > MAX_BODY_BYTES {
+            return Ok(HttpRequest::Refused("431 Request Header Fields Too Large", "request head too large"));
+        }
+    };
+
+    let length = match check_head(&String::from_utf8_lossy(&buffer[..head_end])) {
+        Ok(length) => length,
+        Err(refused) => return Ok(refused),
+    };
+    while buffer.len() < head_end + length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(anyhow::anyhow!("Connection closed before the body ended"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    match serde_json::from_slice(&buffer[head_end..head_end + length]) {
+        Ok(body) => Ok(HttpRequest::JsonRpc(body)),
+        Err(_) => Ok(HttpRequest::JsonRpc(Value::Null)),
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Answer JSON-RPC requests on `listener` with `handler(method, params)` until the process exits
+/// Batches are answered in order, a body that is not JSON-RPC gets a parse error
+pub async fn serve<F, Fut>(listener: TcpListener, mut handler: F) -> Result<()>
+where
+    F: FnMut(String, Value) -> Fut,
+    Fut: std::future::Future<Output = Result<Value, Rejection>>,
+{
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let body = match read_request(&mut stream).await {
+            Ok(HttpRequest::JsonRpc(body)) => body,
+            Ok(HttpRequest::Refused(status, reason)) => {
+                let _ = write_response(&mut stream, status, &json!({ "error": reason }).to_string()).await;
+                continue;
+            }
+            Err(_) => continue,
+        };
+
+        let (requests, batch) = match body {
+            Value::Array(requests) => (requests, true),
+            request => (vec![request], false),
+        };
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            let (Some(method), id) = (request.get("method").and_then(Value::as_str), request.get("id").cloned()) else {
+                responses.push(response(Value::Null, Err(Rejection { code: -32600, message: "Invalid request".to_string() })));
+                continue;
+            };
+            let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+            let result = handler(method.to_string(), params).await;
+            responses.push(response(id.unwrap_or(Value::Null), result));
+        }
+
+        let body = if batch { Value::Array(responses) } else { responses.remove(0) };
+        let _ = write_response(&mut stream, "200 OK", &body.to_string()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, U256};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("eth_chainId", &json!([])).unwrap(), Call::ChainId);
+        assert_eq!(route("eth_getTransactionReceipt", &json!(["0x01"])).unwrap(), Call::Forward);
+        assert_eq!(route("eth_sign", &json!([])).unwrap_err().code, 4200);
+        assert_eq!(route("wallet_switchEthereumChain", &json!([{ "chainId": "0x2105" }])).unwrap(), Call::SwitchChain(8453));
+
+        let from = "0x1111111111111111111111111111111111111111";
+        let call = json!({ "to": "0x2222222222222222222222222222222222222222", "value": "0x01" });
+        let Call::SendCalls(DappRequest::SendTransaction { from: sender, value, .. }) =
+            route("wallet_sendCalls", &json!([{ "from": from, "calls": [call] }])).unwrap()
+        else {
+            panic!("expected a single call")
+        };
+        assert_eq!((sender, value), (Address::from_str(from).unwrap(), U256::from(1)));
+        assert_eq!(route("wallet_sendCalls", &json!([{ "from": from, "calls": [call, call] }])).unwrap_err().code, 5740);
+    }
+
+    #[test]
+    fn test_check_head() {
+        let head = "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: 42\r\n\r\n";
+        assert_eq!(check_head(head), Ok(42));
+
+        // A page in a browser may post text/plain without asking first, or sends its Origin
+        let form = head.replace("application/json", "text/plain");
+        assert!(matches!(check_head(&form), Err(HttpRequest::Refused("415 Unsupported Media Type", _))));
+        let page = head.replace("Host: localhost", "Origin: https://evil.example");
+        assert!(matches!(check_head(&page), Err(HttpRequest::Refused("403 Forbidden", _))));
+        assert!(matches!(check_head("GET / HTTP/1.1\r\n\r\n"), Err(HttpRequest::Refused("405 Method Not Allowed", _))));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let rejected = response(json!(7), Err(Rejection::user_rejected()));
+        assert_eq!(rejected["error"]["code"], 4001);
+        assert_eq!(rejected["id"], 7);
+        assert_eq!(response(json!(1), Err(Rejection::unsupported_method("eth_sign")))["error"]["code"], 4200);
+    }
+}
@@ -0,0 +1,175 @@
+// Session keys: scoped signing keys registered on an AAAccount
+// A session key may only call one target (and optionally one selector), sending at most its value cap,
+// until it expires or has signed its budget of operations
+
+use alloy::primitives::aliases::U48;
+use alloy::primitives::{keccak256, Address, Bytes, FixedBytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::wallet::Wallet;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface ISessionKeys {
+        function registerSessionKey(address key, address target, bytes4 selector, uint48 validUntil, uint128 maxValue, uint32 maxOps) external;
+        function revokeSessionKey(address key) external;
+        function sessionKeys(address key) external view returns (address target, bytes4 selector, uint48 validUntil, bool active, uint128 maxValue, uint32 opsRemaining);
+    }
+);
+
+/// Parse a selector given as 4 hex bytes (`0xa9059cbb`) or a function signature (`transfer(address,uint256)`)
+/// An empty string or `any` allows every selector
+pub fn parse_selector(selector: &str) -> Result<FixedBytes<4>> {
+    let selector = selector.trim();
+    if selector.is_empty() || selector.eq_ignore_ascii_case("any") {
+        return Ok(FixedBytes::ZERO);
+    }
+    if selector.contains('(') {
+        let hash = keccak256(selector.replace(' ', "").as_bytes());
+        return Ok(FixedBytes::from_slice(&hash[..4]));
+    }
+    FixedBytes::<4>::from_str(selector)
+        .map_err(|e| anyhow::anyhow!("Invalid selector '{}': {}", selector, e))
+}
+
+/// Calldata for `registerSessionKey(key, target, selector, validUntil, maxValue, maxOps)`
+pub fn register_call_data(
+    key: Address,
+    target: Address,
+    selector: FixedBytes<4>,
+    valid_until: u64,
+    max_value: U256,
+    max_ops: u32,
+) -> Result<Bytes> {
+    let valid_until = U48::try_from(valid_until)
+        .map_err(|_| anyhow::anyhow!("Expiry {} does not fit in uint48", valid_until))?;
+    let max_value = u128::try_from(max_value)
+        .map_err(|_| anyhow::anyhow!("Value cap {} does not fit in uint128", max_value))?;
+    if max_ops == 0 {
+        return Err(anyhow::anyhow!("A session key needs at least one operation"));
+    }
+    Ok(ISessionKeys::registerSessionKeyCall { key, target, selector, validUntil: valid_until, maxValue: max_value, maxOps: max_ops }
+        .abi_encode()
+        .into())
+}
+
+/// Calldata for `revokeSessionKey(key)`
+pub fn revoke_call_data(key: Address) -> Bytes {
+    ISessionKeys::revokeSessionKeyCall { key }.abi_encode().into()
+}
+
+/// Whether `key` is currently registered, unexpired and has operations left on `account`
+pub async fn is_active<P: Provider>(provider: &P, account: Address, key: Address, now: u64) -> Result<bool> {
+    let contract = ISessionKeys::new(account, provider);
+    let session = contract.sessionKeys(key).call().await?;
+    Ok(session.active && session.validUntil.to::<u64>() > now && session.opsRemaining > 0)
+}
+
+/// Session key and its scope, saved by `session create` and read by `--session-key`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionKeyFile {
+    pub account: Address,
+    pub chain_id: u64,
+    pub address: Address,
+    pub private_key: String,
+    pub target: Address,
+    pub selector: FixedBytes<4>,
+    /// Unix timestamp after which the account rejects the key
+    pub valid_until: u64,
+    /// Most wei a single call may send
+    #[serde(default)]
+    pub max_value: U256,
+}
+
+impl SessionKeyFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read session key {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write the key file readable by the current user only
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    pub fn wallet(&self) -> Result<Wallet> {
+        Wallet::from_hex(&self.private_key)
+    }
+
+    /// Reject calls the account would refuse for this key, before paying for a round-trip
+    pub fn check_call(&self, target: Address, value: U256, call_data: &[u8], now: u64) -> Result<()> {
+        if now >= self.valid_until {
+            return Err(anyhow::anyhow!("Session key {} expired at {}", self.address, self.valid_until));
+        }
+        if target != self.target {
+            return Err(anyhow::anyhow!("Session key {} may only call {}, not {}", self.address, self.target, target));
+        }
+        if value > self.max_value {
+            return Err(anyhow::anyhow!("Session key {} may send at most {} wei, not {}", self.address, self.max_value, value));
+        }
+        if self.selector != FixedBytes::ZERO && (call_data.len() < 4 || call_data[..4] != self.selector[..]) {
+            return Err(anyhow::anyhow!("Session key {} may only call selector {}", self.address, self.selector));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!(parse_selector("transfer(address, uint256)").unwrap(), FixedBytes::from([0xa9, 0x05, 0x9c, 0xbb]));
+        assert_eq!(parse_selector("0xa9059cbb").unwrap(), FixedBytes::from([0xa9, 0x05, 0x9c, 0xbb]));
+        assert_eq!(parse_selector("any").unwrap(), FixedBytes::ZERO);
+        assert!(parse_selector("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_check_call_scope() {
+        let session = SessionKeyFile {
+            account: Address::from([1u8; 20]),
+            chain_id: 31337,
+            address: Address::from([2u8; 20]),
+            private_key: String::new(),
+            target: Address::from([3u8; 20]),
+            selector: FixedBytes::from([0xa9, 0x05, 0x9c, 0xbb]),
+            valid_until: 1000,
+            max_value: U256::from(100),
+        };
+
+        assert!(session.check_call(session.target, U256::ZERO, &[0xa9, 0x05, 0x9c, 0xbb, 0x00], 999).is_ok());
+        assert!(session.check_call(session.target, U256::from(100), &[0xa9, 0x05, 0x9c, 0xbb], 999).is_ok());
+        assert!(session.check_call(session.target, U256::from(101), &[0xa9, 0x05, 0x9c, 0xbb], 999).is_err());
+        assert!(session.check_call(session.target, U256::ZERO, &[0xa9, 0x05, 0x9c, 0xbb], 1000).is_err());
+        assert!(session.check_call(Address::ZERO, U256::ZERO, &[0xa9, 0x05, 0x9c, 0xbb], 999).is_err());
+        assert!(session.check_call(session.target, U256::ZERO, &[0x12, 0x34, 0x56, 0x78], 999).is_err());
+        assert!(session.check_call(session.target, U256::ZERO, &[], 999).is_err());
+    }
+
+    #[test]
+    fn test_register_call_data() {
+        let (key, target) = (Address::from([2u8; 20]), Address::from([3u8; 20]));
+        let data = register_call_data(key, target, FixedBytes::ZERO, 1_700_000_000, U256::ZERO, 10).unwrap();
+        assert_eq!(&data[..4], ISessionKeys::registerSessionKeyCall::SELECTOR.as_slice());
+        assert_eq!(data.len(), 4 + 6 * 32);
+        assert!(register_call_data(key, target, FixedBytes::ZERO, u64::MAX, U256::ZERO, 10).is_err());
+        assert!(register_call_data(key, target, FixedBytes::ZERO, 1_700_000_000, U256::MAX, 10).is_err());
+        assert!(register_call_data(key, target, FixedBytes::ZERO, 1_700_000_000, U256::ZERO, 0).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+// Per-token daily spending limits kept by a module contract for the accounts that install it
+// The account configures its own limits by calling the module through execute, so msg.sender is the account;
+// the zero token address stands for ETH
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface ISpendingLimitModule {
+        function setDailyLimit(address token, uint256 limit) external;
+        function resetLimit(address token) external;
+        function getLimit(address account, address token) external view returns (uint256 limit, uint256 spent, uint64 resetAt);
+    }
+);
+
+/// Length of one limit window
+pub const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Calldata for `setDailyLimit(token, limit)`
+pub fn set_limit_call_data(token: Address, limit: U256) -> Bytes {
+    ISpendingLimitModule::setDailyLimitCall { token, limit }.abi_encode().into()
+}
+
+/// Calldata for `resetLimit(token)`, dropping the limit and what was spent against it
+pub fn reset_limit_call_data(token: Address) -> Bytes {
+    ISpendingLimitModule::resetLimitCall { token }.abi_encode().into()
+}
+
+/// Limit of one token for one account as the module reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Allowance {
+    /// Zero when no limit is set
+    pub limit: U256,
+    pub spent: U256,
+    /// Unix time the spent amount goes back to zero
+    pub reset_at: u64,
+}
+
+impl Allowance {
+    pub fn is_limited(&self) -> bool {
+        !self.limit.is_zero()
+    }
+
+    /// What can still be spent at `now`, the whole limit once the window has rolled over
+    pub fn remaining(&self, now: u64) -> U256 {
+        if now >= self.reset_at {
+            return self.limit;
+        }
+        self.limit.saturating_sub(self.spent)
+    }
+}
+
+/// Read the limit `module` keeps for `token` on `account`
+pub async fn get_allowance<P: Provider>(provider: &P, module: Address, account: Address, token: Address) -> Result<Allowance> {
+    let limit = ISpendingLimitModule::new(module, provider)
+        .getLimit(account, token)
+        .call()
+        .await
+        .map_err(|e| anyhow::anyhow!("Could not read getLimit() from {}, is it a spending limit module? {}", module, e))?;
+    Ok(Allowance { limit: limit.limit, spent: limit.spent, reset_at: limit.resetAt })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining_rolls_over() {
+        let allowance = Allowance { limit: U256::from(100), spent: U256::from(30), reset_at: 1_000 + DAY_SECS };
+        assert_eq!(allowance.remaining(1_000), U256::from(70));
+        assert_eq!(allowance.remaining(1_000 + DAY_SECS), U256::from(100));
+
+        // A limit lowered below what was already spent leaves nothing, not an underflow
+        let overspent = Allowance { limit: U256::from(10), ..allowance };
+        assert_eq!(overspent.remaining(1_000), U256::ZERO);
+        assert!(!Allowance { limit: U256::ZERO, ..allowance }.is_limited());
+    }
+
+    #[test]
+    fn test_call_data() {
+        let token = Address::from([0x12; 20]);
+        let set = set_limit_call_data(token, U256::from(5));
+        let decoded = ISpendingLimitModule::setDailyLimitCall::abi_decode(&set, true).unwrap();
+        assert_eq!((decoded.token, decoded.limit), (token, U256::from(5)));
+
+        let reset = reset_limit_call_data(Address::ZERO);
+        assert_eq!(&reset[..4], ISpendingLimitModule::resetLimitCall::SELECTOR.as_slice());
+    }
+}
@@ -0,0 +1,203 @@
+// Paymaster sponsorships already obtained, reused while their validity window lasts
+// Persisted under ~/.aa-client so re-running a failed submit does not ask the service to sign the same operation again
+
+use alloy::primitives::{keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::data_dir;
+use crate::hexutil;
+use crate::paymaster::{PackedUserOperationData, PaymasterConfig};
+
+/// File name of the sponsorship cache inside the client data directory
+const SPONSORSHIP_FILE: &str = "sponsorships.json";
+
+/// Validity a cached sponsorship must have left to be reused, bundlers reject operations about to expire
+pub const REUSE_MARGIN_SECS: u64 = 60;
+
+/// One paymaster signature and the window it is valid in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedSponsorship {
+    pub signature: String,
+    pub valid_until: u64,
+    pub valid_after: u64,
+}
+
+impl CachedSponsorship {
+    fn from_config(config: &PaymasterConfig) -> Self {
+        Self {
+            signature: hex::encode(config.signature),
+            valid_until: config.valid_until,
+            valid_after: config.valid_after,
+        }
+    }
+
+    /// Whether the signature is valid at `now` and stays valid for at least the reuse margin
+    pub fn is_usable(&self, now: u64) -> bool {
+        self.valid_after <= now && now + REUSE_MARGIN_SECS < self.valid_until
+    }
+
+    fn to_config(&self, paymaster_address: Address) -> Option<PaymasterConfig> {
+        let signature = hexutil::decode_fixed::<65>("cached signature", &self.signature).ok()?;
+        Some(PaymasterConfig {
+            paymaster_address,
+            signature,
+            valid_until: self.valid_until,
+            valid_after: self.valid_after,
+        })
+    }
+}
+
+/// Hash of the operation as sent for signing, with the service and paymaster it was signed for
+/// paymasterAndData is not part of it, it is where the signature goes
+pub fn operation_key(service_url: &str, paymaster: Address, user_op: &PackedUserOperationData) -> B256 {
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(service_url.as_bytes());
+    preimage.extend_from_slice(paymaster.as_slice());
+    for field in [
+        &user_op.sender,
+        &user_op.nonce,
+        &user_op.init_code,
+        &user_op.call_data,
+        &user_op.account_gas_limits,
+        &user_op.pre_verification_gas,
+        &user_op.gas_fees,
+    ] {
+        preimage.extend_from_slice(field.as_bytes());
+        preimage.push(0);
+    }
+    keccak256(preimage)
+}
+
+/// Sponsorships keyed by `operation_key`, optionally persisted to disk
+pub struct SponsorshipCache {
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<B256, CachedSponsorship>>,
+}
+
+impl SponsorshipCache {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache persisted at `~/.aa-client/sponsorships.json`, in memory when the data directory is unavailable
+    pub fn persistent() -> Self {
+        match data_dir() {
+            Ok(dir) => Self::at_path(dir.join(SPONSORSHIP_FILE)),
+            Err(_) => Self::in_memory(),
+        }
+    }
+
+    pub fn at_path(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Sponsorship for `key` still usable at `now`
+    pub fn get(&self, key: B256, paymaster_address: Address, now: u64) -> Option<PaymasterConfig> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&key)
+            .filter(|entry| entry.is_usable(now))
+            .and_then(|entry| entry.to_config(paymaster_address))
+    }
+
+    /// Remember a new sponsorship, dropping the ones that expired
+    pub fn insert(&self, key: B256, config: &PaymasterConfig, now: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.valid_until > now);
+        entries.insert(key, CachedSponsorship::from_config(config));
+        self.save(&entries);
+    }
+
+    /// Best-effort write, a read-only home directory only loses reuse across invocations
+    fn save(&self, entries: &HashMap<B256, CachedSponsorship>) {
+        let Some(path) = &self.path else { return };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(contents) = serde_json::to_string_pretty(entries) {
+            let _ = std::fs::write(path, contents);
+        }
+    }
+}
+
+static RESPONSOR: OnceLock<bool> = OnceLock::new();
+
+/// Ignore cached sponsorships for the rest of the process (`--responsor`)
+pub fn set_responsor(responsor: bool) {
+    let _ = RESPONSOR.set(responsor);
+}
+
+pub fn responsor() -> bool {
+    RESPONSOR.get().copied().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_op(nonce: &str) -> PackedUserOperationData {
+        PackedUserOperationData {
+            sender: "0x1234567890123456789012345678901234567890".to_string(),
+            nonce: nonce.to_string(),
+            init_code: "0x".to_string(),
+            call_data: "0x1234".to_string(),
+            account_gas_limits: "0x00000000000f424000000000000f4240".to_string(),
+            pre_verification_gas: "46000".to_string(),
+            gas_fees: "0x000000000077359400000000003b9aca00".to_string(),
+            paymaster_and_data: "0x".to_string(),
+        }
+    }
+
+    fn config(valid_until: u64) -> PaymasterConfig {
+        PaymasterConfig {
+            paymaster_address: Address::from([0x22; 20]),
+            signature: [0x1b; 65],
+            valid_until,
+            valid_after: 0,
+        }
+    }
+
+    #[test]
+    fn test_reuse_respects_validity_window() {
+        let paymaster = Address::from([0x22; 20]);
+        let cache = SponsorshipCache::in_memory();
+        let key = operation_key("http://localhost:3000", paymaster, &user_op("1"));
+        cache.insert(key, &config(1_000), 100);
+
+        assert_eq!(cache.get(key, paymaster, 500).unwrap().signature, [0x1b; 65]);
+        assert!(cache.get(key, paymaster, 1_000 - REUSE_MARGIN_SECS).is_none());
+        assert_ne!(key, operation_key("http://localhost:3000", paymaster, &user_op("2")));
+        assert_ne!(key, operation_key("http://localhost:4000", paymaster, &user_op("1")));
+    }
+
+    #[test]
+    fn test_persistent_round_trip() {
+        let path = std::env::temp_dir().join(format!("aa-sponsorships-{}.json", std::process::id()));
+        let paymaster = Address::from([0x22; 20]);
+        let key = operation_key("http://localhost:3000", paymaster, &user_op("1"));
+        let stale = operation_key("http://localhost:3000", paymaster, &user_op("0"));
+
+        let cache = SponsorshipCache::at_path(path.clone());
+        cache.insert(stale, &config(200), 100);
+        cache.insert(key, &config(10_000), 300);
+
+        let reloaded = SponsorshipCache::at_path(path.clone());
+        assert!(reloaded.get(key, paymaster, 400).is_some());
+        assert!(reloaded.entries.lock().unwrap().get(&stale).is_none());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,282 @@
+// Stuck-operation detection behind `ops watch`
+// Journaled operations pending past a threshold are diagnosed from bundler and chain state, then remediated or alerted on
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::account;
+use crate::journal::OperationRecord;
+
+/// paymasterData of the verifier paymaster: signature (65) || validUntil (8) || validAfter (8)
+const VALID_UNTIL_OFFSET: usize = 65;
+
+/// Receipt fields of `eth_getUserOperationReceipt`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptSummary {
+    pub success: bool,
+    pub actual_gas_used: U256,
+    pub actual_gas_cost: U256,
+    /// Logs emitted while executing the operation
+    #[serde(default)]
+    pub logs: Vec<ReceiptLog>,
+    /// Transaction that included the operation, absent from some bundlers' replies
+    #[serde(default)]
+    pub receipt: Option<IncludingTransaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncludingTransaction {
+    pub transaction_hash: B256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReceiptLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+/// Fields of a pending operation as returned by `eth_getUserOperationByHash`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingOperation {
+    pub nonce: U256,
+    pub max_fee_per_gas: U256,
+    #[serde(default)]
+    pub paymaster_data: Option<Bytes>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OperationByHash {
+    user_operation: PendingOperation,
+}
+
+/// Everything the diagnosis looks at, gathered by `observe`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    pub receipt: Option<ReceiptSummary>,
+    /// None when the bundler no longer knows the operation
+    pub pending: Option<PendingOperation>,
+    /// Next nonce the EntryPoint expects from the sender
+    pub onchain_nonce: U256,
+    /// Base fee of the latest block
+    pub base_fee: U256,
+}
+
+/// Why an operation is still pending
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "cause", rename_all = "snake_case")]
+pub enum Diagnosis {
+    /// It landed, only the journal was behind
+    Included,
+    /// The fee cap is below the current base fee
+    FeesTooLow { max_fee_per_gas: U256, base_fee: U256 },
+    /// An earlier nonce of the same account has not landed
+    NonceGap { nonce: U256, expected: U256 },
+    /// Another operation used the nonce, this one can never land
+    NonceUsed { nonce: U256, expected: U256 },
+    /// The paymaster signature expired before inclusion
+    SponsorshipExpired { valid_until: u64 },
+    /// The bundler dropped the operation from its mempool
+    Dropped,
+    /// In the mempool with nothing obviously wrong
+    Waiting,
+}
+
+impl Diagnosis {
+    /// One-line explanation for alerts and terminal output
+    pub fn describe(&self) -> String {
+        match self {
+            Diagnosis::Included => "included on chain".to_string(),
+            Diagnosis::FeesTooLow { max_fee_per_gas, base_fee } => format!(
+                "fees too low: maxFeePerGas {} wei is below the base fee {} wei, resubmit with higher fees",
+                max_fee_per_gas, base_fee
+            ),
+            Diagnosis::NonceGap { nonce, expected } => format!(
+                "nonce gap: operation uses nonce {} but the account is at {}, an earlier operation is missing",
+                nonce, expected
+            ),
+            Diagnosis::NonceUsed { nonce, expected } => format!(
+                "nonce {} already used (account is at {}), the operation was replaced",
+                nonce, expected
+            ),
+            Diagnosis::SponsorshipExpired { valid_until } => format!(
+                "sponsorship expired at {}, request a new one and resubmit",
+                valid_until
+            ),
+            Diagnosis::Dropped => "dropped by the bundler, it will not be included".to_string(),
+            Diagnosis::Waiting => "still in the bundler mempool with no apparent problem".to_string(),
+        }
+    }
+}
+
+/// Decide why an operation is stuck at unix time `now`
+pub fn diagnose(observation: &Observation, now: u64) -> Diagnosis {
+    if observation.receipt.is_some() {
+        return Diagnosis::Included;
+    }
+    let Some(pending) = &observation.pending else {
+        return Diagnosis::Dropped;
+    };
+
+    let expected = observation.onchain_nonce;
+    if pending.nonce < expected {
+        return Diagnosis::NonceUsed { nonce: pending.nonce, expected };
+    }
+    if let Some(valid_until) = pending.paymaster_data.as_ref().and_then(|data| sponsorship_valid_until(data)) {
+        // validUntil 0 means the sponsorship never expires
+        if valid_until != 0 && valid_until < now {
+            return Diagnosis::SponsorshipExpired { valid_until };
+        }
+    }
+    if pending.nonce > expected {
+        return Diagnosis::NonceGap { nonce: pending.nonce, expected };
+    }
+    if pending.max_fee_per_gas < observation.base_fee {
+        return Diagnosis::FeesTooLow { max_fee_per_gas: pending.max_fee_per_gas, base_fee: observation.base_fee };
+    }
+    Diagnosis::Waiting
+}
+
+/// validUntil carried by verifier paymaster data, None for other paymasters
+pub fn sponsorship_valid_until(paymaster_data: &[u8]) -> Option<u64> {
+    let bytes = paymaster_data.get(VALID_UNTIL_OFFSET..VALID_UNTIL_OFFSET + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// What the watcher does with a diagnosed operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Remediation {
+    /// Record the receipt in the journal
+    RecordReceipt,
+    /// Mark the operation failed in the journal, it can never land
+    MarkFailed,
+    /// Leave it pending and alert, fixing it needs a new signature
+    Alert,
+}
+
+/// Remediation for `diagnosis`; with `auto` off, anything short of a receipt is only alerted on
+pub fn remediation(diagnosis: &Diagnosis, auto: bool) -> Remediation {
+    match diagnosis {
+        Diagnosis::Included => Remediation::RecordReceipt,
+        Diagnosis::NonceUsed { .. } | Diagnosis::Dropped if auto => Remediation::MarkFailed,
+        _ => Remediation::Alert,
+    }
+}
+
+/// Webhook payload for a stuck operation
+#[derive(Debug, Clone, Serialize)]
+pub struct StuckAlert {
+    pub user_op_hash: String,
+    pub chain_id: u64,
+    pub sender: Address,
+    pub tag: Option<String>,
+    pub pending_secs: u64,
+    pub diagnosis: Diagnosis,
+    pub summary: String,
+    pub remediation: Remediation,
+}
+
+/// Receipt of an operation, None while it has not landed
+pub async fn get_receipt<P: Provider>(provider: &P, user_op_hash: &str) -> Result<Option<ReceiptSummary>> {
+    Ok(provider
+        .raw_request("eth_getUserOperationReceipt".into(), (user_op_hash.to_string(),))
+        .await?)
+}
+
+/// Gather bundler and chain state for a pending operation
+pub async fn observe<P: Provider>(provider: &P, record: &OperationRecord, entry_point: Address) -> Result<Observation> {
+    let receipt = get_receipt(provider, &record.user_op_hash).await?;
+    if receipt.is_some() {
+        return Ok(Observation { receipt, pending: None, onchain_nonce: U256::ZERO, base_fee: U256::ZERO });
+    }
+
+    let pending: Option<OperationByHash> = provider
+        .raw_request("eth_getUserOperationByHash".into(), (record.user_op_hash.clone(),))
+        .await?;
+    let onchain_nonce = account::get_nonce(provider, entry_point, record.sender).await?;
+    let history = provider.get_fee_history(1, BlockNumberOrTag::Latest, &[]).await?;
+    let base_fee = history.base_fee_per_gas.first().copied().unwrap_or_default();
+
+    Ok(Observation {
+        receipt: None,
+        pending: pending.map(|op| op.user_operation),
+        onchain_nonce,
+        base_fee: U256::from(base_fee),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(nonce: u64, max_fee_per_gas: u64, valid_until: Option<u64>) -> Observation {
+        let paymaster_data = valid_until.map(|valid_until| {
+            let mut data = vec![0u8; 65];
+            data.extend_from_slice(&valid_until.to_be_bytes());
+            data.extend_from_slice(&0u64.to_be_bytes());
+            Bytes::from(data)
+        });
+        Observation {
+            receipt: None,
+            pending: Some(PendingOperation {
+                nonce: U256::from(nonce),
+                max_fee_per_gas: U256::from(max_fee_per_gas),
+                paymaster_data,
+            }),
+            onchain_nonce: U256::from(5),
+            base_fee: U256::from(1_000),
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let now = 1_700_000_000;
+        assert_eq!(diagnose(&pending(5, 2_000, None), now), Diagnosis::Waiting);
+        assert!(matches!(diagnose(&pending(5, 500, None), now), Diagnosis::FeesTooLow { .. }));
+        assert!(matches!(diagnose(&pending(7, 2_000, None), now), Diagnosis::NonceGap { .. }));
+        assert!(matches!(diagnose(&pending(4, 2_000, None), now), Diagnosis::NonceUsed { .. }));
+        assert_eq!(
+            diagnose(&pending(5, 2_000, Some(now - 1)), now),
+            Diagnosis::SponsorshipExpired { valid_until: now - 1 }
+        );
+        assert_eq!(diagnose(&pending(5, 2_000, Some(now + 60)), now), Diagnosis::Waiting);
+
+        let mut dropped = pending(5, 2_000, None);
+        dropped.pending = None;
+        assert_eq!(diagnose(&dropped, now), Diagnosis::Dropped);
+
+        dropped.receipt = Some(ReceiptSummary { success: true, actual_gas_used: U256::from(1), actual_gas_cost: U256::from(1), logs: Vec::new(), receipt: None });
+        assert_eq!(diagnose(&dropped, now), Diagnosis::Included);
+    }
+
+    #[test]
+    fn test_remediation_policy() {
+        assert_eq!(remediation(&Diagnosis::Included, false), Remediation::RecordReceipt);
+        assert_eq!(remediation(&Diagnosis::Dropped, false), Remediation::Alert);
+        assert_eq!(remediation(&Diagnosis::Dropped, true), Remediation::MarkFailed);
+        let gap = Diagnosis::NonceGap { nonce: U256::from(7), expected: U256::from(5) };
+        assert_eq!(remediation(&gap, true), Remediation::Alert);
+    }
+
+    #[test]
+    fn test_receipt_and_operation_wire_format() {
+        let receipt: ReceiptSummary = serde_json::from_str(
+            r#"{"success":true,"actualGasUsed":"0x5208","actualGasCost":"0x10","logs":[]}"#
+        ).unwrap();
+        assert_eq!(receipt.actual_gas_used, U256::from(21000));
+
+        let by_hash: OperationByHash = serde_json::from_str(
+            r#"{"userOperation":{"sender":"0x01","nonce":"0x3","maxFeePerGas":"0x3b9aca00"},"entryPoint":"0x02"}"#
+        ).unwrap();
+        assert_eq!(by_hash.user_operation.nonce, U256::from(3));
+        assert_eq!(by_hash.user_operation.paymaster_data, None);
+    }
+}
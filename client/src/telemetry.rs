@@ -0,0 +1,151 @@
+// Tracing spans of an operation's way to the chain: fill, estimation, sponsorship, submission and the
+// receipt, each with the latency of the bundler or service it waited on
+// Spans are plain `tracing` spans, so a service embedding the library sees them in its own subscriber;
+// with the `otel` feature `init` exports them over OTLP/HTTP, which the binary does for `--otlp-endpoint`
+
+use tracing::level_filters::LevelFilter;
+use tracing::Span;
+
+use crate::output;
+
+/// Span names of the pipeline stages
+pub const FILL: &str = "userop.fill";
+pub const ESTIMATE: &str = "userop.estimate";
+pub const SPONSOR: &str = "userop.sponsor";
+pub const SUBMIT: &str = "userop.submit";
+pub const RECEIPT: &str = "userop.receipt";
+
+/// Attribute holding the UserOperation hash once a bundler accepted the operation
+pub const USER_OP_HASH: &str = "userop.hash";
+
+/// Span name of a network call, by the name the retry policy knows it under
+/// Bundler calls name their endpoint too ("eth_sendUserOperation on <url>"), which is kept as the `call` attribute
+pub fn stage_of(what: &str) -> &str {
+    let method = what.split(" on ").next().unwrap_or(what);
+    match method {
+        "fill UserOperation" => FILL,
+        "eth_estimateUserOperationGas" => ESTIMATE,
+        "eth_sendUserOperation" => SUBMIT,
+        "eth_getUserOperationReceipt" => RECEIPT,
+        _ => what,
+    }
+}
+
+/// Span around one network call including its retries
+pub fn call_span(what: &str) -> Span {
+    tracing::info_span!(
+        "call",
+        otel.name = stage_of(what),
+        call = what,
+        retries = tracing::field::Empty,
+        otel.status_code = tracing::field::Empty,
+        otel.status_message = tracing::field::Empty,
+    )
+}
+
+/// Mark `span` as failed with `error`
+pub fn record_error(span: &Span, error: &impl std::fmt::Display) {
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", error.to_string());
+}
+
+/// Put the hash of the operation on the current span, e.g. the command span or one the embedding service opened
+pub fn record_user_op_hash(user_op_hash: &str) {
+    let span = Span::current();
+    span.record(USER_OP_HASH, user_op_hash);
+    // Spans that did not declare the field still get it as an OpenTelemetry attribute
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        span.set_attribute(USER_OP_HASH, user_op_hash.to_string());
+    }
+}
+
+/// W3C trace context headers of the current span, so the paymaster service joins the trace
+/// Empty without the `otel` feature or outside a sampled trace
+pub fn trace_headers() -> Vec<(String, String)> {
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let mut headers = std::collections::HashMap::new();
+        let context = Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut headers));
+        headers.into_iter().filter(|(_, value): &(String, String)| !value.is_empty()).collect()
+    }
+    #[cfg(not(feature = "otel"))]
+    Vec::new()
+}
+
+/// Exports the spans still buffered when dropped, before the process exits
+pub struct Exporter(#[cfg(feature = "otel")] opentelemetry_sdk::trace::SdkTracerProvider);
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Err(e) = self.0.shutdown() {
+            eprintln!("Could not export traces: {}", e);
+        }
+    }
+}
+
+/// Install client output at `level` and export the client's spans to the OTLP/HTTP collector at `endpoint`,
+/// or OTEL_EXPORTER_OTLP_ENDPOINT; without either only the output is installed
+#[cfg(feature = "otel")]
+pub fn init(endpoint: Option<&str>, level: LevelFilter) -> anyhow::Result<Exporter> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::Layer;
+
+    const SERVICE_NAME: &str = "aa-client";
+
+    let Some(endpoint) = endpoint.map(str::to_string).or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()) else {
+        output::init(level);
+        return Ok(Exporter(SdkTracerProvider::builder().build()));
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid OTLP endpoint {}: {}", endpoint, e))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    // Spans are exported whatever --quiet or --verbose do to the output
+    let spans = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(SERVICE_NAME))
+        .with_filter(Targets::new().with_target("aa_client", LevelFilter::INFO));
+    let _ = tracing_subscriber::registry().with(output::layer(level)).with(spans).try_init();
+    Ok(Exporter(provider))
+}
+
+/// Install client output at `level`; this build cannot export spans
+#[cfg(not(feature = "otel"))]
+pub fn init(endpoint: Option<&str>, level: LevelFilter) -> anyhow::Result<Exporter> {
+    output::init(level);
+    if endpoint.is_some() {
+        crate::out!("⚠️  This build cannot export traces, rebuild with --features otel to use --otlp-endpoint");
+    }
+    Ok(Exporter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_of() {
+        assert_eq!(stage_of("fill UserOperation"), FILL);
+        assert_eq!(stage_of("eth_sendUserOperation"), SUBMIT);
+        assert_eq!(stage_of("eth_estimateUserOperationGas on https://bundler.example"), ESTIMATE);
+        assert_eq!(stage_of("Paymaster /sign"), "Paymaster /sign");
+    }
+}
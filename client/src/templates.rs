@@ -0,0 +1,219 @@
+// Named operation templates kept in ~/.aa-client/templates.toml: a target, a function and the gas and
+// sponsorship settings of a repeated call, run with only the arguments that change (`run mint --arg id=5`)
+
+use alloy::json_abi::Function;
+use alloy::primitives::Bytes;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::calldata;
+use crate::config::data_dir;
+
+const TEMPLATES_FILE: &str = "templates.toml";
+
+/// Everything about an operation except its variable arguments
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    pub target: String,
+    /// Function with named parameters, e.g. `mint(address to, uint256 id)`
+    pub function: String,
+    /// ETH sent with the call, with a unit suffix like `send --value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Arguments fixed when saving, `run --arg` can still override them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sponsored: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_address: Option<String>,
+}
+
+impl Template {
+    fn parsed_function(&self) -> Result<Function> {
+        Function::parse(&self.function).map_err(|e| anyhow::anyhow!("Invalid function signature '{}': {}", self.function, e))
+    }
+
+    /// Reject templates `run` could never encode: unnamed parameters, fixed arguments the function does not take,
+    /// or explicit fees next to sponsorship
+    pub fn validate(&self) -> Result<()> {
+        let function = self.parsed_function()?;
+        if let Some(unnamed) = function.inputs.iter().position(|param| param.name.is_empty()) {
+            return Err(anyhow::anyhow!("Parameter {} of {} needs a name to be given with --arg", unnamed + 1, self.function));
+        }
+        self.check_names(&function, &self.args)?;
+        if self.sponsored && (self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some()) {
+            return Err(anyhow::anyhow!("A sponsored template takes its fees from the paymaster, drop --max-fee-per-gas"));
+        }
+        Ok(())
+    }
+
+    /// Parameters still to be given on `run`, in declaration order
+    pub fn variables(&self) -> Result<Vec<String>> {
+        Ok(self
+            .parsed_function()?
+            .inputs
+            .into_iter()
+            .map(|param| param.name)
+            .filter(|name| !self.args.contains_key(name))
+            .collect())
+    }
+
+    /// Call data with `args` on top of the fixed arguments
+    pub fn call_data(&self, args: &BTreeMap<String, String>) -> Result<Bytes> {
+        let function = self.parsed_function()?;
+        self.check_names(&function, args)?;
+        let mut values = Vec::new();
+        let mut missing = Vec::new();
+        for param in &function.inputs {
+            match args.get(&param.name).or_else(|| self.args.get(&param.name)) {
+                Some(value) => values.push(value.clone()),
+                None => missing.push(format!("--arg {}=...", param.name)),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("Missing template arguments: {}", missing.join(" ")));
+        }
+        calldata::encode(&self.function, &values)
+    }
+
+    fn check_names(&self, function: &Function, args: &BTreeMap<String, String>) -> Result<()> {
+        match args.keys().find(|name| !function.inputs.iter().any(|param| &param.name == *name)) {
+            Some(unknown) => Err(anyhow::anyhow!("{} has no parameter '{}'", self.function, unknown)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `NAME=VALUE` pairs from repeated `--arg` flags
+pub fn parse_args(pairs: &[String]) -> Result<BTreeMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("Expected --arg NAME=VALUE, got '{}'", pair))
+        })
+        .collect()
+}
+
+/// Templates file, one table per template name
+pub struct TemplateStore {
+    path: PathBuf,
+}
+
+impl TemplateStore {
+    /// Store at `~/.aa-client/templates.toml`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(data_dir()?.join(TEMPLATES_FILE)))
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Saved templates sorted by name
+    pub fn list(&self) -> Result<BTreeMap<String, Template>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        toml::from_str(&std::fs::read_to_string(&self.path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid templates file {}: {}", self.path.display(), e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Template> {
+        self.list()?
+            .remove(name)
+            .ok_or_else(|| anyhow::anyhow!("No template named '{}', see `template list`", name))
+    }
+
+    /// Save `template` as `name`, returning whether it replaced an existing one
+    pub fn save(&self, name: &str, template: Template) -> Result<bool> {
+        template.validate()?;
+        let mut templates = self.list()?;
+        let replaced = templates.insert(name.to_string(), template).is_some();
+        self.write(&templates)?;
+        Ok(replaced)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let mut templates = self.list()?;
+        if templates.remove(name).is_none() {
+            return Err(anyhow::anyhow!("No template named '{}', see `template list`", name));
+        }
+        self.write(&templates)
+    }
+
+    fn write(&self, templates: &BTreeMap<String, Template>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, toml::to_string_pretty(templates)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mint() -> Template {
+        Template {
+            target: "0x1234567890123456789012345678901234567890".to_string(),
+            function: "mint(address to, uint256 id)".to_string(),
+            args: parse_args(&["to=0x1111111111111111111111111111111111111111".to_string()]).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_call_data_merges_fixed_arguments() {
+        let template = mint();
+        assert_eq!(template.variables().unwrap(), vec!["id"]);
+
+        let call_data = template.call_data(&parse_args(&["id=5".to_string()]).unwrap()).unwrap();
+        let expected = calldata::encode(
+            "mint(address,uint256)",
+            &["0x1111111111111111111111111111111111111111".to_string(), "5".to_string()],
+        )
+        .unwrap();
+        assert_eq!(call_data, expected);
+
+        let missing = template.call_data(&BTreeMap::new()).unwrap_err().to_string();
+        assert!(missing.contains("--arg id=..."));
+        assert!(template.call_data(&parse_args(&["amount=1".to_string()]).unwrap()).is_err());
+        assert!(parse_args(&["id".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(mint().validate().is_ok());
+        assert!(Template { function: "mint(address,uint256)".to_string(), args: BTreeMap::new(), ..mint() }.validate().is_err());
+        assert!(Template { sponsored: true, max_fee_per_gas: Some("1".to_string()), ..mint() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("aa-client-templates-{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = TemplateStore::open(&path);
+
+        assert!(!store.save("mint", mint()).unwrap());
+        assert!(store.save("mint", Template { sponsored: true, ..mint() }).unwrap());
+        assert!(store.get("mint").unwrap().sponsored);
+        assert!(store.get("burn").is_err());
+
+        store.remove("mint").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
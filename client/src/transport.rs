@@ -0,0 +1,146 @@
+// Same-host transport for local services (paymaster-service, the watch daemon) without exposing a TCP port
+// URLs of the form unix:///path/to.sock or npipe://name select a local socket instead of HTTP over TCP
+
+use anyhow::Result;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::Request;
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::telemetry;
+
+/// Local socket a service URL points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalSocket {
+    /// Unix domain socket path
+    Unix(String),
+    /// Windows named pipe, e.g. `\\.\pipe\paymaster`
+    NamedPipe(String),
+}
+
+impl LocalSocket {
+    /// Parse `unix://<path>` or `npipe://<name>`, returning None for regular HTTP URLs
+    pub fn from_url(url: &str) -> Option<Self> {
+        if let Some(path) = url.strip_prefix("unix://") {
+            return Some(LocalSocket::Unix(path.to_string()));
+        }
+        if let Some(name) = url.strip_prefix("npipe://") {
+            let name = if name.starts_with(r"\\") {
+                name.to_string()
+            } else {
+                format!(r"\\.\pipe\{}", name)
+            };
+            return Some(LocalSocket::NamedPipe(name));
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for LocalSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocalSocket::Unix(path) => write!(f, "unix://{}", path),
+            LocalSocket::NamedPipe(name) => write!(f, "npipe://{}", name),
+        }
+    }
+}
+
+/// Bind a Unix socket at `path` that only the current user may connect to
+/// Only a socket left behind by a previous run is replaced, any other file at `path` is an error
+#[cfg(unix)]
+pub fn bind_unix(path: &str) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => return Err(anyhow::anyhow!("{} exists and is not a socket, refusing to replace it", path)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Reply from a local service
+pub struct LocalReply {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Retry-After header of a rate-limited reply, when given in seconds
+    pub retry_after: Option<std::time::Duration>,
+    pub body: Vec<u8>,
+}
+
+/// POST `body` to `path` on a service listening on a local socket
+pub async fn post(socket: &LocalSocket, path: &str, content_type: &str, body: Vec<u8>) -> Result<LocalReply> {
+    match socket {
+        #[cfg(unix)]
+        LocalSocket::Unix(socket_path) => {
+            let stream = tokio::net::UnixStream::connect(socket_path)
+                .await
+                .map_err(|e| anyhow::anyhow!("Cannot connect to {}: {}", socket_path, e))?;
+            send(stream, path, content_type, body).await
+        }
+        #[cfg(windows)]
+        LocalSocket::NamedPipe(name) => {
+            let pipe = tokio::net::windows::named_pipe::ClientOptions::new()
+                .open(name)
+                .map_err(|e| anyhow::anyhow!("Cannot connect to {}: {}", name, e))?;
+            send(pipe, path, content_type, body).await
+        }
+        #[allow(unreachable_patterns)]
+        other => Err(anyhow::anyhow!("{:?} is not supported on this platform", other)),
+    }
+}
+
+async fn send<IO>(io: IO, path: &str, content_type: &str, body: Vec<u8>) -> Result<LocalReply>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io)).await?;
+    tokio::spawn(connection);
+
+    let mut request = Request::post(path)
+        .header(hyper::header::HOST, "localhost")
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .header(hyper::header::ACCEPT, content_type);
+    for (name, value) in telemetry::trace_headers() {
+        request = request.header(name, value);
+    }
+    let request = request.body(Full::new(Bytes::from(body)))?;
+    let response = sender.send_request(request).await?;
+
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let retry_after = response
+        .headers()
+        .get(hyper::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::retry::parse_retry_after);
+    let body = response.into_body().collect().await?.to_bytes().to_vec();
+
+    Ok(LocalReply { status, content_type, retry_after, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_socket_from_url() {
+        assert_eq!(LocalSocket::from_url("http://localhost:3000"), None);
+        assert_eq!(
+            LocalSocket::from_url("unix:///run/paymaster.sock"),
+            Some(LocalSocket::Unix("/run/paymaster.sock".to_string()))
+        );
+        assert_eq!(
+            LocalSocket::from_url("npipe://paymaster"),
+            Some(LocalSocket::NamedPipe(r"\\.\pipe\paymaster".to_string()))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// Line-based prompts for the guided `tui` mode
+// Every question shows its default in brackets, Enter accepts it and invalid answers are asked again
+
+use anyhow::Result;
+use std::io::{BufRead, Write};
+
+/// Asks questions on `output` and reads the answers from `input`
+pub struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl Prompter<std::io::StdinLock<'static>, std::io::Stdout> {
+    /// Prompts on the terminal
+    pub fn terminal() -> Self {
+        Self::new(std::io::stdin().lock(), std::io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Section heading between groups of questions
+    pub fn heading(&mut self, title: &str) -> Result<()> {
+        writeln!(self.output, "\n== {} ==", title)?;
+        Ok(())
+    }
+
+    /// Free-text answer, `default` when the answer is empty
+    pub fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        loop {
+            match default {
+                Some(default) => write!(self.output, "{} [{}]: ", question, default)?,
+                None => write!(self.output, "{}: ", question)?,
+            }
+            self.output.flush()?;
+
+            let answer = self.read_line()?;
+            match (answer.is_empty(), default) {
+                (false, _) => return Ok(answer),
+                (true, Some(default)) => return Ok(default.to_string()),
+                (true, None) => writeln!(self.output, "An answer is required")?,
+            }
+        }
+    }
+
+    /// Answer converted by `parse`, asked again with the error when it fails
+    pub fn ask_parsed<T>(&mut self, question: &str, default: Option<&str>, parse: impl Fn(&str) -> Result<T>) -> Result<T> {
+        loop {
+            let answer = self.ask(question, default)?;
+            match parse(&answer) {
+                Ok(value) => return Ok(value),
+                Err(e) => writeln!(self.output, "Invalid answer: {}", e)?,
+            }
+        }
+    }
+
+    /// Index of one of `options`, chosen by its number
+    pub fn choose(&mut self, question: &str, options: &[String], default: usize) -> Result<usize> {
+        writeln!(self.output, "{}", question)?;
+        for (i, option) in options.iter().enumerate() {
+            writeln!(self.output, "  {}) {}", i + 1, option)?;
+        }
+        let count = options.len();
+        self.ask_parsed("Choice", Some(&(default + 1).to_string()), |answer| match answer.parse::<usize>() {
+            Ok(choice) if (1..=count).contains(&choice) => Ok(choice - 1),
+            _ => Err(anyhow::anyhow!("enter a number from 1 to {}", count)),
+        })
+    }
+
+    /// Yes or no
+    pub fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        self.ask_parsed(question, Some(if default { "y" } else { "n" }), |answer| match answer.to_lowercase().as_str() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err(anyhow::anyhow!("answer y or n")),
+        })
+    }
+
+    /// Answers until an empty line, e.g. the arguments of a function call
+    pub fn ask_list(&mut self, question: &str) -> Result<Vec<String>> {
+        writeln!(self.output, "{} (one per line, empty line to finish)", question)?;
+        let mut answers = Vec::new();
+        loop {
+            write!(self.output, "  {}> ", answers.len() + 1)?;
+            self.output.flush()?;
+            let answer = self.read_line()?;
+            if answer.is_empty() {
+                return Ok(answers);
+            }
+            answers.push(answer);
+        }
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("Input closed before the walkthrough finished"));
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompter(input: &str) -> Prompter<&[u8], Vec<u8>> {
+        Prompter::new(input.as_bytes(), Vec::new())
+    }
+
+    #[test]
+    fn test_defaults_and_retries() {
+        let mut p = prompter("\n0x01\n\nmaybe\nY\n");
+        assert_eq!(p.ask("Salt", Some("0x00")).unwrap(), "0x00");
+        assert_eq!(p.ask("Salt", Some("0x00")).unwrap(), "0x01");
+        assert_eq!(p.ask_parsed("Gas", Some("7"), |a| Ok(a.parse::<u64>()?)).unwrap(), 7);
+        assert!(p.confirm("Send", false).unwrap());
+        assert!(String::from_utf8(p.output).unwrap().contains("answer y or n"));
+    }
+
+    #[test]
+    fn test_choose_and_list() {
+        let options = vec!["Sepolia".to_string(), "Anvil".to_string()];
+        let mut p = prompter("3\n2\n\n0xabc\n42\n\n");
+        assert_eq!(p.choose("Network", &options, 0).unwrap(), 1);
+        assert_eq!(p.choose("Network", &options, 0).unwrap(), 0);
+        assert_eq!(p.ask_list("Arguments").unwrap(), vec!["0xabc", "42"]);
+        assert!(p.ask("Target", None).is_err());
+    }
+}
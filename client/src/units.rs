@@ -0,0 +1,103 @@
+// Human-readable token and ether amounts
+// Amounts are parsed as exact decimals, never through floating point
+
+use alloy::primitives::U256;
+use anyhow::Result;
+
+/// Scale a decimal amount (`1.5`) to base units with `decimals` fractional digits
+pub fn parse_units(amount: &str, decimals: u8) -> Result<U256> {
+    let amount = amount.trim();
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(anyhow::anyhow!("Invalid amount '{}'", amount));
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::anyhow!("Invalid amount '{}', expected a non-negative decimal number", amount));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(anyhow::anyhow!("Amount '{}' has more than {} decimal places", amount, decimals));
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::ZERO);
+    }
+    U256::from_str_radix(digits, 10).map_err(|_| anyhow::anyhow!("Amount '{}' is too large", amount))
+}
+
+/// Parse an ether value with an `eth`, `gwei` or `wei` suffix (`0.1eth`, `20 gwei`); bare numbers are wei
+pub fn parse_value(value: &str) -> Result<U256> {
+    let lower = value.trim().to_ascii_lowercase();
+    let (amount, decimals) = if let Some(amount) = lower.strip_suffix("gwei") {
+        (amount, 9)
+    } else if let Some(amount) = lower.strip_suffix("wei") {
+        (amount, 0)
+    } else if let Some(amount) = lower.strip_suffix("ether").or_else(|| lower.strip_suffix("eth")) {
+        (amount, 18)
+    } else {
+        (lower.as_str(), 0)
+    };
+    parse_units(amount, decimals)
+        .map_err(|e| anyhow::anyhow!("Invalid value '{}' (e.g. 0.1eth, 20gwei, 1000wei): {}", value, e))
+}
+
+/// Base units as a decimal with `decimals` fractional digits, trailing zeros trimmed
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1.5", 6).unwrap(), U256::from(1_500_000));
+        assert_eq!(parse_units("1000000", 0).unwrap(), U256::from(1_000_000));
+        assert_eq!(parse_units(".25", 2).unwrap(), U256::from(25));
+        assert_eq!(parse_units("0", 18).unwrap(), U256::ZERO);
+        assert_eq!(parse_units("1", 18).unwrap(), U256::from(10u64).pow(U256::from(18)));
+
+        assert!(parse_units("1.0000001", 6).is_err());
+        assert!(parse_units("-1", 6).is_err());
+        assert!(parse_units("1e6", 6).is_err());
+        assert!(parse_units(".", 6).is_err());
+    }
+
+    #[test]
+    fn test_parse_value_suffixes() {
+        let gwei = U256::from(1_000_000_000u64);
+        assert_eq!(parse_value("0.1eth").unwrap(), gwei * U256::from(100_000_000u64));
+        assert_eq!(parse_value("1 ETHER").unwrap(), gwei * gwei);
+        assert_eq!(parse_value("20gwei").unwrap(), gwei * U256::from(20));
+        assert_eq!(parse_value("1.5 gwei").unwrap(), U256::from(1_500_000_000u64));
+        assert_eq!(parse_value("1000wei").unwrap(), U256::from(1000));
+        assert_eq!(parse_value("1000").unwrap(), U256::from(1000));
+
+        assert!(parse_value("0.1").is_err());
+        assert!(parse_value("0.5wei").is_err());
+        assert!(parse_value("1btc").is_err());
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::from(100_000_000_000_000_000u64), 18), "0.1");
+        assert_eq!(format_units(U256::from(1_500_000), 6), "1.5");
+        assert_eq!(format_units(U256::from(3_000_000), 6), "3");
+        assert_eq!(format_units(U256::ZERO, 18), "0");
+        assert_eq!(format_units(U256::from(42), 0), "42");
+    }
+}
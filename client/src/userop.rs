@@ -1,8 +1,15 @@
 // Refactored to use aa-sdk-rs types and functionality
 // This replaces the custom implementation with the proper SDK
 
-use alloy::primitives::{Address, Bytes, U256};
+use alloy::eips::eip7702::SignedAuthorization;
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::signers::{Signature, SignerSync};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::multisig::UnsignedUserOperation;
+use crate::remote_signer::RemoteSigner;
+use crate::wallet::Wallet;
 
 // Re-export aa-sdk-rs types for compatibility
 pub use aa_sdk_rs::types::{
@@ -27,6 +34,13 @@ impl UserOperationBuilder {
         Self { request }
     }
 
+    /// Create a UserOperationBuilder executing several calls in one batch
+    pub fn new_batch(calls: Vec<ExecuteCall>) -> Self {
+        let request = UserOperationRequest::new_with_call(AccountCall::ExecuteBatch(calls));
+        
+        Self { request }
+    }
+
     /// Set the sender address
     pub fn with_sender(mut self, sender: Address) -> Self {
         self.request = self.request.sender(sender);
@@ -85,11 +99,434 @@ impl From<UserOpHash> for UserOperationResponse {
     }
 }
 
+/// UserOperation in the ERC-4337 JSON-RPC wire format (camelCase fields, hex quantities)
+/// Every field is optional so partially filled operations from other tools (viem, userop.js) load as well
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcUserOperation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory_data: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_data: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_verification_gas: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_verification_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_post_op_gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster_data: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Bytes>,
+    /// EIP-7702 authorization delegating the sender EOA, included by the bundler in its transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip7702_auth: Option<SignedAuthorization>,
+}
+
+impl RpcUserOperation {
+    pub fn from_json(contents: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(contents).map_err(|e| anyhow::anyhow!("Not an ERC-4337 UserOperation: {}", e))
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl From<&UserOperationRequest> for RpcUserOperation {
+    fn from(request: &UserOperationRequest) -> Self {
+        Self {
+            sender: request.sender,
+            nonce: request.nonce,
+            factory: request.factory,
+            factory_data: request.factory_data.clone(),
+            call_data: request.call_data.clone(),
+            call_gas_limit: request.call_gas_limit,
+            verification_gas_limit: request.verification_gas_limit,
+            pre_verification_gas: request.pre_verification_gas,
+            max_fee_per_gas: request.max_fee_per_gas,
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+            paymaster: request.paymaster,
+            paymaster_verification_gas_limit: request.paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit: request.paymaster_post_op_gas_limit,
+            paymaster_data: request.paymaster_data.clone(),
+            signature: None,
+            eip7702_auth: None,
+        }
+    }
+}
+
+/// The signature is not part of a request, the SDK signs when sending
+impl From<RpcUserOperation> for UserOperationRequest {
+    fn from(op: RpcUserOperation) -> Self {
+        let mut request = UserOperationRequest::default();
+        request.sender = op.sender;
+        request.nonce = op.nonce;
+        request.factory = op.factory;
+        request.factory_data = op.factory_data;
+        request.call_data = op.call_data;
+        request.call_gas_limit = op.call_gas_limit;
+        request.verification_gas_limit = op.verification_gas_limit;
+        request.pre_verification_gas = op.pre_verification_gas;
+        request.max_fee_per_gas = op.max_fee_per_gas;
+        request.max_priority_fee_per_gas = op.max_priority_fee_per_gas;
+        request.paymaster = op.paymaster;
+        request.paymaster_verification_gas_limit = op.paymaster_verification_gas_limit;
+        request.paymaster_post_op_gas_limit = op.paymaster_post_op_gas_limit;
+        request.paymaster_data = op.paymaster_data;
+        request
+    }
+}
+
+impl From<&UnsignedUserOperation> for RpcUserOperation {
+    fn from(user_op: &UnsignedUserOperation) -> Self {
+        Self {
+            sender: Some(user_op.sender),
+            nonce: Some(user_op.nonce),
+            factory: user_op.factory,
+            factory_data: user_op.factory_data.clone(),
+            call_data: Some(user_op.call_data.clone()),
+            call_gas_limit: Some(user_op.call_gas_limit),
+            verification_gas_limit: Some(user_op.verification_gas_limit),
+            pre_verification_gas: Some(user_op.pre_verification_gas),
+            max_fee_per_gas: Some(user_op.max_fee_per_gas),
+            max_priority_fee_per_gas: Some(user_op.max_priority_fee_per_gas),
+            paymaster: user_op.paymaster,
+            paymaster_verification_gas_limit: user_op.paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit: user_op.paymaster_post_op_gas_limit,
+            paymaster_data: user_op.paymaster_data.clone(),
+            signature: None,
+            eip7702_auth: None,
+        }
+    }
+}
+
+impl TryFrom<&RpcUserOperation> for UnsignedUserOperation {
+    type Error = anyhow::Error;
+
+    /// Every hashed field must be filled in, a default would hash a different operation than the one sent
+    fn try_from(op: &RpcUserOperation) -> anyhow::Result<Self> {
+        fn required<T: Clone>(value: &Option<T>, field: &str) -> anyhow::Result<T> {
+            value.clone().ok_or_else(|| anyhow::anyhow!("UserOperation has no {}, fill it before hashing", field))
+        }
+
+        Ok(Self {
+            sender: required(&op.sender, "sender")?,
+            nonce: required(&op.nonce, "nonce")?,
+            factory: op.factory,
+            factory_data: op.factory_data.clone(),
+            call_data: required(&op.call_data, "callData")?,
+            call_gas_limit: required(&op.call_gas_limit, "callGasLimit")?,
+            verification_gas_limit: required(&op.verification_gas_limit, "verificationGasLimit")?,
+            pre_verification_gas: required(&op.pre_verification_gas, "preVerificationGas")?,
+            max_fee_per_gas: required(&op.max_fee_per_gas, "maxFeePerGas")?,
+            max_priority_fee_per_gas: required(&op.max_priority_fee_per_gas, "maxPriorityFeePerGas")?,
+            paymaster: op.paymaster,
+            paymaster_verification_gas_limit: op.paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit: op.paymaster_post_op_gas_limit,
+            paymaster_data: op.paymaster_data.clone(),
+        })
+    }
+}
+
+impl TryFrom<&UserOperationRequest> for UnsignedUserOperation {
+    type Error = anyhow::Error;
+
+    fn try_from(request: &UserOperationRequest) -> anyhow::Result<Self> {
+        Self::try_from(&RpcUserOperation::from(request))
+    }
+}
+
+/// EntryPoint v0.7 userOpHash of a filled-in request, computed offline
+pub fn user_op_hash(request: &UserOperationRequest, entry_point: Address, chain_id: u64) -> anyhow::Result<B256> {
+    Ok(UnsignedUserOperation::try_from(request)?.hash(entry_point, chain_id))
+}
+
+/// Every input that determines a UserOperation, including data fetched from the chain
+/// Building from the same context always yields a byte-identical operation, so the context
+/// can be shared with co-signers and re-built by them to audit what they are signing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildContext {
+    pub chain_id: u64,
+    pub entry_point: Address,
+    pub sender: Address,
+    /// EntryPoint nonce fetched for the sender
+    pub nonce: U256,
+    /// Factory and calldata when the account is not deployed yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factory_data: Option<Bytes>,
+    pub target: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl BuildContext {
+    /// Build the unsigned operation; pure, no I/O and no clock or randomness
+    pub fn build(&self) -> UnsignedUserOperation {
+        let mut user_op = UnsignedUserOperation::execute(self.sender, self.nonce, self.target, self.value, self.data.clone());
+        user_op.factory = self.factory;
+        user_op.factory_data = self.factory_data.clone();
+        user_op.call_gas_limit = self.call_gas_limit;
+        user_op.verification_gas_limit = self.verification_gas_limit;
+        user_op.pre_verification_gas = self.pre_verification_gas;
+        user_op.max_fee_per_gas = self.max_fee_per_gas;
+        user_op.max_priority_fee_per_gas = self.max_priority_fee_per_gas;
+        user_op
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read build context {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// Filled and signed UserOperation, written by `create --sign` and sent later by `submit-file`
+/// Filling and signing need no network, so the file can be produced on an air-gapped machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedUserOperation {
+    pub chain_id: u64,
+    pub entry_point: Address,
+    pub user_op: UnsignedUserOperation,
+    /// Hash that was signed, kept for review before submitting
+    pub user_op_hash: B256,
+    pub signer: Address,
+    pub signature: Bytes,
+}
+
+impl SignedUserOperation {
+    /// Import an operation signed by another tool, recovering its signer from the ECDSA signature
+    pub fn from_rpc(op: &RpcUserOperation, entry_point: Address, chain_id: u64) -> anyhow::Result<Self> {
+        let user_op = UnsignedUserOperation::try_from(op)?;
+        let signature = op.signature.clone().ok_or_else(|| anyhow::anyhow!("UserOperation is not signed"))?;
+        let user_op_hash = user_op.hash(entry_point, chain_id);
+        let signer = recover_signer(&signature, user_op_hash)?;
+        Ok(Self { chain_id, entry_point, user_op, user_op_hash, signer, signature })
+    }
+
+    /// Sign the UserOperation hash (EIP-191) with the owner key
+    pub fn sign(user_op: UnsignedUserOperation, entry_point: Address, chain_id: u64, wallet: &Wallet) -> anyhow::Result<Self> {
+        let user_op_hash = user_op.hash(entry_point, chain_id);
+        let signature = wallet.signer().sign_message_sync(user_op_hash.as_slice())?;
+        Ok(Self {
+            chain_id,
+            entry_point,
+            user_op,
+            user_op_hash,
+            signer: wallet.address(),
+            signature: Bytes::from(signature.as_bytes().to_vec()),
+        })
+    }
+
+    /// Sign the UserOperation hash (EIP-191) with an account held by a remote signer
+    pub async fn sign_remote(user_op: UnsignedUserOperation, entry_point: Address, chain_id: u64, signer: &RemoteSigner) -> anyhow::Result<Self> {
+        let user_op_hash = user_op.hash(entry_point, chain_id);
+        let signature = signer.sign_message(user_op_hash.as_slice()).await?;
+        Ok(Self {
+            chain_id,
+            entry_point,
+            user_op,
+            user_op_hash,
+            signer: signer.address(),
+            signature: Bytes::from(signature.as_bytes().to_vec()),
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read signed UserOperation {}: {}", path.display(), e))?;
+        Self::from_json(&contents)
+    }
+
+    /// Parse a signed operation, rejecting it when the operation or signature was altered
+    pub fn from_json(contents: &str) -> anyhow::Result<Self> {
+        let signed: Self = serde_json::from_str(contents)?;
+
+        let expected = signed.user_op.hash(signed.entry_point, signed.chain_id);
+        if signed.user_op_hash != expected {
+            return Err(anyhow::anyhow!(
+                "Stored hash {} does not match its UserOperation ({}), the file was modified",
+                signed.user_op_hash, expected
+            ));
+        }
+        let recovered = recover_signer(&signed.signature, expected)?;
+        if recovered != signed.signer {
+            return Err(anyhow::anyhow!("Signature claimed by {} was made by {}", signed.signer, recovered));
+        }
+        Ok(signed)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Operation in the form `eth_sendUserOperation` expects
+    pub fn to_rpc(&self) -> RpcUserOperation {
+        RpcUserOperation { signature: Some(self.signature.clone()), ..RpcUserOperation::from(&self.user_op) }
+    }
+}
+
+// Owner key behind an EIP-191 signature of the UserOperation hash
+fn recover_signer(signature: &[u8], user_op_hash: B256) -> anyhow::Result<Address> {
+    let signature = Signature::try_from(signature)
+        .map_err(|e| anyhow::anyhow!("Signature is not a 65-byte ECDSA signature: {}", e))?;
+    Ok(signature.recover_address_from_msg(user_op_hash.as_slice())?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use alloy::primitives::Address;
 
+    fn context() -> BuildContext {
+        BuildContext {
+            chain_id: 11155111,
+            entry_point: Address::from([1u8; 20]),
+            sender: Address::from([2u8; 20]),
+            nonce: U256::from(5),
+            factory: None,
+            factory_data: None,
+            target: Address::from([3u8; 20]),
+            value: U256::from(1000),
+            data: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            call_gas_limit: U256::from(200000u64),
+            verification_gas_limit: U256::from(300000u64),
+            pre_verification_gas: U256::from(60000u64),
+            max_fee_per_gas: U256::from(5000000000u64),
+            max_priority_fee_per_gas: U256::from(200000000u64),
+        }
+    }
+
+    #[test]
+    fn test_build_is_byte_identical_for_identical_context() {
+        let first = context().build();
+        let second = context().build();
+        
+        assert_eq!(serde_json::to_vec(&first).unwrap(), serde_json::to_vec(&second).unwrap());
+        assert_eq!(first.hash(Address::from([1u8; 20]), 11155111), second.hash(Address::from([1u8; 20]), 11155111));
+    }
+
+    #[test]
+    fn test_context_round_trip_reproduces_operation() {
+        let original = context();
+        let json = serde_json::to_string(&original).unwrap();
+        let restored: BuildContext = serde_json::from_str(&json).unwrap();
+        
+        assert_eq!(restored, original);
+        assert_eq!(restored.build(), original.build());
+    }
+
+    #[test]
+    fn test_any_context_change_changes_operation() {
+        let base = context();
+        let mut bumped = context();
+        bumped.nonce = U256::from(6);
+        
+        assert_ne!(base.build().hash(base.entry_point, base.chain_id), bumped.build().hash(bumped.entry_point, bumped.chain_id));
+    }
+
+    #[test]
+    fn test_user_op_hash_matches_unpacked_operation() {
+        let ctx = context();
+        let mut request = UserOperationBuilder::new(ctx.target, ctx.value, ctx.data.clone())
+            .with_sender(ctx.sender)
+            .with_nonce(ctx.nonce)
+            .with_gas_fees(ctx.max_fee_per_gas, ctx.max_priority_fee_per_gas)
+            .build();
+        assert!(user_op_hash(&request, ctx.entry_point, ctx.chain_id).is_err());
+        
+        let expected = ctx.build();
+        request.call_data = Some(expected.call_data.clone());
+        request.call_gas_limit = Some(ctx.call_gas_limit);
+        request.verification_gas_limit = Some(ctx.verification_gas_limit);
+        request.pre_verification_gas = Some(ctx.pre_verification_gas);
+        assert_eq!(
+            user_op_hash(&request, ctx.entry_point, ctx.chain_id).unwrap(),
+            expected.hash(ctx.entry_point, ctx.chain_id)
+        );
+        
+        // Sponsorship is part of the hash
+        request.paymaster = Some(Address::from([4u8; 20]));
+        assert_ne!(
+            user_op_hash(&request, ctx.entry_point, ctx.chain_id).unwrap(),
+            expected.hash(ctx.entry_point, ctx.chain_id)
+        );
+    }
+
+    #[test]
+    fn test_signed_operation_round_trip_and_tamper_detection() {
+        let ctx = context();
+        let wallet = Wallet::from_hex("0x0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        let signed = SignedUserOperation::sign(ctx.build(), ctx.entry_point, ctx.chain_id, &wallet).unwrap();
+        
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(SignedUserOperation::from_json(&json).unwrap(), signed);
+        assert_eq!(SignedUserOperation::from_rpc(&signed.to_rpc(), ctx.entry_point, ctx.chain_id).unwrap(), signed);
+        
+        let mut altered = signed.clone();
+        altered.user_op.max_fee_per_gas = U256::from(1);
+        assert!(SignedUserOperation::from_json(&serde_json::to_string(&altered).unwrap()).is_err());
+        
+        let mut wrong_signer = signed;
+        wrong_signer.signer = Address::from([7u8; 20]);
+        assert!(SignedUserOperation::from_json(&serde_json::to_string(&wrong_signer).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_rpc_wire_format() {
+        let json = r#"{
+            "sender": "0x0202020202020202020202020202020202020202",
+            "nonce": "0x5",
+            "callData": "0xb61d27f6",
+            "callGasLimit": "0x30d40",
+            "verificationGasLimit": "0x493e0",
+            "preVerificationGas": "0xea60",
+            "maxFeePerGas": "0x12a05f200",
+            "maxPriorityFeePerGas": "0xbebc200",
+            "signature": "0x"
+        }"#;
+        let op = RpcUserOperation::from_json(json).unwrap();
+        assert_eq!(op.nonce, Some(U256::from(5)));
+        assert_eq!(op.call_gas_limit, Some(U256::from(200000u64)));
+        assert_eq!(op.factory, None);
+        
+        let exported: serde_json::Value = serde_json::from_str(&op.to_json().unwrap()).unwrap();
+        assert_eq!(exported["maxFeePerGas"], "0x12a05f200");
+        assert!(exported.get("paymaster").is_none());
+        
+        let user_op = UnsignedUserOperation::try_from(&op).unwrap();
+        assert_eq!(RpcUserOperation { signature: op.signature.clone(), ..RpcUserOperation::from(&user_op) }, op);
+        
+        let request = UserOperationRequest::from(op.clone());
+        assert_eq!(RpcUserOperation { signature: op.signature.clone(), ..RpcUserOperation::from(&request) }, op);
+    }
+
     #[test]
     fn test_user_operation_builder() {
         let target = Address::from([1u8; 20]);
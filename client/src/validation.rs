@@ -0,0 +1,123 @@
+// Guardrails on the ether an operation sends, checked before anything is signed
+// A value cap, a confirmation above a threshold and a denylist that always holds the common burn addresses,
+// set from the profile (`max_value`, `confirm_value_above`, `denylist`) or the matching flags
+
+use alloy::primitives::{address, Address, U256};
+use anyhow::Result;
+use std::sync::OnceLock;
+
+use crate::tui::Prompter;
+use crate::units;
+
+/// Addresses ether sent to is gone for good, denied whatever the configuration
+pub const BURN_ADDRESSES: [Address; 2] = [Address::ZERO, address!("000000000000000000000000000000000000dEaD")];
+
+/// Limits on the value of a single operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValueGuard {
+    /// Operations sending more are refused
+    pub max_value: Option<U256>,
+    /// Operations sending more need confirmation
+    pub confirm_above: Option<U256>,
+    /// Targets no value may be sent to, on top of `BURN_ADDRESSES`
+    pub denylist: Vec<Address>,
+    /// Confirmation given up front (`--yes`)
+    pub confirmed: bool,
+}
+
+/// What the guard makes of a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    /// Allowed once the user agrees to the question
+    Confirm(String),
+}
+
+impl ValueGuard {
+    /// Judge sending `value` wei to `target`, failing when the guard refuses it outright
+    pub fn check(&self, target: Address, value: U256) -> Result<Verdict> {
+        if value.is_zero() {
+            return Ok(Verdict::Allowed);
+        }
+        if BURN_ADDRESSES.contains(&target) || self.denylist.contains(&target) {
+            return Err(anyhow::anyhow!("Refusing to send {} ETH to denylisted address {}", units::format_units(value, 18), target));
+        }
+        if let Some(max_value) = self.max_value.filter(|max_value| value > *max_value) {
+            return Err(anyhow::anyhow!(
+                "Value {} ETH is above the {} ETH limit per operation (max_value)",
+                units::format_units(value, 18),
+                units::format_units(max_value, 18)
+            ));
+        }
+        match self.confirm_above {
+            Some(threshold) if value > threshold && !self.confirmed => Ok(Verdict::Confirm(format!(
+                "Send {} ETH to {}? It is above the {} ETH confirmation threshold",
+                units::format_units(value, 18),
+                target,
+                units::format_units(threshold, 18)
+            ))),
+            _ => Ok(Verdict::Allowed),
+        }
+    }
+}
+
+static GUARD: OnceLock<ValueGuard> = OnceLock::new();
+
+/// Apply `guard` to every operation of the rest of the process
+pub fn set_guard(guard: ValueGuard) {
+    let _ = GUARD.set(guard);
+}
+
+/// Check sending `value` wei to `target` against the guard, asking on the terminal when it needs confirmation
+pub fn check_value(target: Address, value: U256) -> Result<()> {
+    let default = ValueGuard::default();
+    match GUARD.get().unwrap_or(&default).check(target, value)? {
+        Verdict::Allowed => Ok(()),
+        Verdict::Confirm(question) => {
+            use std::io::IsTerminal;
+            if !std::io::stdin().is_terminal() {
+                return Err(anyhow::anyhow!("{}\nNo terminal to confirm on, pass --yes to send it anyway", question));
+            }
+            if Prompter::terminal().confirm(&question, false)? {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("Transfer of {} ETH to {} not confirmed", units::format_units(value, 18), target))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth(amount: &str) -> U256 {
+        units::parse_value(&format!("{}eth", amount)).unwrap()
+    }
+
+    #[test]
+    fn test_limits_and_confirmation() {
+        let target = Address::repeat_byte(0x11);
+        let guard = ValueGuard { max_value: Some(eth("1")), confirm_above: Some(eth("0.1")), ..Default::default() };
+
+        assert_eq!(guard.check(target, eth("0.1")).unwrap(), Verdict::Allowed);
+        assert!(matches!(guard.check(target, eth("0.5")).unwrap(), Verdict::Confirm(_)));
+        assert!(guard.check(target, eth("1.5")).unwrap_err().to_string().contains("above the 1 ETH limit"));
+
+        let confirmed = ValueGuard { confirmed: true, ..guard };
+        assert_eq!(confirmed.check(target, eth("0.5")).unwrap(), Verdict::Allowed);
+    }
+
+    #[test]
+    fn test_denylist() {
+        let denied = Address::repeat_byte(0x22);
+        let guard = ValueGuard { denylist: vec![denied], ..Default::default() };
+
+        assert!(guard.check(denied, U256::from(1)).is_err());
+        for burn in BURN_ADDRESSES {
+            assert!(ValueGuard::default().check(burn, eth("0.01")).is_err());
+        }
+        // Calls without value are not transfers
+        assert_eq!(guard.check(denied, U256::ZERO).unwrap(), Verdict::Allowed);
+    }
+}
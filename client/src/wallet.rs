@@ -4,6 +4,9 @@
 use alloy::primitives::Address;
 use alloy::signers::{k256::ecdsa::SigningKey, local::LocalSigner};
 use anyhow::Result;
+use std::io::BufRead;
+
+use crate::hexutil;
 
 // Re-export aa-sdk-rs signer types
 pub use aa_sdk_rs::signer::SmartAccountSigner;
@@ -23,21 +26,9 @@ impl Wallet {
         Ok(Self { signer })
     }
 
-    /// Create a wallet from a hex string private key using alloy hex parsing
+    /// Create a wallet from a hex string private key
     pub fn from_hex(private_key_hex: &str) -> Result<Self> {
-        // Use alloy's hex parsing instead of custom implementation
-        let private_key_hex = private_key_hex.strip_prefix("0x").unwrap_or(private_key_hex);
-        let bytes = hex::decode(private_key_hex)
-            .map_err(|e| anyhow::anyhow!("Invalid hex string: {}", e))?;
-        
-        if bytes.len() != 32 {
-            return Err(anyhow::anyhow!("Private key must be 32 bytes, got {}", bytes.len()));
-        }
-        
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&bytes);
-        
-        Self::new(private_key)
+        Self::new(hexutil::decode_fixed::<32>("private key", private_key_hex)?)
     }
 
     /// Get the wallet's address using aa-sdk-rs SmartAccountSigner trait
@@ -60,6 +51,59 @@ impl Wallet {
     }
 }
 
+/// Environment variable consulted for the owner private key
+pub const PRIVATE_KEY_ENV: &str = "AA_PRIVATE_KEY";
+
+/// Resolve the owner private key from, in order of precedence:
+/// `--private-key`, `--private-key-stdin`, `AA_PRIVATE_KEY`, then the profile keystore.
+/// The keystore is only read when no other source provided a key.
+pub fn resolve_private_key(
+    flag: Option<&str>,
+    from_stdin: bool,
+    keystore: impl FnOnce() -> Result<Option<String>>,
+) -> Result<String> {
+    resolve_private_key_from(
+        flag,
+        from_stdin.then(|| std::io::stdin().lock()),
+        std::env::var(PRIVATE_KEY_ENV).ok(),
+        keystore,
+    )
+}
+
+fn resolve_private_key_from<R: BufRead>(
+    flag: Option<&str>,
+    stdin: Option<R>,
+    env: Option<String>,
+    keystore: impl FnOnce() -> Result<Option<String>>,
+) -> Result<String> {
+    if flag.is_some() && stdin.is_some() {
+        return Err(anyhow::anyhow!("--private-key and --private-key-stdin cannot be used together"));
+    }
+    
+    if let Some(key) = flag {
+        return Ok(key.to_string());
+    }
+    
+    if let Some(mut reader) = stdin {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let key = line.trim();
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("No private key received on stdin"));
+        }
+        return Ok(key.to_string());
+    }
+    
+    if let Some(key) = env.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()) {
+        return Ok(key);
+    }
+    
+    keystore()?.ok_or_else(|| anyhow::anyhow!(
+        "No private key: use --private-key-stdin or --wallet, set {} or configure a profile keystore",
+        PRIVATE_KEY_ENV
+    ))
+}
+
 /// Wallet factory for creating wallets
 pub struct WalletFactory;
 
@@ -166,6 +210,35 @@ mod tests {
         assert_eq!(private_key.len(), 66);
     }
 
+    #[test]
+    fn test_resolve_private_key_precedence() {
+        let no_keystore = || Ok(None);
+        let keystore = || Ok(Some("keystore".to_string()));
+        let no_stdin: Option<&[u8]> = None;
+        
+        let key = resolve_private_key_from(Some("flag"), no_stdin, Some("env".to_string()), keystore).unwrap();
+        assert_eq!(key, "flag");
+        
+        let key = resolve_private_key_from(None, Some(&b"0xstdin\n"[..]), Some("env".to_string()), keystore).unwrap();
+        assert_eq!(key, "0xstdin");
+        
+        let key = resolve_private_key_from(None, no_stdin, Some(" env ".to_string()), keystore).unwrap();
+        assert_eq!(key, "env");
+        
+        let key = resolve_private_key_from(None, no_stdin, None, keystore).unwrap();
+        assert_eq!(key, "keystore");
+        
+        assert!(resolve_private_key_from(None, no_stdin, None, no_keystore).is_err());
+    }
+
+    #[test]
+    fn test_resolve_private_key_rejects_conflicts_and_empty_stdin() {
+        let keystore = || Ok(None);
+        
+        assert!(resolve_private_key_from(Some("flag"), Some(&b"stdin"[..]), None, keystore).is_err());
+        assert!(resolve_private_key_from(None, Some(&b"\n"[..]), None, keystore).is_err());
+    }
+
     #[test]
     fn test_signer_access() {
         let wallet = Wallet::new([1u8; 32]).unwrap();
@@ -0,0 +1,138 @@
+// Named owner keys kept encrypted under ~/.aa-client/wallets, selected with `--wallet <name>`
+// Each key is a standard Ethereum V3 keystore (scrypt, AES-128-CTR) that geth or foundry can also open;
+// an index next to them maps names to addresses so `wallet list` needs no password
+
+use alloy::primitives::Address;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::data_dir;
+use crate::hexutil;
+
+const WALLETS_DIR: &str = "wallets";
+const INDEX_FILE: &str = "index.json";
+
+/// Environment variable holding the wallet password, for non-interactive use
+pub const PASSWORD_ENV: &str = "AA_WALLET_PASSWORD";
+
+/// Directory of encrypted keystores plus their name index
+pub struct WalletStore {
+    dir: PathBuf,
+}
+
+impl WalletStore {
+    /// Store at `~/.aa-client/wallets`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(data_dir()?.join(WALLETS_DIR)))
+    }
+
+    /// Store in an explicit directory
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Named wallets and their addresses, sorted by name
+    pub fn list(&self) -> Result<BTreeMap<String, Address>> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Corrupt wallet index {}: {}", path.display(), e))
+    }
+
+    /// Encrypt `private_key` with `password` and store it as `name`
+    pub fn import(&self, name: &str, private_key: &str, password: &str) -> Result<Address> {
+        validate_name(name)?;
+        let mut wallets = self.list()?;
+        if wallets.contains_key(name) {
+            return Err(anyhow::anyhow!("A wallet named '{}' already exists", name));
+        }
+
+        let key = hexutil::decode_fixed::<32>("private key", private_key)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let (signer, _) = PrivateKeySigner::encrypt_keystore(
+            &self.dir,
+            &mut rand::thread_rng(),
+            key,
+            password,
+            Some(&keystore_file(name)),
+        )?;
+
+        wallets.insert(name.to_string(), signer.address());
+        std::fs::write(self.dir.join(INDEX_FILE), serde_json::to_string_pretty(&wallets)?)?;
+        Ok(signer.address())
+    }
+
+    /// Decrypt the wallet `name`, returning its private key in hex
+    pub fn unlock(&self, name: &str, password: &str) -> Result<String> {
+        let path = self.keystore_path(name)?;
+        let signer = PrivateKeySigner::decrypt_keystore(&path, password)
+            .map_err(|e| anyhow::anyhow!("Cannot unlock wallet '{}' (wrong password?): {}", name, e))?;
+        Ok(format!("0x{}", hex::encode(signer.credential().to_bytes())))
+    }
+
+    fn keystore_path(&self, name: &str) -> Result<PathBuf> {
+        if !self.list()?.contains_key(name) {
+            return Err(anyhow::anyhow!("No wallet named '{}', see `wallet list`", name));
+        }
+        Ok(self.dir.join(keystore_file(name)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Password from `AA_WALLET_PASSWORD`, otherwise asked on the terminal (twice when `confirm` is set)
+pub fn password(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password(prompt)?;
+    if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+        return Err(anyhow::anyhow!("Passwords do not match"));
+    }
+    Ok(password)
+}
+
+fn keystore_file(name: &str) -> String {
+    format!("{}.json", name)
+}
+
+/// Names become file names, so only letters, digits, `-` and `_` are allowed
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow::anyhow!("Wallet name '{}' may only contain letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Wallet;
+
+    #[test]
+    fn test_import_list_unlock() {
+        let dir = std::env::temp_dir().join(format!("aa-client-wallets-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = WalletStore::open(&dir);
+        let key = format!("0x{}", "11".repeat(32));
+
+        let address = store.import("deployer", &key, "secret").unwrap();
+        assert_eq!(address, Wallet::from_hex(&key).unwrap().address());
+        assert_eq!(store.list().unwrap().get("deployer"), Some(&address));
+
+        assert_eq!(store.unlock("deployer", "secret").unwrap(), key);
+        assert!(store.unlock("deployer", "wrong").is_err());
+        assert!(store.unlock("operator", "secret").is_err());
+        assert!(store.import("deployer", &key, "secret").is_err());
+        assert!(store.import("../escape", &key, "secret").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
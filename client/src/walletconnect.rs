@@ -0,0 +1,679 @@
+// WalletConnect v2 wallet side behind `connect`: pairs with a dApp through the relay, approves its session proposal
+// for the smart account and answers its requests until either side disconnects.
+// Everything on the relay is end-to-end encrypted (type 0 envelopes, ChaCha20-Poly1305); the pairing key comes from
+// the wc: URI and the session key from an X25519 exchange with the dApp's proposal key
+
+use alloy::primitives::{Address, Bytes, U256};
+use anyhow::Result;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use ed25519_dalek::{Signer, SigningKey};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::str::FromStr;
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::hexutil;
+
+pub const DEFAULT_RELAY_URL: &str = "wss://relay.walletconnect.com";
+
+/// Methods the smart account answers; anything else is rejected with code 4200
+pub const METHODS: [&str; 4] = ["eth_sendTransaction", "personal_sign", "eth_signTypedData", "eth_signTypedData_v4"];
+pub const EVENTS: [&str; 2] = ["chainChanged", "accountsChanged"];
+
+/// Sessions last a week unless extended, as in the reference wallet SDK
+const SESSION_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+const RELAY_AUTH_TTL_SECS: u64 = 24 * 60 * 60;
+
+// Relay tags of the sign protocol, one per request and response type
+const TAG_PAIRING_PING: u64 = 1002;
+const TAG_SESSION_PROPOSE_RESPONSE: u64 = 1101;
+const TAG_SESSION_SETTLE: u64 = 1102;
+const TAG_SESSION_REQUEST_RESPONSE: u64 = 1109;
+const TAG_SESSION_DELETE: u64 = 1112;
+const TAG_SESSION_PING: u64 = 1114;
+const TAG_SESSION_PROPOSE_REJECT: u64 = 1120;
+
+/// Seconds the relay keeps a published message for a peer that is offline
+const MESSAGE_TTL_SECS: u64 = 300;
+const SESSION_REQUEST_TTL_SECS: u64 = 86_400;
+
+/// Contents of a `wc:` pairing URI shown by the dApp as a QR code or link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairingUri {
+    pub topic: String,
+    pub sym_key: [u8; 32],
+    /// Unix time after which the dApp no longer listens on the pairing topic
+    pub expiry: Option<u64>,
+}
+
+impl FromStr for PairingUri {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rest = s.trim().strip_prefix("wc:").ok_or_else(|| anyhow::anyhow!("Not a WalletConnect URI, expected wc:..."))?;
+        let (path, query) = rest.split_once('?').ok_or_else(|| anyhow::anyhow!("WalletConnect URI has no parameters"))?;
+        let (topic, version) = path.split_once('@').ok_or_else(|| anyhow::anyhow!("WalletConnect URI has no version"))?;
+        if version != "2" {
+            return Err(anyhow::anyhow!("WalletConnect v{} URIs are not supported, only v2", version));
+        }
+
+        let (mut sym_key, mut expiry, mut protocol) = (None, None, None);
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "symKey" => sym_key = Some(hexutil::decode_fixed::<32>("symKey", &value)?),
+                "expiryTimestamp" => expiry = Some(value.parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid expiryTimestamp '{}'", value))?),
+                "relay-protocol" => protocol = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        if let Some(protocol) = protocol.filter(|p| p != "irn") {
+            return Err(anyhow::anyhow!("Unsupported relay protocol '{}', only irn", protocol));
+        }
+
+        Ok(PairingUri {
+            topic: topic.to_string(),
+            sym_key: sym_key.ok_or_else(|| anyhow::anyhow!("WalletConnect URI has no symKey"))?,
+            expiry,
+        })
+    }
+}
+
+/// Relay topic of a symmetric key: the hex sha256 of the key
+pub fn topic_of(key: &[u8; 32]) -> String {
+    hex::encode(Sha256::digest(key))
+}
+
+/// Session key both sides derive from their X25519 exchange: HKDF-SHA256 without salt or info
+pub fn session_key(secret: &StaticSecret, peer_public: &[u8; 32]) -> [u8; 32] {
+    let shared = secret.diffie_hellman(&PublicKey::from(*peer_public));
+    let mut key = [0u8; 32];
+    hkdf::Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&[], &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 length");
+    key
+}
+
+/// Type 0 envelope: base64 of 0x00 || 12-byte nonce || ciphertext and tag
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<String> {
+    let mut iv = [0u8; 12];
+    getrandom::getrandom(&mut iv).map_err(|e| anyhow::anyhow!("No randomness for the envelope nonce: {}", e))?;
+    let sealed = ChaCha20Poly1305::new(key.into())
+        .encrypt(Nonce::from_slice(&iv), plaintext)
+        .expect("encryption into a Vec cannot fail");
+
+    let mut envelope = Vec::with_capacity(1 + iv.len() + sealed.len());
+    envelope.push(0);
+    envelope.extend_from_slice(&iv);
+    envelope.extend_from_slice(&sealed);
+    Ok(STANDARD.encode(envelope))
+}
+
+/// Open a type 0 envelope sealed with `key`
+pub fn open(key: &[u8; 32], message: &str) -> Result<Vec<u8>> {
+    let envelope = STANDARD.decode(message.trim()).map_err(|e| anyhow::anyhow!("Relay message is not base64: {}", e))?;
+    match envelope.first() {
+        Some(0) if envelope.len() > 13 => {}
+        Some(0) => return Err(anyhow::anyhow!("Relay message is too short for an envelope")),
+        Some(other) => return Err(anyhow::anyhow!("Unsupported envelope type {}", other)),
+        None => return Err(anyhow::anyhow!("Empty relay message")),
+    }
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(&envelope[1..13]), &envelope[13..])
+        .map_err(|_| anyhow::anyhow!("Relay message does not decrypt with the topic key"))
+}
+
+/// did:key of an Ed25519 public key, the issuer the relay knows a client by
+pub fn did_key(public_key: &[u8; 32]) -> String {
+    let mut multicodec = vec![0xed, 0x01];
+    multicodec.extend_from_slice(public_key);
+    format!("did:key:z{}", bs58::encode(multicodec).into_string())
+}
+
+/// EdDSA JWT authenticating the connection to `relay_url`, signed by a key made for this connection only
+pub fn relay_auth(key: &SigningKey, relay_url: &str, now: u64) -> Result<String> {
+    let mut subject = [0u8; 32];
+    getrandom::getrandom(&mut subject).map_err(|e| anyhow::anyhow!("No randomness for the relay auth subject: {}", e))?;
+    let header = json!({ "alg": "EdDSA", "typ": "JWT" });
+    let claims = json!({
+        "iss": did_key(key.verifying_key().as_bytes()),
+        "sub": hex::encode(subject),
+        "aud": relay_url,
+        "iat": now,
+        "exp": now + RELAY_AUTH_TTL_SECS,
+    });
+    let signing_input = format!("{}.{}", URL_SAFE_NO_PAD.encode(header.to_string()), URL_SAFE_NO_PAD.encode(claims.to_string()));
+    let signature = key.sign(signing_input.as_bytes());
+    Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes())))
+}
+
+/// JSON-RPC error returned to the dApp instead of a result
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} (code {code})")]
+pub struct Rejection {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Rejection {
+    pub fn user_rejected() -> Self {
+        Rejection { code: 5000, message: "User rejected".to_string() }
+    }
+
+    pub fn unsupported_chain(chain: &str) -> Self {
+        Rejection { code: 5100, message: format!("Unsupported chain {}", chain) }
+    }
+
+    pub fn unsupported_method(method: &str) -> Self {
+        Rejection { code: 4200, message: format!("Unsupported method {}", method) }
+    }
+
+    pub fn invalid_params(reason: impl std::fmt::Display) -> Self {
+        Rejection { code: -32602, message: format!("Invalid params: {}", reason) }
+    }
+}
+
+/// Any other failure while handling a request, e.g. a submission the bundler refused
+impl From<anyhow::Error> for Rejection {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<Rejection>() {
+            Ok(rejection) => rejection,
+            Err(error) => Rejection { code: -32000, message: error.to_string() },
+        }
+    }
+}
+
+/// A dApp request the smart account can answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DappRequest {
+    SendTransaction { from: Address, to: Address, value: U256, data: Bytes },
+    /// EIP-191 signature over raw message bytes
+    PersonalSign { address: Address, message: Vec<u8> },
+    /// EIP-712 signature over typed data JSON
+    SignTypedData { address: Address, json: String },
+}
+
+impl DappRequest {
+    /// Address the dApp expects to act, which must be the smart account
+    pub fn address(&self) -> Address {
+        match self {
+            DappRequest::SendTransaction { from, .. } => *from,
+            DappRequest::PersonalSign { address, .. } | DappRequest::SignTypedData { address, .. } => *address,
+        }
+    }
+}
+
+fn param_address(value: Option<&Value>, what: &str) -> Result<Address, Rejection> {
+    value
+        .and_then(Value::as_str)
+        .ok_or_else(|| Rejection::invalid_params(format!("missing {}", what)))
+        .and_then(|s| Address::from_str(s).map_err(|_| Rejection::invalid_params(format!("{} is not an address", what))))
+}
+
+/// Decode the params of a session request; unknown methods and malformed params become rejections
+pub fn parse_request(method: &str, params: &Value) -> Result<DappRequest, Rejection> {
+    match method {
+        "eth_sendTransaction" => {
+            let tx = params.get(0).ok_or_else(|| Rejection::invalid_params("missing transaction"))?;
+            if tx.get("to").is_none_or(Value::is_null) {
+                return Err(Rejection::invalid_params("contract creation is not supported, the transaction has no 'to'"));
+            }
+            let value = match tx.get("value").and_then(Value::as_str) {
+                Some(value) => U256::from_str(value).map_err(|_| Rejection::invalid_params("value is not a number"))?,
+                None => U256::ZERO,
+            };
+            let data = match tx.get("data").or_else(|| tx.get("input")).and_then(Value::as_str) {
+                Some(data) => hexutil::decode_bytes("data", data).map_err(Rejection::invalid_params)?,
+                None => Bytes::new(),
+            };
+            Ok(DappRequest::SendTransaction {
+                from: param_address(tx.get("from"), "from")?,
+                to: param_address(tx.get("to"), "to")?,
+                value,
+                data,
+            })
+        }
+        "personal_sign" => {
+            let message = params.get(0).and_then(Value::as_str).ok_or_else(|| Rejection::invalid_params("missing message"))?;
+            // Hex is the encoding dApps are told to use, other text is signed as it is
+            let message = match message.starts_with("0x") {
+                true => hexutil::decode("message", message).map_err(Rejection::invalid_params)?,
+                false => message.as_bytes().to_vec(),
+            };
+            Ok(DappRequest::PersonalSign { address: param_address(params.get(1), "address")?, message })
+        }
+        "eth_signTypedData" | "eth_signTypedData_v4" => {
+            let json = match params.get(1) {
+                Some(Value::String(json)) => json.clone(),
+                Some(object @ Value::Object(_)) => object.to_string(),
+                _ => return Err(Rejection::invalid_params("missing typed data")),
+            };
+            Ok(DappRequest::SignTypedData { address: param_address(params.get(0), "address")?, json })
+        }
+        other => Err(Rejection::unsupported_method(other)),
+    }
+}
+
+/// Chain id of a CAIP-2 `eip155:<id>` reference
+pub fn parse_chain(chain: &str) -> Option<u64> {
+    chain.strip_prefix("eip155:")?.parse().ok()
+}
+
+/// Who is asking: the dApp's own description of itself, not verified by anyone
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub name: String,
+    pub url: String,
+}
+
+impl Metadata {
+    fn from_json(value: &Value) -> Self {
+        let field = |name: &str| value.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+        Metadata { name: field("name"), url: field("url") }
+    }
+}
+
+/// A dApp's session proposal waiting for approval
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    id: u64,
+    proposer_key: [u8; 32],
+    required_namespaces: Value,
+    pub dapp: Metadata,
+    /// Chains the dApp requires, empty when it only lists optional ones
+    pub required_chains: Vec<String>,
+    /// Methods the dApp requires that the account does not answer
+    pub unsupported_methods: Vec<String>,
+}
+
+impl Proposal {
+    fn from_params(id: u64, params: &Value) -> Result<Self> {
+        let proposer = params.get("proposer").ok_or_else(|| anyhow::anyhow!("Session proposal has no proposer"))?;
+        let proposer_key = proposer.get("publicKey").and_then(Value::as_str).unwrap_or_default();
+        let required_namespaces = params.get("requiredNamespaces").cloned().unwrap_or_else(|| json!({}));
+        let eip155 = required_namespaces.get("eip155");
+        let strings = |field: &str| -> Vec<String> {
+            eip155
+                .and_then(|namespace| namespace.get(field))
+                .and_then(Value::as_array)
+                .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+
+        Ok(Proposal {
+            id,
+            proposer_key: hexutil::decode_fixed::<32>("proposer public key", proposer_key)?,
+            dapp: Metadata::from_json(proposer.get("metadata").unwrap_or(&Value::Null)),
+            required_chains: strings("chains"),
+            unsupported_methods: strings("methods").into_iter().filter(|m| !METHODS.contains(&m.as_str())).collect(),
+            required_namespaces,
+        })
+    }
+
+    /// Whether the account can serve every chain the dApp requires
+    pub fn supports_chain(&self, chain_id: u64) -> bool {
+        self.required_chains.iter().all(|chain| parse_chain(chain) == Some(chain_id))
+    }
+}
+
+/// Socket to the relay server: subscriptions, publishing and the messages arriving on subscribed topics
+pub struct Relay {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+    /// Messages that arrived while waiting for the response to a call, as (topic, message)
+    inbox: VecDeque<(String, String)>,
+}
+
+impl Relay {
+    pub async fn connect(relay_url: &str, project_id: &str) -> Result<Self> {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).map_err(|e| anyhow::anyhow!("No randomness for the relay key: {}", e))?;
+        let auth = relay_auth(&SigningKey::from_bytes(&seed), relay_url, crate::clock::now_secs())?;
+        let url = format!("{}/?auth={}&projectId={}", relay_url.trim_end_matches('/'), auth, project_id);
+
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .map_err(|e| anyhow::anyhow!("Could not connect to the WalletConnect relay {}: {}", relay_url, e))?;
+        Ok(Relay { socket, next_id: rpc_id()?, inbox: VecDeque::new() })
+    }
+
+    async fn send(&mut self, payload: Value) -> Result<()> {
+        self.socket.send(Message::Text(payload.to_string())).await?;
+        Ok(())
+    }
+
+    /// Next JSON-RPC payload from the relay, acknowledging subscription deliveries into the inbox on the way
+    async fn receive(&mut self) -> Result<Value> {
+        loop {
+            let text = match self.socket.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | None => return Err(anyhow::anyhow!("The WalletConnect relay closed the connection")),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(anyhow::anyhow!("WalletConnect relay connection failed: {}", e)),
+            };
+            let payload: Value = serde_json::from_str(&text)?;
+            if payload.get("method").and_then(Value::as_str) != Some("irn_subscription") {
+                return Ok(payload);
+            }
+
+            let data = &payload["params"]["data"];
+            if let (Some(topic), Some(message)) = (data["topic"].as_str(), data["message"].as_str()) {
+                self.inbox.push_back((topic.to_string(), message.to_string()));
+            }
+            self.send(json!({ "id": payload["id"], "jsonrpc": "2.0", "result": true })).await?;
+        }
+    }
+
+    async fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "id": id, "jsonrpc": "2.0", "method": method, "params": params })).await?;
+        loop {
+            let payload = self.receive().await?;
+            if payload.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = payload.get("error") {
+                return Err(anyhow::anyhow!("WalletConnect relay refused {}: {}", method, error));
+            }
+            return Ok(payload.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    pub async fn subscribe(&mut self, topic: &str) -> Result<()> {
+        self.call("irn_subscribe", json!({ "topic": topic })).await.map(|_| ())
+    }
+
+    async fn publish(&mut self, topic: &str, key: &[u8; 32], payload: &Value, tag: u64, ttl: u64) -> Result<()> {
+        let message = seal(key, payload.to_string().as_bytes())?;
+        let params = json!({ "topic": topic, "message": message, "ttl": ttl, "tag": tag, "prompt": false });
+        self.call("irn_publish", params).await.map(|_| ())
+    }
+
+    /// Next message on a subscribed topic, as (topic, encrypted message)
+    async fn next_message(&mut self) -> Result<(String, String)> {
+        loop {
+            if let Some(message) = self.inbox.pop_front() {
+                return Ok(message);
+            }
+            // Anything that is not a delivery is a stray response, nothing waits for it any more
+            self.receive().await?;
+        }
+    }
+}
+
+/// Sign protocol ids are the time in milliseconds followed by three random digits
+fn rpc_id() -> Result<u64> {
+    let mut random = [0u8; 2];
+    getrandom::getrandom(&mut random).map_err(|e| anyhow::anyhow!("No randomness for the request id: {}", e))?;
+    Ok(crate::clock::now_secs() * 1_000_000 + u64::from(u16::from_be_bytes(random) % 1000))
+}
+
+/// A sign protocol message decrypted from a topic
+#[derive(Debug, Clone)]
+enum Incoming {
+    Request { id: u64, method: String, params: Value },
+    /// Answer to one of our own requests (settle, delete), nothing to do
+    Response,
+}
+
+fn decode_incoming(key: &[u8; 32], message: &str) -> Result<Incoming> {
+    let payload: Value = serde_json::from_slice(&open(key, message)?)?;
+    let id = payload.get("id").and_then(Value::as_u64).ok_or_else(|| anyhow::anyhow!("Sign protocol message has no id"))?;
+    match payload.get("method").and_then(Value::as_str) {
+        Some(method) => Ok(Incoming::Request { id, method: method.to_string(), params: payload.get("params").cloned().unwrap_or(Value::Null) }),
+        None => Ok(Incoming::Response),
+    }
+}
+
+/// A pairing opened from a `wc:` URI, waiting for the dApp's session proposal
+pub struct Pairing {
+    relay: Relay,
+    uri: PairingUri,
+}
+
+impl Pairing {
+    pub async fn open(relay_url: &str, project_id: &str, uri: PairingUri) -> Result<Self> {
+        if uri.expiry.is_some_and(|expiry| expiry <= crate::clock::now_secs()) {
+            return Err(anyhow::anyhow!("The pairing URI has expired, ask the dApp for a new one"));
+        }
+        let mut relay = Relay::connect(relay_url, project_id).await?;
+        relay.subscribe(&uri.topic).await?;
+        Ok(Pairing { relay, uri })
+    }
+
+    /// Wait for the session proposal, answering pairing pings meanwhile
+    pub async fn proposal(&mut self) -> Result<Proposal> {
+        loop {
+            let (topic, message) = self.relay.next_message().await?;
+            if topic != self.uri.topic {
+                continue;
+            }
+            let Incoming::Request { id, method, params } = decode_incoming(&self.uri.sym_key, &message)? else { continue };
+            match method.as_str() {
+                "wc_sessionPropose" => return Proposal::from_params(id, &params),
+                "wc_pairingPing" => self.respond(id, Ok(json!(true)), TAG_PAIRING_PING + 1).await?,
+                _ => {}
+            }
+        }
+    }
+
+    async fn respond(&mut self, id: u64, result: Result<Value, Rejection>, tag: u64) -> Result<()> {
+        let payload = response(id, result);
+        self.relay.publish(&self.uri.topic, &self.uri.sym_key, &payload, tag, MESSAGE_TTL_SECS).await
+    }
+
+    pub async fn reject(mut self, proposal: &Proposal, rejection: Rejection) -> Result<()> {
+        self.respond(proposal.id, Err(rejection), TAG_SESSION_PROPOSE_REJECT).await
+    }
+
+    /// Approve the proposal for `account` on `chain_id` and settle the session
+    pub async fn approve(mut self, proposal: Proposal, account: Address, chain_id: u64) -> Result<Session> {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret).map_err(|e| anyhow::anyhow!("No randomness for the session key: {}", e))?;
+        let secret = StaticSecret::from(secret);
+        let public_key = hex::encode(PublicKey::from(&secret).as_bytes());
+        let key = session_key(&secret, &proposal.proposer_key);
+        let topic = topic_of(&key);
+
+        // Listen on the session topic before the dApp learns our key and starts publishing there
+        self.relay.subscribe(&topic).await?;
+        let result = json!({ "relay": { "protocol": "irn" }, "responderPublicKey": public_key });
+        self.respond(proposal.id, Ok(result), TAG_SESSION_PROPOSE_RESPONSE).await?;
+
+        let mut session = Session { relay: self.relay, topic, key, chain_id, dapp: proposal.dapp.clone() };
+        let settle = json!({
+            "relay": { "protocol": "irn" },
+            "namespaces": {
+                "eip155": {
+                    "accounts": [format!("eip155:{}:{}", chain_id, account)],
+                    "methods": METHODS,
+                    "events": EVENTS,
+                }
+            },
+            "requiredNamespaces": proposal.required_namespaces,
+            "pairingTopic": self.uri.topic,
+            "controller": {
+                "publicKey": public_key,
+                "metadata": {
+                    "name": "aa-client",
+                    "description": "ERC-4337 smart account",
+                    "url": "https://github.com/ricardo-perello/account-abstraction",
+                    "icons": [],
+                },
+            },
+            "expiry": crate::clock::now_secs() + SESSION_EXPIRY_SECS,
+        });
+        session.request("wc_sessionSettle", settle, TAG_SESSION_SETTLE).await?;
+        Ok(session)
+    }
+}
+
+/// An approved session: dApp requests arrive on its topic and are answered there
+pub struct Session {
+    relay: Relay,
+    topic: String,
+    key: [u8; 32],
+    chain_id: u64,
+    pub dapp: Metadata,
+}
+
+enum Event {
+    Message(Result<(String, String)>),
+    Interrupted,
+}
+
+impl Session {
+    async fn request(&mut self, method: &str, params: Value, tag: u64) -> Result<()> {
+        let payload = json!({ "id": rpc_id()?, "jsonrpc": "2.0", "method": method, "params": params });
+        self.relay.publish(&self.topic, &self.key, &payload, tag, MESSAGE_TTL_SECS).await
+    }
+
+    async fn respond(&mut self, id: u64, result: Result<Value, Rejection>, tag: u64, ttl: u64) -> Result<()> {
+        let payload = response(id, result);
+        self.relay.publish(&self.topic, &self.key, &payload, tag, ttl).await
+    }
+
+    /// Answer dApp requests with `handler` until the dApp disconnects or Ctrl-C ends the session
+    pub async fn serve<F, Fut>(mut self, mut handler: F) -> Result<()>
+    where
+        F: FnMut(DappRequest) -> Fut,
+        Fut: std::future::Future<Output = Result<Value, Rejection>>,
+    {
+        loop {
+            let event = tokio::select! {
+                message = self.relay.next_message() => Event::Message(message),
+                _ = tokio::signal::ctrl_c() => Event::Interrupted,
+            };
+            let (topic, message) = match event {
+                Event::Message(message) => message?,
+                Event::Interrupted => {
+                    self.request("wc_sessionDelete", json!({ "code": 6000, "message": "User disconnected" }), TAG_SESSION_DELETE).await?;
+                    return Ok(());
+                }
+            };
+            if topic != self.topic {
+                continue;
+            }
+            let Incoming::Request { id, method, params } = decode_incoming(&self.key, &message)? else { continue };
+
+            match method.as_str() {
+                "wc_sessionRequest" => {
+                    let chain = params["chainId"].as_str().unwrap_or_default();
+                    let result = match parse_chain(chain) == Some(self.chain_id) {
+                        true => match parse_request(params["request"]["method"].as_str().unwrap_or_default(), &params["request"]["params"]) {
+                            Ok(request) => handler(request).await,
+                            Err(rejection) => Err(rejection),
+                        },
+                        false => Err(Rejection::unsupported_chain(chain)),
+                    };
+                    self.respond(id, result, TAG_SESSION_REQUEST_RESPONSE, SESSION_REQUEST_TTL_SECS).await?;
+                }
+                "wc_sessionPing" => self.respond(id, Ok(json!(true)), TAG_SESSION_PING + 1, MESSAGE_TTL_SECS).await?,
+                "wc_sessionDelete" => {
+                    self.respond(id, Ok(json!(true)), TAG_SESSION_DELETE + 1, MESSAGE_TTL_SECS).await?;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn response(id: u64, result: Result<Value, Rejection>) -> Value {
+    match result {
+        Ok(result) => json!({ "id": id, "jsonrpc": "2.0", "result": result }),
+        Err(rejection) => json!({ "id": id, "jsonrpc": "2.0", "error": { "code": rejection.code, "message": rejection.message } }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    #[test]
+    fn test_parse_pairing_uri() {
+        let key = "587d5484ce2a2a6ee3ba1962fdd7e8588e06200c46823bd18fbd67def96ad303";
+        let uri: PairingUri = format!("wc:7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9@2?relay-protocol=irn&symKey={}&expiryTimestamp=1705000000", key)
+            .parse()
+            .unwrap();
+        assert_eq!(uri.topic, "7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9");
+        assert_eq!(hex::encode(uri.sym_key), key);
+        assert_eq!(uri.expiry, Some(1_705_000_000));
+
+        assert!(format!("wc:abc@1?symKey={}", key).parse::<PairingUri>().is_err());
+        assert!("wc:abc@2?relay-protocol=irn".parse::<PairingUri>().is_err());
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let key = [7u8; 32];
+        let sealed = seal(&key, b"{\"id\":1}").unwrap();
+        assert_eq!(STANDARD.decode(&sealed).unwrap()[0], 0);
+        assert_eq!(open(&key, &sealed).unwrap(), b"{\"id\":1}");
+        // Someone holding another topic's key cannot read or forge the message
+        assert!(open(&[8u8; 32], &sealed).is_err());
+    }
+
+    #[test]
+    fn test_session_key_is_shared() {
+        let (wallet, dapp) = (StaticSecret::from([1u8; 32]), StaticSecret::from([2u8; 32]));
+        let wallet_key = session_key(&wallet, PublicKey::from(&dapp).as_bytes());
+        assert_eq!(wallet_key, session_key(&dapp, PublicKey::from(&wallet).as_bytes()));
+        assert_eq!(topic_of(&wallet_key).len(), 64);
+    }
+
+    #[test]
+    fn test_relay_auth_verifies() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        assert!(did_key(key.verifying_key().as_bytes()).starts_with("did:key:z6Mk"));
+
+        let jwt = relay_auth(&key, DEFAULT_RELAY_URL, 1_700_000_000).unwrap();
+        let (signing_input, signature) = jwt.rsplit_once('.').unwrap();
+        let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        VerifyingKey::from_bytes(key.verifying_key().as_bytes()).unwrap().verify(signing_input.as_bytes(), &signature).unwrap();
+
+        let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(signing_input.split('.').nth(1).unwrap()).unwrap()).unwrap();
+        assert_eq!(claims["aud"], DEFAULT_RELAY_URL);
+        assert_eq!(claims["exp"], 1_700_000_000 + RELAY_AUTH_TTL_SECS);
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let from = "0x1111111111111111111111111111111111111111";
+        let tx = json!([{ "from": from, "to": "0x2222222222222222222222222222222222222222", "value": "0x10", "data": "0xabcd" }]);
+        let DappRequest::SendTransaction { from: sender, value, data, .. } = parse_request("eth_sendTransaction", &tx).unwrap() else { panic!() };
+        assert_eq!((sender, value, data.to_vec()), (Address::from_str(from).unwrap(), U256::from(16), vec![0xab, 0xcd]));
+        assert!(parse_request("eth_sendTransaction", &json!([{ "from": from, "data": "0x60" }])).is_err());
+
+        let signed = parse_request("personal_sign", &json!(["0x68656c6c6f", from])).unwrap();
+        assert_eq!(signed, DappRequest::PersonalSign { address: Address::from_str(from).unwrap(), message: b"hello".to_vec() });
+
+        let typed = parse_request("eth_signTypedData_v4", &json!([from, { "primaryType": "Mail" }])).unwrap();
+        assert!(matches!(typed, DappRequest::SignTypedData { json, .. } if json.contains("Mail")));
+
+        assert_eq!(parse_request("eth_sign", &json!([])).unwrap_err().code, 4200);
+    }
+
+    #[test]
+    fn test_proposal_chains() {
+        let params = json!({
+            "proposer": { "publicKey": hex::encode([9u8; 32]), "metadata": { "name": "Swap", "url": "https://swap.example" } },
+            "requiredNamespaces": { "eip155": { "chains": ["eip155:8453"], "methods": ["eth_sendTransaction", "wallet_switchEthereumChain"] } },
+        });
+        let proposal = Proposal::from_params(1, &params).unwrap();
+        assert_eq!(proposal.dapp.name, "Swap");
+        assert!(proposal.supports_chain(8453));
+        assert!(!proposal.supports_chain(1));
+        assert_eq!(proposal.unsupported_methods, vec!["wallet_switchEthereumChain".to_string()]);
+    }
+}
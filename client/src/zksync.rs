@@ -0,0 +1,289 @@
+// zkSync Era native account abstraction: every account, EOA or contract, validates its own transactions,
+// so calls are sent as EIP-712 transactions (type 113) straight to the node instead of UserOperations
+// Fees can be paid by a paymaster named in the transaction, with the flow encoded in its paymasterInput
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::rlp::{Encodable, Header};
+use alloy::signers::SignerSync;
+use alloy::sol;
+use alloy::sol_types::{eip712_domain, SolCall, SolStruct};
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::wallet::Wallet;
+
+/// Transaction type of zkSync EIP-712 transactions
+pub const EIP712_TX_TYPE: u8 = 0x71;
+
+/// Gas per pubdata byte the node accepts by default
+pub const DEFAULT_GAS_PER_PUBDATA: u64 = 50_000;
+
+sol!(
+    #[allow(missing_docs)]
+    interface IPaymasterFlow {
+        function general(bytes input) external;
+        function approvalBased(address token, uint256 minAllowance, bytes innerInput) external;
+    }
+);
+
+sol!(
+    /// Struct the transaction hash is signed over, addresses widened to uint256 as in the bootloader
+    #[allow(missing_docs)]
+    struct Transaction {
+        uint256 txType;
+        uint256 from;
+        uint256 to;
+        uint256 gasLimit;
+        uint256 gasPerPubdataByteLimit;
+        uint256 maxFeePerGas;
+        uint256 maxPriorityFeePerGas;
+        uint256 paymaster;
+        uint256 nonce;
+        uint256 value;
+        bytes data;
+        bytes32[] factoryDeps;
+        bytes paymasterInput;
+    }
+);
+
+/// Paymaster paying for a transaction and the flow it expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymasterParams {
+    pub paymaster: Address,
+    pub input: Bytes,
+}
+
+impl PaymasterParams {
+    /// General flow: the paymaster pays without taking anything from the account
+    pub fn general(paymaster: Address, inner_input: Bytes) -> Self {
+        Self { paymaster, input: IPaymasterFlow::generalCall { input: inner_input }.abi_encode().into() }
+    }
+
+    /// Approval-based flow: the account approves `min_allowance` of `token` for the paymaster,
+    /// which takes its fee in that token
+    pub fn approval_based(paymaster: Address, token: Address, min_allowance: U256, inner_input: Bytes) -> Self {
+        let call = IPaymasterFlow::approvalBasedCall { token, minAllowance: min_allowance, innerInput: inner_input };
+        Self { paymaster, input: call.abi_encode().into() }
+    }
+}
+
+/// A call from a native account, without factory dependencies since it deploys no contracts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeTransaction {
+    pub chain_id: u64,
+    pub from: Address,
+    pub to: Address,
+    pub nonce: U256,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: U256,
+    pub gas_per_pubdata: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster: Option<PaymasterParams>,
+}
+
+/// Gas and fees returned by `zks_estimateFee`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Fee {
+    pub gas_limit: U256,
+    pub gas_per_pubdata_limit: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl NativeTransaction {
+    /// Hash the account signs: EIP-712 over `Transaction` in the `zkSync` version 2 domain
+    pub fn signing_hash(&self) -> B256 {
+        let word = |address: Address| U256::from_be_slice(address.as_slice());
+        let transaction = Transaction {
+            txType: U256::from(EIP712_TX_TYPE),
+            from: word(self.from),
+            to: word(self.to),
+            gasLimit: self.gas_limit,
+            gasPerPubdataByteLimit: self.gas_per_pubdata,
+            maxFeePerGas: self.max_fee_per_gas,
+            maxPriorityFeePerGas: self.max_priority_fee_per_gas,
+            paymaster: self.paymaster.as_ref().map(|p| word(p.paymaster)).unwrap_or_default(),
+            nonce: self.nonce,
+            value: self.value,
+            data: self.data.clone(),
+            factoryDeps: Vec::new(),
+            paymasterInput: self.paymaster.as_ref().map(|p| p.input.clone()).unwrap_or_default(),
+        };
+        transaction.eip712_signing_hash(&eip712_domain! {
+            name: "zkSync",
+            version: "2",
+            chain_id: self.chain_id,
+        })
+    }
+
+    /// Raw transaction signed with the owner key, as the default account and ECDSA-validating accounts expect
+    pub fn sign(&self, wallet: &Wallet) -> Result<Bytes> {
+        let signature = wallet.signer().sign_hash_sync(&self.signing_hash())?;
+        Ok(self.encode(&signature.as_bytes()))
+    }
+
+    /// Type byte followed by the RLP fields, `signature` travelling as the custom signature
+    /// so that contract accounts receive it unchanged
+    pub fn encode(&self, signature: &[u8]) -> Bytes {
+        let mut fields = Vec::new();
+        self.nonce.encode(&mut fields);
+        self.max_priority_fee_per_gas.encode(&mut fields);
+        self.max_fee_per_gas.encode(&mut fields);
+        self.gas_limit.encode(&mut fields);
+        self.to.encode(&mut fields);
+        self.value.encode(&mut fields);
+        self.data.encode(&mut fields);
+        // Placeholder v, r, s: the signature is carried by the custom signature field
+        self.chain_id.encode(&mut fields);
+        Bytes::new().encode(&mut fields);
+        Bytes::new().encode(&mut fields);
+        self.chain_id.encode(&mut fields);
+        self.from.encode(&mut fields);
+        self.gas_per_pubdata.encode(&mut fields);
+        encode_list(&[], &mut fields);
+        Bytes::copy_from_slice(signature).encode(&mut fields);
+        let mut paymaster = Vec::new();
+        if let Some(params) = &self.paymaster {
+            params.paymaster.encode(&mut paymaster);
+            params.input.encode(&mut paymaster);
+        }
+        encode_list(&paymaster, &mut fields);
+
+        let mut raw = vec![EIP712_TX_TYPE];
+        encode_list(&fields, &mut raw);
+        raw.into()
+    }
+
+    /// Request for `zks_estimateFee`, which needs the paymaster to price its validation
+    pub fn fee_request(&self) -> serde_json::Value {
+        let mut meta = serde_json::json!({ "gasPerPubdata": self.gas_per_pubdata });
+        if let Some(params) = &self.paymaster {
+            meta["paymasterParams"] = serde_json::json!({ "paymaster": params.paymaster, "paymasterInput": params.input.to_vec() });
+        }
+        serde_json::json!({
+            "from": self.from,
+            "to": self.to,
+            "value": self.value,
+            "data": self.data,
+            "eip712Meta": meta,
+        })
+    }
+
+    pub fn apply_fee(&mut self, fee: &Fee) {
+        self.gas_limit = fee.gas_limit;
+        self.gas_per_pubdata = fee.gas_per_pubdata_limit;
+        self.max_fee_per_gas = fee.max_fee_per_gas;
+        self.max_priority_fee_per_gas = fee.max_priority_fee_per_gas;
+    }
+}
+
+fn encode_list(payload: &[u8], out: &mut Vec<u8>) {
+    Header { list: true, payload_length: payload.len() }.encode(out);
+    out.extend_from_slice(payload);
+}
+
+pub async fn estimate_fee<P: Provider>(provider: &P, transaction: &NativeTransaction) -> Result<Fee> {
+    Ok(provider.raw_request("zks_estimateFee".into(), (transaction.fee_request(),)).await?)
+}
+
+/// Send a raw transaction, returning its hash
+pub async fn send<P: Provider>(provider: &P, raw: &Bytes) -> Result<B256> {
+    Ok(provider.raw_request("eth_sendRawTransaction".into(), (raw,)).await?)
+}
+
+/// Whether the transaction succeeded, None while it has no receipt
+/// Read as plain JSON since zkSync receipts carry a transaction type the Ethereum receipt types reject
+pub async fn receipt_status<P: Provider>(provider: &P, hash: B256) -> Result<Option<bool>> {
+    let receipt: Option<serde_json::Value> = provider.raw_request("eth_getTransactionReceipt".into(), (hash,)).await?;
+    Ok(receipt.map(|receipt| receipt["status"].as_str() == Some("0x1")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::b256;
+    use alloy::rlp::Decodable;
+
+    fn transaction() -> NativeTransaction {
+        NativeTransaction {
+            chain_id: 300,
+            from: Address::from([0x11; 20]),
+            to: Address::from([0x22; 20]),
+            nonce: U256::from(3),
+            value: U256::ZERO,
+            data: Bytes::from(vec![0xde, 0xad]),
+            gas_limit: U256::from(500_000),
+            gas_per_pubdata: U256::from(DEFAULT_GAS_PER_PUBDATA),
+            max_fee_per_gas: U256::from(25_000_000),
+            max_priority_fee_per_gas: U256::ZERO,
+            paymaster: None,
+        }
+    }
+
+    #[test]
+    fn test_signing_hash_uses_zksync_type() {
+        let transaction = transaction();
+        let typed = Transaction {
+            txType: U256::from(EIP712_TX_TYPE),
+            from: U256::ZERO,
+            to: U256::ZERO,
+            gasLimit: U256::ZERO,
+            gasPerPubdataByteLimit: U256::ZERO,
+            maxFeePerGas: U256::ZERO,
+            maxPriorityFeePerGas: U256::ZERO,
+            paymaster: U256::ZERO,
+            nonce: U256::ZERO,
+            value: U256::ZERO,
+            data: Bytes::new(),
+            factoryDeps: Vec::new(),
+            paymasterInput: Bytes::new(),
+        };
+        // EIP712_TRANSACTION_TYPE_HASH in the bootloader's TransactionHelper
+        assert_eq!(typed.eip712_type_hash(), b256!("848e1bfa1ac4e3576b728bda6721b215c70a7799a5b4866282a71bab954baac8"));
+
+        let mut sponsored = transaction.clone();
+        sponsored.paymaster = Some(PaymasterParams::general(Address::from([0x33; 20]), Bytes::new()));
+        assert_ne!(transaction.signing_hash(), sponsored.signing_hash());
+    }
+
+    #[test]
+    fn test_encode() {
+        let wallet = Wallet::from_hex("0x0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        let mut transaction = transaction();
+        transaction.paymaster = Some(PaymasterParams::approval_based(Address::from([0x33; 20]), Address::from([0x44; 20]), U256::from(1), Bytes::new()));
+        let raw = transaction.sign(&wallet).unwrap();
+        assert_eq!(raw[0], EIP712_TX_TYPE);
+
+        let mut payload = &raw[1..];
+        let header = Header::decode(&mut payload).unwrap();
+        assert!(header.list);
+        assert_eq!(header.payload_length, payload.len());
+        assert_eq!(U256::decode(&mut payload).unwrap(), U256::from(3));
+
+        let input = &transaction.paymaster.as_ref().unwrap().input;
+        assert_eq!(input[..4], IPaymasterFlow::approvalBasedCall::SELECTOR);
+        assert!(raw.ends_with(input));
+    }
+
+    #[test]
+    fn test_fee_request() {
+        let mut transaction = transaction();
+        transaction.paymaster = Some(PaymasterParams::general(Address::from([0x33; 20]), Bytes::new()));
+        let request = transaction.fee_request();
+        assert_eq!(request["eip712Meta"]["gasPerPubdata"], "0xc350");
+        assert_eq!(request["eip712Meta"]["paymasterParams"]["paymasterInput"][0], 0x8c);
+
+        let fee: Fee = serde_json::from_value(serde_json::json!({
+            "gas_limit": "0x7a120",
+            "gas_per_pubdata_limit": "0xc350",
+            "max_fee_per_gas": "0x17d7840",
+            "max_priority_fee_per_gas": "0x0",
+        }))
+        .unwrap();
+        transaction.apply_fee(&fee);
+        assert_eq!(transaction.gas_limit, U256::from(500_000));
+    }
+}
@@ -14,15 +14,58 @@ contract AAAccount is BaseAccount {
     // Owner management using EnumerableSet for gas efficiency
     mapping(address => bool) public owners;
     EnumerableSet.AddressSet private _ownerSet;
+    // Owner signatures a UserOperation needs; zero behaves as one, so accounts without a threshold keep working
+    uint256 public ownerThreshold;
+
+    // Scoped keys that can sign UserOperations without an owner key
+    struct SessionKey {
+        address target;
+        bytes4 selector;
+        uint48 validUntil;
+        bool active;
+        // Most wei a single call may send, zero allows none
+        uint128 maxValue;
+        // Operations the key may still sign, every operation it validates uses one
+        uint32 opsRemaining;
+    }
+    // Keys registered under an earlier epoch no longer validate; a recovery starts a new one
+    uint256 public sessionKeyEpoch;
+    mapping(uint256 => mapping(address => SessionKey)) private _sessionKeys;
+
+    // Social recovery: guardians can replace the owner set after a timelock
+    struct Recovery {
+        address[] newOwners;
+        uint64 executeAfter;
+        uint256 approvals;
+    }
+    mapping(address => bool) public guardians;
+    EnumerableSet.AddressSet private _guardianSet;
+    uint256 public guardianThreshold;
+    uint64 public recoveryDelay;
+    Recovery private _pendingRecovery;
+    // Bumped whenever a recovery completes or is cancelled, so stale approvals never carry over
+    uint256 public recoveryRound;
+    mapping(uint256 => mapping(address => bool)) private _recoveryApprovals;
     
 
     
     // Events
     event OwnerAdded(address indexed owner);
     event OwnerRemoved(address indexed owner);
+    event ThresholdChanged(uint256 threshold);
     event TransactionExecuted(address indexed target, uint256 value, bytes data);
     event BatchTransactionExecuted(address[] targets, uint256[] values, bytes[] datas);
     event AccountInitialized(address indexed owner);
+    event SessionKeyRegistered(address indexed key, address indexed target, bytes4 selector, uint48 validUntil, uint128 maxValue, uint32 maxOps);
+    event SessionKeyRevoked(address indexed key);
+    event SessionKeysInvalidated(uint256 epoch);
+    event GuardianAdded(address indexed guardian);
+    event GuardianRemoved(address indexed guardian);
+    event RecoveryConfigured(uint256 threshold, uint64 delay);
+    event RecoveryInitiated(address indexed guardian, address[] newOwners, uint64 executeAfter);
+    event RecoveryApproved(address indexed guardian, uint256 approvals);
+    event RecoveryExecuted(address[] newOwners);
+    event RecoveryCancelled();
 
     IEntryPoint private _entryPoint;
     bool private _initialized;
@@ -53,14 +96,73 @@ contract AAAccount is BaseAccount {
 
     /**
      * Validate the signature of a user operation
+     * A single 65-byte signature may come from an owner or a session key; several owner
+     * signatures are concatenated in ascending signer order, at least ownerThreshold of them
      */
     function _validateSignature(
         PackedUserOperation calldata userOp,
         bytes32 userOpHash
-    ) internal view virtual override returns (uint256 validationData) {
-        // For now, always return success
-        // In a real implementation, you would validate the signature
-        return 0;
+    ) internal virtual override returns (uint256 validationData) {
+        bytes32 hash = MessageHashUtils.toEthSignedMessageHash(userOpHash);
+        bytes calldata signature = userOp.signature;
+
+        if (signature.length == 65) {
+            (address signer, ECDSA.RecoverError error,) = ECDSA.tryRecover(hash, signature);
+            if (error != ECDSA.RecoverError.NoError) {
+                return SIG_VALIDATION_FAILED;
+            }
+            if (owners[signer]) {
+                return _requiredSignatures() == 1 ? SIG_VALIDATION_SUCCESS : SIG_VALIDATION_FAILED;
+            }
+            return _validateSessionKey(signer, userOp.callData);
+        }
+
+        return _checkOwnerSignatures(hash, signature) ? SIG_VALIDATION_SUCCESS : SIG_VALIDATION_FAILED;
+    }
+
+    /**
+     * @dev Concatenated owner signatures over `hash`, distinct owners in ascending order and enough of them
+     */
+    function _checkOwnerSignatures(bytes32 hash, bytes calldata signature) internal view returns (bool) {
+        if (signature.length == 0 || signature.length % 65 != 0 || signature.length / 65 < _requiredSignatures()) {
+            return false;
+        }
+        address lastSigner = address(0);
+        for (uint256 i = 0; i < signature.length; i += 65) {
+            (address signer, ECDSA.RecoverError error,) = ECDSA.tryRecover(hash, signature[i:i + 65]);
+            // Ascending order rules out the same owner signing twice
+            if (error != ECDSA.RecoverError.NoError || !owners[signer] || signer <= lastSigner) {
+                return false;
+            }
+            lastSigner = signer;
+        }
+        return true;
+    }
+
+    function _requiredSignatures() internal view returns (uint256) {
+        return ownerThreshold > 1 ? ownerThreshold : 1;
+    }
+
+    /**
+     * @dev Session keys may only call execute(target, value, data) on their allowed target, with their
+     * allowed selector (any selector when zero) and at most maxValue, until they expire or run out of operations
+     */
+    function _validateSessionKey(address key, bytes calldata callData) internal returns (uint256) {
+        SessionKey storage session = _sessionKeys[sessionKeyEpoch][key];
+        if (!session.active || session.opsRemaining == 0 || callData.length < 4 || bytes4(callData[:4]) != this.execute.selector) {
+            return SIG_VALIDATION_FAILED;
+        }
+
+        (address target, uint256 value, bytes memory data) = abi.decode(callData[4:], (address, uint256, bytes));
+        if (target != session.target || value > session.maxValue) {
+            return SIG_VALIDATION_FAILED;
+        }
+        if (session.selector != bytes4(0) && (data.length < 4 || bytes4(data) != session.selector)) {
+            return SIG_VALIDATION_FAILED;
+        }
+        // Validation may write the account's own storage, so the budget is spent here
+        session.opsRemaining -= 1;
+        return _packValidationData(false, session.validUntil, 0);
     }
 
     /**
@@ -87,6 +189,7 @@ contract AAAccount is BaseAccount {
      */
     function _requireForExecute() internal view {
         require(owners[msg.sender], "AAAccount: caller is not owner");
+        require(_requiredSignatures() == 1, "AAAccount: threshold requires a UserOperation");
     }
 
     /**
@@ -145,8 +248,18 @@ contract AAAccount is BaseAccount {
     }
 
     // Owner management functions
+    /**
+     * @dev Owner management is allowed from an owner directly, or from the account itself
+     * so it can be driven by a UserOperation calling execute(address(this), 0, addOwner(...)).
+     * Above a threshold of one, a single owner key must not act alone and only the account itself may
+     */
+    function _requireOwnerOrSelf() internal view {
+        require(owners[msg.sender] || msg.sender == address(this), "AAAccount: caller is not an owner");
+        require(msg.sender == address(this) || _requiredSignatures() == 1, "AAAccount: threshold requires a UserOperation");
+    }
+
     function addOwner(address newOwner) external {
-        require(owners[msg.sender], "AAAccount: caller is not an owner");
+        _requireOwnerOrSelf();
         require(newOwner != address(0), "AAAccount: invalid owner address");
         require(!owners[newOwner], "AAAccount: owner already exists");
         
@@ -157,17 +270,220 @@ contract AAAccount is BaseAccount {
     }
     
     function removeOwner(address ownerToRemove) external {
-        require(owners[msg.sender], "AAAccount: caller is not an owner");
+        _requireOwnerOrSelf();
         require(ownerToRemove != msg.sender, "AAAccount: cannot remove self");
         require(owners[ownerToRemove], "AAAccount: owner does not exist");
         require(_ownerSet.length() > 1, "AAAccount: cannot remove last owner");
+        require(_ownerSet.length() > ownerThreshold, "AAAccount: would drop below threshold");
         
         owners[ownerToRemove] = false;
         _ownerSet.remove(ownerToRemove);
         
         emit OwnerRemoved(ownerToRemove);
     }
+
+    /**
+     * @dev Number of owner signatures every UserOperation needs, k of the n owners
+     */
+    function setThreshold(uint256 threshold) external {
+        _requireOwnerOrSelf();
+        require(threshold > 0 && threshold <= _ownerSet.length(), "AAAccount: invalid threshold");
+
+        ownerThreshold = threshold;
+        emit ThresholdChanged(threshold);
+    }
     
+    /**
+     * @dev Allow `key` to sign up to `maxOps` UserOperations calling `selector` on `target` until `validUntil`
+     * @param selector Allowed function selector on the target, zero allows any
+     * @param maxValue Most wei one call may send, zero allows none
+     */
+    function registerSessionKey(
+        address key,
+        address target,
+        bytes4 selector,
+        uint48 validUntil,
+        uint128 maxValue,
+        uint32 maxOps
+    ) external {
+        _requireOwnerOrSelf();
+        require(key != address(0), "AAAccount: invalid session key");
+        require(!owners[key], "AAAccount: session key is an owner");
+        // Calls into the account itself could add owners or widen session keys
+        require(target != address(0) && target != address(this), "AAAccount: invalid session target");
+        require(validUntil > block.timestamp, "AAAccount: session already expired");
+        require(maxOps > 0, "AAAccount: session has no operations");
+
+        _sessionKeys[sessionKeyEpoch][key] = SessionKey({
+            target: target,
+            selector: selector,
+            validUntil: validUntil,
+            active: true,
+            maxValue: maxValue,
+            opsRemaining: maxOps
+        });
+        emit SessionKeyRegistered(key, target, selector, validUntil, maxValue, maxOps);
+    }
+
+    function revokeSessionKey(address key) external {
+        _requireOwnerOrSelf();
+        require(_sessionKeys[sessionKeyEpoch][key].active, "AAAccount: unknown session key");
+
+        delete _sessionKeys[sessionKeyEpoch][key];
+        emit SessionKeyRevoked(key);
+    }
+
+    /**
+     * @dev Scope of `key` in the current epoch, all zero for keys that were never registered or were invalidated
+     */
+    function sessionKeys(address key) external view returns (
+        address target,
+        bytes4 selector,
+        uint48 validUntil,
+        bool active,
+        uint128 maxValue,
+        uint32 opsRemaining
+    ) {
+        SessionKey storage session = _sessionKeys[sessionKeyEpoch][key];
+        return (session.target, session.selector, session.validUntil, session.active, session.maxValue, session.opsRemaining);
+    }
+
+    // Guardian management and social recovery
+    function addGuardian(address guardian) external {
+        _requireOwnerOrSelf();
+        require(guardian != address(0) && guardian != address(this), "AAAccount: invalid guardian");
+        require(!owners[guardian], "AAAccount: guardian is an owner");
+        require(!guardians[guardian], "AAAccount: guardian already exists");
+
+        guardians[guardian] = true;
+        _guardianSet.add(guardian);
+        emit GuardianAdded(guardian);
+        _cancelPendingRecovery();
+    }
+
+    function removeGuardian(address guardian) external {
+        _requireOwnerOrSelf();
+        require(guardians[guardian], "AAAccount: guardian does not exist");
+        require(_guardianSet.length() > guardianThreshold, "AAAccount: would drop below threshold");
+
+        guardians[guardian] = false;
+        _guardianSet.remove(guardian);
+        emit GuardianRemoved(guardian);
+        // Its approval would otherwise still count towards the pending recovery
+        _cancelPendingRecovery();
+    }
+
+    /**
+     * @dev Number of guardian approvals and the delay before a recovery can be executed
+     */
+    function setRecoveryConfig(uint256 threshold, uint64 delay) external {
+        _requireOwnerOrSelf();
+        require(threshold > 0 && threshold <= _guardianSet.length(), "AAAccount: invalid threshold");
+
+        guardianThreshold = threshold;
+        recoveryDelay = delay;
+        emit RecoveryConfigured(threshold, delay);
+    }
+
+    /**
+     * @dev Propose a new owner set; the initiating guardian counts as the first approval
+     */
+    function initiateRecovery(address[] calldata newOwners) external {
+        require(guardians[msg.sender], "AAAccount: caller is not a guardian");
+        require(guardianThreshold > 0, "AAAccount: recovery not configured");
+        require(_pendingRecovery.executeAfter == 0, "AAAccount: recovery already pending");
+        require(newOwners.length > 0, "AAAccount: no owners");
+
+        uint64 executeAfter = uint64(block.timestamp) + recoveryDelay;
+        _pendingRecovery.newOwners = newOwners;
+        _pendingRecovery.executeAfter = executeAfter;
+        _pendingRecovery.approvals = 1;
+        _recoveryApprovals[recoveryRound][msg.sender] = true;
+
+        emit RecoveryInitiated(msg.sender, newOwners, executeAfter);
+    }
+
+    function approveRecovery() external {
+        require(guardians[msg.sender], "AAAccount: caller is not a guardian");
+        require(_pendingRecovery.executeAfter != 0, "AAAccount: no pending recovery");
+        require(!_recoveryApprovals[recoveryRound][msg.sender], "AAAccount: already approved");
+
+        _recoveryApprovals[recoveryRound][msg.sender] = true;
+        _pendingRecovery.approvals += 1;
+        emit RecoveryApproved(msg.sender, _pendingRecovery.approvals);
+    }
+
+    /**
+     * @dev Replace every owner once enough guardians approved and the timelock passed; callable by anyone
+     */
+    function executeRecovery() external {
+        Recovery memory recovery = _pendingRecovery;
+        require(recovery.executeAfter != 0, "AAAccount: no pending recovery");
+        require(recovery.approvals >= guardianThreshold, "AAAccount: not enough approvals");
+        require(block.timestamp >= recovery.executeAfter, "AAAccount: recovery timelocked");
+
+        address[] memory oldOwners = _ownerSet.values();
+        for (uint256 i = 0; i < oldOwners.length; i++) {
+            owners[oldOwners[i]] = false;
+            _ownerSet.remove(oldOwners[i]);
+            emit OwnerRemoved(oldOwners[i]);
+        }
+        for (uint256 i = 0; i < recovery.newOwners.length; i++) {
+            address newOwner = recovery.newOwners[i];
+            require(newOwner != address(0), "AAAccount: invalid owner address");
+            require(!owners[newOwner] && !guardians[newOwner], "AAAccount: invalid new owner");
+            owners[newOwner] = true;
+            _ownerSet.add(newOwner);
+            emit OwnerAdded(newOwner);
+        }
+        // A smaller recovered owner set must still be able to sign
+        if (ownerThreshold > recovery.newOwners.length) {
+            ownerThreshold = recovery.newOwners.length;
+            emit ThresholdChanged(ownerThreshold);
+        }
+
+        // Session keys were registered by the owners being replaced
+        sessionKeyEpoch += 1;
+        emit SessionKeysInvalidated(sessionKeyEpoch);
+
+        _clearRecovery();
+        emit RecoveryExecuted(recovery.newOwners);
+    }
+
+    /**
+     * @dev Owners that still hold their keys can stop a recovery during the timelock
+     */
+    function cancelRecovery() external {
+        _requireOwnerOrSelf();
+        require(_pendingRecovery.executeAfter != 0, "AAAccount: no pending recovery");
+
+        _clearRecovery();
+        emit RecoveryCancelled();
+    }
+
+    function _clearRecovery() private {
+        delete _pendingRecovery;
+        recoveryRound += 1;
+    }
+
+    /**
+     * @dev Drop a pending recovery when the guardian set changes, approvals only count for the set they were given under
+     */
+    function _cancelPendingRecovery() private {
+        if (_pendingRecovery.executeAfter != 0) {
+            _clearRecovery();
+            emit RecoveryCancelled();
+        }
+    }
+
+    function getGuardians() external view returns (address[] memory) {
+        return _guardianSet.values();
+    }
+
+    function getPendingRecovery() external view returns (address[] memory newOwners, uint64 executeAfter, uint256 approvals) {
+        return (_pendingRecovery.newOwners, _pendingRecovery.executeAfter, _pendingRecovery.approvals);
+    }
+
     function getOwners() external view returns (address[] memory) {
         return _ownerSet.values();
     }
@@ -193,11 +509,8 @@ contract AAAccount is BaseAccount {
      * @return magicValue EIP-1271 magic value if valid, 0 otherwise
      */
     function isValidSignature(bytes32 hash, bytes calldata signature) external view returns (bytes4 magicValue) {
-        // Use ECDSA.tryRecover for safe signature recovery
-        (address signer, ECDSA.RecoverError error,) = ECDSA.tryRecover(hash, signature);
-        
-        // Check for recovery errors or if signer is not an owner
-        if (error != ECDSA.RecoverError.NoError || !owners[signer]) {
+        // Same owner signatures and threshold as a UserOperation, recovered with ECDSA.tryRecover
+        if (!_checkOwnerSignatures(hash, signature)) {
             return 0x00000000; // Invalid signature
         }
         
@@ -233,6 +546,7 @@ contract AAAccount is BaseAccount {
      */
     function executeDirectly(address target, uint256 value, bytes calldata data) external {
         require(owners[msg.sender], "AAAccount: caller is not an owner");
+        require(_requiredSignatures() == 1, "AAAccount: threshold requires a UserOperation");
         require(target != address(0), "AAAccount: invalid target");
         
         (bool success, ) = target.call{value: value}(data);
@@ -242,7 +556,10 @@ contract AAAccount is BaseAccount {
     }
 
     function executeBatch(address[] calldata targets, uint256[] calldata values, bytes[] calldata datas) external {
-        _requireForExecute();
+        // Like execute, the EntryPoint has already validated the UserOp
+        if (msg.sender != address(entryPoint())) {
+            _requireForExecute();
+        }
         require(
             targets.length == values.length && targets.length == datas.length,
             "AAAccount: array length mismatch"
@@ -266,6 +583,7 @@ contract AAAccount is BaseAccount {
      */
     function executeBatchDirectly(address[] calldata targets, uint256[] calldata values, bytes[] calldata datas) external {
         require(owners[msg.sender], "AAAccount: caller is not an owner");
+        require(_requiredSignatures() == 1, "AAAccount: threshold requires a UserOperation");
         require(
             targets.length == values.length && targets.length == datas.length,
             "AAAccount: array length mismatch"
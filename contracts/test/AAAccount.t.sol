@@ -113,6 +113,22 @@ contract AAAccountTest is Test {
         assertTrue(newAccount.owners(owner1));
     }
     
+    function testAddAndRemoveOwnerViaSelfCall() public {
+        uint256 salt = uint256(keccak256("test-salt-self-call"));
+        AAAccount newAccount = factory.createAccountDirect(owner1, salt);
+        
+        // A UserOperation reaches owner management through execute(address(this), ...)
+        vm.prank(address(entryPoint));
+        newAccount.execute(address(newAccount), 0, abi.encodeCall(AAAccount.addOwner, (owner2)));
+        assertTrue(newAccount.owners(owner2));
+        assertEq(newAccount.ownerCount(), 2);
+        
+        vm.prank(address(entryPoint));
+        newAccount.execute(address(newAccount), 0, abi.encodeCall(AAAccount.removeOwner, (owner2)));
+        assertFalse(newAccount.owners(owner2));
+        assertEq(newAccount.ownerCount(), 1);
+    }
+    
     function testNonOwnerCannotAddOwner() public {
         // Create account with owner1
         uint256 salt = uint256(keccak256("test-salt-5"));
@@ -347,6 +363,34 @@ contract AAAccountTest is Test {
         assertEq(owner2.balance, initialOwner2Balance + 0.1 ether, "Owner2 should receive exactly 0.1 ETH");
     }
 
+    function testExecuteBatchFromEntryPoint() public {
+        uint256 salt = uint256(keccak256("test-batch-entrypoint"));
+        AAAccount testAccount = factory.createAccountDirect(owner1, salt);
+        vm.deal(address(testAccount), 1 ether);
+        
+        address[] memory targets = new address[](2);
+        targets[0] = owner2;
+        targets[1] = owner3;
+        uint256[] memory values = new uint256[](2);
+        values[0] = 0.1 ether;
+        values[1] = 0.2 ether;
+        bytes[] memory datas = new bytes[](2);
+        
+        uint256 owner2Before = owner2.balance;
+        uint256 owner3Before = owner3.balance;
+        
+        vm.prank(address(entryPoint));
+        testAccount.executeBatch(targets, values, datas);
+        
+        assertEq(owner2.balance, owner2Before + 0.1 ether);
+        assertEq(owner3.balance, owner3Before + 0.2 ether);
+        
+        // Anyone else still needs to be an owner
+        vm.prank(address(0xBEEF));
+        vm.expectRevert("AAAccount: caller is not owner");
+        testAccount.executeBatch(targets, values, datas);
+    }
+
     function testIsValidSignatureEIP1271() public {
         // Create account
         uint256 salt = uint256(keccak256("test-eip1271"));
@@ -437,4 +481,399 @@ contract AAAccountTest is Test {
         // Should succeed
         assertEq(validationData, 0);
     }
+
+    function _transferOp(AAAccount testAccount, address target, bytes memory data) internal view returns (PackedUserOperation memory userOp, bytes32 userOpHash) {
+        userOp = PackedUserOperation({
+            sender: address(testAccount),
+            nonce: 0,
+            initCode: hex"",
+            callData: abi.encodeWithSelector(AAAccount.execute.selector, target, 0, data),
+            accountGasLimits: bytes32(abi.encodePacked(uint128(100000), uint128(100000))),
+            preVerificationGas: 21000,
+            gasFees: bytes32(abi.encodePacked(uint128(1000000000), uint128(1000000000))),
+            paymasterAndData: hex"",
+            signature: hex""
+        });
+        userOpHash = entryPoint.getUserOpHash(userOp);
+    }
+
+    function _sign(uint256 key, bytes32 userOpHash) internal pure returns (bytes memory) {
+        (uint8 v, bytes32 r, bytes32 s) = vm.sign(key, MessageHashUtils.toEthSignedMessageHash(userOpHash));
+        return abi.encodePacked(r, s, v);
+    }
+
+    function testMultiOwnerCombinedSignature() public {
+        address[] memory owners = new address[](2);
+        owners[0] = owner1;
+        owners[1] = owner2;
+        AAAccount testAccount = factory.createAccountWithOwners(owners, uint256(keccak256("test-combined-sig")));
+        
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(testAccount, owner3, hex"");
+        bytes memory sig1 = _sign(owner1Key, userOpHash);
+        bytes memory sig2 = _sign(owner2Key, userOpHash);
+        
+        // owner2 (0x3C44...) sorts before owner1 (0x7099...)
+        userOp.signature = abi.encodePacked(sig2, sig1);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 0);
+        
+        userOp.signature = abi.encodePacked(sig1, sig2);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        
+        userOp.signature = abi.encodePacked(sig2, sig2);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+    }
+
+    function testOwnerThreshold() public {
+        address[] memory owners = new address[](3);
+        owners[0] = owner1;
+        owners[1] = owner2;
+        owners[2] = owner3;
+        AAAccount testAccount = factory.createAccountWithOwners(owners, uint256(keccak256("test-threshold")));
+        assertEq(testAccount.ownerThreshold(), 0);
+        
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: invalid threshold");
+        testAccount.setThreshold(4);
+        vm.prank(owner1);
+        testAccount.setThreshold(2);
+        assertEq(testAccount.ownerThreshold(), 2);
+        
+        // One owner signature is no longer enough, two are
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(testAccount, owner3, hex"");
+        bytes memory sig1 = _sign(owner1Key, userOpHash);
+        bytes memory sig2 = _sign(owner2Key, userOpHash);
+        userOp.signature = sig1;
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        userOp.signature = abi.encodePacked(sig2, sig1);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 0);
+        
+        // A single owner cannot act directly any more, only the account itself
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: threshold requires a UserOperation");
+        testAccount.setThreshold(1);
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: threshold requires a UserOperation");
+        testAccount.executeDirectly(owner3, 0, hex"");
+        
+        vm.startPrank(address(testAccount));
+        testAccount.removeOwner(owner3);
+        vm.expectRevert("AAAccount: would drop below threshold");
+        testAccount.removeOwner(owner2);
+        testAccount.setThreshold(1);
+        vm.stopPrank();
+        assertEq(testAccount.ownerThreshold(), 1);
+    }
+
+    function testSessionKeyScopedValidation() public {
+        AAAccount testAccount = factory.createAccountDirect(owner1, uint256(keccak256("test-session")));
+        uint256 sessionKey = 0xA11CE;
+        address sessionAddr = vm.addr(sessionKey);
+        address target = address(0xCAFE);
+        bytes4 selector = bytes4(keccak256("ping()"));
+        uint48 validUntil = uint48(block.timestamp + 1 hours);
+        
+        vm.prank(owner1);
+        testAccount.registerSessionKey(sessionAddr, target, selector, validUntil, 0, 10);
+        
+        // Allowed target and selector: valid until expiry
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(testAccount, target, abi.encodeWithSelector(selector));
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), uint256(validUntil) << 160);
+        
+        // Other target
+        (userOp, userOpHash) = _transferOp(testAccount, address(0xBEEF), abi.encodeWithSelector(selector));
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        
+        // Other selector
+        (userOp, userOpHash) = _transferOp(testAccount, target, abi.encodeWithSelector(bytes4(keccak256("pong()"))));
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        
+        // Revoked
+        vm.prank(owner1);
+        testAccount.revokeSessionKey(sessionAddr);
+        (userOp, userOpHash) = _transferOp(testAccount, target, abi.encodeWithSelector(selector));
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+    }
+
+    function testSessionKeyCannotTargetAccount() public {
+        AAAccount testAccount = factory.createAccountDirect(owner1, uint256(keccak256("test-session-self")));
+        
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: invalid session target");
+        testAccount.registerSessionKey(address(0xA11CE), address(testAccount), bytes4(0), uint48(block.timestamp + 1 hours), 0, 1);
+        
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: session has no operations");
+        testAccount.registerSessionKey(address(0xA11CE), address(0xCAFE), bytes4(0), uint48(block.timestamp + 1 hours), 0, 0);
+        
+        vm.prank(owner2);
+        vm.expectRevert("AAAccount: caller is not an owner");
+        testAccount.registerSessionKey(address(0xA11CE), address(0xCAFE), bytes4(0), uint48(block.timestamp + 1 hours), 0, 1);
+    }
+
+    function testSessionKeyValueAndOperationBudget() public {
+        AAAccount testAccount = factory.createAccountDirect(owner1, uint256(keccak256("test-session-budget")));
+        uint256 sessionKey = 0xA11CE;
+        address sessionAddr = vm.addr(sessionKey);
+        address target = address(0xCAFE);
+        uint48 validUntil = uint48(block.timestamp + 1 hours);
+        
+        vm.prank(owner1);
+        testAccount.registerSessionKey(sessionAddr, target, bytes4(0), validUntil, 0.1 ether, 2);
+        
+        // More than maxValue is refused and does not use up the budget
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(testAccount, target, hex"");
+        userOp.callData = abi.encodeWithSelector(AAAccount.execute.selector, target, 0.1 ether + 1, hex"");
+        userOpHash = entryPoint.getUserOpHash(userOp);
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        
+        // Up to maxValue, for maxOps operations
+        userOp.callData = abi.encodeWithSelector(AAAccount.execute.selector, target, 0.1 ether, hex"");
+        userOpHash = entryPoint.getUserOpHash(userOp);
+        userOp.signature = _sign(sessionKey, userOpHash);
+        for (uint256 i = 0; i < 2; i++) {
+            vm.prank(address(entryPoint));
+            assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), uint256(validUntil) << 160);
+        }
+        (,,,,, uint32 opsRemaining) = testAccount.sessionKeys(sessionAddr);
+        assertEq(opsRemaining, 0);
+        
+        // Budget spent
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+    }
+
+    /// @dev Keys of three fresh owners, in ascending order of their addresses
+    function _sortedOwnerKeys() internal pure returns (uint256[3] memory keys) {
+        keys = [uint256(0xA1), uint256(0xB2), uint256(0xC3)];
+        for (uint256 i = 0; i < 3; i++) {
+            for (uint256 j = i + 1; j < 3; j++) {
+                if (vm.addr(keys[j]) < vm.addr(keys[i])) {
+                    (keys[i], keys[j]) = (keys[j], keys[i]);
+                }
+            }
+        }
+    }
+
+    function _validate(AAAccount testAccount, PackedUserOperation memory userOp, bytes32 userOpHash, bytes memory signature) internal returns (uint256) {
+        userOp.signature = signature;
+        vm.prank(address(entryPoint));
+        return testAccount.validateUserOp(userOp, userOpHash, 0);
+    }
+
+    function testThresholdSignatureValidation() public {
+        uint256[3] memory keys = _sortedOwnerKeys();
+        
+        // 1-of-1: the owner's signature and nothing else
+        AAAccount single = factory.createAccountDirect(vm.addr(keys[0]), uint256(keccak256("test-1-of-1")));
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(single, owner3, hex"");
+        assertEq(_validate(single, userOp, userOpHash, _sign(keys[0], userOpHash)), 0);
+        assertEq(_validate(single, userOp, userOpHash, _sign(keys[1], userOpHash)), 1);
+        
+        // 2-of-3
+        address[] memory owners = new address[](3);
+        for (uint256 i = 0; i < 3; i++) {
+            owners[i] = vm.addr(keys[i]);
+        }
+        AAAccount multi = factory.createAccountWithOwners(owners, uint256(keccak256("test-2-of-3")));
+        vm.prank(address(multi));
+        multi.setThreshold(2);
+        (userOp, userOpHash) = _transferOp(multi, owner3, hex"");
+        bytes memory sig0 = _sign(keys[0], userOpHash);
+        bytes memory sig1 = _sign(keys[1], userOpHash);
+        bytes memory sig2 = _sign(keys[2], userOpHash);
+        
+        // Any two owners in ascending order, or all three
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, sig1)), 0);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, sig2)), 0);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig1, sig2)), 0);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, sig1, sig2)), 0);
+        
+        // Too few
+        assertEq(_validate(multi, userOp, userOpHash, sig0), 1);
+        assertEq(_validate(multi, userOp, userOpHash, hex""), 1);
+        
+        // Unsorted and duplicate signers
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig1, sig0)), 1);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig2, sig0, sig1)), 1);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig1, sig1)), 1);
+        
+        // A non-owner among the signers
+        bytes memory stranger = _sign(0xD4, userOpHash);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, stranger)), 1);
+        
+        // Malformed: truncated, trailing bytes, garbage and a high-s signature
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, bytes32(0), bytes32(0))), 1);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, sig1, hex"00")), 1);
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, bytes32(uint256(1)), bytes32(uint256(2)), uint8(27))), 1);
+        (uint8 v, bytes32 r, bytes32 s) = vm.sign(keys[1], MessageHashUtils.toEthSignedMessageHash(userOpHash));
+        bytes32 highS = bytes32(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141 - uint256(s));
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, r, highS, v == 27 ? uint8(28) : uint8(27))), 1);
+        
+        // A signature over another operation
+        (, bytes32 otherHash) = _transferOp(multi, owner2, hex"");
+        assertEq(_validate(multi, userOp, userOpHash, abi.encodePacked(sig0, _sign(keys[1], otherHash))), 1);
+    }
+
+    function _accountWithGuardians(bytes memory saltLabel) internal returns (AAAccount testAccount, address guardian1, address guardian2) {
+        testAccount = factory.createAccountDirect(owner1, uint256(keccak256(saltLabel)));
+        guardian1 = address(0x6A1);
+        guardian2 = address(0x6A2);
+        
+        vm.startPrank(owner1);
+        testAccount.addGuardian(guardian1);
+        testAccount.addGuardian(guardian2);
+        testAccount.setRecoveryConfig(2, 1 days);
+        vm.stopPrank();
+    }
+
+    function testSocialRecoveryReplacesOwners() public {
+        (AAAccount testAccount, address guardian1, address guardian2) = _accountWithGuardians("test-recovery");
+        assertEq(testAccount.getGuardians().length, 2);
+        
+        address[] memory newOwners = new address[](1);
+        newOwners[0] = owner3;
+        
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: caller is not a guardian");
+        testAccount.initiateRecovery(newOwners);
+        
+        vm.prank(guardian1);
+        testAccount.initiateRecovery(newOwners);
+        
+        vm.expectRevert("AAAccount: not enough approvals");
+        testAccount.executeRecovery();
+        
+        vm.prank(guardian2);
+        testAccount.approveRecovery();
+        
+        vm.expectRevert("AAAccount: recovery timelocked");
+        testAccount.executeRecovery();
+        
+        vm.warp(block.timestamp + 1 days);
+        testAccount.executeRecovery();
+        
+        assertFalse(testAccount.owners(owner1));
+        assertTrue(testAccount.owners(owner3));
+        assertEq(testAccount.ownerCount(), 1);
+        (address[] memory pending, uint64 executeAfter,) = testAccount.getPendingRecovery();
+        assertEq(pending.length, 0);
+        assertEq(executeAfter, 0);
+    }
+
+    function testOwnerCanCancelRecovery() public {
+        (AAAccount testAccount, address guardian1, address guardian2) = _accountWithGuardians("test-recovery-cancel");
+        
+        address[] memory newOwners = new address[](1);
+        newOwners[0] = owner3;
+        vm.prank(guardian1);
+        testAccount.initiateRecovery(newOwners);
+        
+        vm.prank(owner1);
+        testAccount.cancelRecovery();
+        
+        // Approvals from the cancelled round do not count towards a new one
+        vm.prank(guardian2);
+        testAccount.initiateRecovery(newOwners);
+        vm.prank(guardian2);
+        vm.expectRevert("AAAccount: already approved");
+        testAccount.approveRecovery();
+        vm.prank(guardian1);
+        testAccount.approveRecovery();
+        
+        vm.warp(block.timestamp + 1 days);
+        testAccount.executeRecovery();
+        assertTrue(testAccount.owners(owner3));
+    }
+
+    function testRecoveryInvalidatesSessionKeys() public {
+        (AAAccount testAccount, address guardian1, address guardian2) = _accountWithGuardians("test-recovery-session");
+        uint256 sessionKey = 0xA11CE;
+        address sessionAddr = vm.addr(sessionKey);
+        address target = address(0xCAFE);
+        
+        vm.prank(owner1);
+        testAccount.registerSessionKey(sessionAddr, target, bytes4(0), uint48(block.timestamp + 30 days), 0, 10);
+        
+        address[] memory newOwners = new address[](1);
+        newOwners[0] = owner3;
+        vm.prank(guardian1);
+        testAccount.initiateRecovery(newOwners);
+        vm.prank(guardian2);
+        testAccount.approveRecovery();
+        vm.warp(block.timestamp + 1 days);
+        testAccount.executeRecovery();
+        
+        assertEq(testAccount.sessionKeyEpoch(), 1);
+        (,,, bool active,,) = testAccount.sessionKeys(sessionAddr);
+        assertFalse(active);
+        
+        (PackedUserOperation memory userOp, bytes32 userOpHash) = _transferOp(testAccount, target, hex"");
+        userOp.signature = _sign(sessionKey, userOpHash);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), 1);
+        
+        // The old owner cannot revoke, the new one can register keys again
+        vm.prank(owner1);
+        vm.expectRevert("AAAccount: caller is not an owner");
+        testAccount.revokeSessionKey(sessionAddr);
+        vm.prank(owner3);
+        testAccount.registerSessionKey(sessionAddr, target, bytes4(0), uint48(block.timestamp + 1 hours), 0, 10);
+        vm.prank(address(entryPoint));
+        assertEq(testAccount.validateUserOp(userOp, userOpHash, 0), uint256(uint48(block.timestamp + 1 hours)) << 160);
+    }
+
+    function testGuardianChangeCancelsRecovery() public {
+        (AAAccount testAccount, address guardian1, address guardian2) = _accountWithGuardians("test-recovery-guardians");
+        address guardian3 = address(0x6A3);
+        vm.prank(owner1);
+        testAccount.addGuardian(guardian3);
+        
+        address[] memory newOwners = new address[](1);
+        newOwners[0] = owner3;
+        vm.prank(guardian1);
+        testAccount.initiateRecovery(newOwners);
+        
+        // Removing a guardian that already approved drops the recovery with its approval
+        vm.prank(owner1);
+        testAccount.removeGuardian(guardian1);
+        (, uint64 executeAfter, uint256 approvals) = testAccount.getPendingRecovery();
+        assertEq(executeAfter, 0);
+        assertEq(approvals, 0);
+        vm.prank(guardian2);
+        vm.expectRevert("AAAccount: no pending recovery");
+        testAccount.approveRecovery();
+        
+        // So does adding one
+        vm.prank(guardian2);
+        testAccount.initiateRecovery(newOwners);
+        vm.prank(owner1);
+        testAccount.addGuardian(guardian1);
+        vm.warp(block.timestamp + 1 days);
+        vm.expectRevert("AAAccount: no pending recovery");
+        testAccount.executeRecovery();
+        
+        // A new round needs approvals from the current guardians
+        vm.prank(guardian2);
+        testAccount.initiateRecovery(newOwners);
+        vm.prank(guardian3);
+        testAccount.approveRecovery();
+        vm.warp(block.timestamp + 1 days);
+        testAccount.executeRecovery();
+        assertTrue(testAccount.owners(owner3));
+    }
 }
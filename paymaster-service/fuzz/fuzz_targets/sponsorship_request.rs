@@ -0,0 +1,52 @@
+// Sponsorship request bodies as a client could send them, JSON or CBOR, through decoding and the signing
+// pipeline. The request is authorized so the fuzzer's bytes reach the UserOperation's hex fields: every
+// body must end in a signature or a rejection, never a panic
+#![no_main]
+
+use std::sync::{Arc, OnceLock};
+
+use libfuzzer_sys::fuzz_target;
+use paymaster_service::clock::FixedClock;
+use paymaster_service::key_manager::KeyManager;
+use paymaster_service::signature_service::{PaymasterKind, SignatureService, SponsorshipRequest};
+use paymaster_service::wire::WireFormat;
+use paymaster_service::Config;
+
+const NOW: u64 = 1_750_000_000;
+const API_KEY: &str = "fuzz";
+
+fn service() -> &'static SignatureService {
+    static SERVICE: OnceLock<SignatureService> = OnceLock::new();
+    SERVICE.get_or_init(|| {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "verifier_keys": { "default": "0000000000000000000000000000000000000000000000000000000000000001" },
+            "api_keys": { API_KEY: "Fuzz" },
+            "server_port": 0,
+            "log_level": "error",
+        }))
+        .expect("fuzz config");
+        SignatureService::new(
+            Arc::new(KeyManager::new(&config)),
+            config.api_keys.clone(),
+            1,
+            vec![0x11; 20],
+            PaymasterKind::Verifying,
+        )
+        .with_clock(Arc::new(FixedClock::new(NOW)))
+    })
+}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| tokio::runtime::Builder::new_current_thread().build().expect("fuzz runtime"))
+}
+
+fuzz_target!(|body: &[u8]| {
+    for format in [WireFormat::Json, WireFormat::Cbor] {
+        let Ok(mut request) = format.decode::<SponsorshipRequest>(body) else { continue };
+        request.api_key = API_KEY.to_string();
+        request.preauth_token = None;
+        request.valid_until = NOW + 3600;
+        let _ = runtime().block_on(service().sign_sponsorship(request));
+    }
+});
@@ -0,0 +1,229 @@
+// Hourly sponsorship aggregates per tenant, persisted so that dashboards survive restarts
+// and served both as Prometheus counters and as date-range queries on /analytics
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::invoice::as_decimal;
+
+/// Width of a bucket
+pub const BUCKET_SECS: u64 = 3600;
+
+/// Tenant of rejections whose API key or pre-auth token was not recognised
+pub const UNKNOWN_TENANT: &str = "unknown";
+
+/// Activity of one tenant during one hour
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bucket {
+    /// Unix time the hour starts at
+    pub start: u64,
+    pub tenant: String,
+    /// Production sponsorships signed
+    pub sponsored: u64,
+    /// Sum of the signed operations' maximum gas cost, what they may draw from the deposit
+    #[serde(serialize_with = "as_decimal", deserialize_with = "from_decimal")]
+    pub committed_wei: u128,
+    /// Refused requests by reason, see `SignatureError::reason`
+    pub rejections: BTreeMap<String, u64>,
+}
+
+fn from_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+/// Served by `GET /analytics`
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsReport {
+    pub from: u64,
+    pub to: u64,
+    pub bucket_secs: u64,
+    pub buckets: Vec<Bucket>,
+}
+
+/// Parse a query bound: unix seconds, an RFC 3339 time or a `YYYY-MM-DD` day (UTC midnight)
+pub fn parse_time(value: &str) -> Option<u64> {
+    if let Ok(seconds) = value.parse() {
+        return Some(seconds);
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return u64::try_from(time.timestamp()).ok();
+    }
+    let day = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    u64::try_from(day.and_hms_opt(0, 0, 0)?.and_utc().timestamp()).ok()
+}
+
+/// Aggregates kept in memory, optionally snapshotted to a JSON file after every change
+#[derive(Default)]
+pub struct Analytics {
+    buckets: Mutex<BTreeMap<(u64, String), Bucket>>,
+    path: Option<PathBuf>,
+}
+
+impl Analytics {
+    /// Aggregates kept in memory only, lost on restart
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregates persisted to `path`, loading the buckets already there
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut buckets = BTreeMap::new();
+        if path.exists() {
+            let saved: Vec<Bucket> = serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            for bucket in saved {
+                buckets.insert((bucket.start, bucket.tenant.clone()), bucket);
+            }
+        }
+        Ok(Self { buckets: Mutex::new(buckets), path: Some(path) })
+    }
+
+    pub fn record_sponsored(&self, tenant: &str, max_gas_cost: u128, timestamp: u64) -> std::io::Result<()> {
+        self.update(tenant, timestamp, |bucket| {
+            bucket.sponsored += 1;
+            bucket.committed_wei = bucket.committed_wei.saturating_add(max_gas_cost);
+        })
+    }
+
+    pub fn record_rejection(&self, tenant: &str, reason: &str, timestamp: u64) -> std::io::Result<()> {
+        self.update(tenant, timestamp, |bucket| *bucket.rejections.entry(reason.to_string()).or_default() += 1)
+    }
+
+    fn update(&self, tenant: &str, timestamp: u64, change: impl FnOnce(&mut Bucket)) -> std::io::Result<()> {
+        let start = timestamp - timestamp % BUCKET_SECS;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry((start, tenant.to_string())).or_insert_with(|| Bucket {
+            start,
+            tenant: tenant.to_string(),
+            ..Default::default()
+        });
+        change(bucket);
+        self.save(&buckets)
+    }
+
+    // Write a temporary file and rename it over the snapshot, so a crash never leaves half a file
+    fn save(&self, buckets: &BTreeMap<(u64, String), Bucket>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&buckets.values().collect::<Vec<_>>())?)?;
+        std::fs::rename(&temporary, path)
+    }
+
+    /// Buckets starting in `[from, to)`, optionally for one tenant, oldest first
+    pub fn query(&self, from: u64, to: u64, tenant: Option<&str>) -> Vec<Bucket> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|bucket| bucket.start >= from && bucket.start < to)
+            .filter(|bucket| tenant.is_none_or(|tenant| bucket.tenant == tenant))
+            .cloned()
+            .collect()
+    }
+
+    /// All-time totals per tenant in the Prometheus text format
+    pub fn to_prometheus(&self) -> String {
+        let mut sponsored: BTreeMap<String, u64> = BTreeMap::new();
+        let mut committed: BTreeMap<String, u128> = BTreeMap::new();
+        let mut rejections: BTreeMap<(String, String), u64> = BTreeMap::new();
+        for bucket in self.buckets.lock().unwrap().values() {
+            *sponsored.entry(bucket.tenant.clone()).or_default() += bucket.sponsored;
+            let total = committed.entry(bucket.tenant.clone()).or_default();
+            *total = total.saturating_add(bucket.committed_wei);
+            for (reason, count) in &bucket.rejections {
+                *rejections.entry((bucket.tenant.clone(), reason.clone())).or_default() += count;
+            }
+        }
+
+        let mut text = String::new();
+        header(&mut text, "paymaster_sponsored_operations_total", "Production sponsorships signed");
+        for (tenant, count) in &sponsored {
+            let _ = writeln!(text, "paymaster_sponsored_operations_total{{tenant=\"{}\"}} {}", label(tenant), count);
+        }
+        header(&mut text, "paymaster_committed_wei_total", "Maximum gas cost of signed sponsorships in wei");
+        for (tenant, wei) in &committed {
+            let _ = writeln!(text, "paymaster_committed_wei_total{{tenant=\"{}\"}} {}", label(tenant), wei);
+        }
+        header(&mut text, "paymaster_rejections_total", "Sponsorship requests refused, by reason");
+        for ((tenant, reason), count) in &rejections {
+            let _ = writeln!(text, "paymaster_rejections_total{{tenant=\"{}\",reason=\"{}\"}} {}", label(tenant), label(reason), count);
+        }
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+}
+
+// Escape a label value as the text format requires
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-09-15 00:00 UTC
+    const HOUR: u64 = 1_789_430_400;
+
+    #[test]
+    fn test_buckets_and_query() {
+        let analytics = Analytics::new();
+        analytics.record_sponsored("Web App", 1_000, HOUR + 10).unwrap();
+        analytics.record_sponsored("Web App", 500, HOUR + 3599).unwrap();
+        analytics.record_sponsored("Web App", 700, HOUR + 3600).unwrap();
+        analytics.record_rejection("Bot", "invalid_timestamp", HOUR).unwrap();
+
+        let first_hour = analytics.query(HOUR, HOUR + 3600, None);
+        assert_eq!(first_hour.len(), 2);
+        let web = first_hour.iter().find(|bucket| bucket.tenant == "Web App").unwrap();
+        assert_eq!((web.sponsored, web.committed_wei), (2, 1_500));
+        assert_eq!(analytics.query(HOUR, HOUR + 7200, Some("Bot"))[0].rejections["invalid_timestamp"], 1);
+        assert_eq!(analytics.query(HOUR, HOUR + 7200, Some("Web App")).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1789430400"), Some(HOUR));
+        assert_eq!(parse_time("2026-09-15"), Some(HOUR));
+        assert_eq!(parse_time("2026-09-15T02:00:00+02:00"), Some(HOUR));
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn test_prometheus_totals() {
+        let analytics = Analytics::new();
+        analytics.record_sponsored("Web \"App\"", 1_000, HOUR).unwrap();
+        analytics.record_sponsored("Web \"App\"", 1_000, HOUR + 3600).unwrap();
+        analytics.record_rejection(UNKNOWN_TENANT, "invalid_api_key", HOUR).unwrap();
+
+        let text = analytics.to_prometheus();
+        assert!(text.contains("# TYPE paymaster_sponsored_operations_total counter"));
+        assert!(text.contains("paymaster_sponsored_operations_total{tenant=\"Web \\\"App\\\"\"} 2"));
+        assert!(text.contains("paymaster_committed_wei_total{tenant=\"Web \\\"App\\\"\"} 2000"));
+        assert!(text.contains("paymaster_rejections_total{tenant=\"unknown\",reason=\"invalid_api_key\"} 1"));
+    }
+
+    #[test]
+    fn test_snapshot_persists_buckets() {
+        let path = std::env::temp_dir().join(format!("paymaster-analytics-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let analytics = Analytics::open(&path).unwrap();
+        analytics.record_sponsored("Web App", u128::MAX / 2, HOUR).unwrap();
+        analytics.record_rejection("Web App", "usage_ledger", HOUR).unwrap();
+
+        let reopened = Analytics::open(&path).unwrap();
+        assert_eq!(reopened.query(0, u64::MAX, None), analytics.query(0, u64::MAX, None));
+        assert_eq!(reopened.query(0, u64::MAX, None)[0].committed_wei, u128::MAX / 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
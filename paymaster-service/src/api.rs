@@ -1,26 +1,210 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use serde::Deserialize;
 use std::sync::Arc;
-use crate::signature_service::{SignatureService, SponsorshipRequest, SponsorshipResponse, Metrics};
+use subtle::ConstantTimeEq;
+use crate::analytics::{self, AnalyticsReport};
+use crate::deployment::{DeploymentPrediction, DeploymentRequest};
+use crate::deposit::PaymasterStatus;
+use crate::invoice::{self, Invoice};
+use crate::leader::Role;
+use crate::reconcile::{self, TenantSettlement};
+use crate::preauth::{PreAuthRequest, PreAuthResponse};
+use crate::stub::{StubRequest, StubResponse};
+use crate::signature_service::{SignatureError, SignatureService, SponsorshipRequest, SponsorshipResponse, Metrics, VerifierInfo};
+use crate::wire::{Wire, WireResponse};
 
 pub async fn sign_sponsorship(
     State(signature_service): State<Arc<SignatureService>>,
-    Json(request): Json<SponsorshipRequest>,
-) -> Result<Json<SponsorshipResponse>, (StatusCode, String)> {
+    Wire { value: request, response_format }: Wire<SponsorshipRequest>,
+) -> Result<WireResponse<SponsorshipResponse>, (StatusCode, String)> {
     signature_service
         .sign_sponsorship(request)
         .await
-        .map(Json)
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+        .map(|value| WireResponse { value, format: response_format })
+        .map_err(signing_error)
+}
+
+pub async fn issue_preauth(
+    State(signature_service): State<Arc<SignatureService>>,
+    Wire { value: request, response_format }: Wire<PreAuthRequest>,
+) -> Result<WireResponse<PreAuthResponse>, (StatusCode, String)> {
+    signature_service
+        .issue_preauth(request)
+        .await
+        .map(|value| WireResponse { value, format: response_format })
+        .map_err(signing_error)
+}
+
+pub async fn paymaster_stub(
+    State(signature_service): State<Arc<SignatureService>>,
+    Wire { value: request, response_format }: Wire<StubRequest>,
+) -> Result<WireResponse<StubResponse>, (StatusCode, String)> {
+    signature_service
+        .paymaster_stub(request)
+        .await
+        .map(|value| WireResponse { value, format: response_format })
+        .map_err(signing_error)
+}
+
+// A standby answers 503 so that load balancers retry the request on the leader
+fn signing_error(e: SignatureError) -> (StatusCode, String) {
+    match e {
+        SignatureError::Standby => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+        e => (StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Header carrying the admin API key
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    /// `YYYY-MM`, defaults to the current month
+    pub month: Option<String>,
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+}
+
+// Admin endpoints take the admin key in `x-admin-key`, or as a bearer token for Prometheus scrapes
+fn check_admin(signature_service: &SignatureService, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(admin_key) = &signature_service.invoicing().admin_api_key else {
+        return Err((StatusCode::NOT_FOUND, "Admin endpoints are disabled".to_string()));
+    };
+    let admin_header = headers.get(ADMIN_KEY_HEADER).map(|v| v.as_bytes());
+    let bearer = headers.get(header::AUTHORIZATION).and_then(|v| v.as_bytes().strip_prefix(b"Bearer "));
+    let presented = match (admin_header, bearer) {
+        (Some(admin_header), Some(bearer)) if admin_header != bearer => {
+            return Err((StatusCode::UNAUTHORIZED, "x-admin-key and the bearer token differ".to_string()));
+        }
+        (admin_header, bearer) => admin_header.or(bearer),
+    };
+    // Constant time, so response timing does not reveal how much of a guess matched
+    if !presented.is_some_and(|key| bool::from(key.ct_eq(admin_key.as_bytes()))) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid admin key".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    /// Unix seconds, RFC 3339 or `YYYY-MM-DD`, defaults to 24 hours before `to`
+    pub from: Option<String>,
+    /// Exclusive, defaults to now
+    pub to: Option<String>,
+    pub tenant: Option<String>,
+}
+
+/// Hourly sponsorship aggregates in a time range
+pub async fn get_analytics(
+    State(signature_service): State<Arc<SignatureService>>,
+    headers: HeaderMap,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsReport>, (StatusCode, String)> {
+    check_admin(&signature_service, &headers)?;
+
+    let bound = |value: Option<String>, default: u64| match value {
+        Some(value) => analytics::parse_time(&value)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Invalid time '{}', expected unix seconds, RFC 3339 or YYYY-MM-DD", value))),
+        None => Ok(default),
+    };
+    let to = bound(query.to, signature_service.now())?;
+    let from = bound(query.from, to.saturating_sub(24 * 3600))?;
+    let buckets = signature_service.analytics().query(from, to, query.tenant.as_deref());
+    Ok(Json(AnalyticsReport { from, to, bucket_secs: analytics::BUCKET_SECS, buckets }))
+}
+
+/// Sponsorship counters in the Prometheus text format
+pub async fn get_prometheus_metrics(
+    State(signature_service): State<Arc<SignatureService>>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    check_admin(&signature_service, &headers)?;
+    let content_type = "text/plain; version=0.0.4";
+    Ok(([(header::CONTENT_TYPE, content_type)], signature_service.analytics().to_prometheus()).into_response())
+}
+
+/// Per-tenant usage statements for one month
+pub async fn get_invoices(
+    State(signature_service): State<Arc<SignatureService>>,
+    headers: HeaderMap,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    check_admin(&signature_service, &headers)?;
+    let settings = signature_service.invoicing();
+
+    let month = query.month.unwrap_or_else(|| {
+        let now = chrono::DateTime::from_timestamp(signature_service.now() as i64, 0).unwrap_or_default();
+        now.format("%Y-%m").to_string()
+    });
+    if !invoice::is_valid_month(&month) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid month '{}', expected YYYY-MM", month)));
+    }
+    let invoice = Invoice::build(&month, &signature_service.usage().records_for_month(&month), settings.markup_bps);
+
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(Json(invoice).into_response()),
+        "csv" => Ok(([(header::CONTENT_TYPE, "text/csv")], invoice.to_csv()).into_response()),
+        other => Err((StatusCode::BAD_REQUEST, format!("Unsupported format '{}', use json or csv", other))),
+    }
+}
+
+/// Used, unused and pending sponsorships per tenant, with what the used ones actually cost
+pub async fn get_reconciliation(
+    State(signature_service): State<Arc<SignatureService>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TenantSettlement>>, (StatusCode, String)> {
+    check_admin(&signature_service, &headers)?;
+    Ok(Json(reconcile::summarize(&signature_service.usage().records())))
+}
+
+/// The paymaster's EntryPoint deposit and stake, for clients to check before relying on sponsorship
+pub async fn get_paymaster_status(
+    State(signature_service): State<Arc<SignatureService>>,
+) -> Result<Json<PaymasterStatus>, (StatusCode, String)> {
+    signature_service.paymaster_status().await.map(Json).map_err(|e| match e {
+        SignatureError::DepositStatusDisabled => (StatusCode::NOT_FOUND, e.to_string()),
+        e => (StatusCode::BAD_GATEWAY, e.to_string()),
+    })
+}
+
+/// Dry run of an account deployment: the address the init_code's factory creates, compared with the sender
+pub async fn predict_deployment(
+    State(signature_service): State<Arc<SignatureService>>,
+    Json(request): Json<DeploymentRequest>,
+) -> Result<Json<DeploymentPrediction>, (StatusCode, String)> {
+    signature_service.predict_deployment(request).await.map(Json).map_err(|e| match e {
+        SignatureError::DeploymentPredictionDisabled => (StatusCode::NOT_FOUND, e.to_string()),
+        SignatureError::DepositError(_) => (StatusCode::BAD_GATEWAY, e.to_string()),
+        e => (StatusCode::BAD_REQUEST, e.to_string()),
+    })
 }
 
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+/// 200 on the replica that signs, 503 on standbys, for load balancer health checks routing `/sign`
+pub async fn leader_check(
+    State(signature_service): State<Arc<SignatureService>>,
+) -> StatusCode {
+    match signature_service.role() {
+        Role::Standby => StatusCode::SERVICE_UNAVAILABLE,
+        Role::Standalone | Role::Leader => StatusCode::OK,
+    }
+}
+
+/// Verifier addresses the service signs with, for deploy scripts and clients to compare with `verifier()`
+pub async fn get_verifier(
+    State(signature_service): State<Arc<SignatureService>>,
+) -> Json<VerifierInfo> {
+    Json(signature_service.verifier_info().await)
+}
+
 pub async fn get_metrics(
     State(signature_service): State<Arc<SignatureService>>,
 ) -> Json<Metrics> {
@@ -0,0 +1,106 @@
+// Dry run of an account deployment: the EntryPoint's getSenderAddress runs the factory in init_code and
+// reverts with the address it would create, so integrators offering free account creation can check
+// that an operation's sender really is the account its init_code deploys before sponsoring it
+
+use serde::{Deserialize, Serialize};
+
+use crate::deposit::{DepositError, DepositReader};
+
+/// Selector of `getSenderAddress(bytes)`
+const GET_SENDER_ADDRESS: [u8; 4] = [0x9b, 0x24, 0x9f, 0x69];
+
+/// Selector of the `SenderAddressResult(address)` error getSenderAddress always reverts with
+const SENDER_ADDRESS_RESULT: [u8; 4] = [0x6c, 0xa7, 0xb8, 0x06];
+
+#[derive(Debug, Deserialize)]
+pub struct DeploymentRequest {
+    pub api_key: String,
+    /// Counterfactual account address the operation will be sent from
+    pub sender: String,
+    /// Factory address followed by its calldata, as in the operation
+    pub init_code: String,
+}
+
+/// Served by `POST /deployment/predict`
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentPrediction {
+    pub sender: String,
+    /// Address the factory deploys for this init_code
+    pub predicted_sender: String,
+    pub factory: String,
+    /// Whether `sender` is the predicted address, a deployment with a wrong sender fails with AA14
+    pub matches: bool,
+}
+
+/// Calldata of `getSenderAddress(init_code)`
+pub fn get_sender_address_call(init_code: &[u8]) -> Vec<u8> {
+    let mut call = GET_SENDER_ADDRESS.to_vec();
+    call.extend_from_slice(&word(32));
+    call.extend_from_slice(&word(init_code.len() as u64));
+    call.extend_from_slice(init_code);
+    call.resize(call.len() + (32 - init_code.len() % 32) % 32, 0);
+    call
+}
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Decode the `SenderAddressResult(address)` revert data into the predicted address
+pub fn decode_sender_address_result(data: &[u8]) -> Option<[u8; 20]> {
+    if data.len() < 36 || data[..4] != SENDER_ADDRESS_RESULT {
+        return None;
+    }
+    data[36 - 20..36].try_into().ok()
+}
+
+impl DepositReader {
+    /// Address the factory in `init_code` deploys, read from getSenderAddress's revert
+    pub async fn sender_address(&self, init_code: &[u8]) -> Result<[u8; 20], DepositError> {
+        let response = self.call(&get_sender_address_call(init_code)).await?;
+        // Nodes put the revert data either directly in `error.data` or one level deeper
+        let error = &response["error"];
+        let revert = error["data"].as_str().or_else(|| error["data"]["data"].as_str()).ok_or_else(|| {
+            DepositError::InvalidResponse(format!("getSenderAddress did not revert: {}", response))
+        })?;
+        let data = hex::decode(revert.strip_prefix("0x").unwrap_or(revert))
+            .map_err(|e| DepositError::InvalidResponse(e.to_string()))?;
+        decode_sender_address_result(&data).ok_or_else(|| DepositError::Rpc(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha3::{Digest, Keccak256};
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(Keccak256::digest(b"getSenderAddress(bytes)")[..4], GET_SENDER_ADDRESS);
+        assert_eq!(Keccak256::digest(b"SenderAddressResult(address)")[..4], SENDER_ADDRESS_RESULT);
+    }
+
+    #[test]
+    fn test_get_sender_address_call() {
+        let call = get_sender_address_call(&[0x11; 40]);
+        assert_eq!(call.len(), 4 + 32 + 32 + 64);
+        assert_eq!(call[4 + 31], 32);
+        assert_eq!(call[4 + 63], 40);
+        assert_eq!(call[68..108], [0x11; 40]);
+        assert!(call[108..].iter().all(|byte| *byte == 0));
+        assert_eq!(get_sender_address_call(&[0x11; 64]).len(), 4 + 32 + 32 + 64);
+    }
+
+    #[test]
+    fn test_decode_sender_address_result() {
+        let mut data = SENDER_ADDRESS_RESULT.to_vec();
+        data.extend_from_slice(&[0u8; 12]);
+        data.extend_from_slice(&[0x33; 20]);
+        assert_eq!(decode_sender_address_result(&data), Some([0x33; 20]));
+        assert_eq!(decode_sender_address_result(&data[..30]), None);
+        data[0] = 0;
+        assert_eq!(decode_sender_address_result(&data), None);
+    }
+}
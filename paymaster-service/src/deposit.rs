@@ -0,0 +1,160 @@
+// The paymaster's EntryPoint deposit and stake, read with eth_call so that clients can check the deposit
+// covers an operation before relying on a sponsorship instead of finding out through AA31 at the bundler
+
+use alloy_primitives::U256;
+use serde::Serialize;
+
+use crate::signature_service::PaymasterKind;
+
+/// EntryPoint v0.7, used when `entry_point` is not configured
+pub const DEFAULT_ENTRY_POINT: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
+
+/// Selector of `getDepositInfo(address)`
+const GET_DEPOSIT_INFO: [u8; 4] = [0x52, 0x87, 0xce, 0x12];
+
+/// `DepositInfo` as stored by the EntryPoint's StakeManager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepositInfo {
+    pub deposit: U256,
+    pub staked: bool,
+    pub stake: U256,
+    pub unstake_delay_sec: u32,
+    /// When an unlocked stake can be withdrawn, 0 while it is locked
+    pub withdraw_time: u64,
+}
+
+impl DepositInfo {
+    /// Decode the return data of `getDepositInfo`: a static struct of five words
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 5 * 32 {
+            return None;
+        }
+        let word = |index: usize| U256::from_be_slice(&data[index * 32..(index + 1) * 32]);
+        Some(Self {
+            deposit: word(0),
+            staked: word(1) != U256::ZERO,
+            stake: word(2),
+            unstake_delay_sec: word(3).saturating_to(),
+            withdraw_time: word(4).saturating_to(),
+        })
+    }
+}
+
+/// Calldata of `getDepositInfo(paymaster)`
+pub fn get_deposit_info_call(paymaster: &[u8]) -> Vec<u8> {
+    let mut call = GET_DEPOSIT_INFO.to_vec();
+    call.extend_from_slice(&[0u8; 12]);
+    call.extend_from_slice(paymaster);
+    call
+}
+
+/// Served by `GET /paymaster`
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymasterStatus {
+    pub paymaster: String,
+    pub chain_id: u64,
+    pub entry_point: String,
+    pub paymaster_kind: PaymasterKind,
+    #[serde(flatten)]
+    pub deposit: DepositInfo,
+}
+
+#[derive(Debug)]
+pub enum DepositError {
+    Rpc(String),
+    InvalidResponse(String),
+}
+
+impl std::fmt::Display for DepositError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DepositError::Rpc(e) => write!(f, "RPC error: {}", e),
+            DepositError::InvalidResponse(e) => write!(f, "Invalid EntryPoint response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DepositError {}
+
+/// Reads deposit information from the EntryPoint over JSON-RPC
+pub struct DepositReader {
+    http: reqwest::Client,
+    rpc_url: String,
+    entry_point: String,
+}
+
+impl DepositReader {
+    pub fn new(rpc_url: String, entry_point: String) -> Self {
+        Self { http: reqwest::Client::new(), rpc_url, entry_point }
+    }
+
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    pub async fn read(&self, paymaster: &[u8]) -> Result<DepositInfo, DepositError> {
+        let response = self.call(&get_deposit_info_call(paymaster)).await?;
+        if let Some(error) = response.get("error") {
+            return Err(DepositError::Rpc(error.to_string()));
+        }
+
+        let result = response["result"]
+            .as_str()
+            .ok_or_else(|| DepositError::InvalidResponse(response.to_string()))?;
+        let data = hex::decode(result.strip_prefix("0x").unwrap_or(result))
+            .map_err(|e| DepositError::InvalidResponse(e.to_string()))?;
+        DepositInfo::decode(&data).ok_or_else(|| DepositError::InvalidResponse(result.to_string()))
+    }
+
+    /// `eth_call` to the EntryPoint at the latest block, returning the whole JSON-RPC response
+    /// so that callers expecting a revert can read its data
+    pub(crate) async fn call(&self, data: &[u8]) -> Result<serde_json::Value, DepositError> {
+        let call = serde_json::json!({ "to": self.entry_point, "data": format!("0x{}", hex::encode(data)) });
+        self.request("eth_call", serde_json::json!([call, "latest"])).await
+    }
+
+    /// Send one JSON-RPC request to the node, returning the whole response
+    pub(crate) async fn request(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, DepositError> {
+        let request = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        self.http
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| DepositError::Rpc(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| DepositError::Rpc(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha3::{Digest, Keccak256};
+
+    #[test]
+    fn test_get_deposit_info_call() {
+        assert_eq!(Keccak256::digest(b"getDepositInfo(address)")[..4], GET_DEPOSIT_INFO);
+        let call = get_deposit_info_call(&[0x11; 20]);
+        assert_eq!(call.len(), 36);
+        assert_eq!(call[16..], [0x11; 20]);
+    }
+
+    #[test]
+    fn test_decode_deposit_info() {
+        let mut data = vec![0u8; 160];
+        data[31] = 100; // deposit
+        data[63] = 1; // staked
+        data[95] = 5; // stake
+        data[124..128].copy_from_slice(&86_400u32.to_be_bytes()); // unstakeDelaySec
+
+        let info = DepositInfo::decode(&data).unwrap();
+        assert_eq!(info.deposit, U256::from(100));
+        assert!(info.staked);
+        assert_eq!(info.stake, U256::from(5));
+        assert_eq!(info.unstake_delay_sec, 86_400);
+        assert_eq!(info.withdraw_time, 0);
+        assert!(DepositInfo::decode(&data[..128]).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::signature_service::PackedUserOperation;
+
+/// Markup is expressed in basis points of the gas cost
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// One sponsorship, recorded when its signature is issued
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Client name of the API key the sponsorship was charged to
+    pub tenant: String,
+    pub sender: String,
+    pub nonce: String,
+    pub timestamp: u64,
+    /// Upper bound from the operation's gas limits and fee cap, billed until the actual cost is known
+    pub max_gas_cost: u128,
+    /// Cost charged to the paymaster deposit once reconciled against the receipt
+    #[serde(default)]
+    pub actual_gas_cost: Option<u128>,
+    /// End of the signature's validity, after which an unused sponsorship can no longer be spent
+    #[serde(default)]
+    pub valid_until: Option<u64>,
+    #[serde(default)]
+    pub settlement: Settlement,
+    /// Signed with the sandbox verifier, kept in the tenant's history but never invoiced
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// Whether a sponsorship was spent on chain, see `reconcile`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Settlement {
+    /// Not seen on chain yet and still valid
+    #[default]
+    Pending,
+    /// Included, `actual_gas_cost` is what the EntryPoint charged
+    Used,
+    /// Expired without being included, costs nothing
+    Unused,
+}
+
+impl UsageRecord {
+    pub fn new(tenant: &str, user_op: &PackedUserOperation, timestamp: u64, valid_until: u64) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            sender: user_op.sender.to_ascii_lowercase(),
+            nonce: user_op.nonce.to_string(),
+            timestamp,
+            max_gas_cost: max_gas_cost(user_op),
+            actual_gas_cost: None,
+            valid_until: Some(valid_until),
+            settlement: Settlement::Pending,
+            sandbox: false,
+        }
+    }
+
+    /// `YYYY-MM` the record is invoiced in (UTC)
+    pub fn month(&self) -> String {
+        chrono::DateTime::from_timestamp(self.timestamp as i64, 0)
+            .unwrap_or_default()
+            .format("%Y-%m")
+            .to_string()
+    }
+
+    fn billed_gas_cost(&self) -> u128 {
+        self.actual_gas_cost.unwrap_or(self.max_gas_cost)
+    }
+}
+
+/// Most the operation can cost the deposit: (verification + call + preVerification gas) * maxFeePerGas
+pub fn max_gas_cost(user_op: &PackedUserOperation) -> u128 {
+    let (verification_gas, call_gas) = unpack_u128_pair(&user_op.account_gas_limits);
+    let (_, max_fee_per_gas) = unpack_u128_pair(&user_op.gas_fees);
+    let pre_verification_gas = user_op.pre_verification_gas.saturating_to::<u128>();
+
+    verification_gas
+        .saturating_add(call_gas)
+        .saturating_add(pre_verification_gas)
+        .saturating_mul(max_fee_per_gas)
+}
+
+// Split a packed bytes32 (accountGasLimits, gasFees) into its high and low 128-bit halves
+fn unpack_u128_pair(packed: &str) -> (u128, u128) {
+    let bytes = hex::decode(packed.strip_prefix("0x").unwrap_or(packed)).unwrap_or_default();
+    let value = U256::try_from_be_slice(&bytes).unwrap_or_default();
+    ((value >> 128usize).to::<u128>(), (value & U256::from(u128::MAX)).to::<u128>())
+}
+
+/// Sponsorships issued by this service, optionally persisted as JSON lines
+#[derive(Default)]
+pub struct UsageLedger {
+    records: Mutex<Vec<UsageRecord>>,
+    path: Option<PathBuf>,
+}
+
+impl UsageLedger {
+    /// Ledger kept in memory only, lost on restart
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ledger appended to `path`, loading the records already there
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut records = Vec::new();
+        if path.exists() {
+            for line in std::io::BufReader::new(std::fs::File::open(&path)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(&line)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                records.push(record);
+            }
+        }
+        Ok(Self { records: Mutex::new(records), path: Some(path) })
+    }
+
+    /// Record a sponsorship, persisting it before it counts
+    pub fn record(&self, record: UsageRecord) -> std::io::Result<()> {
+        let mut records = self.records.lock().unwrap();
+        if let Some(path) = &self.path {
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            std::fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)?;
+        }
+        records.push(record);
+        Ok(())
+    }
+
+    /// Update records in place with `apply`, which returns how many it changed, rewriting the file when any did
+    pub fn settle(&self, apply: impl FnOnce(&mut [UsageRecord]) -> usize) -> std::io::Result<usize> {
+        let mut records = self.records.lock().unwrap();
+        let changed = apply(&mut records);
+        if let (Some(path), true) = (&self.path, changed > 0) {
+            // Rename over the ledger so a crash never leaves it half written
+            let mut lines = Vec::new();
+            for record in records.iter() {
+                lines.extend(serde_json::to_vec(record)?);
+                lines.push(b'\n');
+            }
+            let temporary = path.with_extension("tmp");
+            std::fs::write(&temporary, lines)?;
+            std::fs::rename(&temporary, path)?;
+        }
+        Ok(changed)
+    }
+
+    pub fn records(&self) -> Vec<UsageRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Records invoiced in `month` (`YYYY-MM`)
+    pub fn records_for_month(&self, month: &str) -> Vec<UsageRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.month() == month)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Invoicing parameters and the key guarding the admin endpoints
+#[derive(Debug, Clone, Default)]
+pub struct InvoiceSettings {
+    /// Admin endpoints are disabled when unset
+    pub admin_api_key: Option<String>,
+    pub markup_bps: u32,
+}
+
+/// One tenant's usage for a month
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Statement {
+    pub tenant: String,
+    pub month: String,
+    pub operations: u64,
+    /// Operations billed at their upper bound because their actual cost is not known yet
+    pub unreconciled_operations: u64,
+    #[serde(serialize_with = "as_decimal")]
+    pub gas_cost_wei: u128,
+    #[serde(serialize_with = "as_decimal")]
+    pub markup_wei: u128,
+    #[serde(serialize_with = "as_decimal")]
+    pub total_wei: u128,
+}
+
+// Wei amounts exceed what JSON numbers carry exactly in most clients
+pub(crate) fn as_decimal<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Invoice for every tenant with usage in a month
+#[derive(Debug, Clone, Serialize)]
+pub struct Invoice {
+    pub month: String,
+    pub markup_bps: u32,
+    pub statements: Vec<Statement>,
+}
+
+impl Invoice {
+    /// Per-tenant statements for `month` from the production `records`, sorted by tenant
+    pub fn build(month: &str, records: &[UsageRecord], markup_bps: u32) -> Self {
+        let mut by_tenant: BTreeMap<&str, Vec<&UsageRecord>> = BTreeMap::new();
+        for record in records.iter().filter(|record| record.month() == month && !record.sandbox) {
+            by_tenant.entry(&record.tenant).or_default().push(record);
+        }
+
+        let statements = by_tenant
+            .into_iter()
+            .map(|(tenant, records)| {
+                let gas_cost_wei = records.iter().map(|r| r.billed_gas_cost()).fold(0u128, u128::saturating_add);
+                let markup_wei = gas_cost_wei.saturating_mul(markup_bps as u128) / BPS_DENOMINATOR;
+                Statement {
+                    tenant: tenant.to_string(),
+                    month: month.to_string(),
+                    operations: records.len() as u64,
+                    unreconciled_operations: records.iter().filter(|r| r.actual_gas_cost.is_none()).count() as u64,
+                    gas_cost_wei,
+                    markup_wei,
+                    total_wei: gas_cost_wei.saturating_add(markup_wei),
+                }
+            })
+            .collect();
+
+        Self { month: month.to_string(), markup_bps, statements }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("tenant,month,operations,unreconciled_operations,gas_cost_wei,markup_wei,total_wei\n");
+        for s in &self.statements {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                csv_field(&s.tenant), s.month, s.operations, s.unreconciled_operations, s.gas_cost_wei, s.markup_wei, s.total_wei
+            ));
+        }
+        csv
+    }
+}
+
+// Quote tenant names containing separators or quotes (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Check a `YYYY-MM` month argument
+pub fn is_valid_month(month: &str) -> bool {
+    month.len() == 7 && chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-09-15 and 2026-10-01 (UTC)
+    const SEPTEMBER: u64 = 1_789_430_400;
+    const OCTOBER: u64 = 1_790_812_800;
+
+    fn record(tenant: &str, timestamp: u64, max_gas_cost: u128, actual_gas_cost: Option<u128>) -> UsageRecord {
+        UsageRecord {
+            tenant: tenant.to_string(),
+            sender: "0x1234567890123456789012345678901234567890".to_string(),
+            nonce: "1".to_string(),
+            timestamp,
+            max_gas_cost,
+            actual_gas_cost,
+            valid_until: None,
+            settlement: Settlement::Pending,
+            sandbox: false,
+        }
+    }
+
+    #[test]
+    fn test_max_gas_cost_from_packed_fields() {
+        let user_op = PackedUserOperation {
+            sender: "0x1234567890123456789012345678901234567890".to_string(),
+            nonce: U256::from(1),
+            init_code: "0x".to_string(),
+            call_data: "0x".to_string(),
+            account_gas_limits: format!("0x{:032x}{:032x}", 1_000_000u64, 1_000_000u64),
+            pre_verification_gas: U256::from(21000),
+            gas_fees: format!("0x{:032x}{:032x}", 1_000_000_000u64, 2_000_000_000u64), // 1 gwei priority, 2 gwei max
+            paymaster_and_data: "0x".to_string(),
+        };
+        assert_eq!(max_gas_cost(&user_op), 2_021_000 * 2_000_000_000);
+    }
+
+    #[test]
+    fn test_statements_per_tenant_and_month() {
+        let records = vec![
+            record("Web App", SEPTEMBER, 1_000, Some(400)),
+            record("Web App", SEPTEMBER + 60, 1_000, None),
+            record("Bot, Inc", SEPTEMBER, 500, Some(500)),
+            record("Web App", OCTOBER, 9_000, None),
+            UsageRecord { sandbox: true, ..record("Web App", SEPTEMBER, 7_000, None) },
+        ];
+        let invoice = Invoice::build("2026-09", &records, 250);
+
+        assert_eq!(invoice.statements.len(), 2);
+        let web = &invoice.statements[1];
+        assert_eq!((web.tenant.as_str(), web.operations, web.unreconciled_operations), ("Web App", 2, 1));
+        assert_eq!((web.gas_cost_wei, web.markup_wei, web.total_wei), (1_400, 35, 1_435));
+
+        let csv = invoice.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains("\"Bot, Inc\",2026-09,1,0,500,12,512"));
+        assert_eq!(serde_json::to_value(web).unwrap()["total_wei"], "1435");
+    }
+
+    #[test]
+    fn test_ledger_persists_records() {
+        let path = std::env::temp_dir().join(format!("paymaster-usage-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let ledger = UsageLedger::open(&path).unwrap();
+        ledger.record(record("Web App", SEPTEMBER, 1_000, None)).unwrap();
+        ledger.record(record("Web App", OCTOBER, 1_000, None)).unwrap();
+
+        let reopened = UsageLedger::open(&path).unwrap();
+        assert_eq!(reopened.records_for_month("2026-09").len(), 1);
+        assert!(is_valid_month("2026-09"));
+        assert!(!is_valid_month("2026-13"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use secp256k1::{SecretKey, Secp256k1};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tokio::sync::RwLock;
 use crate::Config;
 
@@ -70,6 +70,7 @@ impl KeyManager {
     }
     
     // New method for signing EIP-191 messages with recovery byte
+    #[tracing::instrument(name = "key_manager.sign", skip(self, message), fields(backend = "local"))]
     pub async fn sign_eip191_message(
         &self,
         verifier_name: &str,
@@ -112,8 +113,22 @@ impl KeyManager {
         let keys = self.keys.read().await;
         keys.len()
     }
+    
+    /// Ethereum address of each verifier key, by verifier name
+    pub async fn verifier_addresses(&self) -> BTreeMap<String, String> {
+        let keys = self.keys.read().await;
+        keys.iter()
+            .map(|(name, secret_key)| (name.clone(), ethereum_address(&secret_key.public_key(&self.secp))))
+            .collect()
+    }
+}
 
-
+// Last 20 bytes of keccak256 over the uncompressed public key without its 0x04 prefix
+fn ethereum_address(public_key: &secp256k1::PublicKey) -> String {
+    use sha3::{Digest, Keccak256};
+    
+    let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
 }
 
 #[cfg(test)]
@@ -128,11 +143,32 @@ mod tests {
         crate::Config {
             verifier_keys,
             api_keys: HashMap::new(),
+            sandbox_api_keys: Vec::new(),
             server_port: 3000,
+            listen_socket: None,
+            listen_socket_mode: None,
             log_level: "info".to_string(),
             chain_id: Some(1),
             paymaster_address: Some("0x0000000000000000000000000000000000000000".to_string()),
             is_simple_paymaster: Some(false),
+            paymaster_kind: None,
+            usage_ledger: None,
+            analytics_file: None,
+            admin_api_key: None,
+            invoice_markup_bps: 0,
+            rpc_url: None,
+            entry_point: None,
+            coordination_redis_url: None,
+            coordination_lock_key: None,
+            coordination_lease_secs: None,
+            reconcile_interval_secs: None,
+            reconcile_from_block: None,
+            fixed_time: None,
+            rng_seed: None,
+            otlp_endpoint: None,
+            otlp_headers: HashMap::new(),
+            otel_service_name: None,
+            otel_sample_ratio: None,
         }
     }
 
@@ -158,6 +194,20 @@ mod tests {
         assert_eq!(sig_bytes.len(), 64); // Compact signature should be 64 bytes
     }
 
+    #[tokio::test]
+    async fn test_verifier_addresses() {
+        let mut config = create_test_config();
+        config.verifier_keys.clear();
+        for vector in aa_test_vectors::SPONSORSHIPS {
+            config.verifier_keys.insert(vector.name.to_string(), vector.verifier_key.to_string());
+        }
+        let addresses = KeyManager::new(&config).verifier_addresses().await;
+        
+        for vector in aa_test_vectors::SPONSORSHIPS {
+            assert_eq!(addresses[vector.name], vector.verifier);
+        }
+    }
+
     #[tokio::test]
     async fn test_verifier_not_found() {
         let config = create_test_config();
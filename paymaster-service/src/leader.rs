@@ -0,0 +1,188 @@
+// Leader election between paymaster replicas: only the replica holding a lease in Redis signs sponsorships,
+// so usage is recorded against tenant budgets once, while standbys keep serving the read endpoints
+// and take over when the leader stops renewing
+
+use secp256k1::rand::{thread_rng, RngCore};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_LOCK_KEY: &str = "paymaster:leader";
+pub const DEFAULT_LEASE_SECS: u64 = 10;
+
+// Take the lease when it is free, extend it when this node already holds it
+const ACQUIRE_OR_RENEW: &str = r"
+local holder = redis.call('GET', KEYS[1])
+if holder == false then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return 1
+end
+if holder == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+";
+
+/// What this replica does, served by `/metrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// No coordination configured, the replica signs on its own
+    Standalone,
+    Leader,
+    /// Serves read endpoints and rejects signing until it wins the lease
+    Standby,
+}
+
+/// Where the leader lease is kept
+#[derive(Clone)]
+pub enum LeaseStore {
+    Redis { client: redis::Client, key: String },
+    /// Lease shared in-process, for tests and single-host setups: holder and expiry in unix ms
+    Memory(Arc<Mutex<Option<(String, u64)>>>),
+}
+
+impl LeaseStore {
+    pub fn redis(url: &str, key: String) -> Result<Self, redis::RedisError> {
+        Ok(LeaseStore::Redis { client: redis::Client::open(url)?, key })
+    }
+
+    pub fn memory() -> Self {
+        LeaseStore::Memory(Arc::default())
+    }
+
+    /// Take or extend the lease for `node_id` until `now_ms + ttl_ms`, returning whether `node_id` holds it
+    pub async fn acquire(&self, node_id: &str, ttl_ms: u64, now_ms: u64) -> Result<bool, redis::RedisError> {
+        match self {
+            LeaseStore::Redis { client, key } => {
+                let mut connection = client.get_multiplexed_async_connection().await?;
+                let held: i64 = redis::Script::new(ACQUIRE_OR_RENEW)
+                    .key(key)
+                    .arg(node_id)
+                    .arg(ttl_ms)
+                    .invoke_async(&mut connection)
+                    .await?;
+                Ok(held == 1)
+            }
+            LeaseStore::Memory(lease) => {
+                let mut lease = lease.lock().unwrap();
+                match lease.as_ref() {
+                    Some((holder, expires_at)) if holder != node_id && *expires_at > now_ms => Ok(false),
+                    _ => {
+                        *lease = Some((node_id.to_string(), now_ms + ttl_ms));
+                        Ok(true)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// This replica's view of the election
+pub struct Leadership {
+    node_id: String,
+    store: LeaseStore,
+    lease: Duration,
+    /// Unix ms until which this replica may sign, 0 while standby
+    leader_until: AtomicU64,
+}
+
+impl Leadership {
+    pub fn new(store: LeaseStore, lease: Duration) -> Self {
+        let mut suffix = [0u8; 4];
+        thread_rng().fill_bytes(&mut suffix);
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "paymaster".to_string());
+        Self {
+            node_id: format!("{}-{}-{}", host, std::process::id(), hex::encode(suffix)),
+            store,
+            lease,
+            leader_until: AtomicU64::new(0),
+        }
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Leader only while the last renewal is fresh, so a replica cut off from Redis steps down
+    /// before its lease expires and a standby can take it
+    pub fn is_leader(&self) -> bool {
+        now_ms() < self.leader_until.load(Ordering::SeqCst)
+    }
+
+    pub fn role(&self) -> Role {
+        if self.is_leader() { Role::Leader } else { Role::Standby }
+    }
+
+    /// Run one election round, returning whether this replica leads afterwards
+    pub async fn renew(&self) -> bool {
+        let started = now_ms();
+        let lease_ms = self.lease.as_millis() as u64;
+        let was_leader = self.is_leader();
+        let held = match self.store.acquire(&self.node_id, lease_ms, started).await {
+            Ok(held) => held,
+            Err(e) => {
+                tracing::warn!("Leader lease unavailable, standing by: {}", e);
+                false
+            }
+        };
+        // Give up local leadership a third of the lease early, ahead of any standby taking over
+        self.leader_until.store(if held { started + lease_ms * 2 / 3 } else { 0 }, Ordering::SeqCst);
+
+        if held && !was_leader {
+            tracing::info!("Replica {} is now the leader", self.node_id);
+        } else if !held && was_leader {
+            tracing::warn!("Replica {} lost the leader lease, standing by", self.node_id);
+        }
+        held
+    }
+
+    /// Renew every third of the lease for as long as the process runs
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.lease / 3);
+            loop {
+                interval.tick().await;
+                self.renew().await;
+            }
+        })
+    }
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_lease_fails_over() {
+        let store = LeaseStore::memory();
+        assert!(store.acquire("a", 1_000, 0).await.unwrap());
+        assert!(!store.acquire("b", 1_000, 500).await.unwrap());
+        // Renewing keeps the lease past its first expiry
+        assert!(store.acquire("a", 1_000, 900).await.unwrap());
+        assert!(!store.acquire("b", 1_000, 1_500).await.unwrap());
+        // A leader that stops renewing is replaced
+        assert!(store.acquire("b", 1_000, 1_900).await.unwrap());
+        assert!(!store.acquire("a", 1_000, 2_000).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_single_leader() {
+        let store = LeaseStore::memory();
+        let first = Leadership::new(store.clone(), Duration::from_secs(DEFAULT_LEASE_SECS));
+        let second = Leadership::new(store, Duration::from_secs(DEFAULT_LEASE_SECS));
+        assert_ne!(first.node_id(), second.node_id());
+        assert_eq!(first.role(), Role::Standby);
+
+        assert!(first.renew().await);
+        assert!(!second.renew().await);
+        assert_eq!(first.role(), Role::Leader);
+        assert_eq!(second.role(), Role::Standby);
+    }
+}
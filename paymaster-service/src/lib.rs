@@ -1,21 +1,107 @@
 use serde::Deserialize;
 
+use crate::signature_service::PaymasterKind;
+
 pub mod key_manager;
 pub mod signature_service;
 pub mod api;
+pub mod wire;
+pub mod transport;
+pub mod preauth;
+pub mod stub;
+pub mod invoice;
+pub mod deposit;
+pub mod deployment;
+pub mod reconcile;
+pub mod leader;
+pub mod analytics;
+pub mod clock;
+pub mod telemetry;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub verifier_keys: std::collections::HashMap<String, String>,
     pub api_keys: std::collections::HashMap<String, String>,
+    /// API keys in sandbox mode: signed with the `sandbox` verifier key, never the production one
+    #[serde(default)]
+    pub sandbox_api_keys: Vec<String>,
     pub server_port: u16,
+    /// Unix domain socket path (or Windows named pipe) to listen on instead of TCP
+    pub listen_socket: Option<String>,
+    /// Octal permissions of the Unix socket, `600` when unset
+    #[serde(default)]
+    pub listen_socket_mode: Option<String>,
     pub log_level: String,
     pub chain_id: Option<u64>,
     pub paymaster_address: Option<String>,
+    /// Older spelling of `paymaster_kind = "simple"`
     pub is_simple_paymaster: Option<bool>,
+    /// `verifying` (default) or `simple`
+    #[serde(default)]
+    pub paymaster_kind: Option<PaymasterKind>,
+    /// JSON-lines file sponsorships are recorded in for invoicing; kept in memory when unset
+    #[serde(default)]
+    pub usage_ledger: Option<String>,
+    /// JSON file hourly sponsorship aggregates are kept in for /analytics; kept in memory when unset
+    #[serde(default)]
+    pub analytics_file: Option<String>,
+    /// Key for the /admin endpoints, which are disabled when unset
+    #[serde(default)]
+    pub admin_api_key: Option<String>,
+    /// Markup on sponsored gas cost in invoices, in basis points
+    #[serde(default)]
+    pub invoice_markup_bps: u32,
+    /// JSON-RPC endpoint the paymaster's EntryPoint deposit is read from for /paymaster
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// EntryPoint holding the deposit, v0.7 when unset
+    #[serde(default)]
+    pub entry_point: Option<String>,
+    /// Redis holding the leader lease; when set only the leader signs and standbys serve reads
+    #[serde(default)]
+    pub coordination_redis_url: Option<String>,
+    /// Key of the leader lease, `paymaster:leader` when unset
+    #[serde(default)]
+    pub coordination_lock_key: Option<String>,
+    /// How long a lease lasts without renewal, 10 seconds when unset
+    #[serde(default)]
+    pub coordination_lease_secs: Option<u64>,
+    /// Seconds between rounds settling the usage ledger from UserOperationEvent logs, 60 when unset, 0 disables
+    #[serde(default)]
+    pub reconcile_interval_secs: Option<u64>,
+    /// First block scanned for UserOperationEvent logs, 10000 blocks back from the head when unset
+    #[serde(default)]
+    pub reconcile_from_block: Option<u64>,
+    /// Unix time the service clock is frozen at, for integration tests and replays; never in production
+    #[serde(default)]
+    pub fixed_time: Option<u64>,
+    /// Seed making pre-auth tokens reproducible, for integration tests and replays; never in production
+    #[serde(default)]
+    pub rng_seed: Option<u64>,
+    /// OTLP/HTTP collector traces and metrics of the signing pipeline are exported to, e.g. `http://localhost:4318`
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Headers sent with every export, e.g. the API key of a hosted collector
+    #[serde(default)]
+    pub otlp_headers: std::collections::HashMap<String, String>,
+    /// `service.name` of exported traces and metrics, `paymaster-service` when unset
+    #[serde(default)]
+    pub otel_service_name: Option<String>,
+    /// Share of new traces that are sampled, all when unset; traces started by the caller keep their decision
+    #[serde(default)]
+    pub otel_sample_ratio: Option<f64>,
 }
 
 impl Config {
+    /// `paymaster_kind`, falling back to `is_simple_paymaster`
+    pub fn paymaster_kind(&self) -> PaymasterKind {
+        self.paymaster_kind.unwrap_or(if self.is_simple_paymaster == Some(true) {
+            PaymasterKind::Simple
+        } else {
+            PaymasterKind::Verifying
+        })
+    }
+    
     pub fn load() -> Result<Self, config::ConfigError> {
         // Allow config file to be specified via environment variable or default
         let config_file = std::env::var("PAYMASTER_CONFIG")
@@ -4,22 +4,31 @@ use axum::{
 };
 use std::sync::Arc;
 
-mod key_manager;
-mod signature_service;
-mod api;
-
-use signature_service::SignatureService;
-use key_manager::KeyManager;
+use paymaster_service::api;
+use paymaster_service::transport::{self, ListenAddr};
+use paymaster_service::signature_service::{PaymasterKind, SignatureError, SignatureService, PRODUCTION_VERIFIER, SANDBOX_VERIFIER};
+use paymaster_service::key_manager::KeyManager;
+use paymaster_service::deposit::{DepositReader, DEFAULT_ENTRY_POINT};
+use paymaster_service::analytics::Analytics;
+use paymaster_service::clock::{FixedClock, SeededEntropy};
+use paymaster_service::invoice::{InvoiceSettings, UsageLedger};
+use paymaster_service::leader::{Leadership, LeaseStore, DEFAULT_LEASE_SECS, DEFAULT_LOCK_KEY};
+use paymaster_service::reconcile::{self, Reconciler};
+use paymaster_service::telemetry;
 use paymaster_service::Config;
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
     // Load configuration
     let config = Config::load().expect("Failed to load config");
     
+    // Initialize tracing, exporting to an OpenTelemetry collector when one is configured
+    let _telemetry = telemetry::init(&config).expect("Failed to initialize telemetry");
+    
+    if !config.sandbox_api_keys.is_empty() && !config.verifier_keys.contains_key(SANDBOX_VERIFIER) {
+        tracing::warn!("Sandbox API keys configured without a '{}' verifier key, their requests will fail", SANDBOX_VERIFIER);
+    }
+    
     // Initialize services
     let key_manager = Arc::new(KeyManager::new(&config));
     
@@ -32,29 +41,120 @@ async fn main() {
         vec![0u8; 20] // Default to zero address
     };
     
-    let is_simple_paymaster = config.is_simple_paymaster.unwrap_or(false);
+    let paymaster_kind = config.paymaster_kind();
+    if !config.sandbox_api_keys.is_empty() && paymaster_kind == PaymasterKind::Simple {
+        tracing::warn!("Sandbox API keys configured for a SimplePaymaster, their requests are refused");
+    }
+    
+    let usage = match &config.usage_ledger {
+        Some(path) => UsageLedger::open(path).expect("Failed to open usage ledger"),
+        None => {
+            tracing::warn!("No usage_ledger configured, sponsorship usage is kept in memory and lost on restart");
+            UsageLedger::new()
+        }
+    };
+    let analytics = match &config.analytics_file {
+        Some(path) => Analytics::open(path).expect("Failed to open analytics file"),
+        None => Analytics::new(),
+    };
+    let invoicing = InvoiceSettings {
+        admin_api_key: config.admin_api_key.clone(),
+        markup_bps: config.invoice_markup_bps,
+    };
     
-    let signature_service = Arc::new(SignatureService::new(
+    let signature_service = SignatureService::new(
         key_manager, 
         config.api_keys, 
         chain_id, 
-        paymaster_address,
-        is_simple_paymaster
-    ));
+        paymaster_address.clone(),
+        paymaster_kind
+    )
+        .with_sandbox_keys(config.sandbox_api_keys)
+        .with_invoicing(usage, invoicing)
+        .with_analytics(analytics);
+    let entry_point = config.entry_point.clone().unwrap_or_else(|| DEFAULT_ENTRY_POINT.to_string());
+    let signature_service = match &config.rpc_url {
+        Some(rpc_url) => signature_service.with_deposit_reader(DepositReader::new(rpc_url.clone(), entry_point.clone())),
+        None => signature_service,
+    };
+    let signature_service = match &config.coordination_redis_url {
+        Some(redis_url) => {
+            let key = config.coordination_lock_key.clone().unwrap_or_else(|| DEFAULT_LOCK_KEY.to_string());
+            let store = LeaseStore::redis(redis_url, key).expect("Invalid coordination_redis_url");
+            let lease = std::time::Duration::from_secs(config.coordination_lease_secs.unwrap_or(DEFAULT_LEASE_SECS));
+            let leadership = Arc::new(Leadership::new(store, lease));
+            if !leadership.renew().await {
+                tracing::info!("Replica {} starting as standby", leadership.node_id());
+            }
+            leadership.clone().spawn();
+            signature_service.with_leadership(leadership)
+        }
+        None => signature_service,
+    };
+    let signature_service = match config.fixed_time {
+        Some(now) => {
+            tracing::warn!("Clock frozen at {} by fixed_time, sponsorship windows will not advance", now);
+            signature_service.with_clock(Arc::new(FixedClock::new(now)))
+        }
+        None => signature_service,
+    };
+    let signature_service = match config.rng_seed {
+        Some(seed) => {
+            tracing::warn!("Pre-auth tokens seeded by rng_seed are predictable, never use it in production");
+            signature_service.with_entropy(Box::new(SeededEntropy::new(seed)))
+        }
+        None => signature_service,
+    };
+    let signature_service = Arc::new(signature_service);
+    
+    // The deployed paymaster's verifier() must match this, or every sponsorship fails with AA34
+    match signature_service.verifier_info().await.verifier {
+        Some(verifier) => tracing::info!("Signing sponsorships as verifier {}", verifier),
+        None => tracing::warn!("No '{}' verifier key configured, production API keys cannot be signed for", PRODUCTION_VERIFIER),
+    }
+    
+    // A paymaster without deposit signs sponsorships every bundler rejects with AA31
+    match signature_service.paymaster_status().await {
+        Ok(status) if status.deposit.deposit.is_zero() => {
+            tracing::warn!("Paymaster {} has no deposit at EntryPoint {}, sponsored operations will fail", status.paymaster, status.entry_point);
+        }
+        Ok(status) => tracing::info!("Paymaster deposit: {} wei", status.deposit.deposit),
+        Err(SignatureError::DepositStatusDisabled) => {}
+        Err(e) => tracing::warn!("{}", e),
+    }
+    
+    // Settle the usage ledger with what sponsored operations actually cost, from the EntryPoint's logs
+    if let (Some(rpc_url), true) = (&config.rpc_url, config.reconcile_interval_secs != Some(0)) {
+        let reader = DepositReader::new(rpc_url.clone(), entry_point);
+        let reconciler = Arc::new(Reconciler::new(reader, &paymaster_address, config.reconcile_from_block));
+        let interval = config.reconcile_interval_secs.unwrap_or(reconcile::DEFAULT_INTERVAL_SECS);
+        reconciler.spawn(signature_service.clone(), std::time::Duration::from_secs(interval));
+    }
     
     // Build application
     let app = Router::new()
         .route("/health", get(api::health_check))
+        .route("/leader", get(api::leader_check))
         .route("/sign", post(api::sign_sponsorship))
+        .route("/preauth", post(api::issue_preauth))
+        .route("/stub", post(api::paymaster_stub))
         .route("/metrics", get(api::get_metrics))
+        .route("/paymaster", get(api::get_paymaster_status))
+        .route("/verifier", get(api::get_verifier))
+        .route("/deployment/predict", post(api::predict_deployment))
+        .route("/admin/invoices", get(api::get_invoices))
+        .route("/admin/reconciliation", get(api::get_reconciliation))
+        .route("/analytics", get(api::get_analytics))
+        .route("/metrics/prometheus", get(api::get_prometheus_metrics))
+        .route_layer(axum::middleware::from_fn(telemetry::trace_request))
         .with_state(signature_service);
     
     // Start server
-    let addr = format!("[::]:{}", config.server_port);
+    let addr = ListenAddr::from_config(config.server_port, config.listen_socket.as_deref(), config.listen_socket_mode.as_deref())
+        .expect("Invalid listen_socket_mode");
     tracing::info!("Starting paymaster service on {}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    transport::serve(app, &addr).await.unwrap();
 }
 
 
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::clock::{Entropy, OsEntropy};
+
+/// Default lifetime of a pre-auth token
+pub const DEFAULT_TTL_SECS: u64 = 600;
+/// Tokens are meant for one-time links, never long-lived credentials
+pub const MAX_TTL_SECS: u64 = 3600;
+
+const TOKEN_PREFIX: &str = "pa_";
+
+#[derive(Debug, Deserialize)]
+pub struct PreAuthRequest {
+    pub api_key: String,
+    /// Only this smart account may redeem the token
+    pub sender: String,
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreAuthResponse {
+    pub token: String,
+    pub sender: String,
+    pub expires_at: u64,
+}
+
+/// What a token was issued for
+#[derive(Debug, Clone)]
+struct Grant {
+    sender: String,
+    /// API key whose policy the sponsorship is charged to
+    api_key: String,
+    expires_at: u64,
+}
+
+/// Outstanding single-use pre-auth tokens, kept in memory only
+pub struct PreAuthStore {
+    grants: Mutex<HashMap<String, Grant>>,
+    entropy: Box<dyn Entropy>,
+}
+
+impl Default for PreAuthStore {
+    fn default() -> Self {
+        Self::with_entropy(Box::new(OsEntropy))
+    }
+}
+
+impl PreAuthStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store drawing its tokens from `entropy`
+    pub fn with_entropy(entropy: Box<dyn Entropy>) -> Self {
+        Self { grants: Mutex::new(HashMap::new()), entropy }
+    }
+
+    /// Issue a token for one sponsored operation from `sender`, returning it with its expiry
+    pub fn issue(&self, api_key: &str, sender: &str, ttl_secs: u64, now: u64) -> (String, u64) {
+        let mut bytes = [0u8; 32];
+        self.entropy.fill(&mut bytes);
+        let token = format!("{}{}", TOKEN_PREFIX, hex::encode(bytes));
+        let expires_at = now + ttl_secs.min(MAX_TTL_SECS);
+
+        let mut grants = self.grants.lock().unwrap();
+        grants.retain(|_, grant| grant.expires_at > now);
+        grants.insert(
+            token.clone(),
+            Grant {
+                sender: normalize_address(sender),
+                api_key: api_key.to_string(),
+                expires_at,
+            },
+        );
+        (token, expires_at)
+    }
+
+    /// Consume a token for `sender`, returning the API key it was issued under
+    /// Unknown, expired and already used tokens are indistinguishable to the caller
+    pub fn redeem(&self, token: &str, sender: &str, now: u64) -> Option<String> {
+        let mut grants = self.grants.lock().unwrap();
+        let grant = grants.get(token)?;
+        if grant.expires_at <= now || grant.sender != normalize_address(sender) {
+            return None;
+        }
+        grants.remove(token).map(|grant| grant.api_key)
+    }
+
+    /// API key a token for `sender` was issued under, leaving the token unspent
+    pub fn check(&self, token: &str, sender: &str, now: u64) -> Option<String> {
+        let grants = self.grants.lock().unwrap();
+        let grant = grants.get(token)?;
+        (grant.expires_at > now && grant.sender == normalize_address(sender)).then(|| grant.api_key.clone())
+    }
+
+    pub fn outstanding(&self) -> usize {
+        self.grants.lock().unwrap().len()
+    }
+}
+
+fn normalize_address(address: &str) -> String {
+    address.strip_prefix("0x").unwrap_or(address).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDER: &str = "0x1234567890123456789012345678901234567890";
+
+    #[test]
+    fn test_token_is_single_use() {
+        let store = PreAuthStore::new();
+        let (token, expires_at) = store.issue("key", SENDER, 60, 1000);
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(expires_at, 1060);
+
+        // Checking does not spend it
+        assert_eq!(store.check(&token, SENDER, 1001), Some("key".to_string()));
+        assert_eq!(store.redeem(&token, &SENDER.to_uppercase().replace("0X", "0x"), 1001), Some("key".to_string()));
+        assert_eq!(store.redeem(&token, SENDER, 1002), None);
+    }
+
+    #[test]
+    fn test_token_bound_to_sender_and_expiry() {
+        let store = PreAuthStore::new();
+        let (token, _) = store.issue("key", SENDER, 60, 1000);
+
+        // A wrong sender does not burn the token
+        assert_eq!(store.redeem(&token, "0x0000000000000000000000000000000000000001", 1001), None);
+        assert_eq!(store.redeem(&token, SENDER, 1060), None);
+        assert_eq!(store.outstanding(), 1);
+
+        let (_, expires_at) = store.issue("key", SENDER, 10 * MAX_TTL_SECS, 2000);
+        assert_eq!(expires_at, 2000 + MAX_TTL_SECS);
+        // Issuing prunes expired grants
+        assert_eq!(store.outstanding(), 1);
+    }
+
+    #[test]
+    fn test_seeded_tokens_repeat() {
+        use crate::clock::SeededEntropy;
+
+        let issue = || PreAuthStore::with_entropy(Box::new(SeededEntropy::new(7))).issue("key", SENDER, 60, 1000).0;
+        assert_eq!(issue(), issue());
+        assert_ne!(issue(), PreAuthStore::new().issue("key", SENDER, 60, 1000).0);
+    }
+}
@@ -0,0 +1,328 @@
+// Reconciliation of issued sponsorships against the EntryPoint's UserOperationEvent logs: a background job
+// settles every ledger record with the gas the EntryPoint actually charged the deposit, instead of the
+// upper bound it was billed at when signed, and flags signatures that expired without ever being used
+
+use alloy_primitives::U256;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::deposit::{DepositError, DepositReader};
+use crate::invoice::{as_decimal, Settlement, UsageLedger, UsageRecord};
+use crate::signature_service::SignatureService;
+
+/// Seconds between reconciliation rounds when `reconcile_interval_secs` is not set
+pub const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Blocks scanned back from the head on startup when `reconcile_from_block` is not set
+pub const DEFAULT_LOOKBACK_BLOCKS: u64 = 10_000;
+
+/// Widest block range asked for in one eth_getLogs, which most providers cap
+const MAX_BLOCK_RANGE: u64 = 2_000;
+
+/// Topic of `UserOperationEvent(bytes32,address,address,uint256,bool,uint256,uint256)`
+const USER_OPERATION_EVENT: &str = "0x49628fd1471006c1482da88028e9ce4dbb080b815c9b0344d39e5a8e6ec1419f";
+
+/// An operation the EntryPoint executed with this paymaster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserOperationEvent {
+    pub user_op_hash: String,
+    /// Lowercase 0x address, as in the usage ledger
+    pub sender: String,
+    pub nonce: U256,
+    pub success: bool,
+    pub actual_gas_cost: u128,
+}
+
+impl UserOperationEvent {
+    /// Decode an eth_getLogs entry: sender in the second indexed topic, the rest in the data words
+    pub fn decode(log: &serde_json::Value) -> Option<Self> {
+        let topics = log["topics"].as_array()?;
+        let user_op_hash = topics.get(1)?.as_str()?.to_string();
+        let sender = topics.get(2)?.as_str()?;
+        let data = log["data"].as_str()?;
+        let data = hex::decode(data.strip_prefix("0x").unwrap_or(data)).ok()?;
+        if data.len() < 4 * 32 || sender.len() != 66 {
+            return None;
+        }
+        let word = |index: usize| U256::from_be_slice(&data[index * 32..(index + 1) * 32]);
+        Some(Self {
+            user_op_hash,
+            sender: format!("0x{}", sender[26..].to_ascii_lowercase()),
+            nonce: word(0),
+            success: word(1) != U256::ZERO,
+            actual_gas_cost: word(2).saturating_to(),
+        })
+    }
+}
+
+/// Settle pending records: used when an event matches their sender and nonce, unused when their
+/// signature expired before `scanned_until` without one, provided the scan since `scanned_since` saw
+/// their whole validity. Returns how many records changed
+pub fn settle(records: &mut [UsageRecord], events: &[UserOperationEvent], scanned_since: u64, scanned_until: u64) -> usize {
+    let mut changed = 0;
+    // Sandbox signatures are only valid against the test paymaster, whose events are not scanned
+    for record in records.iter_mut().filter(|record| record.settlement == Settlement::Pending && !record.sandbox) {
+        if let Some(event) = events.iter().find(|e| e.sender == record.sender && e.nonce.to_string() == record.nonce) {
+            record.settlement = Settlement::Used;
+            record.actual_gas_cost = Some(event.actual_gas_cost);
+            changed += 1;
+        } else if record.timestamp >= scanned_since && record.valid_until.is_some_and(|valid_until| valid_until < scanned_until) {
+            record.settlement = Settlement::Unused;
+            record.actual_gas_cost = Some(0);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// One tenant's sponsorships by settlement, served by `GET /admin/reconciliation`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TenantSettlement {
+    pub tenant: String,
+    pub used: u64,
+    /// Signatures issued but never spent
+    pub unused: u64,
+    pub pending: u64,
+    /// Upper bound reserved by every sponsorship when it was signed
+    #[serde(serialize_with = "as_decimal")]
+    pub max_gas_cost_wei: u128,
+    /// What the used ones actually cost the deposit
+    #[serde(serialize_with = "as_decimal")]
+    pub actual_gas_cost_wei: u128,
+}
+
+/// Settlement totals per tenant for production sponsorships, sorted by tenant
+pub fn summarize(records: &[UsageRecord]) -> Vec<TenantSettlement> {
+    let mut tenants: BTreeMap<&str, TenantSettlement> = BTreeMap::new();
+    for record in records.iter().filter(|record| !record.sandbox) {
+        let tenant = tenants.entry(&record.tenant).or_insert_with(|| TenantSettlement {
+            tenant: record.tenant.clone(),
+            ..Default::default()
+        });
+        match record.settlement {
+            Settlement::Used => tenant.used += 1,
+            Settlement::Unused => tenant.unused += 1,
+            Settlement::Pending => tenant.pending += 1,
+        }
+        tenant.max_gas_cost_wei = tenant.max_gas_cost_wei.saturating_add(record.max_gas_cost);
+        if record.settlement == Settlement::Used {
+            tenant.actual_gas_cost_wei = tenant.actual_gas_cost_wei.saturating_add(record.actual_gas_cost.unwrap_or_default());
+        }
+    }
+    tenants.into_values().collect()
+}
+
+/// Outcome of one reconciliation round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    /// Last block scanned
+    pub to_block: u64,
+    pub events: usize,
+    pub settled: usize,
+}
+
+// Next block to scan, and the timestamp of the first block ever scanned
+#[derive(Clone, Copy)]
+struct Cursor {
+    next_block: u64,
+    scanned_since: u64,
+}
+
+/// Scans the EntryPoint for this paymaster's operations and settles the usage ledger with them
+pub struct Reconciler {
+    reader: DepositReader,
+    /// Third indexed topic of the paymaster's events
+    paymaster_topic: String,
+    from_block: Option<u64>,
+    cursor: Mutex<Option<Cursor>>,
+}
+
+impl Reconciler {
+    /// Scan from `from_block`, or `DEFAULT_LOOKBACK_BLOCKS` back from the head when it is None
+    pub fn new(reader: DepositReader, paymaster: &[u8], from_block: Option<u64>) -> Self {
+        Self {
+            reader,
+            paymaster_topic: format!("0x{:0>64}", hex::encode(paymaster)),
+            from_block,
+            cursor: Mutex::new(None),
+        }
+    }
+
+    /// Scan the blocks since the last round and settle `ledger` with the events found
+    pub async fn run_once(&self, ledger: &UsageLedger) -> Result<Round, DepositError> {
+        let latest = quantity(&self.rpc("eth_blockNumber", serde_json::json!([])).await?)?;
+        let saved = *self.cursor.lock().unwrap();
+        let cursor = match saved {
+            Some(cursor) => cursor,
+            None => {
+                let next_block = self.from_block.unwrap_or(latest.saturating_sub(DEFAULT_LOOKBACK_BLOCKS));
+                Cursor { next_block, scanned_since: self.block_timestamp(next_block).await? }
+            }
+        };
+        if cursor.next_block > latest {
+            return Ok(Round { to_block: latest, events: 0, settled: 0 });
+        }
+
+        let mut events = Vec::new();
+        let mut from = cursor.next_block;
+        while from <= latest {
+            let to = latest.min(from + MAX_BLOCK_RANGE - 1);
+            events.extend(self.logs(from, to).await?);
+            from = to + 1;
+        }
+        let scanned_until = self.block_timestamp(latest).await?;
+        let settled = ledger
+            .settle(|records| settle(records, &events, cursor.scanned_since, scanned_until))
+            .map_err(|e| DepositError::Rpc(format!("Could not update the usage ledger: {}", e)))?;
+
+        *self.cursor.lock().unwrap() = Some(Cursor { next_block: latest + 1, ..cursor });
+        Ok(Round { to_block: latest, events: events.len(), settled })
+    }
+
+    /// Run a round every `interval` for as long as the process runs
+    pub fn spawn(self: Arc<Self>, service: Arc<SignatureService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match self.run_once(service.usage()).await {
+                    Ok(round) if round.settled > 0 => {
+                        tracing::info!("Reconciled {} sponsorships up to block {}", round.settled, round.to_block)
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Reconciliation round failed, retrying next interval: {}", e),
+                }
+            }
+        })
+    }
+
+    async fn logs(&self, from: u64, to: u64) -> Result<Vec<UserOperationEvent>, DepositError> {
+        let filter = serde_json::json!({
+            "address": self.reader.entry_point(),
+            "fromBlock": format!("0x{:x}", from),
+            "toBlock": format!("0x{:x}", to),
+            "topics": [USER_OPERATION_EVENT, null, null, self.paymaster_topic],
+        });
+        let logs = self.rpc("eth_getLogs", serde_json::json!([filter])).await?;
+        let logs = logs.as_array().ok_or_else(|| DepositError::InvalidResponse(logs.to_string()))?;
+        Ok(logs.iter().filter_map(UserOperationEvent::decode).collect())
+    }
+
+    async fn block_timestamp(&self, block: u64) -> Result<u64, DepositError> {
+        let block = self.rpc("eth_getBlockByNumber", serde_json::json!([format!("0x{:x}", block), false])).await?;
+        quantity(&block["timestamp"])
+    }
+
+    async fn rpc(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, DepositError> {
+        let mut response = self.reader.request(method, params).await?;
+        if let Some(error) = response.get("error") {
+            return Err(DepositError::Rpc(error.to_string()));
+        }
+        Ok(response["result"].take())
+    }
+}
+
+fn quantity(value: &serde_json::Value) -> Result<u64, DepositError> {
+    value
+        .as_str()
+        .and_then(|text| u64::from_str_radix(text.strip_prefix("0x").unwrap_or(text), 16).ok())
+        .ok_or_else(|| DepositError::InvalidResponse(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha3::{Digest, Keccak256};
+
+    const SENDER: &str = "0x1234567890123456789012345678901234567890";
+
+    fn record(nonce: u64, timestamp: u64, valid_until: Option<u64>) -> UsageRecord {
+        UsageRecord {
+            tenant: "Web App".to_string(),
+            sender: SENDER.to_string(),
+            nonce: nonce.to_string(),
+            timestamp,
+            max_gas_cost: 1_000,
+            actual_gas_cost: None,
+            valid_until,
+            settlement: Settlement::Pending,
+            sandbox: false,
+        }
+    }
+
+    fn event(nonce: u64, actual_gas_cost: u128) -> UserOperationEvent {
+        UserOperationEvent {
+            user_op_hash: format!("0x{:064x}", nonce),
+            sender: SENDER.to_string(),
+            nonce: U256::from(nonce),
+            success: true,
+            actual_gas_cost,
+        }
+    }
+
+    #[test]
+    fn test_event_topic() {
+        let signature = b"UserOperationEvent(bytes32,address,address,uint256,bool,uint256,uint256)";
+        assert_eq!(USER_OPERATION_EVENT, format!("0x{}", hex::encode(Keccak256::digest(signature))));
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let word = |value: u64| format!("{:064x}", value);
+        let log = serde_json::json!({
+            "topics": [USER_OPERATION_EVENT, format!("0x{}", "ab".repeat(32)), format!("0x{:0>64}", "ABCDEF1234567890ABCDEF1234567890ABCDEF12"), format!("0x{}", word(0))],
+            "data": format!("0x{}{}{}{}", word(7), word(1), word(42_000), word(21_000)),
+        });
+        let event = UserOperationEvent::decode(&log).unwrap();
+        assert_eq!(event.sender, "0xabcdef1234567890abcdef1234567890abcdef12");
+        assert_eq!((event.nonce, event.success, event.actual_gas_cost), (U256::from(7), true, 42_000));
+        assert!(UserOperationEvent::decode(&serde_json::json!({ "topics": [], "data": "0x" })).is_none());
+    }
+
+    #[test]
+    fn test_settle_used_unused_and_pending() {
+        let mut records = vec![
+            record(1, 100, Some(200)),
+            // Expired without an event
+            record(2, 100, Some(200)),
+            // Still valid
+            record(3, 100, Some(900)),
+            // Signed before the scan started, its event may be in blocks that were not scanned
+            record(4, 10, Some(200)),
+            // Sandbox signature, whatever the production paymaster's events say
+            UsageRecord { sandbox: true, ..record(1, 100, Some(200)) },
+        ];
+        assert_eq!(settle(&mut records, &[event(1, 400)], 50, 500), 2);
+        assert_eq!((records[0].settlement, records[0].actual_gas_cost), (Settlement::Used, Some(400)));
+        assert_eq!((records[1].settlement, records[1].actual_gas_cost), (Settlement::Unused, Some(0)));
+        assert_eq!(records[2].settlement, Settlement::Pending);
+        assert_eq!(records[3].settlement, Settlement::Pending);
+        assert_eq!(records[4].settlement, Settlement::Pending);
+
+        // Settled records are left alone on later rounds
+        assert_eq!(settle(&mut records, &[event(2, 999)], 50, 600), 0);
+
+        let summary = summarize(&records);
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].used, summary[0].unused, summary[0].pending), (1, 1, 2));
+        assert_eq!((summary[0].max_gas_cost_wei, summary[0].actual_gas_cost_wei), (4_000, 400));
+    }
+
+    #[test]
+    fn test_ledger_settles_on_disk() {
+        let path = std::env::temp_dir().join(format!("paymaster-reconcile-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let ledger = UsageLedger::open(&path).unwrap();
+        ledger.record(record(1, 100, Some(200))).unwrap();
+        ledger.record(record(2, 100, Some(900))).unwrap();
+        assert_eq!(ledger.settle(|records| settle(records, &[event(1, 400)], 0, 300)).unwrap(), 1);
+
+        let reopened = UsageLedger::open(&path).unwrap().records();
+        assert_eq!(reopened, ledger.records());
+        assert_eq!(reopened[0].actual_gas_cost, Some(400));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,46 @@
 use alloy_primitives::U256;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use crate::analytics::{Analytics, UNKNOWN_TENANT};
+use crate::clock::{Clock, Entropy, SystemClock};
+use crate::deployment::{DeploymentPrediction, DeploymentRequest};
+use crate::deposit::{DepositError, DepositReader, PaymasterStatus};
+use crate::invoice::{InvoiceSettings, UsageLedger, UsageRecord};
 use crate::key_manager::{KeyManager, KeyManagerError};
+use crate::leader::{Leadership, Role};
+use crate::preauth::{PreAuthRequest, PreAuthResponse, PreAuthStore, DEFAULT_TTL_SECS};
+use crate::stub::{self, StubRequest, StubResponse};
+use crate::telemetry;
+
+/// Verifier key for production API keys
+pub const PRODUCTION_VERIFIER: &str = "default";
+/// Test verifier key for sandbox API keys, never trusted by the production paymaster
+pub const SANDBOX_VERIFIER: &str = "sandbox";
+
+/// Version of the `/sign` response layout
+/// Version 1 had no `version` or `paymaster_kind` and signalled a SimplePaymaster with an empty signature
+pub const RESPONSE_VERSION: u32 = 2;
+
+/// Paymaster contract the service sponsors for, which decides what `/sign` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymasterKind {
+    /// VerifierSignaturePaymaster: paymasterData is the verifier signature, validUntil and validAfter
+    #[default]
+    Verifying,
+    /// SimplePaymaster: sponsors any operation, paymasterData is empty and nothing is signed
+    Simple,
+}
 
 #[derive(Debug, Deserialize)]
 pub struct SponsorshipRequest {
+    /// Not needed when redeeming a pre-auth token
+    #[serde(default)]
     pub api_key: String,
+    /// Single-use token from /preauth, used instead of the API key
+    #[serde(default)]
+    pub preauth_token: Option<String>,
     pub user_operation: PackedUserOperation,
     pub valid_until: u64,
     pub valid_after: Option<u64>,
@@ -15,28 +49,83 @@ pub struct SponsorshipRequest {
 #[derive(Debug, Deserialize)]
 pub struct PackedUserOperation {
     pub sender: String,
+    #[serde(deserialize_with = "deserialize_u256")]
     pub nonce: U256,
     pub init_code: String,
     pub call_data: String,
     pub account_gas_limits: String,    // bytes32 packed
+    #[serde(deserialize_with = "deserialize_u256")]
     pub pre_verification_gas: U256,
     pub gas_fees: String,              // bytes32 packed
     pub paymaster_and_data: String,
 }
 
+// Accept quantities as decimal/0x-hex strings or plain integers regardless of wire format
+// (U256's own serde impl expects raw bytes for binary formats such as CBOR)
+fn deserialize_u256<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Quantity {
+        Text(String),
+        Number(u64),
+    }
+
+    match Quantity::deserialize(deserializer)? {
+        Quantity::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        Quantity::Number(number) => Ok(U256::from(number)),
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct SponsorshipResponse {
-    pub signature: String,
+    /// Always `RESPONSE_VERSION`
+    pub version: u32,
+    pub paymaster_kind: PaymasterKind,
+    /// Verifier signature, absent for a SimplePaymaster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
     pub valid_until: u64,
     pub valid_after: u64,
+    /// Hex paymasterData to append after the paymaster address and gas limits, empty for a SimplePaymaster
     pub paymaster_data: String,
+    /// Signed with the sandbox verifier key, not valid against the production paymaster
+    pub sandbox: bool,
+}
+
+/// Served by `GET /verifier`: the keys this service signs with, for comparing with the paymaster's `verifier()`
+#[derive(Debug, Serialize)]
+pub struct VerifierInfo {
+    pub chain_id: u64,
+    pub paymaster: String,
+    pub paymaster_kind: PaymasterKind,
+    /// Address of the production key, which the deployed paymaster's `verifier()` must return
+    pub verifier: Option<String>,
+    /// Address of the sandbox key, for the test paymaster sandbox API keys are signed for
+    pub sandbox_verifier: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum SignatureError {
     InvalidApiKey,
     InvalidTimestamp,
+    InvalidPreAuthToken,
+    InvalidSender,
+    /// init_code too short to hold a factory address
+    InvalidInitCode,
+    /// A UserOperation field that is not hex or does not fit its word, named as on the wire
+    InvalidUserOperation(&'static str),
+    /// getSenderAddress ran the factory but no account came out of it
+    DeploymentFailed,
     KeyManagerError(KeyManagerError),
+    UsageLedgerError(std::io::Error),
+    DepositStatusDisabled,
+    /// Predicting a deployment needs `rpc_url` for getSenderAddress
+    DeploymentPredictionDisabled,
+    DepositError(DepositError),
+    /// Only the leader signs when replicas coordinate
+    Standby,
+    /// A sandbox API key on a SimplePaymaster, which would pay from the production deposit
+    SandboxUnsupported,
 }
 
 impl std::fmt::Display for SignatureError {
@@ -44,7 +133,40 @@ impl std::fmt::Display for SignatureError {
         match self {
             SignatureError::InvalidApiKey => write!(f, "Invalid API key"),
             SignatureError::InvalidTimestamp => write!(f, "Invalid timestamp"),
+            SignatureError::InvalidPreAuthToken => write!(f, "Invalid, expired or already used pre-auth token"),
+            SignatureError::InvalidSender => write!(f, "Invalid sender address"),
+            SignatureError::InvalidInitCode => write!(f, "Invalid init_code, expected a factory address followed by its calldata"),
+            SignatureError::InvalidUserOperation(field) => write!(f, "Invalid UserOperation {}", field),
+            SignatureError::DeploymentFailed => write!(f, "The factory in init_code did not deploy an account"),
             SignatureError::KeyManagerError(e) => write!(f, "Key manager error: {}", e),
+            SignatureError::UsageLedgerError(e) => write!(f, "Usage ledger error: {}", e),
+            SignatureError::DepositStatusDisabled => write!(f, "EntryPoint reads are disabled, configure rpc_url"),
+            SignatureError::DeploymentPredictionDisabled => write!(f, "Deployment prediction is disabled, configure rpc_url"),
+            SignatureError::DepositError(e) => write!(f, "Could not read from the EntryPoint: {}", e),
+            SignatureError::Standby => write!(f, "This replica is a standby, signing is served by the leader"),
+            SignatureError::SandboxUnsupported => write!(f, "Sandbox API keys need a verifying paymaster, a SimplePaymaster sponsors from the production deposit"),
+        }
+    }
+}
+
+impl SignatureError {
+    /// Stable label the rejection is counted under in analytics
+    pub fn reason(&self) -> &'static str {
+        match self {
+            SignatureError::InvalidApiKey => "invalid_api_key",
+            SignatureError::InvalidTimestamp => "invalid_timestamp",
+            SignatureError::InvalidPreAuthToken => "invalid_preauth_token",
+            SignatureError::InvalidSender => "invalid_sender",
+            SignatureError::InvalidInitCode => "invalid_init_code",
+            SignatureError::InvalidUserOperation(_) => "invalid_user_operation",
+            SignatureError::DeploymentFailed => "deployment_failed",
+            SignatureError::KeyManagerError(_) => "key_manager",
+            SignatureError::UsageLedgerError(_) => "usage_ledger",
+            SignatureError::DepositStatusDisabled => "deposit_status_disabled",
+            SignatureError::DeploymentPredictionDisabled => "deployment_prediction_disabled",
+            SignatureError::DepositError(_) => "deposit_read",
+            SignatureError::Standby => "standby",
+            SignatureError::SandboxUnsupported => "sandbox_unsupported",
         }
     }
 }
@@ -60,9 +182,17 @@ impl From<KeyManagerError> for SignatureError {
 pub struct SignatureService {
     key_manager: Arc<KeyManager>,
     api_keys: HashMap<String, String>, // api_key -> client_name
+    sandbox_keys: HashSet<String>,
     chain_id: u64,
     paymaster_address: Vec<u8>,
-    is_simple_paymaster: bool,
+    kind: PaymasterKind,
+    preauth: PreAuthStore,
+    usage: UsageLedger,
+    invoicing: InvoiceSettings,
+    analytics: Analytics,
+    deposit_reader: Option<DepositReader>,
+    leadership: Option<Arc<Leadership>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl SignatureService {
@@ -71,53 +201,271 @@ impl SignatureService {
         api_keys: HashMap<String, String>, 
         chain_id: u64,
         paymaster_address: Vec<u8>,
-        is_simple_paymaster: bool,
+        kind: PaymasterKind,
     ) -> Self {
         Self {
             key_manager,
             api_keys,
+            sandbox_keys: HashSet::new(),
             chain_id,
             paymaster_address,
-            is_simple_paymaster,
+            kind,
+            preauth: PreAuthStore::new(),
+            usage: UsageLedger::new(),
+            invoicing: InvoiceSettings::default(),
+            analytics: Analytics::new(),
+            deposit_reader: None,
+            leadership: None,
+            clock: Arc::new(SystemClock),
         }
     }
     
+    /// Record sponsorships in `usage` and invoice them with `invoicing`
+    pub fn with_invoicing(mut self, usage: UsageLedger, invoicing: InvoiceSettings) -> Self {
+        self.usage = usage;
+        self.invoicing = invoicing;
+        self
+    }
+    
+    pub fn kind(&self) -> PaymasterKind {
+        self.kind
+    }
+    
+    /// Aggregate sponsorships and rejections into `analytics`
+    pub fn with_analytics(mut self, analytics: Analytics) -> Self {
+        self.analytics = analytics;
+        self
+    }
+    
+    pub fn analytics(&self) -> &Analytics {
+        &self.analytics
+    }
+    
+    pub fn usage(&self) -> &UsageLedger {
+        &self.usage
+    }
+    
+    pub fn invoicing(&self) -> &InvoiceSettings {
+        &self.invoicing
+    }
+    
+    /// Serve the paymaster's EntryPoint deposit and stake read through `reader`
+    pub fn with_deposit_reader(mut self, reader: DepositReader) -> Self {
+        self.deposit_reader = Some(reader);
+        self
+    }
+    
+    /// Deposit and stake backing the sponsorships this service signs
+    pub async fn paymaster_status(&self) -> Result<PaymasterStatus, SignatureError> {
+        let reader = self.deposit_reader.as_ref().ok_or(SignatureError::DepositStatusDisabled)?;
+        let deposit = reader.read(&self.paymaster_address).await.map_err(SignatureError::DepositError)?;
+        Ok(PaymasterStatus {
+            paymaster: format!("0x{}", hex::encode(&self.paymaster_address)),
+            chain_id: self.chain_id,
+            entry_point: reader.entry_point().to_string(),
+            paymaster_kind: self.kind,
+            deposit,
+        })
+    }
+    
+    /// Predict the account `request.init_code` deploys and check it is `request.sender`, without signing
+    pub async fn predict_deployment(&self, request: DeploymentRequest) -> Result<DeploymentPrediction, SignatureError> {
+        if !self.api_keys.contains_key(&request.api_key) {
+            return Err(SignatureError::InvalidApiKey);
+        }
+        let sender = self.decode_hex(&request.sender);
+        if sender.len() != 20 {
+            return Err(SignatureError::InvalidSender);
+        }
+        let init_code = self.decode_hex(&request.init_code);
+        if init_code.len() < 20 {
+            return Err(SignatureError::InvalidInitCode);
+        }
+
+        let reader = self.deposit_reader.as_ref().ok_or(SignatureError::DeploymentPredictionDisabled)?;
+        let predicted = reader.sender_address(&init_code).await.map_err(SignatureError::DepositError)?;
+        if predicted == [0u8; 20] {
+            return Err(SignatureError::DeploymentFailed);
+        }
+        Ok(DeploymentPrediction {
+            sender: format!("0x{}", hex::encode(&sender)),
+            predicted_sender: format!("0x{}", hex::encode(predicted)),
+            factory: format!("0x{}", hex::encode(&init_code[..20])),
+            matches: predicted[..] == sender[..],
+        })
+    }
+    
+    /// Paymaster fields to estimate gas with, nothing is signed or recorded
+    pub async fn paymaster_stub(&self, request: StubRequest) -> Result<StubResponse, SignatureError> {
+        let now = self.now();
+        let api_key = match &request.preauth_token {
+            // Tokens live in the leader's memory, where they are redeemed
+            Some(token) => {
+                self.ensure_leader()?;
+                self.preauth.check(token, &request.sender, now).ok_or(SignatureError::InvalidPreAuthToken)?
+            }
+            None => request.api_key,
+        };
+        if !self.api_keys.contains_key(&api_key) {
+            return Err(SignatureError::InvalidApiKey);
+        }
+        self.check_sandbox(&api_key)?;
+
+        Ok(StubResponse {
+            paymaster_kind: self.kind,
+            paymaster: format!("0x{}", hex::encode(&self.paymaster_address)),
+            paymaster_data: stub::stub_paymaster_data(self.kind, now),
+            paymaster_verification_gas_limit: stub::STUB_VERIFICATION_GAS_LIMIT,
+            paymaster_post_op_gas_limit: match self.kind {
+                PaymasterKind::Verifying => stub::STUB_POST_OP_GAS_LIMIT,
+                // SimplePaymaster returns no context, so its postOp is never called
+                PaymasterKind::Simple => 0,
+            },
+            is_final: self.kind == PaymasterKind::Simple,
+        })
+    }
+    
+    /// Sign only while `leadership` holds the leader lease
+    pub fn with_leadership(mut self, leadership: Arc<Leadership>) -> Self {
+        self.leadership = Some(leadership);
+        self
+    }
+    
+    pub fn role(&self) -> Role {
+        self.leadership.as_ref().map_or(Role::Standalone, |leadership| leadership.role())
+    }
+    
+    // Standbys refuse anything that records usage or state only the leader would see
+    fn ensure_leader(&self) -> Result<(), SignatureError> {
+        match self.role() {
+            Role::Standby => Err(SignatureError::Standby),
+            Role::Standalone | Role::Leader => Ok(()),
+        }
+    }
+    
+    /// Take the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Draw pre-auth tokens from `entropy`; outstanding tokens are dropped, so set it before serving
+    pub fn with_entropy(mut self, entropy: Box<dyn Entropy>) -> Self {
+        self.preauth = PreAuthStore::with_entropy(entropy);
+        self
+    }
+    
+    /// Current unix time by the service's clock
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+    
+    /// Put these API keys in sandbox mode
+    pub fn with_sandbox_keys(mut self, keys: impl IntoIterator<Item = String>) -> Self {
+        self.sandbox_keys = keys.into_iter().collect();
+        self
+    }
+
+    pub fn is_sandbox(&self, api_key: &str) -> bool {
+        self.sandbox_keys.contains(api_key)
+    }
+
+    // Whether `api_key` is in sandbox mode, refusing it where nothing but the production deposit can pay
+    fn check_sandbox(&self, api_key: &str) -> Result<bool, SignatureError> {
+        let sandbox = self.is_sandbox(api_key);
+        if sandbox && self.kind == PaymasterKind::Simple {
+            return Err(SignatureError::SandboxUnsupported);
+        }
+        Ok(sandbox)
+    }
+
     pub async fn sign_sponsorship(
         &self,
         request: SponsorshipRequest,
     ) -> Result<SponsorshipResponse, SignatureError> {
-        // 1. Check API key
-        println!("🔍 DEBUG: API key validation:");
-        println!("  Received API key: '{}'", request.api_key);
-        println!("  Configured API keys: {:?}", self.api_keys.keys().collect::<Vec<_>>());
+        self.ensure_leader()?;
         
-        if !self.api_keys.contains_key(&request.api_key) {
-            println!("❌ API key validation failed!");
-            return Err(SignatureError::InvalidApiKey);
+        // Rejections are counted against the tenant of the API key when it is a known one
+        let tenant = self.api_keys.get(&request.api_key).cloned();
+        let sandbox = self.is_sandbox(&request.api_key);
+        let result = self.sign(request).await;
+        match &result {
+            Ok(response) => telemetry::record_outcome("signed", response.sandbox),
+            Err(e) => {
+                telemetry::record_outcome(e.reason(), sandbox);
+                let now = self.now();
+                if let Err(io) = self.analytics.record_rejection(tenant.as_deref().unwrap_or(UNKNOWN_TENANT), e.reason(), now) {
+                    tracing::warn!("Could not record analytics: {}", io);
+                }
+            }
         }
-        println!("✅ API key validation passed");
-
-        // 2. Validate timestamp
-        if request.valid_until <= chrono::Utc::now().timestamp() as u64 {
+        result
+    }
+    
+    // Policy: whether the request may be sponsored at all, returning the API key it is billed to
+    fn authorize(&self, request: &SponsorshipRequest, now: u64) -> Result<String, SignatureError> {
+        // 1. Validate timestamp (before authenticating, so a bad request never burns a pre-auth token)
+        if request.valid_until <= now {
             return Err(SignatureError::InvalidTimestamp);
         }
+
+        // 2. Check API key, or redeem a pre-auth token bound to this sender
+        if let Some(token) = &request.preauth_token {
+            let api_key = self
+                .preauth
+                .redeem(token, &request.user_operation.sender, now)
+                .ok_or(SignatureError::InvalidPreAuthToken)?;
+            // The issuing key may have been revoked since
+            if !self.api_keys.contains_key(&api_key) {
+                return Err(SignatureError::InvalidApiKey);
+            }
+            println!("✅ Pre-auth token redeemed for {}", request.user_operation.sender);
+            Ok(api_key)
+        } else {
+            println!("🔍 DEBUG: API key validation:");
+            println!("  Received API key: '{}'", request.api_key);
+            println!("  Configured API keys: {:?}", self.api_keys.keys().collect::<Vec<_>>());
+            
+            if !self.api_keys.contains_key(&request.api_key) {
+                println!("❌ API key validation failed!");
+                return Err(SignatureError::InvalidApiKey);
+            }
+            println!("✅ API key validation passed");
+            Ok(request.api_key.clone())
+        }
+    }
+    
+    async fn sign(&self, request: SponsorshipRequest) -> Result<SponsorshipResponse, SignatureError> {
+        let now = self.now();
+        // A malformed operation is refused before a pre-auth token is redeemed, so the client can retry with it
+        check_user_operation(&request.user_operation)?;
+        let api_key = telemetry::stage_sync(telemetry::STAGE_POLICY, || self.authorize(&request, now))?;
+        
+        let sandbox = self.check_sandbox(&api_key)?;
+        if sandbox {
+            println!("🧪 Sandbox API key - signing with the test verifier, not the production deposit");
+        }
+        // Sandbox signatures cannot spend the deposit, they are recorded but never invoiced
+        let usage = UsageRecord {
+            sandbox,
+            ..UsageRecord::new(&self.api_keys[&api_key], &request.user_operation, now, request.valid_until)
+        };
         
         let valid_after = request.valid_after.unwrap_or(0);
         
-        // Check if this is a simple paymaster (no signatures needed)
-        if self.is_simple_paymaster {
-            println!("🔧 SimplePaymaster detected - no signature needed");
-            
-            // For SimplePaymaster, return empty data
-            let paymaster_data: Vec<u8> = Vec::new(); // Empty data for SimplePaymaster
-            
-            println!("🔧 SimplePaymaster data: empty (0x)");
+        // A SimplePaymaster checks nothing, so there is nothing to sign
+        if self.kind == PaymasterKind::Simple {
+            println!("🔧 SimplePaymaster - no signature needed, paymaster data is empty");
+            self.record_usage(usage)?;
             
             return Ok(SponsorshipResponse {
-                signature: "0x".to_string(), // No signature needed
+                version: RESPONSE_VERSION,
+                paymaster_kind: PaymasterKind::Simple,
+                signature: None,
                 valid_until: request.valid_until,
                 valid_after,
-                paymaster_data: "0x".to_string(), // Empty data
+                paymaster_data: String::new(),
+                sandbox,
             });
         }
         
@@ -143,9 +491,9 @@ impl SignatureService {
         let eip191_message = self.create_eip191_message(&paymaster_hash);
         println!("🔍 DEBUG: EIP-191 message: {}", hex::encode(&eip191_message));
         
-        // 5. Sign with default verifier key
-        let signature = self.key_manager
-            .sign_eip191_message("default", &eip191_message)
+        // 5. Sign with the production verifier key, or the test key for sandbox API keys
+        let verifier = if sandbox { SANDBOX_VERIFIER } else { PRODUCTION_VERIFIER };
+        let signature = telemetry::stage(telemetry::STAGE_SIGN, self.key_manager.sign_eip191_message(verifier, &eip191_message))
             .await?;
         println!("🔍 DEBUG: Generated signature:");
         println!("  length: {}", signature.len());
@@ -156,12 +504,56 @@ impl SignatureService {
         println!("🔍 DEBUG: Final paymaster data:");
         println!("  length: {}", paymaster_data.len());
         println!("  hex: {}", hex::encode(&paymaster_data));
+        self.record_usage(usage)?;
         
         Ok(SponsorshipResponse {
-            signature: hex::encode(&signature),
+            version: RESPONSE_VERSION,
+            paymaster_kind: PaymasterKind::Verifying,
+            signature: Some(hex::encode(&signature)),
             valid_until: request.valid_until,
             valid_after,
             paymaster_data: hex::encode(&paymaster_data),
+            sandbox,
+        })
+    }
+    
+    // A sponsorship that cannot be billed is not handed out, one missing from analytics only logs a warning
+    fn record_usage(&self, record: UsageRecord) -> Result<(), SignatureError> {
+        telemetry::stage_sync(telemetry::STAGE_STORAGE, || {
+            // Analytics count what the production deposit is committed to
+            let production = (!record.sandbox).then(|| (record.tenant.clone(), record.max_gas_cost, record.timestamp));
+            self.usage.record(record).map_err(SignatureError::UsageLedgerError)?;
+            if let Some((tenant, max_gas_cost, timestamp)) = production {
+                if let Err(e) = self.analytics.record_sponsored(&tenant, max_gas_cost, timestamp) {
+                    tracing::warn!("Could not record analytics: {}", e);
+                }
+            }
+            Ok(())
+        })
+    }
+    
+    /// Issue a short-lived token that sponsors a single operation from one sender
+    pub async fn issue_preauth(&self, request: PreAuthRequest) -> Result<PreAuthResponse, SignatureError> {
+        // Tokens live in the leader's memory, where they are redeemed
+        self.ensure_leader()?;
+        if !self.api_keys.contains_key(&request.api_key) {
+            return Err(SignatureError::InvalidApiKey);
+        }
+        // Refused here rather than when the token is redeemed, where it would be burnt
+        self.check_sandbox(&request.api_key)?;
+        let sender = request.sender.strip_prefix("0x").unwrap_or(&request.sender);
+        if sender.len() != 40 || hex::decode(sender).is_err() {
+            return Err(SignatureError::InvalidSender);
+        }
+
+        let now = self.now();
+        let ttl_secs = request.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+        let (token, expires_at) = self.preauth.issue(&request.api_key, &request.sender, ttl_secs, now);
+
+        Ok(PreAuthResponse {
+            token,
+            sender: request.sender,
+            expires_at,
         })
     }
     
@@ -271,7 +663,7 @@ impl SignatureService {
         println!("  hex: {}", hex::encode(&encoded));
         
         let hash = Keccak256::digest(&encoded);
-        println!("🔍 DEBUG: Final paymaster hash: {}", hex::encode(&hash));
+        println!("🔍 DEBUG: Final paymaster hash: {}", hex::encode(hash));
         hash.to_vec()
     }
     
@@ -297,24 +689,56 @@ impl SignatureService {
         data                                                  // Total: 81 bytes
     }
     
-    // Helper to decode hex strings
+    // Helper to decode hex strings, empty for malformed ones (sponsored operations are checked beforehand)
     fn decode_hex(&self, hex_str: &str) -> Vec<u8> {
         let hex_clean = hex_str.strip_prefix("0x").unwrap_or(hex_str);
         hex::decode(hex_clean).unwrap_or_default()
     }
     
+    pub async fn verifier_info(&self) -> VerifierInfo {
+        let mut addresses = self.key_manager.verifier_addresses().await;
+        VerifierInfo {
+            chain_id: self.chain_id,
+            paymaster: format!("0x{}", hex::encode(&self.paymaster_address)),
+            paymaster_kind: self.kind,
+            verifier: addresses.remove(PRODUCTION_VERIFIER),
+            sandbox_verifier: addresses.remove(SANDBOX_VERIFIER),
+        }
+    }
+    
     pub async fn get_metrics(&self) -> Metrics {
         Metrics {
             verifier_count: self.key_manager.get_verifier_count().await,
             service_status: "healthy".to_string(),
+            role: self.role(),
+        }
+    }
+}
+
+/// Reject an operation whose hex fields `pack_for_paymaster` would read as empty or could not fit in a word,
+/// so the service never signs a hash of something other than what was sent
+fn check_user_operation(user_op: &PackedUserOperation) -> Result<(), SignatureError> {
+    fn decode(field: &'static str, value: &str, max_len: usize) -> Result<usize, SignatureError> {
+        match hex::decode(value.strip_prefix("0x").unwrap_or(value)) {
+            Ok(bytes) if bytes.len() <= max_len => Ok(bytes.len()),
+            _ => Err(SignatureError::InvalidUserOperation(field)),
         }
     }
+    if decode("sender", &user_op.sender, 20).ok() != Some(20) {
+        return Err(SignatureError::InvalidSender);
+    }
+    decode("init_code", &user_op.init_code, usize::MAX)?;
+    decode("call_data", &user_op.call_data, usize::MAX)?;
+    decode("account_gas_limits", &user_op.account_gas_limits, 32)?;
+    decode("gas_fees", &user_op.gas_fees, 32)?;
+    Ok(())
 }
 
 #[derive(Debug, Serialize)]
 pub struct Metrics {
     pub verifier_count: usize,
     pub service_status: String,
+    pub role: Role,
 }
 
 #[cfg(test)]
@@ -330,11 +754,32 @@ mod tests {
         crate::Config {
             verifier_keys,
             api_keys: HashMap::new(),
+            sandbox_api_keys: Vec::new(),
             server_port: 3000,
+            listen_socket: None,
+            listen_socket_mode: None,
             log_level: "info".to_string(),
             chain_id: Some(1),
             paymaster_address: Some("0x0000000000000000000000000000000000000000".to_string()),
             is_simple_paymaster: Some(false),
+            paymaster_kind: None,
+            usage_ledger: None,
+            analytics_file: None,
+            admin_api_key: None,
+            invoice_markup_bps: 0,
+            rpc_url: None,
+            entry_point: None,
+            coordination_redis_url: None,
+            coordination_lock_key: None,
+            coordination_lease_secs: None,
+            reconcile_interval_secs: None,
+            reconcile_from_block: None,
+            fixed_time: None,
+            rng_seed: None,
+            otlp_endpoint: None,
+            otlp_headers: HashMap::new(),
+            otel_service_name: None,
+            otel_sample_ratio: None,
         }
     }
 
@@ -347,6 +792,7 @@ mod tests {
     fn create_test_request() -> SponsorshipRequest {
         SponsorshipRequest {
             api_key: "test_key_123".to_string(),
+            preauth_token: None,
             user_operation: PackedUserOperation {
                 sender: "0x1234567890123456789012345678901234567890".to_string(),
                 nonce: U256::from(1),
@@ -372,7 +818,7 @@ mod tests {
             api_keys, 
             1, // chain_id
             vec![0u8; 20], // paymaster_address
-            false, // is_simple_paymaster
+            PaymasterKind::Verifying,
         );
         
         let request = create_test_request();
@@ -382,17 +828,59 @@ mod tests {
         let response = result.unwrap();
         
         // Check response structure
-        assert!(!response.signature.is_empty());
+        assert_eq!(response.version, RESPONSE_VERSION);
+        assert_eq!(response.paymaster_kind, PaymasterKind::Verifying);
         assert!(!response.paymaster_data.is_empty());
         assert!(response.valid_until > 0);
         
         // Signature should be hex encoded (130 chars for 65 bytes)
-        assert_eq!(response.signature.len(), 130);
+        assert_eq!(response.signature.as_deref().map(str::len), Some(130));
         
         // Paymaster data from service response should contain signature + validUntil + validAfter (65 + 8 + 8 bytes = 162 hex chars)
         assert_eq!(response.paymaster_data.len(), 162);
     }
 
+    #[tokio::test]
+    async fn test_canonical_vectors() {
+        use aa_test_vectors::{unprefixed, SPONSORSHIPS};
+        
+        for vector in SPONSORSHIPS {
+            let mut config = create_test_config();
+            config.verifier_keys.insert(PRODUCTION_VERIFIER.to_string(), vector.verifier_key.to_string());
+            let service = SignatureService::new(
+                Arc::new(KeyManager::new(&config)),
+                create_test_api_keys(),
+                vector.chain_id,
+                hex::decode(unprefixed(vector.paymaster)).unwrap(),
+                PaymasterKind::Verifying,
+            );
+            let op = vector.user_operation;
+            let request = SponsorshipRequest {
+                user_operation: PackedUserOperation {
+                    sender: op.sender.to_string(),
+                    nonce: op.nonce.parse().unwrap(),
+                    init_code: op.init_code.to_string(),
+                    call_data: op.call_data.to_string(),
+                    account_gas_limits: op.account_gas_limits.to_string(),
+                    pre_verification_gas: U256::from(op.pre_verification_gas),
+                    gas_fees: op.gas_fees.to_string(),
+                    paymaster_and_data: "0x".to_string(),
+                },
+                valid_until: vector.valid_until,
+                valid_after: Some(vector.valid_after),
+                ..create_test_request()
+            };
+            
+            let paymaster_hash = service.create_paymaster_hash(&request.user_operation, vector.valid_until, vector.valid_after);
+            assert_eq!(hex::encode(&paymaster_hash), unprefixed(vector.paymaster_hash), "{}", vector.name);
+            assert_eq!(hex::encode(service.create_eip191_message(&paymaster_hash)), unprefixed(vector.eip191_digest), "{}", vector.name);
+            
+            let response = service.sign_sponsorship(request).await.unwrap();
+            assert_eq!(response.signature.as_deref(), Some(unprefixed(vector.signature)), "{}", vector.name);
+            assert_eq!(response.paymaster_data, unprefixed(vector.paymaster_data), "{}", vector.name);
+        }
+    }
+
     #[tokio::test]
     async fn test_invalid_api_key() {
         let config = create_test_config();
@@ -403,7 +891,7 @@ mod tests {
             api_keys, 
             1, // chain_id
             vec![0u8; 20], // paymaster_address
-            false, // is_simple_paymaster
+            PaymasterKind::Verifying,
         );
         
         let mut request = create_test_request();
@@ -425,7 +913,7 @@ mod tests {
             api_keys, 
             1, // chain_id
             vec![0u8; 20], // paymaster_address
-            false, // is_simple_paymaster
+            PaymasterKind::Verifying,
         );
         
         let mut request = create_test_request();
@@ -447,7 +935,7 @@ mod tests {
             api_keys, 
             1, // chain_id
             vec![0u8; 20], // paymaster_address
-            true, // is_simple_paymaster
+            PaymasterKind::Simple,
         );
         
         let request = create_test_request();
@@ -457,9 +945,371 @@ mod tests {
         let response = result.unwrap();
         
         // Check response structure for simple paymaster
-        assert_eq!(response.signature, "0x"); // No signature needed
-        assert_eq!(response.paymaster_data, "0x"); // Empty data
+        assert_eq!(response.paymaster_kind, PaymasterKind::Simple);
+        assert_eq!(response.signature, None); // No signature needed
+        assert_eq!(response.paymaster_data, ""); // Empty data
         assert!(response.valid_until > 0);
+        
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["version"], RESPONSE_VERSION);
+        assert_eq!(json["paymaster_kind"], "simple");
+        assert!(json.get("signature").is_none());
     }
-}
 
+    #[tokio::test]
+    async fn test_analytics_count_sponsorships_and_rejections() {
+        let config = create_test_config();
+        let service = SignatureService::new(Arc::new(KeyManager::new(&config)), create_test_api_keys(), 1, vec![0u8; 20], PaymasterKind::Verifying);
+        
+        service.sign_sponsorship(create_test_request()).await.unwrap();
+        let mut expired = create_test_request();
+        expired.valid_until = 1;
+        service.sign_sponsorship(expired).await.unwrap_err();
+        let mut invalid = create_test_request();
+        invalid.api_key = "invalid_key".to_string();
+        service.sign_sponsorship(invalid).await.unwrap_err();
+        
+        let buckets = service.analytics().query(0, u64::MAX, None);
+        let client = buckets.iter().find(|bucket| bucket.tenant == "Test Client").unwrap();
+        assert_eq!(client.sponsored, 1);
+        assert_eq!(client.committed_wei, crate::invoice::max_gas_cost(&create_test_request().user_operation));
+        assert_eq!(client.rejections["invalid_timestamp"], 1);
+        let unknown = buckets.iter().find(|bucket| bucket.tenant == UNKNOWN_TENANT).unwrap();
+        assert_eq!(unknown.rejections["invalid_api_key"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_standby_refuses_to_sign() {
+        use crate::leader::LeaseStore;
+        use std::time::Duration;
+        
+        let config = create_test_config();
+        let store = LeaseStore::memory();
+        let service = |leadership: &Arc<Leadership>| {
+            SignatureService::new(Arc::new(KeyManager::new(&config)), create_test_api_keys(), 1, vec![0u8; 20], PaymasterKind::Verifying)
+                .with_leadership(leadership.clone())
+        };
+        let leader = Arc::new(Leadership::new(store.clone(), Duration::from_secs(10)));
+        let standby = Arc::new(Leadership::new(store, Duration::from_secs(10)));
+        assert!(leader.renew().await);
+        assert!(!standby.renew().await);
+        
+        assert!(service(&leader).sign_sponsorship(create_test_request()).await.is_ok());
+        let standby = service(&standby);
+        assert!(matches!(standby.sign_sponsorship(create_test_request()).await.unwrap_err(), SignatureError::Standby));
+        assert_eq!(standby.get_metrics().await.role, Role::Standby);
+        assert!(standby.usage().records_for_month(&chrono::Utc::now().format("%Y-%m").to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_paymaster_kind_config() {
+        let mut config = create_test_config();
+        assert_eq!(config.paymaster_kind(), PaymasterKind::Verifying);
+        config.is_simple_paymaster = Some(true);
+        assert_eq!(config.paymaster_kind(), PaymasterKind::Simple);
+        config.paymaster_kind = Some(PaymasterKind::Verifying);
+        assert_eq!(config.paymaster_kind(), PaymasterKind::Verifying);
+        assert_eq!(serde_json::from_str::<PaymasterKind>("\"simple\"").unwrap(), PaymasterKind::Simple);
+    }
+
+    #[tokio::test]
+    async fn test_preauth_token_replaces_api_key_once() {
+        let config = create_test_config();
+        let key_manager = Arc::new(KeyManager::new(&config));
+        let signature_service = SignatureService::new(
+            key_manager, 
+            create_test_api_keys(), 
+            1, // chain_id
+            vec![0u8; 20], // paymaster_address
+            PaymasterKind::Verifying,
+        );
+        
+        let grant = signature_service
+            .issue_preauth(PreAuthRequest {
+                api_key: "test_key_123".to_string(),
+                sender: "0x1234567890123456789012345678901234567890".to_string(),
+                ttl_secs: Some(60),
+            })
+            .await
+            .unwrap();
+        
+        let redeem = || {
+            let mut request = create_test_request();
+            request.api_key = String::new();
+            request.preauth_token = Some(grant.token.clone());
+            request
+        };
+        // A malformed operation does not spend the token
+        let mut malformed = redeem();
+        malformed.user_operation.call_data = "0xzz".to_string();
+        assert!(matches!(
+            signature_service.sign_sponsorship(malformed).await.unwrap_err(),
+            SignatureError::InvalidUserOperation("call_data")
+        ));
+        assert!(signature_service.sign_sponsorship(redeem()).await.is_ok());
+        assert!(matches!(
+            signature_service.sign_sponsorship(redeem()).await.unwrap_err(),
+            SignatureError::InvalidPreAuthToken
+        ));
+        
+        let invalid_key = signature_service
+            .issue_preauth(PreAuthRequest {
+                api_key: "invalid_key".to_string(),
+                sender: "0x1234567890123456789012345678901234567890".to_string(),
+                ttl_secs: None,
+            })
+            .await;
+        assert!(matches!(invalid_key.unwrap_err(), SignatureError::InvalidApiKey));
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_key_signs_with_test_verifier() {
+        let mut config = create_test_config();
+        let mut api_keys = create_test_api_keys();
+        api_keys.insert("sandbox_key".to_string(), "Sandbox Client".to_string());
+        let sandbox_service = |config: &crate::Config, api_keys: HashMap<String, String>| {
+            SignatureService::new(Arc::new(KeyManager::new(config)), api_keys, 1, vec![0u8; 20], PaymasterKind::Verifying)
+                .with_sandbox_keys(["sandbox_key".to_string()])
+        };
+        
+        // Without a test verifier the production key is never used as a fallback
+        let mut request = create_test_request();
+        request.api_key = "sandbox_key".to_string();
+        let result = sandbox_service(&config, api_keys.clone()).sign_sponsorship(request).await;
+        assert!(matches!(result.unwrap_err(), SignatureError::KeyManagerError(KeyManagerError::VerifierNotFound)));
+        
+        config.verifier_keys.insert(
+            SANDBOX_VERIFIER.to_string(),
+            "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+        );
+        let service = sandbox_service(&config, api_keys);
+        
+        let production = service.sign_sponsorship(create_test_request()).await.unwrap();
+        let mut request = create_test_request();
+        request.valid_until = production.valid_until;
+        request.api_key = "sandbox_key".to_string();
+        let sandbox = service.sign_sponsorship(request).await.unwrap();
+        
+        assert!(!production.sandbox);
+        assert!(sandbox.sandbox);
+        assert_ne!(production.signature, sandbox.signature);
+        
+        // Both are recorded, only the production one is invoiced or counted against the deposit
+        let records = service.usage().records();
+        assert_eq!(records.iter().map(|r| (r.tenant.as_str(), r.sandbox)).collect::<Vec<_>>(), [("Test Client", false), ("Sandbox Client", true)]);
+        let invoice = crate::invoice::Invoice::build(&records[0].month(), &records, 0);
+        assert_eq!(invoice.statements.len(), 1);
+        assert_eq!(invoice.statements[0].tenant, "Test Client");
+        assert!(crate::reconcile::summarize(&records).iter().all(|s| s.tenant != "Sandbox Client"));
+        assert!(service.analytics().query(0, u64::MAX, Some("Sandbox Client")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_simple_paymaster_refuses_sandbox_keys() {
+        let config = create_test_config();
+        let mut api_keys = create_test_api_keys();
+        api_keys.insert("sandbox_key".to_string(), "Sandbox Client".to_string());
+        let service = SignatureService::new(Arc::new(KeyManager::new(&config)), api_keys, 1, vec![0u8; 20], PaymasterKind::Simple)
+            .with_sandbox_keys(["sandbox_key".to_string()]);
+
+        // Empty paymaster data would be sponsored from the production deposit
+        let mut request = create_test_request();
+        request.api_key = "sandbox_key".to_string();
+        let result = service.sign_sponsorship(request).await;
+        assert!(matches!(result.unwrap_err(), SignatureError::SandboxUnsupported));
+        assert!(service.usage().records().is_empty());
+
+        let stub = service
+            .paymaster_stub(StubRequest { api_key: "sandbox_key".to_string(), preauth_token: None, sender: create_test_request().user_operation.sender })
+            .await;
+        assert!(matches!(stub.unwrap_err(), SignatureError::SandboxUnsupported));
+        let preauth = service
+            .issue_preauth(PreAuthRequest { api_key: "sandbox_key".to_string(), sender: create_test_request().user_operation.sender, ttl_secs: None })
+            .await;
+        assert!(matches!(preauth.unwrap_err(), SignatureError::SandboxUnsupported));
+
+        // Production keys are sponsored as before
+        assert!(service.sign_sponsorship(create_test_request()).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_malformed_user_operation_is_rejected() {
+        let service = SignatureService::new(
+            Arc::new(KeyManager::new(&create_test_config())),
+            create_test_api_keys(),
+            1,
+            vec![0u8; 20],
+            PaymasterKind::Verifying,
+        );
+        
+        // Malformed hex used to be hashed as empty bytes, and a gas word over 32 bytes panicked
+        let mut request = create_test_request();
+        request.user_operation.gas_fees = "0xzz".to_string();
+        let result = service.sign_sponsorship(request).await;
+        assert!(matches!(result.unwrap_err(), SignatureError::InvalidUserOperation("gas_fees")));
+        
+        let mut request = create_test_request();
+        request.user_operation.account_gas_limits = format!("0x{}", "01".repeat(33));
+        let result = service.sign_sponsorship(request).await;
+        assert!(matches!(result.unwrap_err(), SignatureError::InvalidUserOperation("account_gas_limits")));
+        
+        let mut request = create_test_request();
+        request.user_operation.sender = format!("0x{}", "12".repeat(21));
+        let result = service.sign_sponsorship(request).await;
+        assert!(matches!(result.unwrap_err(), SignatureError::InvalidSender));
+    }
+    
+    // Properties of the packing functions, checked against alloy's ABI encoder over arbitrary operations
+    mod properties {
+        use super::*;
+        use alloy_primitives::{keccak256, Address, Bytes, B256};
+        use alloy_sol_types::SolValue;
+        use proptest::prelude::*;
+        
+        fn service(chain_id: u64, paymaster: [u8; 20]) -> SignatureService {
+            SignatureService::new(
+                Arc::new(KeyManager::new(&create_test_config())),
+                create_test_api_keys(),
+                chain_id,
+                paymaster.to_vec(),
+                PaymasterKind::Verifying,
+            )
+        }
+        
+        fn hex_field(bytes: &[u8], prefixed: bool) -> String {
+            format!("{}{}", if prefixed { "0x" } else { "" }, hex::encode(bytes))
+        }
+        
+        #[derive(Debug, Clone)]
+        struct Op {
+            sender: [u8; 20],
+            nonce: U256,
+            init_code: Vec<u8>,
+            call_data: Vec<u8>,
+            account_gas_limits: Vec<u8>,
+            pre_verification_gas: U256,
+            gas_fees: Vec<u8>,
+            prefixed: bool,
+        }
+        
+        impl Op {
+            fn packed(&self) -> PackedUserOperation {
+                PackedUserOperation {
+                    sender: hex_field(&self.sender, self.prefixed),
+                    nonce: self.nonce,
+                    init_code: hex_field(&self.init_code, self.prefixed),
+                    call_data: hex_field(&self.call_data, self.prefixed),
+                    account_gas_limits: hex_field(&self.account_gas_limits, self.prefixed),
+                    pre_verification_gas: self.pre_verification_gas,
+                    gas_fees: hex_field(&self.gas_fees, self.prefixed),
+                    paymaster_and_data: "0x".to_string(),
+                }
+            }
+            
+            // abi.encode of VerifierSignaturePaymaster._packForPaymaster's fields
+            fn abi_encoded(&self) -> Vec<u8> {
+                (
+                    Address::from(self.sender),
+                    self.nonce,
+                    keccak256(&self.init_code),
+                    keccak256(&self.call_data),
+                    B256::left_padding_from(&self.account_gas_limits),
+                    self.pre_verification_gas,
+                    B256::left_padding_from(&self.gas_fees),
+                )
+                    .abi_encode_params()
+            }
+        }
+        
+        fn word() -> impl Strategy<Value = U256> {
+            any::<[u8; 32]>().prop_map(U256::from_be_bytes)
+        }
+        
+        fn op() -> impl Strategy<Value = Op> {
+            (
+                any::<[u8; 20]>(),
+                word(),
+                proptest::collection::vec(any::<u8>(), 0..200),
+                proptest::collection::vec(any::<u8>(), 0..200),
+                proptest::collection::vec(any::<u8>(), 0..=32),
+                word(),
+                proptest::collection::vec(any::<u8>(), 0..=32),
+                any::<bool>(),
+            )
+                .prop_map(|(sender, nonce, init_code, call_data, account_gas_limits, pre_verification_gas, gas_fees, prefixed)| Op {
+                    sender,
+                    nonce,
+                    init_code,
+                    call_data,
+                    account_gas_limits,
+                    pre_verification_gas,
+                    gas_fees,
+                    prefixed,
+                })
+        }
+        
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+            
+            #[test]
+            fn pack_for_paymaster_matches_abi_encode(op in op()) {
+                let service = service(1, [0u8; 20]);
+                prop_assert!(check_user_operation(&op.packed()).is_ok());
+                let packed = service.pack_for_paymaster(&op.packed());
+                prop_assert_eq!(packed.len(), 7 * 32);
+                prop_assert_eq!(packed, op.abi_encoded());
+            }
+            
+            #[test]
+            fn paymaster_hash_matches_abi_encode(
+                op in op(),
+                chain_id in any::<u64>(),
+                paymaster in any::<[u8; 20]>(),
+                valid_until in any::<u64>(),
+                valid_after in any::<u64>(),
+            ) {
+                let hash = service(chain_id, paymaster).create_paymaster_hash(&op.packed(), valid_until, valid_after);
+                // abi.encode(_packForPaymaster(u), block.chainid, address(this), validUntil, validAfter)
+                let encoded = (Bytes::from(op.abi_encoded()), U256::from(chain_id), Address::from(paymaster), valid_until, valid_after)
+                    .abi_encode_params();
+                prop_assert_eq!(hash, keccak256(encoded).to_vec());
+            }
+            
+            #[test]
+            fn paymaster_data_matches_encode_packed(
+                signature in proptest::collection::vec(any::<u8>(), 65),
+                valid_until in any::<u64>(),
+                valid_after in any::<u64>(),
+            ) {
+                let data = service(1, [0u8; 20]).encode_paymaster_data(&signature, valid_until, valid_after);
+                prop_assert_eq!(data.len(), 81);
+                prop_assert_eq!(data, (Bytes::from(signature), valid_until, valid_after).abi_encode_packed());
+            }
+            
+            // Fields straight from the request body: whatever they hold, the check decides without panicking
+            // and whatever it lets through packs into seven words
+            #[test]
+            fn arbitrary_hex_fields_never_panic(
+                sender in "(0x)?[0-9a-fA-F]{0,44}|.{0,44}",
+                init_code in "(0x)?[0-9a-fA-F]{0,80}|.{0,80}",
+                call_data in "(0x)?[0-9a-fA-F]{0,80}|.{0,80}",
+                account_gas_limits in "(0x)?[0-9a-fA-F]{0,70}|.{0,70}",
+                gas_fees in "(0x)?[0-9a-fA-F]{0,70}|.{0,70}",
+            ) {
+                let user_op = PackedUserOperation {
+                    sender,
+                    nonce: U256::ZERO,
+                    init_code,
+                    call_data,
+                    account_gas_limits,
+                    pre_verification_gas: U256::ZERO,
+                    gas_fees,
+                    paymaster_and_data: "0x".to_string(),
+                };
+                if check_user_operation(&user_op).is_ok() {
+                    prop_assert_eq!(service(1, [0u8; 20]).pack_for_paymaster(&user_op).len(), 7 * 32);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Stub paymaster data for gas estimation, in the shape of ERC-7677's pm_getPaymasterStubData
+// A sponsored operation estimated without its paymaster fields leaves out the paymaster's validation
+// and postOp, and the bundler rejects it with AA33 (out of gas) once the real signature is attached.
+// The stub is as long as the real paymasterData, and its signature recovers (to an address that is not
+// the verifier), so validation runs to the end and returns SIG_VALIDATION_FAILED instead of reverting
+
+use serde::{Deserialize, Serialize};
+
+use crate::signature_service::PaymasterKind;
+
+/// Gas VerifierSignaturePaymaster's validation is given: the pmHash over the packed operation, ecrecover
+/// and the postOp context, with headroom
+pub const STUB_VERIFICATION_GAS_LIMIT: u64 = 60_000;
+
+/// Gas its postOp is given to decode the context and emit GasSponsored
+pub const STUB_POST_OP_GAS_LIMIT: u64 = 20_000;
+
+/// Signature of a throwaway key over an unrelated digest: 65 bytes, recoverable, never the verifier's
+pub const STUB_SIGNATURE: &str = "9983a724ea5d466ac4c6aa9dca8444b4b2ec8626708a7ac0bbdc1d58417ed9190a10a54a69f6b79208c67e072a1a91e02dc2fbb52f840ba84f3d7df8811627271b";
+
+/// How far ahead the stub's validUntil lies, so it has the byte mix of a real one
+const STUB_VALIDITY_SECS: u64 = 3600;
+
+#[derive(Debug, Deserialize)]
+pub struct StubRequest {
+    /// Not needed with a pre-auth token
+    #[serde(default)]
+    pub api_key: String,
+    /// Checked but not redeemed, the token is spent by `/sign`
+    #[serde(default)]
+    pub preauth_token: Option<String>,
+    pub sender: String,
+}
+
+/// Served by `POST /stub`
+#[derive(Debug, Serialize)]
+pub struct StubResponse {
+    pub paymaster_kind: PaymasterKind,
+    pub paymaster: String,
+    /// Hex paymasterData to estimate with
+    pub paymaster_data: String,
+    pub paymaster_verification_gas_limit: u64,
+    pub paymaster_post_op_gas_limit: u64,
+    /// The stub is already the data `/sign` would return, as for a SimplePaymaster
+    pub is_final: bool,
+}
+
+/// Stub of `kind`'s paymasterData, hex encoded
+pub fn stub_paymaster_data(kind: PaymasterKind, now: u64) -> String {
+    match kind {
+        PaymasterKind::Verifying => {
+            let valid_until = now + STUB_VALIDITY_SECS;
+            format!("{}{}{}", STUB_SIGNATURE, hex::encode(valid_until.to_be_bytes()), hex::encode(0u64.to_be_bytes()))
+        }
+        PaymasterKind::Simple => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stub_is_as_long_as_signed_data() {
+        // 65-byte signature, validUntil and validAfter
+        let data = hex::decode(stub_paymaster_data(PaymasterKind::Verifying, 1_700_000_000)).unwrap();
+        assert_eq!(data.len(), 65 + 8 + 8);
+        assert_eq!(u64::from_be_bytes(data[65..73].try_into().unwrap()), 1_700_000_000 + STUB_VALIDITY_SECS);
+        assert_eq!(stub_paymaster_data(PaymasterKind::Simple, 1_700_000_000), "");
+    }
+
+    #[test]
+    fn test_stub_signature_recovers() {
+        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+        use sha3::{Digest, Keccak256};
+
+        // ECDSA.recover reverts on signatures that do not recover, which would fail estimation outright
+        let signature = hex::decode(STUB_SIGNATURE).unwrap();
+        let recovery_id = RecoveryId::from_i32(signature[64] as i32 - 27).unwrap();
+        let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id).unwrap();
+        let digest = Keccak256::digest(b"any digest");
+        let message = secp256k1::Message::from_digest_slice(&digest).unwrap();
+        assert!(secp256k1::Secp256k1::new().recover_ecdsa(&message, &signature).is_ok());
+    }
+}
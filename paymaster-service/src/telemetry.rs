@@ -0,0 +1,220 @@
+// OpenTelemetry for the signing pipeline: every sponsorship is a trace whose stages (request parsing, policy
+// evaluation, key signing, storage) are child spans, and stage latencies and outcomes are metrics.
+// Both are exported over OTLP/HTTP when `otlp_endpoint` is configured; otherwise spans only feed the log
+// and the metric instruments are no-ops
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::Config;
+
+pub const DEFAULT_SERVICE_NAME: &str = "paymaster-service";
+
+/// Stages of the signing pipeline, the names of their spans and of the `stage` metric attribute
+pub const STAGE_PARSE: &str = "request.parse";
+pub const STAGE_POLICY: &str = "policy.evaluate";
+pub const STAGE_SIGN: &str = "key.sign";
+pub const STAGE_STORAGE: &str = "storage.record";
+
+/// Keeps the exporters running; flushes what is buffered when dropped at shutdown
+pub struct TelemetryGuard {
+    tracer_provider: Option<SdkTracerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Could not flush traces: {}", e);
+            }
+        }
+        if let Some(provider) = self.meter_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Could not flush metrics: {}", e);
+            }
+        }
+    }
+}
+
+/// `endpoint` with the OTLP/HTTP path of one signal, keeping a path prefix of a collector behind a proxy
+pub fn signal_url(endpoint: &str, signal: &str) -> String {
+    format!("{}/v1/{}", endpoint.trim_end_matches('/'), signal)
+}
+
+fn sampler(ratio: Option<f64>) -> Sampler {
+    match ratio {
+        None => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        Some(ratio) => Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio.clamp(0.0, 1.0)))),
+    }
+}
+
+/// Install the log output at `log_level` and, with an OTLP endpoint, the trace and metric exporters
+pub fn init(config: &Config) -> Result<TelemetryGuard, String> {
+    let level = LevelFilter::from_str(&config.log_level).unwrap_or(LevelFilter::INFO);
+    let fmt = tracing_subscriber::fmt::layer();
+    let Some(endpoint) = &config.otlp_endpoint else {
+        tracing_subscriber::registry().with(level).with(fmt).init();
+        return Ok(TelemetryGuard { tracer_provider: None, meter_provider: None });
+    };
+
+    let resource = Resource::builder()
+        .with_service_name(config.otel_service_name.clone().unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string()))
+        .build();
+
+    let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(signal_url(endpoint, "traces"))
+        .with_headers(config.otlp_headers.clone())
+        .build()
+        .map_err(|e| format!("Invalid OTLP trace exporter: {}", e))?;
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_batch_exporter(span_exporter)
+        .with_sampler(sampler(config.otel_sample_ratio))
+        .with_resource(resource.clone())
+        .build();
+
+    let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+        .with_http()
+        .with_endpoint(signal_url(endpoint, "metrics"))
+        .with_headers(config.otlp_headers.clone())
+        .build()
+        .map_err(|e| format!("Invalid OTLP metric exporter: {}", e))?;
+    let meter_provider = SdkMeterProvider::builder()
+        .with_periodic_exporter(metric_exporter)
+        .with_resource(resource)
+        .build();
+    opentelemetry::global::set_meter_provider(meter_provider.clone());
+    // A `traceparent` header makes the sponsorship part of the caller's trace, e.g. the bundler's
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let otel = tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer(DEFAULT_SERVICE_NAME));
+    tracing_subscriber::registry().with(level).with(fmt).with(otel).init();
+    tracing::info!("Exporting traces and metrics to {}", endpoint);
+
+    Ok(TelemetryGuard { tracer_provider: Some(tracer_provider), meter_provider: Some(meter_provider) })
+}
+
+struct Instruments {
+    stage_duration: Histogram<f64>,
+    sponsorships: Counter<u64>,
+}
+
+// Created on first use, after `init` has installed the meter provider
+fn instruments() -> &'static Instruments {
+    static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+    INSTRUMENTS.get_or_init(|| {
+        let meter = opentelemetry::global::meter(DEFAULT_SERVICE_NAME);
+        Instruments {
+            stage_duration: meter
+                .f64_histogram("paymaster.stage.duration")
+                .with_unit("s")
+                .with_description("Time spent in each stage of the signing pipeline")
+                .build(),
+            sponsorships: meter
+                .u64_counter("paymaster.sponsorships")
+                .with_description("Sponsorship requests by outcome: signed, or the rejection reason")
+                .build(),
+        }
+    })
+}
+
+fn record_stage(stage: &'static str, elapsed: Duration) {
+    instruments().stage_duration.record(elapsed.as_secs_f64(), &[KeyValue::new("stage", stage)]);
+}
+
+/// Count a finished sponsorship request, `signed` or the reason it was rejected
+pub fn record_outcome(outcome: &'static str, sandbox: bool) {
+    instruments().sponsorships.add(1, &[KeyValue::new("outcome", outcome), KeyValue::new("sandbox", sandbox)]);
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Middleware opening the root span of every request, under the caller's trace when it sends one
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {}", request.method(), route),
+        http.request.method = %request.method(),
+        http.route = %route,
+        http.response.status_code = tracing::field::Empty,
+    );
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    // Fails only when no OpenTelemetry layer is installed, and then there is no trace to join
+    let _ = span.set_parent(parent);
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    response
+}
+
+/// Run an async pipeline stage in its own span and record its latency
+pub async fn stage<F: Future>(name: &'static str, future: F) -> F::Output {
+    let started = Instant::now();
+    let output = future.instrument(tracing::info_span!("stage", otel.name = name)).await;
+    record_stage(name, started.elapsed());
+    output
+}
+
+/// `stage` for a synchronous step
+pub fn stage_sync<T>(name: &'static str, step: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let output = tracing::info_span!("stage", otel.name = name).in_scope(step);
+    record_stage(name, started.elapsed());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_url() {
+        assert_eq!(signal_url("http://localhost:4318", "traces"), "http://localhost:4318/v1/traces");
+        assert_eq!(signal_url("https://otel.example/collector/", "metrics"), "https://otel.example/collector/v1/metrics");
+    }
+
+    #[tokio::test]
+    async fn test_stages_run_without_exporter() {
+        // Without init the instruments are no-ops and stages only time and wrap their step
+        assert_eq!(stage(STAGE_SIGN, async { 7 }).await, 7);
+        assert_eq!(stage_sync(STAGE_POLICY, || "allowed"), "allowed");
+        record_outcome("signed", false);
+    }
+}
@@ -0,0 +1,169 @@
+use axum::Router;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Permissions of a Unix socket when `listen_socket_mode` is not set: only the service's user may connect
+pub const DEFAULT_SOCKET_MODE: u32 = 0o600;
+
+/// Where the service accepts connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    /// TCP on all interfaces
+    Tcp(u16),
+    /// Unix domain socket path with its permissions, or Windows named pipe name (`\\.\pipe\...`)
+    Socket { path: String, mode: u32 },
+}
+
+impl ListenAddr {
+    /// A configured socket takes precedence over the TCP port
+    /// `listen_socket_mode` is octal, e.g. `660` to let the service's group connect too
+    pub fn from_config(server_port: u16, listen_socket: Option<&str>, listen_socket_mode: Option<&str>) -> Result<Self, String> {
+        let mode = match listen_socket_mode {
+            Some(mode) => u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+                .ok()
+                .filter(|mode| *mode <= 0o777)
+                .ok_or_else(|| format!("Invalid listen_socket_mode '{}', expected octal permissions such as 600", mode))?,
+            None => DEFAULT_SOCKET_MODE,
+        };
+        Ok(match listen_socket {
+            Some(path) if !path.is_empty() => ListenAddr::Socket { path: path.to_string(), mode },
+            _ => ListenAddr::Tcp(server_port),
+        })
+    }
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenAddr::Tcp(port) => write!(f, "[::]:{}", port),
+            ListenAddr::Socket { path, .. } => write!(f, "{}", path),
+        }
+    }
+}
+
+/// Serve the application until the listener fails
+pub async fn serve(app: Router, addr: &ListenAddr) -> std::io::Result<()> {
+    match addr {
+        ListenAddr::Tcp(_) => {
+            let listener = tokio::net::TcpListener::bind(addr.to_string()).await?;
+            axum::serve(listener, app).await
+        }
+        ListenAddr::Socket { path, mode } => serve_socket(app, path, *mode).await,
+    }
+}
+
+/// Serve HTTP/1.1 on a single accepted stream
+async fn serve_connection<IO>(io: IO, app: Router)
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = TowerToHyperService::new(app);
+    if let Err(e) = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(io), service)
+        .await
+    {
+        tracing::debug!("Connection closed with error: {}", e);
+    }
+}
+
+/// Bind a Unix socket at `path` and restrict who may connect to it
+/// Only a socket left behind by a previous run is replaced, any other file at `path` is an error
+#[cfg(unix)]
+fn bind_unix(path: &str, mode: u32) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket, refusing to replace it", path),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+async fn serve_socket(app: Router, path: &str, mode: u32) -> std::io::Result<()> {
+    let listener = bind_unix(path, mode)?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(serve_connection(stream, app.clone()));
+    }
+}
+
+#[cfg(windows)]
+async fn serve_socket(app: Router, path: &str, _mode: u32) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new().first_pipe_instance(true).create(path)?;
+
+    loop {
+        server.connect().await?;
+        // Hand the connected instance off and open a fresh one for the next client
+        let connected = std::mem::replace(&mut server, ServerOptions::new().create(path)?);
+        tokio::spawn(serve_connection(connected, app.clone()));
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn serve_socket(_app: Router, path: &str, _mode: u32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("Local socket {} is not supported on this platform", path),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_takes_precedence_over_port() {
+        assert_eq!(ListenAddr::from_config(3000, None, None), Ok(ListenAddr::Tcp(3000)));
+        assert_eq!(ListenAddr::from_config(3000, Some(""), None), Ok(ListenAddr::Tcp(3000)));
+        assert_eq!(
+            ListenAddr::from_config(3000, Some("/tmp/paymaster.sock"), None),
+            Ok(ListenAddr::Socket { path: "/tmp/paymaster.sock".to_string(), mode: 0o600 })
+        );
+        assert_eq!(ListenAddr::Tcp(3000).to_string(), "[::]:3000");
+    }
+
+    #[test]
+    fn test_socket_mode() {
+        let socket = |mode| ListenAddr::from_config(3000, Some("/tmp/paymaster.sock"), Some(mode));
+        assert_eq!(socket("660"), Ok(ListenAddr::Socket { path: "/tmp/paymaster.sock".to_string(), mode: 0o660 }));
+        assert_eq!(socket("0o640"), Ok(ListenAddr::Socket { path: "/tmp/paymaster.sock".to_string(), mode: 0o640 }));
+        assert!(socket("rw").is_err());
+        assert!(socket("1777").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_unix_replaces_only_sockets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("paymaster-transport-{}.sock", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        drop(bind_unix(path, 0o600).unwrap());
+        assert_eq!(std::fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+        // The stale socket of a previous run is replaced
+        drop(bind_unix(path, 0o660).unwrap());
+        assert_eq!(std::fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o660);
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::write(path, "not a socket").unwrap();
+        assert!(bind_unix(path, 0o600).is_err());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "not a socket");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::telemetry;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Body encoding negotiated through Content-Type / Accept headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    Json,
+    Cbor,
+}
+
+impl WireFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            WireFormat::Json => JSON_CONTENT_TYPE,
+            WireFormat::Cbor => CBOR_CONTENT_TYPE,
+        }
+    }
+
+    fn from_header(headers: &HeaderMap, name: header::HeaderName) -> Option<Self> {
+        let value = headers.get(name)?.to_str().ok()?;
+        if value.contains(CBOR_CONTENT_TYPE) {
+            Some(WireFormat::Cbor)
+        } else if value.contains(JSON_CONTENT_TYPE) {
+            Some(WireFormat::Json)
+        } else {
+            None
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, String> {
+        match self {
+            WireFormat::Json => serde_json::from_slice(body).map_err(|e| e.to_string()),
+            WireFormat::Cbor => ciborium::from_reader(body).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            WireFormat::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            WireFormat::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(value, &mut body).map_err(|e| e.to_string())?;
+                Ok(body)
+            }
+        }
+    }
+}
+
+/// Extractor accepting JSON or CBOR bodies
+/// The response format follows `Accept`, falling back to the request's own encoding
+pub struct Wire<T> {
+    pub value: T,
+    pub response_format: WireFormat,
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Wire<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let headers = req.headers();
+        // JSON stays the default so existing clients without a Content-Type keep working
+        let request_format = WireFormat::from_header(headers, header::CONTENT_TYPE)
+            .unwrap_or(WireFormat::Json);
+        let response_format = WireFormat::from_header(headers, header::ACCEPT)
+            .unwrap_or(request_format);
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        let value = telemetry::stage_sync(telemetry::STAGE_PARSE, || request_format.decode(&body))
+            .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid request body: {}", e)))?;
+
+        Ok(Wire { value, response_format })
+    }
+}
+
+/// Response encoded in the negotiated wire format
+pub struct WireResponse<T> {
+    pub value: T,
+    pub format: WireFormat,
+}
+
+impl<T: Serialize> IntoResponse for WireResponse<T> {
+    fn into_response(self) -> Response {
+        match self.format.encode(&self.value) {
+            Ok(body) => (
+                [(header::CONTENT_TYPE, HeaderValue::from_static(self.format.content_type()))],
+                body,
+            )
+                .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Sample {
+        name: String,
+        value: u64,
+    }
+
+    #[test]
+    fn test_cbor_round_trip_is_smaller_than_json() {
+        let sample = Sample { name: "sponsorship".to_string(), value: 1_700_000_000 };
+
+        let cbor = WireFormat::Cbor.encode(&sample).unwrap();
+        let json = WireFormat::Json.encode(&sample).unwrap();
+
+        assert_eq!(WireFormat::Cbor.decode::<Sample>(&cbor).unwrap(), sample);
+        assert!(cbor.len() < json.len());
+    }
+
+    #[test]
+    fn test_format_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(WireFormat::from_header(&headers, header::CONTENT_TYPE), None);
+
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/cbor"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json; charset=utf-8"));
+        assert_eq!(WireFormat::from_header(&headers, header::CONTENT_TYPE), Some(WireFormat::Cbor));
+        assert_eq!(WireFormat::from_header(&headers, header::ACCEPT), Some(WireFormat::Json));
+    }
+}
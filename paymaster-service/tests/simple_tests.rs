@@ -1,52 +1,95 @@
 use axum::http::StatusCode;
+use paymaster_service::Config;
 use serde_json::json;
 use std::collections::HashMap;
 use tower::ServiceExt;
-use paymaster_service::{Config};
 
 mod test_app {
     use super::*;
     use axum::{
-        routing::{post, get},
+        routing::{get, post},
         Router,
     };
     use std::sync::Arc;
 
     pub fn create_test_config() -> Config {
         let mut verifier_keys = HashMap::new();
-        verifier_keys.insert("default".to_string(), "0000000000000000000000000000000000000000000000000000000000000001".to_string());
-        
+        verifier_keys.insert(
+            "default".to_string(),
+            "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        );
+
         let mut api_keys = HashMap::new();
         api_keys.insert("test_key_123".to_string(), "Test Client".to_string());
-        
+
         Config {
             verifier_keys,
             api_keys,
+            sandbox_api_keys: Vec::new(),
             server_port: 3000,
+            listen_socket: None,
+            listen_socket_mode: None,
             log_level: "info".to_string(),
             chain_id: Some(1),
             paymaster_address: Some("0x0000000000000000000000000000000000000000".to_string()),
+            is_simple_paymaster: Some(false),
+            paymaster_kind: None,
+            usage_ledger: None,
+            analytics_file: None,
+            admin_api_key: None,
+            invoice_markup_bps: 0,
+            rpc_url: None,
+            entry_point: None,
+            coordination_redis_url: None,
+            coordination_lock_key: None,
+            coordination_lease_secs: None,
+            reconcile_interval_secs: None,
+            reconcile_from_block: None,
+            fixed_time: None,
+            rng_seed: None,
+            otlp_endpoint: None,
+            otlp_headers: HashMap::new(),
+            otel_service_name: None,
+            otel_sample_ratio: None,
         }
     }
 
     pub async fn create_test_app() -> Router {
-        use paymaster_service::signature_service::SignatureService;
-        use paymaster_service::key_manager::KeyManager;
         use paymaster_service::api;
-        
+        use paymaster_service::invoice::{InvoiceSettings, UsageLedger};
+        use paymaster_service::key_manager::KeyManager;
+        use paymaster_service::signature_service::{PaymasterKind, SignatureService};
+
         let config = create_test_config();
         let key_manager = Arc::new(KeyManager::new(&config));
-        let signature_service = Arc::new(SignatureService::new(
-            key_manager, 
-            config.api_keys,
-            1, // chain_id
-            vec![0u8; 20] // paymaster_address
-        ));
-        
+        let signature_service = Arc::new(
+            SignatureService::new(
+                key_manager,
+                config.api_keys,
+                1,             // chain_id
+                vec![0u8; 20], // paymaster_address
+                PaymasterKind::Verifying,
+            )
+            .with_invoicing(
+                UsageLedger::new(),
+                InvoiceSettings {
+                    admin_api_key: Some("admin_secret".to_string()),
+                    markup_bps: 100,
+                },
+            ),
+        );
+
         Router::new()
             .route("/health", get(api::health_check))
             .route("/sign", post(api::sign_sponsorship))
+            .route("/preauth", post(api::issue_preauth))
+            .route("/stub", post(api::paymaster_stub))
             .route("/metrics", get(api::get_metrics))
+            .route("/admin/invoices", get(api::get_invoices))
+            .route("/verifier", get(api::get_verifier))
+            .route("/analytics", get(api::get_analytics))
+            .route("/metrics/prometheus", get(api::get_prometheus_metrics))
+            .route("/paymaster", get(api::get_paymaster_status))
             .with_state(signature_service)
     }
 }
@@ -54,7 +97,7 @@ mod test_app {
 #[tokio::test]
 async fn test_health_check() {
     let app = test_app::create_test_app().await;
-    
+
     let response = app
         .oneshot(
             axum::http::Request::builder()
@@ -65,14 +108,14 @@ async fn test_health_check() {
         )
         .await
         .unwrap();
-    
+
     assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[tokio::test]
 async fn test_valid_sign_request() {
     let app = test_app::create_test_app().await;
-    
+
     let request_body = json!({
         "api_key": "test_key_123",
         "user_operation": {
@@ -88,26 +131,28 @@ async fn test_valid_sign_request() {
         "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
         "valid_after": 0
     });
-    
+
     let response = app
         .oneshot(
             axum::http::Request::builder()
                 .method("POST")
                 .uri("/sign")
                 .header("content-type", "application/json")
-                .body(axum::body::Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .body(axum::body::Body::from(
+                    serde_json::to_vec(&request_body).unwrap(),
+                ))
                 .unwrap(),
         )
         .await
         .unwrap();
-    
+
     assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[tokio::test]
 async fn test_invalid_api_key() {
     let app = test_app::create_test_app().await;
-    
+
     let request_body = json!({
         "api_key": "invalid_key",
         "user_operation": {
@@ -123,26 +168,28 @@ async fn test_invalid_api_key() {
         "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
         "valid_after": 0
     });
-    
+
     let response = app
         .oneshot(
             axum::http::Request::builder()
                 .method("POST")
                 .uri("/sign")
                 .header("content-type", "application/json")
-                .body(axum::body::Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .body(axum::body::Body::from(
+                    serde_json::to_vec(&request_body).unwrap(),
+                ))
                 .unwrap(),
         )
         .await
         .unwrap();
-    
+
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
 async fn test_get_metrics() {
     let app = test_app::create_test_app().await;
-    
+
     let response = app
         .oneshot(
             axum::http::Request::builder()
@@ -153,6 +200,725 @@ async fn test_get_metrics() {
         )
         .await
         .unwrap();
-    
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_cbor_sign_request() {
+    let app = test_app::create_test_app().await;
+
+    let request_body = json!({
+        "api_key": "test_key_123",
+        "user_operation": {
+            "sender": "0x1234567890123456789012345678901234567890",
+            "nonce": "1",
+            "init_code": "0x",
+            "call_data": "0x1234",
+            "account_gas_limits": "0x00000000000f424000000000000f4240",
+            "pre_verification_gas": "21000",
+            "gas_fees": "0x000000000077359400000000003b9aca00",
+            "paymaster_and_data": "0x"
+        },
+        "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
+        "valid_after": 0
+    });
+    let mut body = Vec::new();
+    ciborium::into_writer(&request_body, &mut body).unwrap();
+
+    let response = app
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/sign")
+                .header("content-type", "application/cbor")
+                .header("accept", "application/cbor")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/cbor");
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let decoded: serde_json::Value = ciborium::from_reader(bytes.as_ref()).unwrap();
+    assert_eq!(decoded["signature"].as_str().unwrap().len(), 130);
+}
+
+#[tokio::test]
+async fn test_preauth_token_redeemed_once() {
+    let app = test_app::create_test_app().await;
+    let sender = "0x1234567890123456789012345678901234567890";
+
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/preauth")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(
+                    serde_json::to_vec(&json!({
+                        "api_key": "test_key_123",
+                        "sender": sender,
+                        "ttl_secs": 300
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let grant: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let token = grant["token"].as_str().unwrap().to_string();
+
+    // Redeeming needs no API key
+    let request_body = json!({
+        "preauth_token": token,
+        "user_operation": {
+            "sender": sender,
+            "nonce": "1",
+            "init_code": "0x",
+            "call_data": "0x1234",
+            "account_gas_limits": "0x00000000000f424000000000000f4240",
+            "pre_verification_gas": "21000",
+            "gas_fees": "0x000000000077359400000000003b9aca00",
+            "paymaster_and_data": "0x"
+        },
+        "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
+        "valid_after": 0
+    });
+
+    for expected in [StatusCode::OK, StatusCode::BAD_REQUEST] {
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/sign")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(
+                        serde_json::to_vec(&request_body).unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_paymaster_stub() {
+    let app = test_app::create_test_app().await;
+    let stub = |body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/stub")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = stub(json!({ "api_key": "test_key_123", "sender": "0x1234567890123456789012345678901234567890" })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let stub_data: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(stub_data["paymaster_kind"], "verifying");
+    assert_eq!(stub_data["is_final"], false);
+    // As long as signed paymasterData, so estimation pays for all of it
+    assert_eq!(
+        stub_data["paymaster_data"].as_str().unwrap().len(),
+        2 * (65 + 8 + 8)
+    );
+    assert!(
+        stub_data["paymaster_verification_gas_limit"]
+            .as_u64()
+            .unwrap()
+            > 0
+    );
+
+    let response =
+        stub(json!({ "api_key": "wrong", "sender": "0x1234567890123456789012345678901234567890" }))
+            .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_invoice_lists_sponsored_operations() {
+    let app = test_app::create_test_app().await;
+
+    let request_body = json!({
+        "api_key": "test_key_123",
+        "user_operation": {
+            "sender": "0x1234567890123456789012345678901234567890",
+            "nonce": "1",
+            "init_code": "0x",
+            "call_data": "0x1234",
+            "account_gas_limits": "0x00000000000f424000000000000f4240",
+            "pre_verification_gas": "21000",
+            "gas_fees": "0x000000000077359400000000003b9aca00",
+            "paymaster_and_data": "0x"
+        },
+        "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
+        "valid_after": 0
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/sign")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(
+                    serde_json::to_vec(&request_body).unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let month = chrono::Utc::now().format("%Y-%m").to_string();
+    let invoice = |admin_key: &'static str| {
+        axum::http::Request::builder()
+            .method("GET")
+            .uri(format!("/admin/invoices?month={}&format=csv", month))
+            .header("x-admin-key", admin_key)
+            .body(axum::body::Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(invoice("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.oneshot(invoice("admin_secret")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let csv = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(csv
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with(&format!("Test Client,{},1,1,", month)));
+}
+
+#[tokio::test]
+async fn test_verifier_endpoint() {
+    let app = test_app::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            axum::http::Request::builder()
+                .uri("/verifier")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    // Address of private key 1
+    assert_eq!(
+        body["verifier"],
+        "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+    );
+    assert_eq!(body["sandbox_verifier"], serde_json::Value::Null);
+    assert_eq!(body["paymaster_kind"], "verifying");
+    assert_eq!(body["chain_id"], 1);
+}
+
+#[tokio::test]
+async fn test_analytics_and_prometheus_metrics() {
+    let app = test_app::create_test_app().await;
+
+    let request_body = json!({
+        "api_key": "invalid_key",
+        "user_operation": {
+            "sender": "0x1234567890123456789012345678901234567890",
+            "nonce": "1",
+            "init_code": "0x",
+            "call_data": "0x1234",
+            "account_gas_limits": "0x00000000000f424000000000000f4240",
+            "pre_verification_gas": "21000",
+            "gas_fees": "0x000000000077359400000000003b9aca00",
+            "paymaster_and_data": "0x"
+        },
+        "valid_until": (chrono::Utc::now().timestamp() + 3600) as u64,
+        "valid_after": 0
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/sign")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(
+                    serde_json::to_vec(&request_body).unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let get = |uri: &str, authorization: &str| {
+        axum::http::Request::builder()
+            .uri(uri)
+            .header("authorization", authorization)
+            .body(axum::body::Body::empty())
+            .unwrap()
+    };
+    let response = app
+        .clone()
+        .oneshot(get("/analytics", "Bearer wrong"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let with_admin_header = |admin_key: &str, authorization: &str| {
+        let mut request = get("/analytics", authorization);
+        request
+            .headers_mut()
+            .insert("x-admin-key", admin_key.parse().unwrap());
+        request
+    };
+    // Both headers must carry the same key
+    let response = app
+        .clone()
+        .oneshot(with_admin_header("admin_secret", "Bearer wrong"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .clone()
+        .oneshot(with_admin_header("wrong", "Bearer admin_secret"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .clone()
+        .oneshot(with_admin_header("admin_secret", "Bearer admin_secret"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(get("/analytics?from=yesterday", "Bearer admin_secret"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(get("/analytics?tenant=unknown", "Bearer admin_secret"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(report["bucket_secs"], 3600);
+    assert_eq!(report["buckets"][0]["rejections"]["invalid_api_key"], 1);
+
+    let response = app
+        .oneshot(get("/metrics/prometheus", "Bearer admin_secret"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(text
+        .contains("paymaster_rejections_total{tenant=\"unknown\",reason=\"invalid_api_key\"} 1"));
+}
+
+#[tokio::test]
+async fn test_paymaster_status_reads_deposit() {
+    use axum::{
+        routing::{get, post},
+        Json, Router,
+    };
+    use paymaster_service::api;
+    use paymaster_service::deposit::{DepositReader, DEFAULT_ENTRY_POINT};
+    use paymaster_service::key_manager::KeyManager;
+    use paymaster_service::signature_service::{PaymasterKind, SignatureService};
+    use std::sync::Arc;
+
+    // Node answering every eth_call with a DepositInfo of 1000 wei, staked 5 wei
+    let node = Router::new().route(
+        "/",
+        post(|Json(call): Json<serde_json::Value>| async move {
+            assert_eq!(call["params"][0]["to"], DEFAULT_ENTRY_POINT);
+            let word = |value: u64| format!("{:064x}", value);
+            let result = format!(
+                "0x{}{}{}{}{}",
+                word(1000),
+                word(1),
+                word(5),
+                word(86400),
+                word(0)
+            );
+            Json(json!({ "jsonrpc": "2.0", "id": call["id"], "result": result }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let node_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, node).await });
+
+    let config = test_app::create_test_config();
+    let status = |reader: Option<DepositReader>| {
+        let service = SignatureService::new(
+            Arc::new(KeyManager::new(&config)),
+            HashMap::new(),
+            1,
+            vec![0x22; 20],
+            PaymasterKind::Simple,
+        );
+        let service = match reader {
+            Some(reader) => service.with_deposit_reader(reader),
+            None => service,
+        };
+        Router::new()
+            .route("/paymaster", get(api::get_paymaster_status))
+            .with_state(Arc::new(service))
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/paymaster")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+    };
+
+    let response = status(None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = status(Some(DepositReader::new(
+        node_url,
+        DEFAULT_ENTRY_POINT.to_string(),
+    )))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["paymaster"], format!("0x{}", "22".repeat(20)));
+    assert_eq!(body["paymaster_kind"], "simple");
+    assert_eq!(body["deposit"], "0x3e8");
+    assert_eq!(body["staked"], true);
+    assert_eq!(body["unstake_delay_sec"], 86400);
+}
+
+#[tokio::test]
+async fn test_predict_deployment() {
+    use axum::{routing::post, Json, Router};
+    use paymaster_service::api;
+    use paymaster_service::deposit::{DepositReader, DEFAULT_ENTRY_POINT};
+    use paymaster_service::key_manager::KeyManager;
+    use paymaster_service::signature_service::{PaymasterKind, SignatureService};
+    use std::sync::Arc;
+
+    // Node reverting every getSenderAddress with SenderAddressResult(0x4444...)
+    let node = Router::new().route("/", post(|Json(call): Json<serde_json::Value>| async move {
+        assert!(call["params"][0]["data"].as_str().unwrap().starts_with("0x9b249f69"));
+        let data = format!("0x6ca7b806{}{}", "00".repeat(12), "44".repeat(20));
+        Json(json!({ "jsonrpc": "2.0", "id": call["id"], "error": { "code": 3, "message": "execution reverted", "data": data } }))
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let node_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, node).await });
+
+    let config = test_app::create_test_config();
+    let predict = |reader: Option<DepositReader>, request: serde_json::Value| {
+        let service = SignatureService::new(
+            Arc::new(KeyManager::new(&config)),
+            config.api_keys.clone(),
+            1,
+            vec![0x22; 20],
+            PaymasterKind::Verifying,
+        );
+        let service = match reader {
+            Some(reader) => service.with_deposit_reader(reader),
+            None => service,
+        };
+        Router::new()
+            .route("/deployment/predict", post(api::predict_deployment))
+            .with_state(Arc::new(service))
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/deployment/predict")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(request.to_string()))
+                    .unwrap(),
+            )
+    };
+    let request = |sender: &str| {
+        json!({
+            "api_key": "test_key_123",
+            "sender": sender,
+            "init_code": format!("0x{}5fbfb9cf{}", "55".repeat(20), "00".repeat(64)),
+        })
+    };
+    let reader = || {
+        Some(DepositReader::new(
+            node_url.clone(),
+            DEFAULT_ENTRY_POINT.to_string(),
+        ))
+    };
+
+    let response = predict(None, request(&format!("0x{}", "44".repeat(20))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&bytes), "Deployment prediction is disabled, configure rpc_url");
+
+    let response = predict(reader(), request(&format!("0x{}", "44".repeat(20))))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["predicted_sender"], format!("0x{}", "44".repeat(20)));
+    assert_eq!(body["factory"], format!("0x{}", "55".repeat(20)));
+    assert_eq!(body["matches"], true);
+
+    let response = predict(reader(), request(&format!("0x{}", "66".repeat(20))))
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["matches"], false);
+
+    let mut unknown_key = request(&format!("0x{}", "44".repeat(20)));
+    unknown_key["api_key"] = json!("nope");
+    assert_eq!(
+        predict(reader(), unknown_key).await.unwrap().status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn test_reconciler_settles_ledger_from_logs() {
+    use axum::{routing::post, Json, Router};
+    use paymaster_service::deposit::{DepositReader, DEFAULT_ENTRY_POINT};
+    use paymaster_service::invoice::{Settlement, UsageLedger, UsageRecord};
+    use paymaster_service::reconcile::Reconciler;
+    use paymaster_service::signature_service::PackedUserOperation;
+
+    // Node at block 100 (timestamp 1000 + block) with one UserOperationEvent for nonce 1 of the sender
+    let node = Router::new().route("/", post(|Json(call): Json<serde_json::Value>| async move {
+        let word = |value: u64| format!("{:064x}", value);
+        let result = match call["method"].as_str().unwrap() {
+            "eth_blockNumber" => json!("0x64"),
+            "eth_getBlockByNumber" => {
+                let block = u64::from_str_radix(call["params"][0].as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+                json!({ "timestamp": format!("0x{:x}", 1000 + block) })
+            }
+            "eth_getLogs" => {
+                assert_eq!(call["params"][0]["topics"][3], format!("0x{}{}", "00".repeat(12), "22".repeat(20)));
+                json!([{
+                    "topics": [call["params"][0]["topics"][0], format!("0x{}", word(9)), format!("0x{}{}", "00".repeat(12), "11".repeat(20)), call["params"][0]["topics"][3]],
+                    "data": format!("0x{}{}{}{}", word(1), word(1), word(4_000), word(2_000)),
+                }])
+            }
+            method => panic!("unexpected {}", method),
+        };
+        Json(json!({ "jsonrpc": "2.0", "id": call["id"], "result": result }))
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let node_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, node).await });
+
+    let user_op = |nonce: u64| PackedUserOperation {
+        sender: format!("0x{}", "11".repeat(20)),
+        nonce: alloy_primitives::U256::from(nonce),
+        init_code: "0x".to_string(),
+        call_data: "0x".to_string(),
+        account_gas_limits: format!("0x{:032x}{:032x}", 100u64, 100u64),
+        pre_verification_gas: alloy_primitives::U256::from(100),
+        gas_fees: format!("0x{:032x}{:032x}", 10u64, 10u64),
+        paymaster_and_data: "0x".to_string(),
+    };
+    let ledger = UsageLedger::new();
+    ledger
+        .record(UsageRecord::new("Test Client", &user_op(1), 1010, 1050))
+        .unwrap();
+    ledger
+        .record(UsageRecord::new("Test Client", &user_op(2), 1010, 1050))
+        .unwrap();
+
+    let reader = DepositReader::new(node_url, DEFAULT_ENTRY_POINT.to_string());
+    let reconciler = Reconciler::new(reader, &[0x22; 20], Some(0));
+    let round = reconciler.run_once(&ledger).await.unwrap();
+    assert_eq!((round.to_block, round.events, round.settled), (100, 1, 2));
+
+    let records = ledger.records();
+    assert_eq!(
+        (records[0].settlement, records[0].actual_gas_cost),
+        (Settlement::Used, Some(4_000))
+    );
+    // Valid until 1050, and the scan reached timestamp 1100 without seeing it
+    assert_eq!(
+        (records[1].settlement, records[1].actual_gas_cost),
+        (Settlement::Unused, Some(0))
+    );
+
+    // Nothing new to scan on the next round
+    assert_eq!(reconciler.run_once(&ledger).await.unwrap().settled, 0);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_health_check_over_unix_socket() {
+    use paymaster_service::transport::{self, ListenAddr};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let path = std::env::temp_dir().join(format!("paymaster-test-{}.sock", std::process::id()));
+    let addr = ListenAddr::Socket {
+        path: path.to_string_lossy().into_owned(),
+        mode: transport::DEFAULT_SOCKET_MODE,
+    };
+
+    let app = test_app::create_test_app().await;
+    tokio::spawn(async move { transport::serve(app, &addr).await });
+
+    // Wait for the listener to come up
+    let mut stream = None;
+    for _ in 0..50 {
+        if let Ok(s) = tokio::net::UnixStream::connect(&path).await {
+            stream = Some(s);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let mut stream = stream.expect("socket listener did not start");
+
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 200"),
+        "unexpected response: {}",
+        response
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_fixed_clock_and_seed_are_reproducible() {
+    use axum::{routing::post, Router};
+    use paymaster_service::api;
+    use paymaster_service::clock::{FixedClock, SeededEntropy};
+    use paymaster_service::key_manager::KeyManager;
+    use paymaster_service::signature_service::{PaymasterKind, SignatureService};
+    use std::sync::Arc;
+
+    const NOW: u64 = 2_000_000_000;
+    let app = || {
+        let config = test_app::create_test_config();
+        let service = SignatureService::new(
+            Arc::new(KeyManager::new(&config)),
+            config.api_keys,
+            1,
+            vec![0u8; 20],
+            PaymasterKind::Verifying,
+        )
+        .with_clock(Arc::new(FixedClock::new(NOW)))
+        .with_entropy(Box::new(SeededEntropy::new(7)));
+        Router::new()
+            .route("/sign", post(api::sign_sponsorship))
+            .route("/preauth", post(api::issue_preauth))
+            .with_state(Arc::new(service))
+    };
+    let post = |app: Router, uri: &'static str, body: serde_json::Value| async move {
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        (
+            status,
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+    };
+    let sign = |valid_until: u64| {
+        json!({
+            "api_key": "test_key_123",
+            "user_operation": {
+                "sender": "0x1234567890123456789012345678901234567890",
+                "nonce": "1",
+                "init_code": "0x",
+                "call_data": "0x1234",
+                "account_gas_limits": "0x00000000000f424000000000000f4240",
+                "pre_verification_gas": "21000",
+                "gas_fees": "0x000000000077359400000000003b9aca00",
+                "paymaster_and_data": "0x"
+            },
+            "valid_until": valid_until,
+            "valid_after": 0
+        })
+    };
+    let preauth = json!({ "api_key": "test_key_123", "sender": "0x1234567890123456789012345678901234567890" });
+
+    // Expiry is judged by the fixed clock, not the wall clock
+    let (status, _) = post(app(), "/sign", sign(NOW)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Two fresh services give byte-identical sponsorships and tokens
+    let (status, first) = post(app(), "/sign", sign(NOW + 3600)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(post(app(), "/sign", sign(NOW + 3600)).await.1, first);
+
+    let (status, token) = post(app(), "/preauth", preauth.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(post(app(), "/preauth", preauth).await.1, token);
+    let grant: serde_json::Value = serde_json::from_slice(&token).unwrap();
+    assert_eq!(grant["expires_at"], NOW + 600);
 }
@@ -0,0 +1,138 @@
+//! Canonical sponsorship vectors for the `client` and `paymaster-service` tests, so that the
+//! UserOperation packing on both sides is checked against the same bytes and cannot drift apart.
+//!
+//! The expected values were computed outside both crates, with alloy's ABI encoder and libsecp256k1,
+//! following `VerifierSignaturePaymaster._packForPaymaster` and `_pmHash`. Byte strings are `0x` hex.
+
+/// A v0.7 UserOperation, unpacked as the client builds it and packed as the service receives it
+#[derive(Debug, Clone, Copy)]
+pub struct UserOperation {
+    /// Lowercase, as the client sends it
+    pub sender: &'static str,
+    /// Decimal, 192-bit key and 64-bit sequence
+    pub nonce: &'static str,
+    pub factory: Option<&'static str>,
+    pub factory_data: &'static str,
+    /// `factory ++ factory_data`, empty without a factory
+    pub init_code: &'static str,
+    pub call_data: &'static str,
+    pub verification_gas_limit: u128,
+    pub call_gas_limit: u128,
+    pub pre_verification_gas: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    /// `verificationGasLimit ++ callGasLimit` as bytes32
+    pub account_gas_limits: &'static str,
+    /// `maxPriorityFeePerGas ++ maxFeePerGas` as bytes32
+    pub gas_fees: &'static str,
+}
+
+/// One sponsorship signed by a VerifierSignaturePaymaster verifier
+#[derive(Debug, Clone, Copy)]
+pub struct Sponsorship {
+    pub name: &'static str,
+    pub user_operation: UserOperation,
+    pub chain_id: u64,
+    pub paymaster: &'static str,
+    pub valid_until: u64,
+    pub valid_after: u64,
+    /// Verifier private key, without `0x` as in the service config
+    pub verifier_key: &'static str,
+    /// Address of `verifier_key`
+    pub verifier: &'static str,
+    /// `_pmHash`: keccak256 of `abi.encode(_packForPaymaster(op), chainid, paymaster, validUntil, validAfter)`
+    pub paymaster_hash: &'static str,
+    /// EIP-191 digest of `paymaster_hash`, what the verifier signs
+    pub eip191_digest: &'static str,
+    /// `r ++ s ++ v` with v 27 or 28, RFC 6979 deterministic
+    pub signature: &'static str,
+    /// `signature ++ validUntil ++ validAfter`, appended after the paymaster gas limits
+    pub paymaster_data: &'static str,
+}
+
+pub const SPONSORSHIPS: &[Sponsorship] = &[
+    Sponsorship {
+        name: "transfer",
+        user_operation: UserOperation {
+            sender: "0x1234567890123456789012345678901234567890",
+            nonce: "1",
+            factory: None,
+            factory_data: "0x",
+            init_code: "0x",
+            call_data: "0x1234",
+            verification_gas_limit: 1_000_000,
+            call_gas_limit: 1_000_000,
+            pre_verification_gas: 21_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            max_fee_per_gas: 2_000_000_000,
+            account_gas_limits: "0x000000000000000000000000000f4240000000000000000000000000000f4240",
+            gas_fees: "0x0000000000000000000000003b9aca0000000000000000000000000077359400",
+        },
+        chain_id: 11155111,
+        paymaster: "0xb0828f3a1f54d52dc91122e6191ffe46da37020f",
+        valid_until: 4_102_444_800,
+        valid_after: 0,
+        verifier_key: "0000000000000000000000000000000000000000000000000000000000000001",
+        verifier: "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf",
+        paymaster_hash: "0x7a355612e88050eb8b7c52d939a64db9f88417e69c1dbeb95220a20fd38d13f1",
+        eip191_digest: "0x613b90370ba996ca33a63937f60c7dbb40ae5f8982f8fe6b4ca9e38f986d7218",
+        signature: "0xc0f82fb636a0bda123e38f5f8352db13f952798a6f1e148d7fe7c41ba9b2f13a76917059fe71daa716112865b921139a03a5d6a2bb03fa7620281f90ccf8898d1c",
+        paymaster_data: "0xc0f82fb636a0bda123e38f5f8352db13f952798a6f1e148d7fe7c41ba9b2f13a76917059fe71daa716112865b921139a03a5d6a2bb03fa7620281f90ccf8898d1c00000000f48657000000000000000000",
+    },
+    Sponsorship {
+        name: "deployment",
+        user_operation: UserOperation {
+            sender: "0xd59c5d74a376f08e3036262f1d59be24de138c41",
+            // Key 7, sequence 3
+            nonce: "129127208515966861315",
+            factory: Some("0x159ea6a05b9aa5d05d41fc9558233308ce1affca"),
+            // createAccount(0x1111..., 42)
+            factory_data: "0x5fbfb9cf0000000000000000000000001111111111111111111111111111111111111111000000000000000000000000000000000000000000000000000000000000002a",
+            init_code: "0x159ea6a05b9aa5d05d41fc9558233308ce1affca5fbfb9cf0000000000000000000000001111111111111111111111111111111111111111000000000000000000000000000000000000000000000000000000000000002a",
+            // execute(0x2222..., 0.001 ether, "")
+            call_data: "0xb61d27f6000000000000000000000000222222222222222222222222222222222222222200000000000000000000000000000000000000000000000000038d7ea4c6800000000000000000000000000000000000000000000000000000000000000000600000000000000000000000000000000000000000000000000000000000000000",
+            verification_gas_limit: 2_500_000,
+            call_gas_limit: 80_000,
+            pre_verification_gas: 65_432,
+            max_priority_fee_per_gas: 1_500_000_000,
+            max_fee_per_gas: 30_000_000_000,
+            account_gas_limits: "0x000000000000000000000000002625a000000000000000000000000000013880",
+            gas_fees: "0x00000000000000000000000059682f00000000000000000000000006fc23ac00",
+        },
+        chain_id: 1,
+        paymaster: "0x3333333333333333333333333333333333333333",
+        valid_until: 4_102_444_800,
+        valid_after: 1_790_000_000,
+        verifier_key: "9ec161507ad1cfd507ae6e6bf012a66d609276782ae64f70ca41174d402d10ae",
+        verifier: "0x21d541ef2237b2a63076666651238ac8a7cde752",
+        paymaster_hash: "0x88a1c3061b777c842fcc0862e9bf2d2be683a32202c4bc76c31e469e0bbfa80d",
+        eip191_digest: "0xf11a16496053de4b12f8b7442f272c1dc53ec69054c727012fc6c9ac7ae4ca46",
+        signature: "0x401230aa215ba97d97dfc014a5f2e54423f4072db20b1ffb66372e1657d67f551d3c8879fe1d1c387634dd9a06870eb561e7cc453fcb14c671a505af32c32e201b",
+        paymaster_data: "0x401230aa215ba97d97dfc014a5f2e54423f4072db20b1ffb66372e1657d67f551d3c8879fe1d1c387634dd9a06870eb561e7cc453fcb14c671a505af32c32e201b00000000f4865700000000006ab13b80",
+    },
+];
+
+/// Strip the `0x` prefix of a vector field
+pub fn unprefixed(hex: &str) -> &str {
+    hex.strip_prefix("0x").unwrap_or(hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_are_consistent() {
+        for vector in SPONSORSHIPS {
+            let op = vector.user_operation;
+            let init_code = op.factory.map(|factory| format!("{}{}", factory, unprefixed(op.factory_data)));
+            assert_eq!(init_code.as_deref().unwrap_or("0x"), op.init_code, "{}", vector.name);
+            assert_eq!(op.account_gas_limits, format!("0x{:032x}{:032x}", op.verification_gas_limit, op.call_gas_limit));
+            assert_eq!(op.gas_fees, format!("0x{:032x}{:032x}", op.max_priority_fee_per_gas, op.max_fee_per_gas));
+
+            let times = format!("{:016x}{:016x}", vector.valid_until, vector.valid_after);
+            assert_eq!(vector.paymaster_data, format!("{}{}", vector.signature, times), "{}", vector.name);
+            assert_eq!(unprefixed(vector.signature).len(), 130);
+        }
+    }
+}